@@ -0,0 +1,349 @@
+//! Derive macros for `xdr_codec::Pack`, `xdr_codec::Unpack`, `xdr_codec::PackedFixedSize` and
+//! `xdr_codec::reflect::XdrReflect`
+//!
+//! These are re-exported by `xdr-codec` when its `derive` feature is enabled, and should be used
+//! from there. They generate implementations for structs which pack and unpack each field in
+//! declaration order, which is the XDR encoding of a struct with the same fields. Named, tuple
+//! and unit structs are supported.
+//!
+//! Fields are packed with their own `Pack` and `Unpack` implementations, unless annotated with
+//! an `xdr` attribute:
+//!
+//!  * `#[xdr(max = N)]` - a `String` is a `string<N>`, and anything else (such as `Vec<T>`) is a
+//!    flex array `T<N>`, failing if it's longer than `N`.
+//!  * `#[xdr(opaque)]` - a `[u8; N]` is a fixed-size `opaque[N]`, and anything else (such as
+//!    `Vec<u8>`) is a flexible `opaque<>`. This can be combined with `max` for `opaque<N>`.
+//!  * `#[xdr(trailing)]` - the field was appended in a later version of the protocol, so it's
+//!    set to its `Default` if the input ends before it (see `xdr_codec::unpack_trailing`). It's
+//!    always packed. Only the last fields of a struct can be trailing.
+//!
+//! `XdrFixedSize` implements `PackedFixedSize` for a struct whose fields all encode to a fixed
+//! size: either plain fields whose types implement `PackedFixedSize`, or `#[xdr(opaque)]` byte
+//! arrays. Flex arrays, strings and trailing fields are rejected.
+//!
+//! `XdrReflect` implements `xdr_codec::reflect::XdrReflect` for a struct, describing it as an
+//! XDR struct named after the Rust type, with the fields encoded as the other derives encode
+//! them. Tuple struct fields are named `f0`, `f1` and so on. Generic structs aren't supported.
+//!
+//! Type parameters are required to implement `Pack`, `Unpack` or `PackedFixedSize` themselves.
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use] extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as Tokens;
+use syn::{Data, DeriveInput, Expr, Fields, GenericParam, Generics, Index, Type,
+          parse_macro_input, parse_quote};
+
+/// How a field is encoded
+enum Codec {
+    Plain,
+    String(Expr),
+    Flex(Expr),
+    OpaqueFlex(Option<Expr>),
+    OpaqueArray,
+    Trailing,
+}
+
+fn field_codec(field: &syn::Field) -> syn::Result<Codec> {
+    let mut max = None;
+    let mut opaque = false;
+    let mut trailing = false;
+
+    for attr in &field.attrs {
+        if !attr.path().is_ident("xdr") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("max") {
+                max = Some(meta.value()?.parse::<Expr>()?);
+                Ok(())
+            } else if meta.path.is_ident("opaque") {
+                opaque = true;
+                Ok(())
+            } else if meta.path.is_ident("trailing") {
+                trailing = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown xdr attribute; expected `max`, `opaque` or `trailing`"))
+            }
+        })?;
+    }
+
+    let is_string = match field.ty {
+        Type::Path(ref p) => p.path.segments.last().is_some_and(|s| s.ident == "String"),
+        _ => false,
+    };
+    let is_array = matches!(field.ty, Type::Array(_));
+
+    if trailing {
+        if opaque || max.is_some() {
+            return Err(syn::Error::new_spanned(&field.ty, "trailing field can't have other xdr attributes"));
+        }
+        return Ok(Codec::Trailing);
+    }
+
+    Ok(match (opaque, max) {
+        (true, None) if is_array => Codec::OpaqueArray,
+        (true, Some(_)) if is_array =>
+            return Err(syn::Error::new_spanned(&field.ty, "fixed-size opaque can't have a max")),
+        (true, max) => Codec::OpaqueFlex(max),
+        (false, Some(max)) if is_string => Codec::String(max),
+        (false, Some(max)) => Codec::Flex(max),
+        (false, None) => Codec::Plain,
+    })
+}
+
+// Add `bound` to each type parameter, and an extra type parameter for the stream
+fn impl_generics(generics: &Generics, param: GenericParam, bound: syn::TypeParamBound) -> Generics {
+    let mut generics = generics.clone();
+    for p in generics.params.iter_mut() {
+        if let GenericParam::Type(ref mut t) = *p {
+            t.bounds.push(bound.clone());
+        }
+    }
+    generics.params.push(param);
+    generics
+}
+
+fn option(max: &Option<Expr>) -> Tokens {
+    match *max {
+        Some(ref max) => quote!(::std::option::Option::Some(#max)),
+        None => quote!(::std::option::Option::None),
+    }
+}
+
+fn pack_field(codec: &Codec, access: &Tokens) -> Tokens {
+    match *codec {
+        Codec::Plain | Codec::Trailing => quote!(::xdr_codec::Pack::pack(&#access, out)?),
+        Codec::String(ref max) => quote!(::xdr_codec::pack_string(&#access, Some(#max), out)?),
+        Codec::Flex(ref max) => quote!(::xdr_codec::pack_flex(&#access, Some(#max), out)?),
+        Codec::OpaqueFlex(ref max) => {
+            let max = option(max);
+            quote!(::xdr_codec::pack_opaque_flex(&#access, #max, out)?)
+        },
+        Codec::OpaqueArray => quote!(::xdr_codec::pack_opaque_array(&#access[..], #access.len(), out)?),
+    }
+}
+
+fn unpack_field(codec: &Codec, ty: &Type) -> Tokens {
+    match *codec {
+        Codec::Plain =>
+            quote!({ let (v, fsz) = ::xdr_codec::Unpack::unpack(input)?; sz += fsz; v }),
+        Codec::String(ref max) =>
+            quote!({ let (v, fsz) = ::xdr_codec::unpack_string(input, Some(#max))?; sz += fsz; v }),
+        Codec::Flex(ref max) =>
+            quote!({ let (v, fsz) = ::xdr_codec::unpack_flex(input, Some(#max))?; sz += fsz; v }),
+        Codec::OpaqueFlex(ref max) => {
+            let max = option(max);
+            quote!({ let (v, fsz) = ::xdr_codec::unpack_opaque_flex(input, #max)?; sz += fsz; v })
+        },
+        Codec::Trailing =>
+            quote!({ let (v, fsz) = ::xdr_codec::unpack_trailing(input)?; sz += fsz; v }),
+        Codec::OpaqueArray =>
+            // The array length is the size of the type, as it's an array of bytes
+            quote!({
+                let mut v: #ty = [0; ::std::mem::size_of::<#ty>()];
+                let len = v.len();
+                sz += ::xdr_codec::unpack_opaque_array(input, &mut v[..], len)?;
+                v
+            }),
+    }
+}
+
+fn struct_fields<'a>(input: &'a DeriveInput, what: &str) -> syn::Result<Vec<(Codec, &'a syn::Field)>> {
+    let fields = match input.data {
+        Data::Struct(ref s) => &s.fields,
+        _ => return Err(syn::Error::new_spanned(&input.ident, format!("{} can only be derived for structs", what))),
+    };
+    let fields = fields.iter().map(|f| field_codec(f).map(|c| (c, f))).collect::<syn::Result<Vec<_>>>()?;
+
+    // Once one field is missing, so are all the ones after it
+    if let Some(first) = fields.iter().position(|(c, _)| matches!(c, Codec::Trailing)) {
+        if let Some(&(_, f)) = fields[first..].iter().find(|(c, _)| !matches!(c, Codec::Trailing)) {
+            return Err(syn::Error::new_spanned(f, "fields after a trailing field must also be trailing"));
+        }
+    }
+    Ok(fields)
+}
+
+fn derive_pack(input: &DeriveInput) -> syn::Result<Tokens> {
+    let fields = struct_fields(input, "XdrPack")?;
+    let name = &input.ident;
+
+    let packs = fields.iter().enumerate().map(|(i, &(ref codec, field))| {
+        let access = match field.ident {
+            Some(ref id) => quote!(self.#id),
+            None => {
+                let idx = Index::from(i);
+                quote!(self.#idx)
+            },
+        };
+        pack_field(codec, &access)
+    });
+
+    let generics = impl_generics(&input.generics,
+                                 parse_quote!(__Out: ::xdr_codec::Write),
+                                 parse_quote!(::xdr_codec::Pack<__Out>));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let body = if fields.is_empty() {
+        quote!(let _ = out; Ok(0))
+    } else {
+        quote!(let mut sz = 0; #( sz += #packs; )* Ok(sz))
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::xdr_codec::Pack<__Out> for #name #ty_generics #where_clause {
+            #[inline]
+            fn pack(&self, out: &mut __Out) -> ::xdr_codec::Result<usize> {
+                #body
+            }
+        }
+    })
+}
+
+fn derive_unpack(input: &DeriveInput) -> syn::Result<Tokens> {
+    let fields = struct_fields(input, "XdrUnpack")?;
+    let name = &input.ident;
+
+    let unpacks: Vec<_> = fields.iter().map(|&(ref codec, field)| unpack_field(codec, &field.ty)).collect();
+    let ctor = match input.data {
+        Data::Struct(ref s) => match s.fields {
+            Fields::Named(_) => {
+                let names = fields.iter().map(|&(_, f)| f.ident.as_ref().unwrap());
+                quote!(#name { #( #names: #unpacks, )* })
+            },
+            Fields::Unnamed(_) => quote!(#name ( #( #unpacks, )* )),
+            Fields::Unit => quote!(#name),
+        },
+        _ => unreachable!(),
+    };
+
+    let generics = impl_generics(&input.generics,
+                                 parse_quote!(__In: ::xdr_codec::Read),
+                                 parse_quote!(::xdr_codec::Unpack<__In>));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    let body = if fields.is_empty() {
+        quote!(let _ = input; Ok((#ctor, 0)))
+    } else {
+        quote!(let mut sz = 0; let ret = #ctor; Ok((ret, sz)))
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::xdr_codec::Unpack<__In> for #name #ty_generics #where_clause {
+            #[inline]
+            fn unpack(input: &mut __In) -> ::xdr_codec::Result<(Self, usize)> {
+                #body
+            }
+        }
+    })
+}
+
+fn derive_fixed_size(input: &DeriveInput) -> syn::Result<Tokens> {
+    let fields = struct_fields(input, "XdrFixedSize")?;
+    let name = &input.ident;
+
+    let sizes = fields.iter().map(|&(ref codec, field)| {
+        let ty = &field.ty;
+        match *codec {
+            Codec::Plain => Ok(quote!(<#ty as ::xdr_codec::PackedFixedSize>::SIZE)),
+            // Padded to a multiple of 4
+            Codec::OpaqueArray => Ok(quote!((::std::mem::size_of::<#ty>() + 3) & !3)),
+            _ => Err(syn::Error::new_spanned(field, "field doesn't have a fixed size")),
+        }
+    }).collect::<syn::Result<Vec<_>>>()?;
+
+    let mut generics = input.generics.clone();
+    for p in generics.params.iter_mut() {
+        if let GenericParam::Type(ref mut t) = *p {
+            t.bounds.push(parse_quote!(::xdr_codec::PackedFixedSize));
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::xdr_codec::PackedFixedSize for #name #ty_generics #where_clause {
+            const SIZE: usize = 0 #( + #sizes )*;
+        }
+    })
+}
+
+fn derive_reflect(input: &DeriveInput) -> syn::Result<Tokens> {
+    let fields = struct_fields(input, "XdrReflect")?;
+    let name = &input.ident;
+    let xdrname = name.to_string();
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "XdrReflect can't be derived for generic structs"));
+    }
+
+    let types = fields.iter().enumerate().map(|(i, &(ref codec, field))| {
+        let fname = match field.ident {
+            Some(ref id) => id.to_string(),
+            None => format!("f{}", i),
+        };
+        let ty = &field.ty;
+        let xdrty = match *codec {
+            Codec::Plain | Codec::Trailing => quote!(<#ty as ::xdr_codec::reflect::XdrReflect>::xdr_type(schema)),
+            Codec::String(ref max) | Codec::Flex(ref max) =>
+                quote!(<#ty as ::xdr_codec::reflect::XdrReflect>::xdr_type(schema).with_max(#max)),
+            Codec::OpaqueFlex(ref max) => {
+                let max = option(max);
+                quote!(::xdr_codec::value::Type::Opaque(#max))
+            },
+            Codec::OpaqueArray => quote!(::xdr_codec::value::Type::FixedOpaque(::std::mem::size_of::<#ty>())),
+        };
+        quote!((::std::string::String::from(#fname), #xdrty))
+    });
+
+    Ok(quote! {
+        impl ::xdr_codec::reflect::XdrReflect for #name {
+            fn xdr_type(schema: &mut ::xdr_codec::value::Schema) -> ::xdr_codec::value::Type {
+                if schema.get(#xdrname).is_none() {
+                    // Defined first so that recursive references stop here
+                    schema.define(#xdrname, ::xdr_codec::value::Type::Void);
+                    let ty = ::xdr_codec::value::Type::Struct(vec![ #( #types, )* ]);
+                    schema.define(#xdrname, ty);
+                }
+                ::xdr_codec::value::Type::Named(::std::string::String::from(#xdrname))
+            }
+        }
+    })
+}
+
+fn expand(input: TokenStream, f: fn(&DeriveInput) -> syn::Result<Tokens>) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match f(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+/// Derive `Pack` for a struct.
+#[proc_macro_derive(XdrPack, attributes(xdr))]
+pub fn xdr_pack(input: TokenStream) -> TokenStream {
+    expand(input, derive_pack)
+}
+
+/// Derive `Unpack` for a struct.
+#[proc_macro_derive(XdrUnpack, attributes(xdr))]
+pub fn xdr_unpack(input: TokenStream) -> TokenStream {
+    expand(input, derive_unpack)
+}
+
+/// Derive `PackedFixedSize` for a struct.
+#[proc_macro_derive(XdrFixedSize, attributes(xdr))]
+pub fn xdr_fixed_size(input: TokenStream) -> TokenStream {
+    expand(input, derive_fixed_size)
+}
+
+/// Derive `xdr_codec::reflect::XdrReflect` for a struct.
+#[proc_macro_derive(XdrReflect, attributes(xdr))]
+pub fn xdr_reflect(input: TokenStream) -> TokenStream {
+    expand(input, derive_reflect)
+}
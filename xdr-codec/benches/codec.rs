@@ -0,0 +1,143 @@
+//! Pack/unpack microbenchmarks.
+//!
+//! Run with `cargo bench --features perf`. These use a plain timing loop rather
+//! than the unstable `test` crate so they work on stable Rust. Each benchmark
+//! reports the mean time per iteration over a fixed wall-clock budget.
+extern crate xdr_codec;
+
+use std::hint::black_box;
+use std::io::Cursor;
+use std::time::{Duration, Instant};
+
+use xdr_codec::{Pack, Unpack, Opaque, pack_flex, pack_string, unpack_flex, unpack_string};
+
+const BUDGET: Duration = Duration::from_millis(500);
+
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    // warm up
+    for _ in 0..100 {
+        f();
+    }
+
+    let mut iters = 0u64;
+    let start = Instant::now();
+    while start.elapsed() < BUDGET {
+        for _ in 0..100 {
+            f();
+        }
+        iters += 100;
+    }
+    let elapsed = start.elapsed();
+    let ns = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+
+    println!("{:<32} {:>12.1} ns/iter ({} iters)", name, ns as f64 / iters as f64, iters);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Message {
+    xid: u32,
+    proc_: u32,
+    cookie: u64,
+    flags: Option<u32>,
+    name: String,
+    data: Vec<u32>,
+}
+
+impl<Out: xdr_codec::Write> Pack<Out> for Message {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> xdr_codec::Result<usize> {
+        let mut sz = 0;
+        sz += self.xid.pack(out)?;
+        sz += self.proc_.pack(out)?;
+        sz += self.cookie.pack(out)?;
+        sz += self.flags.pack(out)?;
+        sz += pack_string(&self.name, Some(255), out)?;
+        sz += pack_flex(&self.data, None, out)?;
+        Ok(sz)
+    }
+}
+
+impl<In: xdr_codec::Read> Unpack<In> for Message {
+    #[inline]
+    fn unpack(input: &mut In) -> xdr_codec::Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = Message {
+            xid: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            proc_: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            cookie: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            flags: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            name: { let (v, fsz) = unpack_string(input, Some(255))?; sz += fsz; v },
+            data: { let (v, fsz) = unpack_flex(input, None)?; sz += fsz; v },
+        };
+        Ok((ret, sz))
+    }
+}
+
+fn encoded<T: Pack<Vec<u8>>>(v: &T) -> Vec<u8> {
+    let mut buf = Vec::new();
+    v.pack(&mut buf).expect("pack failed");
+    buf
+}
+
+fn main() {
+    let msg = Message {
+        xid: 0x1234_5678,
+        proc_: 7,
+        cookie: 0xdead_beef_cafe_f00d,
+        flags: Some(3),
+        name: String::from("some/file/name"),
+        data: (0..32).collect(),
+    };
+    let u32s: Vec<u32> = (0..1024).collect();
+    let f64s: Vec<f64> = (0..1024).map(|v| v as f64 * 0.5).collect();
+    let opaque = vec![0xa5u8; 4093];
+
+    let mut buf = Vec::with_capacity(64 * 1024);
+
+    bench("pack u32", || {
+        buf.clear();
+        black_box(black_box(42u32).pack(&mut buf).unwrap());
+    });
+    bench("pack Vec<u32> x1024", || {
+        buf.clear();
+        black_box(black_box(&u32s).pack(&mut buf).unwrap());
+    });
+    bench("pack Vec<f64> x1024", || {
+        buf.clear();
+        black_box(black_box(&f64s).pack(&mut buf).unwrap());
+    });
+    bench("pack opaque 4093", || {
+        buf.clear();
+        black_box(Opaque::borrowed(black_box(&opaque[..])).pack(&mut buf).unwrap());
+    });
+    bench("pack struct", || {
+        buf.clear();
+        black_box(black_box(&msg).pack(&mut buf).unwrap());
+    });
+
+    let u32s_enc = encoded(&u32s);
+    let f64s_enc = encoded(&f64s);
+    let opaque_enc = encoded(&Opaque::borrowed(&opaque));
+    let msg_enc = encoded(&msg);
+
+    bench("unpack u32", || {
+        let mut cur = Cursor::new(&u32s_enc[4..]);
+        black_box(u32::unpack(&mut cur).unwrap());
+    });
+    bench("unpack Vec<u32> x1024", || {
+        let mut cur = Cursor::new(&u32s_enc[..]);
+        black_box(Vec::<u32>::unpack(&mut cur).unwrap());
+    });
+    bench("unpack Vec<f64> x1024", || {
+        let mut cur = Cursor::new(&f64s_enc[..]);
+        black_box(Vec::<f64>::unpack(&mut cur).unwrap());
+    });
+    bench("unpack opaque 4093", || {
+        let mut cur = Cursor::new(&opaque_enc[..]);
+        black_box(Opaque::unpack(&mut cur).unwrap());
+    });
+    bench("unpack struct", || {
+        let mut cur = Cursor::new(&msg_enc[..]);
+        black_box(Message::unpack(&mut cur).unwrap());
+    });
+}
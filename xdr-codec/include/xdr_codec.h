@@ -0,0 +1,80 @@
+/*
+ * C interface to xdr-codec, built with the `ffi` feature. See the `ffi`
+ * module documentation for details.
+ */
+#ifndef XDR_CODEC_H
+#define XDR_CODEC_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Status codes */
+#define XDR_OK           0  /* success */
+#define XDR_EMPTY        1  /* no record ready */
+#define XDR_ERR_NULL    -1  /* null pointer argument */
+#define XDR_ERR_SHORT   -2  /* input ended in the middle of a value */
+#define XDR_ERR_LEN     -3  /* length over its limit or buffer size */
+#define XDR_ERR_INVALID -4  /* invalid value */
+#define XDR_ERR_FRAMING -5  /* record framing error */
+#define XDR_ERR_OTHER   -6  /* any other failure */
+
+/* No limit, for `maxsz` arguments */
+#define XDR_NOMAX SIZE_MAX
+
+typedef struct XdrEncoder XdrEncoder;
+typedef struct XdrDecoder XdrDecoder;
+typedef struct XdrRecordDecoder XdrRecordDecoder;
+
+const char *xdr_strerror(int status);
+
+XdrEncoder *xdr_encoder_new(void);
+void xdr_encoder_free(XdrEncoder *enc);
+const uint8_t *xdr_encoder_data(const XdrEncoder *enc, size_t *len);
+void xdr_encoder_clear(XdrEncoder *enc);
+
+/* `data` must remain valid and unchanged until the decoder is freed */
+XdrDecoder *xdr_decoder_new(const uint8_t *data, size_t len);
+void xdr_decoder_free(XdrDecoder *dec);
+size_t xdr_decoder_remaining(const XdrDecoder *dec);
+
+int xdr_pack_int(XdrEncoder *enc, int32_t val);
+int xdr_pack_uint(XdrEncoder *enc, uint32_t val);
+int xdr_pack_hyper(XdrEncoder *enc, int64_t val);
+int xdr_pack_uhyper(XdrEncoder *enc, uint64_t val);
+int xdr_pack_float(XdrEncoder *enc, float val);
+int xdr_pack_double(XdrEncoder *enc, double val);
+int xdr_pack_bool(XdrEncoder *enc, bool val);
+int xdr_pack_opaque_fixed(XdrEncoder *enc, const uint8_t *data, size_t len);
+int xdr_pack_opaque(XdrEncoder *enc, const uint8_t *data, size_t len, size_t maxsz);
+int xdr_pack_string(XdrEncoder *enc, const char *s, size_t maxsz);
+
+int xdr_unpack_int(XdrDecoder *dec, int32_t *val);
+int xdr_unpack_uint(XdrDecoder *dec, uint32_t *val);
+int xdr_unpack_hyper(XdrDecoder *dec, int64_t *val);
+int xdr_unpack_uhyper(XdrDecoder *dec, uint64_t *val);
+int xdr_unpack_float(XdrDecoder *dec, float *val);
+int xdr_unpack_double(XdrDecoder *dec, double *val);
+int xdr_unpack_bool(XdrDecoder *dec, bool *val);
+int xdr_unpack_opaque_fixed(XdrDecoder *dec, uint8_t *buf, size_t len);
+int xdr_unpack_opaque(XdrDecoder *dec, uint8_t *buf, size_t bufsz, size_t *len);
+int xdr_unpack_string(XdrDecoder *dec, char *buf, size_t bufsz, size_t *len);
+
+/* Append `data` to `out` as one record; `fragsz` 0 uses the default */
+int xdr_record_frame(XdrEncoder *out, const uint8_t *data, size_t len, size_t fragsz);
+
+XdrRecordDecoder *xdr_record_decoder_new(size_t max_fragment, size_t max_record);
+void xdr_record_decoder_free(XdrRecordDecoder *rd);
+int xdr_record_decoder_feed(XdrRecordDecoder *rd, const uint8_t *data, size_t len);
+/* The record is valid until the next call, or until the decoder is freed */
+int xdr_record_decoder_next(XdrRecordDecoder *rd, const uint8_t **data, size_t *len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* XDR_CODEC_H */
@@ -0,0 +1,127 @@
+//! `quickcheck` support
+//!
+//! Implements `quickcheck::Arbitrary` for the wrapper types in this crate (`Opaque`,
+//! `BoundedString`, `BoundedVec`, `FixedOpaque` and `XdrString`), so types built from them can
+//! be generated for property tests. Bounded types never exceed their limit, including when
+//! shrinking.
+//!
+//! `roundtrips` checks that a value survives packing and unpacking, and `quickcheck_roundtrip`
+//! runs it over arbitrary values of a type:
+//!
+//! ```
+//! # extern crate xdr_codec;
+//! use xdr_codec::bounded::BoundedVec;
+//! use xdr_codec::arbitrary::quickcheck_roundtrip;
+//!
+//! # fn main() {
+//! quickcheck_roundtrip::<BoundedVec<u32, 10>>();
+//! # }
+//! ```
+//!
+//! This module is only available with the `quickcheck` feature.
+use std::fmt::Debug;
+
+use quickcheck::{Arbitrary, Gen, quickcheck};
+
+use super::{Pack, Unpack, Opaque, pack_to_vec, unpack_from_bytes};
+use bounded::{BoundedString, BoundedVec};
+use fixed::FixedOpaque;
+use string::XdrString;
+
+impl Arbitrary for Opaque<'static> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        Opaque::owned(Arbitrary::arbitrary(g))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.0.to_vec().shrink().map(Opaque::owned))
+    }
+}
+
+impl<const MAX: usize> Arbitrary for BoundedString<MAX> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let mut s = String::arbitrary(g);
+        let mut len = s.len().min(MAX);
+
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        s.truncate(len);
+
+        BoundedString::new(s).expect("truncated string too long")
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.as_str().to_string().shrink().filter_map(|s| BoundedString::new(s).ok()))
+    }
+}
+
+impl<T: Arbitrary, const MAX: usize> Arbitrary for BoundedVec<T, MAX> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let mut v = Vec::arbitrary(g);
+        v.truncate(MAX);
+
+        BoundedVec::new(v).expect("truncated vec too long")
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.as_slice().to_vec().shrink().filter_map(|v| BoundedVec::new(v).ok()))
+    }
+}
+
+impl<const N: usize> Arbitrary for FixedOpaque<N> {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let mut bytes = [0; N];
+
+        for b in bytes.iter_mut() {
+            *b = u8::arbitrary(g);
+        }
+
+        FixedOpaque(bytes)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        // Shrink towards all zeros, one byte at a time
+        let bytes = self.0;
+
+        Box::new((0..N).filter(move |&i| bytes[i] != 0).map(move |i| {
+            let mut b = bytes;
+            b[i] = 0;
+            FixedOpaque(b)
+        }))
+    }
+}
+
+impl Arbitrary for XdrString {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        XdrString(Arbitrary::arbitrary(g))
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.0.shrink().map(XdrString))
+    }
+}
+
+/// Return true if `val` unpacks to an equal value from its own encoding, using all of it.
+pub fn roundtrips<T>(val: T) -> bool
+    where T: PartialEq + Pack<Vec<u8>> + for<'a> Unpack<&'a [u8]>
+{
+    let enc = match pack_to_vec(&val) {
+        Ok(enc) => enc,
+        Err(_) => return false,
+    };
+
+    match unpack_from_bytes::<T>(&enc) {
+        Ok((uv, sz)) => sz == enc.len() && uv == val,
+        Err(_) => false,
+    }
+}
+
+/// Check with `quickcheck` that arbitrary values of `T` survive packing and unpacking.
+///
+/// Panics (failing the test) with a minimal counterexample if one doesn't.
+pub fn quickcheck_roundtrip<T>()
+    where T: Arbitrary + Debug + PartialEq + Pack<Vec<u8>> + for<'a> Unpack<&'a [u8]>
+{
+    quickcheck(roundtrips as fn(T) -> bool);
+}
@@ -0,0 +1,435 @@
+//! Arena allocation for decoding
+//!
+//! Unpacking a message with many arrays, opaques and strings makes an allocation for each of
+//! them, which can dominate the cost of decoding at high request rates. The functions here are
+//! counterparts of `unpack_flex`, `unpack_opaque_flex` and `unpack_string` which allocate from a
+//! `bumpalo::Bump` arena supplied by the caller instead. The arena can be reset once the request
+//! has been handled, freeing everything decoded into it at once:
+//!
+//! ```
+//! use xdr_codec::arena::{Bump, unpack_opaque_flex_in, unpack_string_in};
+//!
+//! let mut bump = Bump::new();
+//! for _ in 0..3 {
+//!     {
+//!         let mut input = &[0, 0, 0, 2,  b'h', b'i', 0, 0,  0, 0, 0, 1,  0xff, 0, 0, 0][..];
+//!         let (name, _) = unpack_string_in(&mut input, None, &bump).unwrap();
+//!         let (data, _) = unpack_opaque_flex_in(&mut input, None, &bump).unwrap();
+//!         assert_eq!((name, data), ("hi", &[0xff][..]));
+//!     }
+//!     bump.reset();
+//! }
+//! ```
+//!
+//! As with the heap allocating versions, the lengths read from the input aren't trusted to size
+//! allocations, so a bogus length can't exhaust the arena before the data has actually been read.
+//! Strings honour `DecodeOptions::lossy_strings`.
+//!
+//! `XdrValueRef` is the arena counterpart of the dynamically typed `Value`, for generic tools
+//! which decode a lot of messages. `Schema::unpack_in` decodes one from a byte slice: opaque data
+//! and strings are borrowed from the input rather than copied, and the arrays, structs and unions
+//! holding them are allocated from the arena, so nothing is allocated per value and nothing needs
+//! dropping. `to_value` makes an owned `Value` from one which has to outlive the arena.
+//!
+//! ```
+//! use xdr_codec::arena::{Bump, XdrValueRef};
+//! use xdr_codec::value::{Schema, Type};
+//!
+//! let mut schema = Schema::new();
+//! schema.define("file", Type::Struct(vec![("name".to_string(), Type::String(None)),
+//!                                         ("size".to_string(), Type::UHyper)]));
+//!
+//! let enc = [0, 0, 0, 2,  b'h', b'i', 0, 0,  0, 0, 0, 0, 0, 0, 0, 42];
+//! let bump = Bump::new();
+//! let (val, _) = schema.unpack_named_in("file", &mut &enc[..], &bump).unwrap();
+//! assert_eq!(val.field("name"), Some(&XdrValueRef::String("hi")));
+//! assert_eq!(val.field("size").and_then(XdrValueRef::as_i64), Some(42));
+//! ```
+//!
+//! This module is only available with the `bumpalo` feature.
+use std::io::{self, Read, Write};
+use std::str;
+
+pub use bumpalo::Bump;
+use bumpalo::collections::Vec as BumpVec;
+
+use error::*;
+use options::{check_input_len, decode_options};
+use quadruple::XdrQuadruple;
+use value::{Schema, Type, Value, nest};
+use super::{Pack, Unpack, MAX_PREALLOC, check_elems, prealloc, pack_array, pack_flex, pack_opaque_array,
+            pack_opaque_flex, pack_string, padding, unpack_opaque_borrowed, unpack_padding};
+
+fn flex_len<In: Read>(input: &mut In, maxsz: Option<usize>) -> Result<(usize, usize)> {
+    let (elems, sz) = usize::unpack(input)?;
+
+    if let Some(m) = maxsz {
+        if elems > m {
+            bail!(ErrorKind::InvalidLen(m));
+        }
+    }
+    Ok((elems, sz))
+}
+
+/// Unpack a (perhaps) length-limited array into a `Vec` allocated from `bump`.
+pub fn unpack_flex_in<'b, In, T>(input: &mut In, maxsz: Option<usize>, bump: &'b Bump)
+                                 -> Result<(BumpVec<'b, T>, usize)>
+    where In: Read, T: Unpack<In>
+{
+    let (elems, mut sz) = flex_len(input, maxsz)?;
+    check_elems::<T>(elems)?;
+    let mut out = BumpVec::with_capacity_in(prealloc::<T>(elems), bump);
+
+    for _ in 0..elems {
+        let (e, esz) = T::unpack(input)?;
+        out.push(e);
+        sz += esz;
+    }
+    sz += unpack_padding(input, sz)?;
+
+    Ok((out, sz))
+}
+
+/// Unpack a (perhaps) length-limited opaque array into a slice allocated from `bump`.
+pub fn unpack_opaque_flex_in<'b, In: Read>(input: &mut In, maxsz: Option<usize>, bump: &'b Bump)
+                                           -> Result<(&'b [u8], usize)> {
+    let (elems, mut sz) = flex_len(input, maxsz)?;
+    check_input_len(elems)?;
+    let mut out = BumpVec::with_capacity_in(prealloc::<u8>(elems), bump);
+
+    // Grow as the data arrives, rather than trusting the length
+    while out.len() < elems {
+        let start = out.len();
+        let chunk = std::cmp::min(elems - start, MAX_PREALLOC);
+        out.resize(start + chunk, 0);
+        if let Err(e) = input.read_exact(&mut out[start..]) {
+            return Err(match e.kind() {
+                io::ErrorKind::UnexpectedEof => io::Error::new(io::ErrorKind::UnexpectedEof, "short opaque data"),
+                _ => e,
+            }.into());
+        }
+    }
+    sz += elems;
+    sz += unpack_padding(input, sz)?;
+
+    Ok((out.into_bump_slice(), sz))
+}
+
+/// Unpack a (perhaps) length-limited string into a `str` allocated from `bump`.
+pub fn unpack_string_in<'b, In: Read>(input: &mut In, maxsz: Option<usize>, bump: &'b Bump)
+                                      -> Result<(&'b str, usize)> {
+    let (v, sz) = unpack_opaque_flex_in(input, maxsz, bump)?;
+    Ok((utf8_in(v, bump)?, sz))
+}
+
+// Check string data is UTF-8, repairing it into `bump` if strings are lossy
+fn utf8_in<'b>(v: &'b [u8], bump: &'b Bump) -> Result<&'b str> {
+    match str::from_utf8(v) {
+        Ok(s) => Ok(s),
+        Err(_) if decode_options().is_lossy_strings() => Ok(bump.alloc_str(&String::from_utf8_lossy(v))),
+        Err(_) => Err(String::from_utf8(v.to_vec()).unwrap_err().into()),
+    }
+}
+
+/// A dynamically typed XDR value, allocated from an arena.
+///
+/// This has the same variants as `Value`, but opaque data and strings are borrowed from the
+/// input, and everything else is allocated from the arena the value was decoded into. It packs
+/// to the encoding it was decoded from (with padding zeroed).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum XdrValueRef<'a> {
+    /// `void`
+    Void,
+    /// `int`
+    Int(i32),
+    /// `unsigned int`
+    UInt(u32),
+    /// `hyper`
+    Hyper(i64),
+    /// `unsigned hyper`
+    UHyper(u64),
+    /// `float`
+    Float(f32),
+    /// `double`
+    Double(f64),
+    /// `quadruple`
+    Quadruple(XdrQuadruple),
+    /// `bool`
+    Bool(bool),
+    /// An enum value, with the name of its variant.
+    Enum { name: &'a str, value: i32 },
+    /// `opaque[n]`
+    FixedOpaque(&'a [u8]),
+    /// `opaque<>`
+    Opaque(&'a [u8]),
+    /// `string<>`
+    String(&'a str),
+    /// `T[n]`
+    FixedArray(&'a [XdrValueRef<'a>]),
+    /// `T<>`
+    Array(&'a [XdrValueRef<'a>]),
+    /// `T *`
+    Optional(Option<&'a XdrValueRef<'a>>),
+    /// A struct's fields, in order.
+    Struct(&'a [(&'a str, XdrValueRef<'a>)]),
+    /// A union's discriminant and the value of the selected arm.
+    Union { discriminant: &'a XdrValueRef<'a>, arm: &'a XdrValueRef<'a> },
+}
+
+impl<'a> XdrValueRef<'a> {
+    /// Return the named field of a struct.
+    pub fn field(&self, name: &str) -> Option<&'a XdrValueRef<'a>> {
+        match *self {
+            XdrValueRef::Struct(fields) => fields.iter().find(|f| f.0 == name).map(|f| &f.1),
+            _ => None,
+        }
+    }
+
+    /// Return an integral value (including an enum or bool) as an `i64`, if it fits.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            XdrValueRef::Int(v) => Some(v as i64),
+            XdrValueRef::UInt(v) => Some(v as i64),
+            XdrValueRef::Hyper(v) => Some(v),
+            XdrValueRef::UHyper(v) if v <= i64::MAX as u64 => Some(v as i64),
+            XdrValueRef::Bool(v) => Some(v as i64),
+            XdrValueRef::Enum { value, .. } => Some(value as i64),
+            _ => None,
+        }
+    }
+
+    /// Copy this into an owned `Value`.
+    pub fn to_value(&self) -> Value {
+        let elems = |v: &[XdrValueRef]| v.iter().map(XdrValueRef::to_value).collect();
+
+        match *self {
+            XdrValueRef::Void => Value::Void,
+            XdrValueRef::Int(v) => Value::Int(v),
+            XdrValueRef::UInt(v) => Value::UInt(v),
+            XdrValueRef::Hyper(v) => Value::Hyper(v),
+            XdrValueRef::UHyper(v) => Value::UHyper(v),
+            XdrValueRef::Float(v) => Value::Float(v),
+            XdrValueRef::Double(v) => Value::Double(v),
+            XdrValueRef::Quadruple(v) => Value::Quadruple(v),
+            XdrValueRef::Bool(v) => Value::Bool(v),
+            XdrValueRef::Enum { name, value } => Value::Enum { name: name.to_string(), value },
+            XdrValueRef::FixedOpaque(v) => Value::FixedOpaque(v.to_vec()),
+            XdrValueRef::Opaque(v) => Value::Opaque(v.to_vec()),
+            XdrValueRef::String(v) => Value::String(v.to_string()),
+            XdrValueRef::FixedArray(v) => Value::FixedArray(elems(v)),
+            XdrValueRef::Array(v) => Value::Array(elems(v)),
+            XdrValueRef::Optional(v) => Value::Optional(v.map(|v| Box::new(v.to_value()))),
+            XdrValueRef::Struct(fields) =>
+                Value::Struct(fields.iter().map(|&(name, ref v)| (name.to_string(), v.to_value())).collect()),
+            XdrValueRef::Union { discriminant, arm } =>
+                Value::Union { discriminant: Box::new(discriminant.to_value()), arm: Box::new(arm.to_value()) },
+        }
+    }
+}
+
+impl<'a, Out: Write> Pack<Out> for XdrValueRef<'a> {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        match *self {
+            XdrValueRef::Void => Ok(0),
+            XdrValueRef::Int(v) => v.pack(out),
+            XdrValueRef::UInt(v) => v.pack(out),
+            XdrValueRef::Hyper(v) => v.pack(out),
+            XdrValueRef::UHyper(v) => v.pack(out),
+            XdrValueRef::Float(v) => v.pack(out),
+            XdrValueRef::Double(v) => v.pack(out),
+            XdrValueRef::Quadruple(ref v) => v.pack(out),
+            XdrValueRef::Bool(v) => v.pack(out),
+            XdrValueRef::Enum { value, .. } => value.pack(out),
+            XdrValueRef::FixedOpaque(v) => pack_opaque_array(v, v.len(), out),
+            XdrValueRef::Opaque(v) => pack_opaque_flex(v, None, out),
+            XdrValueRef::String(v) => pack_string(v, None, out),
+            XdrValueRef::FixedArray(v) => pack_array(v, v.len(), out, None),
+            XdrValueRef::Array(v) => pack_flex(v, None, out),
+            XdrValueRef::Optional(v) => match v {
+                Some(v) => Ok(true.pack(out)? + v.pack(out)?),
+                None => false.pack(out),
+            },
+            XdrValueRef::Struct(fields) => {
+                let mut sz = 0;
+                for (_, v) in fields {
+                    sz += v.pack(out)?;
+                }
+                Ok(sz)
+            }
+            XdrValueRef::Union { discriminant, arm } => Ok(discriminant.pack(out)? + arm.pack(out)?),
+        }
+    }
+}
+
+// Borrow `n` bytes of fixed-size opaque data from the input, skipping its padding
+fn opaque_array_borrowed<'a>(input: &mut &'a [u8], n: usize) -> Result<(&'a [u8], usize)> {
+    let padded = match n.checked_add(padding(n).len()) {
+        Some(p) if p <= input.len() => p,
+        _ => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short opaque array").into()),
+    };
+
+    let data = *input;
+    *input = &data[padded..];
+    Ok((&data[..n], padded))
+}
+
+impl Schema {
+    /// Unpack a value of the named type into `bump`, borrowing its opaque data and strings from
+    /// `input`.
+    pub fn unpack_named_in<'a>(&'a self, name: &str, input: &mut &'a [u8], bump: &'a Bump)
+                               -> Result<(XdrValueRef<'a>, usize)> {
+        match self.get(name) {
+            Some(ty) => self.unpack_in(ty, input, bump),
+            None => bail!(ErrorKind::UnknownType(name.to_string())),
+        }
+    }
+
+    /// Unpack a value of type `ty` into `bump`, borrowing its opaque data and strings from
+    /// `input`. Returns the value and the number of bytes consumed.
+    ///
+    /// Enum values and union discriminants which aren't listed fail with `InvalidEnum` and
+    /// `InvalidCase`, and references to undefined types with `UnknownType`. As with
+    /// `Schema::unpack`, values nested more deeply than `DecodeOptions::max_depth` fail with
+    /// `DepthExceeded`, and variable-length arrays of a type with an empty encoding with
+    /// `InvalidSchema`. Everything allocated from `bump` before a failure stays there until it's
+    /// reset.
+    pub fn unpack_in<'a>(&'a self, ty: &'a Type, input: &mut &'a [u8], bump: &'a Bump)
+                         -> Result<(XdrValueRef<'a>, usize)> {
+        self.unpack_nested_in(ty, input, bump, decode_options().get_max_depth())
+    }
+
+    fn unpack_nested_in<'a>(&'a self, ty: &'a Type, input: &mut &'a [u8], bump: &'a Bump, depth: usize)
+                            -> Result<(XdrValueRef<'a>, usize)> {
+        let depth = nest(depth)?;
+        match *self.resolve(ty)? {
+            Type::FixedArray(ref elem, n) => self.unpack_elems_in(elem, n, input, bump, depth)
+                .map(|(v, sz)| (XdrValueRef::FixedArray(v), sz)),
+            Type::Array(ref elem, max) => self.unpack_array_in(elem, max, input, bump, depth),
+            Type::Optional(ref t) => self.unpack_optional_in(t, input, bump, depth),
+            Type::Struct(ref fields) => self.unpack_struct_in(fields, input, bump, depth),
+            Type::Union { ref discriminant, ref cases, ref default } =>
+                self.unpack_union_in(discriminant, cases, default.as_deref(), input, bump, depth),
+            ref ty => unpack_scalar_in(ty, input, bump),
+        }
+    }
+
+    // As in `Schema::unpack`, the rest of the cases are kept out of `unpack_nested_in` to keep
+    // its stack frame small.
+    fn unpack_array_in<'a>(&'a self, elem: &'a Type, max: Option<usize>, input: &mut &'a [u8], bump: &'a Bump,
+                           depth: usize) -> Result<(XdrValueRef<'a>, usize)> {
+        let (n, lsz) = usize::unpack(input)?;
+        if max.map_or(false, |m| n > m) {
+            bail!(ErrorKind::InvalidLen(max.unwrap()));
+        }
+        self.check_array(elem)?;
+        // Every element takes at least a word
+        if n.saturating_mul(4) > input.len() {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "length exceeds input").into());
+        }
+        let (v, sz) = self.unpack_elems_in(elem, n, input, bump, depth)?;
+        Ok((XdrValueRef::Array(v), lsz + sz))
+    }
+
+    fn unpack_optional_in<'a>(&'a self, t: &'a Type, input: &mut &'a [u8], bump: &'a Bump, depth: usize)
+                              -> Result<(XdrValueRef<'a>, usize)> {
+        let (have, mut sz) = bool::unpack(input)?;
+        let v = if have {
+            let (v, vsz) = self.unpack_nested_in(t, input, bump, depth)?;
+            sz += vsz;
+            Some(alloc(bump, v))
+        } else {
+            None
+        };
+        Ok((XdrValueRef::Optional(v), sz))
+    }
+
+    fn unpack_struct_in<'a>(&'a self, fields: &'a [(String, Type)], input: &mut &'a [u8], bump: &'a Bump,
+                            depth: usize) -> Result<(XdrValueRef<'a>, usize)> {
+        let mut sz = 0;
+        let mut v = BumpVec::with_capacity_in(fields.len(), bump);
+        for (name, t) in fields {
+            let (fv, fsz) = self.unpack_nested_in(t, input, bump, depth)?;
+            v.push((name.as_str(), fv));
+            sz += fsz;
+        }
+        Ok((XdrValueRef::Struct(v.into_bump_slice()), sz))
+    }
+
+    fn unpack_union_in<'a>(&'a self, discriminant: &'a Type, cases: &'a [(i32, Type)], default: Option<&'a Type>,
+                           input: &mut &'a [u8], bump: &'a Bump, depth: usize) -> Result<(XdrValueRef<'a>, usize)> {
+        let (disc, dsz) = self.unpack_nested_in(discriminant, input, bump, depth)?;
+        let d = match disc {
+            XdrValueRef::Int(d) => d,
+            XdrValueRef::UInt(d) => d as i32,
+            XdrValueRef::Bool(d) => d as i32,
+            XdrValueRef::Enum { value, .. } => value,
+            _ => bail!(ErrorKind::InvalidSchema("union discriminant must be int, unsigned int, bool or enum".to_string())),
+        };
+        let arm = match cases.iter().find(|c| c.0 == d) {
+            Some((_, t)) => t,
+            None => match default {
+                Some(t) => t,
+                None => return Err(Error::invalidcase(d)),
+            },
+        };
+        let (arm, asz) = self.unpack_nested_in(arm, input, bump, depth)?;
+        Ok((XdrValueRef::Union { discriminant: alloc(bump, disc), arm: alloc(bump, arm) }, dsz + asz))
+    }
+
+    fn unpack_elems_in<'a>(&'a self, elem: &'a Type, n: usize, input: &mut &'a [u8], bump: &'a Bump,
+                           depth: usize) -> Result<(&'a [XdrValueRef<'a>], usize)> {
+        let mut sz = 0;
+        let mut v = BumpVec::with_capacity_in(prealloc::<XdrValueRef>(n), bump);
+
+        for _ in 0..n {
+            let (e, esz) = self.unpack_nested_in(elem, input, bump, depth)?;
+            v.push(e);
+            sz += esz;
+        }
+
+        Ok((v.into_bump_slice(), sz))
+    }
+}
+
+// Unpack a value of a type which doesn't contain others
+fn unpack_scalar_in<'a>(ty: &'a Type, input: &mut &'a [u8], bump: &'a Bump) -> Result<(XdrValueRef<'a>, usize)> {
+    let ret = match *ty {
+        Type::Void => (XdrValueRef::Void, 0),
+        Type::Int => map(i32::unpack(input)?, XdrValueRef::Int),
+        Type::UInt => map(u32::unpack(input)?, XdrValueRef::UInt),
+        Type::Hyper => map(i64::unpack(input)?, XdrValueRef::Hyper),
+        Type::UHyper => map(u64::unpack(input)?, XdrValueRef::UHyper),
+        Type::Float => map(f32::unpack(input)?, XdrValueRef::Float),
+        Type::Double => map(f64::unpack(input)?, XdrValueRef::Double),
+        Type::Quadruple => map(XdrQuadruple::unpack(input)?, XdrValueRef::Quadruple),
+        Type::Bool => map(bool::unpack(input)?, XdrValueRef::Bool),
+        Type::Enum(ref variants) => {
+            let (value, sz) = i32::unpack(input)?;
+            match variants.iter().find(|v| v.1 == value) {
+                Some((name, _)) => (XdrValueRef::Enum { name, value }, sz),
+                None => return Err(Error::invalidenum(value)),
+            }
+        }
+        Type::FixedOpaque(n) => map(opaque_array_borrowed(input, n)?, XdrValueRef::FixedOpaque),
+        Type::Opaque(max) => map(unpack_opaque_borrowed(input, max)?, XdrValueRef::Opaque),
+        Type::String(max) => {
+            let (v, sz) = unpack_opaque_borrowed(input, max)?;
+            (XdrValueRef::String(utf8_in(v, bump)?), sz)
+        }
+        _ => unreachable!("not a scalar type: {:?}", ty),
+    };
+
+    Ok(ret)
+}
+
+// `Bump::alloc` is always inlined, and takes a lot of stack in unoptimized builds, so keep it out of
+// the recursive functions.
+#[inline(never)]
+fn alloc<'a>(bump: &'a Bump, v: XdrValueRef<'a>) -> &'a XdrValueRef<'a> {
+    bump.alloc(v)
+}
+
+#[inline]
+fn map<'a, T>((v, sz): (T, usize), f: fn(T) -> XdrValueRef<'a>) -> (XdrValueRef<'a>, usize) {
+    (f(v), sz)
+}
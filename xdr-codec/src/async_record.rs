@@ -0,0 +1,214 @@
+//! Async record marking
+//!
+//! These are equivalents of `XdrRecordReader` and `XdrRecordWriter` for asynchronous streams,
+//! using the `AsyncRead` and `AsyncWrite` traits from `futures::io`, so they work with any
+//! runtime which provides those (or has an adapter for them). The reader is a `Stream` of
+//! complete records, and the writer is a `Sink` of records.
+//!
+//! This module is only available with the `futures` feature.
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use futures_io::{AsyncRead, AsyncWrite};
+use futures_sink::Sink;
+
+use decoder::RecordDecoder;
+use record::LAST_REC;
+
+const RDBUF: usize = 8192;
+const WRBUF: usize = 65536;
+
+/// Read records from an asynchronous bytestream.
+///
+/// This is a `Stream` of complete records, each reassembled from its fragments. The stream ends
+/// at EOF; an incomplete record at EOF is discarded. It returns an error if the underlying
+/// reader fails or if a fragment or record exceeds the configured limits (as an `InvalidData`
+/// IO error wrapping an `ErrorKind::FragmentTooLarge` or `ErrorKind::RecordTooLarge`), and then
+/// ends.
+#[derive(Debug)]
+pub struct AsyncXdrRecordReader<R> {
+    decoder: RecordDecoder,
+    ready: VecDeque<Vec<u8>>,   // decoded records not yet returned
+    buf: Box<[u8]>,             // read buffer
+    done: bool,                 // EOF or error
+    reader: R,
+}
+
+impl<R: AsyncRead + Unpin> AsyncXdrRecordReader<R> {
+    /// Wrap a record reader around an `AsyncRead`.
+    pub fn new(reader: R) -> AsyncXdrRecordReader<R> {
+        AsyncXdrRecordReader::with_limits(reader, None, None)
+    }
+
+    /// Wrap a record reader around an `AsyncRead`, with limits on fragment and record sizes.
+    pub fn with_limits(reader: R, max_fragment: Option<usize>, max_record: Option<usize>) -> AsyncXdrRecordReader<R> {
+        AsyncXdrRecordReader {
+            decoder: RecordDecoder::with_limits(max_fragment, max_record),
+            ready: VecDeque::new(),
+            buf: vec![0; RDBUF].into_boxed_slice(),
+            done: false,
+            reader,
+        }
+    }
+
+    /// Poll for the next complete record. This is the `Stream` implementation, available
+    /// without pinning.
+    pub fn poll_next_record(&mut self, cx: &mut Context) -> Poll<Option<io::Result<Vec<u8>>>> {
+        loop {
+            if let Some(rec) = self.ready.pop_front() {
+                return Poll::Ready(Some(Ok(rec)));
+            }
+            if self.done {
+                return Poll::Ready(None);
+            }
+
+            // Report any error left over from the previous read
+            if let Err(e) = self.decoder.feed(&[]) {
+                self.done = true;
+                return Poll::Ready(Some(Err(io::Error::new(io::ErrorKind::InvalidData, e))));
+            }
+
+            let sz = match Pin::new(&mut self.reader).poll_read(cx, &mut self.buf) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(0)) => {
+                    self.done = true;
+                    continue;
+                },
+                Poll::Ready(Ok(sz)) => sz,
+                Poll::Ready(Err(ref e)) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Poll::Ready(Err(e)) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                },
+            };
+
+            match self.decoder.feed(&self.buf[..sz]) {
+                Ok(recs) => self.ready.extend(recs),
+                Err(e) => {
+                    self.done = true;
+                    return Poll::Ready(Some(Err(io::Error::new(io::ErrorKind::InvalidData, e))));
+                },
+            }
+        }
+    }
+
+    /// Return the underlying reader. Any data read from it which isn't yet part of a returned
+    /// record is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncXdrRecordReader<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_record(cx)
+    }
+}
+
+/// Write records into an asynchronous bytestream.
+///
+/// This is a `Sink` of records. Each record is split into fragments of at most the fragment
+/// size and queued in an internal buffer along with the fragment headers; the buffer is written
+/// out when the sink is flushed, or when it's full and another record is sent. Closing the sink
+/// flushes and then closes the underlying writer.
+#[derive(Debug)]
+pub struct AsyncXdrRecordWriter<W> {
+    fragsz: usize,              // max fragment size
+    out: Vec<u8>,               // encoded fragments to write
+    pos: usize,                 // amount of `out` written
+    writer: W,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncXdrRecordWriter<W> {
+    /// Wrap a record writer around an `AsyncWrite`, using the default fragment size (64k).
+    pub fn new(writer: W) -> AsyncXdrRecordWriter<W> {
+        AsyncXdrRecordWriter::with_fragment_size(writer, WRBUF)
+    }
+
+    /// Wrap a record writer around an `AsyncWrite`, with a specific maximum fragment size.
+    /// Panics if the size is zero or larger than 2^31-1.
+    pub fn with_fragment_size(writer: W, fragsz: usize) -> AsyncXdrRecordWriter<W> {
+        if fragsz == 0 { panic!("fragsz must be non-zero") }
+        if fragsz > (LAST_REC - 1) as usize { panic!("fragsz must be less than 2^31") }
+        AsyncXdrRecordWriter { fragsz, out: Vec::new(), pos: 0, writer }
+    }
+
+    /// Queue a complete record to be written. No IO is done until the writer is flushed.
+    pub fn queue_record(&mut self, record: &[u8]) {
+        let mut frags = record.chunks(self.fragsz).peekable();
+
+        if frags.peek().is_none() {
+            self.out.extend_from_slice(&LAST_REC.to_be_bytes());
+        }
+        while let Some(frag) = frags.next() {
+            let eor = if frags.peek().is_none() { LAST_REC } else { 0 };
+            self.out.extend_from_slice(&(frag.len() as u32 | eor).to_be_bytes());
+            self.out.extend_from_slice(frag);
+        }
+    }
+
+    // Write out queued data
+    fn poll_write_out(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        while self.pos < self.out.len() {
+            match Pin::new(&mut self.writer).poll_write(cx, &self.out[self.pos..]) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Ok(0)) =>
+                    return Poll::Ready(Err(io::Error::new(io::ErrorKind::WriteZero, "failed to write record"))),
+                Poll::Ready(Ok(sz)) => self.pos += sz,
+                Poll::Ready(Err(ref e)) if e.kind() == io::ErrorKind::Interrupted => (),
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+            }
+        }
+        self.out.clear();
+        self.pos = 0;
+        Poll::Ready(Ok(()))
+    }
+
+    /// Write out all queued records and flush the underlying writer.
+    pub fn poll_flush_records(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        match self.poll_write_out(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut self.writer).poll_flush(cx),
+            res => res,
+        }
+    }
+
+    /// Return the underlying writer. Any queued records which haven't been flushed are lost.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: AsyncWrite + Unpin> Sink<Vec<u8>> for AsyncXdrRecordWriter<W> {
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.out.len() >= this.fragsz {
+            this.poll_write_out(cx)
+        } else {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Vec<u8>) -> io::Result<()> {
+        self.get_mut().queue_record(&item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.get_mut().poll_flush_records(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        match this.poll_write_out(cx) {
+            Poll::Ready(Ok(())) => Pin::new(&mut this.writer).poll_close(cx),
+            res => res,
+        }
+    }
+}
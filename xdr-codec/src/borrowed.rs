@@ -0,0 +1,199 @@
+//! Zero-copy unpacking from byte slices
+//!
+//! `UnpackRef` is a variant of `Unpack` for decoding from a byte slice which is already in memory
+//! (for example, a memory-mapped file). Opaque data and strings are returned as references into
+//! the input rather than being copied, so scanning large inputs doesn't allocate for every
+//! variable-length field. Fixed-size types are decoded as normal, and `String` is also
+//! implemented (as a copy) for structures which want an owned string.
+//!
+//! The input is a `&mut &'a [u8]`, which is advanced past each value as it's unpacked, in the
+//! same way as `unpack_opaque_borrowed`.
+use std::borrow::Cow;
+use std::cmp::min;
+use std::io;
+use std::mem;
+
+use error::*;
+
+use super::{Unpack, Opaque, unpack_opaque_borrowed};
+use options;
+
+/// Unpack a value which may borrow from the input buffer.
+pub trait UnpackRef<'a>: Sized {
+    /// Unpack a value from the front of `input`, advancing it. Returns the value and the number
+    /// of bytes consumed.
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)>;
+}
+
+/// Unpack a value from a byte slice, borrowing from it where possible.
+#[inline]
+pub fn unpack_ref<'a, T: UnpackRef<'a>>(input: &mut &'a [u8]) -> Result<T> {
+    T::unpack_ref(input).map(|(v, _)| v)
+}
+
+/// Unpack a (perhaps) length-limited string, borrowing it from the input.
+///
+/// The string is checked for valid UTF-8. This is always strict; unpack a `Cow<str>` to have
+/// `DecodeOptions::lossy_strings` respected.
+pub fn unpack_str_borrowed<'a>(input: &mut &'a [u8], maxsz: Option<usize>) -> Result<(&'a str, usize)> {
+    let (v, sz) = unpack_opaque_borrowed(input, maxsz)?;
+
+    match ::std::str::from_utf8(v) {
+        Ok(s) => Ok((s, sz)),
+        // Report this with the same error as `unpack_string`; only costs a copy on failure
+        Err(_) => Err(String::from_utf8(v.to_vec()).unwrap_err().into()),
+    }
+}
+
+#[cfg(feature = "bytecodec")]
+impl<'a> UnpackRef<'a> for u8 {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+#[cfg(feature = "bytecodec")]
+impl<'a> UnpackRef<'a> for i8 {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+impl<'a> UnpackRef<'a> for u32 {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+impl<'a> UnpackRef<'a> for i32 {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+impl<'a> UnpackRef<'a> for u64 {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+impl<'a> UnpackRef<'a> for i64 {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+#[cfg(feature = "hyper128")]
+impl<'a> UnpackRef<'a> for u128 {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+#[cfg(feature = "hyper128")]
+impl<'a> UnpackRef<'a> for i128 {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+impl<'a> UnpackRef<'a> for f32 {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+impl<'a> UnpackRef<'a> for f64 {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+impl<'a> UnpackRef<'a> for bool {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+impl<'a> UnpackRef<'a> for () {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+impl<'a> UnpackRef<'a> for usize {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+impl<'a> UnpackRef<'a> for String {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> { Self::unpack(input) }
+}
+
+impl<'a> UnpackRef<'a> for &'a [u8] {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> {
+        unpack_opaque_borrowed(input, None)
+    }
+}
+
+impl<'a> UnpackRef<'a> for &'a str {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> {
+        unpack_str_borrowed(input, None)
+    }
+}
+
+impl<'a> UnpackRef<'a> for Opaque<'a> {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> {
+        unpack_opaque_borrowed(input, None).map(|(v, sz)| (Opaque::borrowed(v), sz))
+    }
+}
+
+impl<'a> UnpackRef<'a> for Cow<'a, [u8]> {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> {
+        unpack_opaque_borrowed(input, None).map(|(v, sz)| (Cow::Borrowed(v), sz))
+    }
+}
+
+impl<'a> UnpackRef<'a> for Cow<'a, str> {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> {
+        let (v, sz) = unpack_opaque_borrowed(input, None)?;
+
+        match ::std::str::from_utf8(v) {
+            Ok(s) => Ok((Cow::Borrowed(s), sz)),
+            Err(_) => options::utf8_string(v.to_vec()).map(|s| (Cow::Owned(s), sz)),
+        }
+    }
+}
+
+impl<'a, T: UnpackRef<'a>> UnpackRef<'a> for Option<T> {
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> {
+        let (have, mut sz) = bool::unpack(input)?;
+        let ret = if have {
+            let (v, osz) = T::unpack_ref(input)?;
+            sz += osz;
+            Some(v)
+        } else {
+            None
+        };
+        Ok((ret, sz))
+    }
+}
+
+impl<'a, T: UnpackRef<'a>> UnpackRef<'a> for Vec<T> {
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> {
+        let (elems, mut sz) = usize::unpack(input)?;
+
+        // Each element takes at least 4 bytes, so don't trust a count which couldn't fit
+        if mem::size_of::<T>() != 0 && elems > input.len() / 4 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "length exceeds input").into());
+        }
+        let mut out = Vec::with_capacity(min(elems, input.len() / 4));
+        for _ in 0..elems {
+            let (v, esz) = T::unpack_ref(input)?;
+            out.push(v);
+            sz += esz;
+        }
+        Ok((out, sz))
+    }
+}
+
+impl<'a, T: UnpackRef<'a>> UnpackRef<'a> for Box<T> {
+    #[inline]
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> {
+        T::unpack_ref(input).map(|(v, sz)| (Box::new(v), sz))
+    }
+}
@@ -0,0 +1,232 @@
+//! Length-bounded strings and arrays
+//!
+//! XDR `string<N>` and `T<N>` declare a maximum length. With plain `String` and `Vec<T>` the
+//! limit has to be passed to `pack_string`/`unpack_flex` and friends at every use. These
+//! wrappers carry the limit in their type instead: they can't be constructed with more than
+//! `MAX` elements, and unpacking one fails with `ErrorKind::InvalidLen` if the encoded length
+//! exceeds it.
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{Read, Write};
+use std::ops::{Deref, DerefMut};
+
+use error::*;
+use skip::{Skip, skip_flex, skip_string};
+use super::{Pack, Unpack, unpack_flex, unpack_string};
+
+/// A string of at most `MAX` bytes, encoded as `string<MAX>`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct BoundedString<const MAX: usize>(String);
+
+impl<const MAX: usize> BoundedString<MAX> {
+    /// Wrap a string, failing with `InvalidLen` if it's longer than `MAX` bytes.
+    pub fn new(s: String) -> Result<Self> {
+        if s.len() > MAX {
+            bail!(ErrorKind::InvalidLen(MAX));
+        }
+        Ok(BoundedString(s))
+    }
+
+    /// Return the string as a `&str`.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Return the underlying `String`.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl<const MAX: usize> Deref for BoundedString<MAX> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<const MAX: usize> AsRef<str> for BoundedString<MAX> {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<const MAX: usize> fmt::Display for BoundedString<MAX> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<const MAX: usize> TryFrom<String> for BoundedString<MAX> {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        BoundedString::new(s)
+    }
+}
+
+impl<'a, const MAX: usize> TryFrom<&'a str> for BoundedString<MAX> {
+    type Error = Error;
+
+    fn try_from(s: &'a str) -> Result<Self> {
+        BoundedString::new(s.to_string())
+    }
+}
+
+impl<const MAX: usize> From<BoundedString<MAX>> for String {
+    fn from(s: BoundedString<MAX>) -> String {
+        s.0
+    }
+}
+
+impl<Out: Write, const MAX: usize> Pack<Out> for BoundedString<MAX> {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.0.as_str().pack(out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        Pack::<Out>::packed_size_hint(self.0.as_str())
+    }
+}
+
+impl<In: Read, const MAX: usize> Unpack<In> for BoundedString<MAX> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_string(input, Some(MAX)).map(|(s, sz)| (BoundedString(s), sz))
+    }
+}
+
+impl<In: Read, const MAX: usize> Skip<In> for BoundedString<MAX> {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> {
+        skip_string(input, Some(MAX))
+    }
+}
+
+/// An array of at most `MAX` elements, encoded as `T<MAX>`.
+///
+/// The elements can be modified in place through `DerefMut`, but the length can only be changed
+/// with methods which check it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BoundedVec<T, const MAX: usize>(Vec<T>);
+
+impl<T, const MAX: usize> BoundedVec<T, MAX> {
+    /// Wrap a vector, failing with `InvalidLen` if it has more than `MAX` elements.
+    pub fn new(v: Vec<T>) -> Result<Self> {
+        if v.len() > MAX {
+            bail!(ErrorKind::InvalidLen(MAX));
+        }
+        Ok(BoundedVec(v))
+    }
+
+    /// Append an element, failing with `InvalidLen` if the array is already full.
+    pub fn push(&mut self, v: T) -> Result<()> {
+        if self.0.len() >= MAX {
+            bail!(ErrorKind::InvalidLen(MAX));
+        }
+        self.0.push(v);
+        Ok(())
+    }
+
+    /// Remove and return the last element, if any.
+    pub fn pop(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    /// Return the elements as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        &self.0
+    }
+
+    /// Return the underlying `Vec`.
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+
+impl<T, const MAX: usize> Default for BoundedVec<T, MAX> {
+    fn default() -> Self {
+        BoundedVec(Vec::new())
+    }
+}
+
+impl<T, const MAX: usize> Deref for BoundedVec<T, MAX> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T, const MAX: usize> DerefMut for BoundedVec<T, MAX> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.0
+    }
+}
+
+impl<T, const MAX: usize> AsRef<[T]> for BoundedVec<T, MAX> {
+    fn as_ref(&self) -> &[T] {
+        &self.0
+    }
+}
+
+impl<T, const MAX: usize> TryFrom<Vec<T>> for BoundedVec<T, MAX> {
+    type Error = Error;
+
+    fn try_from(v: Vec<T>) -> Result<Self> {
+        BoundedVec::new(v)
+    }
+}
+
+impl<T, const MAX: usize> From<BoundedVec<T, MAX>> for Vec<T> {
+    fn from(v: BoundedVec<T, MAX>) -> Vec<T> {
+        v.0
+    }
+}
+
+impl<'a, T, const MAX: usize> IntoIterator for &'a BoundedVec<T, MAX> {
+    type Item = &'a T;
+    type IntoIter = ::std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<T, const MAX: usize> IntoIterator for BoundedVec<T, MAX> {
+    type Item = T;
+    type IntoIter = ::std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<Out: Write, T: Pack<Out>, const MAX: usize> Pack<Out> for BoundedVec<T, MAX> {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.0[..].pack(out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        Pack::<Out>::packed_size_hint(&self.0[..])
+    }
+}
+
+impl<In: Read, T: Unpack<In>, const MAX: usize> Unpack<In> for BoundedVec<T, MAX> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_flex(input, Some(MAX)).map(|(v, sz)| (BoundedVec(v), sz))
+    }
+}
+
+impl<In: Read, T: Skip<In>, const MAX: usize> Skip<In> for BoundedVec<T, MAX> {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> {
+        skip_flex::<In, T>(input, Some(MAX))
+    }
+}
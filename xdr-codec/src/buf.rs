@@ -0,0 +1,153 @@
+//! Packing into `bytes::BufMut` and unpacking from `bytes::Buf`
+//!
+//! Any `Pack` or `Unpack` implementation can be used with `bytes` buffers through `pack_buf` and
+//! `unpack_buf`, which wrap the buffer in its `std::io` adapter without copying. Opaque data and
+//! strings have direct implementations (`pack_opaque_buf`, `unpack_opaque_buf` and friends)
+//! which move the payload with a single slice copy rather than going through `Read`/`Write`.
+//!
+//! `Bytes` packs as opaque data. `unpack_opaque_bytes` returns opaque data as `Bytes`; when
+//! decoding from a `Bytes` buffer the result shares its storage rather than being copied.
+//!
+//! This module is only available with the `bytes` feature.
+use std::io::{self, Read, Write};
+
+use bytes::{Buf, BufMut, Bytes};
+use bytes::buf::{Reader, Writer};
+
+use error::*;
+use super::{Pack, Unpack, Opaque, padding, unpack_opaque_flex};
+use options;
+use endian;
+
+/// Pack a value into a `BufMut`.
+///
+/// If the buffer can't grow to hold the encoding this returns a `WriteZero` IO error; the buffer
+/// may have been partially written.
+#[inline]
+pub fn pack_buf<B, T>(val: &T, buf: &mut B) -> Result<usize>
+    where B: BufMut, T: ?Sized + for<'a> Pack<Writer<&'a mut B>>
+{
+    val.pack(&mut buf.writer())
+}
+
+/// Unpack a value from a `Buf`, advancing it past the encoding.
+///
+/// Running out of data results in an `UnexpectedEof` IO error.
+#[inline]
+pub fn unpack_buf<B, T>(buf: &mut B) -> Result<(T, usize)>
+    where B: Buf, T: for<'a> Unpack<Reader<&'a mut B>>
+{
+    T::unpack(&mut buf.reader())
+}
+
+fn opaque_len(len: usize, maxsz: Option<usize>) -> Result<u32> {
+    if let Some(m) = maxsz {
+        if len > m {
+            bail!(ErrorKind::InvalidLen(m));
+        }
+    }
+    super::xdr_len(len)
+}
+
+/// Pack a (perhaps) length-limited opaque array into a `BufMut`.
+///
+/// This is equivalent to `pack_opaque_flex`, but checks for space up front and copies the data
+/// with a single `put_slice`.
+pub fn pack_opaque_buf<B: BufMut>(val: &[u8], maxsz: Option<usize>, buf: &mut B) -> Result<usize> {
+    let len = opaque_len(val.len(), maxsz)?;
+    let p = padding(val.len());
+    let sz = 4 + val.len() + p.len();
+
+    if buf.remaining_mut() < sz {
+        return Err(io::Error::new(io::ErrorKind::WriteZero, "buffer full").into());
+    }
+
+    let mut hdr = [0; 4];
+    endian::write_u32(&mut hdr, len);
+    buf.put_slice(&hdr);
+    buf.put_slice(val);
+    buf.put_slice(p);
+
+    Ok(sz)
+}
+
+/// Pack a (perhaps) length-limited string into a `BufMut`.
+#[inline]
+pub fn pack_string_buf<B: BufMut>(val: &str, maxsz: Option<usize>, buf: &mut B) -> Result<usize> {
+    pack_opaque_buf(val.as_bytes(), maxsz, buf)
+}
+
+/// Check there's a whole (perhaps) length-limited opaque array at the front of `buf`, and return
+/// its length, padded length and the size of the header. The header is consumed.
+fn opaque_header<B: Buf>(buf: &mut B, maxsz: Option<usize>) -> Result<(usize, usize, usize)> {
+    let (elems, sz) = unpack_buf::<_, usize>(buf)?;
+
+    if let Some(m) = maxsz {
+        if elems > m {
+            bail!(ErrorKind::InvalidLen(m));
+        }
+    }
+
+    let padded = match elems.checked_add(padding(elems).len()) {
+        Some(p) => p,
+        None => bail!(ErrorKind::InvalidLen(elems)),
+    };
+    if buf.remaining() < padded {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short opaque data").into());
+    }
+
+    Ok((elems, padded, sz))
+}
+
+/// Unpack a (perhaps) length-limited opaque array from a `Buf`.
+///
+/// This is equivalent to `unpack_opaque_flex`, but checks the whole payload is present before
+/// allocating, and copies it out with `copy_to_slice`.
+pub fn unpack_opaque_buf<B: Buf>(buf: &mut B, maxsz: Option<usize>) -> Result<(Vec<u8>, usize)> {
+    let (elems, padded, sz) = opaque_header(buf, maxsz)?;
+
+    let mut out = vec![0; elems];
+    buf.copy_to_slice(&mut out);
+    buf.advance(padded - elems);
+
+    Ok((out, sz + padded))
+}
+
+/// Unpack a (perhaps) length-limited string from a `Buf`.
+#[inline]
+pub fn unpack_string_buf<B: Buf>(buf: &mut B, maxsz: Option<usize>) -> Result<(String, usize)> {
+    let (v, sz) = unpack_opaque_buf(buf, maxsz)?;
+
+    options::utf8_string(v).map(|s| (s, sz))
+}
+
+/// Unpack a (perhaps) length-limited opaque array from a `Buf` as `Bytes`.
+///
+/// This uses `Buf::copy_to_bytes`, so if `buf` is a `Bytes` (or `BytesMut`) the result is a
+/// reference-counted slice of the same storage and no data is copied.
+pub fn unpack_opaque_bytes<B: Buf>(buf: &mut B, maxsz: Option<usize>) -> Result<(Bytes, usize)> {
+    let (elems, padded, sz) = opaque_header(buf, maxsz)?;
+
+    let out = buf.copy_to_bytes(elems);
+    buf.advance(padded - elems);
+
+    Ok((out, sz + padded))
+}
+
+/// `Bytes` is packed as opaque data.
+impl<Out: Write> Pack<Out> for Bytes {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Opaque::borrowed(self).pack(out)
+    }
+}
+
+/// `Bytes` is unpacked from opaque data. Reading from a general `Read` has to copy; use
+/// `unpack_opaque_bytes` to share the input buffer.
+impl<In: Read> Unpack<In> for Bytes {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz) = unpack_opaque_flex(input, None)?;
+        Ok((Bytes::from(v), sz))
+    }
+}
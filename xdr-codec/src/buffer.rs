@@ -0,0 +1,84 @@
+//! In-memory encoding
+//!
+//! Everything needed to encode and decode XDR can be done with in-memory buffers, without any
+//! operating system IO. This matters on targets like `wasm32-unknown-unknown`, where a
+//! browser-based inspector is handed messages as byte arrays. `Pack` and `Unpack` are defined in
+//! terms of `std::io::Write` and `Read`, but the implementations for `Vec<u8>` and `&[u8]` used
+//! here only copy memory:
+//!
+//!  * `pack_to_vec` and `pack_to_slice` encode a value, into a new `Vec` or an existing buffer
+//!  * `unpack_from_bytes`, `unpack_complete` and `borrowed::unpack_ref` decode from a slice
+//!  * `frame_record` and `split_records` add and remove record marking (see the `record`
+//!    module) on whole buffers; `decoder::RecordDecoder` does the same for data arriving in
+//!    pieces
+//!  * `value::Schema::unpack` decodes a dynamically typed `Value` from a slice
+//!
+//! ```
+//! use xdr_codec::{pack_to_vec, unpack_complete};
+//! use xdr_codec::buffer::{frame_record, pack_to_slice, split_records};
+//!
+//! let mut buf = [0; 12];
+//! assert_eq!(pack_to_slice(&[1u32, 2][..], &mut buf).unwrap(), 12);
+//!
+//! let stream = [frame_record(&buf, None), frame_record(&pack_to_vec(&3u32).unwrap(), None)].concat();
+//! let recs = split_records(&stream).unwrap();
+//! assert_eq!(unpack_complete::<Vec<u32>>(&recs[0]).unwrap(), [1, 2]);
+//! assert_eq!(unpack_complete::<u32>(&recs[1]).unwrap(), 3);
+//! ```
+//!
+//! Avoid `record::XdrRecordReader` and `stream::XdrReader` over anything other than a slice on
+//! these targets. The `ndmp` module's message timestamps are 0 on `wasm32-unknown-unknown`,
+//! which has no clock.
+use std::io::{self, Cursor, Write};
+
+use decoder::RecordDecoder;
+use error::*;
+use record::XdrRecordWriter;
+use super::Pack;
+
+/// Pack `val` into the start of `buf`, returning the size of its encoding.
+///
+/// Fails with `CapacityExceeded` if the value's size hint shows it won't fit, or with a
+/// `WriteZero` IO error if it turns out not to fit part way through; in that case the contents
+/// of `buf` are unspecified.
+pub fn pack_to_slice<T>(val: &T, buf: &mut [u8]) -> Result<usize>
+    where T: ?Sized + for<'a> Pack<Cursor<&'a mut [u8]>>
+{
+    if let Some(sz) = Pack::<Cursor<&mut [u8]>>::packed_size_hint(val) {
+        if sz > buf.len() {
+            bail!(ErrorKind::CapacityExceeded(sz, buf.len()));
+        }
+    }
+    val.pack(&mut Cursor::new(buf))
+}
+
+/// Record-mark `data` as a single record, in fragments of at most `fragsz` bytes (by default,
+/// the same as `XdrRecordWriter::new`).
+///
+/// Panics if `fragsz` is zero or 2^31 or more.
+pub fn frame_record(data: &[u8], fragsz: Option<usize>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + 4);
+    {
+        let mut w = match fragsz {
+            Some(sz) => XdrRecordWriter::with_fragment_size(&mut out, sz),
+            None => XdrRecordWriter::new(&mut out),
+        };
+        // Writing to a `Vec` can't fail
+        let _ = w.write_all(data).and_then(|_| w.finish());
+    }
+    out
+}
+
+/// Split a buffer of record-marked data into its records.
+///
+/// The buffer must end at a record boundary; if it doesn't, this fails with an `UnexpectedEof`
+/// IO error.
+pub fn split_records(data: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut dec = RecordDecoder::new();
+    let recs = dec.feed(data)?;
+
+    if !dec.is_idle() {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "incomplete record").into());
+    }
+    Ok(recs)
+}
@@ -0,0 +1,151 @@
+//! Fixed-capacity collections
+//!
+//! On embedded targets it's often preferable to decode bounded arrays and strings into storage
+//! of a fixed size rather than onto the heap. This module implements `Pack`, `Unpack` and `Skip`
+//! for `heapless::Vec<T, N>` and `heapless::String<N>`, which encode as `T<N>` and `string<N>`.
+//! Unpacking an array or string with more than `N` elements fails with
+//! `ErrorKind::CapacityExceeded`, before anything is read into the container:
+//!
+//! ```
+//! use xdr_codec::{Error, ErrorKind, unpack};
+//! use xdr_codec::capped::{String, Vec};
+//!
+//! let v: Vec<u32, 2> = unpack(&mut &[0, 0, 0, 2,  0, 0, 0, 1,  0, 0, 0, 2][..]).unwrap();
+//! assert_eq!(v, [1, 2]);
+//!
+//! match unpack::<_, String<2>>(&mut &[0, 0, 0, 3,  b'a', b'b', b'c', 0][..]) {
+//!     Err(Error(ErrorKind::CapacityExceeded(3, 2), _)) => (),
+//!     res => panic!("bad result {:?}", res),
+//! }
+//! ```
+//!
+//! The functions here take an additional `maxsz` limit, which fails with `InvalidLen` as usual,
+//! for fields whose XDR bound is smaller than the capacity. `unpack_opaque_flex_capped` unpacks
+//! opaque data into a `Vec<u8, N>`.
+//!
+//! This module is only available with the `heapless` feature.
+use std::borrow::Cow;
+use std::io::{self, Read, Write};
+use std::str;
+
+pub use heapless::{String, Vec};
+
+use error::*;
+use options::decode_options;
+use skip::{Skip, skip_flex, skip_string};
+use super::{Pack, Unpack, unpack_padding};
+
+fn capped_len<In: Read>(input: &mut In, maxsz: Option<usize>, cap: usize) -> Result<(usize, usize)> {
+    let (elems, sz) = usize::unpack(input)?;
+
+    if let Some(m) = maxsz {
+        if elems > m {
+            bail!(ErrorKind::InvalidLen(m));
+        }
+    }
+    if elems > cap {
+        bail!(ErrorKind::CapacityExceeded(elems, cap));
+    }
+    Ok((elems, sz))
+}
+
+/// Unpack a (perhaps) length-limited array into a `heapless::Vec`.
+pub fn unpack_flex_capped<In, T, const N: usize>(input: &mut In, maxsz: Option<usize>) -> Result<(Vec<T, N>, usize)>
+    where In: Read, T: Unpack<In>
+{
+    let (elems, mut sz) = capped_len(input, maxsz, N)?;
+    let mut out = Vec::new();
+
+    for _ in 0..elems {
+        let (e, esz) = T::unpack(input)?;
+        // Can't fail, as the length has been checked
+        let _ = out.push(e);
+        sz += esz;
+    }
+    sz += unpack_padding(input, sz)?;
+
+    Ok((out, sz))
+}
+
+/// Unpack a (perhaps) length-limited opaque array into a `heapless::Vec`.
+pub fn unpack_opaque_flex_capped<In: Read, const N: usize>(input: &mut In, maxsz: Option<usize>)
+                                                           -> Result<(Vec<u8, N>, usize)> {
+    let (elems, mut sz) = capped_len(input, maxsz, N)?;
+    let mut out = Vec::new();
+
+    // As above, this fits
+    let _ = out.resize_default(elems);
+    if let Err(e) = input.read_exact(&mut out) {
+        return Err(match e.kind() {
+            io::ErrorKind::UnexpectedEof => io::Error::new(io::ErrorKind::UnexpectedEof, "short opaque data"),
+            _ => e,
+        }.into());
+    }
+    sz += elems;
+    sz += unpack_padding(input, sz)?;
+
+    Ok((out, sz))
+}
+
+/// Unpack a (perhaps) length-limited string into a `heapless::String`.
+///
+/// This honours `DecodeOptions::lossy_strings`; if replacing invalid sequences makes the string
+/// too long, this fails with `CapacityExceeded`.
+pub fn unpack_string_capped<In: Read, const N: usize>(input: &mut In, maxsz: Option<usize>)
+                                                      -> Result<(String<N>, usize)> {
+    let (v, sz) = unpack_opaque_flex_capped::<_, N>(input, maxsz)?;
+
+    let text = match str::from_utf8(&v) {
+        Ok(s) => Cow::Borrowed(s),
+        Err(_) if decode_options().is_lossy_strings() => ::std::string::String::from_utf8_lossy(&v),
+        Err(_) => return Err(::std::string::String::from_utf8(v.to_vec()).unwrap_err().into()),
+    };
+
+    let mut s = String::new();
+    if s.push_str(&text).is_err() {
+        bail!(ErrorKind::CapacityExceeded(text.len(), N));
+    }
+    Ok((s, sz))
+}
+
+impl<Out: Write, T: Pack<Out>, const N: usize> Pack<Out> for Vec<T, N> {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self[..].pack(out)
+    }
+}
+
+impl<In: Read, T: Unpack<In>, const N: usize> Unpack<In> for Vec<T, N> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_flex_capped(input, None)
+    }
+}
+
+impl<In: Read, T: Skip<In>, const N: usize> Skip<In> for Vec<T, N> {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> {
+        skip_flex::<In, T>(input, Some(N))
+    }
+}
+
+impl<Out: Write, const N: usize> Pack<Out> for String<N> {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.as_str().pack(out)
+    }
+}
+
+impl<In: Read, const N: usize> Unpack<In> for String<N> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_string_capped(input, None)
+    }
+}
+
+impl<In: Read, const N: usize> Skip<In> for String<N> {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> {
+        skip_string(input, Some(N))
+    }
+}
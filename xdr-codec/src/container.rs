@@ -0,0 +1,216 @@
+//! Indexed container files
+//!
+//! A container is a file of XDR records with an index at the end, so that any record can be read
+//! with a couple of seeks instead of scanning everything before it. It's meant for large
+//! archives of captured or exported messages.
+//!
+//! `ContainerWriter` appends records, keeping the index in memory until `finish` writes it out.
+//! `ContainerReader` reads the trailer when it's opened, then looks up each record's index
+//! entry as it's needed, so opening an archive costs the same however many records it holds.
+//!
+//! ```
+//! use std::io::Cursor;
+//! use xdr_codec::container::{ContainerReader, ContainerWriter};
+//!
+//! let mut w = ContainerWriter::new(Vec::new()).unwrap();
+//! for i in 0..100u32 {
+//!     w.pack(&i).unwrap();
+//! }
+//! let file = w.finish().unwrap();
+//!
+//! let mut r = ContainerReader::open(Cursor::new(file)).unwrap();
+//! assert_eq!(r.len(), 100);
+//! assert_eq!(r.unpack::<u32>(42).unwrap(), 42);
+//! ```
+//!
+//! The file starts with the magic number `XDRC` and a format version (`unsigned int`, currently
+//! 1). Then come the records, each padded to a multiple of 4 bytes. The index follows, with an
+//! entry for each record giving its offset from the start of the file and its length (both
+//! `unsigned hyper`). The file ends with a 20 byte trailer: the offset of the index and the
+//! number of records (both `unsigned hyper`) and the magic number `XDRI`.
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use error::*;
+use super::{Pack, Unpack, padding};
+
+const MAGIC: &[u8; 4] = b"XDRC";
+const INDEX_MAGIC: &[u8; 4] = b"XDRI";
+const VERSION: u32 = 1;
+const HEADER_SIZE: u64 = 8;
+const TRAILER_SIZE: u64 = 20;
+const ENTRY_SIZE: u64 = 16;
+
+fn invalid<T>(msg: String) -> Result<T> {
+    Err(ErrorKind::InvalidValue(msg).into())
+}
+
+/// Writes a container file.
+#[derive(Debug)]
+pub struct ContainerWriter<W: Write> {
+    writer: W,
+    pos: u64,
+    index: Vec<(u64, u64)>,
+}
+
+impl<W: Write> ContainerWriter<W> {
+    /// Start a container, writing its header.
+    pub fn new(mut writer: W) -> Result<ContainerWriter<W>> {
+        writer.write_all(MAGIC)?;
+        let sz = VERSION.pack(&mut writer)?;
+        Ok(ContainerWriter { writer, pos: (MAGIC.len() + sz) as u64, index: Vec::new() })
+    }
+
+    /// Append a record, returning its index.
+    pub fn append(&mut self, data: &[u8]) -> Result<usize> {
+        self.writer.write_all(data)?;
+        let pad = padding(data.len());
+        self.writer.write_all(pad)?;
+        Ok(self.add_entry(data.len(), pad.len()))
+    }
+
+    /// Append the encoding of a value as a record, returning its index.
+    pub fn pack<T: ?Sized + Pack<W>>(&mut self, val: &T) -> Result<usize> {
+        let sz = val.pack(&mut self.writer)?;
+        // XDR values are always a multiple of 4 bytes, but `Pack` doesn't promise it
+        let pad = padding(sz);
+        self.writer.write_all(pad)?;
+        Ok(self.add_entry(sz, pad.len()))
+    }
+
+    fn add_entry(&mut self, len: usize, pad: usize) -> usize {
+        self.index.push((self.pos, len as u64));
+        self.pos += (len + pad) as u64;
+        self.index.len() - 1
+    }
+
+    /// The number of records written so far.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Whether no records have been written.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Write the index and trailer, returning the underlying writer.
+    ///
+    /// A container which isn't finished can't be read.
+    pub fn finish(mut self) -> Result<W> {
+        for &(off, len) in &self.index {
+            let _ = off.pack(&mut self.writer)?;
+            let _ = len.pack(&mut self.writer)?;
+        }
+        let _ = self.pos.pack(&mut self.writer)?;
+        let _ = (self.index.len() as u64).pack(&mut self.writer)?;
+        self.writer.write_all(INDEX_MAGIC)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads records from a container file in any order.
+#[derive(Debug)]
+pub struct ContainerReader<R: Read + Seek> {
+    reader: R,
+    index_off: u64,
+    count: u64,
+}
+
+impl<R: Read + Seek> ContainerReader<R> {
+    /// Open a container, checking its header and trailer.
+    ///
+    /// Fails with `InvalidValue` if it isn't a container, or wasn't finished.
+    pub fn open(mut reader: R) -> Result<ContainerReader<R>> {
+        let mut magic = [0; 4];
+        let _ = reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut magic)?;
+        if magic != *MAGIC {
+            return invalid("not an XDR container".to_string());
+        }
+        let (version, _) = u32::unpack(&mut reader)?;
+        if version != VERSION {
+            return invalid(format!("unsupported container version {}", version));
+        }
+
+        let end = reader.seek(SeekFrom::End(0))?;
+        if end < HEADER_SIZE + TRAILER_SIZE {
+            return invalid("container has no index".to_string());
+        }
+        let _ = reader.seek(SeekFrom::Start(end - TRAILER_SIZE))?;
+        let (index_off, _) = u64::unpack(&mut reader)?;
+        let (count, _) = u64::unpack(&mut reader)?;
+        reader.read_exact(&mut magic)?;
+
+        let index_len = count.checked_mul(ENTRY_SIZE).and_then(|l| l.checked_add(index_off));
+        if magic != *INDEX_MAGIC || index_off < HEADER_SIZE || index_len != Some(end - TRAILER_SIZE) {
+            return invalid("container has no index".to_string());
+        }
+
+        Ok(ContainerReader { reader, index_off, count })
+    }
+
+    /// The number of records.
+    pub fn len(&self) -> usize {
+        self.count as usize
+    }
+
+    /// Whether there are no records.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Return the offset and length of record `n`.
+    ///
+    /// Fails with `InvalidValue` if there's no such record, or its index entry is corrupt.
+    pub fn entry(&mut self, n: usize) -> Result<(u64, u64)> {
+        if n as u64 >= self.count {
+            return invalid(format!("record {} out of range ({} records)", n, self.count));
+        }
+        let _ = self.reader.seek(SeekFrom::Start(self.index_off + n as u64 * ENTRY_SIZE))?;
+        let (off, _) = u64::unpack(&mut self.reader)?;
+        let (len, _) = u64::unpack(&mut self.reader)?;
+
+        if off < HEADER_SIZE || off.checked_add(len).is_none_or(|e| e > self.index_off) {
+            return invalid(format!("record {} has a bad index entry", n));
+        }
+        Ok((off, len))
+    }
+
+    // Seek to record `n`, returning a reader for its contents
+    fn record(&mut self, n: usize) -> Result<(io::Take<&mut R>, u64)> {
+        let (off, len) = self.entry(n)?;
+        let _ = self.reader.seek(SeekFrom::Start(off))?;
+        Ok(((&mut self.reader).take(len), len))
+    }
+
+    /// Read record `n`.
+    pub fn read(&mut self, n: usize) -> Result<Vec<u8>> {
+        let (mut rec, len) = self.record(n)?;
+        let mut buf = Vec::with_capacity(len as usize);
+        let _ = rec.read_to_end(&mut buf)?;
+        if (buf.len() as u64) < len {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "truncated record").into());
+        }
+        Ok(buf)
+    }
+
+    /// Unpack record `n` as a value of type `T`, which must use the whole record.
+    ///
+    /// Fails with `TrailingData` if the value is shorter than the record.
+    pub fn unpack<T>(&mut self, n: usize) -> Result<T>
+        where T: for<'a> Unpack<io::Take<&'a mut R>>
+    {
+        let (mut rec, len) = self.record(n)?;
+        let (val, sz) = T::unpack(&mut rec)?;
+        if (sz as u64) < len {
+            bail!(ErrorKind::TrailingData((len - sz as u64) as usize));
+        }
+        Ok(val)
+    }
+
+    /// Return the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
@@ -0,0 +1,162 @@
+//! Unpacking with context
+//!
+//! Some values can't be decoded from the input alone: a field might only be present in later
+//! versions of a protocol, or have a limit negotiated when the connection was set up. The
+//! `UnpackWith` trait is `Unpack` with an extra context argument, which is passed down through
+//! nested values by the `_with` helpers in this module, and by the implementations for `Vec`,
+//! `Option` and `Box`. The basic types implement it for any context by ignoring it, so they can
+//! be mixed in freely; a hand-written implementation can unpack any other context-free field with
+//! `Unpack` as usual:
+//!
+//! ```
+//! use xdr_codec::{Read, Result, Unpack, UnpackWith};
+//! use xdr_codec::context::{unpack_flex_with, unpack_with};
+//!
+//! struct Version(u32);
+//!
+//! #[derive(Debug, PartialEq)]
+//! struct Entry { id: u32, flags: u32 }
+//!
+//! impl<In: Read> UnpackWith<In, Version> for Entry {
+//!     fn unpack_with(input: &mut In, ctx: &Version) -> Result<(Self, usize)> {
+//!         let (id, mut sz) = u32::unpack(input)?;
+//!         // Version 2 added flags
+//!         let flags = if ctx.0 >= 2 {
+//!             let (v, fsz) = u32::unpack(input)?;
+//!             sz += fsz;
+//!             v
+//!         } else {
+//!             0
+//!         };
+//!         Ok((Entry { id, flags }, sz))
+//!     }
+//! }
+//!
+//! let enc = [0, 0, 0, 1,  0, 0, 0, 7,  0, 0, 0, 4];
+//! let (v, _) = unpack_flex_with::<_, _, Entry>(&mut &enc[..], Some(10), &Version(2)).unwrap();
+//! assert_eq!(v, [Entry { id: 7, flags: 4 }]);
+//! let v: Vec<Entry> = unpack_with(&mut &enc[..], &Version(1)).unwrap();
+//! assert_eq!(v, [Entry { id: 7, flags: 0 }]);
+//! let v: Vec<u32> = unpack_with(&mut &enc[..], &Version(1)).unwrap();
+//! assert_eq!(v, [7]);
+//! ```
+use std::io::Read;
+
+use error::*;
+use super::{Unpack, Opaque, check_elems, prealloc, unpack_padding};
+
+/// Unpack a value using a context.
+///
+/// The basic types implement this for any context, ignoring it. `Vec<T>`, `Option<T>` and
+/// `Box<T>` pass the context on to `T`.
+pub trait UnpackWith<In: Read, Ctx: ?Sized>: Sized {
+    /// Unpack a value from `input` using `ctx`, returning it and the number of bytes consumed.
+    fn unpack_with(input: &mut In, ctx: &Ctx) -> Result<(Self, usize)>;
+}
+
+macro_rules! ignore_ctx {
+    ($($(#[$attr:meta])* $ty:ty),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            impl<In: Read, Ctx: ?Sized> UnpackWith<In, Ctx> for $ty {
+                #[inline]
+                fn unpack_with(input: &mut In, _ctx: &Ctx) -> Result<(Self, usize)> {
+                    Self::unpack(input)
+                }
+            }
+        )*
+    }
+}
+
+ignore_ctx! {
+    #[cfg(feature = "bytecodec")] u8,
+    #[cfg(feature = "bytecodec")] i8,
+    u32, i32, u64, i64,
+    #[cfg(feature = "hyper128")] u128,
+    #[cfg(feature = "hyper128")] i128,
+    f32, f64, bool, usize, (), String, Opaque<'static>,
+}
+
+impl<In: Read, Ctx: ?Sized, T: UnpackWith<In, Ctx>> UnpackWith<In, Ctx> for Vec<T> {
+    #[inline]
+    fn unpack_with(input: &mut In, ctx: &Ctx) -> Result<(Self, usize)> {
+        unpack_flex_with(input, None, ctx)
+    }
+}
+
+impl<In: Read, Ctx: ?Sized, T: UnpackWith<In, Ctx>> UnpackWith<In, Ctx> for Option<T> {
+    #[inline]
+    fn unpack_with(input: &mut In, ctx: &Ctx) -> Result<(Self, usize)> {
+        unpack_option_with(input, ctx)
+    }
+}
+
+impl<In: Read, Ctx: ?Sized, T: UnpackWith<In, Ctx>> UnpackWith<In, Ctx> for Box<T> {
+    #[inline]
+    fn unpack_with(input: &mut In, ctx: &Ctx) -> Result<(Self, usize)> {
+        T::unpack_with(input, ctx).map(|(v, sz)| (Box::new(v), sz))
+    }
+}
+
+/// Unpack a value using a context. This is the counterpart of `unpack`.
+#[inline]
+pub fn unpack_with<In, Ctx, T>(input: &mut In, ctx: &Ctx) -> Result<T>
+    where In: Read, Ctx: ?Sized, T: UnpackWith<In, Ctx>
+{
+    T::unpack_with(input, ctx).map(|(v, _)| v)
+}
+
+/// Unpack a (perhaps) length-limited array, passing `ctx` to each element.
+pub fn unpack_flex_with<In, Ctx, T>(input: &mut In, maxsz: Option<usize>, ctx: &Ctx) -> Result<(Vec<T>, usize)>
+    where In: Read, Ctx: ?Sized, T: UnpackWith<In, Ctx>
+{
+    let (elems, mut sz) = usize::unpack(input)?;
+
+    if let Some(m) = maxsz {
+        if elems > m {
+            bail!(ErrorKind::InvalidLen(m));
+        }
+    }
+
+    check_elems::<T>(elems)?;
+    let mut out = Vec::with_capacity(prealloc::<T>(elems));
+    for _ in 0..elems {
+        let (e, esz) = T::unpack_with(input, ctx)?;
+        out.push(e);
+        sz += esz;
+    }
+    sz += unpack_padding(input, sz)?;
+
+    Ok((out, sz))
+}
+
+/// Unpack a fixed-size array of `n` elements, passing `ctx` to each one.
+pub fn unpack_array_with<In, Ctx, T>(input: &mut In, n: usize, ctx: &Ctx) -> Result<(Vec<T>, usize)>
+    where In: Read, Ctx: ?Sized, T: UnpackWith<In, Ctx>
+{
+    let mut out = Vec::with_capacity(prealloc::<T>(n));
+    let mut sz = 0;
+
+    for _ in 0..n {
+        let (e, esz) = T::unpack_with(input, ctx)?;
+        out.push(e);
+        sz += esz;
+    }
+    Ok((out, sz))
+}
+
+/// Unpack an optional value (`*T` in XDR), passing `ctx` to it if it's present.
+pub fn unpack_option_with<In, Ctx, T>(input: &mut In, ctx: &Ctx) -> Result<(Option<T>, usize)>
+    where In: Read, Ctx: ?Sized, T: UnpackWith<In, Ctx>
+{
+    let (have, mut sz) = bool::unpack(input)?;
+
+    let ret = if have {
+        let (v, osz) = T::unpack_with(input, ctx)?;
+        sz += osz;
+        Some(v)
+    } else {
+        None
+    };
+    Ok((ret, sz))
+}
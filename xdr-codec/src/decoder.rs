@@ -0,0 +1,217 @@
+//! Push-style (sans-IO) decoding
+//!
+//! The decoders in this module don't read from anything themselves. Instead, bytes are fed to
+//! them as they arrive - from an event loop, a proxy, or a custom runtime - and they return
+//! whatever complete items those bytes finished, keeping any incomplete remainder for the next
+//! call.
+//!
+//! `RecordDecoder` reassembles record-marked streams (see the `record` module) into complete
+//! records. `ValueDecoder` decodes a plain stream of XDR values of a given type.
+use std::cmp::min;
+use std::io;
+use std::marker::PhantomData;
+use std::mem;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use error::*;
+use record::LAST_REC;
+use options::with_input_limit;
+use super::{Unpack, MAX_PREALLOC, host_len};
+
+/// Incremental decoder for record-marked streams.
+///
+/// Fragments are accumulated until a fragment with the end of record marker arrives, and then
+/// the whole record is returned from `feed`. Optional limits on fragment and record size are
+/// checked as each fragment header arrives, before any of its data is buffered. If the same
+/// `feed` call completed records before the bad header, those are returned first and the error
+/// is returned by the next call. Once `feed` has returned an error the stream framing is lost,
+/// and it will keep returning the same error.
+#[derive(Debug, Default)]
+pub struct RecordDecoder {
+    hdr: [u8; 4],               // partial fragment header
+    hdrlen: usize,              // bytes of header so far
+    infrag: bool,               // header complete, reading fragment body
+    remains: usize,             // bytes remaining in fragment
+    eor: bool,                  // current fragment is last in record
+    record: Vec<u8>,            // record so far
+    max_fragment: Option<usize>,
+    max_record: Option<usize>,
+}
+
+impl RecordDecoder {
+    /// Create a decoder with no size limits.
+    pub fn new() -> RecordDecoder {
+        RecordDecoder::default()
+    }
+
+    /// Create a decoder which fails with `FragmentTooLarge` or `RecordTooLarge` if a fragment
+    /// or record exceeds the given sizes.
+    pub fn with_limits(max_fragment: Option<usize>, max_record: Option<usize>) -> RecordDecoder {
+        RecordDecoder { max_fragment, max_record, ..RecordDecoder::default() }
+    }
+
+    /// Feed bytes into the decoder, returning any records they complete.
+    pub fn feed(&mut self, mut data: &[u8]) -> Result<Vec<Vec<u8>>> {
+        let mut records = Vec::new();
+
+        loop {
+            if !self.infrag {
+                let sz = min(data.len(), self.hdr.len() - self.hdrlen);
+                self.hdr[self.hdrlen..self.hdrlen + sz].copy_from_slice(&data[..sz]);
+                self.hdrlen += sz;
+                data = &data[sz..];
+
+                if self.hdrlen < self.hdr.len() {
+                    break;
+                }
+                // Leaves the header in place on error, so the next call fails the same way
+                if let Err(e) = self.start_fragment() {
+                    if records.is_empty() {
+                        return Err(e);
+                    }
+                    break;
+                }
+            }
+
+            let sz = min(data.len(), self.remains);
+            self.record.extend_from_slice(&data[..sz]);
+            self.remains -= sz;
+            data = &data[sz..];
+
+            if self.remains > 0 {
+                break;
+            }
+
+            self.infrag = false;
+            if self.eor {
+                records.push(mem::take(&mut self.record));
+            }
+        }
+
+        Ok(records)
+    }
+
+    fn start_fragment(&mut self) -> Result<()> {
+        let rechdr = BigEndian::read_u32(&self.hdr);
+        let size = host_len(rechdr & !LAST_REC)?;
+        let recsz = self.record.len().saturating_add(size);
+
+        if let Some(max) = self.max_fragment {
+            if size > max {
+                trace_event!(debug, size, max, "fragment too large");
+                bail!(ErrorKind::FragmentTooLarge(size, max));
+            }
+        }
+        if let Some(max) = self.max_record {
+            if recsz > max {
+                trace_event!(debug, size = recsz, max, "record too large");
+                bail!(ErrorKind::RecordTooLarge(recsz, max));
+            }
+        }
+
+        trace_event!(trace, size, eor = (rechdr & LAST_REC) != 0, "read fragment");
+        self.hdrlen = 0;
+        self.infrag = true;
+        self.remains = size;
+        self.eor = (rechdr & LAST_REC) != 0;
+        // The header isn't trusted to size the allocation; the buffer grows as data arrives
+        self.record.reserve(min(size, MAX_PREALLOC));
+
+        Ok(())
+    }
+
+    /// Return true if the decoder is at a record boundary, with no partial record buffered.
+    pub fn is_idle(&self) -> bool {
+        !self.infrag && self.hdrlen == 0 && self.record.is_empty()
+    }
+}
+
+/// Incremental decoder for a stream of values of type `T`.
+///
+/// Bytes are buffered until they contain at least one complete value. Each call to `feed`
+/// attempts to unpack values from the buffered data, treating an `UnexpectedEof` IO error as
+/// meaning more data is needed; any other error is returned. Because an incomplete value is
+/// re-decoded from the start each time more data arrives, this is best suited to values which
+/// are small relative to the chunks being fed.
+///
+/// An optional limit on the size of an incomplete value stops a peer which never finishes one
+/// from making the decoder buffer without bound. If the same `feed` call completed values before
+/// the limit was exceeded, those are returned first and the error is returned by the next call;
+/// after that it keeps returning the same error.
+#[derive(Debug)]
+pub struct ValueDecoder<T> {
+    buf: Vec<u8>,
+    max_pending: Option<usize>,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for ValueDecoder<T> {
+    fn default() -> Self {
+        ValueDecoder { buf: Vec::new(), max_pending: None, phantom: PhantomData }
+    }
+}
+
+impl<T> ValueDecoder<T> where T: for<'a> Unpack<&'a [u8]> {
+    /// Create a new decoder.
+    pub fn new() -> ValueDecoder<T> {
+        ValueDecoder::default()
+    }
+
+    /// Create a decoder which fails with `CapacityExceeded` if more than `max` bytes are buffered
+    /// without completing a value.
+    pub fn with_limit(max: usize) -> ValueDecoder<T> {
+        ValueDecoder { max_pending: Some(max), ..ValueDecoder::default() }
+    }
+
+    /// Feed bytes into the decoder, returning any values they complete.
+    pub fn feed(&mut self, data: &[u8]) -> Result<Vec<T>> {
+        self.check_pending()?;
+        self.buf.extend_from_slice(data);
+
+        let mut values = Vec::new();
+        let mut used = 0;
+
+        loop {
+            let mut input = &self.buf[used..];
+            if input.is_empty() {
+                break;
+            }
+            // A length longer than what's buffered is treated as needing more data
+            match with_input_limit(input.len(), || T::unpack(&mut input)) {
+                Ok((v, _)) => {
+                    let next = self.buf.len() - input.len();
+                    values.push(v);
+                    // Types with an empty encoding would otherwise decode forever
+                    if next == used {
+                        break;
+                    }
+                    used = next;
+                },
+                Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.buf.drain(..used);
+        if values.is_empty() {
+            self.check_pending()?;
+        }
+        Ok(values)
+    }
+
+    fn check_pending(&self) -> Result<()> {
+        match self.max_pending {
+            Some(max) if self.buf.len() > max => {
+                trace_event!(debug, size = self.buf.len(), max, "incomplete value too large");
+                bail!(ErrorKind::CapacityExceeded(self.buf.len(), max))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Number of bytes buffered which aren't yet part of a complete value.
+    pub fn pending(&self) -> usize {
+        self.buf.len()
+    }
+}
@@ -0,0 +1,191 @@
+//! Compressed records
+//!
+//! These wrap `XdrRecordWriter` and `XdrRecordReader` to compress each record body individually
+//! with deflate (RFC 1951, without zlib or gzip framing). Record marking itself is unchanged, so
+//! each record remains separately decodable, and the streams can be read with a plain
+//! `XdrRecordReader` and decompressed separately if need be. Both ends must agree on using
+//! compression.
+//!
+//! This module is only available with the `deflate` feature.
+use std::io::{self, BufRead, Read, Write};
+
+use flate2::Compression;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+
+use error::*;
+use record::{XdrRecordReader, XdrRecordWriter, is_retry};
+
+/// Default compression level.
+pub const DEFAULT_LEVEL: u32 = 6;
+
+/// Write compressed records.
+///
+/// Data written is accumulated until `end_record`, and then the record is compressed and written
+/// to the underlying `XdrRecordWriter`, so this holds a whole uncompressed record in memory.
+///
+/// As with `XdrRecordWriter`, the current record is ended when this is destroyed (writing an
+/// empty record if nothing has been written since the last one), ignoring errors. Use `finish`
+/// to see errors.
+pub struct DeflateRecordWriter<W: Write> {
+    body: Vec<u8>,      // uncompressed body of current record
+    pending: bool,      // record needs ending
+    level: Compression,
+    writer: Option<XdrRecordWriter<W>>, // only None once finished
+}
+
+impl<W: Write> DeflateRecordWriter<W> {
+    /// Wrap a record writer, using the default compression level.
+    pub fn new(writer: XdrRecordWriter<W>) -> DeflateRecordWriter<W> {
+        DeflateRecordWriter::with_level(writer, DEFAULT_LEVEL)
+    }
+
+    /// Wrap a record writer with a compression level from 0 (none) to 9 (best).
+    pub fn with_level(writer: XdrRecordWriter<W>, level: u32) -> DeflateRecordWriter<W> {
+        DeflateRecordWriter {
+            body: Vec::new(),
+            pending: true,
+            level: Compression::new(level),
+            writer: Some(writer),
+        }
+    }
+
+    /// End the current record, compressing it and writing it out.
+    pub fn end_record(&mut self) -> io::Result<()> {
+        let writer = self.writer.as_mut().expect("writer already taken");
+        {
+            let mut enc = DeflateEncoder::new(&mut *writer, self.level);
+            enc.write_all(&self.body)?;
+            enc.finish()?;
+        }
+        writer.end_record()?;
+
+        self.body.clear();
+        self.pending = false;
+        Ok(())
+    }
+
+    /// End the stream and return the underlying writer.
+    ///
+    /// This ends the current record in the same way as dropping the writer would. If that fails
+    /// the error is returned and the underlying writer is dropped without any further attempt to
+    /// write to it.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.pending {
+            if let Err(e) = self.end_record() {
+                // Avoid the record writer's drop trying to end the record again
+                if let Some(writer) = self.writer.take() {
+                    let _ = writer.into_inner();
+                }
+                return Err(e);
+            }
+        }
+        self.writer.take().expect("writer already taken").finish()
+    }
+
+    /// Return the underlying record writer without writing anything further. The current
+    /// record, if not yet ended, is discarded.
+    pub fn into_inner(mut self) -> XdrRecordWriter<W> {
+        self.writer.take().expect("writer already taken")
+    }
+}
+
+impl<W: Write> Drop for DeflateRecordWriter<W> {
+    fn drop(&mut self) {
+        if self.writer.is_some() && self.pending {
+            let _ = self.end_record();
+        }
+    }
+}
+
+impl<W: Write> Write for DeflateRecordWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.body.extend_from_slice(buf);
+        self.pending = true;
+        Ok(buf.len())
+    }
+
+    /// Flush the underlying writer. Nothing of the current record is written until it's ended.
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.as_mut().expect("writer already taken").flush()
+    }
+}
+
+/// Read compressed records.
+///
+/// This is an iterator over the decompressed records, with the same error behaviour as
+/// `XdrRecordReader::records()`: it ends after an error, except for `WouldBlock` and
+/// `Interrupted`, after which it can be resumed. A record which doesn't decompress is an
+/// `InvalidInput` or `InvalidData` IO error.
+pub struct DeflateRecordReader<R: BufRead> {
+    max_record: Option<usize>,  // decompressed size limit
+    done: bool,
+    reader: XdrRecordReader<R>,
+}
+
+impl<R: BufRead> DeflateRecordReader<R> {
+    /// Wrap a record reader.
+    pub fn new(reader: XdrRecordReader<R>) -> DeflateRecordReader<R> {
+        DeflateRecordReader { max_record: None, done: false, reader }
+    }
+
+    /// Set the maximum size of a decompressed record. A record which decompresses to more than
+    /// this fails with an `InvalidData` IO error wrapping `ErrorKind::RecordTooLarge` (with the
+    /// size reported as one more than the limit, since decompression stops there). The default
+    /// is no limit.
+    ///
+    /// This is in addition to any limits set on the underlying reader, which apply to the
+    /// compressed records. Without it, a small compressed record can expand to exhaust memory.
+    pub fn set_max_record(&mut self, max: Option<usize>) {
+        self.max_record = max;
+    }
+
+    /// Return a reference to the underlying record reader.
+    pub fn get_ref(&self) -> &XdrRecordReader<R> {
+        &self.reader
+    }
+
+    /// Return the underlying record reader.
+    pub fn into_inner(self) -> XdrRecordReader<R> {
+        self.reader
+    }
+
+    fn inflate(&self, rec: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        let mut dec = DeflateDecoder::new(rec);
+
+        match self.max_record {
+            None => { dec.read_to_end(&mut out)?; },
+            Some(max) => {
+                dec.take(max as u64 + 1).read_to_end(&mut out)?;
+                if out.len() > max {
+                    let err = Error::from(ErrorKind::RecordTooLarge(out.len(), max));
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+                }
+            },
+        }
+
+        Ok(out)
+    }
+}
+
+impl<R: BufRead> Iterator for DeflateRecordReader<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let res = match self.reader.read_record() {
+            Some(Ok(rec)) => Some(self.inflate(&rec)),
+            res => res,
+        };
+        match res {
+            Some(Ok(_)) => (),
+            Some(Err(ref e)) if is_retry(e) => (),
+            _ => self.done = true,
+        }
+        res
+    }
+}
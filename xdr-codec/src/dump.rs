@@ -0,0 +1,202 @@
+//! Annotated hexdumps
+//!
+//! `Dump` builds a hexdump of an encoding where each 4-byte word is labelled with the field it
+//! belongs to, for comparing against packet captures of other implementations. Fields are added
+//! by name, either by packing values with `Dump::field`, or by unpacking them from existing bytes
+//! with `DumpReader::field`:
+//!
+//! ```
+//! use xdr_codec::dump::Dump;
+//!
+//! let mut d = Dump::new();
+//! d.field("xid", &0x1234u32).unwrap()
+//!  .field("proc", &42u32).unwrap()
+//!  .group("cred", |d| d.field("flavor", &1u32)?.field("body", "abc").map(|_| ()))
+//!  .unwrap();
+//!
+//! assert_eq!(d.to_string(),
+//!            "00000000  0x00001234  ...4  xid = 4660\n\
+//!             00000004  0x0000002a  ...*  proc = 42\n\
+//!             00000008  0x00000001  ....  cred.flavor = 1\n\
+//!             0000000c  0x00000003  ....  cred.body = \"abc\"\n\
+//!             00000010  0x61626300  abc.\n");
+//! ```
+//!
+//! Words which continue a field from the previous line are left unlabelled, and values whose
+//! `Debug` output is long are truncated.
+use std::fmt::{self, Debug, Display, Write};
+
+use error::*;
+use super::{Pack, Unpack};
+
+/// Longest `Debug` output shown for a value.
+const MAX_VALUE: usize = 60;
+
+/// A hexdump with each field labelled. See the module documentation.
+#[derive(Debug, Clone, Default)]
+pub struct Dump {
+    bytes: Vec<u8>,
+    labels: Vec<(usize, String)>,   // offset of first byte, label
+    prefix: String,
+}
+
+impl Dump {
+    /// Create an empty dump.
+    pub fn new() -> Dump {
+        Dump::default()
+    }
+
+    /// Pack `val` onto the end of the dump, labelled `name`.
+    pub fn field<T>(&mut self, name: &str, val: &T) -> Result<&mut Self>
+        where T: ?Sized + Pack<Vec<u8>> + Debug
+    {
+        let start = self.bytes.len();
+
+        if let Err(e) = val.pack(&mut self.bytes) {
+            self.bytes.truncate(start);
+            return Err(e);
+        }
+        self.label(start, name, val);
+
+        Ok(self)
+    }
+
+    /// Add fields with `f`, each with its name prefixed by `name.`.
+    ///
+    /// This is useful for the fields of a nested structure.
+    pub fn group<F>(&mut self, name: &str, f: F) -> Result<&mut Self>
+        where F: FnOnce(&mut Dump) -> Result<()>
+    {
+        let outer = self.prefix.len();
+
+        self.prefix.push_str(name);
+        self.prefix.push('.');
+        let res = f(self);
+        self.prefix.truncate(outer);
+
+        res.map(|_| self)
+    }
+
+    /// Return the encoded bytes.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn label<T: ?Sized + Debug>(&mut self, start: usize, name: &str, val: &T) {
+        let mut value = format!("{:?}", val);
+
+        if value.chars().count() > MAX_VALUE {
+            value = value.chars().take(MAX_VALUE).collect();
+            value.push_str("...");
+        }
+
+        self.labels.push((start, format!("{}{} = {}", self.prefix, name, value)));
+    }
+}
+
+impl Display for Dump {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut labels = self.labels.iter().peekable();
+
+        for (i, word) in self.bytes.chunks(4).enumerate() {
+            let off = i * 4;
+            let mut line = format!("{:08x}  0x", off);
+
+            for b in word {
+                let _ = write!(line, "{:02x}", b);
+            }
+            line.push_str(&"  ".repeat(4 - word.len()));
+            line.push_str("  ");
+            for &b in word {
+                line.push(if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' });
+            }
+
+            // Fields are word-aligned; a field which packed to nothing shares its line with the
+            // next one
+            let mut notes = Vec::new();
+            while let Some(&&(start, ref label)) = labels.peek() {
+                if start >= off + 4 {
+                    break;
+                }
+                notes.push(label.as_str());
+                labels.next();
+            }
+            if !notes.is_empty() {
+                line.push_str(&" ".repeat(4 - word.len()));
+                line.push_str("  ");
+                line.push_str(&notes.join(", "));
+            }
+
+            writeln!(f, "{}", line)?;
+        }
+
+        // Fields which packed to nothing at the end
+        let rest: Vec<&str> = labels.map(|(_, l)| l.as_str()).collect();
+        if !rest.is_empty() {
+            writeln!(f, "{:08x}  {}", self.bytes.len(), rest.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Build a `Dump` by unpacking fields from existing bytes.
+#[derive(Debug)]
+pub struct DumpReader<'a> {
+    input: &'a [u8],
+    dump: Dump,
+}
+
+impl<'a> DumpReader<'a> {
+    /// Read fields from the start of `input`.
+    pub fn new(input: &'a [u8]) -> DumpReader<'a> {
+        DumpReader { input, dump: Dump::new() }
+    }
+
+    /// Unpack a `T` labelled `name`, and return it.
+    ///
+    /// On error nothing is consumed, so the field can be retried as another type.
+    pub fn field<T>(&mut self, name: &str) -> Result<T>
+        where T: Unpack<&'a [u8]> + Debug
+    {
+        let before = self.input;
+        let val = match T::unpack(&mut self.input) {
+            Ok((val, _)) => val,
+            Err(e) => {
+                self.input = before;
+                return Err(e);
+            }
+        };
+
+        let start = self.dump.bytes.len();
+        self.dump.bytes.extend_from_slice(&before[..before.len() - self.input.len()]);
+        self.dump.label(start, name, &val);
+
+        Ok(val)
+    }
+
+    /// Unpack fields with `f`, each with its name prefixed by `name.`.
+    pub fn group<T, F>(&mut self, name: &str, f: F) -> Result<T>
+        where F: FnOnce(&mut DumpReader<'a>) -> Result<T>
+    {
+        let outer = self.dump.prefix.len();
+
+        self.dump.prefix.push_str(name);
+        self.dump.prefix.push('.');
+        let res = f(self);
+        self.dump.prefix.truncate(outer);
+
+        res
+    }
+
+    /// Return the bytes which haven't been read yet.
+    pub fn remains(&self) -> &'a [u8] {
+        self.input
+    }
+
+    /// Return the dump, with any unread bytes appended unlabelled.
+    pub fn finish(mut self) -> Dump {
+        self.dump.bytes.extend_from_slice(self.input);
+        self.dump
+    }
+}
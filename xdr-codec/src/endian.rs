@@ -0,0 +1,162 @@
+//! Byte order of primitive types
+//!
+//! XDR is big-endian, but some formats (such as VirtualBox saved state and some firmware images)
+//! use XDR's layout with little-endian integers and floats. With the `little_endian` feature, the
+//! byte order is part of a value's type: wrapping it in `Le` packs and unpacks everything inside
+//! it little-endian, and `Be` switches back to standard XDR for a part of a little-endian value.
+//!
+//! ```
+//! # #[cfg(feature = "little_endian")] {
+//! use xdr_codec::{pack_to_vec, unpack};
+//! use xdr_codec::endian::Le;
+//!
+//! let enc = pack_to_vec(&Le(vec![1u32, 2])).unwrap();
+//! assert_eq!(enc, [2, 0, 0, 0,  1, 0, 0, 0,  2, 0, 0, 0]);
+//!
+//! let Le(v): Le<Vec<u32>> = unpack(&mut &enc[..]).unwrap();
+//! assert_eq!(v, [1, 2]);
+//! # }
+//! ```
+//!
+//! This affects integers, floats, bools, enums, lengths and union discriminants. Everything else
+//! about the encoding is unchanged: sizes are still multiples of 4, the words of a `hyper128`
+//! are most significant first, and `XdrQuadruple` holds its raw big-endian encoding. Record
+//! marking is framing rather than XDR data, so its headers are always big-endian.
+//!
+//! As the byte order goes with the value, a wrapped value is packed the same way wherever it's
+//! packed, such as on the workers of a `pipeline::EncodePipeline`. Types can also hold `Le` and
+//! `Be` fields to describe formats which mix the two.
+//!
+//! Without the feature, the byte order is always big-endian, and there's no cost for checking it.
+#[cfg(feature = "little_endian")]
+use std::cell::Cell;
+#[cfg(feature = "little_endian")]
+use std::io::{Read, Write};
+
+use byteorder::{BigEndian, ByteOrder};
+#[cfg(feature = "little_endian")]
+use byteorder::LittleEndian;
+
+#[cfg(feature = "little_endian")]
+use error::*;
+#[cfg(feature = "little_endian")]
+use skip::Skip;
+#[cfg(feature = "little_endian")]
+use super::{Pack, Unpack};
+
+// Whether the value being packed or unpacked is (at this point) little-endian. This is only set
+// for the duration of packing or unpacking an `Le` or `Be`, so it's never observable outside one.
+#[cfg(feature = "little_endian")]
+thread_local!(static LITTLE: Cell<bool> = const { Cell::new(false) });
+
+// Call `f` with primitives in the given byte order, restoring the previous order when it returns
+// (or panics)
+#[cfg(feature = "little_endian")]
+#[inline]
+fn with_order<R, F: FnOnce() -> R>(little: bool, f: F) -> R {
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            LITTLE.with(|l| l.set(self.0))
+        }
+    }
+
+    let _restore = Restore(LITTLE.with(|l| l.replace(little)));
+
+    f()
+}
+
+/// A value whose primitives are packed and unpacked little-endian.
+///
+/// Only available with the `little_endian` feature.
+#[cfg(feature = "little_endian")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Le<T>(pub T);
+
+/// A value whose primitives are packed and unpacked big-endian, as standard XDR, even within an
+/// `Le`.
+///
+/// Only available with the `little_endian` feature.
+#[cfg(feature = "little_endian")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Be<T>(pub T);
+
+macro_rules! ordered {
+    ($($name:ident = $little:expr),*) => {
+        $(
+            #[cfg(feature = "little_endian")]
+            impl<Out: Write, T: Pack<Out>> Pack<Out> for $name<T> {
+                #[inline]
+                fn pack(&self, out: &mut Out) -> Result<usize> {
+                    with_order($little, || self.0.pack(out))
+                }
+            }
+
+            #[cfg(feature = "little_endian")]
+            impl<In: Read, T: Unpack<In>> Unpack<In> for $name<T> {
+                #[inline]
+                fn unpack(input: &mut In) -> Result<(Self, usize)> {
+                    with_order($little, || T::unpack(input)).map(|(v, sz)| ($name(v), sz))
+                }
+            }
+
+            #[cfg(feature = "little_endian")]
+            impl<In: Read, T: Skip<In>> Skip<In> for $name<T> {
+                #[inline]
+                fn skip(input: &mut In) -> Result<usize> {
+                    with_order($little, || T::skip(input))
+                }
+            }
+        )*
+    }
+}
+
+ordered!(Le = true, Be = false);
+
+macro_rules! order_fns {
+    ($($read:ident, $write:ident: $ty:ty;)*) => {
+        $(
+            #[cfg(feature = "little_endian")]
+            #[inline]
+            pub(crate) fn $read(buf: &[u8]) -> $ty {
+                if LITTLE.with(|l| l.get()) {
+                    LittleEndian::$read(buf)
+                } else {
+                    BigEndian::$read(buf)
+                }
+            }
+
+            #[cfg(feature = "little_endian")]
+            #[inline]
+            pub(crate) fn $write(buf: &mut [u8], v: $ty) {
+                if LITTLE.with(|l| l.get()) {
+                    LittleEndian::$write(buf, v)
+                } else {
+                    BigEndian::$write(buf, v)
+                }
+            }
+
+            #[cfg(not(feature = "little_endian"))]
+            #[inline]
+            pub(crate) fn $read(buf: &[u8]) -> $ty {
+                BigEndian::$read(buf)
+            }
+
+            #[cfg(not(feature = "little_endian"))]
+            #[inline]
+            pub(crate) fn $write(buf: &mut [u8], v: $ty) {
+                BigEndian::$write(buf, v)
+            }
+        )*
+    }
+}
+
+order_fns! {
+    read_u32, write_u32: u32;
+    read_i32, write_i32: i32;
+    read_u64, write_u64: u64;
+    read_i64, write_i64: i64;
+    read_f32, write_f32: f32;
+    read_f64, write_f64: f64;
+}
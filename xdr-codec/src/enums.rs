@@ -0,0 +1,203 @@
+//! Hand-written enums
+//!
+//! XDR enums are encoded as their `int` value. `xdrgen` generates a C-like Rust `enum` with
+//! explicit discriminants for each one, packing it as an `i32` and failing to unpack with
+//! `ErrorKind::InvalidEnum` for values which aren't one of the variants. The `xdr_enum!` macro
+//! does the same for hand-written enums:
+//!
+//! ```
+//! #[macro_use] extern crate xdr_codec;
+//! use std::convert::TryFrom;
+//!
+//! xdr_enum! {
+//!     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+//!     pub enum Colour {
+//!         Red = 1,
+//!         Green = 2,
+//!         Blue = 4,
+//!     }
+//! }
+//!
+//! # fn main() {
+//! assert_eq!(Colour::try_from(2).unwrap(), Colour::Green);
+//! assert!(Colour::try_from(3).is_err());
+//! assert_eq!(xdr_codec::pack_to_vec(&Colour::Blue).unwrap(), vec![0, 0, 0, 4]);
+//! # }
+//! ```
+//!
+//! As well as the enum itself, this generates implementations of `XdrEnum`, `TryFrom<i32>`,
+//! `From<Enum> for i32`, `Pack`, `Unpack` and `Skip`. Every variant must have an explicit value.
+//! Attributes are passed through, so `#[repr(i32)]` can be used to have the values typed as `i32`
+//! rather than the default `isize`.
+//!
+//! A peer running a newer version of a protocol may send enum values this end doesn't know
+//! about yet. Wrapping the enum's type in `Lenient` keeps such values as their raw `int` rather
+//! than failing to decode the whole message, and packs them unchanged, so they can be passed on:
+//!
+//! ```
+//! # #[macro_use] extern crate xdr_codec;
+//! # xdr_enum! {
+//! #     #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+//! #     pub enum Colour { Red = 1, Green = 2, Blue = 4 }
+//! # }
+//! use xdr_codec::enums::Lenient;
+//!
+//! # fn main() {
+//! let v: Vec<Lenient<Colour>> = xdr_codec::unpack(&mut &[0, 0, 0, 2,  0, 0, 0, 1,  0, 0, 0, 3][..]).unwrap();
+//! assert_eq!(v, [Lenient::Known(Colour::Red), Lenient::Unknown(3)]);
+//! # }
+//! ```
+use std::io::{Read, Write};
+
+use error::*;
+use super::{Pack, Unpack};
+use skip::Skip;
+
+/// Conversion between a C-like enum and its XDR `int` value.
+///
+/// This is normally implemented with `xdr_enum!`.
+pub trait XdrEnum: Sized {
+    /// Return the enum's value.
+    fn to_i32(&self) -> i32;
+
+    /// Return the variant with the given value, or `ErrorKind::InvalidEnum` if there isn't one.
+    fn from_i32(v: i32) -> Result<Self>;
+}
+
+/// An enum value which may not be one of the known variants of `T`.
+///
+/// This unpacks any `int`, so it never fails with `ErrorKind::InvalidEnum`. It's intended for
+/// fields of messages from peers which may be running a newer version of the protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lenient<T> {
+    /// One of `T`'s variants.
+    Known(T),
+    /// A value which isn't one of `T`'s variants.
+    Unknown(i32),
+}
+
+impl<T: XdrEnum> Lenient<T> {
+    /// Return the known variant, if it is one.
+    pub fn known(&self) -> Option<&T> {
+        match *self {
+            Lenient::Known(ref v) => Some(v),
+            Lenient::Unknown(_) => None,
+        }
+    }
+
+    /// Return true if the value is one of `T`'s variants.
+    pub fn is_known(&self) -> bool {
+        self.known().is_some()
+    }
+
+    /// Convert to the known variant, or fail with `ErrorKind::InvalidEnum` as unpacking `T`
+    /// directly would have.
+    pub fn into_known(self) -> Result<T> {
+        match self {
+            Lenient::Known(v) => Ok(v),
+            Lenient::Unknown(v) => Err(Error::invalidenum(v)),
+        }
+    }
+}
+
+impl<T> From<T> for Lenient<T> {
+    fn from(v: T) -> Self {
+        Lenient::Known(v)
+    }
+}
+
+impl<T: XdrEnum> XdrEnum for Lenient<T> {
+    #[inline]
+    fn to_i32(&self) -> i32 {
+        match *self {
+            Lenient::Known(ref v) => v.to_i32(),
+            Lenient::Unknown(v) => v,
+        }
+    }
+
+    #[inline]
+    fn from_i32(v: i32) -> Result<Self> {
+        Ok(T::from_i32(v).map_or(Lenient::Unknown(v), Lenient::Known))
+    }
+}
+
+impl<Out: Write, T: XdrEnum> Pack<Out> for Lenient<T> {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.to_i32().pack(out)
+    }
+}
+
+impl<In: Read, T: XdrEnum> Unpack<In> for Lenient<T> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz) = i32::unpack(input)?;
+        Ok((Self::from_i32(v)?, sz))
+    }
+}
+
+impl<In: Read, T: XdrEnum> Skip<In> for Lenient<T> {}
+
+/// Define a C-like enum which is packed and unpacked as an XDR enum. See the `enums` module.
+#[macro_export]
+macro_rules! xdr_enum {
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident {
+            $( $(#[$vattr:meta])* $variant:ident = $value:expr ),+ $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis enum $name {
+            $( $(#[$vattr])* $variant = $value ),+
+        }
+
+        impl $crate::XdrEnum for $name {
+            #[inline]
+            fn to_i32(&self) -> i32 {
+                match *self {
+                    $( $name::$variant => ($value) as i32, )+
+                }
+            }
+
+            #[inline]
+            fn from_i32(v: i32) -> $crate::Result<Self> {
+                match v {
+                    $( x if x == ($value) as i32 => Ok($name::$variant), )+
+                    v => Err($crate::Error::invalidenum(v)),
+                }
+            }
+        }
+
+        impl ::std::convert::TryFrom<i32> for $name {
+            type Error = $crate::Error;
+
+            fn try_from(v: i32) -> $crate::Result<Self> {
+                <$name as $crate::XdrEnum>::from_i32(v)
+            }
+        }
+
+        impl ::std::convert::From<$name> for i32 {
+            fn from(v: $name) -> i32 {
+                $crate::XdrEnum::to_i32(&v)
+            }
+        }
+
+        impl<Out: $crate::Write> $crate::Pack<Out> for $name {
+            #[inline]
+            fn pack(&self, out: &mut Out) -> $crate::Result<usize> {
+                $crate::Pack::pack(&$crate::XdrEnum::to_i32(self), out)
+            }
+        }
+
+        impl<In: $crate::Read> $crate::Unpack<In> for $name {
+            #[inline]
+            fn unpack(input: &mut In) -> $crate::Result<(Self, usize)> {
+                let (v, sz): (i32, _) = $crate::Unpack::unpack(input)?;
+                Ok((<$name as $crate::XdrEnum>::from_i32(v)?, sz))
+            }
+        }
+
+        impl<In: $crate::Read> $crate::Skip<In> for $name {}
+    };
+}
@@ -0,0 +1,79 @@
+//! Type-erased packing
+//!
+//! `Pack<Out>` is generic over the writer, so a `dyn Pack` has to name one concrete writer type.
+//! `ErasedPack` packs to a `&mut dyn Write` instead, so values of different types can be held
+//! together as `Box<dyn ErasedPack>` (for example in a queue of outgoing messages) and packed by
+//! code which isn't generic. It is implemented for everything which can pack to a `dyn Write`,
+//! which includes all the types in this crate and generated code, and is sealed so that stays
+//! the only implementation.
+//!
+//! `dyn ErasedPack` implements `Pack` for any writer, so boxed values can be packed like any
+//! other, including as the elements of an array:
+//!
+//! ```
+//! use xdr_codec::{ErasedPack, pack_to_vec};
+//!
+//! let queue: Vec<Box<dyn ErasedPack>> = vec![Box::new(1u32), Box::new(vec![2u32, 3])];
+//! assert_eq!(pack_to_vec(&queue[1]).unwrap(), [0, 0, 0, 2,  0, 0, 0, 2,  0, 0, 0, 3]);
+//!
+//! let mut out = Vec::new();
+//! for msg in &queue {
+//!     msg.erased_pack(&mut out).unwrap();
+//! }
+//! assert_eq!(out, [0, 0, 0, 1,  0, 0, 0, 2,  0, 0, 0, 2,  0, 0, 0, 3]);
+//! ```
+//!
+//! Each write goes through a virtual call, so this is slower than packing with a concrete
+//! writer; packing into a `Vec<u8>` first and writing that out avoids most of the cost.
+use std::io::Write;
+
+use error::*;
+use super::Pack;
+
+mod sealed {
+    use std::io::Write;
+    use super::Pack;
+
+    pub trait Sealed {}
+
+    impl<T: ?Sized> Sealed for T where T: for<'a> Pack<&'a mut dyn Write> {}
+}
+
+/// Object-safe packing to a `dyn Write`.
+///
+/// This is sealed: it's implemented for every type which implements `Pack` for a `&mut dyn
+/// Write`, and can't be implemented otherwise.
+pub trait ErasedPack: sealed::Sealed {
+    /// Pack `self` to `out`, returning the number of bytes written.
+    fn erased_pack(&self, out: &mut dyn Write) -> Result<usize>;
+}
+
+impl<T: ?Sized> ErasedPack for T
+    where T: for<'a> Pack<&'a mut dyn Write>
+{
+    #[inline]
+    fn erased_pack(&self, mut out: &mut dyn Write) -> Result<usize> {
+        self.pack(&mut out)
+    }
+}
+
+impl<'e, Out: Write> Pack<Out> for dyn ErasedPack + 'e {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.erased_pack(out)
+    }
+}
+
+impl<'e, Out: Write> Pack<Out> for dyn ErasedPack + Send + 'e {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.erased_pack(out)
+    }
+}
+
+impl<'e, Out: Write> Pack<Out> for dyn ErasedPack + Send + Sync + 'e {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.erased_pack(out)
+    }
+}
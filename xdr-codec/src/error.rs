@@ -1,28 +1,148 @@
-error_chain! {
-    foreign_links {
-        IOError(::std::io::Error);
-        InvalidUtf8(::std::string::FromUtf8Error);
-    }
+use std::error::Error as StdError;
+use std::ffi::NulError;
+use std::fmt;
+use std::io;
+use std::string::FromUtf8Error;
 
-    errors {
-        InvalidCase(v: i32) {
-            description("invalid union case")
-            display("invalid union case: '{}'", v)
-        }
-        InvalidEnum(v: i32) {
-            description("invalid enum value")
-            display("invalid enum value: '{}'", v)
+/// The kind of an `Error`.
+///
+/// New kinds may be added, so matches on it need a wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A free-form error message.
+    Msg(String),
+    /// An IO error from the underlying stream.
+    IOError(io::Error),
+    /// A string which isn't valid UTF-8.
+    InvalidUtf8(FromUtf8Error),
+    /// A string containing a NUL, which can't be converted to a C string.
+    InvalidCString(NulError),
+    /// A union discriminant with no matching arm.
+    InvalidCase(i32),
+    /// A value which isn't one of an enum's variants.
+    InvalidEnum(i32),
+    /// An array length which is too large (giving the limit), or doesn't match.
+    InvalidLen(usize),
+    /// A network address which can't be converted.
+    InvalidAddr(String),
+    /// A time which is out of range (seconds and nanoseconds).
+    InvalidTime(i64, u32),
+    /// An array or string too long for a fixed-capacity container (length and capacity).
+    CapacityExceeded(usize, usize),
+    /// A record fragment larger than the limit (size and limit).
+    FragmentTooLarge(usize, usize),
+    /// A record larger than the limit (size and limit).
+    RecordTooLarge(usize, usize),
+    /// A record whose checksum doesn't match its contents (expected and actual).
+    ChecksumMismatch(u32, u32),
+    /// A final record fragment too short to contain a checksum (giving its size).
+    MissingChecksum(usize),
+    /// An encoding which differs from the canonical one at the given offset.
+    NonCanonical(usize),
+    /// The orderly end of a stream, between values.
+    Eof,
+    /// Bytes left over after a value which should have taken up all the input.
+    TrailingData(usize),
+    /// A schema referring to a type which isn't defined.
+    UnknownType(String),
+    /// A schema which is inconsistent.
+    InvalidSchema(String),
+    /// A dynamically typed value which doesn't match its type.
+    InvalidValue(String),
+    /// A value nested more deeply than the limit (giving the limit).
+    DepthExceeded(usize),
+}
+
+impl ErrorKind {
+    /// A short description of the error, without any details.
+    pub fn description(&self) -> &str {
+        match *self {
+            ErrorKind::Msg(ref s) => s,
+            ErrorKind::IOError(_) => "IO error",
+            ErrorKind::InvalidUtf8(_) => "invalid UTF-8",
+            ErrorKind::InvalidCString(_) => "invalid C string",
+            ErrorKind::InvalidCase(_) => "invalid union case",
+            ErrorKind::InvalidEnum(_) => "invalid enum value",
+            ErrorKind::InvalidLen(_) => "invalid array len",
+            ErrorKind::InvalidAddr(_) => "invalid network address",
+            ErrorKind::InvalidTime(..) => "invalid time",
+            ErrorKind::CapacityExceeded(..) => "capacity exceeded",
+            ErrorKind::FragmentTooLarge(..) => "record fragment too large",
+            ErrorKind::RecordTooLarge(..) => "record too large",
+            ErrorKind::ChecksumMismatch(..) => "record checksum mismatch",
+            ErrorKind::MissingChecksum(_) => "record fragment too short for checksum",
+            ErrorKind::NonCanonical(_) => "non-canonical encoding",
+            ErrorKind::Eof => "end of stream",
+            ErrorKind::TrailingData(_) => "trailing data after value",
+            ErrorKind::UnknownType(_) => "unknown type",
+            ErrorKind::InvalidSchema(_) => "invalid schema",
+            ErrorKind::InvalidValue(_) => "value doesn't match type",
+            ErrorKind::DepthExceeded(_) => "value nested too deeply",
         }
-        InvalidLen(v: usize) {
-            description("invalid array len")
-            display("invalid array len: '{}'", v)
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ErrorKind::Msg(ref s) => f.write_str(s),
+            ErrorKind::IOError(ref e) => e.fmt(f),
+            ErrorKind::InvalidUtf8(ref e) => e.fmt(f),
+            ErrorKind::InvalidCString(ref e) => e.fmt(f),
+            ErrorKind::InvalidCase(v) => write!(f, "invalid union case: '{}'", v),
+            ErrorKind::InvalidEnum(v) => write!(f, "invalid enum value: '{}'", v),
+            ErrorKind::InvalidLen(v) => write!(f, "invalid array len: '{}'", v),
+            ErrorKind::InvalidAddr(ref v) => write!(f, "invalid network address: '{}'", v),
+            ErrorKind::InvalidTime(secs, nsecs) => write!(f, "invalid time: {}s {}ns", secs, nsecs),
+            ErrorKind::CapacityExceeded(len, cap) => write!(f, "capacity exceeded: length {}, capacity {}", len, cap),
+            ErrorKind::FragmentTooLarge(size, max) =>
+                write!(f, "record fragment too large: {} bytes, limit {}", size, max),
+            ErrorKind::RecordTooLarge(size, max) => write!(f, "record too large: {} bytes, limit {}", size, max),
+            ErrorKind::ChecksumMismatch(expected, actual) =>
+                write!(f, "record checksum mismatch: expected {:08x}, got {:08x}", expected, actual),
+            ErrorKind::MissingChecksum(size) =>
+                write!(f, "record fragment too short for checksum: {} bytes", size),
+            ErrorKind::NonCanonical(offset) => write!(f, "non-canonical encoding at byte {}", offset),
+            ErrorKind::Eof => f.write_str("end of stream"),
+            ErrorKind::TrailingData(size) => write!(f, "{} bytes of trailing data after value", size),
+            ErrorKind::UnknownType(ref name) => write!(f, "unknown type: '{}'", name),
+            ErrorKind::InvalidSchema(ref v) => write!(f, "invalid schema: {}", v),
+            ErrorKind::InvalidValue(ref v) => write!(f, "value doesn't match type: {}", v),
+            ErrorKind::DepthExceeded(max) => write!(f, "value nested too deeply: limit {}", max),
         }
     }
 }
 
-unsafe impl Sync for Error {}
+/// Additional state carried by an `Error`.
+///
+/// This has no public contents; it's there so that errors can be matched as
+/// `Error(ErrorKind::..., _)`.
+#[derive(Debug, Default)]
+pub struct State {
+    captured: Option<Vec<u8>>,  // input consumed before the error, from `tee::unpack_captured`
+}
+
+/// Error from packing or unpacking.
+///
+/// Everything it can contain is `Send` and `Sync`, so it is too.
+#[derive(Debug)]
+pub struct Error(pub ErrorKind, pub State);
+
+/// Result of packing or unpacking.
+pub type Result<T> = ::std::result::Result<T, Error>;
 
 impl Error {
+    /// Construct an error of the given kind.
+    pub fn from_kind(kind: ErrorKind) -> Error {
+        Error(kind, State::default())
+    }
+
+    /// Return the kind of error.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.0
+    }
+
     pub fn invalidcase(v: i32) -> Error {
         ErrorKind::InvalidCase(v).into()
     }
@@ -34,4 +154,74 @@ impl Error {
     pub fn invalidlen(v: usize) -> Error {
         ErrorKind::InvalidLen(v).into()
     }
-}
\ No newline at end of file
+
+    /// Return true if this is `ErrorKind::Eof`, the orderly end of a stream between values.
+    pub fn is_eof(&self) -> bool {
+        matches!(*self.kind(), ErrorKind::Eof)
+    }
+
+    /// Attach the input which was consumed before this error occurred.
+    pub fn with_captured(mut self, bytes: Vec<u8>) -> Error {
+        self.1.captured = Some(bytes);
+        self
+    }
+
+    /// The input consumed before this error occurred, if it was captured (see
+    /// `tee::unpack_captured`).
+    pub fn captured(&self) -> Option<&[u8]> {
+        self.1.captured.as_deref()
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self.0 {
+            ErrorKind::IOError(ref e) => Some(e),
+            ErrorKind::InvalidUtf8(ref e) => Some(e),
+            ErrorKind::InvalidCString(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Error {
+        Error::from_kind(kind)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        ErrorKind::IOError(e).into()
+    }
+}
+
+impl From<FromUtf8Error> for Error {
+    fn from(e: FromUtf8Error) -> Error {
+        ErrorKind::InvalidUtf8(e).into()
+    }
+}
+
+impl From<NulError> for Error {
+    fn from(e: NulError) -> Error {
+        ErrorKind::InvalidCString(e).into()
+    }
+}
+
+impl From<String> for Error {
+    fn from(s: String) -> Error {
+        ErrorKind::Msg(s).into()
+    }
+}
+
+impl<'a> From<&'a str> for Error {
+    fn from(s: &'a str) -> Error {
+        ErrorKind::Msg(s.to_string()).into()
+    }
+}
@@ -0,0 +1,497 @@
+//! C interface
+//!
+//! This exposes the basic encoders and decoders and record framing with a C ABI, so that C (or
+//! anything else which can call C) can share this implementation. The declarations are in
+//! `include/xdr_codec.h`. To build a shared or static library, use
+//!
+//! ```text
+//! cargo rustc --release --features ffi --crate-type cdylib   # or staticlib
+//! ```
+//!
+//! An `XdrEncoder` is a growable buffer which the `xdr_pack_*` functions append to. An
+//! `XdrDecoder` reads from a buffer owned by the caller, which must stay valid and unchanged
+//! while the decoder exists; a failed `xdr_unpack_*` call doesn't move its position. Opaque data
+//! and strings are unpacked into caller-provided buffers, whose size is the limit on the length
+//! accepted. An `XdrRecordDecoder` reassembles record-marked streams which are fed to it in
+//! pieces (see `decoder::RecordDecoder`), and `xdr_record_frame` frames a record for writing.
+//!
+//! All functions return one of the `XDR_*` status codes, and `xdr_strerror` describes them.
+//! Handles are created with `*_new` and must be released with the matching `*_free`; they
+//! aren't thread-safe.
+//!
+//! This module is only available with the `ffi` feature.
+use std::collections::VecDeque;
+use std::ffi::CStr;
+use std::io::{self, Write};
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+use std::slice;
+
+use decoder::RecordDecoder;
+use error::*;
+use options::with_input_limit;
+use record::XdrRecordWriter;
+use super::{Pack, Unpack, pack_opaque_array, pack_opaque_flex, unpack_opaque_array, unpack_opaque_borrowed};
+
+/// Success.
+pub const XDR_OK: c_int = 0;
+/// `xdr_record_decoder_next` has no complete record to return.
+pub const XDR_EMPTY: c_int = 1;
+/// A required pointer argument was null.
+pub const XDR_ERR_NULL: c_int = -1;
+/// The input ended in the middle of a value.
+pub const XDR_ERR_SHORT: c_int = -2;
+/// A length is larger than its limit or the buffer provided for it.
+pub const XDR_ERR_LEN: c_int = -3;
+/// A value is invalid, such as a boolean other than 0 or 1, or a string containing a NUL.
+pub const XDR_ERR_INVALID: c_int = -4;
+/// Record framing failed, such as a fragment or record over its size limit.
+pub const XDR_ERR_FRAMING: c_int = -5;
+/// Any other failure.
+pub const XDR_ERR_OTHER: c_int = -6;
+
+/// A `maxsz` which means no limit.
+pub const XDR_NOMAX: usize = usize::MAX;
+
+/// Encoder handle, accumulating packed data.
+#[derive(Debug, Default)]
+pub struct XdrEncoder {
+    buf: Vec<u8>,
+}
+
+/// Decoder handle, reading from a caller-owned buffer.
+#[derive(Debug)]
+pub struct XdrDecoder {
+    data: *const u8,
+    len: usize,
+    pos: usize,
+}
+
+/// Record decoder handle.
+#[derive(Debug, Default)]
+pub struct XdrRecordDecoder {
+    decoder: RecordDecoder,
+    ready: VecDeque<Vec<u8>>,   // complete records not yet returned
+    current: Vec<u8>,           // last record returned
+}
+
+fn status(err: &Error) -> c_int {
+    match err.0 {
+        ErrorKind::IOError(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => XDR_ERR_SHORT,
+        ErrorKind::InvalidLen(_) | ErrorKind::CapacityExceeded(..) => XDR_ERR_LEN,
+        ErrorKind::InvalidUtf8(_) | ErrorKind::InvalidCString(_) | ErrorKind::InvalidCase(_) |
+        ErrorKind::InvalidEnum(_) => XDR_ERR_INVALID,
+        ErrorKind::FragmentTooLarge(..) | ErrorKind::RecordTooLarge(..) | ErrorKind::ChecksumMismatch(..) |
+        ErrorKind::MissingChecksum(_) => XDR_ERR_FRAMING,
+        _ => XDR_ERR_OTHER,
+    }
+}
+
+fn limit(maxsz: usize) -> Option<usize> {
+    if maxsz == XDR_NOMAX { None } else { Some(maxsz) }
+}
+
+// A slice from a C pointer and length, which may be null if the length is 0
+unsafe fn c_slice<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        Some(&[])
+    } else if data.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(data, len))
+    }
+}
+
+unsafe fn encode<F>(enc: *mut XdrEncoder, f: F) -> c_int
+    where F: FnOnce(&mut Vec<u8>) -> Result<usize>
+{
+    let enc = match enc.as_mut() {
+        Some(enc) => enc,
+        None => return XDR_ERR_NULL,
+    };
+    // Don't leave part of a value behind on failure
+    let start = enc.buf.len();
+    match f(&mut enc.buf) {
+        Ok(_) => XDR_OK,
+        Err(ref e) => {
+            enc.buf.truncate(start);
+            status(e)
+        },
+    }
+}
+
+unsafe fn decode<F>(dec: *mut XdrDecoder, f: F) -> c_int
+    where F: FnOnce(&mut &[u8]) -> Result<()>
+{
+    let dec = match dec.as_mut() {
+        Some(dec) => dec,
+        None => return XDR_ERR_NULL,
+    };
+    let data = match c_slice(dec.data, dec.len) {
+        Some(data) => &data[dec.pos..],
+        None => return XDR_ERR_NULL,
+    };
+
+    let mut input = data;
+    match with_input_limit(data.len(), || f(&mut input)) {
+        Ok(()) => {
+            dec.pos += data.len() - input.len();
+            XDR_OK
+        },
+        Err(ref e) => status(e),
+    }
+}
+
+/// Describe a status code. The result is a static NUL-terminated string.
+#[no_mangle]
+pub extern "C" fn xdr_strerror(status: c_int) -> *const c_char {
+    let msg: &'static [u8] = match status {
+        XDR_OK => b"success\0",
+        XDR_EMPTY => b"no record ready\0",
+        XDR_ERR_NULL => b"null pointer\0",
+        XDR_ERR_SHORT => b"input too short\0",
+        XDR_ERR_LEN => b"invalid length\0",
+        XDR_ERR_INVALID => b"invalid value\0",
+        XDR_ERR_FRAMING => b"record framing error\0",
+        XDR_ERR_OTHER => b"error\0",
+        _ => b"unknown status\0",
+    };
+    msg.as_ptr() as *const c_char
+}
+
+/// Create an empty encoder.
+#[no_mangle]
+pub extern "C" fn xdr_encoder_new() -> *mut XdrEncoder {
+    Box::into_raw(Box::default())
+}
+
+/// Free an encoder.
+///
+/// # Safety
+///
+/// `enc` must be null or a handle from `xdr_encoder_new` which hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_encoder_free(enc: *mut XdrEncoder) {
+    if !enc.is_null() {
+        drop(Box::from_raw(enc));
+    }
+}
+
+/// Return the encoded data, storing its length in `*len`. The pointer is valid until the
+/// encoder is next changed or freed.
+///
+/// # Safety
+///
+/// `enc` must be a valid encoder handle and `len` a valid pointer; null returns null.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_encoder_data(enc: *const XdrEncoder, len: *mut usize) -> *const u8 {
+    match (enc.as_ref(), len.as_mut()) {
+        (Some(enc), Some(len)) => {
+            *len = enc.buf.len();
+            enc.buf.as_ptr()
+        },
+        _ => ptr::null(),
+    }
+}
+
+/// Discard the encoded data, keeping the buffer for reuse.
+///
+/// # Safety
+///
+/// `enc` must be null or a valid encoder handle.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_encoder_clear(enc: *mut XdrEncoder) {
+    if let Some(enc) = enc.as_mut() {
+        enc.buf.clear();
+    }
+}
+
+/// Create a decoder reading `len` bytes from `data`.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes (or may be null if `len` is 0), which stay valid
+/// and unchanged until the decoder is freed.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_decoder_new(data: *const u8, len: usize) -> *mut XdrDecoder {
+    if data.is_null() && len != 0 {
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new(XdrDecoder { data, len, pos: 0 }))
+}
+
+/// Free a decoder. The data it was reading isn't affected.
+///
+/// # Safety
+///
+/// `dec` must be null or a handle from `xdr_decoder_new` which hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_decoder_free(dec: *mut XdrDecoder) {
+    if !dec.is_null() {
+        drop(Box::from_raw(dec));
+    }
+}
+
+/// The number of bytes not yet decoded.
+///
+/// # Safety
+///
+/// `dec` must be null or a valid decoder handle; null returns 0.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_decoder_remaining(dec: *const XdrDecoder) -> usize {
+    dec.as_ref().map_or(0, |dec| dec.len - dec.pos)
+}
+
+macro_rules! ffi_primitives {
+    ($($pack:ident, $unpack:ident: $ty:ty, $xdr:expr;)*) => {
+        $(
+            #[doc = concat!("Pack an XDR `", $xdr, "`.")]
+            ///
+            /// # Safety
+            ///
+            /// `enc` must be a valid encoder handle.
+            #[no_mangle]
+            pub unsafe extern "C" fn $pack(enc: *mut XdrEncoder, val: $ty) -> c_int {
+                encode(enc, |out| val.pack(out))
+            }
+
+            #[doc = concat!("Unpack an XDR `", $xdr, "` into `*val`.")]
+            ///
+            /// # Safety
+            ///
+            /// `dec` must be a valid decoder handle and `val` a valid pointer.
+            #[no_mangle]
+            pub unsafe extern "C" fn $unpack(dec: *mut XdrDecoder, val: *mut $ty) -> c_int {
+                let val = match val.as_mut() {
+                    Some(val) => val,
+                    None => return XDR_ERR_NULL,
+                };
+                decode(dec, |input| {
+                    *val = <$ty>::unpack(input)?.0;
+                    Ok(())
+                })
+            }
+        )*
+    }
+}
+
+ffi_primitives! {
+    xdr_pack_int, xdr_unpack_int: i32, "int";
+    xdr_pack_uint, xdr_unpack_uint: u32, "unsigned int";
+    xdr_pack_hyper, xdr_unpack_hyper: i64, "hyper";
+    xdr_pack_uhyper, xdr_unpack_uhyper: u64, "unsigned hyper";
+    xdr_pack_float, xdr_unpack_float: f32, "float";
+    xdr_pack_double, xdr_unpack_double: f64, "double";
+    xdr_pack_bool, xdr_unpack_bool: bool, "bool";
+}
+
+/// Pack `len` bytes from `data` as a fixed-length `opaque[len]`.
+///
+/// # Safety
+///
+/// `enc` must be a valid encoder handle, and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_pack_opaque_fixed(enc: *mut XdrEncoder, data: *const u8, len: usize) -> c_int {
+    match c_slice(data, len) {
+        Some(data) => encode(enc, |out| pack_opaque_array(data, len, out)),
+        None => XDR_ERR_NULL,
+    }
+}
+
+/// Pack `len` bytes from `data` as a variable-length `opaque<maxsz>`. Use `XDR_NOMAX` for
+/// `opaque<>`.
+///
+/// # Safety
+///
+/// `enc` must be a valid encoder handle, and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_pack_opaque(enc: *mut XdrEncoder, data: *const u8, len: usize, maxsz: usize)
+                                         -> c_int {
+    match c_slice(data, len) {
+        Some(data) => encode(enc, |out| pack_opaque_flex(data, limit(maxsz), out)),
+        None => XDR_ERR_NULL,
+    }
+}
+
+/// Pack a NUL-terminated string as a `string<maxsz>`. Use `XDR_NOMAX` for `string<>`.
+///
+/// # Safety
+///
+/// `enc` must be a valid encoder handle, and `s` a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_pack_string(enc: *mut XdrEncoder, s: *const c_char, maxsz: usize) -> c_int {
+    if s.is_null() {
+        return XDR_ERR_NULL;
+    }
+    // C strings needn't be UTF-8, but encode the same way as opaque data
+    let s = CStr::from_ptr(s).to_bytes();
+    encode(enc, |out| pack_opaque_flex(s, limit(maxsz), out))
+}
+
+/// Unpack a fixed-length `opaque[len]` into the `len` bytes at `buf`.
+///
+/// # Safety
+///
+/// `dec` must be a valid decoder handle, and `buf` must point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_unpack_opaque_fixed(dec: *mut XdrDecoder, buf: *mut u8, len: usize) -> c_int {
+    if buf.is_null() && len != 0 {
+        return XDR_ERR_NULL;
+    }
+    let buf: &mut [u8] = if len == 0 { &mut [] } else { slice::from_raw_parts_mut(buf, len) };
+    decode(dec, |input| unpack_opaque_array(input, buf, len).map(|_| ()))
+}
+
+/// Unpack a variable-length `opaque<>` into `buf`, storing its length in `*len`. Fails with
+/// `XDR_ERR_LEN` if it's longer than `bufsz`.
+///
+/// # Safety
+///
+/// `dec` must be a valid decoder handle, `buf` must point to `bufsz` writable bytes, and `len`
+/// must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_unpack_opaque(dec: *mut XdrDecoder, buf: *mut u8, bufsz: usize, len: *mut usize)
+                                           -> c_int {
+    let len = match len.as_mut() {
+        Some(len) if !buf.is_null() || bufsz == 0 => len,
+        _ => return XDR_ERR_NULL,
+    };
+    decode(dec, |input| {
+        let (data, _) = unpack_opaque_borrowed(input, Some(bufsz))?;
+        if !data.is_empty() {
+            slice::from_raw_parts_mut(buf, data.len()).copy_from_slice(data);
+        }
+        *len = data.len();
+        Ok(())
+    })
+}
+
+/// Unpack a `string<>` into `buf` as a NUL-terminated string, storing its length (not including
+/// the NUL) in `*len` if `len` isn't null. Fails with `XDR_ERR_LEN` if it doesn't fit in `bufsz`
+/// bytes with the terminator, and `XDR_ERR_INVALID` if it contains a NUL.
+///
+/// # Safety
+///
+/// `dec` must be a valid decoder handle, `buf` must point to `bufsz` writable bytes, and `len`
+/// must be null or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_unpack_string(dec: *mut XdrDecoder, buf: *mut c_char, bufsz: usize, len: *mut usize)
+                                           -> c_int {
+    if buf.is_null() {
+        return XDR_ERR_NULL;
+    }
+    if bufsz == 0 {
+        return XDR_ERR_LEN;
+    }
+    decode(dec, |input| {
+        let (data, _) = unpack_opaque_borrowed(input, Some(bufsz - 1))?;
+        if data.contains(&0) {
+            bail!(ErrorKind::InvalidCString(::std::ffi::CString::new(data).unwrap_err()));
+        }
+        let out = slice::from_raw_parts_mut(buf as *mut u8, data.len() + 1);
+        out[..data.len()].copy_from_slice(data);
+        out[data.len()] = 0;
+        if let Some(len) = len.as_mut() {
+            *len = data.len();
+        }
+        Ok(())
+    })
+}
+
+/// Append `len` bytes from `data` to `out` as one record-marked record, split into fragments of
+/// at most `fragsz` bytes (0 for the default).
+///
+/// # Safety
+///
+/// `out` must be a valid encoder handle, and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_record_frame(out: *mut XdrEncoder, data: *const u8, len: usize, fragsz: usize)
+                                          -> c_int {
+    let data = match c_slice(data, len) {
+        Some(data) => data,
+        None => return XDR_ERR_NULL,
+    };
+    if fragsz > i32::MAX as usize {
+        return XDR_ERR_LEN;
+    }
+    // The writer buffers a whole fragment, but none will be larger than the data, so there's no
+    // need to allocate for any more
+    let fragsz = if fragsz == 0 { 0 } else { fragsz.min(len.max(1)) };
+    encode(out, |out| {
+        let mut w = if fragsz == 0 { XdrRecordWriter::new(out) } else { XdrRecordWriter::with_fragment_size(out, fragsz) };
+        w.write_all(data)?;
+        w.finish()?;
+        Ok(len)
+    })
+}
+
+/// Create a record decoder. Fragments larger than `max_fragment`, or records larger than
+/// `max_record`, fail with `XDR_ERR_FRAMING`; use `XDR_NOMAX` for no limit.
+#[no_mangle]
+pub extern "C" fn xdr_record_decoder_new(max_fragment: usize, max_record: usize) -> *mut XdrRecordDecoder {
+    Box::into_raw(Box::new(XdrRecordDecoder {
+        decoder: RecordDecoder::with_limits(limit(max_fragment), limit(max_record)),
+        ..XdrRecordDecoder::default()
+    }))
+}
+
+/// Free a record decoder.
+///
+/// # Safety
+///
+/// `rd` must be null or a handle from `xdr_record_decoder_new` which hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_record_decoder_free(rd: *mut XdrRecordDecoder) {
+    if !rd.is_null() {
+        drop(Box::from_raw(rd));
+    }
+}
+
+/// Feed `len` bytes of a record-marked stream to the decoder. Any records they complete are
+/// returned by `xdr_record_decoder_next`. Once this fails the stream framing is lost, and it
+/// keeps returning the same error.
+///
+/// # Safety
+///
+/// `rd` must be a valid record decoder handle, and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_record_decoder_feed(rd: *mut XdrRecordDecoder, data: *const u8, len: usize) -> c_int {
+    let (rd, data) = match (rd.as_mut(), c_slice(data, len)) {
+        (Some(rd), Some(data)) => (rd, data),
+        _ => return XDR_ERR_NULL,
+    };
+
+    match rd.decoder.feed(data) {
+        Ok(recs) => {
+            rd.ready.extend(recs);
+            XDR_OK
+        },
+        Err(ref e) => status(e),
+    }
+}
+
+/// Return the next complete record, storing a pointer to it in `*data` and its length in
+/// `*len`, or `XDR_EMPTY` if there isn't one. The record is valid until the next call to this,
+/// or until the decoder is freed.
+///
+/// # Safety
+///
+/// `rd` must be a valid record decoder handle, and `data` and `len` valid pointers.
+#[no_mangle]
+pub unsafe extern "C" fn xdr_record_decoder_next(rd: *mut XdrRecordDecoder, data: *mut *const u8, len: *mut usize)
+                                                 -> c_int {
+    let (rd, data, len) = match (rd.as_mut(), data.as_mut(), len.as_mut()) {
+        (Some(rd), Some(data), Some(len)) => (rd, data, len),
+        _ => return XDR_ERR_NULL,
+    };
+
+    match rd.ready.pop_front() {
+        Some(rec) => {
+            rd.current = rec;
+            *data = rd.current.as_ptr();
+            *len = rd.current.len();
+            XDR_OK
+        },
+        None => XDR_EMPTY,
+    }
+}
@@ -0,0 +1,126 @@
+//! Fixed-length opaque data
+//!
+//! XDR `opaque[N]` is exactly `N` bytes, padded to a multiple of 4, with no length on the wire.
+//! It's commonly used for file handles, verifiers and cookies. `FixedOpaque<N>` holds it as a
+//! `[u8; N]`, so the length is guaranteed by the type.
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+use std::ops::{Deref, DerefMut};
+
+use error::*;
+use skip::{Skip, skip_opaque_array};
+use super::{Pack, PackedFixedSize, Unpack, pack_opaque_array, padding, unpack_opaque_array};
+
+/// Exactly `N` bytes of opaque data, encoded as `opaque[N]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedOpaque<const N: usize>(pub [u8; N]);
+
+impl<const N: usize> FixedOpaque<N> {
+    /// Wrap an array.
+    pub fn new(bytes: [u8; N]) -> Self {
+        FixedOpaque(bytes)
+    }
+
+    /// Copy from a slice, failing with `InvalidLen` if it isn't exactly `N` bytes.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != N {
+            bail!(ErrorKind::InvalidLen(bytes.len()));
+        }
+        let mut ret = [0; N];
+        ret.copy_from_slice(bytes);
+        Ok(FixedOpaque(ret))
+    }
+
+    /// Return the bytes as a slice.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Return the underlying array.
+    pub fn into_inner(self) -> [u8; N] {
+        self.0
+    }
+}
+
+impl<const N: usize> Default for FixedOpaque<N> {
+    fn default() -> Self {
+        FixedOpaque([0; N])
+    }
+}
+
+impl<const N: usize> Deref for FixedOpaque<N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> DerefMut for FixedOpaque<N> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for FixedOpaque<N> {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for FixedOpaque<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        FixedOpaque(bytes)
+    }
+}
+
+impl<const N: usize> From<FixedOpaque<N>> for [u8; N] {
+    fn from(v: FixedOpaque<N>) -> Self {
+        v.0
+    }
+}
+
+impl<'a, const N: usize> TryFrom<&'a [u8]> for FixedOpaque<N> {
+    type Error = Error;
+
+    fn try_from(bytes: &'a [u8]) -> Result<Self> {
+        FixedOpaque::from_slice(bytes)
+    }
+}
+
+impl<Out: Write, const N: usize> Pack<Out> for FixedOpaque<N> {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_opaque_array(&self.0, N, out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        Some(N + padding(N).len())
+    }
+
+    #[inline]
+    fn is_fixed_size() -> bool {
+        true
+    }
+}
+
+impl<const N: usize> PackedFixedSize for FixedOpaque<N> {
+    const SIZE: usize = (N + 3) & !3;
+}
+
+impl<In: Read, const N: usize> Unpack<In> for FixedOpaque<N> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut bytes = [0; N];
+        let sz = unpack_opaque_array(input, &mut bytes, N)?;
+        Ok((FixedOpaque(bytes), sz))
+    }
+}
+
+impl<In: Read, const N: usize> Skip<In> for FixedOpaque<N> {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> {
+        skip_opaque_array(input, N)
+    }
+}
@@ -0,0 +1,97 @@
+//! Fuzzing entry points
+//!
+//! Ready-made targets for `cargo fuzz` (or any other fuzzer which hands over a byte slice). Each
+//! one feeds the input to a decoder and ignores decode errors, since rejecting malformed input is
+//! the expected outcome; they only panic when the codec itself misbehaves, such as a value which
+//! doesn't survive being packed and unpacked again. Variable-length arrays don't preallocate more
+//! than a bounded amount however large their encoded length, so short inputs can't trigger huge
+//! allocations.
+//!
+//! A target for a generated type is two lines:
+//!
+//! ```ignore
+//! #![no_main]
+//! #[macro_use] extern crate libfuzzer_sys;
+//! extern crate xdr_codec;
+//! extern crate my_protocol;
+//!
+//! fuzz_target!(|data: &[u8]| xdr_codec::fuzz::fuzz_roundtrip::<my_protocol::Request>(data));
+//! ```
+use super::{Pack, Unpack, pack_to_vec, unpack_from_bytes};
+use skip::Skip;
+use borrowed::UnpackRef;
+use record::XdrRecordReader;
+
+/// Largest record `fuzz_records` will assemble.
+const MAX_RECORD: usize = 1024 * 1024;
+
+/// Unpack a `T` from `data`, discarding the result.
+pub fn fuzz_unpack<T>(data: &[u8])
+    where T: for<'a> Unpack<&'a [u8]>
+{
+    if let Ok((_, sz)) = unpack_from_bytes::<T>(data) {
+        assert!(sz <= data.len(), "unpacked {} bytes from {} bytes of input", sz, data.len());
+    }
+}
+
+/// Unpack a `T` from `data` with `UnpackRef`, discarding the result.
+pub fn fuzz_unpack_ref<'a, T: UnpackRef<'a>>(mut data: &'a [u8]) {
+    let len = data.len();
+
+    if let Ok((_, sz)) = T::unpack_ref(&mut data) {
+        assert_eq!(sz + data.len(), len, "consumed size doesn't match input advance");
+    }
+}
+
+/// Unpack a `T` from `data`, and check that it packs to an encoding which unpacks to the same
+/// value with the same size.
+///
+/// The values are compared by packing them again, so that ones which aren't equal to themselves
+/// (such as a NaN float) still count as surviving the round trip.
+pub fn fuzz_roundtrip<T>(data: &[u8])
+    where T: Pack<Vec<u8>> + for<'a> Unpack<&'a [u8]>
+{
+    let (val, _) = match unpack_from_bytes::<T>(data) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    let enc = pack_to_vec(&val).expect("pack of unpacked value failed");
+    let (again, sz) = unpack_from_bytes::<T>(&enc).expect("unpack of packed value failed");
+
+    assert_eq!(sz, enc.len(), "unpack of packed value used {} of {} bytes", sz, enc.len());
+    let again = pack_to_vec(&again).expect("pack of re-unpacked value failed");
+    assert_eq!(enc, again, "value changed in round trip");
+}
+
+/// Check that skipping a `T` in `data` consumes as much as unpacking it.
+pub fn fuzz_skip<T>(data: &[u8])
+    where T: for<'a> Skip<&'a [u8]>
+{
+    let unpacked = unpack_from_bytes::<T>(data).map(|(_, sz)| sz);
+    let skipped = T::skip(&mut &data[..]);
+
+    match (unpacked, skipped) {
+        (Ok(u), Ok(s)) => assert_eq!(u, s, "skip size differs from unpack size"),
+        (Ok(u), Err(e)) => panic!("unpack used {} bytes but skip failed: {}", u, e),
+        // Skip doesn't validate everything unpack does (eg UTF-8), so it may succeed alone
+        (Err(_), _) => (),
+    }
+}
+
+/// Read `data` as a stream of records, unpacking a `T` from each one.
+///
+/// Records are limited to 1MB, so length headers can't cause large allocations.
+pub fn fuzz_records<T>(data: &[u8])
+    where T: for<'a> Unpack<&'a [u8]>
+{
+    let mut rr = XdrRecordReader::new(data);
+    rr.set_max_record(Some(MAX_RECORD));
+
+    for rec in rr {
+        match rec {
+            Ok(rec) => fuzz_unpack::<T>(&rec),
+            Err(_) => break,
+        }
+    }
+}
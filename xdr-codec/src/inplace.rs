@@ -0,0 +1,139 @@
+//! Unpacking into an existing value
+//!
+//! `Unpack` returns a new value each time, so a server decoding a stream of similar messages
+//! allocates fresh `Vec`s and `String`s for every one. The `UnpackInto` trait decodes into a
+//! value which already exists instead, overwriting it while keeping the capacity of any vectors
+//! and strings it owns. `Vec<T>` reuses its existing elements as well, so nested buffers are
+//! kept too. A hand-written implementation unpacks each field into place:
+//!
+//! ```
+//! use xdr_codec::{Read, Result, UnpackInto};
+//!
+//! #[derive(Default)]
+//! struct Msg { id: u32, body: Vec<u32> }
+//!
+//! impl<In: Read> UnpackInto<In> for Msg {
+//!     fn unpack_into(&mut self, input: &mut In) -> Result<usize> {
+//!         Ok(self.id.unpack_into(input)? + self.body.unpack_into(input)?)
+//!     }
+//! }
+//!
+//! let mut msg = Msg::default();
+//! msg.unpack_into(&mut &[0, 0, 0, 1,  0, 0, 0, 2,  0, 0, 0, 5,  0, 0, 0, 6][..]).unwrap();
+//! assert_eq!((msg.id, &msg.body[..]), (1, &[5, 6][..]));
+//!
+//! let cap = msg.body.capacity();
+//! msg.unpack_into(&mut &[0, 0, 0, 2,  0, 0, 0, 1,  0, 0, 0, 7][..]).unwrap();
+//! assert_eq!((msg.id, &msg.body[..]), (2, &[7][..]));
+//! assert_eq!(msg.body.capacity(), cap);
+//! ```
+//!
+//! If unpacking fails, the value is left in a valid but unspecified state.
+use std::borrow::Cow;
+use std::io::Read;
+use std::mem;
+
+use error::*;
+use super::{Unpack, Opaque, check_elems, options, prealloc, unpack_padding, unpack_opaque_flex_into};
+
+/// Unpack a value in place, reusing its allocations.
+///
+/// Implementations must leave `self` equal to what `Unpack::unpack` would have returned for the
+/// same input.
+pub trait UnpackInto<In: Read> {
+    /// Unpack from `input` into `self`, returning the number of bytes consumed.
+    fn unpack_into(&mut self, input: &mut In) -> Result<usize>;
+}
+
+macro_rules! unpack_replace {
+    ($($(#[$attr:meta])* $ty:ty),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            impl<In: Read> UnpackInto<In> for $ty {
+                #[inline]
+                fn unpack_into(&mut self, input: &mut In) -> Result<usize> {
+                    let (v, sz) = Self::unpack(input)?;
+                    *self = v;
+                    Ok(sz)
+                }
+            }
+        )*
+    }
+}
+
+unpack_replace! {
+    #[cfg(feature = "bytecodec")] u8,
+    #[cfg(feature = "bytecodec")] i8,
+    u32, i32, u64, i64,
+    #[cfg(feature = "hyper128")] u128,
+    #[cfg(feature = "hyper128")] i128,
+    f32, f64, bool, usize, (),
+}
+
+/// Elements already in the vector are unpacked into; any extra ones are dropped, and missing ones
+/// are unpacked into `T::default()`.
+impl<In: Read, T: UnpackInto<In> + Default> UnpackInto<In> for Vec<T> {
+    fn unpack_into(&mut self, input: &mut In) -> Result<usize> {
+        let (elems, mut sz) = usize::unpack(input)?;
+
+        check_elems::<T>(elems)?;
+        self.truncate(elems);
+        for e in self.iter_mut() {
+            sz += e.unpack_into(input)?;
+        }
+        self.reserve(prealloc::<T>(elems - self.len()));
+        while self.len() < elems {
+            let mut e = T::default();
+            sz += e.unpack_into(input)?;
+            self.push(e);
+        }
+        sz += unpack_padding(input, sz)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> UnpackInto<In> for String {
+    fn unpack_into(&mut self, input: &mut In) -> Result<usize> {
+        let mut v = mem::take(self).into_bytes();
+        v.clear();
+        let sz = unpack_opaque_flex_into(input, &mut v, None)?;
+        *self = options::utf8_string(v)?;
+        Ok(sz)
+    }
+}
+
+/// The buffer is reused if the current value is `Cow::Owned`; the result is always owned.
+impl<In: Read> UnpackInto<In> for Opaque<'static> {
+    fn unpack_into(&mut self, input: &mut In) -> Result<usize> {
+        let mut v = match mem::take(&mut self.0) {
+            Cow::Owned(v) => v,
+            Cow::Borrowed(_) => Vec::new(),
+        };
+        v.clear();
+        let sz = unpack_opaque_flex_into(input, &mut v, None)?;
+        self.0 = Cow::Owned(v);
+        Ok(sz)
+    }
+}
+
+/// A present value is unpacked into the existing one if there is one, or into `T::default()`.
+impl<In: Read, T: UnpackInto<In> + Default> UnpackInto<In> for Option<T> {
+    fn unpack_into(&mut self, input: &mut In) -> Result<usize> {
+        let (have, mut sz) = bool::unpack(input)?;
+
+        if have {
+            sz += self.get_or_insert_with(T::default).unpack_into(input)?;
+        } else {
+            *self = None;
+        }
+        Ok(sz)
+    }
+}
+
+impl<In: Read, T: UnpackInto<In> + ?Sized> UnpackInto<In> for Box<T> {
+    #[inline]
+    fn unpack_into(&mut self, input: &mut In) -> Result<usize> {
+        (**self).unpack_into(input)
+    }
+}
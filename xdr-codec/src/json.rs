@@ -0,0 +1,245 @@
+//! JSON conversion for `Value`
+//!
+//! `Value::to_json` converts a decoded value to a `serde_json::Value` for logging, diffing and
+//! post-processing with JSON tools:
+//!
+//! - numbers and bools map directly; NaN and infinite floats become `null`
+//! - `quadruple` is converted to the nearest `f64`
+//! - enums become the name of their variant
+//! - opaque data becomes a string of hex digits
+//! - arrays become arrays, and structs become objects keyed by field name
+//! - optional values are `null` or the value itself
+//! - unions become `{"discriminant": ..., "arm": ...}`
+//! - `void` is `null`
+//!
+//! `Schema::value_from_json` goes the other way, using the type to resolve the ambiguities. It
+//! also accepts enums as numbers, opaque data as an array of byte values, and `null` for floats
+//! (as NaN). Quadruples round-trip through `f64`, so may lose precision.
+//!
+//! This module is only available with the `json` feature.
+use serde_json::{self, Map, Number};
+
+use error::*;
+use quadruple::XdrQuadruple;
+use value::{Value, Type, Schema};
+
+type Json = serde_json::Value;
+
+fn float(v: f64) -> Json {
+    Number::from_f64(v).map_or(Json::Null, Json::Number)
+}
+
+fn hex(v: &[u8]) -> Json {
+    Json::String(v.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+impl Value {
+    /// Convert to JSON. See the `json` module for the mapping.
+    pub fn to_json(&self) -> Json {
+        match *self {
+            Value::Void => Json::Null,
+            Value::Int(v) => Json::from(v),
+            Value::UInt(v) => Json::from(v),
+            Value::Hyper(v) => Json::from(v),
+            Value::UHyper(v) => Json::from(v),
+            Value::Float(v) => float(v as f64),
+            Value::Double(v) => float(v),
+            Value::Quadruple(v) => float(v.into()),
+            Value::Bool(v) => Json::Bool(v),
+            Value::Enum { ref name, .. } => Json::String(name.clone()),
+            Value::FixedOpaque(ref v) | Value::Opaque(ref v) => hex(v),
+            Value::String(ref v) => Json::String(v.clone()),
+            Value::FixedArray(ref v) | Value::Array(ref v) => Json::Array(v.iter().map(Value::to_json).collect()),
+            Value::Optional(None) => Json::Null,
+            Value::Optional(Some(ref v)) => v.to_json(),
+            Value::Struct(ref fields) => {
+                Json::Object(fields.iter().map(|(n, v)| (n.clone(), v.to_json())).collect())
+            }
+            Value::Union { ref discriminant, ref arm } => {
+                let mut obj = Map::new();
+                obj.insert("discriminant".to_string(), discriminant.to_json());
+                obj.insert("arm".to_string(), arm.to_json());
+                Json::Object(obj)
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a Value> for Json {
+    fn from(v: &'a Value) -> Json {
+        v.to_json()
+    }
+}
+
+fn mismatch<T>(what: &str, json: &Json) -> Result<T> {
+    bail!(ErrorKind::InvalidValue(format!("expected {}, got {}", what, json)))
+}
+
+fn int<T>(json: &Json, what: &str) -> Result<T>
+    where T: ::std::convert::TryFrom<i64> + ::std::convert::TryFrom<u64>
+{
+    let v = match (json.as_i64(), json.as_u64()) {
+        (Some(v), _) => T::try_from(v).ok(),
+        (None, Some(v)) => T::try_from(v).ok(),
+        _ => None,
+    };
+
+    match v {
+        Some(v) => Ok(v),
+        None => mismatch(what, json),
+    }
+}
+
+fn double(json: &Json) -> Result<f64> {
+    match *json {
+        Json::Null => Ok(f64::NAN),
+        ref j => match j.as_f64() {
+            Some(v) => Ok(v),
+            None => mismatch("number", json),
+        },
+    }
+}
+
+fn bytes(json: &Json) -> Result<Vec<u8>> {
+    match *json {
+        Json::String(ref s) if s.len() % 2 == 0 => {
+            let v: Option<Vec<u8>> = (0..s.len())
+                .step_by(2)
+                .map(|i| s.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+                .collect();
+            match v {
+                Some(v) => Ok(v),
+                None => mismatch("hex string", json),
+            }
+        }
+        Json::Array(ref a) => a.iter().map(|b| int::<u8>(b, "byte value")).collect(),
+        _ => mismatch("hex string", json),
+    }
+}
+
+fn check_len(len: usize, max: Option<usize>) -> Result<()> {
+    match max {
+        Some(m) if len > m => bail!(ErrorKind::InvalidLen(m)),
+        _ => Ok(()),
+    }
+}
+
+impl Schema {
+    /// Convert JSON to a `Value` of type `ty`. See the `json` module for the accepted forms.
+    ///
+    /// Fails with `InvalidValue` if the JSON doesn't have the shape of the type, and with the
+    /// same errors as `unpack` for invalid lengths, enum values and union discriminants.
+    pub fn value_from_json(&self, ty: &Type, json: &Json) -> Result<Value> {
+        let v = match *self.resolve(ty)? {
+            Type::Void => Value::Void,
+            Type::Int => Value::Int(int(json, "int")?),
+            Type::UInt => Value::UInt(int(json, "unsigned int")?),
+            Type::Hyper => Value::Hyper(int(json, "hyper")?),
+            Type::UHyper => Value::UHyper(int(json, "unsigned hyper")?),
+            Type::Float => Value::Float(double(json)? as f32),
+            Type::Double => Value::Double(double(json)?),
+            Type::Quadruple => Value::Quadruple(XdrQuadruple::from(double(json)?)),
+            Type::Bool => match json.as_bool() {
+                Some(v) => Value::Bool(v),
+                None => return mismatch("bool", json),
+            },
+            Type::Enum(ref variants) => {
+                let found = match *json {
+                    Json::String(ref s) => variants.iter().find(|v| v.0 == *s),
+                    ref j => {
+                        let n = int::<i32>(j, "enum name or value")?;
+                        match variants.iter().find(|v| v.1 == n) {
+                            None => return Err(Error::invalidenum(n)),
+                            found => found,
+                        }
+                    }
+                };
+                match found {
+                    Some((name, value)) => Value::Enum { name: name.clone(), value: *value },
+                    None => return mismatch("enum name", json),
+                }
+            }
+            Type::FixedOpaque(n) => {
+                let v = bytes(json)?;
+                if v.len() != n {
+                    bail!(ErrorKind::InvalidLen(v.len()));
+                }
+                Value::FixedOpaque(v)
+            }
+            Type::Opaque(max) => {
+                let v = bytes(json)?;
+                check_len(v.len(), max)?;
+                Value::Opaque(v)
+            }
+            Type::String(max) => match *json {
+                Json::String(ref s) => {
+                    check_len(s.len(), max)?;
+                    Value::String(s.clone())
+                }
+                _ => return mismatch("string", json),
+            },
+            Type::FixedArray(ref elem, n) => {
+                let v = self.json_elems(elem, json)?;
+                if v.len() != n {
+                    bail!(ErrorKind::InvalidLen(v.len()));
+                }
+                Value::FixedArray(v)
+            }
+            Type::Array(ref elem, max) => {
+                let v = self.json_elems(elem, json)?;
+                check_len(v.len(), max)?;
+                Value::Array(v)
+            }
+            Type::Optional(ref t) => match *json {
+                Json::Null => Value::Optional(None),
+                ref j => Value::Optional(Some(Box::new(self.value_from_json(t, j)?))),
+            },
+            Type::Struct(ref fields) => {
+                let obj = match *json {
+                    Json::Object(ref obj) => obj,
+                    _ => return mismatch("object", json),
+                };
+                let mut v = Vec::with_capacity(fields.len());
+                for (name, t) in fields {
+                    // Missing fields are only OK if they can be null
+                    let f = obj.get(name).unwrap_or(&Json::Null);
+                    v.push((name.clone(), self.value_from_json(t, f)?));
+                }
+                Value::Struct(v)
+            }
+            Type::Union { ref discriminant, ref cases, ref default } => {
+                let (d, a) = match *json {
+                    Json::Object(ref obj) => (obj.get("discriminant"), obj.get("arm")),
+                    _ => return mismatch("union object", json),
+                };
+                let disc = match d {
+                    Some(d) => self.value_from_json(discriminant, d)?,
+                    None => return mismatch("union discriminant", json),
+                };
+                let dv = match disc.as_i64() {
+                    Some(v) => v as i32,
+                    None => bail!(ErrorKind::InvalidSchema("union discriminant must be int, unsigned int, bool or enum".to_string())),
+                };
+                let arm = match cases.iter().find(|c| c.0 == dv) {
+                    Some((_, t)) => t,
+                    None => match *default {
+                        Some(ref t) => t,
+                        None => return Err(Error::invalidcase(dv)),
+                    },
+                };
+                let arm = self.value_from_json(arm, a.unwrap_or(&Json::Null))?;
+                Value::Union { discriminant: Box::new(disc), arm: Box::new(arm) }
+            }
+            Type::Named(_) => unreachable!("resolve returned a named type"),
+        };
+
+        Ok(v)
+    }
+
+    fn json_elems(&self, elem: &Type, json: &Json) -> Result<Vec<Value>> {
+        match *json {
+            Json::Array(ref a) => a.iter().map(|j| self.value_from_json(elem, j)).collect(),
+            _ => mismatch("array", json),
+        }
+    }
+}
@@ -17,21 +17,192 @@
 //! However, some protocols are mis-specified to use byte arrays (I'm looking at
 //! you, gluster), so the option to support the exists. You can enable byte codec
 //! with the `bytecodec` feature.
+//!
+//! Similarly, XDR has no 128-bit integer type, but some protocols extend it with one. The
+//! `hyper128` feature implements `Pack` and `Unpack` for `u128` and `i128`, encoded as a pair of
+//! hypers (most significant first). This is not part of RFC4506.
+//!
+//! The `little_endian` feature allows little-endian variants of XDR to be packed and unpacked,
+//! by wrapping values in `endian::Le`.
+//!
+//! With the `derive` feature, `#[derive(XdrPack, XdrUnpack)]` implements `Pack` and `Unpack` for
+//! hand-written structs, packing the fields in declaration order, and `#[derive(XdrFixedSize)]`
+//! implements `PackedFixedSize`. `#[derive(XdrReflect)]` describes a struct's XDR type, so its
+//! `.x` definition can be generated with `reflect::spec_for`. See `xdr-codec-derive` for the
+//! field attributes they accept.
+//!
+//! The `quickcheck` feature implements `quickcheck::Arbitrary` for this crate's wrapper types and
+//! adds round-trip property test helpers, in the `arbitrary` module. The `json` feature converts
+//! dynamically typed `Value`s to and from `serde_json::Value`. The `bumpalo` feature adds
+//! functions in the `arena` module to unpack arrays, opaques and strings into a `bumpalo` arena,
+//! and to decode `Value`s as arena allocated `XdrValueRef`s which borrow from the input. The
+//! `smallvec` feature adds the `small` module for unpacking short opaques without a heap
+//! allocation. The `heapless` feature implements `Pack` and `Unpack` for `heapless::Vec` and
+//! `heapless::String`, in the `capped` module, so bounded arrays and strings can be decoded into
+//! fixed-capacity containers.
+//!
+//! The `testvectors` feature adds a table of canonical encodings from RFC4506 and the C
+//! `rpcgen` runtime, and `testvectors::check` to test a type against the ones for its
+//! declaration.
+//!
+//! The `ffi` feature exports a C interface to the basic encoders, decoders and record framing,
+//! in the `ffi` module, for building this crate as a C library.
+//!
+//! The `pipeline` feature adds `pipeline::EncodePipeline`, which packs values on worker threads
+//! and writes them in order from a single IO thread.
+//!
+//! With the `tracing` feature, record framing (fragments and record boundaries, as written and
+//! read) and decode failures in `XdrReader`, `unpack_from_bytes` and `verify_canonical` are
+//! reported as `tracing` events, at `trace` and `debug` level respectively.
+//!
+//! The crate works entirely on in-memory buffers where there's no operating system IO, such as
+//! on `wasm32-unknown-unknown`; see the `buffer` module.
+//!
+//! The `prelude` module lists the items generated code depends on, which are kept stable across
+//! minor releases, and `tests/public-api.txt` is a snapshot of the whole public API which the tests
+//! check, so any change to it is deliberate. Traits which are meant to be used but not
+//! implemented downstream (`XdrPrimitive` and `ErasedPack`) are sealed, so they can be extended
+//! without breaking anyone.
 #![crate_type = "lib"]
 
 extern crate byteorder;
-#[macro_use] extern crate error_chain;
+#[cfg(feature = "bytes")]
+extern crate bytes;
+#[cfg(feature = "futures")]
+extern crate futures_io;
+#[cfg(feature = "futures")]
+extern crate futures_core;
+#[cfg(feature = "futures")]
+extern crate futures_sink;
+#[cfg(feature = "deflate")]
+extern crate flate2;
+#[cfg(feature = "derive")]
+extern crate xdr_codec_derive;
+#[cfg(feature = "quickcheck")]
+extern crate quickcheck;
+#[cfg(feature = "json")]
+extern crate serde_json;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "bumpalo")]
+extern crate bumpalo;
+#[cfg(feature = "smallvec")]
+extern crate smallvec;
+#[cfg(feature = "heapless")]
+extern crate heapless;
 
 pub use std::io::{Write, Read};
+use std::io::{self, IoSlice};
 use std::ops::Deref;
-use std::cmp::min;
+use std::cmp::{min, max};
+use std::mem;
 use std::borrow::{Cow, Borrow};
-use byteorder::{BigEndian, WriteBytesExt, ReadBytesExt};
+use std::convert::TryFrom;
+use std::collections::{VecDeque, BTreeSet, HashSet};
+use std::hash::{Hash, BuildHasher};
+use std::rc::Rc;
+use std::sync::Arc;
+use byteorder::WriteBytesExt;
+
+// Return early with an error
+macro_rules! bail {
+    ($e:expr) => { return Err($e.into()) };
+}
+
+// Emit a `tracing` event at `$level`, if the `tracing` feature is enabled.
+#[cfg(feature = "tracing")]
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)*) => { ::tracing::$level!($($arg)*) }
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)*) => { () }
+}
 
 pub mod record;
+pub mod time;
+pub mod net;
+pub mod os;
+pub mod quadruple;
+pub mod stream;
+pub mod skip;
+pub mod erased;
+pub mod context;
+pub mod inplace;
+pub mod borrowed;
+pub mod bounded;
+pub mod fixed;
+pub mod string;
+pub mod decoder;
+pub mod buffer;
+pub mod tee;
+pub mod transcode;
+pub mod container;
+pub mod reflect;
+#[macro_use]
+pub mod enums;
+pub mod union;
+pub mod options;
+pub mod endian;
+pub mod fuzz;
+pub mod testing;
+pub mod dump;
+pub mod value;
+pub mod prelude;
+#[cfg(feature = "sunrpc_types")]
+pub mod sunrpc_types;
+#[cfg(feature = "nfs3")]
+pub mod nfs3;
+#[cfg(feature = "nfs4")]
+pub mod nfs4;
+#[cfg(feature = "mount")]
+pub mod mount;
+#[cfg(feature = "nlm")]
+pub mod nlm;
+#[cfg(feature = "nsm")]
+pub mod nsm;
+#[cfg(feature = "libvirt")]
+pub mod libvirt;
+#[cfg(feature = "ndmp")]
+pub mod ndmp;
+#[cfg(feature = "nvlist")]
+pub mod nvlist;
+#[cfg(feature = "bytes")]
+pub mod buf;
+#[cfg(feature = "futures")]
+pub mod async_record;
+#[cfg(feature = "deflate")]
+pub mod deflate;
+#[cfg(feature = "quickcheck")]
+pub mod arbitrary;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "bumpalo")]
+pub mod arena;
+#[cfg(feature = "smallvec")]
+pub mod small;
+#[cfg(feature = "heapless")]
+pub mod capped;
+#[cfg(feature = "testvectors")]
+pub mod testvectors;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "pipeline")]
+pub mod pipeline;
 
 mod error;
+mod crc;
 pub use error::*;
+pub use skip::{Skip, skip};
+pub use erased::ErasedPack;
+pub use context::UnpackWith;
+pub use inplace::UnpackInto;
+pub use enums::XdrEnum;
+pub use options::{DecodeOptions, decode_options, with_decode_options, with_input_limit};
+pub use value::Value;
+#[cfg(feature = "derive")]
+pub use xdr_codec_derive::{XdrFixedSize, XdrPack, XdrReflect, XdrUnpack};
 
 #[cfg(test)]
 mod test;
@@ -47,6 +218,53 @@ pub fn padding(sz: usize) -> &'static [u8] {
     &PADDING[..(4 - (sz % 4)) % 4]
 }
 
+// Read and discard the padding following `sz` bytes of data, returning its size.
+#[inline]
+fn unpack_padding<In: Read>(input: &mut In, sz: usize) -> Result<usize> {
+    let mut buf = [0; 4];
+    let p = &mut buf[..padding(sz).len()];
+    input.read_exact(p)?;
+    options::consume_input(p.len());
+    Ok(p.len())
+}
+
+/// Convert a host length into an XDR count.
+///
+/// XDR lengths and array counts are unsigned 32-bit values. This fails with `InvalidLen` if `len`
+/// can't be represented on the wire, rather than silently truncating it.
+#[inline]
+pub fn xdr_len(len: usize) -> Result<u32> {
+    xdr_len_from(len as u64).map_err(|_| Error::invalidlen(len))
+}
+
+/// Convert an XDR count into a host length.
+///
+/// This fails with `InvalidLen` if `len` can't be represented as a `usize` on this target (ie,
+/// 16-bit targets, or in principle any target where `usize` is narrower than 32 bits).
+#[inline]
+pub fn host_len(len: u32) -> Result<usize> {
+    host_len_max(len, usize::MAX as u64).map(|v| v as usize)
+}
+
+// Width-independent implementations of `xdr_len` and `host_len`, so that the edge cases for
+// narrow targets can be exercised on any host.
+#[inline]
+fn xdr_len_from(len: u64) -> Result<u32> {
+    if len > u32::MAX as u64 {
+        bail!(ErrorKind::InvalidLen(min(len, usize::MAX as u64) as usize));
+    }
+    Ok(len as u32)
+}
+
+#[inline]
+fn host_len_max(len: u32, max: u64) -> Result<u64> {
+    if len as u64 > max {
+        // The length doesn't fit, so report the largest one we could have handled
+        bail!(ErrorKind::InvalidLen(max as usize));
+    }
+    Ok(len as u64)
+}
+
 /// Wrapper for XDR opaque data.
 ///
 /// In XDR terms, "opaque data" is a plain array of bytes, packed as tightly as possible, and then
@@ -57,6 +275,7 @@ pub struct Opaque<'a>(pub Cow<'a, [u8]>);
 
 impl<'a> Opaque<'a> {
     pub fn owned(v: Vec<u8>) -> Opaque<'a> { Opaque(Cow::Owned(v)) }
+    #[inline]
     pub fn borrowed(v: &'a [u8]) -> Opaque<'a> { Opaque(Cow::Borrowed(v)) }
 }
 
@@ -76,21 +295,40 @@ pub fn pack<Out: Write, T: Pack<Out>>(val: &T, out: &mut Out) -> Result<()> {
     val.pack(out).map(|_| ())
 }
 
+/// Pack a value into a newly allocated `Vec<u8>`.
+#[inline]
+pub fn pack_to_vec<T: ?Sized + Pack<Vec<u8>>>(val: &T) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(val.packed_size_hint().unwrap_or(0));
+    val.pack(&mut out)?;
+    Ok(out)
+}
+
+/// Compute the encoded size of a value without keeping the encoding.
+///
+/// This packs the value into a `CountingWriter`, so it still fails if the value can't be
+/// encoded (for example, if an array exceeds its size limit).
+#[inline]
+pub fn packed_len<T: ?Sized + Pack<stream::CountingWriter>>(val: &T) -> Result<usize> {
+    let mut out = stream::CountingWriter::new();
+    val.pack(&mut out)?;
+    usize::try_from(out.count()).map_err(|_| Error::invalidlen(usize::MAX))
+}
+
 /// Pack a fixed-size array.
 ///
 /// As the size is fixed, it doesn't need to be encoded. `sz` is in units of array elements.
 /// If the `val` is too large, it is truncated; it is too small, then the array is padded out with
 /// default values (if provided). If the array is too small and there's no pad/default value, then it fails
 /// with `Error::InvalidLen`.
+///
+/// A zero-sized array packs to nothing, and never needs a default. Elements which themselves pack to
+/// nothing (such as `()`, or empty structs) are allowed.
 pub fn pack_array<Out, T>(val: &[T], sz: usize, out: &mut Out, defl: Option<&T>) -> Result<usize>
     where Out: Write, T: Pack<Out>
 {
-    let mut vsz = 0;
     let val = &val[..min(sz, val.len())];
 
-    for v in val {
-        vsz += v.pack(out)?;
-    }
+    let mut vsz = T::pack_slice(val, out)?;
     assert!(vsz % 4 == 0);
 
     if val.len() < sz {
@@ -108,7 +346,9 @@ pub fn pack_array<Out, T>(val: &[T], sz: usize, out: &mut Out, defl: Option<&T>)
 /// Pack a fixed-size byte array
 ///
 /// As size is fixed, it doesn't need to be encoded. `sz` is in bytes (and array elements, which are u8)
-/// If the array is too large, it is truncated; if its too small its padded with `0x00`.
+/// If the array is too large, it is truncated; if its too small its padded with `0x00`. A zero-sized
+/// array packs to nothing.
+#[inline]
 pub fn pack_opaque_array<Out: Write>(val: &[u8], sz: usize, out: &mut Out) -> Result<usize> {
     let mut vsz;
     let val = &val[..min(sz, val.len())];
@@ -137,6 +377,27 @@ pub fn pack_flex<Out: Write, T: Pack<Out>>(val: &[T], maxsz: Option<usize>, out:
     val.pack(out)
 }
 
+/// Pack a linked list, with size limit check.
+///
+/// XDR specifications often express a list as a struct with an optional pointer to the next
+/// element (`struct entry { ...; entry *next; }`). That encodes as each element preceded by
+/// `TRUE`, then a final `FALSE`, which is how this packs `val`. `T` is the element without the
+/// link.
+pub fn pack_list<Out: Write, T: Pack<Out>>(val: &[T], maxsz: Option<usize>, out: &mut Out) -> Result<usize> {
+    if maxsz.map_or(false, |m| val.len() > m) {
+        bail!(ErrorKind::InvalidLen(maxsz.unwrap()));
+    }
+
+    let mut sz = 0;
+    for v in val {
+        sz += true.pack(out)?;
+        sz += v.pack(out)?;
+    }
+    sz += false.pack(out)?;
+
+    Ok(sz)
+}
+
 /// Pack a dynamically sized opaque array, with size limit check.
 ///
 /// This packs an array of packable objects, and also applies an optional size limit.
@@ -149,6 +410,27 @@ pub fn pack_opaque_flex<Out: Write>(val: &[u8], maxsz: Option<usize>, out: &mut
     Opaque::borrowed(val).pack(out)
 }
 
+/// Pack opaque data of length `len`, streaming it from `input`.
+///
+/// This writes the length, then copies the data from `input` to `out` through a small buffer,
+/// then adds the padding, so large payloads don't need to be held in memory. Exactly `len` bytes
+/// are consumed from `input`; if it ends early, this fails with an `UnexpectedEof` IO error after
+/// having written a partial encoding to `out`.
+pub fn pack_opaque_from_reader<In: Read, Out: Write>(len: usize, input: &mut In, out: &mut Out) -> Result<usize> {
+    let mut sz = xdr_len(len)?.pack(out)?;
+
+    let copied = io::copy(&mut input.take(len as u64), out)?;
+    if copied < len as u64 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short opaque data").into());
+    }
+    sz += len;
+
+    let p = padding(len);
+    out.write_all(p)?;
+
+    Ok(sz + p.len())
+}
+
 /// Pack a string with size limit check.
 #[inline]
 pub fn pack_string<Out: Write>(val: &str, maxsz: Option<usize>, out: &mut Out) -> Result<usize> {
@@ -162,7 +444,8 @@ pub fn pack_string<Out: Write>(val: &str, maxsz: Option<usize>, out: &mut Out) -
 /// default value (if provided); if it is too small, the excess elements are discarded.
 ///
 /// If the provided array is too large and there is no default, then decoding fails with an `InvalidLen` error.
-/// All the elements in `array` will be initialized after a successful return.
+/// All the elements in `array` will be initialized after a successful return. A zero-sized wire array
+/// consumes nothing from the input.
 pub fn unpack_array<In, T>(input: &mut In, array: &mut [T], arraysz: usize, defl: Option<&T>) -> Result<usize>
     where In: Read, T: Unpack<In> + Clone
 {
@@ -198,62 +481,157 @@ pub fn unpack_array<In, T>(input: &mut In, array: &mut [T], arraysz: usize, defl
     Ok(rsz)
 }
 
+/// Unpack a fixed-sized array into a Rust array
+///
+/// This is the same as `unpack_array` with an exact size of `N`, but returns the elements as a
+/// `[T; N]` rather than filling in a slice, so it needs neither a default element nor any heap
+/// allocation. It's intended for small arrays whose size is known at compile time.
+pub fn unpack_fixed_array<In, T, const N: usize>(input: &mut In) -> Result<([T; N], usize)>
+    where In: Read, T: Unpack<In>
+{
+    let mut elems: [Option<T>; N] = std::array::from_fn(|_| None);
+    let mut sz = 0;
+
+    for elem in &mut elems {
+        let (v, esz) = T::unpack(input)?;
+        *elem = Some(v);
+        sz += esz;
+    }
+
+    Ok((elems.map(|e| e.unwrap()), sz))
+}
+
 /// Unpack a fixed-sized opaque array
 ///
 /// Unpack a fixed-size array of raw bytes. The results are placed in `bytes`, but the actual wire-size of
 /// the array is `bytesz`. If the supplied `bytes` is too large, the remainer is filled in with 0x00;
 /// if it is too small, the excess elements are discarded.
 ///
-/// All the bytes in `bytes` will be initialized after a successful call.
+/// All the bytes in `bytes` will be initialized after a successful call. Exactly `bytesz` bytes
+/// plus padding are consumed from `input`, regardless of the size of `bytes`; in particular a
+/// zero-sized array consumes nothing.
 pub fn unpack_opaque_array<In: Read>(input: &mut In, bytes: &mut [u8], bytesz: usize) -> Result<usize> {
     let sz = min(bytesz, bytes.len());
-    let mut rsz = 0;
 
-    while rsz < sz {
-        let r = input.read(&mut bytes[rsz..])?;
-        rsz += r;
-    }
+    options::check_input_len(bytesz)?;
+    input.read_exact(&mut bytes[..sz])?;
 
     // Fill in excess
-    if sz < bytes.len() {
-        for b in &mut bytes[sz..] {
-            *b = 0;
-        }
+    for b in &mut bytes[sz..] {
+        *b = 0;
     }
 
     // Mop up unused data on the wire and padding
-    let p = padding(bytesz).len();
-    if bytes.len() < bytesz + p {
-        for _ in bytes.len()..(bytesz + p) {
-            let _ = input.read_u8()?;
-            rsz += 1;
-        }
+    let excess = (bytesz - sz + padding(bytesz).len()) as u64;
+    if excess > 0 && io::copy(&mut input.take(excess), &mut io::sink())? != excess {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short opaque array").into());
     }
+    options::consume_input(sz + excess as usize);
 
-    Ok(rsz)
+    Ok(sz + excess as usize)
+}
+
+/// Most memory to reserve up front for a variable-length array.
+///
+/// The element count comes from the input, so it can't be trusted to size an allocation; beyond
+/// this the array grows as elements are actually decoded.
+const MAX_PREALLOC: usize = 64 * 1024;
+
+#[inline]
+fn prealloc<T>(elems: usize) -> usize {
+    min(elems, MAX_PREALLOC / max(mem::size_of::<T>(), 1))
+}
+
+// Check an array of `elems` elements could fit in the input. Every XDR type other than void
+// takes at least a word.
+#[inline]
+pub(crate) fn check_elems<T>(elems: usize) -> Result<()> {
+    let elemsz = if mem::size_of::<T>() == 0 { 0 } else { 4 };
+    options::check_input_len(elems.saturating_mul(elemsz))
 }
 
 /// Unpack a (perhaps) length-limited array
 pub fn unpack_flex<In: Read, T: Unpack<In>>(input: &mut In, maxsz: Option<usize>) -> Result<(Vec<T>, usize)> {
-    let (elems, mut sz) = Unpack::unpack(input)?;
+    let mut out = Vec::new();
+    let sz = unpack_flex_into(input, &mut out, maxsz)?;
+
+    Ok((out, sz))
+}
+
+/// Unpack a (perhaps) length-limited array, appending the elements to `out`
+///
+/// This is the same as `unpack_flex`, but uses a vector supplied by the caller so its allocation
+/// can be reused from one message to the next. The elements are appended to whatever `out`
+/// already contains; clear it first to replace them. `maxsz` limits the number of elements
+/// decoded, not the final length of `out`. If this fails, `out` may have had some of the elements
+/// appended.
+pub fn unpack_flex_into<In: Read, T: Unpack<In>>(input: &mut In, out: &mut Vec<T>, maxsz: Option<usize>) -> Result<usize> {
+    let (elems, mut sz) = usize::unpack(input)?;
 
     if maxsz.map_or(false, |m| elems > m) {
         bail!(ErrorKind::InvalidLen(maxsz.unwrap()));
     }
 
-    let mut out = Vec::with_capacity(elems);
+    check_elems::<T>(elems)?;
+    out.reserve(prealloc::<T>(elems));
+
+    sz += T::unpack_vec(input, elems, out)?;
+    sz += unpack_padding(input, sz)?;
+
+    Ok(sz)
+}
+
+/// Unpack a (perhaps) length-limited array into any collection
+///
+/// This is the same as `unpack_flex`, but the elements are added to any collection implementing
+/// `Extend` rather than a `Vec`. For sets, duplicate elements on the wire are merged.
+pub fn unpack_flex_extend<In, T, C>(input: &mut In, maxsz: Option<usize>) -> Result<(C, usize)>
+    where In: Read, T: Unpack<In>, C: Default + Extend<T>
+{
+    let (elems, mut sz) = Unpack::unpack(input)?;
+
+    if let Some(m) = maxsz {
+        if elems > m {
+            bail!(ErrorKind::InvalidLen(m));
+        }
+    }
+
+    check_elems::<T>(elems)?;
+    let mut out = C::default();
 
     for _ in 0..elems {
         let (e, esz) = Unpack::unpack(input)?;
-        out.push(e);
+        out.extend(Some(e));
         sz += esz;
     }
 
-    let p = padding(sz);
-    for _ in 0..p.len() {
-        let _ = input.read_u8()?;
+    sz += unpack_padding(input, sz)?;
+
+    Ok((out, sz))
+}
+
+/// Unpack a (perhaps) length-limited linked list
+///
+/// This is the inverse of `pack_list`: elements are read while they are preceded by `TRUE`. The
+/// length isn't known in advance, so the limit is checked as each element is read.
+pub fn unpack_list<In: Read, T: Unpack<In>>(input: &mut In, maxsz: Option<usize>) -> Result<(Vec<T>, usize)> {
+    let mut out = Vec::new();
+    let mut sz = 0;
+
+    loop {
+        let (more, msz) = bool::unpack(input)?;
+        sz += msz;
+        if !more {
+            break;
+        }
+        if maxsz.map_or(false, |m| out.len() >= m) {
+            bail!(ErrorKind::InvalidLen(maxsz.unwrap()));
+        }
+
+        let (e, esz) = Unpack::unpack(input)?;
+        out.push(e);
+        sz += esz;
     }
-    sz += p.len();
 
     Ok((out, sz))
 }
@@ -262,30 +640,137 @@ pub fn unpack_flex<In: Read, T: Unpack<In>>(input: &mut In, maxsz: Option<usize>
 ///
 /// Unpack an XDR encoded array of bytes, with an optional maximum length.
 pub fn unpack_opaque_flex<In: Read>(input: &mut In, maxsz: Option<usize>) -> Result<(Vec<u8>, usize)> {
-    let (elems, mut sz) = Unpack::unpack(input)?;
+    let mut out = Vec::new();
+    let sz = unpack_opaque_flex_into(input, &mut out, maxsz)?;
+
+    Ok((out, sz))
+}
+
+/// Unpack a (perhaps) length-limited opaque array, appending the bytes to `out`
+///
+/// This is the opaque counterpart of `unpack_flex_into`: the data is appended to `out`, which can
+/// be cleared and reused to avoid allocating a new buffer for every message.
+pub fn unpack_opaque_flex_into<In: Read>(input: &mut In, out: &mut Vec<u8>, maxsz: Option<usize>) -> Result<usize> {
+    let (elems, mut sz) = usize::unpack(input)?;
 
     if maxsz.map_or(false, |m| elems > m) {
         bail!(ErrorKind::InvalidLen(maxsz.unwrap()));
     }
 
-    let mut out = Vec::with_capacity(elems);
+    sz += read_opaque(input, elems, out)?;
+    sz += unpack_padding(input, sz)?;
 
-    sz += input.take(elems as u64).read_to_end(&mut out)?;
+    Ok(sz)
+}
 
-    let p = padding(sz);
-    for _ in 0..p.len() {
-        let _ = input.read_u8()?;
+// Append exactly `len` bytes from `input` to `out`, growing it as the data arrives.
+pub(crate) fn read_opaque<In: Read>(input: &mut In, len: usize, out: &mut Vec<u8>) -> Result<usize> {
+    options::check_input_len(len)?;
+    out.reserve(prealloc::<u8>(len));
+
+    let rsz = input.take(len as u64).read_to_end(out)?;
+    if rsz < len {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short opaque data").into());
     }
-    sz += p.len();
+    options::consume_input(rsz);
+    Ok(rsz)
+}
 
-    Ok((out, sz))
+/// Unpack a (perhaps) length-limited opaque array, streaming it into `sink`.
+///
+/// The data is copied from `input` to `sink` through a small buffer as it's read, rather than
+/// being collected into a `Vec<u8>`, so large payloads don't need to be held in memory. Returns
+/// the number of bytes consumed from `input` (including the length and padding); the number of
+/// bytes written to `sink` is that minus 4 and the padding. If `input` ends early, this fails
+/// with an `UnexpectedEof` IO error after having written the partial data to `sink`.
+pub fn unpack_opaque_to_writer<In, Out>(input: &mut In, sink: &mut Out, maxsz: Option<usize>) -> Result<usize>
+    where In: Read, Out: Write
+{
+    let (elems, mut sz) = usize::unpack(input)?;
+
+    if let Some(m) = maxsz {
+        if elems > m {
+            bail!(ErrorKind::InvalidLen(m));
+        }
+    }
+
+    options::check_input_len(elems)?;
+    let copied = io::copy(&mut input.take(elems as u64), sink)?;
+    if copied < elems as u64 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short opaque data").into());
+    }
+    options::consume_input(elems);
+    sz += elems;
+
+    sz += unpack_padding(input, elems)?;
+
+    Ok(sz)
+}
+
+/// Unpack a (perhaps) length-limited opaque array, borrowing it from the input
+///
+/// This is the same as `unpack_opaque_flex`, but when decoding from a byte slice the data is
+/// returned as a subslice of the input rather than being copied. `input` is advanced past the
+/// opaque data and its padding. This can be used to fill in `Cow<[u8]>` fields with
+/// `Cow::Borrowed`.
+pub fn unpack_opaque_borrowed<'a>(input: &mut &'a [u8], maxsz: Option<usize>) -> Result<(&'a [u8], usize)> {
+    let (elems, sz) = usize::unpack(input)?;
+
+    if let Some(m) = maxsz {
+        if elems > m {
+            bail!(ErrorKind::InvalidLen(m));
+        }
+    }
+
+    let padded = match elems.checked_add(padding(elems).len()) {
+        Some(p) => p,
+        None => bail!(ErrorKind::InvalidLen(elems)),
+    };
+    if input.len() < padded {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short opaque data").into());
+    }
+
+    let data = *input;
+    *input = &data[padded..];
+
+    Ok((&data[..elems], sz + padded))
 }
 
 /// Unpack (perhaps) length-limited string
+#[inline]
 pub fn unpack_string<In: Read>(input: &mut In, maxsz: Option<usize>) -> Result<(String, usize)> {
     let (v, sz) = unpack_opaque_flex(input, maxsz)?;
 
-    String::from_utf8(v).map_err(Error::from).map(|s| (s, sz))
+    options::utf8_string(v).map(|s| (s, sz))
+}
+
+/// Unpack an optional trailing field of a struct.
+///
+/// Newer versions of a protocol sometimes extend a struct by appending fields. Unpacking an
+/// older peer's encoding with the newer definition would fail at the end of the input, so fields
+/// added this way can be unpacked with this instead: if the input ends before any of the field
+/// has been read, this returns `T::default()` with a size of 0. If only part of the field is
+/// present, it still fails, as that's a truncated encoding rather than an older one.
+///
+/// This relies on the input ending where the struct does, so it's only useful for the last
+/// fields of a struct unpacked from a complete record or message (such as one returned by
+/// `XdrRecordReader::records`). `T` must not have an empty encoding. Packing is unchanged, so
+/// the field is always sent. The `#[xdr(trailing)]` attribute of `#[derive(XdrUnpack)]` uses
+/// this.
+pub fn unpack_trailing<In, T>(input: &mut In) -> Result<(T, usize)>
+    where In: Read, T: Default + for<'a> Unpack<io::Chain<&'a [u8], &'a mut In>>
+{
+    let mut first = [0; 1];
+    loop {
+        match input.read(&mut first) {
+            Ok(0) => return Ok((T::default(), 0)),
+            Ok(_) => break,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    T::unpack(&mut (&first[..]).chain(input))
 }
 
 /// Basic packing trait.
@@ -300,13 +785,117 @@ pub fn unpack_string<In: Read>(input: &mut In, maxsz: Option<usize>) -> Result<(
 /// Streams generated by `Pack` can be consumed by `Unpack`.
 pub trait Pack<Out: Write> {
     fn pack(&self, out: &mut Out) -> Result<usize>;
+
+    /// The size of this value's encoding, if it can be found without packing it.
+    ///
+    /// Writers use this to size buffers and choose how to frame records before packing. The
+    /// default is `None`; the basic types, strings, opaques, and containers of values which have
+    /// a size hint return one. If this returns a size, it must be exactly what `pack` writes.
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        None
+    }
+
+    /// Whether every value of this type packs to the same size.
+    ///
+    /// If so, the size hint of one element of an array gives the size of all of them. The
+    /// default is `false`.
+    #[inline]
+    fn is_fixed_size() -> bool where Self: Sized {
+        false
+    }
+
+    /// Pack a slice of values back to back, with no length or padding.
+    ///
+    /// This is used to pack the elements of arrays. The default implementation packs each
+    /// element in turn; the fixed-size primitive types override it to encode in chunks, which is
+    /// much faster for large arrays of numbers.
+    #[inline]
+    fn pack_slice(val: &[Self], out: &mut Out) -> Result<usize> where Self: Sized {
+        let mut sz = 0;
+        for v in val {
+            sz += v.pack(out)?;
+        }
+        Ok(sz)
+    }
+}
+
+/// Types whose encoding is always the same size, known at compile time.
+///
+/// `SIZE` is the number of bytes `pack` always writes, so it can be used to size buffers without
+/// allocating, such as `[u8; Header::SIZE]`. It's implemented for the basic types other than
+/// strings and arrays, and for `FixedOpaque`. With the `derive` feature, `#[derive(XdrFixedSize)]`
+/// implements it for structs whose fields are all fixed-size.
+pub trait PackedFixedSize {
+    /// The size of the encoding in bytes.
+    const SIZE: usize;
+}
+
+macro_rules! packed_fixed_size {
+    ($($(#[$attr:meta])* $ty:ty = $sz:expr),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            impl PackedFixedSize for $ty {
+                const SIZE: usize = $sz;
+            }
+        )*
+    }
+}
+
+packed_fixed_size! {
+    #[cfg(feature = "bytecodec")] u8 = 4,
+    #[cfg(feature = "bytecodec")] i8 = 4,
+    u32 = 4, i32 = 4, u64 = 8, i64 = 8,
+    #[cfg(feature = "hyper128")] u128 = 16,
+    #[cfg(feature = "hyper128")] i128 = 16,
+    f32 = 4, f64 = 8, bool = 4, usize = 4, () = 0,
+}
+
+// Scratch buffer size for bulk packing
+const BULK_BUF: usize = 4096;
+
+// Pack a slice of `esz`-byte primitives by encoding them into a scratch buffer with `put`, and
+// writing it out in chunks.
+#[inline]
+fn pack_bulk<Out: Write, T: Copy>(val: &[T], esz: usize, out: &mut Out, put: fn(&mut [u8], T)) -> Result<usize> {
+    let mut buf = [0; BULK_BUF];
+
+    for chunk in val.chunks(BULK_BUF / esz) {
+        let bytes = &mut buf[..chunk.len() * esz];
+        for (b, v) in bytes.chunks_exact_mut(esz).zip(chunk) {
+            put(b, *v);
+        }
+        out.write_all(bytes)?;
+    }
+
+    Ok(val.len() * esz)
+}
+
+// Pack an `N`-byte primitive, encoded with `put`.
+#[inline]
+fn pack_prim<Out: Write, T, const N: usize>(val: T, put: fn(&mut [u8], T), out: &mut Out) -> Result<usize> {
+    let mut buf = [0; N];
+    put(&mut buf, val);
+    out.write_all(&buf)?;
+    Ok(N)
 }
 
 #[cfg(feature = "bytecodec")]
 impl<Out: Write> Pack<Out> for u8 {
     #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
-        out.write_u32::<BigEndian>(*self as u32).map_err(Error::from).map(|_| 4)
+        pack_prim::<_, _, 4>(*self as u32, endian::write_u32, out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> { Some(4) }
+
+    #[inline]
+    fn is_fixed_size() -> bool { true }
+
+    #[inline]
+    fn pack_slice(val: &[Self], out: &mut Out) -> Result<usize> {
+        pack_bulk(val, 4, out, |b, v| endian::write_u32(b, v as u32))
     }
 }
 
@@ -314,113 +903,357 @@ impl<Out: Write> Pack<Out> for u8 {
 impl<Out: Write> Pack<Out> for i8 {
     #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
-        out.write_i32::<BigEndian>(*self as i32).map_err(Error::from).map(|_| 4)
+        pack_prim::<_, _, 4>(*self as i32, endian::write_i32, out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> { Some(4) }
+
+    #[inline]
+    fn is_fixed_size() -> bool { true }
+
+    #[inline]
+    fn pack_slice(val: &[Self], out: &mut Out) -> Result<usize> {
+        pack_bulk(val, 4, out, |b, v| endian::write_i32(b, v as i32))
     }
 }
 
 impl<Out: Write> Pack<Out> for u32 {
     #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
-        out.write_u32::<BigEndian>(*self).map_err(Error::from).map(|_| 4)
+        pack_prim::<_, _, 4>(*self, endian::write_u32, out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> { Some(4) }
+
+    #[inline]
+    fn is_fixed_size() -> bool { true }
+
+    #[inline]
+    fn pack_slice(val: &[Self], out: &mut Out) -> Result<usize> {
+        pack_bulk(val, 4, out, endian::write_u32)
     }
 }
 
 impl<Out: Write> Pack<Out> for i32 {
     #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
-        out.write_i32::<BigEndian>(*self).map_err(Error::from).map(|_| 4)
+        pack_prim::<_, _, 4>(*self, endian::write_i32, out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> { Some(4) }
+
+    #[inline]
+    fn is_fixed_size() -> bool { true }
+
+    #[inline]
+    fn pack_slice(val: &[Self], out: &mut Out) -> Result<usize> {
+        pack_bulk(val, 4, out, endian::write_i32)
     }
 }
 
 impl<Out: Write> Pack<Out> for u64 {
     #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
-        out.write_u64::<BigEndian>(*self).map_err(Error::from).map(|_| 8)
+        pack_prim::<_, _, 8>(*self, endian::write_u64, out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> { Some(8) }
+
+    #[inline]
+    fn is_fixed_size() -> bool { true }
+
+    #[inline]
+    fn pack_slice(val: &[Self], out: &mut Out) -> Result<usize> {
+        pack_bulk(val, 8, out, endian::write_u64)
     }
 }
 
 impl<Out: Write> Pack<Out> for i64 {
     #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
-        out.write_i64::<BigEndian>(*self).map_err(Error::from).map(|_| 8)
+        pack_prim::<_, _, 8>(*self, endian::write_i64, out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> { Some(8) }
+
+    #[inline]
+    fn is_fixed_size() -> bool { true }
+
+    #[inline]
+    fn pack_slice(val: &[Self], out: &mut Out) -> Result<usize> {
+        pack_bulk(val, 8, out, endian::write_i64)
     }
 }
 
-impl<Out: Write> Pack<Out> for f32 {
+/// 128-bit integers are a non-standard extension, encoded as two hypers with the most significant
+/// first. Only available with the `hyper128` feature.
+#[cfg(feature = "hyper128")]
+impl<Out: Write> Pack<Out> for u128 {
     #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
-        out.write_f32::<BigEndian>(*self).map_err(Error::from).map(|_| 4)
+        Ok(((*self >> 64) as u64).pack(out)? + (*self as u64).pack(out)?)
     }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> { Some(16) }
+
+    #[inline]
+    fn is_fixed_size() -> bool { true }
 }
 
-impl<Out: Write> Pack<Out> for f64 {
+/// 128-bit integers are a non-standard extension, encoded as two hypers with the most significant
+/// first. Only available with the `hyper128` feature.
+#[cfg(feature = "hyper128")]
+impl<Out: Write> Pack<Out> for i128 {
     #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
-        out.write_f64::<BigEndian>(*self).map_err(Error::from).map(|_| 8)
+        (*self as u128).pack(out)
     }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> { Some(16) }
+
+    #[inline]
+    fn is_fixed_size() -> bool { true }
 }
 
-impl<Out: Write> Pack<Out> for bool {
+impl<Out: Write> Pack<Out> for f32 {
     #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
-        (*self as u32).pack(out)
+        pack_prim::<_, _, 4>(*self, endian::write_f32, out)
     }
-}
 
-impl<Out: Write> Pack<Out> for () {
     #[inline]
-    fn pack(&self, _out: &mut Out) -> Result<usize> {
-        Ok(0)
+    fn packed_size_hint(&self) -> Option<usize> { Some(4) }
+
+    #[inline]
+    fn is_fixed_size() -> bool { true }
+
+    #[inline]
+    fn pack_slice(val: &[Self], out: &mut Out) -> Result<usize> {
+        pack_bulk(val, 4, out, endian::write_f32)
     }
 }
 
-impl<Out: Write> Pack<Out> for usize {
+impl<Out: Write> Pack<Out> for f64 {
     #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
-        (*self as u32).pack(out)
+        pack_prim::<_, _, 8>(*self, endian::write_f64, out)
     }
-}
 
-impl<Out: Write, T: Pack<Out>> Pack<Out> for [T] {
-    fn pack(&self, out: &mut Out) -> Result<usize> {
-        let len = self.len();
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> { Some(8) }
 
-        let mut sz = len.pack(out)?;
-        for it in self {
-            sz += it.pack(out)?;
-        }
+    #[inline]
+    fn is_fixed_size() -> bool { true }
+
+    #[inline]
+    fn pack_slice(val: &[Self], out: &mut Out) -> Result<usize> {
+        pack_bulk(val, 8, out, endian::write_f64)
+    }
+}
+
+impl<Out: Write> Pack<Out> for bool {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        (*self as u32).pack(out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> { Some(4) }
+
+    #[inline]
+    fn is_fixed_size() -> bool { true }
+
+    #[inline]
+    fn pack_slice(val: &[Self], out: &mut Out) -> Result<usize> {
+        pack_bulk(val, 4, out, |b, v| endian::write_u32(b, v as u32))
+    }
+}
+
+impl<Out: Write> Pack<Out> for () {
+    #[inline]
+    fn pack(&self, _out: &mut Out) -> Result<usize> {
+        Ok(0)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> { Some(0) }
+
+    #[inline]
+    fn is_fixed_size() -> bool { true }
+}
+
+impl<Out: Write> Pack<Out> for usize {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        xdr_len(*self)?.pack(out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> { Some(4) }
+
+    #[inline]
+    fn is_fixed_size() -> bool { true }
+}
+
+// Packed size of a variable-length array of `len` elements, if they all have a size hint.
+fn seq_size_hint<'a, Out, T, I>(len: usize, iter: I) -> Option<usize>
+    where Out: Write, T: 'a + Pack<Out>, I: IntoIterator<Item=&'a T>
+{
+    xdr_len(len).ok()?;
+
+    let mut iter = iter.into_iter();
+    let elems = if <T as Pack<Out>>::is_fixed_size() {
+        match iter.next() {
+            Some(e) => e.packed_size_hint()?.checked_mul(len)?,
+            None => 0,
+        }
+    } else {
+        iter.try_fold(0usize, |sz, e| sz.checked_add(e.packed_size_hint()?))?
+    };
+
+    let sz = elems.checked_add(4)?;
+    sz.checked_add(padding(sz).len())
+}
+
+// Packed size of opaque data.
+#[inline]
+fn opaque_size_hint(len: usize) -> Option<usize> {
+    xdr_len(len).ok()?;
+    len.checked_add(4 + padding(len).len())
+}
+
+// Pack a sequence of `len` elements as a variable-length array.
+#[inline]
+fn pack_seq<'a, Out, T, I>(len: usize, iter: I, out: &mut Out) -> Result<usize>
+    where Out: Write, T: 'a + Pack<Out>, I: IntoIterator<Item=&'a T>
+{
+    let mut sz = xdr_len(len)?.pack(out)?;
+    for it in iter {
+        sz += it.pack(out)?;
+    }
+
+    let p = padding(sz);
+    if !p.is_empty() {
+        out.write_all(p)?;
+        sz += p.len();
+    }
+
+    Ok(sz)
+}
+
+impl<Out: Write, T: Pack<Out>> Pack<Out> for [T] {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let sz = xdr_len(self.len())?.pack(out)? + T::pack_slice(self, out)?;
 
         let p = padding(sz);
-        if p.len() > 0 {
+        if !p.is_empty() {
             out.write_all(p)?;
-            sz += p.len();
         }
 
-        Ok(sz)
+        Ok(sz + p.len())
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        seq_size_hint(self.len(), self)
     }
 }
 
 impl<Out: Write, T: Pack<Out>> Pack<Out> for Vec<T> {
     #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
-        if self.len() > u32::max_value() as usize {
-            return Err(ErrorKind::InvalidLen(self.len()).into());
-        }
+        self[..].pack(out)
+    }
 
-        (&self[..]).pack(out)
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        self[..].packed_size_hint()
     }
 }
 
+impl<Out: Write, T: Pack<Out>> Pack<Out> for VecDeque<T> {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_seq(self.len(), self, out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        seq_size_hint(self.len(), self)
+    }
+}
+
+impl<Out: Write, T: Pack<Out>> Pack<Out> for BTreeSet<T> {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_seq(self.len(), self, out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        seq_size_hint(self.len(), self)
+    }
+}
+
+/// Sets are packed in iteration order, which is arbitrary for `HashSet`; the encoding of a given
+/// set is therefore not necessarily reproducible.
+impl<Out: Write, T: Pack<Out>, S> Pack<Out> for HashSet<T, S> {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_seq(self.len(), self, out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        seq_size_hint(self.len(), self)
+    }
+}
+
+// Opaque data at least this large is packed with vectored writes.
+const VECTORED_MIN: usize = 4096;
+
+// Write all of `bufs`, like `write_all` for `write_vectored`.
+fn write_all_vectored<Out: Write>(out: &mut Out, mut bufs: &mut [IoSlice]) -> io::Result<()> {
+    IoSlice::advance_slices(&mut bufs, 0);
+    while !bufs.is_empty() {
+        match out.write_vectored(bufs) {
+            Ok(0) => return Err(io::Error::new(io::ErrorKind::WriteZero, "failed to write whole buffer")),
+            Ok(sz) => IoSlice::advance_slices(&mut bufs, sz),
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Large opaque data (4k or more) is packed with vectored writes of the length, the data and
+/// the padding, so the payload can be passed to the underlying writer (and from there, the OS)
+/// without being copied into an intermediate buffer. This only helps if `Out` implements
+/// `write_vectored`.
 impl<'a, Out: Write> Pack<Out> for Opaque<'a> {
+    #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
         let mut sz;
         let data: &[u8] = self.0.borrow();
 
-        if data.len() > u32::max_value() as usize {
-            return Err(ErrorKind::InvalidLen(data.len()).into())
+        if data.len() >= VECTORED_MIN {
+            let mut hdr = [0; 4];
+            endian::write_u32(&mut hdr, xdr_len(data.len())?);
+            let p = padding(data.len());
+
+            write_all_vectored(out, &mut [IoSlice::new(&hdr), IoSlice::new(data), IoSlice::new(p)])?;
+            return Ok(hdr.len() + data.len() + p.len());
         }
 
-        sz = data.len().pack(out)?;
+        sz = xdr_len(data.len())?.pack(out)?;
 
         out.write_all(data)?;
         sz += data.len();
@@ -433,6 +1266,11 @@ impl<'a, Out: Write> Pack<Out> for Opaque<'a> {
 
         Ok(sz)
     }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        opaque_size_hint(self.0.len())
+    }
 }
 
 impl<Out: Write> Pack<Out> for str {
@@ -440,9 +1278,15 @@ impl<Out: Write> Pack<Out> for str {
     fn pack(&self, out: &mut Out) -> Result<usize> {
         Opaque::borrowed(self.as_bytes()).pack(out)
     }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        opaque_size_hint(self.len())
+    }
 }
 
 impl<Out: Write, T: Pack<Out>> Pack<Out> for Option<T> {
+    #[inline]
     fn pack(&self, out: &mut Out) -> Result<usize> {
         match self {
             &None => false.pack(out),
@@ -452,13 +1296,79 @@ impl<Out: Write, T: Pack<Out>> Pack<Out> for Option<T> {
             }
         }
     }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        match *self {
+            None => Some(4),
+            Some(ref v) => v.packed_size_hint()?.checked_add(4),
+        }
+    }
+}
+
+impl<Out: Write, T: Pack<Out> + ?Sized> Pack<Out> for &T {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        (**self).pack(out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        (**self).packed_size_hint()
+    }
 }
 
-impl<Out: Write, T: Pack<Out>> Pack<Out> for Box<T> {
+impl<Out: Write, T: Pack<Out> + ?Sized> Pack<Out> for &mut T {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        (**self).pack(out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        (**self).packed_size_hint()
+    }
+}
+
+impl<Out: Write, T: Pack<Out> + ?Sized> Pack<Out> for Box<T> {
     fn pack(&self, out: &mut Out) -> Result<usize> {
         let t: &T = self.borrow();
         t.pack(out)
     }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        let t: &T = self.borrow();
+        t.packed_size_hint()
+    }
+}
+
+impl<Out: Write, T: Pack<Out> + ?Sized> Pack<Out> for Rc<T> {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let t: &T = self.borrow();
+        t.pack(out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        let t: &T = self.borrow();
+        t.packed_size_hint()
+    }
+}
+
+impl<Out: Write, T: Pack<Out> + ?Sized> Pack<Out> for Arc<T> {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let t: &T = self.borrow();
+        t.pack(out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        let t: &T = self.borrow();
+        t.packed_size_hint()
+    }
 }
 
 impl<'a, Out: Write, T> Pack<Out> for Cow<'a, T>
@@ -468,6 +1378,25 @@ impl<'a, Out: Write, T> Pack<Out> for Cow<'a, T>
         let t: &T = self.borrow();
         t.pack(out)
     }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        let t: &T = self.borrow();
+        t.packed_size_hint()
+    }
+}
+
+/// `Cow<[u8]>` is packed as opaque data, like `Opaque`.
+impl<'a, Out: Write> Pack<Out> for Cow<'a, [u8]> {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Opaque::borrowed(self).pack(out)
+    }
+
+    #[inline]
+    fn packed_size_hint(&self) -> Option<usize> {
+        opaque_size_hint(self.len())
+    }
 }
 
 /// Deserialization (unpacking) helper function
@@ -481,6 +1410,64 @@ pub fn unpack<In: Read, T: Unpack<In>>(input: &mut In) -> Result<T> {
     T::unpack(input).map(|(v, _)| v)
 }
 
+/// Unpack a value from the start of a byte slice.
+///
+/// Returns the value and the number of bytes it took, which may be less than the length of
+/// `bytes`.
+#[inline]
+pub fn unpack_from_bytes<'a, T: Unpack<&'a [u8]>>(bytes: &'a [u8]) -> Result<(T, usize)> {
+    let mut input = bytes;
+    let res = with_input_limit(bytes.len(), || T::unpack(&mut input));
+
+    #[cfg(feature = "tracing")]
+    if let Err(ref e) = res {
+        trace_event!(debug, ty = std::any::type_name::<T>(), offset = bytes.len() - input.len(),
+                     error = %e, "decode failed");
+    }
+    res
+}
+
+/// Unpack a value which must take up the whole of a byte slice.
+///
+/// This is like `unpack_from_bytes`, but fails with `ErrorKind::TrailingData` if any bytes are
+/// left over, rather than silently ignoring them. Unlike `verify_canonical` it doesn't check the
+/// encoding otherwise, so it honours the current `DecodeOptions` and accepts non-zero padding.
+pub fn unpack_complete<'a, T: Unpack<&'a [u8]>>(bytes: &'a [u8]) -> Result<T> {
+    let (val, sz) = unpack_from_bytes(bytes)?;
+
+    if sz < bytes.len() {
+        trace_event!(debug, ty = std::any::type_name::<T>(), offset = sz, "trailing data");
+        bail!(ErrorKind::TrailingData(bytes.len() - sz))
+    }
+    Ok(val)
+}
+
+/// Unpack a value from a byte slice, checking that `bytes` is its canonical encoding.
+///
+/// The value is decoded (strictly, whatever the current `DecodeOptions`) and packed again, and
+/// the result must match `bytes` exactly. This rejects non-zero padding, trailing data and any
+/// other encoding which `Pack` would not have produced, so hashes computed over verified
+/// encodings are stable. Fails with `ErrorKind::NonCanonical` giving the offset of the first
+/// difference.
+///
+/// Types whose encoding isn't deterministic, such as `HashMap` and `HashSet`, may be rejected
+/// even when the input is a valid encoding of the value.
+pub fn verify_canonical<'a, T>(bytes: &'a [u8]) -> Result<T>
+    where T: Unpack<&'a [u8]> + Pack<Vec<u8>>
+{
+    let (val, _) = with_decode_options(DecodeOptions::new(), || unpack_from_bytes::<T>(bytes))?;
+    let enc = pack_to_vec(&val)?;
+
+    let off = match enc.iter().zip(bytes).position(|(a, b)| a != b) {
+        Some(off) => off,
+        None if enc.len() != bytes.len() => min(enc.len(), bytes.len()),
+        None => return Ok(val),
+    };
+
+    trace_event!(debug, ty = std::any::type_name::<T>(), offset = off, "non-canonical encoding");
+    bail!(ErrorKind::NonCanonical(off))
+}
+
 /// Basic unpacking trait
 ///
 /// This trait is used to unpack a type from an XDR encoded byte
@@ -491,13 +1478,116 @@ pub fn unpack<In: Read, T: Unpack<In>>(input: &mut In) -> Result<T> {
 /// as well as for arrays.
 pub trait Unpack<In: Read>: Sized {
     fn unpack(input: &mut In) -> Result<(Self, usize)>;
+
+    /// Unpack `n` values packed back to back, with no length or padding, appending them to `out`.
+    ///
+    /// This is used to unpack the elements of variable-length arrays. The default implementation
+    /// unpacks each element in turn; the fixed-size primitive types override it to read the input
+    /// in chunks and decode them in memory, which is much faster for large arrays of numbers. If
+    /// this fails, `out` may have had some of the elements appended.
+    #[inline]
+    fn unpack_vec(input: &mut In, n: usize, out: &mut Vec<Self>) -> Result<usize> {
+        let mut sz = 0;
+        for _ in 0..n {
+            let (e, esz) = Self::unpack(input)?;
+            out.push(e);
+            sz += esz;
+        }
+        Ok(sz)
+    }
+}
+
+mod sealed {
+    /// Supertrait for traits which downstream crates may use but not implement, so that new
+    /// methods and impls can be added to them without a breaking change.
+    pub trait Sealed {}
+}
+
+/// Fixed-size primitive types
+///
+/// This is implemented for the basic XDR types whose encoding is a fixed number of bytes
+/// regardless of value. It is sealed: the set of types may grow, but it can't be implemented
+/// outside this crate.
+pub trait XdrPrimitive: sealed::Sealed + Copy {
+    /// Size of the encoding in bytes.
+    const XDR_SIZE: usize;
+}
+
+impl sealed::Sealed for u32 {}
+impl XdrPrimitive for u32 { const XDR_SIZE: usize = 4; }
+impl sealed::Sealed for i32 {}
+impl XdrPrimitive for i32 { const XDR_SIZE: usize = 4; }
+impl sealed::Sealed for u64 {}
+impl XdrPrimitive for u64 { const XDR_SIZE: usize = 8; }
+impl sealed::Sealed for i64 {}
+impl XdrPrimitive for i64 { const XDR_SIZE: usize = 8; }
+impl sealed::Sealed for f32 {}
+impl XdrPrimitive for f32 { const XDR_SIZE: usize = 4; }
+impl sealed::Sealed for f64 {}
+impl XdrPrimitive for f64 { const XDR_SIZE: usize = 8; }
+impl sealed::Sealed for bool {}
+impl XdrPrimitive for bool { const XDR_SIZE: usize = 4; }
+#[cfg(feature = "bytecodec")]
+impl sealed::Sealed for u8 {}
+#[cfg(feature = "bytecodec")]
+impl XdrPrimitive for u8 { const XDR_SIZE: usize = 4; }
+#[cfg(feature = "bytecodec")]
+impl sealed::Sealed for i8 {}
+#[cfg(feature = "bytecodec")]
+impl XdrPrimitive for i8 { const XDR_SIZE: usize = 4; }
+#[cfg(feature = "hyper128")]
+impl sealed::Sealed for u128 {}
+#[cfg(feature = "hyper128")]
+impl XdrPrimitive for u128 { const XDR_SIZE: usize = 16; }
+#[cfg(feature = "hyper128")]
+impl sealed::Sealed for i128 {}
+#[cfg(feature = "hyper128")]
+impl XdrPrimitive for i128 { const XDR_SIZE: usize = 16; }
+
+// Unpack `n` `esz`-byte primitives by reading them into a scratch buffer in chunks, and decoding
+// them from it with `get`.
+#[inline]
+fn unpack_bulk<In, T, F>(input: &mut In, n: usize, esz: usize, out: &mut Vec<T>, get: F) -> Result<usize>
+    where In: Read, F: Fn(&[u8]) -> Result<T>
+{
+    let mut buf = [0; BULK_BUF];
+    let mut left = n;
+
+    while left > 0 {
+        let cnt = min(left, BULK_BUF / esz);
+        let bytes = &mut buf[..cnt * esz];
+        input.read_exact(bytes)?;
+        options::consume_input(bytes.len());
+
+        out.reserve(cnt);
+        for b in bytes.chunks_exact(esz) {
+            out.push(get(b)?);
+        }
+        left -= cnt;
+    }
+
+    Ok(n * esz)
+}
+
+// Unpack an `N`-byte primitive, decoded with `get`.
+#[inline]
+fn unpack_prim<In: Read, T, const N: usize>(input: &mut In, get: fn(&[u8]) -> T) -> Result<(T, usize)> {
+    let mut buf = [0; N];
+    input.read_exact(&mut buf)?;
+    options::consume_input(N);
+    Ok((get(&buf), N))
 }
 
 #[cfg(feature = "bytecodec")]
 impl<In: Read> Unpack<In> for u8 {
     #[inline]
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
-        input.read_u32::<BigEndian>().map_err(Error::from).map(|v| (v as u8, 4))
+        unpack_prim::<_, _, 4>(input, endian::read_u32).map(|(v, sz)| (v as u8, sz))
+    }
+
+    #[inline]
+    fn unpack_vec(input: &mut In, n: usize, out: &mut Vec<Self>) -> Result<usize> {
+        unpack_bulk(input, n, 4, out, |b| Ok(endian::read_u32(b) as u8))
     }
 }
 
@@ -505,47 +1595,100 @@ impl<In: Read> Unpack<In> for u8 {
 impl<In: Read> Unpack<In> for i8 {
     #[inline]
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
-        input.read_i32::<BigEndian>().map_err(Error::from).map(|v| (v as i8, 4))
+        unpack_prim::<_, _, 4>(input, endian::read_i32).map(|(v, sz)| (v as i8, sz))
+    }
+
+    #[inline]
+    fn unpack_vec(input: &mut In, n: usize, out: &mut Vec<Self>) -> Result<usize> {
+        unpack_bulk(input, n, 4, out, |b| Ok(endian::read_i32(b) as i8))
     }
 }
 
 impl<In: Read> Unpack<In> for u32 {
     #[inline]
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
-        input.read_u32::<BigEndian>().map_err(Error::from).map(|v| (v, 4))
+        unpack_prim::<_, _, 4>(input, endian::read_u32)
+    }
+
+    #[inline]
+    fn unpack_vec(input: &mut In, n: usize, out: &mut Vec<Self>) -> Result<usize> {
+        unpack_bulk(input, n, 4, out, |b| Ok(endian::read_u32(b)))
     }
 }
 
 impl<In: Read> Unpack<In> for i32 {
     #[inline]
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
-        input.read_i32::<BigEndian>().map_err(Error::from).map(|v| (v, 4))
+        unpack_prim::<_, _, 4>(input, endian::read_i32)
+    }
+
+    #[inline]
+    fn unpack_vec(input: &mut In, n: usize, out: &mut Vec<Self>) -> Result<usize> {
+        unpack_bulk(input, n, 4, out, |b| Ok(endian::read_i32(b)))
     }
 }
 
 impl<In: Read> Unpack<In> for u64 {
     #[inline]
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
-        input.read_u64::<BigEndian>().map_err(Error::from).map(|v| (v, 8))
+        unpack_prim::<_, _, 8>(input, endian::read_u64)
+    }
+
+    #[inline]
+    fn unpack_vec(input: &mut In, n: usize, out: &mut Vec<Self>) -> Result<usize> {
+        unpack_bulk(input, n, 8, out, |b| Ok(endian::read_u64(b)))
     }
 }
 
 impl<In: Read> Unpack<In> for i64 {
     #[inline]
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
-        input.read_i64::<BigEndian>().map_err(Error::from).map(|v| (v, 8))
+        unpack_prim::<_, _, 8>(input, endian::read_i64)
+    }
+
+    #[inline]
+    fn unpack_vec(input: &mut In, n: usize, out: &mut Vec<Self>) -> Result<usize> {
+        unpack_bulk(input, n, 8, out, |b| Ok(endian::read_i64(b)))
+    }
+}
+
+#[cfg(feature = "hyper128")]
+impl<In: Read> Unpack<In> for u128 {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (hi, hsz) = u64::unpack(input)?;
+        let (lo, lsz) = u64::unpack(input)?;
+        Ok(((hi as u128) << 64 | lo as u128, hsz + lsz))
+    }
+}
+
+#[cfg(feature = "hyper128")]
+impl<In: Read> Unpack<In> for i128 {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        u128::unpack(input).map(|(v, sz)| (v as i128, sz))
     }
 }
 
 impl<In: Read> Unpack<In> for f32 {
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
-        input.read_f32::<BigEndian>().map_err(Error::from).map(|v| (v, 4))
+        unpack_prim::<_, _, 4>(input, endian::read_f32)
+    }
+
+    #[inline]
+    fn unpack_vec(input: &mut In, n: usize, out: &mut Vec<Self>) -> Result<usize> {
+        unpack_bulk(input, n, 4, out, |b| Ok(endian::read_f32(b)))
     }
 }
 
 impl<In: Read> Unpack<In> for f64 {
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
-        input.read_f64::<BigEndian>().map_err(Error::from).map(|v| (v, 8))
+        unpack_prim::<_, _, 8>(input, endian::read_f64)
+    }
+
+    #[inline]
+    fn unpack_vec(input: &mut In, n: usize, out: &mut Vec<Self>) -> Result<usize> {
+        unpack_bulk(input, n, 8, out, |b| Ok(endian::read_f64(b)))
     }
 }
 
@@ -560,6 +1703,15 @@ impl<In: Read> Unpack<In> for bool {
                           v => Err(ErrorKind::InvalidEnum(v).into()),
                       })
     }
+
+    #[inline]
+    fn unpack_vec(input: &mut In, n: usize, out: &mut Vec<Self>) -> Result<usize> {
+        unpack_bulk(input, n, 4, out, |b| match endian::read_i32(b) {
+            0 => Ok(false),
+            1 => Ok(true),
+            v => Err(ErrorKind::InvalidEnum(v).into()),
+        })
+    }
 }
 
 impl<In: Read> Unpack<In> for () {
@@ -572,20 +1724,43 @@ impl<In: Read> Unpack<In> for () {
 impl<In: Read> Unpack<In> for usize {
     #[inline]
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
-        u32::unpack(input).map(|(v, sz)| (v as usize, sz))
+        let (v, sz) = u32::unpack(input)?;
+        Ok((host_len(v)?, sz))
     }
 }
 
 impl<In: Read, T: Unpack<In>> Unpack<In> for Vec<T> {
+    #[inline]
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
         unpack_flex(input, None)
     }
 }
 
+impl<In: Read, T: Unpack<In>> Unpack<In> for VecDeque<T> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_flex_extend(input, None)
+    }
+}
+
+impl<In: Read, T: Unpack<In> + Ord> Unpack<In> for BTreeSet<T> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_flex_extend(input, None)
+    }
+}
+
+impl<In: Read, T: Unpack<In> + Eq + Hash, S: BuildHasher + Default> Unpack<In> for HashSet<T, S> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_flex_extend(input, None)
+    }
+}
+
 impl<In: Read> Unpack<In> for String {
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
         let (v, sz) = unpack_opaque_flex(input, None)?;
-        String::from_utf8(v).map_err(Error::from).map(|s| (s, sz))
+        options::utf8_string(v).map(|s| (s, sz))
     }
 }
 
@@ -593,19 +1768,16 @@ impl<'a, In: Read> Unpack<In> for Opaque<'a> {
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
         let (len, mut sz) = usize::unpack(input)?;
         let mut v = Vec::new();
-        sz += input.by_ref().take(len as u64).read_to_end(&mut v)?;
+        sz += read_opaque(input, len, &mut v)?;
 
-        let p = padding(sz);
-        for _ in 0..p.len() {
-            let _ = input.read_u8()?;
-            sz += 1;
-        }
+        sz += unpack_padding(input, sz)?;
 
         Ok((Opaque(Cow::Owned(v)), sz))
     }
 }
 
 impl<In: Read, T: Unpack<In>> Unpack<In> for Option<T> {
+    #[inline]
     fn unpack(input: &mut In) -> Result<(Self, usize)> {
         let (have, mut sz) = Unpack::unpack(input)?;
         let ret = if have {
@@ -626,6 +1798,46 @@ impl<In: Read, T: Unpack<In>> Unpack<In> for Box<T> {
     }
 }
 
+impl<In: Read, T: Unpack<In>> Unpack<In> for Box<[T]> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz): (Vec<T>, _) = Unpack::unpack(input)?;
+        Ok((v.into_boxed_slice(), sz))
+    }
+}
+
+impl<In: Read, T: Unpack<In>> Unpack<In> for Rc<T> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (b, sz) = Unpack::unpack(input)?;
+        Ok((Rc::new(b), sz))
+    }
+}
+
+impl<In: Read, T: Unpack<In>> Unpack<In> for Rc<[T]> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz): (Vec<T>, _) = Unpack::unpack(input)?;
+        Ok((Rc::from(v), sz))
+    }
+}
+
+impl<In: Read, T: Unpack<In>> Unpack<In> for Arc<T> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (b, sz) = Unpack::unpack(input)?;
+        Ok((Arc::new(b), sz))
+    }
+}
+
+impl<In: Read, T: Unpack<In>> Unpack<In> for Arc<[T]> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz): (Vec<T>, _) = Unpack::unpack(input)?;
+        Ok((Arc::from(v), sz))
+    }
+}
+
 impl<'a, In: Read, T> Unpack<In> for Cow<'a, T>
     where T: 'a + Unpack<In> + ToOwned<Owned=T>
 {
@@ -634,3 +1846,16 @@ impl<'a, In: Read, T> Unpack<In> for Cow<'a, T>
         Ok((Cow::Owned(b), sz))
     }
 }
+
+/// `Cow<[u8]>` is unpacked from opaque data.
+///
+/// `Unpack` can't tie the result to the input's lifetime, so this always allocates and returns
+/// `Cow::Owned`, even when unpacking from a byte slice. To borrow the data from a slice without
+/// copying it, use `unpack_opaque_borrowed` or `UnpackRef` instead.
+impl<'a, In: Read> Unpack<In> for Cow<'a, [u8]> {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz) = unpack_opaque_flex(input, None)?;
+        Ok((Cow::Owned(v), sz))
+    }
+}
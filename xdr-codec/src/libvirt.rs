@@ -0,0 +1,282 @@
+//! libvirt remote protocol framing
+//!
+//! libvirt's RPC protocol (used by `libvirtd` and `virtqemud`) is XDR, but with its own framing
+//! rather than ONC RPC record marking. Each message is a big-endian length (counting itself),
+//! then a fixed header, then the XDR body for the procedure:
+//!
+//! ```text
+//! struct virNetMessageHeader {
+//!     unsigned prog;
+//!     unsigned vers;
+//!     int proc;
+//!     virNetMessageType type;
+//!     unsigned serial;
+//!     virNetMessageStatus status;
+//! };
+//! ```
+//!
+//! `MessageWriter` frames and sends messages, and `MessageReader` receives them. Bodies are packed
+//! and unpacked with the procedure's own types, which aren't defined here. Both use blocking IO.
+//!
+//! ```
+//! use xdr_codec::libvirt::*;
+//!
+//! let mut w = MessageWriter::new(Vec::new());
+//! let serial = w.call(REMOTE_PROGRAM, REMOTE_PROTOCOL_VERSION, 1, &0u32).unwrap();
+//! let buf = w.into_inner();
+//!
+//! let mut r = MessageReader::new(&buf[..]);
+//! let msg = r.read_message().unwrap().unwrap();
+//! assert_eq!(msg.header.serial, serial);
+//! assert_eq!(msg.header.type_, virNetMessageType::VIR_NET_CALL);
+//! assert_eq!(msg.unpack_body::<u32>().unwrap(), 0);
+//! assert!(r.read_message().unwrap().is_none());
+//! ```
+//!
+//! This module is only available with the `libvirt` feature.
+#![allow(non_camel_case_types)]
+
+use std::io::{self, Read, Write};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use error::*;
+use super::{Pack, Unpack, host_len, xdr_len};
+
+/// Program number of the main libvirt remote protocol.
+pub const REMOTE_PROGRAM: u32 = 0x20008086;
+/// Version of the main libvirt remote protocol.
+pub const REMOTE_PROTOCOL_VERSION: u32 = 1;
+/// Program number of the QEMU-specific protocol.
+pub const QEMU_PROGRAM: u32 = 0x20008087;
+/// Version of the QEMU-specific protocol.
+pub const QEMU_PROTOCOL_VERSION: u32 = 1;
+/// Program number of the keepalive protocol.
+pub const KEEPALIVE_PROGRAM: u32 = 0x6b656570;
+/// Version of the keepalive protocol.
+pub const KEEPALIVE_PROTOCOL_VERSION: u32 = 1;
+
+/// Largest message libvirt accepts, including the length word.
+pub const VIR_NET_MESSAGE_MAX: usize = 33554432;
+/// Size of the encoded header.
+pub const VIR_NET_MESSAGE_HEADER_MAX: usize = 24;
+/// Size of the length word.
+pub const VIR_NET_MESSAGE_LEN_MAX: usize = 4;
+
+xdr_enum! {
+    /// Kind of message.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum virNetMessageType {
+        VIR_NET_CALL = 0,
+        VIR_NET_REPLY = 1,
+        VIR_NET_MESSAGE = 2,
+        VIR_NET_STREAM = 3,
+        VIR_NET_CALL_WITH_FDS = 4,
+        VIR_NET_REPLY_WITH_FDS = 5,
+        VIR_NET_STREAM_HOLE = 6,
+    }
+}
+
+xdr_enum! {
+    /// Status of a reply or stream message. Calls and events are always `VIR_NET_OK`.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum virNetMessageStatus {
+        VIR_NET_OK = 0,
+        VIR_NET_ERROR = 1,
+        VIR_NET_CONTINUE = 2,
+    }
+}
+
+/// Header of every message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct virNetMessageHeader {
+    pub prog: u32,
+    pub vers: u32,
+    pub proc_: i32,
+    pub type_: virNetMessageType,
+    pub serial: u32,
+    pub status: virNetMessageStatus,
+}
+
+impl<Out: Write> Pack<Out> for virNetMessageHeader {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += self.prog.pack(out)?;
+        sz += self.vers.pack(out)?;
+        sz += self.proc_.pack(out)?;
+        sz += self.type_.pack(out)?;
+        sz += self.serial.pack(out)?;
+        sz += self.status.pack(out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for virNetMessageHeader {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = virNetMessageHeader {
+            prog: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            vers: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            proc_: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            type_: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            serial: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            status: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// A received message: its header and undecoded body.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Message {
+    pub header: virNetMessageHeader,
+    pub body: Vec<u8>,
+}
+
+impl Message {
+    /// Unpack the body as a `T`.
+    pub fn unpack_body<'a, T: Unpack<&'a [u8]>>(&'a self) -> Result<T> {
+        T::unpack(&mut &self.body[..]).map(|(v, _)| v)
+    }
+}
+
+/// Frame and write messages.
+#[derive(Debug)]
+pub struct MessageWriter<W: Write> {
+    writer: W,
+    max: usize,
+    serial: u32,
+}
+
+impl<W: Write> MessageWriter<W> {
+    /// Write messages to `writer`, with a limit of `VIR_NET_MESSAGE_MAX`.
+    pub fn new(writer: W) -> MessageWriter<W> {
+        MessageWriter { writer, max: VIR_NET_MESSAGE_MAX, serial: 0 }
+    }
+
+    /// Set the largest message to write, including the length word. Larger messages fail with
+    /// `RecordTooLarge`, before anything is written.
+    pub fn set_max_message(&mut self, max: usize) {
+        self.max = max;
+    }
+
+    /// Write a message with the given header and body.
+    pub fn write_message<T>(&mut self, header: &virNetMessageHeader, body: &T) -> Result<()>
+        where T: ?Sized + Pack<Vec<u8>>
+    {
+        let mut buf = vec![0; VIR_NET_MESSAGE_LEN_MAX];
+        header.pack(&mut buf)?;
+        body.pack(&mut buf)?;
+
+        if buf.len() > self.max {
+            bail!(ErrorKind::RecordTooLarge(buf.len(), self.max));
+        }
+        let len = xdr_len(buf.len())?;
+        BigEndian::write_u32(&mut buf[..VIR_NET_MESSAGE_LEN_MAX], len);
+
+        self.writer.write_all(&buf)?;
+        Ok(())
+    }
+
+    /// Write a call to procedure `proc_` of program `prog` version `vers`, with the next serial
+    /// number, and return the serial so the reply can be matched.
+    pub fn call<T>(&mut self, prog: u32, vers: u32, proc_: i32, args: &T) -> Result<u32>
+        where T: ?Sized + Pack<Vec<u8>>
+    {
+        let header = virNetMessageHeader {
+            prog,
+            vers,
+            proc_,
+            type_: virNetMessageType::VIR_NET_CALL,
+            serial: self.serial,
+            status: virNetMessageStatus::VIR_NET_OK,
+        };
+
+        self.write_message(&header, args)?;
+        self.serial = self.serial.wrapping_add(1);
+
+        Ok(header.serial)
+    }
+
+    /// Flush the underlying writer.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Read framed messages.
+#[derive(Debug)]
+pub struct MessageReader<R: Read> {
+    reader: R,
+    max: usize,
+}
+
+impl<R: Read> MessageReader<R> {
+    /// Read messages from `reader`, with a limit of `VIR_NET_MESSAGE_MAX`.
+    pub fn new(reader: R) -> MessageReader<R> {
+        MessageReader { reader, max: VIR_NET_MESSAGE_MAX }
+    }
+
+    /// Set the largest message to accept, including the length word. A larger length fails with
+    /// `RecordTooLarge` before the message is read.
+    pub fn set_max_message(&mut self, max: usize) {
+        self.max = max;
+    }
+
+    /// Read the next message, or return `None` at the end of the stream.
+    ///
+    /// The end of the stream within a message is an `UnexpectedEof` IO error, and a length too
+    /// short to hold the header fails with `InvalidLen`.
+    pub fn read_message(&mut self) -> Result<Option<Message>> {
+        let mut lenbuf = [0; VIR_NET_MESSAGE_LEN_MAX];
+        let mut got = 0;
+
+        while got < lenbuf.len() {
+            match self.reader.read(&mut lenbuf[got..]) {
+                Ok(0) if got == 0 => return Ok(None),
+                Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                Ok(n) => got += n,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let len = host_len(BigEndian::read_u32(&lenbuf))?;
+        if len < VIR_NET_MESSAGE_LEN_MAX + VIR_NET_MESSAGE_HEADER_MAX {
+            bail!(ErrorKind::InvalidLen(len));
+        }
+        if len > self.max {
+            bail!(ErrorKind::RecordTooLarge(len, self.max));
+        }
+
+        let mut buf = vec![0; len - VIR_NET_MESSAGE_LEN_MAX];
+        self.reader.read_exact(&mut buf)?;
+
+        let (header, hsz) = virNetMessageHeader::unpack(&mut &buf[..])?;
+        buf.drain(..hsz);
+
+        Ok(Some(Message { header, body: buf }))
+    }
+
+    /// Return the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Iterator for MessageReader<R> {
+    type Item = Result<Message>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_message().transpose()
+    }
+}
@@ -0,0 +1,331 @@
+//! MOUNT protocol types
+//!
+//! The MOUNT protocol is how an NFS client gets the root file handle of an export, and lists
+//! what a server exports and who has it mounted. Version 1 is defined in RFC 1094 (for NFSv2)
+//! and version 3 in RFC 1813 (for NFSv3); they share everything but the `MNT` result. Names
+//! follow the `.x` specifications:
+//!
+//! ```text
+//! typedef struct mountbody *mountlist;
+//!
+//! struct mountbody {
+//!     name       ml_hostname;
+//!     dirpath    ml_directory;
+//!     mountlist  ml_next;
+//! };
+//!
+//! union mountres3 switch (mountstat3 fhs_status) {
+//! case MNT3_OK:
+//!     mountres3_ok  mountinfo;
+//! default:
+//!     void;
+//! };
+//! ```
+//!
+//! The linked lists `mountlist`, `groups` and `exports` are wrappers around a `Vec` of the list
+//! elements without their links, packed with `pack_list`. The `fhstatus` and `mountres3` unions
+//! are enums of the handle or the failure status.
+//!
+//! This module is only available with the `mount` feature.
+#![allow(non_camel_case_types)]
+
+use std::io::{Read, Write};
+
+use error::*;
+use super::{Pack, Unpack, pack_flex, pack_list, pack_opaque_array, pack_opaque_flex, pack_string,
+            unpack_flex, unpack_list, unpack_opaque_array, unpack_opaque_flex, unpack_string};
+
+/// RPC program number of MOUNT.
+pub const MOUNT_PROGRAM: u32 = 100005;
+/// RPC version number of MOUNT version 1, used with NFS version 2.
+pub const MOUNTVERS: u32 = 1;
+/// RPC version number of MOUNT version 3, used with NFS version 3.
+pub const MOUNT_V3: u32 = 3;
+
+pub const MOUNTPROC_NULL: u32 = 0;
+pub const MOUNTPROC_MNT: u32 = 1;
+pub const MOUNTPROC_DUMP: u32 = 2;
+pub const MOUNTPROC_UMNT: u32 = 3;
+pub const MOUNTPROC_UMNTALL: u32 = 4;
+pub const MOUNTPROC_EXPORT: u32 = 5;
+
+pub const MOUNTPROC3_NULL: u32 = 0;
+pub const MOUNTPROC3_MNT: u32 = 1;
+pub const MOUNTPROC3_DUMP: u32 = 2;
+pub const MOUNTPROC3_UMNT: u32 = 3;
+pub const MOUNTPROC3_UMNTALL: u32 = 4;
+pub const MOUNTPROC3_EXPORT: u32 = 5;
+
+/// Maximum length of a `dirpath`.
+pub const MNTPATHLEN: usize = 1024;
+/// Maximum length of a `name`.
+pub const MNTNAMLEN: usize = 255;
+/// Size of a version 1 `fhandle`.
+pub const FHSIZE: usize = 32;
+/// Maximum size of a version 3 `fhandle3`.
+pub const FHSIZE3: usize = 64;
+
+/// Path of an exported directory on the server, of up to `MNTPATHLEN` bytes.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct dirpath(pub String);
+
+impl<Out: Write> Pack<Out> for dirpath {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_string(&self.0, Some(MNTPATHLEN), out)
+    }
+}
+
+impl<In: Read> Unpack<In> for dirpath {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz) = unpack_string(input, Some(MNTPATHLEN))?;
+        Ok((dirpath(v), sz))
+    }
+}
+
+/// Host or netgroup name, of up to `MNTNAMLEN` bytes.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct name(pub String);
+
+impl<Out: Write> Pack<Out> for name {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_string(&self.0, Some(MNTNAMLEN), out)
+    }
+}
+
+impl<In: Read> Unpack<In> for name {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz) = unpack_string(input, Some(MNTNAMLEN))?;
+        Ok((name(v), sz))
+    }
+}
+
+/// Version 1 (NFSv2) file handle.
+pub type fhandle = [u8; FHSIZE];
+
+/// Result of version 1 `MNT`: the file handle, or a Unix error number.
+///
+/// Packing a `Fail` with a status of 0 fails with `InvalidCase`, since it would unpack as an
+/// `Ok`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum fhstatus {
+    Ok(fhandle),
+    Fail(u32),
+}
+
+impl<Out: Write> Pack<Out> for fhstatus {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        match *self {
+            fhstatus::Ok(ref fh) => Ok(0u32.pack(out)? + pack_opaque_array(fh, FHSIZE, out)?),
+            fhstatus::Fail(0) => Err(Error::invalidcase(0)),
+            fhstatus::Fail(stat) => stat.pack(out),
+        }
+    }
+}
+
+impl<In: Read> Unpack<In> for fhstatus {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (stat, sz) = u32::unpack(input)?;
+
+        if stat == 0 {
+            let mut fh = [0; FHSIZE];
+            let fsz = unpack_opaque_array(input, &mut fh, FHSIZE)?;
+            Ok((fhstatus::Ok(fh), sz + fsz))
+        } else {
+            Ok((fhstatus::Fail(stat), sz))
+        }
+    }
+}
+
+/// Version 3 (NFSv3) file handle, of up to `FHSIZE3` bytes.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct fhandle3(pub Vec<u8>);
+
+impl<Out: Write> Pack<Out> for fhandle3 {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_opaque_flex(&self.0, Some(FHSIZE3), out)
+    }
+}
+
+impl<In: Read> Unpack<In> for fhandle3 {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz) = unpack_opaque_flex(input, Some(FHSIZE3))?;
+        Ok((fhandle3(v), sz))
+    }
+}
+
+xdr_enum! {
+    /// Status of version 3 `MNT`.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum mountstat3 {
+        MNT3_OK = 0,
+        MNT3ERR_PERM = 1,
+        MNT3ERR_NOENT = 2,
+        MNT3ERR_IO = 5,
+        MNT3ERR_ACCES = 13,
+        MNT3ERR_NOTDIR = 20,
+        MNT3ERR_INVAL = 22,
+        MNT3ERR_NAMETOOLONG = 63,
+        MNT3ERR_NOTSUPP = 10004,
+        MNT3ERR_SERVERFAULT = 10006,
+    }
+}
+
+/// Successful result of version 3 `MNT`: the file handle, and the authentication flavors the
+/// server accepts for it (the same values as `sunrpc_types::auth_flavor`).
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct mountres3_ok {
+    pub fhandle: fhandle3,
+    pub auth_flavors: Vec<u32>,
+}
+
+impl<Out: Write> Pack<Out> for mountres3_ok {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.fhandle.pack(out)? + pack_flex(&self.auth_flavors, None, out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for mountres3_ok {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = mountres3_ok {
+            fhandle: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            auth_flavors: { let (v, fsz) = unpack_flex(input, None)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Result of version 3 `MNT`.
+///
+/// Packing a `Fail` with a status of `MNT3_OK` fails with `InvalidCase`, since it would unpack
+/// as an `Ok`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum mountres3 {
+    Ok(mountres3_ok),
+    Fail(mountstat3),
+}
+
+impl<Out: Write> Pack<Out> for mountres3 {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        match *self {
+            mountres3::Ok(ref ok) => Ok(mountstat3::MNT3_OK.pack(out)? + ok.pack(out)?),
+            mountres3::Fail(mountstat3::MNT3_OK) => Err(Error::invalidcase(mountstat3::MNT3_OK as i32)),
+            mountres3::Fail(stat) => stat.pack(out),
+        }
+    }
+}
+
+impl<In: Read> Unpack<In> for mountres3 {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (stat, sz) = mountstat3::unpack(input)?;
+
+        if stat == mountstat3::MNT3_OK {
+            let (ok, osz) = mountres3_ok::unpack(input)?;
+            Ok((mountres3::Ok(ok), sz + osz))
+        } else {
+            Ok((mountres3::Fail(stat), sz))
+        }
+    }
+}
+
+/// A client and the directory it has mounted.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct mountbody {
+    pub ml_hostname: name,
+    pub ml_directory: dirpath,
+}
+
+impl<Out: Write> Pack<Out> for mountbody {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.ml_hostname.pack(out)? + self.ml_directory.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for mountbody {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = mountbody {
+            ml_hostname: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            ml_directory: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Result of `DUMP`: the mounts the server knows of.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct mountlist(pub Vec<mountbody>);
+
+impl<Out: Write> Pack<Out> for mountlist {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_list(&self.0, None, out)
+    }
+}
+
+impl<In: Read> Unpack<In> for mountlist {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz) = unpack_list(input, None)?;
+        Ok((mountlist(v), sz))
+    }
+}
+
+/// The hosts and netgroups an export is available to.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct groups(pub Vec<name>);
+
+impl<Out: Write> Pack<Out> for groups {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_list(&self.0, None, out)
+    }
+}
+
+impl<In: Read> Unpack<In> for groups {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz) = unpack_list(input, None)?;
+        Ok((groups(v), sz))
+    }
+}
+
+/// An exported directory.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct exportnode {
+    pub ex_dir: dirpath,
+    pub ex_groups: groups,
+}
+
+impl<Out: Write> Pack<Out> for exportnode {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.ex_dir.pack(out)? + self.ex_groups.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for exportnode {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = exportnode {
+            ex_dir: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            ex_groups: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Result of `EXPORT`: the directories the server exports.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct exports(pub Vec<exportnode>);
+
+impl<Out: Write> Pack<Out> for exports {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_list(&self.0, None, out)
+    }
+}
+
+impl<In: Read> Unpack<In> for exports {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz) = unpack_list(input, None)?;
+        Ok((exports(v), sz))
+    }
+}
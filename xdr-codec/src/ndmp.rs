@@ -0,0 +1,318 @@
+//! NDMP message header and request/reply matching
+//!
+//! The Network Data Management Protocol (NDMP) is used by backup applications to drive data and
+//! tape services on file servers. Each message is a single XDR record (see `record`) made of a
+//! common header followed by the body for its message code. The version 4 header is:
+//!
+//! ```text
+//! struct ndmp_header {
+//!     u_long                   sequence;
+//!     u_long                   time_stamp;
+//!     ndmp_header_message_type message_type;
+//!     ndmp_message             message;
+//!     u_long                   reply_sequence;
+//!     ndmp_error               error_code;
+//! };
+//! ```
+//!
+//! Both sides number their own messages, and a reply names the request it answers in
+//! `reply_sequence`. `Session` assigns sequence numbers and matches replies to outstanding
+//! requests. Message bodies other than `NDMP_CONNECT_OPEN`'s aren't defined here.
+//!
+//! ```
+//! use std::io::Cursor;
+//! use xdr_codec::{Pack, Unpack};
+//! use xdr_codec::ndmp::*;
+//! use xdr_codec::record::{XdrRecordReader, XdrRecordWriter};
+//!
+//! let mut client = Session::new();
+//! let mut w = XdrRecordWriter::new(Vec::new());
+//! client.request(NDMP_CONNECT_OPEN).pack(&mut w).unwrap();
+//! ndmp_connect_open_request { protocol_version: 4 }.pack(&mut w).unwrap();
+//! let buf = w.finish().unwrap();
+//!
+//! // Server
+//! let mut server = Session::new();
+//! let rec = XdrRecordReader::new(Cursor::new(buf)).records().next().unwrap().unwrap();
+//! let mut rec = &rec[..];
+//! let (req, _) = ndmp_header::unpack(&mut rec).unwrap();
+//! let (args, _) = ndmp_connect_open_request::unpack(&mut rec).unwrap();
+//! assert_eq!(args.protocol_version, 4);
+//! let reply = server.reply(&req, ndmp_error::NDMP_NO_ERR);
+//!
+//! assert_eq!(client.complete(&reply).unwrap(), NDMP_CONNECT_OPEN);
+//! assert_eq!(client.pending(), 0);
+//! ```
+//!
+//! This module is only available with the `ndmp` feature.
+#![allow(non_camel_case_types)]
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use error::*;
+use super::{Pack, Unpack};
+
+/// TCP port NDMP servers listen on.
+pub const NDMPPORT: u16 = 10000;
+/// Protocol version described here.
+pub const NDMPVER: u32 = 4;
+
+/// A message code, which determines the body following the header.
+pub type ndmp_message = u32;
+
+pub const NDMP_CONNECT_OPEN: ndmp_message = 0x900;
+pub const NDMP_CONNECT_CLIENT_AUTH: ndmp_message = 0x901;
+pub const NDMP_CONNECT_CLOSE: ndmp_message = 0x902;
+pub const NDMP_CONNECT_SERVER_AUTH: ndmp_message = 0x903;
+
+pub const NDMP_CONFIG_GET_HOST_INFO: ndmp_message = 0x100;
+pub const NDMP_CONFIG_GET_CONNECTION_TYPE: ndmp_message = 0x102;
+pub const NDMP_CONFIG_GET_AUTH_ATTR: ndmp_message = 0x103;
+pub const NDMP_CONFIG_GET_BUTYPE_INFO: ndmp_message = 0x104;
+pub const NDMP_CONFIG_GET_FS_INFO: ndmp_message = 0x105;
+pub const NDMP_CONFIG_GET_TAPE_INFO: ndmp_message = 0x106;
+pub const NDMP_CONFIG_GET_SCSI_INFO: ndmp_message = 0x107;
+pub const NDMP_CONFIG_GET_SERVER_INFO: ndmp_message = 0x108;
+
+pub const NDMP_NOTIFY_DATA_HALTED: ndmp_message = 0x501;
+pub const NDMP_NOTIFY_CONNECTION_STATUS: ndmp_message = 0x502;
+pub const NDMP_NOTIFY_MOVER_HALTED: ndmp_message = 0x503;
+pub const NDMP_NOTIFY_MOVER_PAUSED: ndmp_message = 0x504;
+pub const NDMP_NOTIFY_DATA_READ: ndmp_message = 0x505;
+
+xdr_enum! {
+    /// Whether a message is a request or a reply.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum ndmp_header_message_type {
+        NDMP_MESSAGE_REQUEST = 0,
+        NDMP_MESSAGE_REPLY = 1,
+    }
+}
+
+xdr_enum! {
+    /// Error codes, in reply headers and in many reply bodies.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum ndmp_error {
+        NDMP_NO_ERR = 0,
+        NDMP_NOT_SUPPORTED_ERR = 1,
+        NDMP_DEVICE_BUSY_ERR = 2,
+        NDMP_DEVICE_OPENED_ERR = 3,
+        NDMP_NOT_AUTHORIZED_ERR = 4,
+        NDMP_PERMISSION_ERR = 5,
+        NDMP_DEV_NOT_OPEN_ERR = 6,
+        NDMP_IO_ERR = 7,
+        NDMP_TIMEOUT_ERR = 8,
+        NDMP_ILLEGAL_ARGS_ERR = 9,
+        NDMP_NO_TAPE_LOADED_ERR = 10,
+        NDMP_WRITE_PROTECT_ERR = 11,
+        NDMP_EOF_ERR = 12,
+        NDMP_EOM_ERR = 13,
+        NDMP_FILE_NOT_FOUND_ERR = 14,
+        NDMP_BAD_FILE_ERR = 15,
+        NDMP_NO_DEVICE_ERR = 16,
+        NDMP_NO_BUS_ERR = 17,
+        NDMP_XDR_DECODE_ERR = 18,
+        NDMP_ILLEGAL_STATE_ERR = 19,
+        NDMP_UNDEFINED_ERR = 20,
+        NDMP_XDR_ENCODE_ERR = 21,
+        NDMP_NO_MEM_ERR = 22,
+        NDMP_CONNECT_ERR = 23,
+        NDMP_SEQUENCE_NUM_ERR = 24,
+        NDMP_READ_IN_PROGRESS_ERR = 25,
+        NDMP_PRECONDITION_ERR = 26,
+        NDMP_CLASS_NOT_SUPPORTED_ERR = 27,
+        NDMP_VERSION_NOT_SUPPORTED_ERR = 28,
+        NDMP_EXT_DUPL_CLASSES_ERR = 29,
+        NDMP_EXT_DANDN_ILLEGAL_ERR = 30,
+    }
+}
+
+/// Header of every message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct ndmp_header {
+    pub sequence: u32,
+    /// Seconds since the Unix epoch when the message was sent.
+    pub time_stamp: u32,
+    pub message_type: ndmp_header_message_type,
+    pub message: ndmp_message,
+    /// For a reply, the `sequence` of the request. Zero for requests.
+    pub reply_sequence: u32,
+    pub error_code: ndmp_error,
+}
+
+impl<Out: Write> Pack<Out> for ndmp_header {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += self.sequence.pack(out)?;
+        sz += self.time_stamp.pack(out)?;
+        sz += self.message_type.pack(out)?;
+        sz += self.message.pack(out)?;
+        sz += self.reply_sequence.pack(out)?;
+        sz += self.error_code.pack(out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for ndmp_header {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = ndmp_header {
+            sequence: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            time_stamp: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            message_type: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            message: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            reply_sequence: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            error_code: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Body of an `NDMP_CONNECT_OPEN` request, the first message on a connection. `protocol_version`
+/// is a `u_short`, encoded as a 32-bit word.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ndmp_connect_open_request {
+    pub protocol_version: u32,
+}
+
+impl<Out: Write> Pack<Out> for ndmp_connect_open_request {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.protocol_version.pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for ndmp_connect_open_request {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (protocol_version, sz) = Unpack::unpack(input)?;
+        Ok((ndmp_connect_open_request { protocol_version }, sz))
+    }
+}
+
+/// Body of an `NDMP_CONNECT_OPEN` reply.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct ndmp_connect_open_reply {
+    pub error: ndmp_error,
+}
+
+impl<Out: Write> Pack<Out> for ndmp_connect_open_reply {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.error.pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for ndmp_connect_open_reply {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (error, sz) = Unpack::unpack(input)?;
+        Ok((ndmp_connect_open_reply { error }, sz))
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn now() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as u32).unwrap_or(0)
+}
+
+// There's no clock on bare wasm, and `SystemTime::now` panics
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn now() -> u32 {
+    0
+}
+
+/// One side of an NDMP connection: numbers outgoing messages, and tracks requests awaiting a
+/// reply.
+#[derive(Clone, Debug)]
+pub struct Session {
+    next: u32,
+    pending: HashMap<u32, ndmp_message>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session::new()
+    }
+}
+
+impl Session {
+    /// A new session. Sequence numbers start at 1.
+    pub fn new() -> Session {
+        Session { next: 1, pending: HashMap::new() }
+    }
+
+    fn sequence(&mut self) -> u32 {
+        let seq = self.next;
+        // Zero means "no request" in reply_sequence, so never use it
+        self.next = self.next.checked_add(1).unwrap_or(1);
+        seq
+    }
+
+    /// Header for a request with message code `message`, which is then awaiting a reply.
+    pub fn request(&mut self, message: ndmp_message) -> ndmp_header {
+        let hdr = self.notification(message);
+        self.pending.insert(hdr.sequence, message);
+        hdr
+    }
+
+    /// Header for a request which gets no reply, such as the `NDMP_NOTIFY_*` and `NDMP_LOG_*`
+    /// messages.
+    pub fn notification(&mut self, message: ndmp_message) -> ndmp_header {
+        ndmp_header {
+            sequence: self.sequence(),
+            time_stamp: now(),
+            message_type: ndmp_header_message_type::NDMP_MESSAGE_REQUEST,
+            message,
+            reply_sequence: 0,
+            error_code: ndmp_error::NDMP_NO_ERR,
+        }
+    }
+
+    /// Header for the reply to `request`. `error_code` is `NDMP_NO_ERR` unless the request
+    /// couldn't be handled at all (eg `NDMP_NOT_SUPPORTED_ERR`), in which case the reply has
+    /// no body.
+    pub fn reply(&mut self, request: &ndmp_header, error_code: ndmp_error) -> ndmp_header {
+        ndmp_header {
+            sequence: self.sequence(),
+            time_stamp: now(),
+            message_type: ndmp_header_message_type::NDMP_MESSAGE_REPLY,
+            message: request.message,
+            reply_sequence: request.sequence,
+            error_code,
+        }
+    }
+
+    /// Match a received reply to its request, returning the request's message code.
+    ///
+    /// Fails with `InvalidValue` if `reply` isn't a reply, doesn't answer an outstanding
+    /// request, or has a different message code from the request.
+    pub fn complete(&mut self, reply: &ndmp_header) -> Result<ndmp_message> {
+        if reply.message_type != ndmp_header_message_type::NDMP_MESSAGE_REPLY {
+            bail!(ErrorKind::InvalidValue(format!("message {} is not a reply", reply.sequence)));
+        }
+
+        match self.pending.get(&reply.reply_sequence) {
+            None => bail!(ErrorKind::InvalidValue(format!("no request {} awaiting reply", reply.reply_sequence))),
+            Some(&message) if message != reply.message => {
+                bail!(ErrorKind::InvalidValue(format!("reply to request {} has message {:#x}, expected {:#x}",
+                                                      reply.reply_sequence, reply.message, message)))
+            }
+            Some(_) => (),
+        }
+
+        Ok(self.pending.remove(&reply.reply_sequence).unwrap())
+    }
+
+    /// Number of requests awaiting a reply.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether request `sequence` is awaiting a reply.
+    pub fn is_pending(&self, sequence: u32) -> bool {
+        self.pending.contains_key(&sequence)
+    }
+}
@@ -0,0 +1,128 @@
+//! Network address encodings
+//!
+//! ONC RPC protocols such as rpcbind (RFC 1833) and NFSv4 (RFC 5665) carry transport addresses as
+//! a pair of strings: a "netid" naming the transport (eg `tcp` or `udp6`) and a "universal
+//! address" (uaddr). The uaddr is the usual textual form of the IP address, followed by the port
+//! as two more dot-separated decimal bytes, so `10.0.0.1` port 2049 is `10.0.0.1.8.1`. The
+//! helpers here convert `SocketAddr` to and from these forms.
+//!
+//! This module also implements `Pack` and `Unpack` for raw binary addresses: `Ipv4Addr` and
+//! `Ipv6Addr` are fixed-length 4 and 16 byte opaques, and `IpAddr` is a variable-length opaque
+//! whose length (4 or 16) determines the address family.
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use error::*;
+use super::{Pack, Unpack, pack_opaque_array, pack_opaque_flex, unpack_opaque_array, unpack_opaque_flex};
+
+/// Transport protocol, for constructing a netid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transport {
+    Tcp,
+    Udp,
+}
+
+/// Return the netid for a transport protocol and address family.
+///
+/// This is one of `tcp`, `tcp6`, `udp` or `udp6`.
+pub fn netid(transport: Transport, addr: &SocketAddr) -> &'static str {
+    match (transport, addr.is_ipv4()) {
+        (Transport::Tcp, true) => "tcp",
+        (Transport::Tcp, false) => "tcp6",
+        (Transport::Udp, true) => "udp",
+        (Transport::Udp, false) => "udp6",
+    }
+}
+
+/// Parse a netid into a transport protocol and whether it is IPv6.
+pub fn parse_netid(netid: &str) -> Result<(Transport, bool)> {
+    match netid {
+        "tcp" => Ok((Transport::Tcp, false)),
+        "tcp6" => Ok((Transport::Tcp, true)),
+        "udp" => Ok((Transport::Udp, false)),
+        "udp6" => Ok((Transport::Udp, true)),
+        _ => bail!(ErrorKind::InvalidAddr(netid.to_string())),
+    }
+}
+
+/// Format a socket address as a universal address.
+pub fn to_uaddr(addr: &SocketAddr) -> String {
+    let port = addr.port();
+    format!("{}.{}.{}", addr.ip(), port >> 8, port & 0xff)
+}
+
+/// Parse a universal address into a socket address.
+///
+/// Fails with `InvalidAddr` if the address is malformed.
+pub fn from_uaddr(uaddr: &str) -> Result<SocketAddr> {
+    let bad = || Error::from(ErrorKind::InvalidAddr(uaddr.to_string()));
+    let mut parts = uaddr.rsplitn(3, '.');
+
+    let lo: u8 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(bad)?;
+    let hi: u8 = parts.next().and_then(|p| p.parse().ok()).ok_or_else(bad)?;
+    let ip: IpAddr = parts.next().and_then(|p| p.parse().ok()).ok_or_else(bad)?;
+
+    Ok(SocketAddr::new(ip, (hi as u16) << 8 | lo as u16))
+}
+
+impl<Out: Write> Pack<Out> for Ipv4Addr {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_opaque_array(&self.octets(), 4, out)
+    }
+}
+
+impl<In: Read> Unpack<In> for Ipv4Addr {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut b = [0; 4];
+        let sz = unpack_opaque_array(input, &mut b, 4)?;
+        Ok((Ipv4Addr::from(b), sz))
+    }
+}
+
+impl<Out: Write> Pack<Out> for Ipv6Addr {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_opaque_array(&self.octets(), 16, out)
+    }
+}
+
+impl<In: Read> Unpack<In> for Ipv6Addr {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut b = [0; 16];
+        let sz = unpack_opaque_array(input, &mut b, 16)?;
+        Ok((Ipv6Addr::from(b), sz))
+    }
+}
+
+impl<Out: Write> Pack<Out> for IpAddr {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        match *self {
+            IpAddr::V4(ref a) => pack_opaque_flex(&a.octets(), Some(16), out),
+            IpAddr::V6(ref a) => pack_opaque_flex(&a.octets(), Some(16), out),
+        }
+    }
+}
+
+impl<In: Read> Unpack<In> for IpAddr {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz) = unpack_opaque_flex(input, Some(16))?;
+        let addr = match v.len() {
+            4 => {
+                let mut b = [0; 4];
+                b.copy_from_slice(&v);
+                IpAddr::V4(Ipv4Addr::from(b))
+            },
+            16 => {
+                let mut b = [0; 16];
+                b.copy_from_slice(&v);
+                IpAddr::V6(Ipv6Addr::from(b))
+            },
+            len => bail!(ErrorKind::InvalidLen(len)),
+        };
+        Ok((addr, sz))
+    }
+}
@@ -0,0 +1,896 @@
+//! NFS version 3 protocol types
+//!
+//! The common types and the arguments and results of the most used procedures from RFC 1813,
+//! so that NFS clients, servers and packet inspectors can share one set of definitions. Names
+//! follow the RFC's `.x` specification:
+//!
+//! ```text
+//! struct fattr3 {
+//!     ftype3     type;
+//!     mode3      mode;
+//!     uint32     nlink;
+//!     uid3       uid;
+//!     gid3       gid;
+//!     size3      size;
+//!     size3      used;
+//!     specdata3  rdev;
+//!     uint64     fsid;
+//!     fileid3    fileid;
+//!     nfstime3   atime;
+//!     nfstime3   mtime;
+//!     nfstime3   ctime;
+//! };
+//!
+//! union READ3res switch (nfsstat3 status) {
+//! case NFS3_OK:
+//!     READ3resok   resok;
+//! default:
+//!     READ3resfail resfail;
+//! };
+//! ```
+//!
+//! There are a few departures from what xdrgen would generate, to make the types easier to use:
+//!
+//! - unions switched on a `bool` (`post_op_attr`, `pre_op_attr` and `post_op_fh3`) are `Option`s,
+//!   which have the same encoding
+//! - the `*3res` unions are all `nfs3res`, which keeps the status of a failure along with its
+//!   `*3resfail` body
+//! - the `entryplus3` linked list in `dirlistplus3` is a `Vec`, encoded as the list it replaces
+//! - `filename3` and `nfspath3` are `Vec<u8>`: the RFC declares them as strings, but they hold
+//!   whatever bytes the server's filesystem uses for names, which needn't be UTF-8
+//!
+//! This module is only available with the `nfs3` feature.
+#![allow(non_camel_case_types)]
+
+use std::io::{Read, Write};
+
+use error::*;
+use super::{Pack, Unpack, pack_list, pack_opaque_array, pack_opaque_flex, unpack_list, unpack_opaque_array,
+            unpack_opaque_flex};
+
+/// RPC program number of NFS.
+pub const NFS_PROGRAM: u32 = 100003;
+/// RPC version number of NFS version 3.
+pub const NFS_V3: u32 = 3;
+
+pub const NFSPROC3_NULL: u32 = 0;
+pub const NFSPROC3_GETATTR: u32 = 1;
+pub const NFSPROC3_SETATTR: u32 = 2;
+pub const NFSPROC3_LOOKUP: u32 = 3;
+pub const NFSPROC3_ACCESS: u32 = 4;
+pub const NFSPROC3_READLINK: u32 = 5;
+pub const NFSPROC3_READ: u32 = 6;
+pub const NFSPROC3_WRITE: u32 = 7;
+pub const NFSPROC3_CREATE: u32 = 8;
+pub const NFSPROC3_MKDIR: u32 = 9;
+pub const NFSPROC3_SYMLINK: u32 = 10;
+pub const NFSPROC3_MKNOD: u32 = 11;
+pub const NFSPROC3_REMOVE: u32 = 12;
+pub const NFSPROC3_RMDIR: u32 = 13;
+pub const NFSPROC3_RENAME: u32 = 14;
+pub const NFSPROC3_LINK: u32 = 15;
+pub const NFSPROC3_READDIR: u32 = 16;
+pub const NFSPROC3_READDIRPLUS: u32 = 17;
+pub const NFSPROC3_FSSTAT: u32 = 18;
+pub const NFSPROC3_FSINFO: u32 = 19;
+pub const NFSPROC3_PATHCONF: u32 = 20;
+pub const NFSPROC3_COMMIT: u32 = 21;
+
+/// Maximum size of a file handle.
+pub const NFS3_FHSIZE: usize = 64;
+/// Size of a `cookieverf3`.
+pub const NFS3_COOKIEVERFSIZE: usize = 8;
+/// Size of a `createverf3`.
+pub const NFS3_CREATEVERFSIZE: usize = 8;
+/// Size of a `writeverf3`.
+pub const NFS3_WRITEVERFSIZE: usize = 8;
+
+pub type fileid3 = u64;
+pub type cookie3 = u64;
+pub type uid3 = u32;
+pub type gid3 = u32;
+pub type size3 = u64;
+pub type offset3 = u64;
+pub type mode3 = u32;
+pub type count3 = u32;
+pub type filename3 = Vec<u8>;
+pub type nfspath3 = Vec<u8>;
+pub type cookieverf3 = [u8; NFS3_COOKIEVERFSIZE];
+pub type createverf3 = [u8; NFS3_CREATEVERFSIZE];
+pub type writeverf3 = [u8; NFS3_WRITEVERFSIZE];
+
+xdr_enum! {
+    /// Status of an NFS procedure.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum nfsstat3 {
+        NFS3_OK = 0,
+        NFS3ERR_PERM = 1,
+        NFS3ERR_NOENT = 2,
+        NFS3ERR_IO = 5,
+        NFS3ERR_NXIO = 6,
+        NFS3ERR_ACCES = 13,
+        NFS3ERR_EXIST = 17,
+        NFS3ERR_XDEV = 18,
+        NFS3ERR_NODEV = 19,
+        NFS3ERR_NOTDIR = 20,
+        NFS3ERR_ISDIR = 21,
+        NFS3ERR_INVAL = 22,
+        NFS3ERR_FBIG = 27,
+        NFS3ERR_NOSPC = 28,
+        NFS3ERR_ROFS = 30,
+        NFS3ERR_MLINK = 31,
+        NFS3ERR_NAMETOOLONG = 63,
+        NFS3ERR_NOTEMPTY = 66,
+        NFS3ERR_DQUOT = 69,
+        NFS3ERR_STALE = 70,
+        NFS3ERR_REMOTE = 71,
+        NFS3ERR_BADHANDLE = 10001,
+        NFS3ERR_NOT_SYNC = 10002,
+        NFS3ERR_BAD_COOKIE = 10003,
+        NFS3ERR_NOTSUPP = 10004,
+        NFS3ERR_TOOSMALL = 10005,
+        NFS3ERR_SERVERFAULT = 10006,
+        NFS3ERR_BADTYPE = 10007,
+        NFS3ERR_JUKEBOX = 10008,
+    }
+}
+
+xdr_enum! {
+    /// Type of a file.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum ftype3 {
+        NF3REG = 1,
+        NF3DIR = 2,
+        NF3BLK = 3,
+        NF3CHR = 4,
+        NF3LNK = 5,
+        NF3SOCK = 6,
+        NF3FIFO = 7,
+    }
+}
+
+xdr_enum! {
+    /// How much of a write must be committed to stable storage before replying.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum stable_how {
+        UNSTABLE = 0,
+        DATA_SYNC = 1,
+        FILE_SYNC = 2,
+    }
+}
+
+/// Major and minor device numbers of a block or character special file.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct specdata3 {
+    pub specdata1: u32,
+    pub specdata2: u32,
+}
+
+impl<Out: Write> Pack<Out> for specdata3 {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.specdata1.pack(out)? + self.specdata2.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for specdata3 {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = specdata3 {
+            specdata1: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            specdata2: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// File handle: an opaque value of up to `NFS3_FHSIZE` bytes.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct nfs_fh3 {
+    pub data: Vec<u8>,
+}
+
+impl<Out: Write> Pack<Out> for nfs_fh3 {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_opaque_flex(&self.data, Some(NFS3_FHSIZE), out)
+    }
+}
+
+impl<In: Read> Unpack<In> for nfs_fh3 {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (data, sz) = unpack_opaque_flex(input, Some(NFS3_FHSIZE))?;
+        Ok((nfs_fh3 { data }, sz))
+    }
+}
+
+/// Time since the Unix epoch.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct nfstime3 {
+    pub seconds: u32,
+    pub nseconds: u32,
+}
+
+impl<Out: Write> Pack<Out> for nfstime3 {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.seconds.pack(out)? + self.nseconds.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for nfstime3 {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = nfstime3 {
+            seconds: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            nseconds: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// File attributes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct fattr3 {
+    pub type_: ftype3,
+    pub mode: mode3,
+    pub nlink: u32,
+    pub uid: uid3,
+    pub gid: gid3,
+    pub size: size3,
+    pub used: size3,
+    pub rdev: specdata3,
+    pub fsid: u64,
+    pub fileid: fileid3,
+    pub atime: nfstime3,
+    pub mtime: nfstime3,
+    pub ctime: nfstime3,
+}
+
+impl<Out: Write> Pack<Out> for fattr3 {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += self.type_.pack(out)?;
+        sz += self.mode.pack(out)?;
+        sz += self.nlink.pack(out)?;
+        sz += self.uid.pack(out)?;
+        sz += self.gid.pack(out)?;
+        sz += self.size.pack(out)?;
+        sz += self.used.pack(out)?;
+        sz += self.rdev.pack(out)?;
+        sz += self.fsid.pack(out)?;
+        sz += self.fileid.pack(out)?;
+        sz += self.atime.pack(out)?;
+        sz += self.mtime.pack(out)?;
+        sz += self.ctime.pack(out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for fattr3 {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = fattr3 {
+            type_: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            mode: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            nlink: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            uid: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            gid: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            size: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            used: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            rdev: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            fsid: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            fileid: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            atime: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            mtime: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            ctime: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Attributes returned after an operation, if the server has them.
+pub type post_op_attr = Option<fattr3>;
+
+/// The attributes checked for changes by weak cache consistency.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct wcc_attr {
+    pub size: size3,
+    pub mtime: nfstime3,
+    pub ctime: nfstime3,
+}
+
+impl<Out: Write> Pack<Out> for wcc_attr {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.size.pack(out)? + self.mtime.pack(out)? + self.ctime.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for wcc_attr {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = wcc_attr {
+            size: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            mtime: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            ctime: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Attributes from before an operation, if the server has them.
+pub type pre_op_attr = Option<wcc_attr>;
+
+/// Weak cache consistency data: the attributes before and after a modifying operation.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct wcc_data {
+    pub before: pre_op_attr,
+    pub after: post_op_attr,
+}
+
+impl<Out: Write> Pack<Out> for wcc_data {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.before.pack(out)? + self.after.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for wcc_data {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = wcc_data {
+            before: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            after: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// File handle returned by an operation, if the server has one.
+pub type post_op_fh3 = Option<nfs_fh3>;
+
+/// A name within a directory.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct diropargs3 {
+    pub dir: nfs_fh3,
+    pub name: filename3,
+}
+
+impl<Out: Write> Pack<Out> for diropargs3 {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.dir.pack(out)? + pack_opaque_flex(&self.name, None, out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for diropargs3 {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = diropargs3 {
+            dir: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            name: { let (v, fsz) = unpack_opaque_flex(input, None)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// The result of a procedure: its `*3resok` body when the status is `NFS3_OK`, and otherwise the
+/// status and its `*3resfail` body.
+///
+/// Packing a `Fail` with a status of `NFS3_OK` fails with `InvalidCase`, since it would unpack as
+/// an `Ok`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum nfs3res<T, F> {
+    Ok(T),
+    Fail(nfsstat3, F),
+}
+
+impl<T, F> nfs3res<T, F> {
+    /// Return the status.
+    pub fn status(&self) -> nfsstat3 {
+        match *self {
+            nfs3res::Ok(_) => nfsstat3::NFS3_OK,
+            nfs3res::Fail(stat, _) => stat,
+        }
+    }
+
+    /// Convert to a `Result`.
+    pub fn into_result(self) -> ::std::result::Result<T, (nfsstat3, F)> {
+        match self {
+            nfs3res::Ok(ok) => Ok(ok),
+            nfs3res::Fail(stat, fail) => Err((stat, fail)),
+        }
+    }
+}
+
+impl<Out: Write, T: Pack<Out>, F: Pack<Out>> Pack<Out> for nfs3res<T, F> {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        match *self {
+            nfs3res::Ok(ref ok) => Ok(nfsstat3::NFS3_OK.pack(out)? + ok.pack(out)?),
+            nfs3res::Fail(nfsstat3::NFS3_OK, _) => Err(Error::invalidcase(nfsstat3::NFS3_OK as i32)),
+            nfs3res::Fail(stat, ref fail) => Ok(stat.pack(out)? + fail.pack(out)?),
+        }
+    }
+}
+
+impl<In: Read, T: Unpack<In>, F: Unpack<In>> Unpack<In> for nfs3res<T, F> {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (stat, ssz) = nfsstat3::unpack(input)?;
+
+        if stat == nfsstat3::NFS3_OK {
+            let (ok, sz) = T::unpack(input)?;
+            Ok((nfs3res::Ok(ok), ssz + sz))
+        } else {
+            let (fail, sz) = F::unpack(input)?;
+            Ok((nfs3res::Fail(stat, fail), ssz + sz))
+        }
+    }
+}
+
+/// Arguments of `GETATTR`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct GETATTR3args {
+    pub object: nfs_fh3,
+}
+
+impl<Out: Write> Pack<Out> for GETATTR3args {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.object.pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for GETATTR3args {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (object, sz) = Unpack::unpack(input)?;
+        Ok((GETATTR3args { object }, sz))
+    }
+}
+
+/// Successful result of `GETATTR`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct GETATTR3resok {
+    pub obj_attributes: fattr3,
+}
+
+impl<Out: Write> Pack<Out> for GETATTR3resok {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.obj_attributes.pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for GETATTR3resok {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (obj_attributes, sz) = Unpack::unpack(input)?;
+        Ok((GETATTR3resok { obj_attributes }, sz))
+    }
+}
+
+/// Result of `GETATTR`. Failures have no body.
+pub type GETATTR3res = nfs3res<GETATTR3resok, ()>;
+
+/// Arguments of `LOOKUP`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct LOOKUP3args {
+    pub what: diropargs3,
+}
+
+impl<Out: Write> Pack<Out> for LOOKUP3args {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.what.pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for LOOKUP3args {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (what, sz) = Unpack::unpack(input)?;
+        Ok((LOOKUP3args { what }, sz))
+    }
+}
+
+/// Successful result of `LOOKUP`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct LOOKUP3resok {
+    pub object: nfs_fh3,
+    pub obj_attributes: post_op_attr,
+    pub dir_attributes: post_op_attr,
+}
+
+impl<Out: Write> Pack<Out> for LOOKUP3resok {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.object.pack(out)? + self.obj_attributes.pack(out)? + self.dir_attributes.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for LOOKUP3resok {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = LOOKUP3resok {
+            object: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            obj_attributes: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            dir_attributes: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Failed result of `LOOKUP`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct LOOKUP3resfail {
+    pub dir_attributes: post_op_attr,
+}
+
+impl<Out: Write> Pack<Out> for LOOKUP3resfail {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.dir_attributes.pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for LOOKUP3resfail {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (dir_attributes, sz) = Unpack::unpack(input)?;
+        Ok((LOOKUP3resfail { dir_attributes }, sz))
+    }
+}
+
+/// Result of `LOOKUP`.
+pub type LOOKUP3res = nfs3res<LOOKUP3resok, LOOKUP3resfail>;
+
+/// Arguments of `READ`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct READ3args {
+    pub file: nfs_fh3,
+    pub offset: offset3,
+    pub count: count3,
+}
+
+impl<Out: Write> Pack<Out> for READ3args {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.file.pack(out)? + self.offset.pack(out)? + self.count.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for READ3args {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = READ3args {
+            file: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            offset: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            count: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Successful result of `READ`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct READ3resok {
+    pub file_attributes: post_op_attr,
+    pub count: count3,
+    pub eof: bool,
+    pub data: Vec<u8>,
+}
+
+impl<Out: Write> Pack<Out> for READ3resok {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += self.file_attributes.pack(out)?;
+        sz += self.count.pack(out)?;
+        sz += self.eof.pack(out)?;
+        sz += pack_opaque_flex(&self.data, None, out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for READ3resok {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = READ3resok {
+            file_attributes: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            count: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            eof: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            data: { let (v, fsz) = unpack_opaque_flex(input, None)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Failed result of `READ`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct READ3resfail {
+    pub file_attributes: post_op_attr,
+}
+
+impl<Out: Write> Pack<Out> for READ3resfail {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.file_attributes.pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for READ3resfail {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (file_attributes, sz) = Unpack::unpack(input)?;
+        Ok((READ3resfail { file_attributes }, sz))
+    }
+}
+
+/// Result of `READ`.
+pub type READ3res = nfs3res<READ3resok, READ3resfail>;
+
+/// Arguments of `WRITE`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct WRITE3args {
+    pub file: nfs_fh3,
+    pub offset: offset3,
+    pub count: count3,
+    pub stable: stable_how,
+    pub data: Vec<u8>,
+}
+
+impl<Out: Write> Pack<Out> for WRITE3args {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += self.file.pack(out)?;
+        sz += self.offset.pack(out)?;
+        sz += self.count.pack(out)?;
+        sz += self.stable.pack(out)?;
+        sz += pack_opaque_flex(&self.data, None, out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for WRITE3args {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = WRITE3args {
+            file: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            offset: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            count: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            stable: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            data: { let (v, fsz) = unpack_opaque_flex(input, None)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Successful result of `WRITE`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct WRITE3resok {
+    pub file_wcc: wcc_data,
+    pub count: count3,
+    pub committed: stable_how,
+    pub verf: writeverf3,
+}
+
+impl<Out: Write> Pack<Out> for WRITE3resok {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += self.file_wcc.pack(out)?;
+        sz += self.count.pack(out)?;
+        sz += self.committed.pack(out)?;
+        sz += pack_opaque_array(&self.verf, NFS3_WRITEVERFSIZE, out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for WRITE3resok {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = WRITE3resok {
+            file_wcc: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            count: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            committed: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            verf: {
+                let mut v = [0; NFS3_WRITEVERFSIZE];
+                sz += unpack_opaque_array(input, &mut v, NFS3_WRITEVERFSIZE)?;
+                v
+            },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Failed result of `WRITE`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct WRITE3resfail {
+    pub file_wcc: wcc_data,
+}
+
+impl<Out: Write> Pack<Out> for WRITE3resfail {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.file_wcc.pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for WRITE3resfail {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (file_wcc, sz) = Unpack::unpack(input)?;
+        Ok((WRITE3resfail { file_wcc }, sz))
+    }
+}
+
+/// Result of `WRITE`.
+pub type WRITE3res = nfs3res<WRITE3resok, WRITE3resfail>;
+
+/// Arguments of `READDIRPLUS`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct READDIRPLUS3args {
+    pub dir: nfs_fh3,
+    pub cookie: cookie3,
+    pub cookieverf: cookieverf3,
+    pub dircount: count3,
+    pub maxcount: count3,
+}
+
+impl<Out: Write> Pack<Out> for READDIRPLUS3args {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += self.dir.pack(out)?;
+        sz += self.cookie.pack(out)?;
+        sz += pack_opaque_array(&self.cookieverf, NFS3_COOKIEVERFSIZE, out)?;
+        sz += self.dircount.pack(out)?;
+        sz += self.maxcount.pack(out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for READDIRPLUS3args {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = READDIRPLUS3args {
+            dir: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            cookie: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            cookieverf: {
+                let mut v = [0; NFS3_COOKIEVERFSIZE];
+                sz += unpack_opaque_array(input, &mut v, NFS3_COOKIEVERFSIZE)?;
+                v
+            },
+            dircount: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            maxcount: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// A directory entry returned by `READDIRPLUS`.
+///
+/// This is packed without the `nextentry` link, which is handled by `dirlistplus3`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct entryplus3 {
+    pub fileid: fileid3,
+    pub name: filename3,
+    pub cookie: cookie3,
+    pub name_attributes: post_op_attr,
+    pub name_handle: post_op_fh3,
+}
+
+impl<Out: Write> Pack<Out> for entryplus3 {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += self.fileid.pack(out)?;
+        sz += pack_opaque_flex(&self.name, None, out)?;
+        sz += self.cookie.pack(out)?;
+        sz += self.name_attributes.pack(out)?;
+        sz += self.name_handle.pack(out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for entryplus3 {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = entryplus3 {
+            fileid: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            name: { let (v, fsz) = unpack_opaque_flex(input, None)?; sz += fsz; v },
+            cookie: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            name_attributes: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            name_handle: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// A batch of directory entries, and whether it reaches the end of the directory.
+///
+/// The entries are encoded as a linked list, with `pack_list`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct dirlistplus3 {
+    pub entries: Vec<entryplus3>,
+    pub eof: bool,
+}
+
+impl<Out: Write> Pack<Out> for dirlistplus3 {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(pack_list(&self.entries, None, out)? + self.eof.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for dirlistplus3 {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = dirlistplus3 {
+            entries: { let (v, fsz) = unpack_list(input, None)?; sz += fsz; v },
+            eof: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Successful result of `READDIRPLUS`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct READDIRPLUS3resok {
+    pub dir_attributes: post_op_attr,
+    pub cookieverf: cookieverf3,
+    pub reply: dirlistplus3,
+}
+
+impl<Out: Write> Pack<Out> for READDIRPLUS3resok {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += self.dir_attributes.pack(out)?;
+        sz += pack_opaque_array(&self.cookieverf, NFS3_COOKIEVERFSIZE, out)?;
+        sz += self.reply.pack(out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for READDIRPLUS3resok {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = READDIRPLUS3resok {
+            dir_attributes: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            cookieverf: {
+                let mut v = [0; NFS3_COOKIEVERFSIZE];
+                sz += unpack_opaque_array(input, &mut v, NFS3_COOKIEVERFSIZE)?;
+                v
+            },
+            reply: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Failed result of `READDIRPLUS`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct READDIRPLUS3resfail {
+    pub dir_attributes: post_op_attr,
+}
+
+impl<Out: Write> Pack<Out> for READDIRPLUS3resfail {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.dir_attributes.pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for READDIRPLUS3resfail {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (dir_attributes, sz) = Unpack::unpack(input)?;
+        Ok((READDIRPLUS3resfail { dir_attributes }, sz))
+    }
+}
+
+/// Result of `READDIRPLUS`.
+pub type READDIRPLUS3res = nfs3res<READDIRPLUS3resok, READDIRPLUS3resfail>;
@@ -0,0 +1,515 @@
+//! NFS version 4 encoding helpers
+//!
+//! NFSv4 (RFC 7530) has two encodings which don't map well onto a generated type:
+//!
+//! - attributes are sent as a `bitmap4` of attribute numbers followed by an opaque `attrlist4`
+//!   holding the values of the set attributes packed in increasing order. `fattr4::push` and
+//!   `fattr4::reader` build and walk that list.
+//! - a `COMPOUND` call is a sequence of operations, each an operation number followed by its
+//!   arguments, and the reply is the matching sequence of results up to the first one which
+//!   failed. `CompoundBuilder` and `CompoundReader` pack and unpack the sequence one operation at
+//!   a time, recording the length of each operation's encoding.
+//!
+//! ```
+//! use xdr_codec::nfs4::*;
+//!
+//! let mut attrs = fattr4::new();
+//! attrs.push(FATTR4_TYPE, &1u32).unwrap()
+//!      .push(FATTR4_SIZE, &4096u64).unwrap();
+//!
+//! let mut call = CompoundBuilder::args("setattr", 0);
+//! call.op(OP_PUTROOTFH, &()).unwrap()
+//!     .op(OP_GETATTR, &attrs.attrmask).unwrap();
+//! assert_eq!(call.ops(), &[(OP_PUTROOTFH, 4), (OP_GETATTR, 12)]);
+//!
+//! let mut r = attrs.reader();
+//! assert_eq!(r.next_attr(), Some(FATTR4_TYPE));
+//! assert_eq!(r.value::<u32>().unwrap(), 1);
+//! assert_eq!(r.next_attr(), Some(FATTR4_SIZE));
+//! assert_eq!(r.value::<u64>().unwrap(), 4096);
+//! r.finish().unwrap();
+//! ```
+//!
+//! The operations' own arguments and results are not defined here.
+//!
+//! This module is only available with the `nfs4` feature.
+#![allow(non_camel_case_types)]
+
+use std::io::{Read, Write};
+use std::iter::FromIterator;
+
+use error::*;
+use super::{Pack, Unpack, pack_flex, pack_opaque_flex, pack_string, unpack_flex,
+            unpack_opaque_flex, unpack_string, xdr_len};
+
+/// RPC program number of NFS.
+pub const NFS4_PROGRAM: u32 = 100003;
+/// RPC version number of NFS version 4.
+pub const NFS_V4: u32 = 4;
+
+pub const NFSPROC4_NULL: u32 = 0;
+pub const NFSPROC4_COMPOUND: u32 = 1;
+
+/// Status of an operation or `COMPOUND`. RFC 7530 lists the error values; only success is
+/// needed here.
+pub type nfsstat4 = u32;
+
+pub const NFS4_OK: nfsstat4 = 0;
+
+/// Operation number within a `COMPOUND`.
+pub type nfs_opnum4 = u32;
+
+pub const OP_ACCESS: nfs_opnum4 = 3;
+pub const OP_CLOSE: nfs_opnum4 = 4;
+pub const OP_COMMIT: nfs_opnum4 = 5;
+pub const OP_CREATE: nfs_opnum4 = 6;
+pub const OP_DELEGPURGE: nfs_opnum4 = 7;
+pub const OP_DELEGRETURN: nfs_opnum4 = 8;
+pub const OP_GETATTR: nfs_opnum4 = 9;
+pub const OP_GETFH: nfs_opnum4 = 10;
+pub const OP_LINK: nfs_opnum4 = 11;
+pub const OP_LOCK: nfs_opnum4 = 12;
+pub const OP_LOCKT: nfs_opnum4 = 13;
+pub const OP_LOCKU: nfs_opnum4 = 14;
+pub const OP_LOOKUP: nfs_opnum4 = 15;
+pub const OP_LOOKUPP: nfs_opnum4 = 16;
+pub const OP_NVERIFY: nfs_opnum4 = 17;
+pub const OP_OPEN: nfs_opnum4 = 18;
+pub const OP_OPENATTR: nfs_opnum4 = 19;
+pub const OP_OPEN_CONFIRM: nfs_opnum4 = 20;
+pub const OP_OPEN_DOWNGRADE: nfs_opnum4 = 21;
+pub const OP_PUTFH: nfs_opnum4 = 22;
+pub const OP_PUTPUBFH: nfs_opnum4 = 23;
+pub const OP_PUTROOTFH: nfs_opnum4 = 24;
+pub const OP_READ: nfs_opnum4 = 25;
+pub const OP_READDIR: nfs_opnum4 = 26;
+pub const OP_READLINK: nfs_opnum4 = 27;
+pub const OP_REMOVE: nfs_opnum4 = 28;
+pub const OP_RENAME: nfs_opnum4 = 29;
+pub const OP_RENEW: nfs_opnum4 = 30;
+pub const OP_RESTOREFH: nfs_opnum4 = 31;
+pub const OP_SAVEFH: nfs_opnum4 = 32;
+pub const OP_SECINFO: nfs_opnum4 = 33;
+pub const OP_SETATTR: nfs_opnum4 = 34;
+pub const OP_SETCLIENTID: nfs_opnum4 = 35;
+pub const OP_SETCLIENTID_CONFIRM: nfs_opnum4 = 36;
+pub const OP_VERIFY: nfs_opnum4 = 37;
+pub const OP_WRITE: nfs_opnum4 = 38;
+pub const OP_RELEASE_LOCKOWNER: nfs_opnum4 = 39;
+pub const OP_ILLEGAL: nfs_opnum4 = 10044;
+
+pub const FATTR4_SUPPORTED_ATTRS: u32 = 0;
+pub const FATTR4_TYPE: u32 = 1;
+pub const FATTR4_FH_EXPIRE_TYPE: u32 = 2;
+pub const FATTR4_CHANGE: u32 = 3;
+pub const FATTR4_SIZE: u32 = 4;
+pub const FATTR4_LINK_SUPPORT: u32 = 5;
+pub const FATTR4_SYMLINK_SUPPORT: u32 = 6;
+pub const FATTR4_NAMED_ATTR: u32 = 7;
+pub const FATTR4_FSID: u32 = 8;
+pub const FATTR4_UNIQUE_HANDLES: u32 = 9;
+pub const FATTR4_LEASE_TIME: u32 = 10;
+pub const FATTR4_RDATTR_ERROR: u32 = 11;
+pub const FATTR4_FILEHANDLE: u32 = 19;
+pub const FATTR4_FILEID: u32 = 20;
+pub const FATTR4_MODE: u32 = 33;
+pub const FATTR4_NUMLINKS: u32 = 35;
+pub const FATTR4_OWNER: u32 = 36;
+pub const FATTR4_OWNER_GROUP: u32 = 37;
+pub const FATTR4_SPACE_USED: u32 = 45;
+pub const FATTR4_TIME_ACCESS: u32 = 47;
+pub const FATTR4_TIME_METADATA: u32 = 52;
+pub const FATTR4_TIME_MODIFY: u32 = 53;
+pub const FATTR4_MOUNTED_ON_FILEID: u32 = 55;
+
+/// A set of attribute numbers: bit `n` is bit `n % 32` of word `n / 32`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct bitmap4(pub Vec<u32>);
+
+impl bitmap4 {
+    /// Create an empty bitmap.
+    pub fn new() -> bitmap4 {
+        bitmap4::default()
+    }
+
+    /// Set `bit`, adding words as needed.
+    pub fn set(&mut self, bit: u32) -> &mut Self {
+        let word = (bit / 32) as usize;
+
+        if self.0.len() <= word {
+            self.0.resize(word + 1, 0);
+        }
+        self.0[word] |= 1 << (bit % 32);
+
+        self
+    }
+
+    /// Clear `bit`, dropping any trailing words which become zero.
+    pub fn clear(&mut self, bit: u32) -> &mut Self {
+        if let Some(w) = self.0.get_mut((bit / 32) as usize) {
+            *w &= !(1 << (bit % 32));
+        }
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+
+        self
+    }
+
+    /// Return whether `bit` is set.
+    pub fn is_set(&self, bit: u32) -> bool {
+        match self.0.get((bit / 32) as usize) {
+            Some(w) => w & (1 << (bit % 32)) != 0,
+            None => false,
+        }
+    }
+
+    /// Return whether no bits are set.
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&w| w == 0)
+    }
+
+    /// Iterate over the set bits in increasing order.
+    pub fn bits<'a>(&'a self) -> impl Iterator<Item = u32> + 'a {
+        self.0.iter().enumerate().flat_map(|(i, &w)| {
+            (0..32).filter(move |b| w & (1 << b) != 0).map(move |b| i as u32 * 32 + b)
+        })
+    }
+}
+
+impl FromIterator<u32> for bitmap4 {
+    fn from_iter<I: IntoIterator<Item = u32>>(bits: I) -> bitmap4 {
+        let mut map = bitmap4::new();
+        for b in bits {
+            map.set(b);
+        }
+        map
+    }
+}
+
+impl<Out: Write> Pack<Out> for bitmap4 {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_flex(&self.0, None, out)
+    }
+}
+
+impl<In: Read> Unpack<In> for bitmap4 {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (v, sz) = unpack_flex(input, None)?;
+        Ok((bitmap4(v), sz))
+    }
+}
+
+/// A set of attributes: the attribute numbers present, and their packed values.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct fattr4 {
+    pub attrmask: bitmap4,
+    pub attr_vals: Vec<u8>,
+}
+
+impl fattr4 {
+    /// Create an empty attribute set.
+    pub fn new() -> fattr4 {
+        fattr4::default()
+    }
+
+    /// Append the value of attribute `attr`.
+    ///
+    /// Values are packed in attribute number order, so `attr` must be greater than any attribute
+    /// already added; otherwise this fails with `InvalidValue`.
+    pub fn push<T>(&mut self, attr: u32, val: &T) -> Result<&mut Self>
+        where T: ?Sized + Pack<Vec<u8>>
+    {
+        if let Some(last) = self.attrmask.bits().last() {
+            if attr <= last {
+                bail!(ErrorKind::InvalidValue(format!("attribute {} added after attribute {}", attr, last)));
+            }
+        }
+
+        let start = self.attr_vals.len();
+        if let Err(e) = val.pack(&mut self.attr_vals) {
+            self.attr_vals.truncate(start);
+            return Err(e);
+        }
+        self.attrmask.set(attr);
+
+        Ok(self)
+    }
+
+    /// Return a reader for the attribute values.
+    pub fn reader(&self) -> AttrReader<'_> {
+        AttrReader { attrs: self.attrmask.bits().collect(), next: 0, input: &self.attr_vals }
+    }
+}
+
+impl<Out: Write> Pack<Out> for fattr4 {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.attrmask.pack(out)? + pack_opaque_flex(&self.attr_vals, None, out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for fattr4 {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = fattr4 {
+            attrmask: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            attr_vals: { let (v, fsz) = unpack_opaque_flex(input, None)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Walks the values of an `fattr4` in attribute number order.
+///
+/// The type of each value depends on the attribute, so for each attribute number returned by
+/// `next_attr` the caller unpacks its value with `value`. An attribute whose type isn't known
+/// can't be skipped, since its length isn't known either.
+#[derive(Debug, Clone)]
+pub struct AttrReader<'a> {
+    attrs: Vec<u32>,
+    next: usize,
+    input: &'a [u8],
+}
+
+impl<'a> AttrReader<'a> {
+    /// Return the number of the next attribute, or `None` after the last.
+    pub fn next_attr(&mut self) -> Option<u32> {
+        let attr = self.attrs.get(self.next).cloned();
+        if attr.is_some() {
+            self.next += 1;
+        }
+        attr
+    }
+
+    /// Unpack the value of the attribute returned by `next_attr`.
+    pub fn value<T: Unpack<&'a [u8]>>(&mut self) -> Result<T> {
+        T::unpack(&mut self.input).map(|(v, _)| v)
+    }
+
+    /// Check that every attribute has been read, and that there are no values left over.
+    pub fn finish(self) -> Result<()> {
+        if let Some(attr) = self.attrs.get(self.next) {
+            bail!(ErrorKind::InvalidValue(format!("attribute {} not read", attr)));
+        }
+        if !self.input.is_empty() {
+            bail!(ErrorKind::InvalidLen(self.input.len()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Args { minorversion: u32 },
+    Res { status: nfsstat4 },
+}
+
+/// Builds the arguments or results of a `COMPOUND`.
+///
+/// ```text
+/// struct COMPOUND4args {            struct COMPOUND4res {
+///     utf8str_cs tag;                   nfsstat4 status;
+///     uint32_t minorversion;            utf8str_cs tag;
+///     nfs_argop4 argarray<>;            nfs_resop4 resarray<>;
+/// };                                };
+/// ```
+///
+/// Each operation is added with `op` as its number and the body of its `nfs_argop4` or
+/// `nfs_resop4` arm, and the builder packs as the whole `COMPOUND4args` or `COMPOUND4res`.
+#[derive(Debug, Clone)]
+pub struct CompoundBuilder {
+    kind: Kind,
+    tag: String,
+    ops: Vec<u8>,
+    lens: Vec<(nfs_opnum4, usize)>,
+}
+
+impl CompoundBuilder {
+    /// Build the arguments of a call.
+    pub fn args(tag: &str, minorversion: u32) -> CompoundBuilder {
+        CompoundBuilder::new(Kind::Args { minorversion }, tag)
+    }
+
+    /// Build the results of a call. The status of the `COMPOUND` is the status of the last
+    /// operation, which is the first word of every operation's result.
+    pub fn res(tag: &str) -> CompoundBuilder {
+        CompoundBuilder::new(Kind::Res { status: NFS4_OK }, tag)
+    }
+
+    fn new(kind: Kind, tag: &str) -> CompoundBuilder {
+        CompoundBuilder { kind, tag: tag.to_string(), ops: Vec::new(), lens: Vec::new() }
+    }
+
+    /// Append operation `op` with its arguments or result `body`.
+    ///
+    /// A result which doesn't start with a status fails with `InvalidLen`.
+    pub fn op<T>(&mut self, op: nfs_opnum4, body: &T) -> Result<&mut Self>
+        where T: ?Sized + Pack<Vec<u8>>
+    {
+        let start = self.ops.len();
+        op.pack(&mut self.ops)?;
+        if let Err(e) = body.pack(&mut self.ops) {
+            self.ops.truncate(start);
+            return Err(e);
+        }
+        let sz = self.ops.len() - start;
+
+        if let Kind::Res { ref mut status } = self.kind {
+            let mut body = &self.ops[start + 4..];
+            match nfsstat4::unpack(&mut body) {
+                Ok((s, _)) => *status = s,
+                Err(_) => {
+                    self.ops.truncate(start);
+                    bail!(ErrorKind::InvalidLen(sz - 4));
+                }
+            }
+        }
+        self.lens.push((op, sz));
+
+        Ok(self)
+    }
+
+    /// Return each operation added so far and the length of its encoding, including the
+    /// operation number.
+    pub fn ops(&self) -> &[(nfs_opnum4, usize)] {
+        &self.lens
+    }
+
+    /// Return the status of a `COMPOUND` result, or `None` for arguments.
+    pub fn status(&self) -> Option<nfsstat4> {
+        match self.kind {
+            Kind::Res { status } => Some(status),
+            Kind::Args { .. } => None,
+        }
+    }
+}
+
+impl<Out: Write> Pack<Out> for CompoundBuilder {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        match self.kind {
+            Kind::Args { minorversion } => {
+                sz += pack_string(&self.tag, None, out)?;
+                sz += minorversion.pack(out)?;
+            }
+            Kind::Res { status } => {
+                sz += status.pack(out)?;
+                sz += pack_string(&self.tag, None, out)?;
+            }
+        }
+        sz += xdr_len(self.lens.len())?.pack(out)?;
+        out.write_all(&self.ops)?;
+
+        Ok(sz + self.ops.len())
+    }
+}
+
+/// Unpacks the arguments or results of a `COMPOUND` one operation at a time.
+///
+/// The header is read when the reader is created. For each operation, `peek` returns its number
+/// so the caller can choose the type of its body, which `op` unpacks.
+#[derive(Debug, Clone)]
+pub struct CompoundReader<'a> {
+    kind: Kind,
+    tag: String,
+    remaining: usize,
+    input: &'a [u8],
+    lens: Vec<(nfs_opnum4, usize)>,
+}
+
+impl<'a> CompoundReader<'a> {
+    /// Read a `COMPOUND4args`.
+    pub fn args(mut input: &'a [u8]) -> Result<CompoundReader<'a>> {
+        let (tag, _) = unpack_string(&mut input, None)?;
+        let (minorversion, _) = u32::unpack(&mut input)?;
+        CompoundReader::new(Kind::Args { minorversion }, tag, input)
+    }
+
+    /// Read a `COMPOUND4res`.
+    pub fn res(mut input: &'a [u8]) -> Result<CompoundReader<'a>> {
+        let (status, _) = nfsstat4::unpack(&mut input)?;
+        let (tag, _) = unpack_string(&mut input, None)?;
+        CompoundReader::new(Kind::Res { status }, tag, input)
+    }
+
+    fn new(kind: Kind, tag: String, mut input: &'a [u8]) -> Result<CompoundReader<'a>> {
+        let (remaining, _) = usize::unpack(&mut input)?;
+        Ok(CompoundReader { kind, tag, remaining, input, lens: Vec::new() })
+    }
+
+    /// Return the tag.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Return the minor version of arguments, or `None` for results.
+    pub fn minorversion(&self) -> Option<u32> {
+        match self.kind {
+            Kind::Args { minorversion } => Some(minorversion),
+            Kind::Res { .. } => None,
+        }
+    }
+
+    /// Return the status of results, or `None` for arguments.
+    pub fn status(&self) -> Option<nfsstat4> {
+        match self.kind {
+            Kind::Res { status } => Some(status),
+            Kind::Args { .. } => None,
+        }
+    }
+
+    /// Return the number of operations which haven't been read.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    /// Return the number of the next operation without consuming it, or `None` if they have all
+    /// been read.
+    pub fn peek(&self) -> Result<Option<nfs_opnum4>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        let mut input = self.input;
+        nfs_opnum4::unpack(&mut input).map(|(op, _)| Some(op))
+    }
+
+    /// Unpack the next operation, which must be `op`, returning its body.
+    ///
+    /// A different operation fails with `InvalidCase`, and reading past the last operation with
+    /// `InvalidLen`. On error nothing is consumed.
+    pub fn op<T: Unpack<&'a [u8]>>(&mut self, op: nfs_opnum4) -> Result<T> {
+        match self.peek()? {
+            None => bail!(ErrorKind::InvalidLen(self.lens.len())),
+            Some(o) if o != op => return Err(Error::invalidcase(o as i32)),
+            Some(_) => (),
+        }
+
+        let mut input = &self.input[4..];
+        let (body, sz) = T::unpack(&mut input)?;
+
+        self.input = input;
+        self.remaining -= 1;
+        self.lens.push((op, 4 + sz));
+
+        Ok(body)
+    }
+
+    /// Return each operation read so far and the length of its encoding, including the operation
+    /// number.
+    pub fn ops(&self) -> &[(nfs_opnum4, usize)] {
+        &self.lens
+    }
+
+    /// Check that every operation has been read, and that there is no data left over.
+    pub fn finish(self) -> Result<()> {
+        if self.remaining != 0 {
+            bail!(ErrorKind::InvalidLen(self.lens.len() + self.remaining));
+        }
+        if !self.input.is_empty() {
+            bail!(ErrorKind::InvalidLen(self.input.len()));
+        }
+        Ok(())
+    }
+}
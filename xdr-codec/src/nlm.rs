@@ -0,0 +1,489 @@
+//! Network Lock Manager version 4 protocol types
+//!
+//! NLM provides advisory file locking for NFS version 3. These are the version 4 types from the
+//! X/Open XNFS specification (summarized in RFC 1813), using the `.x` names:
+//!
+//! ```text
+//! union nlm4_testrply switch (nlm4_stats stat) {
+//! case NLM4_DENIED:
+//!     nlm4_holder holder;
+//! default:
+//!     void;
+//! };
+//!
+//! struct nlm4_lockargs {
+//!     netobj cookie;
+//!     bool block;
+//!     bool exclusive;
+//!     nlm4_lock alock;
+//!     bool reclaim;
+//!     int32 state;
+//! };
+//! ```
+//!
+//! `netobj` and `nlm4_holder` are shared with other protocols, so they're defined in
+//! `sunrpc_types` and re-exported here. The one-field `nlm4_stat` struct is represented by its
+//! field, which has the same encoding.
+//!
+//! This module is only available with the `nlm` feature, which also enables `sunrpc_types`.
+#![allow(non_camel_case_types)]
+
+use std::io::{Read, Write};
+
+use error::*;
+use super::{Pack, Unpack, pack_string, unpack_string};
+
+pub use sunrpc_types::{netobj, nlm4_holder, MAXNETOBJ_SZ};
+
+/// RPC program number of NLM.
+pub const NLM_PROG: u32 = 100021;
+/// RPC version number of NLM version 4.
+pub const NLM4_VERS: u32 = 4;
+
+pub const NLMPROC4_NULL: u32 = 0;
+pub const NLMPROC4_TEST: u32 = 1;
+pub const NLMPROC4_LOCK: u32 = 2;
+pub const NLMPROC4_CANCEL: u32 = 3;
+pub const NLMPROC4_UNLOCK: u32 = 4;
+pub const NLMPROC4_GRANTED: u32 = 5;
+pub const NLMPROC4_TEST_MSG: u32 = 6;
+pub const NLMPROC4_LOCK_MSG: u32 = 7;
+pub const NLMPROC4_CANCEL_MSG: u32 = 8;
+pub const NLMPROC4_UNLOCK_MSG: u32 = 9;
+pub const NLMPROC4_GRANTED_MSG: u32 = 10;
+pub const NLMPROC4_TEST_RES: u32 = 11;
+pub const NLMPROC4_LOCK_RES: u32 = 12;
+pub const NLMPROC4_CANCEL_RES: u32 = 13;
+pub const NLMPROC4_UNLOCK_RES: u32 = 14;
+pub const NLMPROC4_GRANTED_RES: u32 = 15;
+pub const NLMPROC4_SHARE: u32 = 20;
+pub const NLMPROC4_UNSHARE: u32 = 21;
+pub const NLMPROC4_NM_LOCK: u32 = 22;
+pub const NLMPROC4_FREE_ALL: u32 = 23;
+
+/// Maximum length of a `caller_name`.
+pub const LM_MAXSTRLEN: usize = 1024;
+/// Maximum length of `nlm4_notify::name`.
+pub const LM_MAXNAMELEN: usize = LM_MAXSTRLEN + 1;
+
+xdr_enum! {
+    /// Status of an NLM procedure.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum nlm4_stats {
+        NLM4_GRANTED = 0,
+        NLM4_DENIED = 1,
+        NLM4_DENIED_NOLOCKS = 2,
+        NLM4_BLOCKED = 3,
+        NLM4_DENIED_GRACE_PERIOD = 4,
+        NLM4_DEADLCK = 5,
+        NLM4_ROFS = 6,
+        NLM4_STALE_FH = 7,
+        NLM4_FBIG = 8,
+        NLM4_FAILED = 9,
+    }
+}
+
+xdr_enum! {
+    /// Access a DOS share reservation denies to others.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum fsh4_mode {
+        fsm_DN = 0,
+        fsm_DR = 1,
+        fsm_DW = 2,
+        fsm_DRW = 3,
+    }
+}
+
+xdr_enum! {
+    /// Access a DOS share reservation requests.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum fsh4_access {
+        fsa_NONE = 0,
+        fsa_R = 1,
+        fsa_W = 2,
+        fsa_RW = 3,
+    }
+}
+
+/// Result of `TEST`: the holder of a conflicting lock if the status is `NLM4_DENIED`, and
+/// otherwise just the status.
+///
+/// Packing a `Stat` of `NLM4_DENIED` fails with `InvalidCase`, since it would need a holder.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum nlm4_testrply {
+    Denied(nlm4_holder),
+    Stat(nlm4_stats),
+}
+
+impl nlm4_testrply {
+    /// Return the status.
+    pub fn stat(&self) -> nlm4_stats {
+        match *self {
+            nlm4_testrply::Denied(_) => nlm4_stats::NLM4_DENIED,
+            nlm4_testrply::Stat(stat) => stat,
+        }
+    }
+}
+
+impl<Out: Write> Pack<Out> for nlm4_testrply {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        match *self {
+            nlm4_testrply::Denied(ref holder) => Ok(nlm4_stats::NLM4_DENIED.pack(out)? + holder.pack(out)?),
+            nlm4_testrply::Stat(nlm4_stats::NLM4_DENIED) => Err(Error::invalidcase(nlm4_stats::NLM4_DENIED as i32)),
+            nlm4_testrply::Stat(stat) => stat.pack(out),
+        }
+    }
+}
+
+impl<In: Read> Unpack<In> for nlm4_testrply {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (stat, sz) = nlm4_stats::unpack(input)?;
+
+        if stat == nlm4_stats::NLM4_DENIED {
+            let (holder, hsz) = nlm4_holder::unpack(input)?;
+            Ok((nlm4_testrply::Denied(holder), sz + hsz))
+        } else {
+            Ok((nlm4_testrply::Stat(stat), sz))
+        }
+    }
+}
+
+/// Result of `TEST`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct nlm4_testres {
+    pub cookie: netobj,
+    pub stat: nlm4_testrply,
+}
+
+impl<Out: Write> Pack<Out> for nlm4_testres {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.cookie.pack(out)? + self.stat.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for nlm4_testres {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = nlm4_testres {
+            cookie: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            stat: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Result of `LOCK`, `CANCEL`, `UNLOCK` and `GRANTED`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct nlm4_res {
+    pub cookie: netobj,
+    pub stat: nlm4_stats,
+}
+
+impl<Out: Write> Pack<Out> for nlm4_res {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.cookie.pack(out)? + self.stat.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for nlm4_res {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = nlm4_res {
+            cookie: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            stat: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// A byte range lock.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct nlm4_lock {
+    pub caller_name: String,
+    pub fh: netobj,
+    pub oh: netobj,
+    pub svid: i32,
+    pub l_offset: u64,
+    pub l_len: u64,
+}
+
+impl<Out: Write> Pack<Out> for nlm4_lock {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += pack_string(&self.caller_name, Some(LM_MAXSTRLEN), out)?;
+        sz += self.fh.pack(out)?;
+        sz += self.oh.pack(out)?;
+        sz += self.svid.pack(out)?;
+        sz += self.l_offset.pack(out)?;
+        sz += self.l_len.pack(out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for nlm4_lock {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = nlm4_lock {
+            caller_name: { let (v, fsz) = unpack_string(input, Some(LM_MAXSTRLEN))?; sz += fsz; v },
+            fh: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            oh: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            svid: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            l_offset: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            l_len: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Arguments of `LOCK`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct nlm4_lockargs {
+    pub cookie: netobj,
+    pub block: bool,
+    pub exclusive: bool,
+    pub alock: nlm4_lock,
+    pub reclaim: bool,
+    pub state: i32,
+}
+
+impl<Out: Write> Pack<Out> for nlm4_lockargs {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += self.cookie.pack(out)?;
+        sz += self.block.pack(out)?;
+        sz += self.exclusive.pack(out)?;
+        sz += self.alock.pack(out)?;
+        sz += self.reclaim.pack(out)?;
+        sz += self.state.pack(out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for nlm4_lockargs {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = nlm4_lockargs {
+            cookie: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            block: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            exclusive: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            alock: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            reclaim: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            state: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Arguments of `CANCEL`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct nlm4_cancargs {
+    pub cookie: netobj,
+    pub block: bool,
+    pub exclusive: bool,
+    pub alock: nlm4_lock,
+}
+
+impl<Out: Write> Pack<Out> for nlm4_cancargs {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += self.cookie.pack(out)?;
+        sz += self.block.pack(out)?;
+        sz += self.exclusive.pack(out)?;
+        sz += self.alock.pack(out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for nlm4_cancargs {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = nlm4_cancargs {
+            cookie: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            block: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            exclusive: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            alock: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Arguments of `TEST`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct nlm4_testargs {
+    pub cookie: netobj,
+    pub exclusive: bool,
+    pub alock: nlm4_lock,
+}
+
+impl<Out: Write> Pack<Out> for nlm4_testargs {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.cookie.pack(out)? + self.exclusive.pack(out)? + self.alock.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for nlm4_testargs {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = nlm4_testargs {
+            cookie: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            exclusive: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            alock: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Arguments of `UNLOCK`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct nlm4_unlockargs {
+    pub cookie: netobj,
+    pub alock: nlm4_lock,
+}
+
+impl<Out: Write> Pack<Out> for nlm4_unlockargs {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.cookie.pack(out)? + self.alock.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for nlm4_unlockargs {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = nlm4_unlockargs {
+            cookie: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            alock: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// A DOS share reservation.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct nlm4_share {
+    pub caller_name: String,
+    pub fh: netobj,
+    pub oh: netobj,
+    pub mode: fsh4_mode,
+    pub access: fsh4_access,
+}
+
+impl<Out: Write> Pack<Out> for nlm4_share {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += pack_string(&self.caller_name, Some(LM_MAXSTRLEN), out)?;
+        sz += self.fh.pack(out)?;
+        sz += self.oh.pack(out)?;
+        sz += self.mode.pack(out)?;
+        sz += self.access.pack(out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for nlm4_share {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = nlm4_share {
+            caller_name: { let (v, fsz) = unpack_string(input, Some(LM_MAXSTRLEN))?; sz += fsz; v },
+            fh: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            oh: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            mode: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            access: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Arguments of `SHARE` and `UNSHARE`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct nlm4_shareargs {
+    pub cookie: netobj,
+    pub share: nlm4_share,
+    pub reclaim: bool,
+}
+
+impl<Out: Write> Pack<Out> for nlm4_shareargs {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.cookie.pack(out)? + self.share.pack(out)? + self.reclaim.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for nlm4_shareargs {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = nlm4_shareargs {
+            cookie: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            share: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            reclaim: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Result of `SHARE` and `UNSHARE`.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct nlm4_shareres {
+    pub cookie: netobj,
+    pub stat: nlm4_stats,
+    pub sequence: i32,
+}
+
+impl<Out: Write> Pack<Out> for nlm4_shareres {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.cookie.pack(out)? + self.stat.pack(out)? + self.sequence.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for nlm4_shareres {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = nlm4_shareres {
+            cookie: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            stat: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            sequence: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Arguments of `FREE_ALL`: a client which has restarted, and its new NSM state.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct nlm4_notify {
+    pub name: String,
+    pub state: i32,
+}
+
+impl<Out: Write> Pack<Out> for nlm4_notify {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(pack_string(&self.name, Some(LM_MAXNAMELEN), out)? + self.state.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for nlm4_notify {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = nlm4_notify {
+            name: { let (v, fsz) = unpack_string(input, Some(LM_MAXNAMELEN))?; sz += fsz; v },
+            state: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
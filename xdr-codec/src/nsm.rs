@@ -0,0 +1,278 @@
+//! Network Status Monitor protocol types
+//!
+//! NSM (`statd`) tracks which hosts hold locks, and tells lock managers when a host restarts so
+//! its locks can be recovered or released. These are the version 1 types from the X/Open XNFS
+//! specification (`sm_inter.x`):
+//!
+//! ```text
+//! struct my_id {
+//!     string my_name<SM_MAXSTRLEN>;
+//!     int    my_prog;
+//!     int    my_vers;
+//!     int    my_proc;
+//! };
+//!
+//! struct mon_id {
+//!     string mon_name<SM_MAXSTRLEN>;
+//!     struct my_id my_id;
+//! };
+//!
+//! struct mon {
+//!     struct mon_id mon_id;
+//!     opaque priv[16];
+//! };
+//! ```
+//!
+//! `status` is the argument of the callback (`my_id.my_proc`) statd makes to a local lock manager
+//! when a monitored host restarts, after being told so with `SM_NOTIFY`.
+//!
+//! This module is only available with the `nsm` feature.
+#![allow(non_camel_case_types)]
+
+use std::io::{Read, Write};
+
+use error::*;
+use super::{Pack, Unpack, pack_opaque_array, pack_string, unpack_opaque_array, unpack_string};
+
+/// RPC program number of NSM.
+pub const SM_PROG: u32 = 100024;
+/// RPC version number of NSM version 1.
+pub const SM_VERS: u32 = 1;
+
+pub const SM_STAT: u32 = 1;
+pub const SM_MON: u32 = 2;
+pub const SM_UNMON: u32 = 3;
+pub const SM_UNMON_ALL: u32 = 4;
+pub const SM_SIMU_CRASH: u32 = 5;
+pub const SM_NOTIFY: u32 = 6;
+
+/// Maximum length of a host name.
+pub const SM_MAXSTRLEN: usize = 1024;
+/// Size of the private data returned to the monitoring lock manager.
+pub const SM_PRIV_SIZE: usize = 16;
+
+xdr_enum! {
+    /// Whether `SM_STAT` or `SM_MON` succeeded.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum res {
+        stat_succ = 0,
+        stat_fail = 1,
+    }
+}
+
+/// Arguments of `SM_STAT`: a host name.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct sm_name {
+    pub mon_name: String,
+}
+
+impl<Out: Write> Pack<Out> for sm_name {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_string(&self.mon_name, Some(SM_MAXSTRLEN), out)
+    }
+}
+
+impl<In: Read> Unpack<In> for sm_name {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (mon_name, sz) = unpack_string(input, Some(SM_MAXSTRLEN))?;
+        Ok((sm_name { mon_name }, sz))
+    }
+}
+
+/// The local RPC procedure to call when a monitored host restarts.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct my_id {
+    pub my_name: String,
+    pub my_prog: i32,
+    pub my_vers: i32,
+    pub my_proc: i32,
+}
+
+impl<Out: Write> Pack<Out> for my_id {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += pack_string(&self.my_name, Some(SM_MAXSTRLEN), out)?;
+        sz += self.my_prog.pack(out)?;
+        sz += self.my_vers.pack(out)?;
+        sz += self.my_proc.pack(out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for my_id {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = my_id {
+            my_name: { let (v, fsz) = unpack_string(input, Some(SM_MAXSTRLEN))?; sz += fsz; v },
+            my_prog: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            my_vers: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            my_proc: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// A monitored host and who to tell when it restarts. Arguments of `SM_UNMON`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct mon_id {
+    pub mon_name: String,
+    pub my_id: my_id,
+}
+
+impl<Out: Write> Pack<Out> for mon_id {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(pack_string(&self.mon_name, Some(SM_MAXSTRLEN), out)? + self.my_id.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for mon_id {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = mon_id {
+            mon_name: { let (v, fsz) = unpack_string(input, Some(SM_MAXSTRLEN))?; sz += fsz; v },
+            my_id: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Arguments of `SM_MON`: a host to monitor, and private data to pass back when it restarts.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct mon {
+    pub mon_id: mon_id,
+    pub priv_: [u8; SM_PRIV_SIZE],
+}
+
+impl<Out: Write> Pack<Out> for mon {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.mon_id.pack(out)? + pack_opaque_array(&self.priv_, SM_PRIV_SIZE, out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for mon {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = mon {
+            mon_id: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            priv_: {
+                let mut v = [0; SM_PRIV_SIZE];
+                sz += unpack_opaque_array(input, &mut v, SM_PRIV_SIZE)?;
+                v
+            },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// The local host's NSM state number, which is odd while it is up and changes on each restart.
+/// Result of `SM_UNMON` and `SM_UNMON_ALL`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct sm_stat {
+    pub state: i32,
+}
+
+impl<Out: Write> Pack<Out> for sm_stat {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.state.pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for sm_stat {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (state, sz) = Unpack::unpack(input)?;
+        Ok((sm_stat { state }, sz))
+    }
+}
+
+/// Result of `SM_STAT` and `SM_MON`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct sm_stat_res {
+    pub res_stat: res,
+    pub state: i32,
+}
+
+impl<Out: Write> Pack<Out> for sm_stat_res {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.res_stat.pack(out)? + self.state.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for sm_stat_res {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = sm_stat_res {
+            res_stat: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            state: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Arguments of `SM_NOTIFY`: a host which has restarted, and its new state.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct stat_chge {
+    pub mon_name: String,
+    pub state: i32,
+}
+
+impl<Out: Write> Pack<Out> for stat_chge {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(pack_string(&self.mon_name, Some(SM_MAXSTRLEN), out)? + self.state.pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for stat_chge {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = stat_chge {
+            mon_name: { let (v, fsz) = unpack_string(input, Some(SM_MAXSTRLEN))?; sz += fsz; v },
+            state: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        };
+
+        Ok((ret, sz))
+    }
+}
+
+/// Arguments of the callback to a lock manager: the host which restarted, its new state, and
+/// the private data from `SM_MON`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct status {
+    pub mon_name: String,
+    pub state: i32,
+    pub priv_: [u8; SM_PRIV_SIZE],
+}
+
+impl<Out: Write> Pack<Out> for status {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += pack_string(&self.mon_name, Some(SM_MAXSTRLEN), out)?;
+        sz += self.state.pack(out)?;
+        sz += pack_opaque_array(&self.priv_, SM_PRIV_SIZE, out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for status {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let ret = status {
+            mon_name: { let (v, fsz) = unpack_string(input, Some(SM_MAXSTRLEN))?; sz += fsz; v },
+            state: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            priv_: {
+                let mut v = [0; SM_PRIV_SIZE];
+                sz += unpack_opaque_array(input, &mut v, SM_PRIV_SIZE)?;
+                v
+            },
+        };
+
+        Ok((ret, sz))
+    }
+}
@@ -0,0 +1,553 @@
+//! ZFS name-value lists (libnvpair) in XDR encoding
+//!
+//! An nvlist is a list of named, typed values, where values can be nested nvlists or arrays.
+//! ZFS uses them for vdev labels, pool configuration, and the properties in send streams, packed
+//! with libnvpair's XDR encoding. An nvlist is encoded as:
+//!
+//! ```text
+//! int          nvl_version;
+//! unsigned int nvl_nvflag;
+//! nvpair       pairs[...];     /* each introduced by a non-zero encode_size */
+//! int          0, 0;           /* end of list */
+//!
+//! struct nvpair {
+//!     int         encode_size;  /* encoded size of the whole pair, including nested nvlists */
+//!     int         decode_size;  /* size of libnvpair's in-memory nvpair_t */
+//!     string      name<>;
+//!     data_type_t type;
+//!     int         nelem;
+//!     ...                       /* value, then any nested nvlists */
+//! };
+//! ```
+//!
+//! Scalars narrower than 32 bits take a full word, fixed-size arrays of them are counted arrays
+//! of words, and a byte array is fixed-length opaque. A string array is `nelem` strings without a
+//! count. A nested nvlist or nvlist array follows its pair's header.
+//!
+//! `NvList` implements `Pack` and `Unpack` for the encoding of an nvlist within another; a
+//! standalone packed nvlist (as in a vdev label) is preceded by a 4-byte header, handled by
+//! `pack_nvlist` and `unpack_nvlist`.
+//!
+//! ```
+//! use xdr_codec::nvlist::*;
+//!
+//! let mut vdev = NvList::new();
+//! vdev.add("type", NvValue::String("disk".to_string()));
+//! vdev.add("guid", NvValue::Uint64(0x1234));
+//!
+//! let mut config = NvList::new();
+//! config.add("name", NvValue::String("tank".to_string()));
+//! config.add("vdev_tree", NvValue::NvList(vdev));
+//!
+//! let buf = pack_nvlist(&config).unwrap();
+//! let (got, _) = unpack_nvlist(&mut &buf[..]).unwrap();
+//! assert_eq!(got, config);
+//! match got.get("vdev_tree") {
+//!     Some(&NvValue::NvList(ref vdev)) => assert_eq!(vdev.get("guid"), Some(&NvValue::Uint64(0x1234))),
+//!     other => panic!("unexpected {:?}", other),
+//! }
+//! ```
+//!
+//! This module is only available with the `nvlist` feature.
+#![allow(non_camel_case_types)]
+
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+
+use error::*;
+use super::{Pack, Unpack, pack_flex, pack_opaque_array, pack_string, read_opaque, unpack_flex,
+            unpack_padding, unpack_string};
+
+/// `nvs_header` encoding byte for XDR.
+pub const NV_ENCODE_XDR: u8 = 1;
+/// nvlist version written by libnvpair.
+pub const NV_VERSION: i32 = 0;
+/// `nvl_nvflag`: names are unique.
+pub const NV_UNIQUE_NAME: u32 = 0x1;
+/// `nvl_nvflag`: name and type pairs are unique.
+pub const NV_UNIQUE_NAME_TYPE: u32 = 0x2;
+
+xdr_enum! {
+    /// Type of a pair's value.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+    pub enum data_type_t {
+        DATA_TYPE_BOOLEAN = 1,
+        DATA_TYPE_BYTE = 2,
+        DATA_TYPE_INT16 = 3,
+        DATA_TYPE_UINT16 = 4,
+        DATA_TYPE_INT32 = 5,
+        DATA_TYPE_UINT32 = 6,
+        DATA_TYPE_INT64 = 7,
+        DATA_TYPE_UINT64 = 8,
+        DATA_TYPE_STRING = 9,
+        DATA_TYPE_BYTE_ARRAY = 10,
+        DATA_TYPE_INT16_ARRAY = 11,
+        DATA_TYPE_UINT16_ARRAY = 12,
+        DATA_TYPE_INT32_ARRAY = 13,
+        DATA_TYPE_UINT32_ARRAY = 14,
+        DATA_TYPE_INT64_ARRAY = 15,
+        DATA_TYPE_UINT64_ARRAY = 16,
+        DATA_TYPE_STRING_ARRAY = 17,
+        DATA_TYPE_HRTIME = 18,
+        DATA_TYPE_NVLIST = 19,
+        DATA_TYPE_NVLIST_ARRAY = 20,
+        DATA_TYPE_BOOLEAN_VALUE = 21,
+        DATA_TYPE_INT8 = 22,
+        DATA_TYPE_UINT8 = 23,
+        DATA_TYPE_BOOLEAN_ARRAY = 24,
+        DATA_TYPE_INT8_ARRAY = 25,
+        DATA_TYPE_UINT8_ARRAY = 26,
+        DATA_TYPE_DOUBLE = 27,
+    }
+}
+
+/// A pair's value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NvValue {
+    /// A name with no value, used as a flag.
+    Boolean,
+    BooleanValue(bool),
+    Byte(u8),
+    Int8(i8),
+    Uint8(u8),
+    Int16(i16),
+    Uint16(u16),
+    Int32(i32),
+    Uint32(u32),
+    Int64(i64),
+    Uint64(u64),
+    Double(f64),
+    /// High-resolution time in nanoseconds.
+    Hrtime(i64),
+    String(String),
+    ByteArray(Vec<u8>),
+    BooleanArray(Vec<bool>),
+    Int8Array(Vec<i8>),
+    Uint8Array(Vec<u8>),
+    Int16Array(Vec<i16>),
+    Uint16Array(Vec<u16>),
+    Int32Array(Vec<i32>),
+    Uint32Array(Vec<u32>),
+    Int64Array(Vec<i64>),
+    Uint64Array(Vec<u64>),
+    StringArray(Vec<String>),
+    NvList(NvList),
+    NvListArray(Vec<NvList>),
+}
+
+fn align8(sz: usize) -> usize {
+    (sz + 7) & !7
+}
+
+impl NvValue {
+    /// The type of this value.
+    pub fn data_type(&self) -> data_type_t {
+        use self::NvValue::*;
+        use self::data_type_t::*;
+
+        match *self {
+            Boolean => DATA_TYPE_BOOLEAN,
+            BooleanValue(_) => DATA_TYPE_BOOLEAN_VALUE,
+            Byte(_) => DATA_TYPE_BYTE,
+            Int8(_) => DATA_TYPE_INT8,
+            Uint8(_) => DATA_TYPE_UINT8,
+            Int16(_) => DATA_TYPE_INT16,
+            Uint16(_) => DATA_TYPE_UINT16,
+            Int32(_) => DATA_TYPE_INT32,
+            Uint32(_) => DATA_TYPE_UINT32,
+            Int64(_) => DATA_TYPE_INT64,
+            Uint64(_) => DATA_TYPE_UINT64,
+            Double(_) => DATA_TYPE_DOUBLE,
+            Hrtime(_) => DATA_TYPE_HRTIME,
+            String(_) => DATA_TYPE_STRING,
+            ByteArray(_) => DATA_TYPE_BYTE_ARRAY,
+            BooleanArray(_) => DATA_TYPE_BOOLEAN_ARRAY,
+            Int8Array(_) => DATA_TYPE_INT8_ARRAY,
+            Uint8Array(_) => DATA_TYPE_UINT8_ARRAY,
+            Int16Array(_) => DATA_TYPE_INT16_ARRAY,
+            Uint16Array(_) => DATA_TYPE_UINT16_ARRAY,
+            Int32Array(_) => DATA_TYPE_INT32_ARRAY,
+            Uint32Array(_) => DATA_TYPE_UINT32_ARRAY,
+            Int64Array(_) => DATA_TYPE_INT64_ARRAY,
+            Uint64Array(_) => DATA_TYPE_UINT64_ARRAY,
+            StringArray(_) => DATA_TYPE_STRING_ARRAY,
+            NvList(_) => DATA_TYPE_NVLIST,
+            NvListArray(_) => DATA_TYPE_NVLIST_ARRAY,
+        }
+    }
+
+    /// Number of elements: 0 for `Boolean`, the length for arrays, and otherwise 1.
+    pub fn nelem(&self) -> usize {
+        use self::NvValue::*;
+
+        match *self {
+            Boolean => 0,
+            ByteArray(ref v) | Uint8Array(ref v) => v.len(),
+            BooleanArray(ref v) => v.len(),
+            Int8Array(ref v) => v.len(),
+            Int16Array(ref v) => v.len(),
+            Uint16Array(ref v) => v.len(),
+            Int32Array(ref v) => v.len(),
+            Uint32Array(ref v) => v.len(),
+            Int64Array(ref v) => v.len(),
+            Uint64Array(ref v) => v.len(),
+            StringArray(ref v) => v.len(),
+            NvListArray(ref v) => v.len(),
+            _ => 1,
+        }
+    }
+
+    /// Size of the value in libnvpair's in-memory representation, for `decode_size`.
+    fn native_size(&self) -> usize {
+        use self::NvValue::*;
+
+        // sizeof (nvlist_t), and a pointer to one
+        const NVLIST: usize = 24;
+        const PTR: usize = 8;
+
+        match *self {
+            Boolean => 0,
+            Byte(_) | Int8(_) | Uint8(_) => 1,
+            Int16(_) | Uint16(_) => 2,
+            BooleanValue(_) | Int32(_) | Uint32(_) => 4,
+            Int64(_) | Uint64(_) | Double(_) | Hrtime(_) => 8,
+            String(ref s) => s.len() + 1,
+            ByteArray(ref v) | Uint8Array(ref v) => v.len(),
+            Int8Array(ref v) => v.len(),
+            Int16Array(ref v) => 2 * v.len(),
+            Uint16Array(ref v) => 2 * v.len(),
+            BooleanArray(ref v) => 4 * v.len(),
+            Int32Array(ref v) => 4 * v.len(),
+            Uint32Array(ref v) => 4 * v.len(),
+            Int64Array(ref v) => 8 * v.len(),
+            Uint64Array(ref v) => 8 * v.len(),
+            StringArray(ref v) => v.iter().map(|s| PTR + s.len() + 1).sum(),
+            NvList(_) => NVLIST,
+            NvListArray(ref v) => (PTR + NVLIST) * v.len(),
+        }
+    }
+
+    fn pack_value<Out: Write>(&self, out: &mut Out) -> Result<usize> {
+        use self::NvValue::*;
+
+        // Sub-word integers are each encoded as a word
+        Ok(match *self {
+            Boolean => 0,
+            BooleanValue(v) => v.pack(out)?,
+            Byte(v) | Uint8(v) => (v as u32).pack(out)?,
+            Int8(v) => (v as i32).pack(out)?,
+            Int16(v) => (v as i32).pack(out)?,
+            Uint16(v) => (v as u32).pack(out)?,
+            Int32(v) => v.pack(out)?,
+            Uint32(v) => v.pack(out)?,
+            Int64(v) | Hrtime(v) => v.pack(out)?,
+            Uint64(v) => v.pack(out)?,
+            Double(v) => v.pack(out)?,
+            String(ref v) => pack_string(v, None, out)?,
+            ByteArray(ref v) => pack_opaque_array(v, v.len(), out)?,
+            BooleanArray(ref v) => pack_flex(v, None, out)?,
+            Int8Array(ref v) => pack_flex(&v.iter().map(|&e| e as i32).collect::<Vec<_>>(), None, out)?,
+            Uint8Array(ref v) => pack_flex(&v.iter().map(|&e| e as u32).collect::<Vec<_>>(), None, out)?,
+            Int16Array(ref v) => pack_flex(&v.iter().map(|&e| e as i32).collect::<Vec<_>>(), None, out)?,
+            Uint16Array(ref v) => pack_flex(&v.iter().map(|&e| e as u32).collect::<Vec<_>>(), None, out)?,
+            Int32Array(ref v) => pack_flex(v, None, out)?,
+            Uint32Array(ref v) => pack_flex(v, None, out)?,
+            Int64Array(ref v) => pack_flex(v, None, out)?,
+            Uint64Array(ref v) => pack_flex(v, None, out)?,
+            StringArray(ref v) => {
+                let mut sz = 0;
+                for s in v {
+                    sz += pack_string(s, None, out)?;
+                }
+                sz
+            }
+            NvList(ref v) => v.pack(out)?,
+            NvListArray(ref v) => {
+                let mut sz = 0;
+                for l in v {
+                    sz += l.pack(out)?;
+                }
+                sz
+            }
+        })
+    }
+
+    fn unpack_value<In: Read>(input: &mut In, ty: data_type_t, nelem: usize) -> Result<(Self, usize)> {
+        use self::NvValue::*;
+        use self::data_type_t::*;
+
+        fn words<In: Read, T: Unpack<In>>(input: &mut In, nelem: usize) -> Result<(Vec<T>, usize)> {
+            let (v, sz) = unpack_flex(input, Some(nelem))?;
+            if v.len() != nelem {
+                bail!(ErrorKind::InvalidLen(v.len()));
+            }
+            Ok((v, sz))
+        }
+
+        let expect = match ty {
+            DATA_TYPE_BOOLEAN => Some(0),
+            DATA_TYPE_BYTE_ARRAY | DATA_TYPE_BOOLEAN_ARRAY | DATA_TYPE_INT8_ARRAY |
+            DATA_TYPE_UINT8_ARRAY | DATA_TYPE_INT16_ARRAY | DATA_TYPE_UINT16_ARRAY |
+            DATA_TYPE_INT32_ARRAY | DATA_TYPE_UINT32_ARRAY | DATA_TYPE_INT64_ARRAY |
+            DATA_TYPE_UINT64_ARRAY | DATA_TYPE_STRING_ARRAY | DATA_TYPE_NVLIST_ARRAY => None,
+            _ => Some(1),
+        };
+        if expect.is_some_and(|e| e != nelem) {
+            bail!(ErrorKind::InvalidLen(nelem));
+        }
+
+        let mut sz = 0;
+        let val = match ty {
+            DATA_TYPE_BOOLEAN => Boolean,
+            DATA_TYPE_BOOLEAN_VALUE => BooleanValue({ let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v }),
+            DATA_TYPE_BYTE => Byte({ let (v, fsz) = u32::unpack(input)?; sz += fsz; v as u8 }),
+            DATA_TYPE_INT8 => Int8({ let (v, fsz) = i32::unpack(input)?; sz += fsz; v as i8 }),
+            DATA_TYPE_UINT8 => Uint8({ let (v, fsz) = u32::unpack(input)?; sz += fsz; v as u8 }),
+            DATA_TYPE_INT16 => Int16({ let (v, fsz) = i32::unpack(input)?; sz += fsz; v as i16 }),
+            DATA_TYPE_UINT16 => Uint16({ let (v, fsz) = u32::unpack(input)?; sz += fsz; v as u16 }),
+            DATA_TYPE_INT32 => Int32({ let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v }),
+            DATA_TYPE_UINT32 => Uint32({ let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v }),
+            DATA_TYPE_INT64 => Int64({ let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v }),
+            DATA_TYPE_UINT64 => Uint64({ let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v }),
+            DATA_TYPE_DOUBLE => Double({ let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v }),
+            DATA_TYPE_HRTIME => Hrtime({ let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v }),
+            DATA_TYPE_STRING => String({ let (v, fsz) = unpack_string(input, None)?; sz += fsz; v }),
+            DATA_TYPE_BYTE_ARRAY => {
+                // nelem isn't trusted to size the allocation
+                let mut v = Vec::new();
+                sz += read_opaque(input, nelem, &mut v)?;
+                sz += unpack_padding(input, nelem)?;
+                ByteArray(v)
+            }
+            DATA_TYPE_BOOLEAN_ARRAY => BooleanArray({ let (v, fsz) = words(input, nelem)?; sz += fsz; v }),
+            DATA_TYPE_INT8_ARRAY => {
+                let (v, fsz) = words::<_, i32>(input, nelem)?;
+                sz += fsz;
+                Int8Array(v.into_iter().map(|e| e as i8).collect())
+            }
+            DATA_TYPE_UINT8_ARRAY => {
+                let (v, fsz) = words::<_, u32>(input, nelem)?;
+                sz += fsz;
+                Uint8Array(v.into_iter().map(|e| e as u8).collect())
+            }
+            DATA_TYPE_INT16_ARRAY => {
+                let (v, fsz) = words::<_, i32>(input, nelem)?;
+                sz += fsz;
+                Int16Array(v.into_iter().map(|e| e as i16).collect())
+            }
+            DATA_TYPE_UINT16_ARRAY => {
+                let (v, fsz) = words::<_, u32>(input, nelem)?;
+                sz += fsz;
+                Uint16Array(v.into_iter().map(|e| e as u16).collect())
+            }
+            DATA_TYPE_INT32_ARRAY => Int32Array({ let (v, fsz) = words(input, nelem)?; sz += fsz; v }),
+            DATA_TYPE_UINT32_ARRAY => Uint32Array({ let (v, fsz) = words(input, nelem)?; sz += fsz; v }),
+            DATA_TYPE_INT64_ARRAY => Int64Array({ let (v, fsz) = words(input, nelem)?; sz += fsz; v }),
+            DATA_TYPE_UINT64_ARRAY => Uint64Array({ let (v, fsz) = words(input, nelem)?; sz += fsz; v }),
+            DATA_TYPE_STRING_ARRAY => {
+                // nelem isn't trusted to size the allocation; each string is at least a word
+                let mut v = Vec::new();
+                for _ in 0..nelem {
+                    let (s, fsz) = unpack_string(input, None)?;
+                    sz += fsz;
+                    v.push(s);
+                }
+                StringArray(v)
+            }
+            DATA_TYPE_NVLIST => NvList({ let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v }),
+            DATA_TYPE_NVLIST_ARRAY => {
+                let mut v = Vec::new();
+                for _ in 0..nelem {
+                    let (l, fsz) = Unpack::unpack(input)?;
+                    sz += fsz;
+                    v.push(l);
+                }
+                NvListArray(v)
+            }
+        };
+
+        Ok((val, sz))
+    }
+}
+
+/// A list of name-value pairs.
+///
+/// Pairs are kept in insertion order, which is also their encoding order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NvList {
+    pub version: i32,
+    /// `NV_UNIQUE_NAME` and/or `NV_UNIQUE_NAME_TYPE`, or 0 to allow duplicates.
+    pub nvflag: u32,
+    pub pairs: Vec<(String, NvValue)>,
+}
+
+impl Default for NvList {
+    fn default() -> Self {
+        NvList::new()
+    }
+}
+
+impl NvList {
+    /// An empty list with unique names, as ZFS uses.
+    pub fn new() -> NvList {
+        NvList::with_flags(NV_UNIQUE_NAME)
+    }
+
+    /// An empty list with the given `nvflag`.
+    pub fn with_flags(nvflag: u32) -> NvList {
+        NvList { version: NV_VERSION, nvflag, pairs: Vec::new() }
+    }
+
+    /// Add a pair to the end of the list. With `NV_UNIQUE_NAME`, this first removes any pair with
+    /// the same name; with `NV_UNIQUE_NAME_TYPE`, any with the same name and type.
+    pub fn add<S: Into<String>>(&mut self, name: S, value: NvValue) {
+        let name = name.into();
+
+        if self.nvflag & NV_UNIQUE_NAME != 0 {
+            self.pairs.retain(|(n, _)| *n != name);
+        } else if self.nvflag & NV_UNIQUE_NAME_TYPE != 0 {
+            let ty = value.data_type();
+            self.pairs.retain(|(n, v)| *n != name || v.data_type() != ty);
+        }
+
+        self.pairs.push((name, value));
+    }
+
+    /// The value of the first pair named `name`.
+    pub fn get(&self, name: &str) -> Option<&NvValue> {
+        self.pairs.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// Remove all pairs named `name`, returning the first one's value.
+    pub fn remove(&mut self, name: &str) -> Option<NvValue> {
+        let mut ret = None;
+        let pairs = ::std::mem::take(&mut self.pairs);
+
+        for (n, v) in pairs {
+            if n != name {
+                self.pairs.push((n, v));
+            } else if ret.is_none() {
+                ret = Some(v);
+            }
+        }
+
+        ret
+    }
+
+    /// Iterate over the pairs.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &NvValue)> {
+        self.pairs.iter().map(|(n, v)| (n.as_str(), v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+// nvlist counts and sizes are signed 32-bit
+fn count(n: usize) -> Result<i32> {
+    i32::try_from(n).map_err(|_| Error::invalidlen(n))
+}
+
+fn pack_pair<Out: Write>(name: &str, value: &NvValue, out: &mut Out) -> Result<usize> {
+    let mut body = Vec::new();
+    pack_string(name, None, &mut body)?;
+    value.data_type().pack(&mut body)?;
+    count(value.nelem())?.pack(&mut body)?;
+    value.pack_value(&mut body)?;
+
+    // libnvpair's nvpair_t is 16 bytes, followed by the NUL-terminated name and the value
+    let encode_size = 8 + body.len();
+    let decode_size = align8(16 + name.len() + 1) + align8(value.native_size());
+
+    let mut sz = 0;
+    sz += count(encode_size)?.pack(out)?;
+    sz += count(decode_size)?.pack(out)?;
+    out.write_all(&body)?;
+
+    Ok(sz + body.len())
+}
+
+impl<Out: Write> Pack<Out> for NvList {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+
+        sz += self.version.pack(out)?;
+        sz += self.nvflag.pack(out)?;
+        for (name, value) in &self.pairs {
+            sz += pack_pair(name, value, out)?;
+        }
+        sz += 0i32.pack(out)?;
+        sz += 0i32.pack(out)?;
+
+        Ok(sz)
+    }
+}
+
+impl<In: Read> Unpack<In> for NvList {
+    /// Fails with `InvalidLen` if a pair's `encode_size` doesn't match its encoding, or its element
+    /// count doesn't suit its type.
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        let mut ret = NvList {
+            version: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            nvflag: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            pairs: Vec::new(),
+        };
+
+        loop {
+            let (encode_size, mut psz) = i32::unpack(input)?;
+            let (_decode_size, fsz) = i32::unpack(input)?;
+            psz += fsz;
+
+            if encode_size == 0 {
+                sz += psz;
+                break;
+            }
+
+            let name = { let (v, fsz) = unpack_string(input, None)?; psz += fsz; v };
+            let ty = { let (v, fsz) = Unpack::unpack(input)?; psz += fsz; v };
+            let nelem = { let (v, fsz) = i32::unpack(input)?; psz += fsz; v };
+            let nelem = match usize::try_from(nelem) {
+                Ok(n) => n,
+                Err(_) => bail!(ErrorKind::InvalidLen(nelem as usize)),
+            };
+            let value = { let (v, fsz) = NvValue::unpack_value(input, ty, nelem)?; psz += fsz; v };
+
+            if usize::try_from(encode_size).ok() != Some(psz) {
+                bail!(ErrorKind::InvalidLen(encode_size as usize));
+            }
+
+            ret.pairs.push((name, value));
+            sz += psz;
+        }
+
+        Ok((ret, sz))
+    }
+}
+
+/// Pack a standalone nvlist, with its leading `nvs_header`.
+pub fn pack_nvlist(nvl: &NvList) -> Result<Vec<u8>> {
+    // Encoding, then the packer's endianness, which doesn't affect XDR
+    let mut out = vec![NV_ENCODE_XDR, cfg!(target_endian = "little") as u8, 0, 0];
+    nvl.pack(&mut out)?;
+    Ok(out)
+}
+
+/// Unpack a standalone nvlist, with its leading `nvs_header`. Fails with `InvalidValue` if the
+/// header isn't for XDR encoding.
+pub fn unpack_nvlist<In: Read>(input: &mut In) -> Result<(NvList, usize)> {
+    let mut hdr = [0; 4];
+    input.read_exact(&mut hdr)?;
+
+    if hdr[0] != NV_ENCODE_XDR {
+        bail!(ErrorKind::InvalidValue(format!("nvlist encoding {} is not XDR", hdr[0])));
+    }
+
+    let (nvl, sz) = NvList::unpack(input)?;
+    Ok((nvl, sz + 4))
+}
@@ -0,0 +1,157 @@
+//! Decoding options
+//!
+//! `Unpack` implementations only see their input stream, so options which change how values are
+//! decoded can't be passed down to them as arguments. Instead they're set for the current thread
+//! for the duration of a closure with `with_decode_options`, and apply to everything unpacked
+//! inside it however deeply it's nested:
+//!
+//! ```
+//! use xdr_codec::{unpack, DecodeOptions, with_decode_options};
+//!
+//! let enc = [0, 0, 0, 3, b'a', 0xff, b'b', 0];
+//! let s: String = with_decode_options(DecodeOptions::new().lossy_strings(true), || {
+//!     unpack(&mut &enc[..])
+//! }).unwrap();
+//! assert_eq!(s, "a\u{fffd}b");
+//! ```
+//!
+//! `XdrReader::set_decode_options` applies options to every value unpacked through a reader.
+//!
+//! In the same way, `with_input_limit` tells the functions which unpack variable-length arrays,
+//! opaques and strings how much input there is. A length which couldn't fit in it is rejected
+//! before anything is allocated or read, rather than after reading up to the end of the input.
+//! `unpack_from_bytes` (and so `unpack_complete`, `verify_canonical` and
+//! `XdrRecordReader::unpack_record`) and `ValueDecoder` set this for you.
+use std::cell::Cell;
+use std::io;
+
+use error::*;
+
+/// Options controlling how values are unpacked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeOptions {
+    lossy_strings: bool,
+    max_depth: usize,
+}
+
+const DEFAULT_OPTIONS: DecodeOptions = DecodeOptions { lossy_strings: false, max_depth: 1000 };
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DEFAULT_OPTIONS
+    }
+}
+
+impl DecodeOptions {
+    /// Default options; all checks are strict.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace invalid UTF-8 in strings with U+FFFD (the replacement character) rather than
+    /// failing with `ErrorKind::InvalidUtf8`.
+    ///
+    /// This applies to `String`, `unpack_string`, `unpack_string_buf` and `Cow<str>` unpacked with
+    /// `UnpackRef`. A borrowed `&str` can't be repaired in place, so it still fails.
+    pub fn lossy_strings(mut self, lossy: bool) -> Self {
+        self.lossy_strings = lossy;
+        self
+    }
+
+    /// Return true if invalid UTF-8 in strings is replaced rather than failing.
+    pub fn is_lossy_strings(&self) -> bool {
+        self.lossy_strings
+    }
+
+    /// Limit how deeply a value decoded with a `Schema` may nest, failing with
+    /// `ErrorKind::DepthExceeded` beyond it. The default is 1000.
+    ///
+    /// Every value is a level below the array, optional, struct or union containing it, so a
+    /// linked list takes two levels per entry. Decoding recurses for each level, so a higher limit
+    /// needs more stack.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Return the limit on how deeply a value decoded with a `Schema` may nest.
+    pub fn get_max_depth(&self) -> usize {
+        self.max_depth
+    }
+}
+
+thread_local!(static OPTIONS: Cell<DecodeOptions> = const { Cell::new(DEFAULT_OPTIONS) });
+
+/// Return the decode options in effect for the current thread.
+pub fn decode_options() -> DecodeOptions {
+    OPTIONS.with(|o| o.get())
+}
+
+/// Call `f` with `opts` as the current thread's decode options.
+///
+/// The previous options are restored when `f` returns (or panics).
+pub fn with_decode_options<R, F: FnOnce() -> R>(opts: DecodeOptions, f: F) -> R {
+    struct Restore(DecodeOptions);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            OPTIONS.with(|o| o.set(self.0))
+        }
+    }
+
+    let _restore = Restore(OPTIONS.with(|o| o.replace(opts)));
+
+    f()
+}
+
+thread_local!(static INPUT_LIMIT: Cell<Option<usize>> = const { Cell::new(None) });
+
+/// Call `f` with the input known to contain at most `len` bytes.
+///
+/// Within `f`, unpacking a variable-length array, opaque or string whose length couldn't fit in
+/// what's left of the `len` bytes fails immediately with an `UnexpectedEof` IO error, the same
+/// error reading the data would eventually have given. Array elements are taken to be at least a
+/// word each, except for zero-sized types. The limit goes down as primitives, opaque data and
+/// padding are read by this crate's `Unpack` implementations; input read any other way isn't
+/// counted, which only makes the check more lenient. The limit applies to everything unpacked
+/// inside `f`, so it shouldn't be used when `f` reads from more than one input. The previous
+/// limit is restored when `f` returns (or panics).
+pub fn with_input_limit<R, F: FnOnce() -> R>(len: usize, f: F) -> R {
+    struct Restore(Option<usize>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            INPUT_LIMIT.with(|l| l.set(self.0))
+        }
+    }
+
+    let _restore = Restore(INPUT_LIMIT.with(|l| l.replace(Some(len))));
+
+    f()
+}
+
+/// Count `bytes` of input as read, reducing the current input limit.
+#[inline]
+pub(crate) fn consume_input(bytes: usize) {
+    INPUT_LIMIT.with(|l| if let Some(left) = l.get() {
+        l.set(Some(left.saturating_sub(bytes)))
+    })
+}
+
+/// Fail if `bytes` more bytes of input can't be present, given what's left of the input limit.
+pub(crate) fn check_input_len(bytes: usize) -> Result<()> {
+    match INPUT_LIMIT.with(|l| l.get()) {
+        Some(limit) if bytes > limit =>
+            Err(io::Error::new(io::ErrorKind::UnexpectedEof, "length exceeds input").into()),
+        _ => Ok(()),
+    }
+}
+
+/// Convert decoded string bytes to a `String`, honouring `lossy_strings`.
+pub(crate) fn utf8_string(v: Vec<u8>) -> Result<String> {
+    match String::from_utf8(v) {
+        Ok(s) => Ok(s),
+        Err(ref e) if decode_options().lossy_strings => Ok(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,114 @@
+//! OS string and path encodings
+//!
+//! Many XDR "string" fields, such as NFS file names, are really arbitrary bytes rather than
+//! UTF-8 text. This module encodes `OsStr`/`OsString` and `Path`/`PathBuf` (on Unix, where they
+//! are arbitrary byte strings) and `CStr`/`CString` using the XDR string wire format, without
+//! any UTF-8 validation.
+//!
+//! A `CString` can't contain NUL bytes, so unpacking one which does fails with `InvalidCString`.
+use std::io::{Read, Write};
+use std::ffi::{CStr, CString};
+#[cfg(unix)]
+use std::ffi::{OsStr, OsString};
+#[cfg(unix)]
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+
+use error::*;
+use super::{Pack, Unpack, Opaque, unpack_opaque_flex};
+#[cfg(unix)]
+use super::pack_opaque_flex;
+
+/// Pack an `OsStr` as a string with size limit check.
+#[cfg(unix)]
+#[inline]
+pub fn pack_os_str<Out: Write>(val: &OsStr, maxsz: Option<usize>, out: &mut Out) -> Result<usize> {
+    pack_opaque_flex(val.as_bytes(), maxsz, out)
+}
+
+/// Unpack a (perhaps) length-limited string as an `OsString`.
+#[cfg(unix)]
+#[inline]
+pub fn unpack_os_string<In: Read>(input: &mut In, maxsz: Option<usize>) -> Result<(OsString, usize)> {
+    let (v, sz) = unpack_opaque_flex(input, maxsz)?;
+    Ok((OsString::from_vec(v), sz))
+}
+
+/// Unpack a (perhaps) length-limited string as a `CString`.
+///
+/// Fails with `InvalidCString` if the string contains a NUL byte.
+#[inline]
+pub fn unpack_cstring<In: Read>(input: &mut In, maxsz: Option<usize>) -> Result<(CString, usize)> {
+    let (v, sz) = unpack_opaque_flex(input, maxsz)?;
+    CString::new(v).map_err(Error::from).map(|s| (s, sz))
+}
+
+#[cfg(unix)]
+impl<Out: Write> Pack<Out> for OsStr {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Opaque::borrowed(self.as_bytes()).pack(out)
+    }
+}
+
+#[cfg(unix)]
+impl<Out: Write> Pack<Out> for OsString {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.as_os_str().pack(out)
+    }
+}
+
+#[cfg(unix)]
+impl<In: Read> Unpack<In> for OsString {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_os_string(input, None)
+    }
+}
+
+#[cfg(unix)]
+impl<Out: Write> Pack<Out> for Path {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.as_os_str().pack(out)
+    }
+}
+
+#[cfg(unix)]
+impl<Out: Write> Pack<Out> for PathBuf {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.as_os_str().pack(out)
+    }
+}
+
+#[cfg(unix)]
+impl<In: Read> Unpack<In> for PathBuf {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_os_string(input, None).map(|(s, sz)| (PathBuf::from(s), sz))
+    }
+}
+
+impl<Out: Write> Pack<Out> for CStr {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Opaque::borrowed(self.to_bytes()).pack(out)
+    }
+}
+
+impl<Out: Write> Pack<Out> for CString {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.as_c_str().pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for CString {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_cstring(input, None)
+    }
+}
@@ -0,0 +1,286 @@
+//! Encoding on worker threads
+//!
+//! When a single thread can't pack values as fast as they can be written, `EncodePipeline`
+//! spreads the packing across worker threads. Values are sent to the pipeline, packed by
+//! whichever worker is free, and written by a single IO thread in the order they were sent:
+//!
+//! ```
+//! use xdr_codec::pipeline::{EncodePipeline, PipelineOptions};
+//!
+//! let opts = PipelineOptions::new().workers(4).record_marking(true);
+//! let mut pipe = EncodePipeline::with_options(Vec::new(), opts);
+//! for i in 0..1000u32 {
+//!     pipe.send(vec![i; 16]).unwrap();
+//! }
+//! let out = pipe.finish().unwrap();
+//! assert_eq!(out.len(), 1000 * (4 + 4 + 16 * 4));
+//! ```
+//!
+//! Values are packed into buffers from a fixed-size pool, which the IO thread returns to the pool
+//! once they've been written. The number of buffers limits how far packing can get ahead of
+//! writing, and so the memory used; `send` blocks once the workers and the queue in front of
+//! them are full.
+//!
+//! The workers pack with the decode options in effect on the thread which created the pipeline,
+//! as `pack_to_vec` would have there.
+//!
+//! If a value fails to pack (or panics while packing), everything sent before it is still written
+//! but nothing after it is; if the writer fails, nothing more is written. Either way `send` starts failing with a
+//! `BrokenPipe` IO error, and `finish` returns the error which stopped the pipeline.
+//!
+//! This module is only available with the `pipeline` feature.
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::thread::{self, JoinHandle};
+
+use byteorder::{BigEndian, ByteOrder};
+
+use buffer::frame_record;
+use error::*;
+use options::{decode_options, with_decode_options};
+use record::LAST_REC;
+use super::Pack;
+
+/// Configuration for an `EncodePipeline`.
+#[derive(Debug, Clone, Copy)]
+pub struct PipelineOptions {
+    workers: usize,
+    queue_len: usize,
+    record_marking: bool,
+}
+
+impl Default for PipelineOptions {
+    fn default() -> Self {
+        PipelineOptions {
+            workers: thread::available_parallelism().map_or(1, |n| n.get()),
+            queue_len: 64,
+            record_marking: false,
+        }
+    }
+}
+
+impl PipelineOptions {
+    /// Default options: a worker per CPU, a queue of 64 values, and no record marking.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of worker threads (at least 1).
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// Set the number of values which can be queued for the workers (at least 1). There is a
+    /// pooled buffer for each of these and for each worker.
+    pub fn queue_len(mut self, len: usize) -> Self {
+        self.queue_len = len.max(1);
+        self
+    }
+
+    /// Write each value as a separate record, with record marking (see the `record` module),
+    /// rather than writing the values one after another.
+    pub fn record_marking(mut self, enable: bool) -> Self {
+        self.record_marking = enable;
+        self
+    }
+}
+
+// Buffers for packing into, shared by the workers and the IO thread
+struct Pool {
+    bufs: Mutex<(Vec<Vec<u8>>, bool)>,
+    ready: Condvar,
+}
+
+impl Pool {
+    fn new(count: usize) -> Pool {
+        Pool { bufs: Mutex::new(((0..count).map(|_| Vec::new()).collect(), false)), ready: Condvar::new() }
+    }
+
+    // Wait for a buffer, or return `None` once the pool is closed
+    fn take(&self) -> Option<Vec<u8>> {
+        let mut bufs = self.bufs.lock().unwrap();
+        loop {
+            if bufs.1 {
+                return None;
+            }
+            if let Some(buf) = bufs.0.pop() {
+                return Some(buf);
+            }
+            bufs = self.ready.wait(bufs).unwrap();
+        }
+    }
+
+    fn put(&self, mut buf: Vec<u8>) {
+        buf.clear();
+        self.bufs.lock().unwrap().0.push(buf);
+        self.ready.notify_one();
+    }
+
+    fn close(&self) {
+        self.bufs.lock().unwrap().1 = true;
+        self.ready.notify_all();
+    }
+}
+
+type Job<T> = (u64, T);
+type Done = (u64, Result<Vec<u8>>);
+
+// Pack `val` into `buf`, with a record mark if `framed`
+fn encode<T: Pack<Vec<u8>>>(val: &T, mut buf: Vec<u8>, framed: bool) -> Result<Vec<u8>> {
+    if !framed {
+        let _ = val.pack(&mut buf)?;
+        return Ok(buf);
+    }
+
+    // Leave room for the fragment header, which is filled in once the size is known
+    buf.extend_from_slice(&[0; 4]);
+    let sz = val.pack(&mut buf)?;
+    if sz < LAST_REC as usize {
+        BigEndian::write_u32(&mut buf[..4], LAST_REC | sz as u32);
+        Ok(buf)
+    } else {
+        // Too large for a single fragment
+        Ok(frame_record(&buf[4..], None))
+    }
+}
+
+// Turn a panic while packing into an error for the value, as otherwise the IO thread would wait
+// for it forever
+fn panicked(payload: Box<dyn Any + Send>) -> Error {
+    let msg = match payload.downcast_ref::<&str>() {
+        Some(msg) => *msg,
+        None => payload.downcast_ref::<String>().map_or("unknown cause", |msg| msg.as_str()),
+    };
+    format!("panicked while packing: {}", msg).into()
+}
+
+fn worker<T: Pack<Vec<u8>>>(jobs: Arc<Mutex<Receiver<Job<T>>>>, done: SyncSender<Done>, pool: Arc<Pool>, framed: bool) {
+    // A buffer is taken before the job so that the oldest value being packed always has one
+    while let Some(buf) = pool.take() {
+        let job = jobs.lock().unwrap().recv();
+        let (seq, val) = match job {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        let res = panic::catch_unwind(AssertUnwindSafe(|| encode(&val, buf, framed)))
+            .unwrap_or_else(|e| Err(panicked(e)));
+        if done.send((seq, res)).is_err() {
+            return;
+        }
+    }
+}
+
+// Write packed values in sequence order, until the workers have all finished or something fails.
+// A value which failed to pack is only reported once everything before it has been written.
+fn writer<W: Write>(mut out: W, done: Receiver<Done>, pool: Arc<Pool>) -> Result<W> {
+    let mut pending = BTreeMap::new();
+    let mut next = 0;
+
+    let res = (|| {
+        for (seq, res) in done {
+            let _ = pending.insert(seq, res);
+            while let Some(res) = pending.remove(&next) {
+                let buf = res?;
+                out.write_all(&buf)?;
+                pool.put(buf);
+                next += 1;
+            }
+        }
+        out.flush()?;
+        Ok(())
+    })();
+
+    // Unblock any workers waiting for a buffer
+    pool.close();
+    res.map(|_| out)
+}
+
+/// Packs values on worker threads and writes them in order from an IO thread.
+pub struct EncodePipeline<T, W> {
+    jobs: Option<SyncSender<Job<T>>>,
+    workers: Vec<JoinHandle<()>>,
+    writer: Option<JoinHandle<Result<W>>>,
+    sent: u64,
+}
+
+impl<T, W> EncodePipeline<T, W>
+    where T: Pack<Vec<u8>> + Send + 'static, W: Write + Send + 'static
+{
+    /// Start a pipeline writing to `out`, with the default options.
+    pub fn new(out: W) -> Self {
+        Self::with_options(out, PipelineOptions::default())
+    }
+
+    /// Start a pipeline writing to `out`.
+    ///
+    /// The current thread's decode options are used for packing.
+    pub fn with_options(out: W, opts: PipelineOptions) -> Self {
+        let pool = Arc::new(Pool::new(opts.workers + opts.queue_len));
+        let (jobs, jobs_rx) = sync_channel(opts.queue_len);
+        let (done, done_rx) = sync_channel(opts.queue_len);
+        let jobs_rx = Arc::new(Mutex::new(jobs_rx));
+        // Packing depends on the decode options, so carry them over to the workers
+        let dopts = decode_options();
+
+        let workers = (0..opts.workers).map(|_| {
+            let (jobs, done, pool) = (jobs_rx.clone(), done.clone(), pool.clone());
+            thread::spawn(move || with_decode_options(dopts, || worker(jobs, done, pool, opts.record_marking)))
+        }).collect();
+        let writer = thread::spawn(move || writer(out, done_rx, pool));
+
+        EncodePipeline { jobs: Some(jobs), workers, writer: Some(writer), sent: 0 }
+    }
+
+    /// Queue a value to be packed and written, blocking while the queue is full.
+    ///
+    /// Fails with a `BrokenPipe` IO error if the pipeline has stopped because of an earlier
+    /// error; `finish` returns the error.
+    pub fn send(&mut self, val: T) -> Result<()> {
+        let jobs = self.jobs.as_ref().expect("pipeline already finished");
+        if jobs.send((self.sent, val)).is_err() {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "encode pipeline stopped").into());
+        }
+        self.sent += 1;
+        Ok(())
+    }
+
+    /// The number of values sent so far.
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// Wait for everything sent to be written, stop the threads, and return the writer.
+    pub fn finish(mut self) -> Result<W> {
+        // Closing the queue makes the workers exit once it's drained, and the IO thread once
+        // they have
+        drop(self.jobs.take());
+        for w in self.workers.drain(..) {
+            if let Err(e) = w.join() {
+                ::std::panic::resume_unwind(e);
+            }
+        }
+        match self.writer.take().expect("pipeline already finished").join() {
+            Ok(res) => res,
+            Err(e) => ::std::panic::resume_unwind(e),
+        }
+    }
+}
+
+impl<T, W> Drop for EncodePipeline<T, W> {
+    fn drop(&mut self) {
+        // Let the threads finish writing what was sent, and wait for them so the writer isn't
+        // used after the pipeline's gone
+        drop(self.jobs.take());
+        for w in self.workers.drain(..) {
+            let _ = w.join();
+        }
+        if let Some(w) = self.writer.take() {
+            let _ = w.join();
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Supported public surface
+//!
+//! This module re-exports the items which code generated by xdrgen (and hand-written code
+//! following the same pattern) relies on. Everything here is covered by semver: it will not be
+//! removed or have its signature changed without a major version bump. New functionality may be
+//! added elsewhere in the crate without touching this set.
+//!
+//! ```
+//! use xdr_codec::prelude::*;
+//! # let _ = pack::<Vec<u8>, u32>;
+//! ```
+pub use super::{Write, Read};
+pub use super::{Error, ErrorKind, Result};
+pub use super::{Pack, Unpack, XdrPrimitive};
+pub use super::{pack, unpack, padding};
+pub use super::{pack_array, pack_opaque_array, pack_flex, pack_opaque_flex, pack_string};
+pub use super::{unpack_array, unpack_opaque_array, unpack_flex, unpack_opaque_flex, unpack_string};
+pub use super::Opaque;
@@ -0,0 +1,155 @@
+//! Quadruple-precision floating point
+//!
+//! RFC 4506 section 4.8 defines `quadruple`, a 128-bit IEEE 754 binary128 float. Rust has no
+//! native type for this, so `XdrQuadruple` holds the raw encoding: 16 bytes in wire (big-endian)
+//! order. It packs and unpacks as-is, so values round-trip exactly, and has conversions to and
+//! from `f64` for code which wants to do arithmetic on them. Converting to `f64` is lossy: the
+//! significand is rounded to nearest-even, values too large for `f64` become infinities and those
+//! too small become zeros.
+use std::io::{Read, Write};
+
+use error::*;
+use super::{Pack, Unpack};
+
+const F64_FRAC_BITS: u32 = 52;
+const F64_EXP_MAX: u64 = 0x7ff;
+const F64_BIAS: i32 = 1023;
+
+const QUAD_FRAC_BITS: u32 = 112;
+const QUAD_EXP_MAX: u128 = 0x7fff;
+const QUAD_BIAS: i32 = 16383;
+
+// Number of significand bits discarded converting quad to f64
+const FRAC_SHIFT: u32 = QUAD_FRAC_BITS - F64_FRAC_BITS;
+
+/// A quadruple-precision float, as its raw big-endian encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct XdrQuadruple(pub [u8; 16]);
+
+impl XdrQuadruple {
+    /// Return the value as a 128-bit integer with the same bit pattern.
+    pub fn to_bits(&self) -> u128 {
+        u128::from_be_bytes(self.0)
+    }
+
+    /// Construct a value from a 128-bit integer with the same bit pattern.
+    pub fn from_bits(bits: u128) -> XdrQuadruple {
+        XdrQuadruple(bits.to_be_bytes())
+    }
+
+    /// Convert an `f64` to a quadruple. This is exact.
+    pub fn from_f64(v: f64) -> XdrQuadruple {
+        let bits = v.to_bits();
+        let sign = (bits >> 63) as u128;
+        let exp = (bits >> F64_FRAC_BITS) & F64_EXP_MAX;
+        let frac = bits & ((1 << F64_FRAC_BITS) - 1);
+
+        let (qexp, qfrac) = if exp == F64_EXP_MAX {
+            // Infinity or NaN, preserving the NaN payload
+            (QUAD_EXP_MAX, (frac as u128) << FRAC_SHIFT)
+        } else if exp == 0 {
+            if frac == 0 {
+                (0, 0)
+            } else {
+                // f64 subnormals are normal in quad; normalize so the leading 1 is implicit
+                let msb = 63 - frac.leading_zeros();
+                let e = msb as i32 - (F64_BIAS - 1) - F64_FRAC_BITS as i32;
+                let frac = (frac as u128) & !(1 << msb);
+                ((e + QUAD_BIAS) as u128, frac << (QUAD_FRAC_BITS - msb))
+            }
+        } else {
+            ((exp as i32 - F64_BIAS + QUAD_BIAS) as u128, (frac as u128) << FRAC_SHIFT)
+        };
+
+        XdrQuadruple::from_bits(sign << 127 | qexp << QUAD_FRAC_BITS | qfrac)
+    }
+
+    /// Convert the quadruple to the nearest `f64`.
+    pub fn to_f64(&self) -> f64 {
+        let bits = self.to_bits();
+        let sign = ((bits >> 127) as u64) << 63;
+        let qexp = (bits >> QUAD_FRAC_BITS) & QUAD_EXP_MAX;
+        let qfrac = bits & ((1 << QUAD_FRAC_BITS) - 1);
+
+        if qexp == QUAD_EXP_MAX {
+            let frac = (qfrac >> FRAC_SHIFT) as u64;
+            return if qfrac == 0 {
+                f64::from_bits(sign | F64_EXP_MAX << F64_FRAC_BITS)
+            } else {
+                // Keep as much payload as fits, but make sure it stays a NaN
+                f64::from_bits(sign | F64_EXP_MAX << F64_FRAC_BITS | frac | 1 << (F64_FRAC_BITS - 1))
+            };
+        }
+        if qexp == 0 {
+            // Zero, or a quad subnormal which is far too small for f64
+            return f64::from_bits(sign);
+        }
+
+        let exp = qexp as i32 - QUAD_BIAS;
+        if exp > F64_BIAS {
+            return f64::from_bits(sign | F64_EXP_MAX << F64_FRAC_BITS);
+        }
+
+        // Significand including the implicit leading 1, and how far to shift it down to fit
+        // in an f64 (more for results which are f64 subnormals)
+        let sig = qfrac | 1 << QUAD_FRAC_BITS;
+        let shift = if exp >= 1 - F64_BIAS {
+            FRAC_SHIFT
+        } else {
+            FRAC_SHIFT + (1 - F64_BIAS - exp) as u32
+        };
+        if shift > QUAD_FRAC_BITS + 1 {
+            return f64::from_bits(sign);
+        }
+
+        let mut m = (sig >> shift) as u64;
+        let rem = sig & ((1 << shift) - 1);
+        let half = 1 << (shift - 1);
+        if rem > half || (rem == half && m & 1 == 1) {
+            m += 1;
+        }
+
+        let bits = if shift == FRAC_SHIFT {
+            // Normal: m includes the implicit 1, which adds one to the exponent (as does any
+            // carry out of the significand from rounding)
+            (((exp + F64_BIAS - 1) as u64) << F64_FRAC_BITS) + m
+        } else {
+            // Subnormal: rounding up to 1 << 52 gives the smallest normal
+            m
+        };
+        if bits >> F64_FRAC_BITS >= F64_EXP_MAX {
+            return f64::from_bits(sign | F64_EXP_MAX << F64_FRAC_BITS);
+        }
+
+        f64::from_bits(sign | bits)
+    }
+}
+
+impl From<f64> for XdrQuadruple {
+    fn from(v: f64) -> Self {
+        XdrQuadruple::from_f64(v)
+    }
+}
+
+impl From<XdrQuadruple> for f64 {
+    fn from(v: XdrQuadruple) -> Self {
+        v.to_f64()
+    }
+}
+
+impl<Out: Write> Pack<Out> for XdrQuadruple {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        out.write_all(&self.0)?;
+        Ok(16)
+    }
+}
+
+impl<In: Read> Unpack<In> for XdrQuadruple {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut b = [0; 16];
+        input.read_exact(&mut b)?;
+        Ok((XdrQuadruple(b), 16))
+    }
+}
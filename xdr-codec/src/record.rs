@@ -12,14 +12,42 @@
 //!
 //! There's no magic number or other way to determine whether a stream
 //! is using record marking; both ends must agree.
-use std::io::{self, Read, BufRead, Write};
+use std::io::{self, Read, BufRead, Write, Seek, SeekFrom};
 use std::cmp::min;
 
 use error::*;
 
-use super::{unpack, pack, Error};
+use byteorder::{BigEndian, ByteOrder};
 
-const LAST_REC: u32 = 1u32 << 31;
+use super::{host_len, pack, unpack_complete, Error, Pack, Unpack};
+use crc::Crc32;
+use stream::StatsHook;
+
+pub(crate) const LAST_REC: u32 = 1u32 << 31;
+const MAX_FRAG: usize = (LAST_REC - 1) as usize;
+const CRCSZ: usize = 4;
+
+/// Statistics for a record reader or writer.
+///
+/// These are cumulative from when the reader or writer was created, and are returned as a
+/// snapshot by the `stats` method of the readers and writers in this module. They can also be
+/// pushed to an application's metrics system with `set_stats_hook`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordStats {
+    /// Complete records (fragments with the end of record marker).
+    pub records: u64,
+    /// Record fragments.
+    pub fragments: u64,
+    /// Payload bytes, not including fragment headers.
+    pub bytes: u64,
+    /// IO errors encountered.
+    pub errors: u64,
+}
+
+// Errors which leave the stream intact, so the operation can be retried
+pub(crate) fn is_retry(err: &io::Error) -> bool {
+    matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted)
+}
 
 fn mapioerr(xdrerr: Error) -> io::Error {
     match xdrerr {
@@ -34,11 +62,40 @@ fn mapioerr(xdrerr: Error) -> io::Error {
 /// beyond. The `BufRead` trait doesn't otherwise allow for record
 /// boundaries to be deliniated. Callers can use the `eor` method to
 /// determine record ends.
+///
+/// The reader implements `BufRead` as well as `Read`: `fill_buf` returns
+/// the underlying reader's buffered data, limited to the rest of the
+/// current fragment, and `consume` advances through it. Fragment headers
+/// are skipped transparently, so both traits see the record payloads as a
+/// continuous stream. Since all reads are served from the underlying
+/// `BufRead`'s buffer, unpacking values a word at a time doesn't result
+/// in a read from the transport for each word.
+///
+/// The reader can be used over a non-blocking stream. If the underlying
+/// reader returns `WouldBlock` (or `Interrupted`), the error is passed
+/// on, but the reader's state - including a partially read fragment
+/// header, or the part of a record or fragment accumulated by
+/// `records()` or `fragments()` - is kept, so the operation can simply be
+/// retried when more data is available.
+///
+/// If the stream was written with per-record checksums (see
+/// `XdrRecordWriter::set_checksum`), enable them with `set_checksum` to
+/// have each record's checksum removed and verified.
 #[derive(Debug)]
 pub struct XdrRecordReader<R: BufRead> {
     size: usize,                // record size
     consumed: usize,            // bytes consumed
     eor: bool,                  // is last record
+    hdr: [u8; 4],               // partially read fragment header or checksum
+    hdrlen: usize,              // bytes of header read
+    partial: Option<Vec<u8>>,   // partial record or fragment for interrupted iterators
+    checksum: Option<Crc32>,    // running checksum of current record, if enabled
+    trailer: bool,              // current fragment ends with an unread checksum
+    stats: RecordStats,         // running totals
+    hook: StatsHook<RecordStats>, // called when stats change
+    recsz: usize,               // declared size of current record so far
+    max_fragment: Option<usize>, // fragment size limit
+    max_record: Option<usize>,  // record size limit
 
     reader: R,                  // reader
 }
@@ -51,36 +108,362 @@ impl<R: BufRead> XdrRecordReader<R> {
             size: 0,
             consumed: 0,
             eor: false,
+            hdr: [0; 4],
+            hdrlen: 0,
+            partial: None,
+            checksum: None,
+            trailer: false,
+            stats: RecordStats::default(),
+            hook: StatsHook::default(),
+            recsz: 0,
+            max_fragment: None,
+            max_record: None,
             reader: rd
         }
     }
 
+    /// Set the maximum size of a single fragment. Reading a fragment header with a larger size
+    /// fails with an `InvalidData` IO error wrapping `ErrorKind::FragmentTooLarge`. The default
+    /// is no limit (other than the 2^31-1 imposed by the format).
+    pub fn set_max_fragment(&mut self, max: Option<usize>) {
+        self.max_fragment = max;
+    }
+
+    /// Set the maximum size of a complete record. This is checked as each fragment header is
+    /// read, so a record which would exceed it fails with an `InvalidData` IO error wrapping
+    /// `ErrorKind::RecordTooLarge` before its data is read. The default is no limit.
+    ///
+    /// Without a limit, a peer can send an unbounded number of fragments for a single record,
+    /// which will exhaust memory if the records are being collected with `records()`.
+    pub fn set_max_record(&mut self, max: Option<usize>) {
+        self.max_record = max;
+    }
+
+    /// Enable or disable verification of per-record checksums.
+    ///
+    /// When enabled, the last 4 bytes of each record's final fragment are taken to be a CRC-32
+    /// of the record, as written by an `XdrRecordWriter` with checksums enabled. They're removed
+    /// from the record data, and checked once the rest of the record has been read. A mismatch
+    /// fails with an `InvalidData` IO error wrapping `ErrorKind::ChecksumMismatch`, and a final
+    /// fragment too short to contain a checksum with `ErrorKind::MissingChecksum`.
+    ///
+    /// With `records()` and `fragments()` the checksum is verified before the record (or its
+    /// final fragment) is returned. With `Read` and `BufRead`, it's verified by the first read
+    /// after the end of the record. This should be set before anything is read, or at a record
+    /// boundary.
+    pub fn set_checksum(&mut self, enable: bool) {
+        self.checksum = if enable { Some(Crc32::default()) } else { None };
+    }
+
+    // Read a 32-bit word (fragment header or checksum), a piece at a time so that it can be
+    // resumed. Returns None on EOF, including in the middle of the word.
+    fn read_word(&mut self) -> io::Result<Option<u32>> {
+        while self.hdrlen < self.hdr.len() {
+            let sz = match self.reader.fill_buf() {
+                Ok([]) => {
+                    self.hdrlen = 0;
+                    return Ok(None)
+                },
+                Ok(data) => {
+                    let sz = min(data.len(), self.hdr.len() - self.hdrlen);
+                    self.hdr[self.hdrlen..self.hdrlen + sz].copy_from_slice(&data[..sz]);
+                    sz
+                },
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    if !is_retry(&e) {
+                        self.stats.errors += 1;
+                        self.hook.notify(&self.stats);
+                    }
+                    return Err(e)
+                },
+            };
+            self.reader.consume(sz);
+            self.hdrlen += sz;
+        }
+        self.hdrlen = 0;
+
+        Ok(Some(BigEndian::read_u32(&self.hdr)))
+    }
+
     // read next record, returns true on EOF
     fn nextrec(&mut self) -> io::Result<bool> {
         assert_eq!(self.consumed, self.size);
 
-        let rechdr: u32 =
-            match unpack(&mut self.reader) {
-                Ok(v) => v,
-                Err(Error(ErrorKind::IOError(ref err), _)) if err.kind() == io::ErrorKind::UnexpectedEof =>
-                    return Ok(true),
-                Err(e) =>
-                    return Err(mapioerr(e)),
-            };
+        let rechdr = match self.read_word()? {
+            Some(rechdr) => rechdr,
+            None => return Ok(true),
+        };
 
-        self.size = (rechdr & !LAST_REC) as usize;
+        let size = match host_len(rechdr & !LAST_REC) {
+            Ok(size) => size,
+            Err(e) => return Err(self.data_error(e.0)),
+        };
+        let eor = (rechdr & LAST_REC) != 0;
+        if self.eor {
+            self.recsz = 0;
+            if let Some(ref mut crc) = self.checksum {
+                *crc = Crc32::default();
+            }
+        }
+        let recsz = self.recsz.saturating_add(size);
+
+        if let Some(max) = self.max_fragment {
+            if size > max {
+                return Err(self.data_error(ErrorKind::FragmentTooLarge(size, max)));
+            }
+        }
+        if let Some(max) = self.max_record {
+            if recsz > max {
+                return Err(self.data_error(ErrorKind::RecordTooLarge(recsz, max)));
+            }
+        }
+        if eor && self.checksum.is_some() && size < CRCSZ {
+            return Err(self.data_error(ErrorKind::MissingChecksum(size)));
+        }
+
+        self.size = size;
         self.consumed = 0;
-        self.eor = (rechdr & LAST_REC) != 0;
+        self.eor = eor;
+        self.recsz = recsz;
+        self.trailer = eor && self.checksum.is_some();
+
+        trace_event!(trace, size, eor, "read fragment");
+        if eor {
+            trace_event!(trace, size = recsz, "end of record");
+        }
+
+        self.stats.fragments += 1;
+        if self.eor && self.totremains() == 0 {
+            self.stats.records += 1;
+            self.hook.notify(&self.stats);
+        }
 
         Ok(false)
     }
 
-    fn totremains(&self) -> usize { self.size - self.consumed }
+    // Read and verify the checksum at the end of the current record, if all its data has been
+    // read and the checksum hasn't. Returns true on EOF.
+    fn check_trailer(&mut self) -> io::Result<bool> {
+        if !self.trailer || self.totremains() > 0 {
+            return Ok(false);
+        }
+
+        let expected = match self.read_word()? {
+            Some(crc) => crc,
+            None => return Ok(true),
+        };
+        self.trailer = false;
+        self.consumed = self.size;
+
+        let actual = self.checksum.map_or(0, |c| c.value());
+        if expected != actual {
+            return Err(self.data_error(ErrorKind::ChecksumMismatch(expected, actual)));
+        }
+        Ok(false)
+    }
+
+    fn data_error(&mut self, kind: ErrorKind) -> io::Error {
+        trace_event!(debug, error = %kind, "bad record framing");
+        self.stats.errors += 1;
+        self.hook.notify(&self.stats);
+        io::Error::new(io::ErrorKind::InvalidData, Error::from(kind))
+    }
+
+    // Fragment payload remaining, not including any checksum
+    fn totremains(&self) -> usize {
+        self.size - self.consumed - if self.trailer { CRCSZ } else { 0 }
+    }
 
     /// Current fragment is the end of the record.
     pub fn eor(&self) -> bool {
         self.eor
     }
+
+    /// Return a snapshot of the statistics for this reader.
+    pub fn stats(&self) -> RecordStats {
+        self.stats
+    }
+
+    /// Set a function to be called with the statistics after each complete record is read, and
+    /// after each error, replacing any previous one.
+    pub fn set_stats_hook<F: FnMut(&RecordStats) + Send + 'static>(&mut self, hook: F) {
+        self.hook.set(hook)
+    }
+
+    /// Return an iterator over the complete records in the stream.
+    ///
+    /// Each record is reassembled from its fragments and returned as a `Vec<u8>`. This should be
+    /// called at a record boundary; if part of a record has already been read, the first item
+    /// will be the remainder of that record.
+    pub fn records(&mut self) -> XdrRecords<'_, R> {
+        XdrRecords { reader: self, done: false }
+    }
+
+    /// Read the next complete record and unpack it as a `T`.
+    ///
+    /// The value must take up the whole record: if there's anything left over, this fails with
+    /// `ErrorKind::TrailingData` (the record is still consumed, so the next call reads the one
+    /// after it). Returns `None` at the end of the stream. Like `records`, this should be called
+    /// at a record boundary.
+    pub fn unpack_record<T>(&mut self) -> Result<Option<T>>
+        where T: for<'a> Unpack<&'a [u8]>
+    {
+        match self.records().next() {
+            None => Ok(None),
+            Some(rec) => unpack_complete(&rec?).map(Some),
+        }
+    }
+
+    /// Return an iterator over the raw fragments in the stream.
+    ///
+    /// Each fragment is returned with its end of record flag, without being reassembled into
+    /// records. Zero-length fragments are returned too. If part of a fragment has already been
+    /// read, the first item will be the remainder of that fragment.
+    pub fn fragments(&mut self) -> XdrFragments<'_, R> {
+        XdrFragments { reader: self, done: false }
+    }
+
+    /// Declared size of the current fragment, not including its header. This includes the
+    /// checksum, if it's the final fragment of a record and checksums are enabled.
+    pub fn fragment_size(&self) -> usize {
+        self.size
+    }
+
+    /// Bytes remaining to be read in the current fragment, not including any checksum.
+    pub fn fragment_remaining(&self) -> usize {
+        self.totremains()
+    }
+
+    /// Return the underlying reader, along with any bytes this reader has taken from it but not
+    /// yet returned.
+    ///
+    /// The leftover bytes are those accumulated by a `records()` or `fragments()` call which was
+    /// interrupted by a retryable error, followed by any partially read fragment header (or
+    /// checksum). They're
+    /// empty if the reader is at a fragment boundary and no iterator was interrupted. Data which
+    /// the underlying `BufRead` has buffered but not yet handed out (such as `BufReader::buffer`)
+    /// remains in the returned reader, as does the unread part of the current fragment (see
+    /// `fragment_remaining`).
+    pub fn into_inner(self) -> (R, Vec<u8>) {
+        let mut leftover = self.partial.unwrap_or_default();
+        leftover.extend_from_slice(&self.hdr[..self.hdrlen]);
+        (self.reader, leftover)
+    }
+
+    // Read the rest of the current fragment, or the next one. Returns None at EOF or on a short
+    // read.
+    fn read_fragment(&mut self) -> Option<io::Result<RecordFragment>> {
+        let mut data = match self.partial.take() {
+            Some(data) => data,
+            None => {
+                // Check the previous record's checksum, if its data was read some other way
+                match self.check_trailer() {
+                    Err(e) => return Some(Err(e)),
+                    Ok(true) => return None,
+                    Ok(false) => (),
+                }
+                if self.totremains() == 0 {
+                    match self.nextrec() {
+                        Err(e) => return Some(Err(e)),
+                        Ok(true) => return None,
+                        Ok(false) => (),
+                    }
+                }
+                Vec::new()
+            },
+        };
+
+        let remains = self.totremains();
+        let eor = self.eor();
+
+        match self.by_ref().take(remains as u64).read_to_end(&mut data) {
+            Ok(sz) if sz == remains => (),
+            Ok(_) => return None,
+            Err(e) => {
+                // Keep what we have so far if we can resume
+                if is_retry(&e) {
+                    self.partial = Some(data);
+                }
+                return Some(Err(e))
+            },
+        }
+
+        match self.check_trailer() {
+            Ok(false) => Some(Ok(RecordFragment { eor, data })),
+            Ok(true) => None,
+            Err(e) => {
+                if is_retry(&e) {
+                    self.partial = Some(data);
+                }
+                Some(Err(e))
+            },
+        }
+    }
+
+    // Read the rest of the current record, or the next one. Returns None at EOF or on a short
+    // read.
+    pub(crate) fn read_record(&mut self) -> Option<io::Result<Vec<u8>>> {
+        let mut buf = match self.partial.take() {
+            Some(buf) => buf,
+            None => {
+                // Check the previous record's checksum, if its data was read some other way
+                match self.check_trailer() {
+                    Err(e) => return Some(Err(e)),
+                    Ok(true) => return None,
+                    Ok(false) => (),
+                }
+                Vec::new()
+            },
+        };
+
+        // loop over fragments until we get a complete record
+        loop {
+            // Do we need next fragment?
+            if self.totremains() == 0 {
+                if self.trailer {                   // resuming in the checksum
+                    break
+                }
+                match self.nextrec() {
+                    Err(e) => {                     // IO error
+                        if is_retry(&e) {
+                            self.partial = Some(buf);
+                        }
+                        return Some(Err(e))
+                    },
+                    Ok(true) => return None,        // EOF
+                    Ok(false) => (),                // keep going
+                }
+            }
+
+            let remains = self.totremains();
+            let eor = self.eor();
+
+            match self.by_ref().take(remains as u64).read_to_end(&mut buf) {
+                Ok(sz) if sz == remains => (),  // OK, keep going
+                Ok(_) => return None,           // short read
+                Err(e) => {                     // error, maybe resumable
+                    if is_retry(&e) {
+                        self.partial = Some(buf);
+                    }
+                    return Some(Err(e))
+                },
+            };
+
+            if eor { break }
+        }
+
+        match self.check_trailer() {
+            Ok(false) => Some(Ok(buf)),
+            Ok(true) => None,
+            Err(e) => {
+                if is_retry(&e) {
+                    self.partial = Some(buf);
+                }
+                Some(Err(e))
+            },
+        }
+    }
 }
 
 impl<R: BufRead> Read for XdrRecordReader<R> {
@@ -100,20 +483,42 @@ impl<R: BufRead> Read for XdrRecordReader<R> {
 impl<R: BufRead> BufRead for XdrRecordReader<R> {
     fn fill_buf(&mut self) -> io::Result<&[u8]> {
         while self.totremains() == 0 {
-            if self.nextrec()? {
+            if self.check_trailer()? || self.nextrec()? {
                 return Ok(&[])
             }
         }
 
         let remains = self.totremains();
-        let data = self.reader.fill_buf()?;
-        Ok(&data[..min(data.len(), remains)])
+        match self.reader.fill_buf() {
+            Ok(data) => Ok(&data[..min(data.len(), remains)]),
+            Err(e) => {
+                if !is_retry(&e) {
+                    self.stats.errors += 1;
+                    self.hook.notify(&self.stats);
+                }
+                Err(e)
+            },
+        }
     }
 
     fn consume(&mut self, sz: usize) {
         assert!(sz <= self.totremains());
+        if sz > 0 {
+            if let Some(ref mut crc) = self.checksum {
+                // Already buffered by the preceding fill_buf
+                if let Ok(data) = self.reader.fill_buf() {
+                    crc.update(&data[..min(sz, data.len())]);
+                }
+            }
+        }
         self.consumed += sz;
         self.reader.consume(sz);
+
+        self.stats.bytes += sz as u64;
+        if sz > 0 && self.eor && self.totremains() == 0 {
+            self.stats.records += 1;
+            self.hook.notify(&self.stats);
+        }
     }
 }
 
@@ -133,7 +538,8 @@ impl<R: BufRead> IntoIterator for XdrRecordReader<R> {
 ///
 /// It will return an IO error once, and then end the iterator.
 /// A short read or an unterminated record will also end the iterator. It will not return a partial
-/// record.
+/// record. The exceptions are `WouldBlock` and `Interrupted` errors, after which the iterator
+/// can be resumed, and will continue with the record it was reading.
 #[derive(Debug)]
 pub struct XdrRecordReaderIter<R: BufRead>(Option<XdrRecordReader<R>>);
 
@@ -142,48 +548,113 @@ impl<R: BufRead> Iterator for XdrRecordReaderIter<R> {
 
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(mut rr) = self.0.take() {
-            let mut buf = Vec::new();
-
-            // loop over fragments until we get a complete record
-            loop {
-                // Do we need next fragment?
-                if rr.totremains() == 0 {
-                    match rr.nextrec() {
-                        Err(e) => return Some(Err(e)),  // IO error
-                        Ok(true) => return None,        // EOF
-                        Ok(false) => (),                // keep going
+            match rr.read_record() {
+                Some(Ok(buf)) => {
+                    self.0 = Some(rr);
+                    Some(Ok(buf))
+                },
+                Some(Err(e)) => {
+                    if is_retry(&e) {
+                        self.0 = Some(rr);
                     }
-                }
-
-                let remains = rr.totremains();
-                let eor = rr.eor();
-
-                match rr.by_ref().take(remains as u64).read_to_end(&mut buf) {
-                    Ok(sz) if sz == remains => (),  // OK, keep going
-                    Ok(_) => return None,           // short read
-                    Err(e) => return Some(Err(e)),  // error
-                };
-
-                if eor { break }
+                    Some(Err(e))
+                },
+                None => None,
             }
-            self.0 = Some(rr);
-            Some(Ok(buf))
         } else {
             None
         }
     }
 }
 
+/// A single record fragment, as returned by `XdrRecordReader::fragments`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordFragment {
+    /// Fragment had the end of record marker set.
+    pub eor: bool,
+    /// Fragment payload.
+    pub data: Vec<u8>,
+}
+
+/// Iterator over raw fragments in the stream, borrowing the reader.
+///
+/// This is returned by `XdrRecordReader::fragments`. Like `XdrRecords`, it ends after returning
+/// an IO error (other than `WouldBlock` or `Interrupted`), or on EOF or a short read.
+#[derive(Debug)]
+pub struct XdrFragments<'a, R: 'a + BufRead> {
+    reader: &'a mut XdrRecordReader<R>,
+    done: bool,
+}
+
+impl<'a, R: BufRead> Iterator for XdrFragments<'a, R> {
+    type Item = io::Result<RecordFragment>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.reader.read_fragment();
+        match res {
+            Some(Ok(_)) => (),
+            Some(Err(ref e)) if is_retry(e) => (),
+            _ => self.done = true,
+        }
+        res
+    }
+}
+
+/// Iterator over records in the stream, borrowing the reader.
+///
+/// This is returned by `XdrRecordReader::records`, and yields the same results as
+/// `XdrRecordReaderIter`. After it ends, the reader is positioned after the last record returned
+/// (or wherever an error left it).
+#[derive(Debug)]
+pub struct XdrRecords<'a, R: 'a + BufRead> {
+    reader: &'a mut XdrRecordReader<R>,
+    done: bool,
+}
+
+impl<'a, R: BufRead> Iterator for XdrRecords<'a, R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.reader.read_record();
+        match res {
+            Some(Ok(_)) => (),
+            Some(Err(ref e)) if is_retry(e) => (),
+            _ => self.done = true,
+        }
+        res
+    }
+}
+
 const WRBUF: usize = 65536;
+const FRAGHDR: usize = 4;
 
 /// Write records into a bytestream.
 ///
-/// Flushes the current buffer as end of record when destroyed.
+/// Writes are accumulated in a buffer, and each fragment (including its header) is passed to the
+/// underlying writer with a single `write_all` when the buffer fills or is flushed. This means
+/// packing a structure field by field doesn't result in lots of small writes to the transport.
+///
+/// When destroyed, this flushes the current buffer as end of record if there's anything in it or
+/// the last record hasn't been ended, so a writer which was never written to writes an empty
+/// record. If the last record was already ended and nothing has been written since, nothing is
+/// written. Any error from that write is ignored; use `finish` to end the stream and see errors.
+///
+/// Optionally, each record can be followed by a CRC-32 of its contents (see `set_checksum`), for
+/// integrity protection of long-lived record streams. Both ends must agree on this.
 pub struct XdrRecordWriter<W: Write> {
-    buf: Vec<u8>,   // accumulated record fragment
+    buf: Vec<u8>,   // fragment header space followed by accumulated record fragment
     bufsz: usize,   // max fragment size
     eor: bool,      // last fragment was eor
-    writer: W,      // writer we're passing on to
+    checksum: Option<Crc32>, // running checksum of current record, if enabled
+    stats: RecordStats, // running totals
+    hook: StatsHook<RecordStats>, // called when stats change
+    writer: Option<W>, // writer we're passing on to; only None once finished
 }
 
 impl<W: Write> XdrRecordWriter<W> {
@@ -195,35 +666,203 @@ impl<W: Write> XdrRecordWriter<W> {
 
     /// Create an instance with a specific buffer size. Panics if the
     /// size is zero.
+    ///
+    /// The buffer size is the maximum fragment size; this is the same as
+    /// `with_fragment_size`.
     pub fn with_buffer(w: W, bufsz: usize) -> XdrRecordWriter<W> {
+        XdrRecordWriter::with_fragment_size(w, bufsz)
+    }
+
+    /// Create an instance which writes fragments of at most `fragsz`
+    /// bytes (not including the fragment header). Records larger than
+    /// this are split into multiple fragments. The writer buffers up to
+    /// one fragment in memory. Panics if the size is zero or larger than
+    /// the maximum fragment size of 2^31-1.
+    pub fn with_fragment_size(w: W, bufsz: usize) -> XdrRecordWriter<W> {
         if bufsz == 0 { panic!("bufsz must be non-zero") }
+        if bufsz > MAX_FRAG { panic!("bufsz must be less than 2^31") }
+        let mut buf = Vec::with_capacity(FRAGHDR + bufsz);
+        buf.extend_from_slice(&[0; FRAGHDR]);
         XdrRecordWriter {
-            buf: Vec::with_capacity(bufsz),
+            buf,
             bufsz: bufsz,
             eor: false,
-            writer: w
+            checksum: None,
+            stats: RecordStats::default(),
+            hook: StatsHook::default(),
+            writer: Some(w),
         }
     }
 
+    /// Maximum fragment size.
+    pub fn fragment_size(&self) -> usize {
+        self.bufsz
+    }
+
+    /// Enable or disable per-record checksums.
+    ///
+    /// When enabled, the end of each record is followed by a 4-byte big-endian CRC-32 (IEEE) of
+    /// the record's contents, as part of its final fragment. The reader must have checksums
+    /// enabled too, to verify and remove it. This should be set before anything is written, or
+    /// at a record boundary. Panics if enabled with a fragment size smaller than 4.
+    pub fn set_checksum(&mut self, enable: bool) {
+        if enable && self.bufsz < CRCSZ { panic!("fragsz too small for checksum") }
+        self.checksum = if enable { Some(Crc32::default()) } else { None };
+    }
+
+    /// End the current record, writing out any buffered data as a final
+    /// fragment with the end of record marker set. If nothing has been
+    /// written since the last record ended, this writes an empty record.
+    pub fn end_record(&mut self) -> io::Result<()> {
+        self.flush_eor(true)
+    }
+
+    /// Pack `val` and end the record, returning the size of its encoding.
+    ///
+    /// If nothing has been written to the record yet and the value's size hint shows it fits in
+    /// a single fragment, it's packed straight into the fragment buffer rather than going through
+    /// this writer's `Write` implementation. Otherwise this is the same as packing the value and
+    /// calling `end_record`.
+    pub fn pack_record<T>(&mut self, val: &T) -> Result<usize>
+        where T: ?Sized + Pack<Vec<u8>> + Pack<Self>
+    {
+        let trailer = if self.checksum.is_some() { CRCSZ } else { 0 };
+        let hint = Pack::<Vec<u8>>::packed_size_hint(val);
+
+        let sz = match hint {
+            Some(hint) if self.fragsz() == 0 && hint.saturating_add(trailer) <= self.bufsz => {
+                let sz = match Pack::<Vec<u8>>::pack(val, &mut self.buf) {
+                    Ok(sz) => sz,
+                    Err(e) => {
+                        self.buf.truncate(FRAGHDR);
+                        return Err(e);
+                    }
+                };
+                if self.fragsz() + trailer > self.bufsz {
+                    // The hint was wrong, so fragment it as usual
+                    let data = self.buf.split_off(FRAGHDR);
+                    self.write_all(&data)?;
+                } else if let Some(ref mut crc) = self.checksum {
+                    crc.update(&self.buf[FRAGHDR..]);
+                }
+                sz
+            }
+            _ => Pack::<Self>::pack(val, self)?,
+        };
+
+        self.end_record()?;
+        Ok(sz)
+    }
+
     /// Flush the current buffer. If `eor` is true, the end of record
     /// marker is set.
     pub fn flush_eor(&mut self, eor: bool) -> io::Result<()> {
-        if !eor && self.buf.len() == 0 { return Ok(()) }
+        if !eor && self.fragsz() == 0 { return Ok(()) }
 
-        let rechdr = self.buf.len() as u32 | (if eor { LAST_REC } else { 0 });
+        let res = if eor && self.checksum.is_some() {
+            self.write_checksum()
+        } else {
+            self.write_fragment(eor)
+        };
+        match res {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                self.stats.errors += 1;
+                self.hook.notify(&self.stats);
+                Err(e)
+            },
+        }
+    }
 
-        pack(&rechdr, &mut self.writer).map_err(mapioerr)?;
-        let _ = self.writer.write_all(&self.buf).map(|_| ())?;
-        self.buf.truncate(0);
+    // size of fragment payload accumulated so far
+    fn fragsz(&self) -> usize {
+        self.buf.len() - FRAGHDR
+    }
+
+    // Finish the record with its checksum, which must be entirely in the final fragment
+    fn write_checksum(&mut self) -> io::Result<()> {
+        if self.fragsz() + CRCSZ > self.bufsz {
+            self.write_fragment(false)?;
+        }
+
+        let crc = self.checksum.map_or(0, |c| c.value());
+        self.buf.extend_from_slice(&crc.to_be_bytes());
+        if let Err(e) = self.write_fragment(true) {
+            // Leave the buffer as it was, so a retry doesn't add a second checksum
+            let len = self.buf.len() - CRCSZ;
+            self.buf.truncate(len);
+            return Err(e);
+        }
+
+        self.checksum = Some(Crc32::default());
+        Ok(())
+    }
+
+    fn write_fragment(&mut self, eor: bool) -> io::Result<()> {
+        let fragsz = self.fragsz();
+        let rechdr = fragsz as u32 | (if eor { LAST_REC } else { 0 });
+
+        pack(&rechdr, &mut &mut self.buf[..FRAGHDR]).map_err(mapioerr)?;
+        let writer = self.writer.as_mut().expect("writer already taken");
+        writer.write_all(&self.buf)?;
+
+        trace_event!(trace, size = fragsz, eor, "wrote fragment");
+
+        // The checksum isn't payload
+        let trailer = if eor && self.checksum.is_some() { CRCSZ } else { 0 };
+        self.stats.fragments += 1;
+        self.stats.bytes += (fragsz - trailer) as u64;
+        if eor {
+            self.stats.records += 1;
+            self.hook.notify(&self.stats);
+        }
+        self.buf.truncate(FRAGHDR);
 
         self.eor = eor;
-        self.writer.flush()
+        writer.flush()
+    }
+
+    // Whether the stream needs a final end of record fragment
+    fn needs_eor(&self) -> bool {
+        self.fragsz() > 0 || !self.eor
+    }
+
+    /// Return a snapshot of the statistics for this writer.
+    pub fn stats(&self) -> RecordStats {
+        self.stats
+    }
+
+    /// Set a function to be called with the statistics after each complete record is written, and
+    /// after each error, replacing any previous one.
+    pub fn set_stats_hook<F: FnMut(&RecordStats) + Send + 'static>(&mut self, hook: F) {
+        self.hook.set(hook)
+    }
+
+    /// End the stream and return the underlying writer.
+    ///
+    /// This writes out any buffered data as a final fragment with the end of record marker set,
+    /// in the same way as dropping the writer would. If that fails the error is returned and the
+    /// underlying writer is dropped without any further attempt to write to it.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.needs_eor() {
+            if let Err(e) = self.flush_eor(true) {
+                self.writer = None;
+                return Err(e);
+            }
+        }
+        Ok(self.writer.take().expect("writer already taken"))
+    }
+
+    /// Return the underlying writer without writing anything further. Any buffered data which
+    /// hasn't been flushed is discarded, and the current record is left unterminated.
+    pub fn into_inner(mut self) -> W {
+        self.writer.take().expect("writer already taken")
     }
 }
 
 impl<W: Write> Drop for XdrRecordWriter<W> {
     fn drop(&mut self) {
-        if self.buf.len() > 0 || !self.eor {
+        if self.writer.is_some() && self.needs_eor() {
             let _ = self.flush_eor(true);
         }
     }
@@ -235,10 +874,13 @@ impl<W: Write> Write for XdrRecordWriter<W> {
 
         while off < buf.len() {
             let chunk = &buf[off..off+min(buf.len() - off, self.bufsz)];
-            if self.buf.len() + chunk.len() > self.bufsz {
+            if self.fragsz() + chunk.len() > self.bufsz {
                 self.flush()?;
             }
 
+            if let Some(ref mut crc) = self.checksum {
+                crc.update(chunk);
+            }
             self.buf.extend(chunk);
             off += chunk.len();
         }
@@ -250,3 +892,146 @@ impl<W: Write> Write for XdrRecordWriter<W> {
         self.flush_eor(false)
     }
 }
+
+/// Write records into a seekable bytestream without buffering.
+///
+/// Rather than accumulating each fragment in memory, this writes a placeholder fragment header
+/// at the start of the record, passes the payload straight through to the underlying writer, and
+/// then seeks back to fill in the real length when the record is ended. Records are therefore
+/// normally written as a single fragment; they're only split if they exceed the maximum fragment
+/// size of 2^31-1 bytes.
+///
+/// Ends the current record when destroyed.
+pub struct XdrRecordSeekWriter<W: Write + Seek> {
+    hdrpos: Option<u64>,    // position of current fragment header, if there is one
+    fragsz: usize,          // size of current fragment
+    stats: RecordStats,     // running totals
+    hook: StatsHook<RecordStats>, // called when stats change
+    writer: W,              // writer we're passing on to
+}
+
+impl<W: Write + Seek> XdrRecordSeekWriter<W> {
+    /// Create a new `XdrRecordSeekWriter` wrapped around a seekable `Write` implementation.
+    pub fn new(w: W) -> XdrRecordSeekWriter<W> {
+        XdrRecordSeekWriter {
+            hdrpos: None,
+            fragsz: 0,
+            stats: RecordStats::default(),
+            hook: StatsHook::default(),
+            writer: w,
+        }
+    }
+
+    // Start a new fragment with a placeholder header
+    fn start_fragment(&mut self) -> io::Result<()> {
+        let pos = self.writer.stream_position()?;
+
+        pack(&0u32, &mut self.writer).map_err(mapioerr)?;
+        self.hdrpos = Some(pos);
+        self.fragsz = 0;
+        Ok(())
+    }
+
+    // Fill in the current fragment's header, and leave the stream positioned after its payload
+    fn end_fragment(&mut self, eor: bool) -> io::Result<()> {
+        let pos = match self.hdrpos {
+            Some(pos) => pos,
+            None => return Ok(()),
+        };
+        let rechdr = self.fragsz as u32 | (if eor { LAST_REC } else { 0 });
+
+        self.writer.seek(SeekFrom::Start(pos))?;
+        pack(&rechdr, &mut self.writer).map_err(mapioerr)?;
+        self.writer.seek(SeekFrom::Current(self.fragsz as i64))?;
+
+        self.stats.fragments += 1;
+        if eor {
+            self.stats.records += 1;
+            self.hook.notify(&self.stats);
+        }
+        self.hdrpos = None;
+        self.fragsz = 0;
+        Ok(())
+    }
+
+    /// End the current record.
+    ///
+    /// This patches the length of the record's final fragment and sets its end of record marker.
+    /// If nothing has been written since the last record ended, this emits an empty record.
+    pub fn end_record(&mut self) -> io::Result<()> {
+        let res = if self.hdrpos.is_some() {
+            self.end_fragment(true)
+        } else {
+            self.start_fragment().and_then(|_| self.end_fragment(true))
+        };
+
+        match res.and_then(|_| self.writer.flush()) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                self.stats.errors += 1;
+                self.hook.notify(&self.stats);
+                Err(e)
+            },
+        }
+    }
+
+    /// Return a snapshot of the statistics for this writer.
+    pub fn stats(&self) -> RecordStats {
+        self.stats
+    }
+
+    /// Set a function to be called with the statistics after each complete record is written, and
+    /// after each error, replacing any previous one.
+    pub fn set_stats_hook<F: FnMut(&RecordStats) + Send + 'static>(&mut self, hook: F) {
+        self.hook.set(hook)
+    }
+
+    fn write_payload(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.hdrpos.is_none() {
+            self.start_fragment()?;
+        } else if self.fragsz == MAX_FRAG {
+            self.end_fragment(false)?;
+            self.start_fragment()?;
+        }
+
+        let len = min(buf.len(), MAX_FRAG - self.fragsz);
+        let n = self.writer.write(&buf[..len])?;
+
+        self.fragsz += n;
+        self.stats.bytes += n as u64;
+        Ok(n)
+    }
+}
+
+impl<W: Write + Seek> Drop for XdrRecordSeekWriter<W> {
+    fn drop(&mut self) {
+        if self.hdrpos.is_some() {
+            let _ = self.end_record();
+        }
+    }
+}
+
+impl<W: Write + Seek> Write for XdrRecordSeekWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        match self.write_payload(buf) {
+            Ok(n) => Ok(n),
+            Err(e) => {
+                self.stats.errors += 1;
+                self.hook.notify(&self.stats);
+                Err(e)
+            },
+        }
+    }
+
+    /// Flush the underlying writer.
+    ///
+    /// Unlike `XdrRecordWriter`, this doesn't end the current fragment, as the fragment length
+    /// isn't known until the record is ended.
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
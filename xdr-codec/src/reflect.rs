@@ -0,0 +1,307 @@
+//! XDR descriptions of Rust types
+//!
+//! `XdrReflect` gives the XDR `Type` a Rust type is packed as, so that the XDR specification
+//! for types defined in Rust can be generated from their definitions and published for other
+//! implementations. A struct, enum or union is described as a named type, defined in a
+//! `Schema`; anything else is described directly. With the `derive` feature,
+//! `#[derive(XdrReflect)]` implements it for structs, using the same field attributes as
+//! `XdrPack` and `XdrUnpack`.
+//!
+//! `spec` renders the types in a schema as `.x` text, which `xdrgen` and `rpcgen` accept:
+//!
+//! ```
+//! use xdr_codec::reflect::{self, XdrReflect};
+//! use xdr_codec::value::{Schema, Type};
+//!
+//! struct Point { x: i32, y: i32 }
+//!
+//! impl XdrReflect for Point {
+//!     fn xdr_type(schema: &mut Schema) -> Type {
+//!         let fields = vec![("x".to_string(), i32::xdr_type(schema)),
+//!                           ("y".to_string(), i32::xdr_type(schema))];
+//!         schema.define("point", Type::Struct(fields));
+//!         Type::Named("point".to_string())
+//!     }
+//! }
+//!
+//! let mut schema = Schema::new();
+//! let _ = <Vec<Point>>::xdr_type(&mut schema);
+//! assert_eq!(reflect::spec(&schema).unwrap(), "struct point {\n    int x;\n    int y;\n};\n");
+//! ```
+//!
+//! Definitions are emitted sorted by name, except that the types each one refers to come
+//! before it. Union arms are named `arm`, and the discriminant `discriminant`, as they are in
+//! the JSON mapping. XDR can't nest an array, opaque, string or optional type directly inside
+//! another; those need to be named types to be rendered.
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use error::*;
+use fixed::FixedOpaque;
+use quadruple::XdrQuadruple;
+use string::XdrString;
+use value::{Schema, Type};
+use super::Opaque;
+
+/// A Rust type with a known XDR description.
+pub trait XdrReflect {
+    /// Return the XDR type of `Self`, defining any named types it uses in `schema`.
+    ///
+    /// Implementations for named types should define themselves only if `schema` doesn't
+    /// already have a definition, and define a placeholder while describing their contents so
+    /// that recursive types terminate.
+    fn xdr_type(schema: &mut Schema) -> Type;
+}
+
+macro_rules! reflect_simple {
+    ($($t:ty => $ty:expr),* $(,)*) => {
+        $(
+            impl XdrReflect for $t {
+                fn xdr_type(_: &mut Schema) -> Type { $ty }
+            }
+        )*
+    }
+}
+
+reflect_simple! {
+    () => Type::Void,
+    i32 => Type::Int,
+    u32 => Type::UInt,
+    usize => Type::UInt,
+    i64 => Type::Hyper,
+    u64 => Type::UHyper,
+    f32 => Type::Float,
+    f64 => Type::Double,
+    bool => Type::Bool,
+    XdrQuadruple => Type::Quadruple,
+    String => Type::String(None),
+    XdrString => Type::String(None),
+}
+
+impl<'a> XdrReflect for Opaque<'a> {
+    fn xdr_type(_: &mut Schema) -> Type {
+        Type::Opaque(None)
+    }
+}
+
+impl<'a> XdrReflect for Cow<'a, str> {
+    fn xdr_type(_: &mut Schema) -> Type {
+        Type::String(None)
+    }
+}
+
+impl<const N: usize> XdrReflect for FixedOpaque<N> {
+    fn xdr_type(_: &mut Schema) -> Type {
+        Type::FixedOpaque(N)
+    }
+}
+
+impl<T: XdrReflect, const N: usize> XdrReflect for [T; N] {
+    fn xdr_type(schema: &mut Schema) -> Type {
+        Type::FixedArray(Box::new(T::xdr_type(schema)), N)
+    }
+}
+
+impl<T: XdrReflect> XdrReflect for Vec<T> {
+    fn xdr_type(schema: &mut Schema) -> Type {
+        Type::Array(Box::new(T::xdr_type(schema)), None)
+    }
+}
+
+impl<T: XdrReflect> XdrReflect for Option<T> {
+    fn xdr_type(schema: &mut Schema) -> Type {
+        Type::Optional(Box::new(T::xdr_type(schema)))
+    }
+}
+
+impl<T: XdrReflect> XdrReflect for Box<T> {
+    fn xdr_type(schema: &mut Schema) -> Type {
+        T::xdr_type(schema)
+    }
+}
+
+impl Type {
+    /// Set the maximum length of a flexible array, opaque or string type. Other types are
+    /// returned unchanged.
+    pub fn with_max(self, max: usize) -> Type {
+        match self {
+            Type::Array(elem, _) => Type::Array(elem, Some(max)),
+            Type::Opaque(_) => Type::Opaque(Some(max)),
+            Type::String(_) => Type::String(Some(max)),
+            ty => ty,
+        }
+    }
+}
+
+/// Render the `.x` specification of `T`, and everything it refers to.
+pub fn spec_for<T: XdrReflect>() -> Result<String> {
+    let mut schema = Schema::new();
+    let _ = T::xdr_type(&mut schema);
+    spec(&schema)
+}
+
+/// Render all the types in `schema` as a `.x` specification.
+///
+/// Fails with `UnknownType` if a type refers to one which isn't defined, and with
+/// `InvalidSchema` if a type can't be expressed in XDR's syntax.
+pub fn spec(schema: &Schema) -> Result<String> {
+    let mut names: Vec<_> = schema.types().map(|(n, _)| n).collect();
+    names.sort();
+
+    let mut w = SpecWriter { schema, out: String::new(), done: HashSet::new() };
+    for name in names {
+        w.define(name)?;
+    }
+    Ok(w.out)
+}
+
+struct SpecWriter<'a> {
+    schema: &'a Schema,
+    out: String,
+    done: HashSet<&'a str>,
+}
+
+fn indent(level: usize) -> String {
+    "    ".repeat(level)
+}
+
+// Collect the named types `ty` refers to
+fn refs<'a>(ty: &'a Type, out: &mut Vec<&'a str>) {
+    match *ty {
+        Type::Named(ref name) => out.push(name),
+        Type::FixedArray(ref t, _) | Type::Array(ref t, _) | Type::Optional(ref t) => refs(t, out),
+        Type::Struct(ref fields) => for (_, t) in fields {
+            refs(t, out)
+        },
+        Type::Union { ref discriminant, ref cases, ref default } => {
+            refs(discriminant, out);
+            for (_, t) in cases {
+                refs(t, out);
+            }
+            if let Some(ref t) = *default {
+                refs(t, out);
+            }
+        },
+        _ => (),
+    }
+}
+
+impl<'a> SpecWriter<'a> {
+    // Emit the definition of `name`, after the types it refers to
+    fn define(&mut self, name: &'a str) -> Result<()> {
+        if !self.done.insert(name) {
+            return Ok(());
+        }
+        let ty = match self.schema.get(name) {
+            Some(ty) => ty,
+            None => bail!(ErrorKind::UnknownType(name.to_string())),
+        };
+
+        let mut deps = Vec::new();
+        refs(ty, &mut deps);
+        for dep in deps {
+            self.define(dep)?;
+        }
+
+        let defn = match *ty {
+            Type::Struct(ref fields) => format!("struct {} {}", name, self.struct_body(fields, 0)?),
+            Type::Enum(ref variants) => format!("enum {} {}", name, enum_body(variants, 0)),
+            Type::Union { ref discriminant, ref cases, ref default } =>
+                format!("union {} {}", name, self.union_body(discriminant, cases, default, 0)?),
+            ref ty => format!("typedef {}", self.decl(ty, name, 0)?),
+        };
+
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        let _ = writeln!(self.out, "{};", defn);
+        Ok(())
+    }
+
+    fn struct_body(&self, fields: &[(String, Type)], level: usize) -> Result<String> {
+        let mut body = String::from("{\n");
+        for (name, ty) in fields {
+            let _ = writeln!(body, "{}{};", indent(level + 1), self.decl(ty, name, level + 1)?);
+        }
+        body.push_str(&indent(level));
+        body.push('}');
+        Ok(body)
+    }
+
+    fn union_body(&self, disc: &Type, cases: &[(i32, Type)], default: &Option<Box<Type>>, level: usize) -> Result<String> {
+        let mut body = format!("switch ({}) {{\n", self.decl(disc, "discriminant", level)?);
+        for &(val, ref ty) in cases {
+            let _ = writeln!(body, "{}case {}:", indent(level), self.case_label(disc, val)?);
+            let _ = writeln!(body, "{}{};", indent(level + 1), self.decl(ty, "arm", level + 1)?);
+        }
+        if let Some(ref ty) = *default {
+            let _ = writeln!(body, "{}default:", indent(level));
+            let _ = writeln!(body, "{}{};", indent(level + 1), self.decl(ty, "arm", level + 1)?);
+        }
+        body.push_str(&indent(level));
+        body.push('}');
+        Ok(body)
+    }
+
+    // Name a union case by its enum variant, where there is one
+    fn case_label(&self, disc: &Type, val: i32) -> Result<String> {
+        Ok(match *self.schema.resolve(disc)? {
+            Type::Enum(ref variants) => match variants.iter().find(|v| v.1 == val) {
+                Some((name, _)) => name.clone(),
+                None => val.to_string(),
+            },
+            Type::Bool => (if val != 0 { "TRUE" } else { "FALSE" }).to_string(),
+            _ => val.to_string(),
+        })
+    }
+
+    // A declaration of `name` with type `ty`
+    fn decl(&self, ty: &Type, name: &str, level: usize) -> Result<String> {
+        Ok(match *ty {
+            Type::Void => "void".to_string(),
+            Type::FixedOpaque(n) => format!("opaque {}[{}]", name, n),
+            Type::Opaque(max) => format!("opaque {}<{}>", name, max_str(max)),
+            Type::String(max) => format!("string {}<{}>", name, max_str(max)),
+            Type::FixedArray(ref t, n) => format!("{} {}[{}]", self.type_spec(t, level)?, name, n),
+            Type::Array(ref t, max) => format!("{} {}<{}>", self.type_spec(t, level)?, name, max_str(max)),
+            Type::Optional(ref t) => format!("{} *{}", self.type_spec(t, level)?, name),
+            ref t => format!("{} {}", self.type_spec(t, level)?, name),
+        })
+    }
+
+    // The type specifier for `ty`, which can't itself be a declaration
+    fn type_spec(&self, ty: &Type, level: usize) -> Result<String> {
+        Ok(match *ty {
+            Type::Int => "int".to_string(),
+            Type::UInt => "unsigned int".to_string(),
+            Type::Hyper => "hyper".to_string(),
+            Type::UHyper => "unsigned hyper".to_string(),
+            Type::Float => "float".to_string(),
+            Type::Double => "double".to_string(),
+            Type::Quadruple => "quadruple".to_string(),
+            Type::Bool => "bool".to_string(),
+            Type::Named(ref name) => name.clone(),
+            Type::Enum(ref variants) => format!("enum {}", enum_body(variants, level)),
+            Type::Struct(ref fields) => format!("struct {}", self.struct_body(fields, level)?),
+            Type::Union { ref discriminant, ref cases, ref default } =>
+                format!("union {}", self.union_body(discriminant, cases, default, level)?),
+            ref ty => bail!(ErrorKind::InvalidSchema(format!("{:?} can't be nested in XDR; it needs a name", ty))),
+        })
+    }
+}
+
+fn enum_body(variants: &[(String, i32)], level: usize) -> String {
+    let mut body = String::from("{\n");
+    for (i, (name, val)) in variants.iter().enumerate() {
+        let sep = if i + 1 < variants.len() { "," } else { "" };
+        let _ = writeln!(body, "{}{} = {}{}", indent(level + 1), name, val, sep);
+    }
+    body.push_str(&indent(level));
+    body.push('}');
+    body
+}
+
+fn max_str(max: Option<usize>) -> String {
+    max.map_or(String::new(), |m| m.to_string())
+}
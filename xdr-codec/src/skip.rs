@@ -0,0 +1,198 @@
+//! Skipping over encoded values
+//!
+//! The `Skip` trait advances an input stream past an encoded value without constructing it. The
+//! default implementation simply unpacks the value and drops it, but the basic types override it
+//! to avoid allocating and decoding: fixed-size types skip their size, and opaque data and strings
+//! read their length and then discard the payload in chunks. Skipping doesn't validate the data
+//! (a skipped `String` isn't checked for UTF-8, nor a `bool` for being 0 or 1), so it only fails
+//! on IO errors or invalid lengths.
+//!
+//! Types which are just unpacked and dropped only need an empty `impl<In: Read> Skip<In> for
+//! MyType {}`.
+use std::io::{self, Read};
+use std::borrow::Cow;
+
+use error::*;
+
+use super::{Unpack, Opaque, XdrPrimitive, padding};
+
+/// Skip over an encoded value.
+pub trait Skip<In: Read>: Unpack<In> {
+    /// Advance `input` past a value of this type, returning the number of bytes consumed.
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> {
+        Self::unpack(input).map(|(_, sz)| sz)
+    }
+}
+
+/// Skip helper function
+///
+/// Skip over a value of type `T`, returning the number of bytes consumed.
+#[inline]
+pub fn skip<In: Read, T: Skip<In>>(input: &mut In) -> Result<usize> {
+    T::skip(input)
+}
+
+/// Discard `n` bytes from `input`, failing with `UnexpectedEof` if it ends first.
+pub fn skip_bytes<In: Read>(input: &mut In, n: usize) -> Result<usize> {
+    let skipped = io::copy(&mut input.take(n as u64), &mut io::sink())?;
+    if skipped < n as u64 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short skip").into());
+    }
+    Ok(n)
+}
+
+#[inline]
+fn skip_fixed<In: Read, T: XdrPrimitive>(input: &mut In) -> Result<usize> {
+    skip_bytes(input, T::XDR_SIZE)
+}
+
+fn flex_len<In: Read>(input: &mut In, maxsz: Option<usize>) -> Result<(usize, usize)> {
+    let (elems, sz) = usize::unpack(input)?;
+
+    if let Some(m) = maxsz {
+        if elems > m {
+            bail!(ErrorKind::InvalidLen(m));
+        }
+    }
+
+    Ok((elems, sz))
+}
+
+/// Skip a fixed-size opaque array of `sz` bytes.
+#[inline]
+pub fn skip_opaque_array<In: Read>(input: &mut In, sz: usize) -> Result<usize> {
+    skip_bytes(input, sz)?;
+    skip_bytes(input, padding(sz).len()).map(|p| sz + p)
+}
+
+/// Skip a (perhaps) length-limited opaque array.
+pub fn skip_opaque_flex<In: Read>(input: &mut In, maxsz: Option<usize>) -> Result<usize> {
+    let (elems, sz) = flex_len(input, maxsz)?;
+
+    skip_opaque_array(input, elems).map(|esz| sz + esz)
+}
+
+/// Skip a (perhaps) length-limited string.
+#[inline]
+pub fn skip_string<In: Read>(input: &mut In, maxsz: Option<usize>) -> Result<usize> {
+    skip_opaque_flex(input, maxsz)
+}
+
+/// Skip a fixed-size array of `sz` elements.
+pub fn skip_array<In: Read, T: Skip<In>>(input: &mut In, sz: usize) -> Result<usize> {
+    let mut rsz = 0;
+    for _ in 0..sz {
+        rsz += T::skip(input)?;
+    }
+    Ok(rsz)
+}
+
+/// Skip a (perhaps) length-limited array.
+pub fn skip_flex<In: Read, T: Skip<In>>(input: &mut In, maxsz: Option<usize>) -> Result<usize> {
+    let (elems, sz) = flex_len(input, maxsz)?;
+
+    skip_array::<In, T>(input, elems).map(|esz| sz + esz)
+}
+
+#[cfg(feature = "bytecodec")]
+impl<In: Read> Skip<In> for u8 {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_fixed::<In, Self>(input) }
+}
+
+#[cfg(feature = "bytecodec")]
+impl<In: Read> Skip<In> for i8 {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_fixed::<In, Self>(input) }
+}
+
+impl<In: Read> Skip<In> for u32 {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_fixed::<In, Self>(input) }
+}
+
+impl<In: Read> Skip<In> for i32 {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_fixed::<In, Self>(input) }
+}
+
+impl<In: Read> Skip<In> for u64 {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_fixed::<In, Self>(input) }
+}
+
+impl<In: Read> Skip<In> for i64 {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_fixed::<In, Self>(input) }
+}
+
+#[cfg(feature = "hyper128")]
+impl<In: Read> Skip<In> for u128 {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_fixed::<In, Self>(input) }
+}
+
+#[cfg(feature = "hyper128")]
+impl<In: Read> Skip<In> for i128 {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_fixed::<In, Self>(input) }
+}
+
+impl<In: Read> Skip<In> for f32 {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_fixed::<In, Self>(input) }
+}
+
+impl<In: Read> Skip<In> for f64 {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_fixed::<In, Self>(input) }
+}
+
+impl<In: Read> Skip<In> for bool {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_fixed::<In, Self>(input) }
+}
+
+impl<In: Read> Skip<In> for () {
+    #[inline]
+    fn skip(_: &mut In) -> Result<usize> { Ok(0) }
+}
+
+impl<In: Read> Skip<In> for usize {}
+
+impl<In: Read, T: Skip<In>> Skip<In> for Vec<T> {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_flex::<In, T>(input, None) }
+}
+
+impl<In: Read> Skip<In> for String {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_string(input, None) }
+}
+
+impl<'a, In: Read> Skip<In> for Opaque<'a> {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_opaque_flex(input, None) }
+}
+
+impl<'a, In: Read> Skip<In> for Cow<'a, [u8]> {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { skip_opaque_flex(input, None) }
+}
+
+impl<In: Read, T: Skip<In>> Skip<In> for Option<T> {
+    fn skip(input: &mut In) -> Result<usize> {
+        let (present, sz) = bool::unpack(input)?;
+        if present {
+            T::skip(input).map(|tsz| sz + tsz)
+        } else {
+            Ok(sz)
+        }
+    }
+}
+
+impl<In: Read, T: Skip<In>> Skip<In> for Box<T> {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> { T::skip(input) }
+}
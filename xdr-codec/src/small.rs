@@ -0,0 +1,60 @@
+//! Small opaque fields
+//!
+//! Many protocols have variable-length opaque fields which are almost always only a few bytes
+//! long, such as RPC verifiers and cookies, but which `unpack_opaque_flex` puts in a new `Vec`
+//! every time. `unpack_opaque_flex_small` unpacks them into a `SmallVec<[u8; N]>` instead, which
+//! holds up to `N` bytes inline and only allocates for longer data:
+//!
+//! ```
+//! use xdr_codec::small::{SmallVec, unpack_opaque_flex_small};
+//!
+//! let (cookie, sz): (SmallVec<[u8; 8]>, _) =
+//!     unpack_opaque_flex_small(&mut &[0, 0, 0, 3,  1, 2, 3, 0][..], Some(400)).unwrap();
+//! assert_eq!((&cookie[..], sz), (&[1, 2, 3][..], 8));
+//! assert!(!cookie.spilled());
+//! ```
+//!
+//! A `SmallVec<[u8; N]>` can be packed with `pack_opaque_flex`.
+//!
+//! This module is only available with the `smallvec` feature.
+use std::cmp::min;
+use std::io::{self, Read};
+
+pub use smallvec::SmallVec;
+
+use error::*;
+use options::check_input_len;
+use super::{Unpack, MAX_PREALLOC, unpack_padding};
+
+/// Unpack a (perhaps) length-limited opaque array into a `SmallVec`.
+///
+/// Data up to `N` bytes long is stored inline; anything longer spills to the heap, which is grown
+/// as the data is read rather than being sized from the untrusted length.
+pub fn unpack_opaque_flex_small<In: Read, const N: usize>(input: &mut In, maxsz: Option<usize>)
+                                                          -> Result<(SmallVec<[u8; N]>, usize)> {
+    let (elems, mut sz) = usize::unpack(input)?;
+
+    if let Some(m) = maxsz {
+        if elems > m {
+            bail!(ErrorKind::InvalidLen(m));
+        }
+    }
+
+    check_input_len(elems)?;
+    let mut out = SmallVec::new();
+    while out.len() < elems {
+        let start = out.len();
+        let chunk = min(elems - start, MAX_PREALLOC);
+        out.resize(start + chunk, 0);
+        if let Err(e) = input.read_exact(&mut out[start..]) {
+            return Err(match e.kind() {
+                io::ErrorKind::UnexpectedEof => io::Error::new(io::ErrorKind::UnexpectedEof, "short opaque data"),
+                _ => e,
+            }.into());
+        }
+    }
+    sz += elems;
+    sz += unpack_padding(input, sz)?;
+
+    Ok((out, sz))
+}
@@ -0,0 +1,536 @@
+//! Stateful XDR streams
+//!
+//! `XdrWriter` wraps a `Write` and keeps track of how many bytes have been written through it,
+//! so that code encoding a sequence of items doesn't need to sum up the sizes returned by each
+//! `pack` call, and can find out how far it is from a 4-byte boundary.
+//!
+//! `CountingWriter` is a sink which discards everything written to it, only counting the bytes.
+//! It's used by `packed_len` to find the encoded size of a value without encoding it anywhere.
+//!
+//! `XdrReader` is the decoding counterpart. As well as tracking the offset, it can peek at the
+//! next 32-bit word without consuming it (useful for looking at a union discriminant or message
+//! type before deciding how to decode the rest), and skip over data. `XdrIterator` uses it to
+//! unpack a stream of values of one type, stopping cleanly at the end of the input.
+//!
+//! An `XdrReader` created with `seekable` skips large amounts of data (such as an unwanted
+//! multi-megabyte opaque body) by seeking past it rather than reading it.
+//!
+//! Both wrappers count the values passed through them, as `StreamStats`. An application can
+//! export these to its metrics system by polling `stats`, or by setting a hook with
+//! `set_stats_hook` which is called with a snapshot after every value.
+use std::fmt;
+use std::io::{self, Read, Write, Seek, SeekFrom};
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::cmp::{min, max};
+
+use error::*;
+
+use super::{Pack, Unpack, Skip, padding};
+use endian;
+use options::{DecodeOptions, with_decode_options};
+
+// Skips shorter than this are read and discarded even if the input is seekable, as that's
+// cheaper than seeking when the input is buffered.
+const SEEK_MIN: u64 = 64 * 1024;
+
+/// Statistics for an `XdrReader` or `XdrWriter`.
+///
+/// These are cumulative from when the wrapper was created. Only values packed or unpacked with
+/// the wrapper's own methods are counted; a value containing other values counts as one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamStats {
+    /// Values packed, unpacked or skipped successfully.
+    pub values: u64,
+    /// Bytes written or consumed; the same as `position`.
+    pub bytes: u64,
+    /// Values which failed to pack, unpack or skip.
+    pub errors: u64,
+}
+
+type HookFn<S> = Box<dyn FnMut(&S) + Send>;
+
+// Optional callback to report statistics to
+pub(crate) struct StatsHook<S>(Option<HookFn<S>>);
+
+impl<S> StatsHook<S> {
+    pub(crate) fn set<F: FnMut(&S) + Send + 'static>(&mut self, hook: F) {
+        self.0 = Some(Box::new(hook));
+    }
+
+    #[inline]
+    pub(crate) fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+
+    #[inline]
+    pub(crate) fn notify(&mut self, stats: &S) {
+        if let Some(ref mut hook) = self.0 {
+            hook(stats)
+        }
+    }
+}
+
+impl<S> Default for StatsHook<S> {
+    fn default() -> Self {
+        StatsHook(None)
+    }
+}
+
+impl<S> fmt::Debug for StatsHook<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.is_set() { "Some(..)" } else { "None" })
+    }
+}
+
+/// Write XDR encoded values to a stream, tracking the position.
+///
+/// The position counts every byte written through the wrapper, whether via `pack` or the `Write`
+/// implementation. It starts at zero, irrespective of the state of the underlying writer. Writes
+/// made directly to the inner writer (with `get_mut`) are not counted.
+///
+/// `XdrWriter` doesn't buffer: each primitive is a separate small write. When writing to an
+/// unbuffered transport such as a socket, wrap it in a `std::io::BufWriter` (or use
+/// `record::XdrRecordWriter`, which buffers each record fragment).
+#[derive(Debug)]
+pub struct XdrWriter<W: Write> {
+    pos: u64,
+    values: u64,
+    errors: u64,
+    hook: StatsHook<StreamStats>,
+    writer: W,
+}
+
+impl<W: Write> XdrWriter<W> {
+    /// Wrap an existing writer.
+    pub fn new(writer: W) -> XdrWriter<W> {
+        XdrWriter { pos: 0, values: 0, errors: 0, hook: StatsHook::default(), writer }
+    }
+
+    /// Pack a value, returning its encoded size.
+    #[inline]
+    pub fn pack<T: ?Sized + Pack<Self>>(&mut self, val: &T) -> Result<usize> {
+        let res = val.pack(self);
+        self.count(res.is_ok());
+        res
+    }
+
+    fn count(&mut self, ok: bool) {
+        if ok { self.values += 1 } else { self.errors += 1 }
+        if self.hook.is_set() {
+            let stats = self.stats();
+            self.hook.notify(&stats);
+        }
+    }
+
+    /// Return a snapshot of the statistics for this writer.
+    pub fn stats(&self) -> StreamStats {
+        StreamStats { values: self.values, bytes: self.pos, errors: self.errors }
+    }
+
+    /// Set a function to be called with the statistics after each value is packed (successfully
+    /// or not), replacing any previous one.
+    pub fn set_stats_hook<F: FnMut(&StreamStats) + Send + 'static>(&mut self, hook: F) {
+        self.hook.set(hook)
+    }
+
+    /// Write zero padding to bring the position up to a multiple of 4, returning the number of
+    /// bytes written.
+    pub fn pad_to_alignment(&mut self) -> Result<usize> {
+        let p = padding(self.misalignment());
+        self.write_all(p)?;
+        Ok(p.len())
+    }
+
+    /// Total number of bytes written.
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Number of bytes past the last 4-byte boundary; 0 if the position is aligned.
+    #[inline]
+    pub fn misalignment(&self) -> usize {
+        (self.pos % 4) as usize
+    }
+
+    /// Return true if the position is a multiple of 4.
+    #[inline]
+    pub fn is_aligned(&self) -> bool {
+        self.misalignment() == 0
+    }
+
+    /// Return a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Return a mutable reference to the underlying writer. Anything written through it isn't
+    /// included in the position.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Write for XdrWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let sz = self.writer.write(buf)?;
+        self.pos += sz as u64;
+        Ok(sz)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// A `Write` implementation which discards data, counting the number of bytes written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CountingWriter {
+    count: u64,
+}
+
+impl CountingWriter {
+    /// Create a new writer with a zero count.
+    pub fn new() -> CountingWriter {
+        CountingWriter::default()
+    }
+
+    /// Number of bytes written so far.
+    #[inline]
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+}
+
+impl Write for CountingWriter {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.count += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Read XDR encoded values from a stream, tracking the offset.
+///
+/// The offset counts bytes consumed through the wrapper, starting from zero. Peeked bytes are
+/// buffered inside the `XdrReader` and aren't counted until they're consumed, so the inner reader
+/// should not be used directly while there's a peeked value pending.
+#[derive(Debug)]
+pub struct XdrReader<R: Read> {
+    pos: u64,
+    peeked: [u8; 4],            // lookahead buffer
+    npeeked: usize,             // valid bytes in lookahead
+    options: Option<DecodeOptions>,
+    values: u64,
+    errors: u64,
+    hook: StatsHook<StreamStats>,
+    seek: Option<fn(&mut R, u64) -> io::Result<u64>>, // seek forward, if seekable
+    reader: R,
+}
+
+// Seek up to `n` bytes forward, but not past the end; returns the distance moved
+fn seek_forward<R: Seek>(reader: &mut R, n: u64) -> io::Result<u64> {
+    let pos = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    let to = max(pos, min(end, pos.saturating_add(n)));
+
+    reader.seek(SeekFrom::Start(to))?;
+    Ok(to - pos)
+}
+
+impl<R: Read> XdrReader<R> {
+    /// Wrap an existing reader.
+    pub fn new(reader: R) -> XdrReader<R> {
+        XdrReader {
+            pos: 0,
+            peeked: [0; 4],
+            npeeked: 0,
+            options: None,
+            values: 0,
+            errors: 0,
+            hook: StatsHook::default(),
+            seek: None,
+            reader,
+        }
+    }
+
+    /// Wrap a seekable reader.
+    ///
+    /// Skipping large amounts of data (with `skip_bytes` or `skip_opaque`) seeks past it,
+    /// rather than reading and discarding it. The input should be positioned at the start
+    /// of the XDR data, and its end should be the end of the data, as skipping checks for running
+    /// past it.
+    pub fn seekable(reader: R) -> XdrReader<R> where R: Seek {
+        XdrReader { seek: Some(seek_forward::<R>), ..XdrReader::new(reader) }
+    }
+
+    /// Set the decode options used by `unpack`.
+    ///
+    /// Without this, values are unpacked with whatever options are current for the thread.
+    pub fn set_decode_options(&mut self, options: DecodeOptions) {
+        self.options = Some(options);
+    }
+
+    /// Unpack a value.
+    ///
+    /// If the input ends before any of the value has been read, this fails with `ErrorKind::Eof`
+    /// rather than an `UnexpectedEof` IO error, so the orderly end of a stream of values can be
+    /// told apart from a truncated one.
+    #[inline]
+    pub fn unpack<T: Unpack<Self>>(&mut self) -> Result<T> {
+        let start = self.pos;
+        let res = match self.options {
+            None => T::unpack(self).map(|(v, _)| v),
+            Some(opts) => with_decode_options(opts, || T::unpack(self).map(|(v, _)| v)),
+        };
+        let res = self.finish(start, res);
+
+        #[cfg(feature = "tracing")]
+        match res {
+            Err(ref e) if !e.is_eof() =>
+                trace_event!(debug, ty = ::std::any::type_name::<T>(), start, offset = self.pos, error = %e,
+                             "decode failed"),
+            _ => (),
+        }
+        res
+    }
+
+    /// Skip over a value without constructing it, returning its encoded size.
+    ///
+    /// Like `unpack`, this fails with `ErrorKind::Eof` if the input has already ended.
+    #[inline]
+    pub fn skip<T: Skip<Self>>(&mut self) -> Result<usize> {
+        let start = self.pos;
+        let res = T::skip(self);
+        self.finish(start, res)
+    }
+
+    // Report a value which failed because the input ended before it started as `Eof`, and update
+    // the statistics. `Eof` isn't counted as an error.
+    fn finish<T>(&mut self, start: u64, res: Result<T>) -> Result<T> {
+        let res = match res {
+            Err(Error(ErrorKind::IOError(ref e), _))
+                if e.kind() == io::ErrorKind::UnexpectedEof && self.pos == start => Err(ErrorKind::Eof.into()),
+            res => res,
+        };
+
+        match res {
+            Ok(_) => self.values += 1,
+            Err(ref e) if e.is_eof() => return res,
+            Err(_) => self.errors += 1,
+        }
+        if self.hook.is_set() {
+            let stats = self.stats();
+            self.hook.notify(&stats);
+        }
+        res
+    }
+
+    /// Return a snapshot of the statistics for this reader.
+    pub fn stats(&self) -> StreamStats {
+        StreamStats { values: self.values, bytes: self.pos, errors: self.errors }
+    }
+
+    /// Set a function to be called with the statistics after each value is unpacked or skipped
+    /// (successfully or not), replacing any previous one.
+    pub fn set_stats_hook<F: FnMut(&StreamStats) + Send + 'static>(&mut self, hook: F) {
+        self.hook.set(hook)
+    }
+
+    /// Skip a (perhaps) length-limited flexible opaque array or string, returning its encoded
+    /// size.
+    ///
+    /// This is the same as `skip::skip_opaque_flex`, but discards the body with `skip_bytes`, so
+    /// a large body is seeked past if the reader is seekable. Like `skip`, it fails with
+    /// `ErrorKind::Eof` if the input has already ended.
+    pub fn skip_opaque(&mut self, maxsz: Option<usize>) -> Result<usize> {
+        let start = self.pos;
+        let res = self.skip_opaque_body(maxsz);
+        self.finish(start, res)
+    }
+
+    fn skip_opaque_body(&mut self, maxsz: Option<usize>) -> Result<usize> {
+        let (len, sz) = usize::unpack(self)?;
+        if let Some(m) = maxsz {
+            if len > m {
+                bail!(ErrorKind::InvalidLen(m));
+            }
+        }
+
+        let body = len + padding(len).len();
+        self.skip_bytes(body as u64)?;
+        Ok(sz + body)
+    }
+
+    /// Return the next 32-bit word without consuming it.
+    ///
+    /// This can be used to look at an unsigned int, int or enum value (including a union
+    /// discriminant); cast the result as needed. If the input ends before a whole word is
+    /// available, this returns an `UnexpectedEof` IO error, but the bytes which were read are
+    /// still returned by subsequent reads.
+    pub fn peek_u32(&mut self) -> Result<u32> {
+        while self.npeeked < 4 {
+            match self.reader.read(&mut self.peeked[self.npeeked..]) {
+                Ok(0) => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short peek").into()),
+                Ok(sz) => self.npeeked += sz,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(endian::read_u32(&self.peeked))
+    }
+
+    /// Return true if the input has ended, without consuming anything.
+    ///
+    /// This reads ahead by a byte if nothing has been peeked already; it's kept for the next
+    /// read. Checking this before unpacking a value distinguishes the clean end of a stream from
+    /// a truncated value, which fails with an `UnexpectedEof` IO error.
+    pub fn is_eof(&mut self) -> Result<bool> {
+        while self.npeeked == 0 {
+            match self.reader.read(&mut self.peeked[..1]) {
+                Ok(0) => return Ok(true),
+                Ok(sz) => self.npeeked += sz,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(false)
+    }
+
+    /// Consume and discard `n` bytes.
+    ///
+    /// Returns an `UnexpectedEof` IO error if the input ends first. If the reader was created
+    /// with `seekable`, large skips seek rather than read.
+    pub fn skip_bytes(&mut self, n: u64) -> Result<()> {
+        let skipped = match self.seek {
+            Some(seek) if n >= SEEK_MIN => {
+                // Anything peeked comes first
+                let peeked = self.npeeked as u64;
+                self.npeeked = 0;
+                self.pos += peeked;
+
+                let sz = seek(&mut self.reader, n - peeked)?;
+                self.pos += sz;
+                peeked + sz
+            },
+            _ => io::copy(&mut self.by_ref().take(n), &mut io::sink())?,
+        };
+        if skipped < n {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "short skip").into());
+        }
+        Ok(())
+    }
+
+    /// Total number of bytes consumed.
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Return a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Return the underlying reader. Any peeked data is lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Read for XdrReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let sz = if self.npeeked > 0 {
+            let sz = min(self.npeeked, buf.len());
+            buf[..sz].copy_from_slice(&self.peeked[..sz]);
+            self.peeked.copy_within(sz..self.npeeked, 0);
+            self.npeeked -= sz;
+            sz
+        } else {
+            self.reader.read(buf)?
+        };
+        self.pos += sz as u64;
+        Ok(sz)
+    }
+}
+
+/// Iterator which unpacks a sequence of `T` from a stream until it ends.
+///
+/// Each item is unpacked with `XdrReader::unpack`, so decode options and statistics set on the
+/// reader apply. Iteration stops with `None` if the stream ends between values (where `unpack`
+/// returns `ErrorKind::Eof`); if it ends part way through one, that's an `UnexpectedEof` IO
+/// error. Iteration also stops after any
+/// error, as the stream's position within the data is unknown.
+///
+/// ```
+/// use xdr_codec::stream::XdrIterator;
+///
+/// let enc = [0, 0, 0, 1,  0, 0, 0, 2];
+/// let v: Vec<u32> = XdrIterator::new(&enc[..]).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(v, [1, 2]);
+/// assert!(XdrIterator::<_, u32>::new(&enc[..6]).nth(1).unwrap().is_err());
+/// ```
+#[derive(Debug)]
+pub struct XdrIterator<R: Read, T> {
+    reader: XdrReader<R>,
+    done: bool,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<R: Read, T: Unpack<XdrReader<R>>> XdrIterator<R, T> {
+    /// Iterate over the values in `reader`.
+    pub fn new(reader: R) -> Self {
+        XdrIterator::from(XdrReader::new(reader))
+    }
+
+    /// Return a reference to the `XdrReader` being iterated over.
+    pub fn get_ref(&self) -> &XdrReader<R> {
+        &self.reader
+    }
+
+    /// Return the `XdrReader` being iterated over.
+    pub fn into_inner(self) -> XdrReader<R> {
+        self.reader
+    }
+}
+
+impl<R: Read, T: Unpack<XdrReader<R>>> From<XdrReader<R>> for XdrIterator<R, T> {
+    fn from(reader: XdrReader<R>) -> Self {
+        XdrIterator { reader, done: false, _item: PhantomData }
+    }
+}
+
+impl<R: Read, T: Unpack<XdrReader<R>>> Iterator for XdrIterator<R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.reader.unpack() {
+            Err(ref e) if e.is_eof() => {
+                self.done = true;
+                None
+            },
+            res => {
+                self.done = res.is_err();
+                Some(res)
+            },
+        }
+    }
+}
+
+impl<R: Read, T: Unpack<XdrReader<R>>> FusedIterator for XdrIterator<R, T> {}
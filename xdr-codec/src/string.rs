@@ -0,0 +1,142 @@
+//! Strings which aren't UTF-8
+//!
+//! XDR `string` is a sequence of bytes. RFC 4506 describes it as ASCII, and `String` requires it
+//! to be UTF-8, but many protocols carry arbitrary bytes in strings (NFS filenames, legacy
+//! hostnames and so on), which fail to unpack as a `String` with `ErrorKind::InvalidUtf8`.
+//! `XdrString` has the same encoding but holds the bytes as-is, converting to a Rust string only
+//! on request.
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{Read, Write};
+use std::ops::Deref;
+use std::str::{self, Utf8Error};
+use std::string::FromUtf8Error;
+
+use error::*;
+use skip::{Skip, skip_string};
+use super::{Pack, Unpack, Opaque, unpack_opaque_flex};
+
+/// An XDR string with no encoding requirement.
+///
+/// The `Debug` format shows it as an escaped byte string, and `Display` as a string with any
+/// invalid UTF-8 replaced with U+FFFD.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct XdrString(pub Vec<u8>);
+
+impl XdrString {
+    /// Wrap some bytes.
+    pub fn new(bytes: Vec<u8>) -> XdrString {
+        XdrString(bytes)
+    }
+
+    /// Return the bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Return the underlying `Vec<u8>`.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    /// Return the string as a `&str`, if it's valid UTF-8.
+    pub fn to_str(&self) -> ::std::result::Result<&str, Utf8Error> {
+        str::from_utf8(&self.0)
+    }
+
+    /// Convert to a `String`, if it's valid UTF-8.
+    pub fn into_string(self) -> ::std::result::Result<String, FromUtf8Error> {
+        String::from_utf8(self.0)
+    }
+
+    /// Return the string with any invalid UTF-8 replaced with U+FFFD.
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.0)
+    }
+}
+
+impl fmt::Debug for XdrString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "XdrString(b\"{}\")", self.0.escape_ascii())
+    }
+}
+
+impl fmt::Display for XdrString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.to_string_lossy().fmt(f)
+    }
+}
+
+impl Deref for XdrString {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for XdrString {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for XdrString {
+    fn from(bytes: Vec<u8>) -> Self {
+        XdrString(bytes)
+    }
+}
+
+impl<'a> From<&'a [u8]> for XdrString {
+    fn from(bytes: &'a [u8]) -> Self {
+        XdrString(bytes.to_vec())
+    }
+}
+
+impl From<String> for XdrString {
+    fn from(s: String) -> Self {
+        XdrString(s.into_bytes())
+    }
+}
+
+impl<'a> From<&'a str> for XdrString {
+    fn from(s: &'a str) -> Self {
+        XdrString(s.as_bytes().to_vec())
+    }
+}
+
+impl From<XdrString> for Vec<u8> {
+    fn from(s: XdrString) -> Self {
+        s.0
+    }
+}
+
+impl TryFrom<XdrString> for String {
+    type Error = FromUtf8Error;
+
+    fn try_from(s: XdrString) -> ::std::result::Result<Self, FromUtf8Error> {
+        s.into_string()
+    }
+}
+
+impl<Out: Write> Pack<Out> for XdrString {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Opaque::borrowed(&self.0).pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for XdrString {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_opaque_flex(input, None).map(|(v, sz)| (XdrString(v), sz))
+    }
+}
+
+impl<In: Read> Skip<In> for XdrString {
+    #[inline]
+    fn skip(input: &mut In) -> Result<usize> {
+        skip_string(input, None)
+    }
+}
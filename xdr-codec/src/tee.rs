@@ -0,0 +1,73 @@
+//! Capturing the encoding of decoded values
+//!
+//! For audit logs and bug reports it's often necessary to keep the exact bytes a value was
+//! decoded from. `TeeReader` wraps a reader and keeps a copy of everything read through it, and
+//! `unpack_captured` uses one to unpack a value along with its encoding. If unpacking fails, the
+//! bytes consumed up to the failure are attached to the error, and can be retrieved with
+//! `Error::captured`:
+//!
+//! ```
+//! use xdr_codec::tee::unpack_captured;
+//!
+//! let (v, sz, raw) = unpack_captured::<_, Vec<u32>>(&mut &[0, 0, 0, 1,  0, 0, 0, 2][..]).unwrap();
+//! assert_eq!((v, sz, raw), (vec![2], 8, vec![0, 0, 0, 1, 0, 0, 0, 2]));
+//!
+//! // Three elements are declared but there are only two
+//! let err = unpack_captured::<_, Vec<u32>>(&mut &[0, 0, 0, 3,  0, 0, 0, 1,  0, 0, 0, 2][..]).unwrap_err();
+//! assert_eq!(err.captured(), Some(&[0, 0, 0, 3,  0, 0, 0, 1,  0, 0, 0, 2][..]));
+//! ```
+use std::io::{self, Read};
+
+use error::*;
+use super::Unpack;
+
+/// A reader which keeps a copy of everything read through it.
+#[derive(Debug)]
+pub struct TeeReader<R: Read> {
+    reader: R,
+    captured: Vec<u8>,
+}
+
+impl<R: Read> TeeReader<R> {
+    /// Wrap a reader.
+    pub fn new(reader: R) -> TeeReader<R> {
+        TeeReader { reader, captured: Vec::new() }
+    }
+
+    /// The bytes read so far.
+    pub fn captured(&self) -> &[u8] {
+        &self.captured
+    }
+
+    /// Return the bytes read so far, and start capturing afresh.
+    pub fn take_captured(&mut self) -> Vec<u8> {
+        ::std::mem::take(&mut self.captured)
+    }
+
+    /// Return the underlying reader and the captured bytes.
+    pub fn into_inner(self) -> (R, Vec<u8>) {
+        (self.reader, self.captured)
+    }
+}
+
+impl<R: Read> Read for TeeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let sz = self.reader.read(buf)?;
+        self.captured.extend_from_slice(&buf[..sz]);
+        Ok(sz)
+    }
+}
+
+/// Unpack a value, returning it along with its size and the bytes it was unpacked from.
+///
+/// On failure, the bytes consumed before the error are attached to it (see `Error::captured`).
+pub fn unpack_captured<In, T>(input: &mut In) -> Result<(T, usize, Vec<u8>)>
+    where In: Read, T: for<'a> Unpack<TeeReader<&'a mut In>>
+{
+    let mut tee = TeeReader::new(input);
+
+    match T::unpack(&mut tee) {
+        Ok((v, sz)) => Ok((v, sz, tee.take_captured())),
+        Err(e) => Err(e.with_captured(tee.take_captured())),
+    }
+}
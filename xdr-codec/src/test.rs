@@ -1,7 +1,14 @@
-use std::io::Cursor;
+use std::io::{self, Cursor, Read, Write, IoSlice};
+use std::borrow::Cow;
+use std::collections::{VecDeque, BTreeSet, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
 use super::{Error, ErrorKind, Pack, Unpack, Opaque,
-            pack_flex, pack_opaque_flex, pack_string, pack_array, pack_opaque_array,
-            unpack_array, unpack_opaque_array, unpack_string, unpack_flex, unpack_opaque_flex};
+            pack_flex, pack_list, pack_opaque_flex, pack_string, pack_array, pack_opaque_array,
+            unpack_array, unpack_opaque_array, unpack_string, unpack_flex, unpack_list, unpack_opaque_flex,
+            unpack_opaque_borrowed, xdr_len, host_len, xdr_len_from, host_len_max,
+            pack_to_vec, unpack_from_bytes, pack_opaque_from_reader,
+            unpack_opaque_to_writer};
 
 #[cfg(feature = "bytecodec")]
 #[test]
@@ -133,6 +140,31 @@ fn basic_64() {
     }
 }
 
+#[cfg(feature = "hyper128")]
+#[test]
+fn basic_128() {
+    let mut out = Cursor::new(Vec::new());
+
+    assert_eq!(0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10u128.pack(&mut out).unwrap(), 16);
+    assert_eq!((-2i128).pack(&mut out).unwrap(), 16);
+    assert_eq!(i128::MIN.pack(&mut out).unwrap(), 16);
+
+    let v = out.into_inner();
+
+    assert_eq!(v.len(), 48);
+    assert_eq!(v, vec![0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+                       0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10,
+                       0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                       0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+                       0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                       0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+    let mut input = Cursor::new(v);
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10u128, 16));
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (-2i128, 16));
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (i128::MIN, 16));
+}
+
 #[test]
 fn basic_bool() {
     let mut out = Cursor::new(Vec::new());
@@ -733,3 +765,689 @@ fn basic_option() {
         res => panic!("bad result {:?}", res),
     }
 }
+
+#[test]
+fn basic_list() {
+    let mut out = Cursor::new(Vec::new());
+
+    assert_eq!(pack_list(&[0x11u32, 0x22], None, &mut out).unwrap(), 20);
+    assert_eq!(pack_list::<_, u32>(&[], None, &mut out).unwrap(), 4);
+
+    let v = out.into_inner();
+    assert_eq!(v, vec![0x00, 0x00, 0x00, 0x01,  0x00, 0x00, 0x00, 0x11,
+                       0x00, 0x00, 0x00, 0x01,  0x00, 0x00, 0x00, 0x22,
+                       0x00, 0x00, 0x00, 0x00,
+                       0x00, 0x00, 0x00, 0x00]);
+
+    let mut input = Cursor::new(v.clone());
+    assert_eq!(unpack_list(&mut input, Some(2)).unwrap(), (vec![0x11u32, 0x22], 20));
+    assert_eq!(unpack_list::<_, u32>(&mut input, None).unwrap(), (vec![], 4));
+
+    let mut input = Cursor::new(v);
+    match unpack_list::<_, u32>(&mut input, Some(1)) {
+        Err(Error(ErrorKind::InvalidLen(1), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let mut out = Cursor::new(Vec::new());
+    match pack_list(&[1u32, 2, 3], Some(2), &mut out) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn vec_helpers() {
+    let v = pack_to_vec("abc").unwrap();
+    assert_eq!(v, vec![0, 0, 0, 3, b'a', b'b', b'c', 0]);
+
+    let v = pack_to_vec(&[1u32, 2][..]).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2]);
+
+    assert_eq!(unpack_from_bytes::<String>(&[0, 0, 0, 3, b'a', b'b', b'c', 0, 9]).unwrap(),
+               (String::from("abc"), 8));
+
+    match unpack_from_bytes::<u64>(&[0, 0, 0, 1]) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+// Writer which accepts at most `max` bytes per call, and counts calls
+struct Chunked {
+    max: usize,
+    writes: usize,
+    vectored: usize,
+    data: Vec<u8>,
+}
+
+impl Write for Chunked {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes += 1;
+        let sz = ::std::cmp::min(buf.len(), self.max);
+        self.data.extend_from_slice(&buf[..sz]);
+        Ok(sz)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        self.vectored += 1;
+        let mut sz = 0;
+        for b in bufs {
+            let bsz = ::std::cmp::min(b.len(), self.max - sz);
+            self.data.extend_from_slice(&b[..bsz]);
+            sz += bsz;
+        }
+        Ok(sz)
+    }
+
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+#[test]
+fn opaque_vectored() {
+    let data: Vec<u8> = (0..10001).map(|v| v as u8).collect();
+    let mut expect = Vec::new();
+    expect.extend_from_slice(&[0, 0, 0x27, 0x11]);
+    expect.extend_from_slice(&data);
+    expect.extend_from_slice(&[0, 0, 0]);
+
+    let mut out = Chunked { max: usize::MAX, writes: 0, vectored: 0, data: Vec::new() };
+    assert_eq!(Opaque::borrowed(&data).pack(&mut out).unwrap(), 10008);
+    assert_eq!((out.writes, out.vectored), (0, 1));
+    assert_eq!(out.data, expect);
+
+    // Partial writes are continued
+    let mut out = Chunked { max: 1000, writes: 0, vectored: 0, data: Vec::new() };
+    assert_eq!(pack_opaque_flex(&data, None, &mut out).unwrap(), 10008);
+    assert_eq!((out.writes, out.vectored), (0, 11));
+    assert_eq!(out.data, expect);
+
+    // Small opaques use plain writes
+    let mut out = Chunked { max: usize::MAX, writes: 0, vectored: 0, data: Vec::new() };
+    assert_eq!(Opaque::borrowed(&data[..5]).pack(&mut out).unwrap(), 12);
+    assert_eq!((out.writes, out.vectored), (3, 0));
+}
+
+#[test]
+fn bulk_pack() {
+    fn check<T: Pack<Vec<u8>> + Copy>(v: &[T]) {
+        let mut each = Vec::new();
+        for x in v {
+            x.pack(&mut each).unwrap();
+        }
+
+        let mut bulk = Vec::new();
+        assert_eq!(T::pack_slice(v, &mut bulk).unwrap(), each.len());
+        assert_eq!(bulk, each);
+
+        let mut arr = Vec::new();
+        assert_eq!(pack_array(v, v.len(), &mut arr, None).unwrap(), each.len());
+        assert_eq!(arr, each);
+
+        let mut flex = Vec::new();
+        assert_eq!(v.pack(&mut flex).unwrap(), each.len() + 4);
+        assert_eq!(&flex[4..], &each[..]);
+    }
+
+    // Sizes which are smaller than, a multiple of, and not a multiple of the chunk size
+    for &n in &[0, 1, 3, 1024, 2000, 5000] {
+        check(&(0..n).map(|v| (v as u32).wrapping_mul(0x0101_0101)).collect::<Vec<_>>());
+        check(&(0..n).map(|v| -v).collect::<Vec<_>>());
+        check(&(0..n).map(|v| (v as u64) << 40 | v as u64).collect::<Vec<_>>());
+        check(&(0..n).map(|v| -(v as i64) << 20).collect::<Vec<_>>());
+        check(&(0..n).map(|v| v as f32 / 3.0).collect::<Vec<_>>());
+        check(&(0..n).map(|v| v as f64 * 1e100).collect::<Vec<_>>());
+        check(&(0..n).map(|v| v % 3 == 0).collect::<Vec<_>>());
+    }
+}
+
+// Reader which records the size of each read request
+struct Reads<'a>(&'a [u8], Vec<usize>);
+
+impl<'a> Read for Reads<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.1.push(buf.len());
+        self.0.read(buf)
+    }
+}
+
+#[test]
+fn bulk_unpack() {
+    fn check<T>(v: &[T])
+        where T: Pack<Vec<u8>> + for<'a> Unpack<Reads<'a>> + for<'a> Unpack<&'a [u8]> + PartialEq + ::std::fmt::Debug
+    {
+        let enc = pack_to_vec(v).unwrap();
+
+        // Same result as unpacking one at a time
+        let mut each = Vec::new();
+        let mut input = &enc[4..];
+        for _ in 0..v.len() {
+            each.push(T::unpack(&mut input).unwrap().0);
+        }
+        assert_eq!(&each[..], v);
+
+        let mut input = Reads(&enc, Vec::new());
+        assert_eq!(Vec::<T>::unpack(&mut input).unwrap(), (each, enc.len()));
+        // Length, then at most one read per 4k of payload
+        assert!(input.1.len() <= 1 + enc.len().div_ceil(4096), "{} reads", input.1.len());
+
+        // Short input
+        match Vec::<T>::unpack(&mut &enc[..enc.len() - 1]) {
+            Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+            res if v.is_empty() => assert!(res.is_err()),
+            res => panic!("bad result {:?}", res),
+        }
+    }
+
+    for &n in &[0, 1, 3, 1024, 2000, 5000] {
+        check(&(0..n).map(|v| (v as u32).wrapping_mul(0x0101_0101)).collect::<Vec<_>>());
+        check(&(0..n).map(|v| -v).collect::<Vec<_>>());
+        check(&(0..n).map(|v| (v as u64) << 40 | v as u64).collect::<Vec<_>>());
+        check(&(0..n).map(|v| -(v as i64) << 20).collect::<Vec<_>>());
+        check(&(0..n).map(|v| v as f32 / 3.0).collect::<Vec<_>>());
+        check(&(0..n).map(|v| v as f64 * 1e100).collect::<Vec<_>>());
+        check(&(0..n).map(|v| v % 3 == 0).collect::<Vec<_>>());
+    }
+
+    // Invalid bool in a later chunk
+    let mut enc = pack_to_vec(&vec![true; 2000]).unwrap();
+    enc[4 + 1500 * 4 + 3] = 2;
+    match Vec::<bool>::unpack(&mut &enc[..]) {
+        Err(Error(ErrorKind::InvalidEnum(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn padding_single_read() {
+    let enc = [0, 0, 0, 5, b'h', b'e', b'l', b'l', b'o', 0, 0, 0];
+
+    let mut input = Reads(&enc, Vec::new());
+    assert_eq!(unpack_string(&mut input, None).unwrap(), (String::from("hello"), 12));
+    assert_eq!(input.1.last(), Some(&3));
+
+    match unpack_opaque_flex(&mut Cursor::new(&enc[..10]), None) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+    // Short data, not just short padding
+    match unpack_string(&mut Cursor::new(&enc[..6]), None) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn opaque_from_reader() {
+    let data: Vec<u8> = (0..100_001).map(|v| v as u8).collect();
+
+    let mut out = Vec::new();
+    let mut input = Cursor::new(&data[..]);
+    assert_eq!(pack_opaque_from_reader(data.len(), &mut input, &mut out).unwrap(), 100_008);
+    assert_eq!(out, pack_to_vec(&Opaque::borrowed(&data)).unwrap());
+
+    // Only consumes `len` bytes
+    let mut out = Vec::new();
+    let mut input = Cursor::new(&data[..]);
+    assert_eq!(pack_opaque_from_reader(6, &mut input, &mut out).unwrap(), 12);
+    assert_eq!(out, vec![0, 0, 0, 6, 0, 1, 2, 3, 4, 5, 0, 0]);
+    assert_eq!(input.position(), 6);
+
+    match pack_opaque_from_reader(10, &mut Cursor::new(&data[..9]), &mut Vec::new()) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn opaque_to_writer() {
+    let data: Vec<u8> = (0..100_001).map(|v| v as u8).collect();
+    let mut enc = pack_to_vec(&Opaque::borrowed(&data)).unwrap();
+    enc.extend_from_slice(&[0xff; 4]);
+
+    let mut sink = Vec::new();
+    let mut input = Cursor::new(&enc[..]);
+    assert_eq!(unpack_opaque_to_writer(&mut input, &mut sink, Some(100_001)).unwrap(), 100_008);
+    assert_eq!(sink, data);
+    assert_eq!(input.position(), 100_008);
+
+    match unpack_opaque_to_writer(&mut Cursor::new(&enc[..]), &mut Vec::new(), Some(100_000)) {
+        Err(Error(ErrorKind::InvalidLen(100_000), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    let mut sink = Vec::new();
+    match unpack_opaque_to_writer(&mut Cursor::new(&enc[..1000]), &mut sink, None) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(&sink[..], &data[..996]);
+}
+
+#[test]
+fn len_conversions() {
+    assert_eq!(xdr_len(0).unwrap(), 0);
+    assert_eq!(xdr_len(1234).unwrap(), 1234);
+    assert_eq!(host_len(0).unwrap(), 0);
+    assert_eq!(host_len(0xffff_ffff).unwrap(), 0xffff_ffff);
+
+    // 64-bit host lengths which don't fit in a count
+    assert_eq!(xdr_len_from(0xffff_ffff).unwrap(), 0xffff_ffff);
+    match xdr_len_from(0x1_0000_0000) {
+        Err(Error(ErrorKind::InvalidLen(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match xdr_len_from(u64::MAX) {
+        Err(Error(ErrorKind::InvalidLen(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // 32-bit target: every count fits
+    assert_eq!(host_len_max(0xffff_ffff, u32::MAX as u64).unwrap(), 0xffff_ffff);
+
+    // 16-bit target: counts above 0xffff don't
+    assert_eq!(host_len_max(0xffff, u16::MAX as u64).unwrap(), 0xffff);
+    match host_len_max(0x1_0000, u16::MAX as u64) {
+        Err(Error(ErrorKind::InvalidLen(0xffff), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn oversized_len() {
+    let mut out = Cursor::new(Vec::new());
+
+    match 0x1_0000_0000usize.pack(&mut out) {
+        Err(Error(ErrorKind::InvalidLen(0x1_0000_0000), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert!(out.into_inner().is_empty());
+}
+
+#[test]
+fn basic_collections() {
+    let expected = vec![0x00, 0x00, 0x00, 0x03,
+                        0x00, 0x00, 0x00, 0x01,
+                        0x00, 0x00, 0x00, 0x02,
+                        0x00, 0x00, 0x00, 0x03];
+    {
+        let mut out = Cursor::new(Vec::new());
+        let mut dq: VecDeque<u32> = VecDeque::new();
+        dq.push_back(2);
+        dq.push_back(3);
+        dq.push_front(1);
+
+        assert_eq!(dq.pack(&mut out).unwrap(), 16);
+        let v = out.into_inner();
+        assert_eq!(v, expected);
+
+        let mut input = Cursor::new(v);
+        assert_eq!(Unpack::unpack(&mut input).unwrap(), (dq, 16));
+    }
+
+    {
+        let mut out = Cursor::new(Vec::new());
+        let set: BTreeSet<u32> = vec![3, 1, 2].into_iter().collect();
+
+        assert_eq!(set.pack(&mut out).unwrap(), 16);
+        let v = out.into_inner();
+        assert_eq!(v, expected);
+
+        let mut input = Cursor::new(v);
+        assert_eq!(Unpack::unpack(&mut input).unwrap(), (set, 16));
+    }
+
+    {
+        let mut out = Cursor::new(Vec::new());
+        let set: HashSet<u32> = vec![3, 1, 2].into_iter().collect();
+
+        assert_eq!(set.pack(&mut out).unwrap(), 16);
+        let v = out.into_inner();
+        assert_eq!(&v[..4], &expected[..4]);
+
+        let mut input = Cursor::new(v);
+        assert_eq!(Unpack::unpack(&mut input).unwrap(), (set, 16));
+    }
+
+    {
+        // duplicates on the wire are merged
+        let mut input = Cursor::new(vec![0x00, 0x00, 0x00, 0x03,
+                                         0x00, 0x00, 0x00, 0x07,
+                                         0x00, 0x00, 0x00, 0x07,
+                                         0x00, 0x00, 0x00, 0x01]);
+        let (set, sz): (BTreeSet<u32>, _) = Unpack::unpack(&mut input).unwrap();
+        assert_eq!(sz, 16);
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![1, 7]);
+    }
+}
+
+#[test]
+#[allow(clippy::needless_borrows_for_generic_args)]
+fn basic_ref() {
+    fn packed<T: Pack<Vec<u8>>>(v: T) -> Vec<u8> {
+        let mut out = Vec::new();
+        v.pack(&mut out).unwrap();
+        out
+    }
+
+    let mut v = vec![10u32, 11];
+    let expected = pack_to_vec(&v).unwrap();
+    assert_eq!(packed(&v), expected);
+    assert_eq!(packed(&mut v), expected);
+    assert_eq!(packed(&&v[..]), expected);
+    assert_eq!(packed(&"hi"), packed("hi".to_string().as_str()));
+
+    // Containers of references
+    let (a, b) = (10u32, 11u32);
+    assert_eq!(pack_to_vec(&vec![&a, &b]).unwrap(), expected);
+    assert_eq!(pack_to_vec(&Some(&a)).unwrap(), vec![0, 0, 0, 1, 0, 0, 0, 10]);
+}
+
+#[test]
+fn basic_shared() {
+    let expected = vec![0x00, 0x00, 0x00, 0x02,
+                        0x00, 0x00, 0x00, 0x0a,
+                        0x00, 0x00, 0x00, 0x0b];
+    {
+        let mut out = Cursor::new(Vec::new());
+        let v = Rc::new(vec![10u32, 11]);
+
+        assert_eq!(v.pack(&mut out).unwrap(), 12);
+        let buf = out.into_inner();
+        assert_eq!(buf, expected);
+
+        let mut input = Cursor::new(buf);
+        assert_eq!(Unpack::unpack(&mut input).unwrap(), (v, 12));
+    }
+
+    {
+        let mut out = Cursor::new(Vec::new());
+        let v = Arc::new(vec![10u32, 11]);
+
+        assert_eq!(v.pack(&mut out).unwrap(), 12);
+        let buf = out.into_inner();
+        assert_eq!(buf, expected);
+
+        let mut input = Cursor::new(buf);
+        assert_eq!(Unpack::unpack(&mut input).unwrap(), (v, 12));
+    }
+
+    {
+        let mut out = Cursor::new(Vec::new());
+        let v: Arc<[u32]> = Arc::from(vec![10u32, 11]);
+
+        assert_eq!(v.pack(&mut out).unwrap(), 12);
+        let buf = out.into_inner();
+        assert_eq!(buf, expected);
+
+        let mut input = Cursor::new(buf);
+        assert_eq!(Unpack::unpack(&mut input).unwrap(), (v, 12));
+    }
+
+    {
+        let mut out = Cursor::new(Vec::new());
+        let v: Rc<[u32]> = Rc::from(vec![10u32, 11]);
+
+        assert_eq!(v.pack(&mut out).unwrap(), 12);
+        let buf = out.into_inner();
+        assert_eq!(buf, expected);
+
+        let mut input = Cursor::new(buf);
+        assert_eq!(Unpack::unpack(&mut input).unwrap(), (v, 12));
+    }
+
+    {
+        let mut out = Cursor::new(Vec::new());
+        let v: Box<[u32]> = vec![10u32, 11].into_boxed_slice();
+
+        assert_eq!(v.pack(&mut out).unwrap(), 12);
+        let buf = out.into_inner();
+        assert_eq!(buf, expected);
+
+        let mut input = Cursor::new(buf);
+        assert_eq!(Unpack::unpack(&mut input).unwrap(), (v, 12));
+    }
+}
+
+#[test]
+fn basic_cow_opaque() {
+    let expected = vec![0x00, 0x00, 0x00, 0x05,
+                        0x01, 0x02, 0x03, 0x04,
+                        0x05, 0x00, 0x00, 0x00];
+    let data = [1u8, 2, 3, 4, 5];
+
+    let mut out = Cursor::new(Vec::new());
+    let v: Cow<[u8]> = Cow::Borrowed(&data[..]);
+
+    assert_eq!(v.pack(&mut out).unwrap(), 12);
+    let buf = out.into_inner();
+    assert_eq!(buf, expected);
+
+    let mut input = Cursor::new(buf.clone());
+    let (uv, sz): (Cow<[u8]>, _) = Unpack::unpack(&mut input).unwrap();
+    assert_eq!(sz, 12);
+    assert_eq!(uv, v);
+
+    // borrowed from the input, which is advanced past it
+    let mut buf = buf;
+    buf.extend(&[0xaa, 0xbb]);
+    let mut input = &buf[..];
+    let (bv, sz) = unpack_opaque_borrowed(&mut input, Some(5)).unwrap();
+    assert_eq!(sz, 12);
+    assert_eq!(bv, &data[..]);
+    assert_eq!(bv.as_ptr(), buf[4..].as_ptr());
+    assert_eq!(input, &[0xaa, 0xbb]);
+
+    let mut input = &buf[..];
+    match unpack_opaque_borrowed(&mut input, Some(4)) {
+        Err(Error(ErrorKind::InvalidLen(4), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let mut input = &buf[..10];
+    match unpack_opaque_borrowed(&mut input, None) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn zero_sized() {
+    // zero-length fixed arrays
+    let mut out = Cursor::new(Vec::new());
+    assert_eq!(pack_array::<_, u32>(&[], 0, &mut out, None).unwrap(), 0);
+    assert_eq!(pack_array(&[1u32, 2], 0, &mut out, None).unwrap(), 0);
+    assert_eq!(pack_opaque_array(&[], 0, &mut out).unwrap(), 0);
+    assert_eq!(pack_opaque_array(&[1, 2, 3], 0, &mut out).unwrap(), 0);
+    assert!(out.into_inner().is_empty());
+
+    let mut input = Cursor::new(vec![0xaa; 4]);
+    let mut arr: [u32; 0] = [];
+    assert_eq!(unpack_array(&mut input, &mut arr[..], 0, None).unwrap(), 0);
+    let mut arr = [7u32, 7];
+    assert_eq!(unpack_array(&mut input, &mut arr[..], 0, Some(&0)).unwrap(), 0);
+    assert_eq!(arr, [0, 0]);
+    match unpack_array(&mut input, &mut arr[..], 0, None) {
+        Err(Error(ErrorKind::InvalidLen(0), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    let mut bytes = [0xffu8; 3];
+    assert_eq!(unpack_opaque_array(&mut input, &mut bytes[..0], 0).unwrap(), 0);
+    assert_eq!(unpack_opaque_array(&mut input, &mut bytes[..], 0).unwrap(), 0);
+    assert_eq!(bytes, [0, 0, 0]);
+    assert_eq!(input.position(), 0);
+
+    // empty structs and arrays of them
+    let mut out = Cursor::new(Vec::new());
+    assert_eq!(().pack(&mut out).unwrap(), 0);
+    assert_eq!(pack_array(&[(), ()], 2, &mut out, None).unwrap(), 0);
+    assert_eq!(pack_array(&[], 2, &mut out, Some(&())).unwrap(), 0);
+    assert_eq!(vec![(), (), ()].pack(&mut out).unwrap(), 4);
+    assert_eq!(Some(()).pack(&mut out).unwrap(), 4);
+    let v = out.into_inner();
+    assert_eq!(v, vec![0, 0, 0, 3,  0, 0, 0, 1]);
+
+    let mut input = Cursor::new(v);
+    let mut arr = [(); 2];
+    assert_eq!(unpack_array(&mut input, &mut arr[..], 2, None).unwrap(), 0);
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (vec![(), (), ()], 4));
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (Some(()), 4));
+}
+
+#[test]
+fn opaque_array_exact_consume() {
+    // A larger buffer than the wire size must not read past the array and its padding
+    let mut input = Cursor::new(vec![1, 2, 3, 0,  9, 9, 9, 9]);
+    let mut bytes = [0xffu8; 8];
+
+    assert_eq!(unpack_opaque_array(&mut input, &mut bytes[..], 3).unwrap(), 4);
+    assert_eq!(bytes, [1, 2, 3, 0, 0, 0, 0, 0]);
+    assert_eq!(input.position(), 4);
+
+    // A buffer between the wire size and the padded size
+    let mut input = Cursor::new(vec![1, 2, 3, 0,  9, 9, 9, 9]);
+    let mut bytes = [0xffu8; 4];
+    assert_eq!(unpack_opaque_array(&mut input, &mut bytes[..], 3).unwrap(), 4);
+    assert_eq!(bytes, [1, 2, 3, 0]);
+    assert_eq!(input.position(), 4);
+
+    // Short input fails rather than looping
+    let mut input = Cursor::new(vec![1, 2]);
+    match unpack_opaque_array(&mut input, &mut bytes[..], 3) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    let mut input = Cursor::new(vec![1, 2, 3]);
+    match unpack_opaque_array(&mut input, &mut bytes[..2], 3) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn crc32() {
+    use super::crc::Crc32;
+
+    // Standard check value
+    let mut crc = Crc32::default();
+    crc.update(b"123456789");
+    assert_eq!(crc.value(), 0xcbf4_3926);
+
+    let mut crc = Crc32::default();
+    crc.update(b"1234");
+    crc.update(b"");
+    crc.update(b"56789");
+    assert_eq!(crc.value(), 0xcbf4_3926);
+
+    assert_eq!(Crc32::default().value(), 0);
+}
+
+#[test]
+fn erased_pack() {
+    use super::ErasedPack;
+
+    let msgs: Vec<Box<dyn ErasedPack + Send>> = vec![Box::new(7u64), Box::new(Opaque::borrowed(b"abc")), Box::new(true)];
+    let enc = pack_to_vec(&msgs).unwrap();
+    assert_eq!(enc, vec![0, 0, 0, 3,
+                         0, 0, 0, 0, 0, 0, 0, 7,
+                         0, 0, 0, 3, b'a', b'b', b'c', 0,
+                         0, 0, 0, 1]);
+
+    // Non-generic plumbing
+    fn send(msg: &dyn ErasedPack, out: &mut dyn Write) -> usize {
+        msg.erased_pack(out).unwrap()
+    }
+    let mut out = Cursor::new(Vec::new());
+    assert_eq!(msgs.iter().map(|m| send(&**m, &mut out)).sum::<usize>(), enc.len() - 4);
+    assert_eq!(&out.into_inner()[..], &enc[4..]);
+}
+
+#[test]
+fn trailing() {
+    use super::unpack_trailing;
+
+    let mut cur = Cursor::new(vec![0, 0, 0, 1,  0, 0, 0, 0, 0, 0, 0, 2]);
+    assert_eq!(unpack_trailing::<_, u32>(&mut cur).unwrap(), (1, 4));
+    assert_eq!(unpack_trailing::<_, Option<u64>>(&mut cur).unwrap(), (None, 4));
+    assert_eq!(cur.position(), 8);
+    cur.set_position(12);
+    assert_eq!(unpack_trailing::<_, Vec<u32>>(&mut cur).unwrap(), (vec![], 0));
+
+    match unpack_trailing::<_, u64>(&mut &[0u8, 0, 0, 0, 0, 0][..]) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn complete() {
+    use super::unpack_complete;
+
+    let enc = [0, 0, 0, 1,  0, 0, 0, 2];
+    assert_eq!(unpack_complete::<u64>(&enc).unwrap(), 0x1_0000_0002);
+    assert_eq!(unpack_complete::<Vec<u32>>(&enc).unwrap(), vec![2]);
+    match unpack_complete::<u32>(&enc) {
+        Err(Error(ErrorKind::TrailingData(4), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match unpack_complete::<Vec<u32>>(&enc[..6]) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn error_traits() {
+    use std::error::Error as StdError;
+
+    fn send_sync<T: Send + Sync + 'static>() {}
+    send_sync::<Error>();
+
+    let e: Error = io::Error::other("boom").into();
+    assert_eq!(e.to_string(), "boom");
+    assert!(e.source().is_some());
+
+    let e = Error::invalidlen(3);
+    assert_eq!(e.to_string(), "invalid array len: '3'");
+    assert_eq!(e.kind().description(), "invalid array len");
+    assert!(e.source().is_none());
+
+    match Error::from("oops") {
+        Error(ErrorKind::Msg(ref s), _) if s == "oops" => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn size_hints() {
+    fn check<T: ?Sized + Pack<Vec<u8>>>(v: &T, hint: Option<usize>) {
+        assert_eq!(v.packed_size_hint(), hint);
+        if let Some(sz) = hint {
+            assert_eq!(pack_to_vec(v).unwrap().len(), sz);
+        }
+    }
+
+    check(&1u32, Some(4));
+    check(&1u64, Some(8));
+    check(&(), Some(0));
+    check("abcde", Some(12));
+    check(&Opaque::borrowed(&[1, 2, 3, 4]), Some(8));
+    check(&vec![1u32, 2, 3], Some(16));
+    check(&Vec::<u64>::new(), Some(4));
+    check(&vec!["a", "bcdef"], Some(24));
+    check(&VecDeque::from(vec![Some(1u32), None]), Some(16));
+    check(&Box::new(Some(Rc::new(3u64))), Some(12));
+    check(&Cow::Borrowed(&[1u8, 2][..]), Some(8));
+
+    // Nothing containing a value without a hint has one
+    struct NoHint;
+    impl<Out: Write> Pack<Out> for NoHint {
+        fn pack(&self, out: &mut Out) -> super::Result<usize> {
+            1u32.pack(out)
+        }
+    }
+    check(&NoHint, None);
+    check(&vec![Some(NoHint)], None);
+    assert!(<u32 as Pack<Vec<u8>>>::is_fixed_size());
+    assert!(!<Vec<u32> as Pack<Vec<u8>>>::is_fixed_size());
+}
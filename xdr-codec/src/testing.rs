@@ -0,0 +1,187 @@
+//! Test support
+//!
+//! `assert_xdr_roundtrip!(value)` checks that a value unpacks from its own encoding, and
+//! `assert_xdr_bytes!(value, expected_hex)` checks that it encodes to particular bytes (for
+//! example, captured from a C `rpcgen` implementation). On failure they print a hexdump of
+//! expected and actual bytes side by side, one XDR word per line, with differing words marked:
+//!
+//! ```text
+//! offset    expected  actual
+//! 00000000  0000002a  0000002a
+//! 00000004  00000003  00000004  <
+//! 00000008  616263--  61626364  <
+//! ```
+//!
+//! Long dumps only show the lines around differences.
+//!
+//! ```
+//! #[macro_use] extern crate xdr_codec;
+//!
+//! # fn main() {
+//! assert_xdr_roundtrip!(vec![1u32, 2, 3]);
+//! assert_xdr_bytes!(*"abc", "00000003 61626300");
+//! # }
+//! ```
+use std::cmp::max;
+use std::fmt::{Debug, Write};
+
+use super::{Pack, Unpack, pack_to_vec, unpack_from_bytes};
+
+/// Lines of unchanged context shown around each difference in long dumps.
+const CONTEXT: usize = 2;
+
+/// Dumps with more lines than this only show the differences and their context.
+const MAX_FULL: usize = 32;
+
+/// Parse a string of hex digits into bytes. Whitespace is ignored.
+///
+/// Panics if the string contains anything else, or an odd number of digits.
+pub fn parse_hex(hex: &str) -> Vec<u8> {
+    let digits: Vec<u8> = hex.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c.to_digit(16) {
+            Some(d) => d as u8,
+            None => panic!("invalid hex digit {:?} in {:?}", c, hex),
+        })
+        .collect();
+
+    assert!(digits.len().is_multiple_of(2), "odd number of hex digits in {:?}", hex);
+
+    digits.chunks(2).map(|d| d[0] << 4 | d[1]).collect()
+}
+
+// One 4-byte word as hex, with `--` for bytes past the end of the data
+fn word(data: &[u8], idx: usize) -> String {
+    (idx * 4..idx * 4 + 4)
+        .map(|i| data.get(i).map_or_else(|| "--".to_string(), |b| format!("{:02x}", b)))
+        .collect()
+}
+
+/// Format `data` as a hexdump, one XDR word per line.
+pub fn hexdump(data: &[u8]) -> String {
+    let mut out = String::new();
+
+    for i in 0..data.len().div_ceil(4) {
+        let _ = writeln!(out, "{:08x}  {}", i * 4, word(data, i));
+    }
+
+    out
+}
+
+/// Format `expected` and `actual` as a side-by-side hexdump, marking words which differ.
+pub fn hexdump_diff(expected: &[u8], actual: &[u8]) -> String {
+    let words = max(expected.len(), actual.len()).div_ceil(4);
+    let lines: Vec<(String, String)> = (0..words).map(|i| (word(expected, i), word(actual, i))).collect();
+
+    let shown: Vec<bool> = if words <= MAX_FULL {
+        vec![true; words]
+    } else {
+        (0..words)
+            .map(|i| {
+                let lo = i.saturating_sub(CONTEXT);
+                let hi = (i + CONTEXT + 1).min(words);
+                lines[lo..hi].iter().any(|(e, a)| e != a)
+            })
+            .collect()
+    };
+
+    let mut out = String::from("offset    expected  actual\n");
+    let mut elided = false;
+
+    for (i, (e, a)) in lines.iter().enumerate() {
+        if !shown[i] {
+            if !elided {
+                out.push_str("...\n");
+                elided = true;
+            }
+            continue;
+        }
+        elided = false;
+
+        let _ = write!(out, "{:08x}  {}  {}", i * 4, e, a);
+        if e != a {
+            out.push_str("  <");
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Check that `val` unpacks to an equal value from its own encoding, using all of it.
+///
+/// Returns a description of the failure, or `None` if it round-trips. This is the check behind
+/// `assert_xdr_roundtrip!`.
+pub fn roundtrip_mismatch<T>(val: &T) -> Option<String>
+    where T: Debug + PartialEq + Pack<Vec<u8>> + for<'a> Unpack<&'a [u8]>
+{
+    let enc = match pack_to_vec(val) {
+        Ok(enc) => enc,
+        Err(e) => return Some(format!("pack failed: {}", e)),
+    };
+
+    let (uv, sz) = match unpack_from_bytes::<T>(&enc) {
+        Ok(v) => v,
+        Err(e) => return Some(format!("unpack failed: {}\n{}", e, hexdump(&enc))),
+    };
+
+    if uv == *val && sz == enc.len() {
+        return None;
+    }
+
+    let mut msg = String::new();
+    if sz != enc.len() {
+        let _ = writeln!(msg, "unpack used {} of {} bytes", sz, enc.len());
+    }
+    if uv != *val {
+        let _ = writeln!(msg, "  packed: {:?}\nunpacked: {:?}", val, uv);
+    }
+    match pack_to_vec(&uv) {
+        Ok(again) => msg.push_str(&hexdump_diff(&enc, &again)),
+        Err(e) => { let _ = writeln!(msg, "repack failed: {}", e); }
+    }
+
+    Some(msg)
+}
+
+/// Check that `val` packs to the bytes given in hex by `expected` (see `parse_hex`).
+///
+/// Returns a description of the failure, or `None` if it matches. This is the check behind
+/// `assert_xdr_bytes!`.
+pub fn bytes_mismatch<T>(val: &T, expected: &str) -> Option<String>
+    where T: ?Sized + Pack<Vec<u8>>
+{
+    let expected = parse_hex(expected);
+
+    match pack_to_vec(val) {
+        Ok(ref enc) if *enc == expected => None,
+        Ok(enc) => Some(hexdump_diff(&expected, &enc)),
+        Err(e) => Some(format!("pack failed: {}", e)),
+    }
+}
+
+/// Assert that a value unpacks to an equal value from its own encoding.
+///
+/// On failure, panics with a hexdump comparing the encoding of the value with the encoding of
+/// what it unpacked to. See the `testing` module.
+#[macro_export]
+macro_rules! assert_xdr_roundtrip {
+    ($val:expr) => {
+        if let Some(msg) = $crate::testing::roundtrip_mismatch(&$val) {
+            panic!("assertion failed: `{}` doesn't round trip\n{}", stringify!($val), msg);
+        }
+    };
+}
+
+/// Assert that a value packs to the bytes given as a hex string (whitespace is ignored).
+///
+/// On failure, panics with a hexdump comparing the expected and actual encodings. See the
+/// `testing` module.
+#[macro_export]
+macro_rules! assert_xdr_bytes {
+    ($val:expr, $expected:expr) => {
+        if let Some(msg) = $crate::testing::bytes_mismatch(&$val, $expected) {
+            panic!("assertion failed: `{}` doesn't encode as expected\n{}", stringify!($val), msg);
+        }
+    };
+}
@@ -0,0 +1,203 @@
+//! Conformance test vectors
+//!
+//! `VECTORS` is a table of canonical XDR encodings, each labelled with the XDR declaration it's
+//! an encoding of: the worked example from RFC4506 section 7, and encodings of the basic types,
+//! strings, optionals, unions and arrays as produced by the C `rpcgen`/glibc `xdr_*` routines.
+//!
+//! `check` runs a type against every vector for a declaration, checking that it unpacks each
+//! encoding using all of it, and packs the result back to exactly the same bytes. This is a quick
+//! way to confirm that a hand-written or generated type interoperates with other
+//! implementations:
+//!
+//! ```
+//! use xdr_codec::testvectors;
+//!
+//! assert_eq!(testvectors::check::<i64>("hyper"), Ok(4));
+//! assert_eq!(testvectors::check::<Option<i32>>("int *"), Ok(2));
+//!
+//! // A string isn't an array of ints
+//! assert!(testvectors::check::<Vec<i32>>("string<>").is_err());
+//! ```
+//!
+//! Declarations are written as they would be in a `.x` file, without a name for simple types
+//! (`int`, `string<>`, `opaque[5]`); `declarations` lists them all.
+//!
+//! This module is only available with the `testvectors` feature.
+use std::fmt::{Debug, Write};
+
+use super::{Pack, Unpack, pack_to_vec, unpack_from_bytes};
+use testing::{hexdump, hexdump_diff, parse_hex};
+
+/// Where a test vector comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// Given in RFC4506.
+    Rfc4506,
+    /// Produced by the C `rpcgen`/glibc `xdr_*` routines.
+    Rpcgen,
+}
+
+/// One canonical encoding of a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vector {
+    /// The XDR declaration of the encoded value.
+    pub decl: &'static str,
+    /// A description of the value.
+    pub name: &'static str,
+    /// Where the encoding comes from.
+    pub source: Source,
+    /// The encoding, in hex (see `testing::parse_hex`).
+    pub hex: &'static str,
+}
+
+impl Vector {
+    /// The encoding as bytes.
+    pub fn bytes(&self) -> Vec<u8> {
+        parse_hex(self.hex)
+    }
+}
+
+/// The union used by the union vectors.
+pub const UNION_DECL: &str = "union switch (int d) { case 0: void; case 1: int i; case 2: string s<>; }";
+
+/// The `file` structure from the example in RFC4506 section 7.
+pub const RFC_FILE_DECL: &str = "struct file { string filename<MAXNAMELEN>; \
+                                 union switch (filekind kind) { case TEXT: void; \
+                                 case DATA: string creator<MAXNAMELEN>; \
+                                 case EXEC: string interpretor<MAXNAMELEN>; } type; \
+                                 string owner<MAXUSERNAME>; opaque data<MAXFILELEN>; }";
+
+macro_rules! vectors {
+    ($($source:ident $decl:expr, $name:expr => $hex:expr;)*) => {
+        /// All the test vectors.
+        pub static VECTORS: &[Vector] = &[
+            $( Vector { decl: $decl, name: $name, source: Source::$source, hex: $hex }, )*
+        ];
+    }
+}
+
+vectors! {
+    Rpcgen "int", "0" => "00000000";
+    Rpcgen "int", "1" => "00000001";
+    Rpcgen "int", "-1" => "ffffffff";
+    Rpcgen "int", "INT_MAX" => "7fffffff";
+    Rpcgen "int", "INT_MIN" => "80000000";
+
+    Rpcgen "unsigned int", "0" => "00000000";
+    Rpcgen "unsigned int", "0x12345678" => "12345678";
+    Rpcgen "unsigned int", "UINT_MAX" => "ffffffff";
+
+    Rpcgen "hyper", "0" => "00000000 00000000";
+    Rpcgen "hyper", "-1" => "ffffffff ffffffff";
+    Rpcgen "hyper", "0x0102030405060708" => "01020304 05060708";
+    Rpcgen "hyper", "LLONG_MIN" => "80000000 00000000";
+
+    Rpcgen "unsigned hyper", "1" => "00000000 00000001";
+    Rpcgen "unsigned hyper", "ULLONG_MAX" => "ffffffff ffffffff";
+
+    Rpcgen "float", "0.0" => "00000000";
+    Rpcgen "float", "1.0" => "3f800000";
+    Rpcgen "float", "-2.5" => "c0200000";
+
+    Rpcgen "double", "0.0" => "00000000 00000000";
+    Rpcgen "double", "1.0" => "3ff00000 00000000";
+    Rpcgen "double", "-2.5" => "c0040000 00000000";
+
+    Rpcgen "bool", "FALSE" => "00000000";
+    Rpcgen "bool", "TRUE" => "00000001";
+
+    Rpcgen "string<>", "empty" => "00000000";
+    Rpcgen "string<>", "\"a\"" => "00000001 61000000";
+    Rpcgen "string<>", "\"abcd\"" => "00000004 61626364";
+    Rpcgen "string<>", "\"hello\"" => "00000005 68656c6c 6f000000";
+
+    Rpcgen "opaque<>", "empty" => "00000000";
+    Rpcgen "opaque<>", "01 02 03" => "00000003 01020300";
+
+    Rpcgen "opaque[5]", "01 02 03 04 05" => "01020304 05000000";
+
+    Rpcgen "int<>", "empty" => "00000000";
+    Rpcgen "int<>", "{1, 2, 3}" => "00000003 00000001 00000002 00000003";
+
+    Rpcgen "int[3]", "{1, -1, 7}" => "00000001 ffffffff 00000007";
+
+    Rpcgen "int *", "NULL" => "00000000";
+    Rpcgen "int *", "-> 7" => "00000001 00000007";
+
+    Rpcgen UNION_DECL, "case 0" => "00000000";
+    Rpcgen UNION_DECL, "case 1: 42" => "00000001 0000002a";
+    Rpcgen UNION_DECL, "case 2: \"hi\"" => "00000002 00000002 68690000";
+
+    Rfc4506 RFC_FILE_DECL, "sillyprog" =>
+        "00000009 73696c6c 7970726f 67000000
+         00000002 00000004 6c697370
+         00000004 6a6f686e
+         00000006 28717569 74290000";
+}
+
+/// The vectors for a declaration.
+pub fn matching(decl: &str) -> impl Iterator<Item=&'static Vector> + '_ {
+    VECTORS.iter().filter(move |v| v.decl == decl)
+}
+
+/// The declarations which have vectors, in the order they appear in `VECTORS`.
+pub fn declarations() -> Vec<&'static str> {
+    let mut ret: Vec<&'static str> = Vec::new();
+    for v in VECTORS {
+        if !ret.contains(&v.decl) {
+            ret.push(v.decl);
+        }
+    }
+    ret
+}
+
+// Check one vector, appending any failure to `msg`
+fn check_one<T>(v: &Vector, msg: &mut String)
+    where T: Debug + Pack<Vec<u8>> + for<'a> Unpack<&'a [u8]>
+{
+    let enc = v.bytes();
+
+    let (val, sz) = match unpack_from_bytes::<T>(&enc) {
+        Ok(val) => val,
+        Err(e) => {
+            let _ = write!(msg, "{}: unpack failed: {}\n{}", v.name, e, hexdump(&enc));
+            return;
+        },
+    };
+
+    if sz != enc.len() {
+        let _ = writeln!(msg, "{}: unpack used {} of {} bytes", v.name, sz, enc.len());
+    }
+    match pack_to_vec(&val) {
+        Ok(ref again) if *again == enc => (),
+        Ok(again) => {
+            let _ = write!(msg, "{}: {:?} packs differently\n{}", v.name, val, hexdump_diff(&enc, &again));
+        },
+        Err(e) => { let _ = writeln!(msg, "{}: repack of {:?} failed: {}", v.name, val, e); },
+    }
+}
+
+/// Run `T` against every vector for `decl`.
+///
+/// Each encoding must unpack, using all the bytes, to a value which packs back to the same
+/// bytes. Returns the number of vectors checked, or a description of all the failures. It's
+/// also an error if there are no vectors for `decl`.
+pub fn check<T>(decl: &str) -> ::std::result::Result<usize, String>
+    where T: Debug + Pack<Vec<u8>> + for<'a> Unpack<&'a [u8]>
+{
+    let mut msg = String::new();
+    let mut count = 0;
+
+    for v in matching(decl) {
+        check_one::<T>(v, &mut msg);
+        count += 1;
+    }
+
+    if count == 0 {
+        Err(format!("no test vectors for `{}`", decl))
+    } else if msg.is_empty() {
+        Ok(count)
+    } else {
+        Err(msg)
+    }
+}
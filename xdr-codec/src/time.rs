@@ -0,0 +1,112 @@
+//! Time encodings
+//!
+//! Many XDR protocols represent times as a pair of seconds and nanoseconds (for example NFSv4's
+//! `nfstime4`). This module implements `Pack` and `Unpack` for `std::time::Duration` and
+//! `std::time::SystemTime` in that form:
+//!
+//! ```text
+//! struct {
+//!     hyper seconds;          // unsigned hyper for Duration
+//!     unsigned int nseconds;  // always less than 1,000,000,000
+//! }
+//! ```
+//!
+//! `SystemTime` is encoded relative to the Unix epoch by default. Times before the epoch have
+//! negative seconds, with `nseconds` still counting forward from the start of that second. The
+//! `pack_time` and `unpack_time` functions allow an arbitrary epoch to be used instead.
+use std::io::{Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use error::*;
+use super::{Pack, Unpack};
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+impl<Out: Write> Pack<Out> for Duration {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.as_secs().pack(out)? + self.subsec_nanos().pack(out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for Duration {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (secs, ssz) = u64::unpack(input)?;
+        let (nsecs, nsz) = u32::unpack(input)?;
+
+        if nsecs >= NANOS_PER_SEC {
+            bail!(ErrorKind::InvalidTime(secs as i64, nsecs));
+        }
+
+        Ok((Duration::new(secs, nsecs), ssz + nsz))
+    }
+}
+
+/// Pack a time relative to an epoch.
+///
+/// `time` is encoded as a signed number of seconds and an unsigned number of nanoseconds since
+/// `epoch`. Fails with `InvalidTime` if the time is too far from the epoch to represent.
+pub fn pack_time<Out: Write>(time: SystemTime, epoch: SystemTime, out: &mut Out) -> Result<usize> {
+    let (secs, nsecs) = match time.duration_since(epoch) {
+        Ok(d) => {
+            if d.as_secs() > i64::MAX as u64 {
+                bail!(ErrorKind::InvalidTime(i64::MAX, d.subsec_nanos()));
+            }
+            (d.as_secs() as i64, d.subsec_nanos())
+        },
+        Err(e) => {
+            // Before the epoch; round seconds down so nanoseconds are positive
+            let d = e.duration();
+            if d.as_secs() > i64::MAX as u64 {
+                bail!(ErrorKind::InvalidTime(i64::MIN, d.subsec_nanos()));
+            }
+            let secs = -(d.as_secs() as i64);
+            match d.subsec_nanos() {
+                0 => (secs, 0),
+                n => (secs - 1, NANOS_PER_SEC - n),
+            }
+        },
+    };
+
+    Ok(secs.pack(out)? + nsecs.pack(out)?)
+}
+
+/// Unpack a time relative to an epoch.
+///
+/// This is the inverse of `pack_time`. Fails with `InvalidTime` if the nanoseconds are out of
+/// range, or if the time can't be represented as a `SystemTime`.
+pub fn unpack_time<In: Read>(input: &mut In, epoch: SystemTime) -> Result<(SystemTime, usize)> {
+    let (secs, ssz) = i64::unpack(input)?;
+    let (nsecs, nsz) = u32::unpack(input)?;
+
+    if nsecs >= NANOS_PER_SEC {
+        bail!(ErrorKind::InvalidTime(secs, nsecs));
+    }
+
+    let time = if secs >= 0 {
+        epoch.checked_add(Duration::new(secs as u64, nsecs))
+    } else {
+        epoch.checked_sub(Duration::new(secs.unsigned_abs(), 0))
+            .and_then(|t| t.checked_add(Duration::new(0, nsecs)))
+    };
+
+    match time {
+        Some(t) => Ok((t, ssz + nsz)),
+        None => bail!(ErrorKind::InvalidTime(secs, nsecs)),
+    }
+}
+
+impl<Out: Write> Pack<Out> for SystemTime {
+    #[inline]
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_time(*self, UNIX_EPOCH, out)
+    }
+}
+
+impl<In: Read> Unpack<In> for SystemTime {
+    #[inline]
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_time(input, UNIX_EPOCH)
+    }
+}
@@ -0,0 +1,160 @@
+//! Rewriting selected parts of messages
+//!
+//! A `Transcoder` decodes messages with a `Schema`, passes every part of each decoded `Value`
+//! to a callback which may change it, and packs the result. Anything the callback leaves alone
+//! is re-encoded exactly as it was received (apart from padding, which is zeroed), so this can
+//! be used to redact credentials or remap user IDs in captured traffic while keeping it
+//! otherwise intact.
+//!
+//! The callback is given the path of each value within the message, and is called on a value
+//! before the values it contains, so it sees anything it replaces rather than what was decoded.
+//! Paths are built from:
+//!
+//!  * struct field names, separated by `.`
+//!  * array indices in brackets, as in `entries[3].name`
+//!  * `discriminant` and `arm` for the parts of a union, as in the JSON mapping
+//!
+//! The whole message has the empty path, and a present optional value has the same path as the
+//! optional itself.
+//!
+//! ```
+//! use xdr_codec::Value;
+//! use xdr_codec::transcode::Transcoder;
+//! use xdr_codec::value::{Schema, Type};
+//!
+//! let mut schema = Schema::new();
+//! schema.define("login", Type::Struct(vec![("uid".to_string(), Type::UInt),
+//!                                          ("password".to_string(), Type::String(None))]));
+//!
+//! let mut tc = Transcoder::named(&schema, "login", |path: &str, val: &mut Value| match path {
+//!     "uid" => *val = Value::UInt(65534),
+//!     "password" => *val = Value::String("*".to_string()),
+//!     _ => (),
+//! });
+//!
+//! let msg = [0, 0, 3, 0xe8,  0, 0, 0, 6, b's', b'e', b'c', b'r', b'e', b't', 0, 0];
+//! assert_eq!(tc.transcode_bytes(&msg).unwrap(), [0, 0, 0xff, 0xfe,  0, 0, 0, 1, b'*', 0, 0, 0]);
+//! ```
+//!
+//! Changes which don't fit the schema, such as an enum variant which isn't defined or a string
+//! longer than its limit, fail with `InvalidValue` rather than producing a message the receiver
+//! can't decode.
+use std::fmt::Write as FmtWrite;
+use std::io::{Read, Write};
+
+use error::*;
+use stream::XdrReader;
+use value::{Schema, Type, Value};
+use super::pack_to_vec;
+
+/// Decodes messages of one type, rewrites them with a callback, and re-encodes them.
+pub struct Transcoder<'a, F> {
+    schema: &'a Schema,
+    ty: Type,
+    rewrite: F,
+}
+
+impl<'a, F: FnMut(&str, &mut Value)> Transcoder<'a, F> {
+    /// Create a transcoder for messages of type `ty`, rewriting them with `rewrite`.
+    pub fn new(schema: &'a Schema, ty: Type, rewrite: F) -> Transcoder<'a, F> {
+        Transcoder { schema, ty, rewrite }
+    }
+
+    /// Create a transcoder for messages of the named type.
+    pub fn named(schema: &'a Schema, name: &str, rewrite: F) -> Transcoder<'a, F> {
+        Transcoder::new(schema, Type::Named(name.to_string()), rewrite)
+    }
+
+    /// Apply the callback to every part of a value, as it would be applied to a decoded
+    /// message.
+    pub fn rewrite(&mut self, val: &mut Value) {
+        walk(&mut self.rewrite, &mut String::new(), val)
+    }
+
+    /// Transcode one message from `input` to `output`, returning the number of bytes read and
+    /// written.
+    ///
+    /// The rewritten message is checked against the schema before anything is written.
+    pub fn transcode<In: Read, Out: Write>(&mut self, input: &mut In, output: &mut Out) -> Result<(usize, usize)> {
+        let (mut val, insz) = self.schema.unpack(&self.ty, input)?;
+        self.rewrite(&mut val);
+
+        let enc = pack_to_vec(&val)?;
+        self.check(&enc)?;
+        output.write_all(&enc)?;
+        Ok((insz, enc.len()))
+    }
+
+    /// Transcode a message which takes up all of `data`.
+    ///
+    /// Fails with `TrailingData` if there's anything after the message.
+    pub fn transcode_bytes(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(data.len());
+        let mut input = data;
+        let (sz, _) = self.transcode(&mut input, &mut out)?;
+
+        if sz != data.len() {
+            bail!(ErrorKind::TrailingData(data.len() - sz));
+        }
+        Ok(out)
+    }
+
+    /// Transcode consecutive messages until `input` ends, returning the number of messages.
+    ///
+    /// The input must end between messages; if it ends part way through one, this fails with
+    /// an `UnexpectedEof` IO error after writing all the messages before it.
+    pub fn transcode_stream<In: Read, Out: Write>(&mut self, input: In, output: &mut Out) -> Result<usize> {
+        let mut input = XdrReader::new(input);
+        let mut count = 0;
+
+        while !input.is_eof()? {
+            let _ = self.transcode(&mut input, output)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    // Make sure a rewritten message still decodes as the schema's type
+    fn check(&self, enc: &[u8]) -> Result<()> {
+        match self.schema.unpack(&self.ty, &mut &enc[..]) {
+            Ok((_, sz)) if sz == enc.len() => Ok(()),
+            Ok(_) => bail!(ErrorKind::InvalidValue("rewritten message doesn't match its type".to_string())),
+            Err(e) => bail!(ErrorKind::InvalidValue(format!("rewritten message doesn't decode: {}", e))),
+        }
+    }
+}
+
+fn push_field(path: &mut String, name: &str) {
+    if !path.is_empty() {
+        path.push('.');
+    }
+    path.push_str(name);
+}
+
+// Apply `rewrite` to `val` and then everything within it
+fn walk<F: FnMut(&str, &mut Value)>(rewrite: &mut F, path: &mut String, val: &mut Value) {
+    rewrite(path, val);
+
+    let len = path.len();
+    match *val {
+        Value::Struct(ref mut fields) => for (name, v) in fields {
+            push_field(path, name);
+            walk(rewrite, path, v);
+            path.truncate(len);
+        },
+        Value::FixedArray(ref mut elems) | Value::Array(ref mut elems) => for (i, v) in elems.iter_mut().enumerate() {
+            let _ = write!(path, "[{}]", i);
+            walk(rewrite, path, v);
+            path.truncate(len);
+        },
+        Value::Optional(Some(ref mut v)) => walk(rewrite, path, v),
+        Value::Union { ref mut discriminant, ref mut arm } => {
+            for (name, v) in [("discriminant", discriminant), ("arm", arm)] {
+                push_field(path, name);
+                walk(rewrite, path, v);
+                path.truncate(len);
+            }
+        },
+        _ => (),
+    }
+}
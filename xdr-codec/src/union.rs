@@ -0,0 +1,118 @@
+//! Discriminated unions
+//!
+//! An XDR union is encoded as its discriminant (an `int`, or an `enum` or `unsigned int`
+//! converted to one) followed by the encoding of the arm selected by that discriminant, if it has
+//! one. `pack_union` encodes that layout directly. For a Rust enum representing a union, the
+//! `XdrUnion` trait describes the mapping between arms and discriminants, and
+//! `pack_xdr_union`/`unpack_xdr_union` (or the `xdr_union_codec!` macro, to implement `Pack`,
+//! `Unpack` and `Skip` with them) do the rest:
+//!
+//! ```
+//! #[macro_use] extern crate xdr_codec;
+//! use xdr_codec::{Error, Pack, Unpack, Read, Result, Write};
+//! use xdr_codec::union::{XdrUnion, pack_union};
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum Reply {
+//!     Ok(u32),
+//!     Err(i32),
+//!     Empty,
+//! }
+//!
+//! impl XdrUnion for Reply {
+//!     fn discriminant(&self) -> i32 {
+//!         match *self {
+//!             Reply::Ok(_) => 0,
+//!             Reply::Err(_) => 1,
+//!             Reply::Empty => 2,
+//!         }
+//!     }
+//!
+//!     fn pack_body<Out: Write>(&self, out: &mut Out) -> Result<usize> {
+//!         match *self {
+//!             Reply::Ok(ref v) => v.pack(out),
+//!             Reply::Err(ref v) => v.pack(out),
+//!             Reply::Empty => Ok(0),
+//!         }
+//!     }
+//!
+//!     fn unpack_body<In: Read>(disc: i32, input: &mut In) -> Result<(Self, usize)> {
+//!         match disc {
+//!             0 => u32::unpack(input).map(|(v, sz)| (Reply::Ok(v), sz)),
+//!             1 => i32::unpack(input).map(|(v, sz)| (Reply::Err(v), sz)),
+//!             2 => Ok((Reply::Empty, 0)),
+//!             d => Err(Error::invalidcase(d)),
+//!         }
+//!     }
+//! }
+//!
+//! xdr_union_codec!(Reply);
+//!
+//! # fn main() {
+//! assert_eq!(xdr_codec::pack_to_vec(&Reply::Err(-1)).unwrap(),
+//!            vec![0, 0, 0, 1,  0xff, 0xff, 0xff, 0xff]);
+//!
+//! let mut out = Vec::new();
+//! pack_union(2, &(), &mut out).unwrap();
+//! assert_eq!(Reply::unpack(&mut &out[..]).unwrap(), (Reply::Empty, 4));
+//! # }
+//! ```
+use std::io::{Read, Write};
+
+use error::*;
+use super::{Pack, Unpack};
+
+/// A Rust type representing an XDR discriminated union.
+pub trait XdrUnion: Sized {
+    /// The discriminant of this value's arm.
+    fn discriminant(&self) -> i32;
+
+    /// Pack the arm's value, not including the discriminant. Void arms pack nothing.
+    fn pack_body<Out: Write>(&self, out: &mut Out) -> Result<usize>;
+
+    /// Unpack the arm selected by `disc`, which has already been read. Discriminants which
+    /// don't select an arm (and the union has no default) should fail with
+    /// `ErrorKind::InvalidCase`.
+    fn unpack_body<In: Read>(disc: i32, input: &mut In) -> Result<(Self, usize)>;
+}
+
+/// Pack a union: its discriminant followed by the body of the selected arm. Use `&()` as the
+/// body of a void arm.
+pub fn pack_union<Out: Write, B: Pack<Out> + ?Sized>(disc: i32, body: &B, out: &mut Out) -> Result<usize> {
+    Ok(disc.pack(out)? + body.pack(out)?)
+}
+
+/// Pack an `XdrUnion`.
+pub fn pack_xdr_union<Out: Write, U: XdrUnion>(val: &U, out: &mut Out) -> Result<usize> {
+    Ok(val.discriminant().pack(out)? + val.pack_body(out)?)
+}
+
+/// Unpack an `XdrUnion`, reading the discriminant and then the selected arm.
+pub fn unpack_xdr_union<In: Read, U: XdrUnion>(input: &mut In) -> Result<(U, usize)> {
+    let (disc, dsz) = i32::unpack(input)?;
+    let (val, bsz) = U::unpack_body(disc, input)?;
+    Ok((val, dsz + bsz))
+}
+
+/// Implement `Pack`, `Unpack` and `Skip` for a type implementing `XdrUnion`. See the `union`
+/// module.
+#[macro_export]
+macro_rules! xdr_union_codec {
+    ($name:ty) => {
+        impl<Out: $crate::Write> $crate::Pack<Out> for $name {
+            #[inline]
+            fn pack(&self, out: &mut Out) -> $crate::Result<usize> {
+                $crate::union::pack_xdr_union(self, out)
+            }
+        }
+
+        impl<In: $crate::Read> $crate::Unpack<In> for $name {
+            #[inline]
+            fn unpack(input: &mut In) -> $crate::Result<(Self, usize)> {
+                $crate::union::unpack_xdr_union(input)
+            }
+        }
+
+        impl<In: $crate::Read> $crate::Skip<In> for $name {}
+    };
+}
@@ -0,0 +1,395 @@
+//! Dynamically typed XDR values
+//!
+//! `Value` holds any XDR value without a corresponding Rust type, for generic tools such as
+//! inspectors and proxies which don't link generated code. Since XDR isn't self-describing,
+//! decoding needs a description of the type: a `Type`, whose named references are resolved
+//! through a `Schema`.
+//!
+//! ```
+//! use xdr_codec::{Value, pack_to_vec};
+//! use xdr_codec::value::{Schema, Type};
+//!
+//! let mut schema = Schema::new();
+//! schema.define("point", Type::Struct(vec![("x".to_string(), Type::Int),
+//!                                          ("y".to_string(), Type::Int)]));
+//!
+//! // A one-element array of points
+//! let enc = [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 2];
+//! let ty = Type::Array(Box::new(Type::Named("point".to_string())), None);
+//! let (val, _) = schema.unpack(&ty, &mut &enc[..]).unwrap();
+//!
+//! assert_eq!(val, Value::Array(vec![
+//!     Value::Struct(vec![("x".to_string(), Value::Int(1)), ("y".to_string(), Value::Int(2))]),
+//! ]));
+//! assert_eq!(pack_to_vec(&val).unwrap(), enc);
+//! ```
+//!
+//! A `Value` packs to the same encoding it was decoded from (with padding zeroed), without
+//! needing the schema.
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use error::*;
+use options::check_input_len;
+use quadruple::XdrQuadruple;
+use super::{Pack, Unpack, decode_options, prealloc, pack_array, pack_flex, pack_opaque_array, pack_string,
+            unpack_opaque_array, unpack_opaque_flex, unpack_string, Opaque};
+
+/// An XDR value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// `void`
+    Void,
+    /// `int`
+    Int(i32),
+    /// `unsigned int`
+    UInt(u32),
+    /// `hyper`
+    Hyper(i64),
+    /// `unsigned hyper`
+    UHyper(u64),
+    /// `float`
+    Float(f32),
+    /// `double`
+    Double(f64),
+    /// `quadruple`
+    Quadruple(XdrQuadruple),
+    /// `bool`
+    Bool(bool),
+    /// An enum value, with the name of its variant.
+    Enum { name: String, value: i32 },
+    /// `opaque[n]`
+    FixedOpaque(Vec<u8>),
+    /// `opaque<>`
+    Opaque(Vec<u8>),
+    /// `string<>`
+    String(String),
+    /// `T[n]`
+    FixedArray(Vec<Value>),
+    /// `T<>`
+    Array(Vec<Value>),
+    /// `T *`
+    Optional(Option<Box<Value>>),
+    /// A struct's fields, in order.
+    Struct(Vec<(String, Value)>),
+    /// A union's discriminant and the value of the selected arm.
+    Union { discriminant: Box<Value>, arm: Box<Value> },
+}
+
+impl Value {
+    /// Return the named field of a struct.
+    pub fn field(&self, name: &str) -> Option<&Value> {
+        match *self {
+            Value::Struct(ref fields) => fields.iter().find(|f| f.0 == name).map(|f| &f.1),
+            _ => None,
+        }
+    }
+
+    /// Return an integral value (including an enum or bool) as an `i64`, if it fits.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Value::Int(v) => Some(v as i64),
+            Value::UInt(v) => Some(v as i64),
+            Value::Hyper(v) => Some(v),
+            Value::UHyper(v) if v <= i64::MAX as u64 => Some(v as i64),
+            Value::Bool(v) => Some(v as i64),
+            Value::Enum { value, .. } => Some(value as i64),
+            _ => None,
+        }
+    }
+}
+
+impl<Out: Write> Pack<Out> for Value {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        match *self {
+            Value::Void => Ok(0),
+            Value::Int(v) => v.pack(out),
+            Value::UInt(v) => v.pack(out),
+            Value::Hyper(v) => v.pack(out),
+            Value::UHyper(v) => v.pack(out),
+            Value::Float(v) => v.pack(out),
+            Value::Double(v) => v.pack(out),
+            Value::Quadruple(ref v) => v.pack(out),
+            Value::Bool(v) => v.pack(out),
+            Value::Enum { value, .. } => value.pack(out),
+            Value::FixedOpaque(ref v) => pack_opaque_array(v, v.len(), out),
+            Value::Opaque(ref v) => Opaque::borrowed(v).pack(out),
+            Value::String(ref v) => pack_string(v, None, out),
+            Value::FixedArray(ref v) => pack_array(v, v.len(), out, None),
+            Value::Array(ref v) => pack_flex(v, None, out),
+            Value::Optional(ref v) => v.pack(out),
+            Value::Struct(ref fields) => {
+                let mut sz = 0;
+                for (_, v) in fields {
+                    sz += v.pack(out)?;
+                }
+                Ok(sz)
+            }
+            Value::Union { ref discriminant, ref arm } => Ok(discriminant.pack(out)? + arm.pack(out)?),
+        }
+    }
+}
+
+/// The description of an XDR type, used to decode a `Value`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Void,
+    Int,
+    UInt,
+    Hyper,
+    UHyper,
+    Float,
+    Double,
+    Quadruple,
+    Bool,
+    /// An enum's variants and their values.
+    Enum(Vec<(String, i32)>),
+    /// `opaque[n]`
+    FixedOpaque(usize),
+    /// `opaque<max>`
+    Opaque(Option<usize>),
+    /// `string<max>`
+    String(Option<usize>),
+    /// `T[n]`
+    FixedArray(Box<Type>, usize),
+    /// `T<max>`
+    Array(Box<Type>, Option<usize>),
+    /// `T *`
+    Optional(Box<Type>),
+    /// A struct's fields, in order.
+    Struct(Vec<(String, Type)>),
+    /// A union with an `int`, `unsigned int`, `bool` or enum discriminant. Each case gives the
+    /// discriminant value (as an `i32`) and the arm's type, and `default` is used for any other
+    /// value.
+    Union { discriminant: Box<Type>, cases: Vec<(i32, Type)>, default: Option<Box<Type>> },
+    /// A type defined in the `Schema`.
+    Named(String),
+}
+
+/// A set of named types.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    types: HashMap<String, Type>,
+}
+
+impl Schema {
+    /// Create an empty schema.
+    pub fn new() -> Schema {
+        Schema::default()
+    }
+
+    /// Define (or redefine) a named type.
+    pub fn define<S: Into<String>>(&mut self, name: S, ty: Type) {
+        let _ = self.types.insert(name.into(), ty);
+    }
+
+    /// Return the definition of a named type.
+    pub fn get(&self, name: &str) -> Option<&Type> {
+        self.types.get(name)
+    }
+
+    /// Iterate over the defined types, in no particular order.
+    pub fn types(&self) -> impl Iterator<Item = (&str, &Type)> {
+        self.types.iter().map(|(n, t)| (n.as_str(), t))
+    }
+
+    // Follow `Named` references to a concrete type
+    pub(crate) fn resolve<'a>(&'a self, mut ty: &'a Type) -> Result<&'a Type> {
+        // A chain longer than the number of types must be a cycle
+        for _ in 0..=self.types.len() {
+            match *ty {
+                Type::Named(ref name) => match self.types.get(name) {
+                    Some(t) => ty = t,
+                    None => bail!(ErrorKind::UnknownType(name.clone())),
+                },
+                ref t => return Ok(t),
+            }
+        }
+
+        match *ty {
+            Type::Named(ref name) => bail!(ErrorKind::InvalidSchema(format!("type '{}' is defined in terms of itself", name))),
+            ref t => Ok(t),
+        }
+    }
+
+    /// Unpack a value of the named type.
+    pub fn unpack_named<In: Read>(&self, name: &str, input: &mut In) -> Result<(Value, usize)> {
+        self.unpack(&Type::Named(name.to_string()), input)
+    }
+
+    /// Unpack a value of type `ty`, returning it and the number of bytes consumed.
+    ///
+    /// Enum values and union discriminants which aren't listed fail with `InvalidEnum` and
+    /// `InvalidCase`, and references to undefined types with `UnknownType`. A value nested more
+    /// deeply than `DecodeOptions::max_depth` fails with `DepthExceeded`, and a variable-length
+    /// array of a type with an empty encoding (such as `void`) with `InvalidSchema`, as its length
+    /// can't be checked against the input.
+    pub fn unpack<In: Read>(&self, ty: &Type, input: &mut In) -> Result<(Value, usize)> {
+        self.unpack_nested(ty, input, decode_options().get_max_depth())
+    }
+
+    // Unpack a value with `depth` levels of nesting left
+    fn unpack_nested<In: Read>(&self, ty: &Type, input: &mut In, depth: usize) -> Result<(Value, usize)> {
+        let depth = nest(depth)?;
+        match *self.resolve(ty)? {
+            Type::FixedArray(ref elem, n) => self.unpack_elems(elem, n, input, depth)
+                .map(|(v, sz)| (Value::FixedArray(v), sz)),
+            Type::Array(ref elem, max) => self.unpack_array(elem, max, input, depth),
+            Type::Optional(ref t) => self.unpack_optional(t, input, depth),
+            Type::Struct(ref fields) => self.unpack_struct(fields, input, depth),
+            Type::Union { ref discriminant, ref cases, ref default } =>
+                self.unpack_union(discriminant, cases, default.as_deref(), input, depth),
+            ref ty => unpack_scalar(ty, input),
+        }
+    }
+
+    // The rest of the cases are kept out of `unpack_nested` so its stack frame, of which there's
+    // one for every level of nesting, stays small.
+    fn unpack_array<In: Read>(&self, elem: &Type, max: Option<usize>, input: &mut In, depth: usize)
+                              -> Result<(Value, usize)> {
+        let (n, lsz) = usize::unpack(input)?;
+        if max.map_or(false, |m| n > m) {
+            bail!(ErrorKind::InvalidLen(max.unwrap()));
+        }
+        self.check_array(elem)?;
+        // Every element takes at least a word
+        check_input_len(n.saturating_mul(4))?;
+        let (v, sz) = self.unpack_elems(elem, n, input, depth)?;
+        Ok((Value::Array(v), lsz + sz))
+    }
+
+    fn unpack_optional<In: Read>(&self, t: &Type, input: &mut In, depth: usize) -> Result<(Value, usize)> {
+        let (have, mut sz) = bool::unpack(input)?;
+        let v = if have {
+            let (v, vsz) = self.unpack_nested(t, input, depth)?;
+            sz += vsz;
+            Some(Box::new(v))
+        } else {
+            None
+        };
+        Ok((Value::Optional(v), sz))
+    }
+
+    fn unpack_struct<In: Read>(&self, fields: &[(String, Type)], input: &mut In, depth: usize)
+                               -> Result<(Value, usize)> {
+        let mut sz = 0;
+        let mut v = Vec::with_capacity(fields.len());
+        for (name, t) in fields {
+            let (fv, fsz) = self.unpack_nested(t, input, depth)?;
+            v.push((name.clone(), fv));
+            sz += fsz;
+        }
+        Ok((Value::Struct(v), sz))
+    }
+
+    fn unpack_union<In: Read>(&self, discriminant: &Type, cases: &[(i32, Type)], default: Option<&Type>,
+                              input: &mut In, depth: usize) -> Result<(Value, usize)> {
+        let (disc, dsz) = self.unpack_nested(discriminant, input, depth)?;
+        let d = match disc {
+            Value::Int(d) => d,
+            Value::UInt(d) => d as i32,
+            Value::Bool(d) => d as i32,
+            Value::Enum { value, .. } => value,
+            _ => bail!(ErrorKind::InvalidSchema("union discriminant must be int, unsigned int, bool or enum".to_string())),
+        };
+        let arm = match cases.iter().find(|c| c.0 == d) {
+            Some((_, t)) => t,
+            None => match default {
+                Some(t) => t,
+                None => return Err(Error::invalidcase(d)),
+            },
+        };
+        let (arm, asz) = self.unpack_nested(arm, input, depth)?;
+        Ok((Value::Union { discriminant: Box::new(disc), arm: Box::new(arm) }, dsz + asz))
+    }
+
+    fn unpack_elems<In: Read>(&self, elem: &Type, n: usize, input: &mut In, depth: usize)
+                              -> Result<(Vec<Value>, usize)> {
+        let mut sz = 0;
+        let mut v = Vec::with_capacity(prealloc::<Value>(n));
+
+        for _ in 0..n {
+            let (e, esz) = self.unpack_nested(elem, input, depth)?;
+            v.push(e);
+            sz += esz;
+        }
+
+        Ok((v, sz))
+    }
+
+    // Check a variable-length array of `elem` can be decoded. The length of an array whose
+    // elements take no space can't be checked against the input, and even the longest one would
+    // be decoded.
+    pub(crate) fn check_array(&self, elem: &Type) -> Result<()> {
+        if self.empty_encoding(elem, self.types.len())? {
+            bail!(ErrorKind::InvalidSchema(format!("array elements of type {:?} have an empty encoding", elem)));
+        }
+        Ok(())
+    }
+
+    // Return true if `ty` always encodes to nothing. `left` bounds the search through a struct
+    // which contains itself, which can't be encoded anyway.
+    fn empty_encoding(&self, ty: &Type, left: usize) -> Result<bool> {
+        Ok(match *self.resolve(ty)? {
+            Type::Void => true,
+            Type::FixedOpaque(n) => n == 0,
+            _ if left == 0 => false,
+            Type::FixedArray(ref t, n) => n == 0 || self.empty_encoding(t, left - 1)?,
+            Type::Struct(ref fields) => {
+                for (_, t) in fields {
+                    if !self.empty_encoding(t, left - 1)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            _ => false,
+        })
+    }
+}
+
+// Unpack a value of a type which doesn't contain others
+fn unpack_scalar<In: Read>(ty: &Type, input: &mut In) -> Result<(Value, usize)> {
+    let ret = match *ty {
+        Type::Void => (Value::Void, 0),
+        Type::Int => map(i32::unpack(input)?, Value::Int),
+        Type::UInt => map(u32::unpack(input)?, Value::UInt),
+        Type::Hyper => map(i64::unpack(input)?, Value::Hyper),
+        Type::UHyper => map(u64::unpack(input)?, Value::UHyper),
+        Type::Float => map(f32::unpack(input)?, Value::Float),
+        Type::Double => map(f64::unpack(input)?, Value::Double),
+        Type::Quadruple => map(XdrQuadruple::unpack(input)?, Value::Quadruple),
+        Type::Bool => map(bool::unpack(input)?, Value::Bool),
+        Type::Enum(ref variants) => {
+            let (value, sz) = i32::unpack(input)?;
+            match variants.iter().find(|v| v.1 == value) {
+                Some((name, _)) => (Value::Enum { name: name.clone(), value }, sz),
+                None => return Err(Error::invalidenum(value)),
+            }
+        }
+        Type::FixedOpaque(n) => {
+            let mut buf = vec![0; n];
+            let sz = unpack_opaque_array(input, &mut buf, n)?;
+            (Value::FixedOpaque(buf), sz)
+        }
+        Type::Opaque(max) => map(unpack_opaque_flex(input, max)?, Value::Opaque),
+        Type::String(max) => map(unpack_string(input, max)?, Value::String),
+        _ => unreachable!("not a scalar type: {:?}", ty),
+    };
+
+    Ok(ret)
+}
+
+// Take a level from the nesting left, failing if there are none
+#[inline]
+pub(crate) fn nest(depth: usize) -> Result<usize> {
+    match depth.checked_sub(1) {
+        Some(depth) => Ok(depth),
+        None => bail!(ErrorKind::DepthExceeded(decode_options().get_max_depth())),
+    }
+}
+
+#[inline]
+fn map<T>((v, sz): (T, usize), f: fn(T) -> Value) -> (Value, usize) {
+    (f(v), sz)
+}
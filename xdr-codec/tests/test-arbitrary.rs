@@ -0,0 +1,51 @@
+#![cfg(feature="quickcheck")]
+extern crate xdr_codec;
+extern crate quickcheck;
+
+use quickcheck::{Arbitrary, quickcheck};
+
+use xdr_codec::Opaque;
+use xdr_codec::arbitrary::{roundtrips, quickcheck_roundtrip};
+use xdr_codec::bounded::{BoundedString, BoundedVec};
+use xdr_codec::fixed::FixedOpaque;
+use xdr_codec::string::XdrString;
+
+#[test]
+fn arbitrary_roundtrip() {
+    quickcheck_roundtrip::<Opaque<'static>>();
+    quickcheck_roundtrip::<BoundedString<5>>();
+    quickcheck_roundtrip::<BoundedVec<u32, 3>>();
+    quickcheck_roundtrip::<BoundedVec<BoundedString<2>, 4>>();
+    quickcheck_roundtrip::<FixedOpaque<7>>();
+    quickcheck_roundtrip::<XdrString>();
+    quickcheck_roundtrip::<Option<Vec<XdrString>>>();
+}
+
+#[test]
+fn arbitrary_bounded() {
+    fn string_limit(s: BoundedString<3>) -> bool {
+        s.len() <= 3 && s.shrink().all(|s| s.len() <= 3)
+    }
+    fn vec_limit(v: BoundedVec<u8, 2>) -> bool {
+        v.len() <= 2 && v.shrink().all(|v| v.len() <= 2)
+    }
+
+    quickcheck(string_limit as fn(BoundedString<3>) -> bool);
+    quickcheck(vec_limit as fn(BoundedVec<u8, 2>) -> bool);
+}
+
+#[test]
+fn arbitrary_fixed_shrink() {
+    let v = FixedOpaque([0, 1, 0, 2]);
+    let shrunk: Vec<_> = v.shrink().collect();
+
+    assert_eq!(shrunk, vec![FixedOpaque([0, 0, 0, 2]), FixedOpaque([0, 1, 0, 0])]);
+}
+
+#[test]
+fn roundtrips_values() {
+    assert!(roundtrips(Opaque::owned(vec![1, 2, 3])));
+    assert!(roundtrips(1.5f64));
+    // NaN never compares equal to itself
+    assert!(!roundtrips(f64::NAN));
+}
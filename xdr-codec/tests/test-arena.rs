@@ -0,0 +1,204 @@
+#![cfg(feature = "bumpalo")]
+extern crate xdr_codec;
+
+use std::io::{self, Cursor};
+
+use xdr_codec::{DecodeOptions, Error, ErrorKind, Opaque, pack_to_vec, with_decode_options};
+use xdr_codec::arena::{Bump, XdrValueRef, unpack_flex_in, unpack_opaque_flex_in, unpack_string_in};
+use xdr_codec::value::{Schema, Type, Value};
+
+#[test]
+fn arena_flex() {
+    let bump = Bump::new();
+    let enc = pack_to_vec(&vec![1u32, 2, 3]).unwrap();
+
+    let (v, sz) = unpack_flex_in::<_, u32>(&mut &enc[..], None, &bump).unwrap();
+    assert_eq!((&v[..], sz), (&[1, 2, 3][..], 16));
+
+    let res = unpack_flex_in::<_, u32>(&mut &enc[..], Some(2), &bump);
+    match res {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn arena_opaque() {
+    let mut bump = Bump::new();
+    let data: Vec<u8> = (0..200_000u32).map(|v| v as u8).collect();
+    let enc = pack_to_vec(&Opaque::borrowed(&data[..199_999])).unwrap();
+
+    {
+        let (v, sz) = unpack_opaque_flex_in(&mut Cursor::new(&enc), None, &bump).unwrap();
+        assert_eq!((v, sz), (&data[..199_999], 200_004));
+    }
+    bump.reset();
+
+    // A huge length with no data behind it fails without allocating it
+    match unpack_opaque_flex_in(&mut &[0x7f, 0xff, 0xff, 0xff, 1, 2, 3, 4][..], None, &bump) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert!(bump.allocated_bytes() < 1 << 20);
+}
+
+#[test]
+fn arena_string() {
+    let bump = Bump::new();
+    let enc = [0, 0, 0, 3, b'a', 0xff, b'b', 0];
+
+    match unpack_string_in(&mut &enc[..], None, &bump) {
+        Err(Error(ErrorKind::InvalidUtf8(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    let (s, sz) = with_decode_options(DecodeOptions::new().lossy_strings(true), || {
+        unpack_string_in(&mut &enc[..], None, &bump)
+    }).unwrap();
+    assert_eq!((s, sz), ("a\u{fffd}b", 8));
+
+    let enc = pack_to_vec("hello").unwrap();
+    assert_eq!(unpack_string_in(&mut &enc[..], Some(5), &bump).unwrap(), ("hello", 12));
+}
+
+fn file_schema() -> Schema {
+    let mut schema = Schema::new();
+    schema.define("kind", Type::Enum(vec![("REG".to_string(), 1), ("DIR".to_string(), 2)]));
+    schema.define("file", Type::Struct(vec![
+        ("kind".to_string(), Type::Named("kind".to_string())),
+        ("name".to_string(), Type::String(Some(255))),
+        ("handle".to_string(), Type::FixedOpaque(6)),
+        ("data".to_string(), Type::Opaque(None)),
+        ("sizes".to_string(), Type::Array(Box::new(Type::UHyper), None)),
+        ("next".to_string(), Type::Optional(Box::new(Type::Named("file".to_string())))),
+        ("ext".to_string(), Type::Union {
+            discriminant: Box::new(Type::Bool),
+            cases: vec![(1, Type::Int)],
+            default: Some(Box::new(Type::Void)),
+        }),
+    ]));
+    schema
+}
+
+fn file(name: &str, next: Option<Value>) -> Value {
+    Value::Struct(vec![
+        ("kind".to_string(), Value::Enum { name: "DIR".to_string(), value: 2 }),
+        ("name".to_string(), Value::String(name.to_string())),
+        ("handle".to_string(), Value::FixedOpaque(vec![1, 2, 3, 4, 5, 6])),
+        ("data".to_string(), Value::Opaque(b"hello".to_vec())),
+        ("sizes".to_string(), Value::Array(vec![Value::UHyper(1), Value::UHyper(u64::MAX)])),
+        ("next".to_string(), Value::Optional(next.map(Box::new))),
+        ("ext".to_string(), Value::Union { discriminant: Box::new(Value::Bool(true)),
+                                           arm: Box::new(Value::Int(-7)) }),
+    ])
+}
+
+#[test]
+fn arena_value() {
+    let schema = file_schema();
+    let enc = pack_to_vec(&file("outer", Some(file("inner", None)))).unwrap();
+    let bump = Bump::new();
+
+    let (val, sz) = schema.unpack_named_in("file", &mut &enc[..], &bump).unwrap();
+    assert_eq!(sz, enc.len());
+    assert_eq!(val.to_value(), schema.unpack_named("file", &mut &enc[..]).unwrap().0);
+    assert_eq!(pack_to_vec(&val).unwrap(), enc);
+
+    assert_eq!(val.field("kind"), Some(&XdrValueRef::Enum { name: "DIR", value: 2 }));
+    assert_eq!(val.field("kind").and_then(XdrValueRef::as_i64), Some(2));
+    assert_eq!(val.field("missing"), None);
+    let inner = match val.field("next") {
+        Some(&XdrValueRef::Optional(Some(v))) => v,
+        v => panic!("bad next {:?}", v),
+    };
+    assert_eq!(inner.field("name"), Some(&XdrValueRef::String("inner")));
+
+    // Opaque data and strings point into the input
+    let range = enc.as_ptr_range();
+    match val.field("data") {
+        Some(&XdrValueRef::Opaque(v)) => assert!(range.contains(&v.as_ptr())),
+        v => panic!("bad data {:?}", v),
+    }
+    match val.field("name") {
+        Some(&XdrValueRef::String(v)) => assert!(range.contains(&v.as_ptr())),
+        v => panic!("bad name {:?}", v),
+    }
+}
+
+#[test]
+fn arena_value_errors() {
+    let schema = file_schema();
+    let enc = pack_to_vec(&file("f", None)).unwrap();
+    let bump = Bump::new();
+
+    match schema.unpack_named_in("nope", &mut &enc[..], &bump) {
+        Err(Error(ErrorKind::UnknownType(ref name), _)) if name == "nope" => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match schema.unpack_named_in("file", &mut &enc[..enc.len() - 4], &bump) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match schema.unpack_named_in("kind", &mut &[0, 0, 0, 3][..], &bump) {
+        Err(Error(ErrorKind::InvalidEnum(3), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match schema.unpack_in(&Type::FixedOpaque(6), &mut &[1, 2, 3, 4, 5, 6, 0][..], &bump) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let enc = [0, 0, 0, 3, b'a', 0xff, b'b', 0];
+    match schema.unpack_in(&Type::String(None), &mut &enc[..], &bump) {
+        Err(Error(ErrorKind::InvalidUtf8(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    let (s, _) = with_decode_options(DecodeOptions::new().lossy_strings(true), || {
+        schema.unpack_in(&Type::String(None), &mut &enc[..], &bump)
+    }).unwrap();
+    assert_eq!(s, XdrValueRef::String("a\u{fffd}b"));
+
+    // Counts longer than the input fail before decoding any elements (here an invalid bool)
+    let arr = Type::Array(Box::new(Type::Bool), None);
+    match schema.unpack_in(&arr, &mut &[0, 0, 0, 2, 0, 0, 0, 2][..], &bump) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+    let arr = Type::Array(Box::new(Type::Void), None);
+    match schema.unpack_in(&arr, &mut &[0xff, 0xff, 0xff, 0xff][..], &bump) {
+        Err(Error(ErrorKind::InvalidSchema(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn arena_value_depth() {
+    let mut schema = Schema::new();
+    schema.define("entry", Type::Struct(vec![
+        ("val".to_string(), Type::Int),
+        ("next".to_string(), Type::Optional(Box::new(Type::Named("entry".to_string())))),
+    ]));
+    let list = |n: usize| {
+        let mut enc = Vec::new();
+        for i in 0..n {
+            enc.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, (i + 1 < n) as u8]);
+        }
+        enc
+    };
+    let bump = Bump::new();
+
+    let enc = list(100);
+    let (_, sz) = with_decode_options(DecodeOptions::new().max_depth(200),
+                                      || schema.unpack_named_in("entry", &mut &enc[..], &bump)).unwrap();
+    assert_eq!(sz, enc.len());
+    match with_decode_options(DecodeOptions::new().max_depth(199),
+                              || schema.unpack_named_in("entry", &mut &enc[..], &bump)) {
+        Err(Error(ErrorKind::DepthExceeded(199), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let enc = list(1_000_000);
+    match schema.unpack_named_in("entry", &mut &enc[..], &bump) {
+        Err(Error(ErrorKind::DepthExceeded(1000), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
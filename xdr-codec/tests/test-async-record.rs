@@ -0,0 +1,104 @@
+#![cfg(feature="futures")]
+extern crate futures;
+extern crate xdr_codec;
+
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::executor::block_on;
+use futures::io::{AsyncRead, Cursor};
+use futures::{SinkExt, StreamExt};
+
+use xdr_codec::{Error, ErrorKind};
+use xdr_codec::async_record::{AsyncXdrRecordReader, AsyncXdrRecordWriter};
+
+// Reader which returns Pending before every byte, one byte at a time
+struct Trickle(Vec<u8>, usize, bool);
+
+impl AsyncRead for Trickle {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+        self.2 = !self.2;
+        if self.2 {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        if self.1 == self.0.len() || buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        buf[0] = self.0[self.1];
+        self.1 += 1;
+        Poll::Ready(Ok(1))
+    }
+}
+
+#[test]
+fn async_read() {
+    let stream = vec![ 0, 0, 0, 2,  1, 2,  0x80, 0, 0, 1,  3,
+                       0x80, 0, 0, 0,
+                       0x80, 0, 0, 2,  4, 5,
+                       0, 0, 0, 1,  6 ];
+
+    let recs: Vec<_> = block_on(AsyncXdrRecordReader::new(Cursor::new(stream.clone())).collect());
+    let recs: Vec<_> = recs.into_iter().map(|r| r.unwrap()).collect();
+    assert_eq!(recs, vec![vec![1, 2, 3], vec![], vec![4, 5]]);
+
+    let recs: Vec<_> = block_on(AsyncXdrRecordReader::new(Trickle(stream, 0, false)).collect());
+    let recs: Vec<_> = recs.into_iter().map(|r| r.unwrap()).collect();
+    assert_eq!(recs, vec![vec![1, 2, 3], vec![], vec![4, 5]]);
+}
+
+#[test]
+fn async_read_limits() {
+    let stream = vec![ 0x80, 0, 0, 1,  1,  0x80, 0, 0, 3,  1, 2, 3,  0x80, 0, 0, 1,  4 ];
+    let mut rd = AsyncXdrRecordReader::with_limits(Cursor::new(stream), Some(2), None);
+
+    assert_eq!(block_on(rd.next()).unwrap().unwrap(), vec![1]);
+    match block_on(rd.next()) {
+        Some(Err(ref e)) if e.kind() == io::ErrorKind::InvalidData => {
+            match e.get_ref().and_then(|e| e.downcast_ref::<Error>()) {
+                Some(&Error(ErrorKind::FragmentTooLarge(3, 2), _)) => (),
+                res => panic!("bad inner {:?}", res),
+            }
+        },
+        res => panic!("bad result {:?}", res),
+    }
+    assert!(block_on(rd.next()).is_none());
+}
+
+#[test]
+fn async_write() {
+    let mut wr = AsyncXdrRecordWriter::with_fragment_size(Cursor::new(Vec::new()), 2);
+
+    block_on(wr.send(vec![1, 2, 3])).unwrap();
+    block_on(wr.send(vec![])).unwrap();
+    block_on(wr.feed(vec![4, 5])).unwrap();
+    block_on(wr.close()).unwrap();
+
+    let out = wr.into_inner().into_inner();
+    assert_eq!(out, vec![ 0, 0, 0, 2,  1, 2,  0x80, 0, 0, 1,  3,
+                          0x80, 0, 0, 0,
+                          0x80, 0, 0, 2,  4, 5 ]);
+}
+
+#[test]
+fn async_roundtrip() {
+    let recs: Vec<Vec<u8>> = (0..50).map(|n| (0..n * 37).map(|b| b as u8).collect()).collect();
+    let mut wr = AsyncXdrRecordWriter::with_fragment_size(Cursor::new(Vec::new()), 100);
+
+    for r in &recs {
+        block_on(wr.feed(r.clone())).unwrap();
+    }
+    block_on(wr.flush()).unwrap();
+
+    let out = wr.into_inner().into_inner();
+    let got: Vec<_> = block_on(AsyncXdrRecordReader::new(Cursor::new(out)).collect());
+    let got: Vec<_> = got.into_iter().map(|r| r.unwrap()).collect();
+    assert_eq!(got, recs);
+}
+
+#[test]
+#[should_panic]
+fn async_write_bad_fragment_size() {
+    let _ = AsyncXdrRecordWriter::with_fragment_size(Cursor::new(Vec::new()), 0);
+}
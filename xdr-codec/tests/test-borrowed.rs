@@ -0,0 +1,96 @@
+extern crate xdr_codec;
+
+use std::borrow::Cow;
+
+use xdr_codec::{Pack, Opaque, Error, ErrorKind, Result, pack_to_vec};
+use xdr_codec::borrowed::{UnpackRef, unpack_ref, unpack_str_borrowed};
+
+#[derive(Debug, PartialEq)]
+struct Entry<'a> {
+    id: u64,
+    name: &'a str,
+    data: Opaque<'a>,
+    tags: Vec<&'a [u8]>,
+    owner: Option<Cow<'a, str>>,
+}
+
+impl<'a> UnpackRef<'a> for Entry<'a> {
+    fn unpack_ref(input: &mut &'a [u8]) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        Ok((Entry {
+            id: { let (v, fsz) = UnpackRef::unpack_ref(input)?; sz += fsz; v },
+            name: { let (v, fsz) = unpack_str_borrowed(input, Some(16))?; sz += fsz; v },
+            data: { let (v, fsz) = UnpackRef::unpack_ref(input)?; sz += fsz; v },
+            tags: { let (v, fsz) = UnpackRef::unpack_ref(input)?; sz += fsz; v },
+            owner: { let (v, fsz) = UnpackRef::unpack_ref(input)?; sz += fsz; v },
+        }, sz))
+    }
+}
+
+fn encode(id: u64, name: &str, data: &[u8], tags: &[&[u8]], owner: Option<&str>) -> Vec<u8> {
+    let mut out = Vec::new();
+    id.pack(&mut out).unwrap();
+    name.pack(&mut out).unwrap();
+    Opaque::borrowed(data).pack(&mut out).unwrap();
+    (tags.len() as u32).pack(&mut out).unwrap();
+    for t in tags {
+        Opaque::borrowed(t).pack(&mut out).unwrap();
+    }
+    owner.is_some().pack(&mut out).unwrap();
+    if let Some(o) = owner {
+        o.pack(&mut out).unwrap();
+    }
+    out
+}
+
+#[test]
+fn borrowed_struct() {
+    let mut buf = encode(1, "first", b"data", &[b"a", b"bc"], Some("me"));
+    buf.extend(encode(2, "second", b"", &[], None));
+    let range = buf.as_ptr() as usize..buf.as_ptr() as usize + buf.len();
+
+    let mut input = &buf[..];
+    let (e, sz) = Entry::unpack_ref(&mut input).unwrap();
+    assert_eq!(sz, 8 + 12 + 8 + 4 + 8 + 8 + 12);
+    assert_eq!(e, Entry {
+        id: 1,
+        name: "first",
+        data: Opaque::borrowed(b"data"),
+        tags: vec![b"a", b"bc"],
+        owner: Some(Cow::Borrowed("me")),
+    });
+
+    // Variable-length fields point into the buffer
+    assert!(range.contains(&(e.name.as_ptr() as usize)));
+    assert!(range.contains(&(e.data.as_ptr() as usize)));
+    assert!(range.contains(&(e.tags[1].as_ptr() as usize)));
+    match e.data.0 {
+        Cow::Borrowed(_) => (),
+        Cow::Owned(_) => panic!("data copied"),
+    }
+
+    let e: Entry = unpack_ref(&mut input).unwrap();
+    assert_eq!((e.id, e.name, e.tags.len(), e.owner), (2, "second", 0, None));
+    assert!(input.is_empty());
+}
+
+#[test]
+fn borrowed_errors() {
+    let buf = encode(1, "a name which is too long", b"", &[], None);
+    match Entry::unpack_ref(&mut &buf[..]) {
+        Err(Error(ErrorKind::InvalidLen(16), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let buf = pack_to_vec(&Opaque::borrowed(&[0xff, 0xfe])).unwrap();
+    match unpack_ref::<&str>(&mut &buf[..]) {
+        Err(Error(ErrorKind::InvalidUtf8(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(unpack_ref::<&[u8]>(&mut &buf[..]).unwrap(), &[0xff, 0xfe]);
+
+    match unpack_ref::<Vec<u32>>(&mut &[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 1][..]) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
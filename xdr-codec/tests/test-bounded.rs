@@ -0,0 +1,68 @@
+extern crate xdr_codec;
+
+use std::convert::TryFrom;
+use std::io::Cursor;
+
+use xdr_codec::{Error, ErrorKind, Skip, Unpack, pack_to_vec};
+use xdr_codec::bounded::{BoundedString, BoundedVec};
+
+type Name = BoundedString<5>;
+type Ids = BoundedVec<u32, 2>;
+
+#[test]
+fn bounded_construct() {
+    let name = Name::try_from("hello").unwrap();
+    assert_eq!(&*name, "hello");
+    assert_eq!(name.to_string(), "hello");
+    assert_eq!(String::from(name), "hello");
+    match Name::new(String::from("hello!")) {
+        Err(Error(ErrorKind::InvalidLen(5), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let mut ids = Ids::default();
+    ids.push(1).unwrap();
+    ids.push(2).unwrap();
+    match ids.push(3) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    ids[0] = 10;
+    assert_eq!(ids.as_slice(), &[10, 2]);
+    assert_eq!(ids.pop(), Some(2));
+    assert_eq!(Vec::from(ids), vec![10]);
+    assert!(Ids::try_from(vec![1, 2, 3]).is_err());
+}
+
+#[test]
+fn bounded_codec() {
+    let name = Name::try_from("abc").unwrap();
+    let ids = Ids::new(vec![7, 8]).unwrap();
+
+    let enc = pack_to_vec(&name).unwrap();
+    assert_eq!(enc, pack_to_vec("abc").unwrap());
+    assert_eq!(Name::unpack(&mut Cursor::new(&enc[..])).unwrap(), (name, 8));
+
+    let enc = pack_to_vec(&ids).unwrap();
+    assert_eq!(enc, pack_to_vec(&vec![7u32, 8]).unwrap());
+    assert_eq!(Ids::unpack(&mut Cursor::new(&enc[..])).unwrap(), (ids, 12));
+}
+
+#[test]
+fn bounded_unpack_limit() {
+    let enc = pack_to_vec("toolong").unwrap();
+    match Name::unpack(&mut Cursor::new(&enc[..])) {
+        Err(Error(ErrorKind::InvalidLen(5), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert!(Name::skip(&mut Cursor::new(&enc[..])).is_err());
+    assert_eq!(BoundedString::<7>::skip(&mut Cursor::new(&enc[..])).unwrap(), 12);
+
+    let enc = pack_to_vec(&vec![1u32, 2, 3]).unwrap();
+    match Ids::unpack(&mut Cursor::new(&enc[..])) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert!(Ids::skip(&mut Cursor::new(&enc[..])).is_err());
+    assert_eq!(BoundedVec::<u32, 3>::skip(&mut Cursor::new(&enc[..])).unwrap(), 16);
+}
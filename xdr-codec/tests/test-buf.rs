@@ -0,0 +1,102 @@
+#![cfg(feature = "bytes")]
+extern crate bytes;
+extern crate xdr_codec;
+
+use std::io;
+
+use bytes::{Buf, Bytes, BytesMut};
+use xdr_codec::{Error, ErrorKind, Opaque};
+use xdr_codec::buf::{pack_buf, unpack_buf, pack_opaque_buf, unpack_opaque_buf, pack_string_buf,
+                     unpack_string_buf, unpack_opaque_bytes};
+
+#[test]
+fn buf_values() {
+    let mut buf = BytesMut::new();
+
+    assert_eq!(pack_buf(&0x0102_0304u32, &mut buf).unwrap(), 4);
+    assert_eq!(pack_buf(&vec![1u32, 2], &mut buf).unwrap(), 12);
+    assert_eq!(pack_buf(&Some(-1i64), &mut buf).unwrap(), 12);
+    assert_eq!(buf.len(), 28);
+
+    let mut buf = buf.freeze();
+    assert_eq!(unpack_buf(&mut buf).unwrap(), (0x0102_0304u32, 4));
+    assert_eq!(unpack_buf(&mut buf).unwrap(), (vec![1u32, 2], 12));
+    assert_eq!(unpack_buf(&mut buf).unwrap(), (Some(-1i64), 12));
+    assert!(!buf.has_remaining());
+
+    match unpack_buf::<_, u32>(&mut buf) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn buf_opaque() {
+    let mut buf = Vec::new();
+
+    assert_eq!(pack_opaque_buf(&[1, 2, 3], None, &mut buf).unwrap(), 8);
+    assert_eq!(pack_string_buf("hello", Some(5), &mut buf).unwrap(), 12);
+    assert_eq!(buf, vec![0, 0, 0, 3, 1, 2, 3, 0,
+                         0, 0, 0, 5, b'h', b'e', b'l', b'l', b'o', 0, 0, 0]);
+
+    let mut input = &buf[..];
+    assert_eq!(unpack_opaque_buf(&mut input, Some(3)).unwrap(), (vec![1, 2, 3], 8));
+    assert_eq!(unpack_string_buf(&mut input, None).unwrap(), (String::from("hello"), 12));
+    assert!(input.is_empty());
+
+    // Encodings are the same as through the io adapters
+    let mut input = &buf[..];
+    let (v, sz) = unpack_buf::<_, Opaque>(&mut input).unwrap();
+    assert_eq!((&v[..], sz), (&[1, 2, 3][..], 8));
+}
+
+#[test]
+fn buf_opaque_bad() {
+    match pack_opaque_buf(&[1, 2, 3], Some(2), &mut Vec::new()) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let mut small = [0u8; 7];
+    match pack_opaque_buf(&[1, 2, 3], None, &mut &mut small[..]) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::WriteZero => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let enc = [0, 0, 0, 3, 1, 2, 3, 0];
+    match unpack_opaque_buf(&mut &enc[..], Some(2)) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match unpack_opaque_buf(&mut &enc[..7], None) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn bytes_opaque() {
+    let data = Bytes::from_static(b"hello");
+    let mut buf = BytesMut::new();
+
+    assert_eq!(pack_buf(&data, &mut buf).unwrap(), 12);
+    assert_eq!(pack_buf(&Bytes::new(), &mut buf).unwrap(), 4);
+    assert_eq!(&buf[..], &[0, 0, 0, 5, b'h', b'e', b'l', b'l', b'o', 0, 0, 0, 0, 0, 0, 0][..]);
+
+    let mut input = buf.freeze();
+    let base = input.as_ptr() as usize;
+    let (v, sz) = unpack_opaque_bytes(&mut input, Some(5)).unwrap();
+    assert_eq!((&v[..], sz), (&b"hello"[..], 12));
+    // Shares storage with the input rather than copying
+    assert_eq!(v.as_ptr() as usize, base + 4);
+
+    assert_eq!(unpack_opaque_bytes(&mut input, None).unwrap(), (Bytes::new(), 4));
+    assert!(!input.has_remaining());
+
+    let enc = [0, 0, 0, 3, 1, 2, 3, 0];
+    assert_eq!(unpack_buf::<_, Bytes>(&mut &enc[..]).unwrap(), (Bytes::from_static(&[1, 2, 3]), 8));
+    match unpack_opaque_bytes(&mut &enc[..6], None) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
@@ -0,0 +1,53 @@
+extern crate xdr_codec;
+
+use std::io;
+
+use xdr_codec::{Error, ErrorKind, Pack, Write, Result, unpack_complete};
+use xdr_codec::buffer::{frame_record, pack_to_slice, split_records};
+
+// No size hint, so only fails once it runs out of space
+struct Words(u32);
+
+impl<Out: Write> Pack<Out> for Words {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = 0;
+        for i in 0..self.0 {
+            sz += i.pack(out)?;
+        }
+        Ok(sz)
+    }
+}
+
+#[test]
+fn slice_pack() {
+    let mut buf = [0xff; 10];
+    assert_eq!(pack_to_slice("abc", &mut buf).unwrap(), 8);
+    assert_eq!(buf, [0, 0, 0, 3, b'a', b'b', b'c', 0, 0xff, 0xff]);
+
+    match pack_to_slice("abcdefg", &mut buf) {
+        Err(Error(ErrorKind::CapacityExceeded(12, 10), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(pack_to_slice(&Words(2), &mut buf).unwrap(), 8);
+    match pack_to_slice(&Words(3), &mut buf) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::WriteZero => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn buffer_records() {
+    let stream = [frame_record(b"abcdef", Some(4)), frame_record(b"", None)].concat();
+    assert_eq!(stream, vec![0, 0, 0, 4, b'a', b'b', b'c', b'd',  0x80, 0, 0, 2, b'e', b'f',
+                            0x80, 0, 0, 0]);
+    assert_eq!(split_records(&stream).unwrap(), vec![b"abcdef".to_vec(), vec![]]);
+    assert_eq!(split_records(&[]).unwrap(), Vec::<Vec<u8>>::new());
+
+    match split_records(&stream[..10]) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let recs = split_records(&frame_record(&[0, 0, 0, 7], None)).unwrap();
+    assert_eq!(unpack_complete::<u32>(&recs[0]).unwrap(), 7);
+}
@@ -0,0 +1,62 @@
+extern crate xdr_codec;
+
+use xdr_codec::{Error, ErrorKind, DecodeOptions, with_decode_options, pack_to_vec, verify_canonical};
+use xdr_codec::bounded::BoundedString;
+use xdr_codec::string::XdrString;
+
+#[test]
+fn canonical_ok() {
+    let enc = pack_to_vec(&Some(vec![true, false])).unwrap();
+    let v: Option<Vec<bool>> = verify_canonical(&enc).unwrap();
+    assert_eq!(v, Some(vec![true, false]));
+
+    let enc = pack_to_vec(&XdrString::from("abc")).unwrap();
+    let v: XdrString = verify_canonical(&enc).unwrap();
+    assert_eq!(v, XdrString::from("abc"));
+
+    let v: Vec<u32> = verify_canonical(&[0, 0, 0, 0]).unwrap();
+    assert!(v.is_empty());
+}
+
+#[test]
+fn canonical_padding() {
+    // Non-zero padding after a 3-byte string
+    match verify_canonical::<XdrString>(&[0, 0, 0, 3, b'a', b'b', b'c', 1]) {
+        Err(Error(ErrorKind::NonCanonical(7), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn canonical_trailing() {
+    match verify_canonical::<u32>(&[0, 0, 0, 1, 0, 0, 0, 0]) {
+        Err(Error(ErrorKind::NonCanonical(4), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn canonical_invalid() {
+    // Errors decoding are reported as they are
+    match verify_canonical::<bool>(&[0, 0, 0, 2]) {
+        Err(Error(ErrorKind::InvalidEnum(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match verify_canonical::<u64>(&[0, 0, 0, 1]) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn canonical_strict_strings() {
+    // Lossy decoding would re-encode differently, so it's not used
+    let bad = [0, 0, 0, 1, 0xff, 0, 0, 0];
+
+    with_decode_options(DecodeOptions::new().lossy_strings(true), || {
+        match verify_canonical::<BoundedString<4>>(&bad) {
+            Err(Error(ErrorKind::InvalidUtf8(_), _)) => (),
+            res => panic!("bad result {:?}", res),
+        }
+    });
+}
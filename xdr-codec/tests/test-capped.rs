@@ -0,0 +1,56 @@
+#![cfg(feature = "heapless")]
+extern crate xdr_codec;
+
+use xdr_codec::{DecodeOptions, Error, ErrorKind, Skip, pack_to_vec, unpack, with_decode_options};
+use xdr_codec::capped::{String, Vec, unpack_flex_capped, unpack_opaque_flex_capped, unpack_string_capped};
+
+#[test]
+fn capped_vec() {
+    let enc = pack_to_vec(&vec![1u32, 2, 3]).unwrap();
+
+    let v: Vec<u32, 4> = unpack(&mut &enc[..]).unwrap();
+    assert_eq!(v, [1, 2, 3]);
+    assert_eq!(pack_to_vec(&v).unwrap(), enc);
+    assert_eq!(<Vec<u32, 4>>::skip(&mut &enc[..]).unwrap(), 16);
+
+    match unpack::<_, Vec<u32, 2>>(&mut &enc[..]) {
+        Err(Error(ErrorKind::CapacityExceeded(3, 2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match unpack_flex_capped::<_, u32, 4>(&mut &enc[..], Some(2)) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let (v, sz) = unpack_opaque_flex_capped::<_, 8>(&mut &[0, 0, 0, 3,  1, 2, 3, 0][..], None).unwrap();
+    assert_eq!((&v[..], sz), (&[1, 2, 3][..], 8));
+    match unpack_opaque_flex_capped::<_, 8>(&mut &[0, 0, 0, 3,  1, 2][..], None) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn capped_string() {
+    let enc = pack_to_vec("hello").unwrap();
+
+    let s: String<8> = unpack(&mut &enc[..]).unwrap();
+    assert_eq!(s, "hello");
+    assert_eq!(pack_to_vec(&s).unwrap(), enc);
+
+    let bad = [0, 0, 0, 2,  b'a', 0xff, 0, 0];
+    match unpack_string_capped::<_, 4>(&mut &bad[..], None) {
+        Err(Error(ErrorKind::InvalidUtf8(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // U+FFFD takes three bytes, which is more than fit
+    with_decode_options(DecodeOptions::default().lossy_strings(true), || {
+        let (s, _) = unpack_string_capped::<_, 4>(&mut &bad[..], None).unwrap();
+        assert_eq!(s, "a\u{fffd}");
+        match unpack_string_capped::<_, 3>(&mut &bad[..], None) {
+            Err(Error(ErrorKind::CapacityExceeded(4, 3), _)) => (),
+            res => panic!("bad result {:?}", res),
+        }
+    });
+}
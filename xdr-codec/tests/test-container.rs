@@ -0,0 +1,97 @@
+extern crate xdr_codec;
+
+use std::io::Cursor;
+
+use xdr_codec::{Error, ErrorKind};
+use xdr_codec::container::{ContainerReader, ContainerWriter};
+use xdr_codec::string::XdrString;
+
+fn container() -> Vec<u8> {
+    let mut w = ContainerWriter::new(Vec::new()).unwrap();
+    assert!(w.is_empty());
+    assert_eq!(w.append(b"abcde").unwrap(), 0);
+    assert_eq!(w.pack(&XdrString::new(b"hello".to_vec())).unwrap(), 1);
+    assert_eq!(w.append(b"").unwrap(), 2);
+    assert_eq!(w.pack(&7u64).unwrap(), 3);
+    assert_eq!(w.len(), 4);
+    w.finish().unwrap()
+}
+
+#[test]
+fn layout() {
+    let file = container();
+    assert_eq!(file, [
+        b'X', b'D', b'R', b'C', 0, 0, 0, 1,
+        b'a', b'b', b'c', b'd', b'e', 0, 0, 0,
+        0, 0, 0, 5, b'h', b'e', b'l', b'l', b'o', 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 7,
+        // index
+        0, 0, 0, 0, 0, 0, 0, 8,   0, 0, 0, 0, 0, 0, 0, 5,
+        0, 0, 0, 0, 0, 0, 0, 16,  0, 0, 0, 0, 0, 0, 0, 12,
+        0, 0, 0, 0, 0, 0, 0, 28,  0, 0, 0, 0, 0, 0, 0, 0,
+        0, 0, 0, 0, 0, 0, 0, 28,  0, 0, 0, 0, 0, 0, 0, 8,
+        // trailer
+        0, 0, 0, 0, 0, 0, 0, 36,  0, 0, 0, 0, 0, 0, 0, 4,  b'X', b'D', b'R', b'I',
+    ][..]);
+}
+
+#[test]
+fn random_access() {
+    let mut r = ContainerReader::open(Cursor::new(container())).unwrap();
+    assert_eq!(r.len(), 4);
+
+    assert_eq!(r.unpack::<u64>(3).unwrap(), 7);
+    assert_eq!(r.read(0).unwrap(), b"abcde");
+    assert_eq!(r.unpack::<XdrString>(1).unwrap().as_bytes(), b"hello");
+    assert!(r.read(2).unwrap().is_empty());
+    assert_eq!(r.entry(1).unwrap(), (16, 12));
+
+    match r.unpack::<u32>(3) {
+        Err(Error(ErrorKind::TrailingData(4), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match r.read(4) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn empty() {
+    let file = ContainerWriter::new(Vec::new()).unwrap().finish().unwrap();
+    assert_eq!(file.len(), 28);
+    let r = ContainerReader::open(Cursor::new(file)).unwrap();
+    assert!(r.is_empty());
+}
+
+#[test]
+fn corrupt() {
+    let file = container();
+
+    let open = |data: &[u8]| ContainerReader::open(Cursor::new(data.to_vec())).map(|_| ());
+    let check = |res: xdr_codec::Result<()>| match res {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    };
+
+    // Unfinished
+    check(open(&file[..36]));
+    // Truncated
+    check(open(&file[..file.len() - 1]));
+    // Not a container
+    check(open(b"XDRX\0\0\0\x01"));
+    // Wrong version
+    let mut bad = file.clone();
+    bad[7] = 2;
+    check(open(&bad));
+
+    // Record running into the index
+    let mut bad = file.clone();
+    bad[36 + 15] = 29;
+    let mut r = ContainerReader::open(Cursor::new(bad)).unwrap();
+    match r.read(0) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(r.read(1).unwrap().len(), 12);
+}
@@ -0,0 +1,50 @@
+extern crate xdr_codec;
+
+use xdr_codec::{Error, ErrorKind, Read, Result, Unpack, UnpackWith, unpack_string};
+use xdr_codec::context::{unpack_array_with, unpack_flex_with, unpack_option_with, unpack_with};
+
+// Limits negotiated at connection setup
+struct Limits {
+    name: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct Named {
+    id: u32,
+    name: String,
+}
+
+impl<In: Read> UnpackWith<In, Limits> for Named {
+    fn unpack_with(input: &mut In, ctx: &Limits) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        Ok((Named {
+            id: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            name: { let (v, fsz) = unpack_string(input, Some(ctx.name))?; sz += fsz; v },
+        }, sz))
+    }
+}
+
+#[test]
+fn context_nested() {
+    let enc = [0, 0, 0, 1,
+               0, 0, 0, 1,  0, 0, 0, 3,  b'a', b'b', b'c', 0];
+
+    let v: Option<Box<Named>> = unpack_with(&mut &enc[..], &Limits { name: 3 }).unwrap();
+    assert_eq!(v, Some(Box::new(Named { id: 1, name: String::from("abc") })));
+
+    match unpack_option_with::<_, _, Named>(&mut &enc[..], &Limits { name: 2 }) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Context-free types ignore it
+    let (v, sz) = unpack_array_with::<_, _, u32>(&mut &enc[..], 3, &Limits { name: 0 }).unwrap();
+    assert_eq!((v, sz), (vec![1, 1, 3], 12));
+    let (v, sz) = <Vec<String>>::unpack_with(&mut &enc[4..], &()).unwrap();
+    assert_eq!((v, sz), (vec![String::from("abc")], 12));
+
+    match unpack_flex_with::<_, _, Named>(&mut &enc[..], Some(0), &Limits { name: 3 }) {
+        Err(Error(ErrorKind::InvalidLen(0), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
@@ -0,0 +1,94 @@
+extern crate xdr_codec;
+
+use xdr_codec::{Error, ErrorKind, pack_to_vec};
+use xdr_codec::decoder::{RecordDecoder, ValueDecoder};
+
+#[test]
+fn records_bytewise() {
+    let stream = vec![ 0, 0, 0, 2,  1, 2,  0x80, 0, 0, 1,  3,
+                       0x80, 0, 0, 0,
+                       0, 0, 0, 0,  0x80, 0, 0, 2,  4, 5 ];
+    let mut dec = RecordDecoder::new();
+    let mut records = Vec::new();
+
+    for b in &stream {
+        records.extend(dec.feed(&[*b]).unwrap());
+        if records.len() == 1 {
+            assert!(dec.is_idle() || !records[0].is_empty());
+        }
+    }
+
+    assert_eq!(records, vec![vec![1, 2, 3], vec![], vec![4, 5]]);
+    assert!(dec.is_idle());
+}
+
+#[test]
+fn records_chunked() {
+    let stream = vec![ 0x80, 0, 0, 3,  1, 2, 3,  0x80, 0, 0, 1,  4,  0x80, 0 ];
+    let mut dec = RecordDecoder::new();
+
+    assert_eq!(dec.feed(&stream).unwrap(), vec![vec![1, 2, 3], vec![4]]);
+    assert!(!dec.is_idle());
+    assert_eq!(dec.feed(&[0, 0]).unwrap(), vec![Vec::<u8>::new()]);
+    assert_eq!(dec.feed(&[]).unwrap(), Vec::<Vec<u8>>::new());
+    assert!(dec.is_idle());
+}
+
+#[test]
+fn records_limits() {
+    let mut dec = RecordDecoder::with_limits(Some(2), None);
+    match dec.feed(&[0x80, 0, 0, 3]) {
+        Err(Error(ErrorKind::FragmentTooLarge(3, 2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let mut dec = RecordDecoder::with_limits(None, Some(3));
+    assert_eq!(dec.feed(&[0, 0, 0, 2,  1, 2]).unwrap(), Vec::<Vec<u8>>::new());
+    match dec.feed(&[0x80, 0, 0, 2]) {
+        Err(Error(ErrorKind::RecordTooLarge(4, 3), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Records before the bad header are still returned, then the error
+    let mut dec = RecordDecoder::with_limits(Some(2), None);
+    assert_eq!(dec.feed(&[0x80, 0, 0, 1,  1,  0x80, 0, 0, 3,  1, 2, 3]).unwrap(), vec![vec![1]]);
+    for _ in 0..2 {
+        match dec.feed(&[]) {
+            Err(Error(ErrorKind::FragmentTooLarge(3, 2), _)) => (),
+            res => panic!("bad result {:?}", res),
+        }
+    }
+}
+
+#[test]
+fn values() {
+    let mut stream = pack_to_vec("hello").unwrap();
+    stream.extend(pack_to_vec("").unwrap());
+    stream.extend(pack_to_vec("world!").unwrap());
+
+    let mut dec = ValueDecoder::<String>::new();
+    let mut got = Vec::new();
+    for chunk in stream.chunks(3) {
+        got.extend(dec.feed(chunk).unwrap());
+    }
+    assert_eq!(got, vec!["hello", "", "world!"]);
+    assert_eq!(dec.pending(), 0);
+
+    assert_eq!(dec.feed(&[0, 0, 0, 1, 0xff]).unwrap(), Vec::<String>::new());
+    assert_eq!(dec.pending(), 5);
+    match dec.feed(&[0, 0, 0]) {
+        Err(Error(ErrorKind::InvalidUtf8(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // A value which never completes can only be buffered up to the limit
+    let mut dec = ValueDecoder::<String>::with_limit(16);
+    assert_eq!(dec.feed(&[0, 0, 0, 3, b'a', b'b', b'c', 0,  0, 0, 0x10, 0]).unwrap(), ["abc"]);
+    assert_eq!(dec.feed(&[b'x'; 12]).unwrap(), Vec::<String>::new());
+    for _ in 0..2 {
+        match dec.feed(b"x") {
+            Err(Error(ErrorKind::CapacityExceeded(17, 16), _)) => (),
+            res => panic!("bad result {:?}", res),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+#![cfg(feature="deflate")]
+extern crate xdr_codec;
+
+use std::io::{self, Cursor, Write};
+
+use xdr_codec::{Error, ErrorKind};
+use xdr_codec::record::{XdrRecordReader, XdrRecordWriter};
+use xdr_codec::deflate::{DeflateRecordReader, DeflateRecordWriter};
+
+fn compressed(recs: &[Vec<u8>]) -> Vec<u8> {
+    let mut dw = DeflateRecordWriter::new(XdrRecordWriter::with_fragment_size(Vec::new(), 16));
+    for rec in recs {
+        dw.write_all(rec).unwrap();
+        dw.end_record().unwrap();
+    }
+    dw.into_inner().finish().unwrap()
+}
+
+#[test]
+fn deflate_roundtrip() {
+    let recs = vec![ vec![0x55; 1000], vec![], (0..200).map(|i| i as u8).collect() ];
+    let enc = compressed(&recs);
+
+    // Framing is unchanged, and the compressible record shrank
+    let raw: Vec<_> = XdrRecordReader::new(Cursor::new(&enc[..])).records().map(|r| r.unwrap()).collect();
+    assert_eq!(raw.len(), 3);
+    assert!(raw[0].len() < 100);
+
+    let rd = DeflateRecordReader::new(XdrRecordReader::new(Cursor::new(&enc[..])));
+    let got: Vec<_> = rd.map(|r| r.unwrap()).collect();
+    assert_eq!(got, recs);
+}
+
+#[test]
+fn deflate_finish() {
+    let mut dw = DeflateRecordWriter::with_level(XdrRecordWriter::new(Vec::new()), 9);
+    xdr_codec::pack(&1234u32, &mut dw).unwrap();
+    let enc = dw.finish().unwrap();
+
+    let mut rd = DeflateRecordReader::new(XdrRecordReader::new(Cursor::new(enc)));
+    assert_eq!(rd.next().unwrap().unwrap(), vec![0, 0, 4, 210]);
+    assert!(rd.next().is_none());
+
+    // Nothing written gives a single empty record, as for a plain record writer
+    let enc = DeflateRecordWriter::new(XdrRecordWriter::new(Vec::new())).finish().unwrap();
+    let rd = DeflateRecordReader::new(XdrRecordReader::new(Cursor::new(enc)));
+    assert_eq!(rd.map(|r| r.unwrap()).collect::<Vec<_>>(), vec![Vec::<u8>::new()]);
+
+    // Dropping ends the record too
+    let mut buf = Vec::new();
+    {
+        let mut dw = DeflateRecordWriter::new(XdrRecordWriter::new(&mut buf));
+        dw.write_all(b"hello").unwrap();
+    }
+    let rd = DeflateRecordReader::new(XdrRecordReader::new(Cursor::new(buf)));
+    assert_eq!(rd.map(|r| r.unwrap()).collect::<Vec<_>>(), vec![b"hello".to_vec()]);
+}
+
+#[test]
+fn deflate_limit() {
+    let enc = compressed(&[vec![0; 100], vec![0; 1000]]);
+    let mut rd = DeflateRecordReader::new(XdrRecordReader::new(Cursor::new(enc)));
+    rd.set_max_record(Some(500));
+
+    assert_eq!(rd.next().unwrap().unwrap(), vec![0; 100]);
+    let err = rd.next().unwrap().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    match err.get_ref().and_then(|e| e.downcast_ref::<Error>()) {
+        Some(&Error(ErrorKind::RecordTooLarge(501, 500), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert!(rd.next().is_none());
+}
+
+#[test]
+fn deflate_corrupt() {
+    // Not a valid deflate stream (reserved block type)
+    let enc = vec![0x80, 0, 0, 2,  0xff, 0xff];
+    let mut rd = DeflateRecordReader::new(XdrRecordReader::new(Cursor::new(enc)));
+    assert!(rd.next().unwrap().is_err());
+    assert!(rd.next().is_none());
+}
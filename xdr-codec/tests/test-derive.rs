@@ -0,0 +1,205 @@
+#![cfg(feature="derive")]
+#[macro_use] extern crate xdr_codec;
+
+use std::io::Cursor;
+
+use xdr_codec::{Error, ErrorKind, Pack, PackedFixedSize, Unpack, pack_to_vec};
+
+#[derive(Debug, PartialEq, XdrPack, XdrUnpack, XdrFixedSize, XdrReflect)]
+struct Inner(u32, bool);
+
+#[derive(Debug, PartialEq, XdrPack, XdrUnpack, XdrReflect)]
+struct Message {
+    xid: u32,
+    cookie: i64,
+    inner: Inner,
+    flags: Option<u32>,
+    #[xdr(max = 8)]
+    name: String,
+    #[xdr(max = 3)]
+    data: Vec<u32>,
+    #[xdr(opaque)]
+    blob: Vec<u8>,
+    #[xdr(opaque, max = 4)]
+    small: Vec<u8>,
+    #[xdr(opaque)]
+    tag: [u8; 3],
+    rest: Vec<i32>,
+}
+
+#[derive(Debug, PartialEq, XdrPack, XdrUnpack)]
+struct Unit;
+
+#[derive(Debug, PartialEq, XdrPack, XdrUnpack, XdrFixedSize)]
+struct Pair<A, B> {
+    a: A,
+    b: B,
+}
+
+fn message() -> Message {
+    Message {
+        xid: 7,
+        cookie: -2,
+        inner: Inner(1, true),
+        flags: Some(3),
+        name: String::from("abc"),
+        data: vec![1, 2],
+        blob: vec![9],
+        small: vec![1, 2, 3, 4],
+        tag: [b'x', b'y', b'z'],
+        rest: vec![],
+    }
+}
+
+#[test]
+fn derive_encoding() {
+    let enc = pack_to_vec(&message()).unwrap();
+
+    assert_eq!(enc, vec![0, 0, 0, 7,
+                         0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+                         0, 0, 0, 1,  0, 0, 0, 1,
+                         0, 0, 0, 1,  0, 0, 0, 3,
+                         0, 0, 0, 3,  b'a', b'b', b'c', 0,
+                         0, 0, 0, 2,  0, 0, 0, 1,  0, 0, 0, 2,
+                         0, 0, 0, 1,  9, 0, 0, 0,
+                         0, 0, 0, 4,  1, 2, 3, 4,
+                         b'x', b'y', b'z', 0,
+                         0, 0, 0, 0]);
+}
+
+#[test]
+fn derive_roundtrip() {
+    let msg = message();
+    let enc = pack_to_vec(&msg).unwrap();
+
+    let mut cur = Cursor::new(&enc[..]);
+    let (got, sz) = Message::unpack(&mut cur).unwrap();
+    assert_eq!(got, msg);
+    assert_eq!(sz, enc.len());
+
+    let mut buf = Vec::new();
+    assert_eq!(Unit.pack(&mut buf).unwrap(), 0);
+    assert!(buf.is_empty());
+    assert_eq!(Unit::unpack(&mut Cursor::new(&buf[..])).unwrap(), (Unit, 0));
+
+    let pair = Pair { a: 1u32, b: Inner(2, false) };
+    let enc = pack_to_vec(&pair).unwrap();
+    assert_eq!(enc, vec![0, 0, 0, 1,  0, 0, 0, 2,  0, 0, 0, 0]);
+    assert_eq!(Pair::<u32, Inner>::unpack(&mut Cursor::new(&enc[..])).unwrap(), (pair, 12));
+}
+
+#[test]
+fn derive_limits() {
+    let mut msg = message();
+    msg.name = String::from("too long for it");
+    match pack_to_vec(&msg) {
+        Err(Error(ErrorKind::InvalidLen(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let mut msg = message();
+    msg.small = vec![0; 5];
+    let mut enc = Vec::new();
+    match msg.pack(&mut enc) {
+        Err(Error(ErrorKind::InvalidLen(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // An oversized flex array is rejected on decode too
+    let mut enc = pack_to_vec(&message()).unwrap();
+    enc[36..40].copy_from_slice(&[0, 0, 0, 4]);
+    match Message::unpack(&mut Cursor::new(&enc[..])) {
+        Err(Error(ErrorKind::InvalidLen(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+// Version 2 of a struct which gained two fields
+#[derive(Debug, PartialEq, XdrPack, XdrUnpack)]
+struct Versioned {
+    id: u32,
+    #[xdr(trailing)]
+    flags: Option<u32>,
+    #[xdr(trailing)]
+    extra: Vec<u32>,
+}
+
+#[test]
+fn derive_trailing() {
+    let v = Versioned { id: 1, flags: Some(2), extra: vec![5] };
+    let enc = pack_to_vec(&v).unwrap();
+    assert_eq!(enc, vec![0, 0, 0, 1,  0, 0, 0, 1,  0, 0, 0, 2,  0, 0, 0, 1,  0, 0, 0, 5]);
+    assert_eq!(Versioned::unpack(&mut &enc[..]).unwrap(), (v, 20));
+
+    // Version 1 encoding, and one with only the first new field
+    let old = Versioned { id: 1, flags: None, extra: vec![] };
+    assert_eq!(Versioned::unpack(&mut &enc[..4]).unwrap(), (old, 4));
+    let mid = Versioned { id: 1, flags: Some(2), extra: vec![] };
+    assert_eq!(Versioned::unpack(&mut &enc[..12]).unwrap(), (mid, 12));
+
+    // A partial field is still an error
+    match Versioned::unpack(&mut &enc[..14]) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[derive(Debug, PartialEq, XdrPack, XdrUnpack, XdrFixedSize)]
+struct Header {
+    xid: u32,
+    len: u64,
+    inner: Inner,
+    #[xdr(opaque)]
+    verf: [u8; 5],
+}
+
+#[test]
+fn derive_fixed_size() {
+    assert_eq!(Header::SIZE, 4 + 8 + 8 + 8);
+    assert_eq!(Pair::<u32, ()>::SIZE, 4);
+
+    let hdr = Header { xid: 1, len: 2, inner: Inner(3, true), verf: [1, 2, 3, 4, 5] };
+    let mut buf = [0; Header::SIZE];
+    assert_eq!(hdr.pack(&mut &mut buf[..]).unwrap(), Header::SIZE);
+    assert_eq!(Header::unpack(&mut &buf[..]).unwrap(), (hdr, Header::SIZE));
+}
+
+// Only used for its description
+#[allow(dead_code)]
+#[derive(XdrReflect)]
+struct Node {
+    value: i32,
+    next: Option<Box<Node>>,
+}
+
+#[test]
+fn derive_reflect() {
+    use xdr_codec::reflect::spec_for;
+
+    assert_eq!(spec_for::<Message>().unwrap(), "\
+struct Inner {
+    unsigned int f0;
+    bool f1;
+};
+
+struct Message {
+    unsigned int xid;
+    hyper cookie;
+    Inner inner;
+    unsigned int *flags;
+    string name<8>;
+    unsigned int data<3>;
+    opaque blob<>;
+    opaque small<4>;
+    opaque tag[3];
+    int rest<>;
+};
+");
+
+    assert_eq!(spec_for::<Vec<Node>>().unwrap(), "\
+struct Node {
+    int value;
+    Node *next;
+};
+");
+}
@@ -0,0 +1,60 @@
+extern crate xdr_codec;
+
+use xdr_codec::{Error, ErrorKind, Opaque, pack_to_vec};
+use xdr_codec::dump::{Dump, DumpReader};
+
+#[test]
+fn dump_values() {
+    let mut d = Dump::new();
+    d.field("ok", &true).unwrap()
+     .field("data", &Opaque::borrowed(b"hello")).unwrap()
+     .field("none", &()).unwrap()
+     .field("list", &vec![1u32; 40]).unwrap();
+
+    let text = d.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+
+    assert_eq!(lines[0], "00000000  0x00000001  ....  ok = true");
+    assert_eq!(lines[1], "00000004  0x00000005  ....  data = Opaque([104, 101, 108, 108, 111])");
+    assert_eq!(lines[2], "00000008  0x68656c6c  hell");
+    assert_eq!(lines[3], "0000000c  0x6f000000  o...");
+    assert_eq!(lines[4], "00000010  0x00000028  ...(  none = (), list = [1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, \
+                          1, 1, 1, 1, 1, 1, 1,...");
+    assert_eq!(lines[5], "00000014  0x00000001  ....");
+    assert_eq!(lines.len(), 5 + 40);
+    assert_eq!(d.bytes().len(), 4 * lines.len());
+}
+
+#[test]
+fn dump_read() {
+    let mut enc = pack_to_vec(&7u32).unwrap();
+    enc.extend(pack_to_vec("hi").unwrap());
+    enc.extend_from_slice(&[1, 2]);
+
+    let mut r = DumpReader::new(&enc);
+    assert_eq!(r.field::<u32>("proc").unwrap(), 7);
+    let s: Opaque = r.group("args", |r| r.field("name")).unwrap();
+    assert_eq!(&s[..], b"hi");
+
+    // Not enough for another word; nothing is consumed
+    match r.field::<u32>("extra") {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(r.remains(), &[1, 2]);
+
+    assert_eq!(r.finish().to_string(),
+               "00000000  0x00000007  ....  proc = 7\n\
+                00000004  0x00000002  ....  args.name = Opaque([104, 105])\n\
+                00000008  0x68690000  hi..\n\
+                0000000c  0x0102      ..\n");
+}
+
+#[test]
+fn dump_empty_tail() {
+    let mut d = Dump::new();
+    d.field("a", &1u32).unwrap().field("b", &()).unwrap();
+
+    assert_eq!(d.to_string(), "00000000  0x00000001  ....  a = 1\n00000004  b = ()\n");
+    assert_eq!(Dump::new().to_string(), "");
+}
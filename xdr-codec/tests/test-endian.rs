@@ -0,0 +1,77 @@
+#![cfg(feature = "little_endian")]
+extern crate xdr_codec;
+
+use std::io::Write;
+
+use xdr_codec::{Opaque, Pack, Result, Unpack, pack_to_vec, unpack};
+use xdr_codec::endian::{Be, Le};
+use xdr_codec::skip::skip;
+
+#[test]
+fn primitives() {
+    let mut v = Vec::new();
+    Le(0x01020304u32).pack(&mut v).unwrap();
+    Le(-2i32).pack(&mut v).unwrap();
+    Le(0x0102030405060708u64).pack(&mut v).unwrap();
+    Le(true).pack(&mut v).unwrap();
+    Le(1.0f32).pack(&mut v).unwrap();
+    Le(-1.0f64).pack(&mut v).unwrap();
+    assert_eq!(v, vec![4, 3, 2, 1,
+                       0xfe, 0xff, 0xff, 0xff,
+                       8, 7, 6, 5, 4, 3, 2, 1,
+                       1, 0, 0, 0,
+                       0, 0, 0x80, 0x3f,
+                       0, 0, 0, 0, 0, 0, 0xf0, 0xbf]);
+
+    let mut input = &v[..];
+    assert_eq!(Le::<u32>::unpack(&mut input).unwrap(), (Le(0x01020304), 4));
+    assert_eq!(Le::<i32>::unpack(&mut input).unwrap(), (Le(-2), 4));
+    assert_eq!(Le::<u64>::unpack(&mut input).unwrap(), (Le(0x0102030405060708), 8));
+    assert_eq!(Le::<bool>::unpack(&mut input).unwrap(), (Le(true), 4));
+    assert_eq!(Le::<f32>::unpack(&mut input).unwrap(), (Le(1.0), 4));
+    assert_eq!(Le::<f64>::unpack(&mut input).unwrap(), (Le(-1.0), 8));
+
+    // Big-endian when not wrapped
+    assert_eq!(pack_to_vec(&0x01020304u32).unwrap(), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn arrays_and_strings() {
+    let val: Vec<u32> = (0..2000).collect();
+    let v = pack_to_vec(&Le(&val)).unwrap();
+    assert_eq!(&v[..12], &[208, 7, 0, 0,  0, 0, 0, 0,  1, 0, 0, 0]);
+    assert_eq!(Le::<Vec<u32>>::unpack(&mut &v[..]).unwrap(), (Le(val), v.len()));
+
+    let v = pack_to_vec(&Le("abcde")).unwrap();
+    assert_eq!(v, vec![5, 0, 0, 0,  b'a', b'b', b'c', b'd',  b'e', 0, 0, 0]);
+    assert_eq!(unpack::<_, Le<String>>(&mut &v[..]).unwrap(), Le("abcde".to_string()));
+    assert_eq!(skip::<_, Le<String>>(&mut &v[..]).unwrap(), 12);
+
+    // Large opaque data is packed with a separately encoded length
+    let data = vec![0x55; 5000];
+    let v = pack_to_vec(&Le(Opaque::borrowed(&data))).unwrap();
+    assert_eq!(&v[..4], &[0x88, 0x13, 0, 0]);
+}
+
+// A header with a big-endian magic number and little-endian contents
+struct Header {
+    magic: Be<u32>,
+    len: u32,
+}
+
+impl<Out: Write> Pack<Out> for Header {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.magic.pack(out)? + self.len.pack(out)?)
+    }
+}
+
+#[test]
+fn nesting() {
+    let hdr = Header { magic: Be(0x01020304), len: 5 };
+    assert_eq!(pack_to_vec(&Le(&hdr)).unwrap(), [1, 2, 3, 4,  5, 0, 0, 0]);
+    assert_eq!(pack_to_vec(&hdr).unwrap(), [1, 2, 3, 4,  0, 0, 0, 5]);
+
+    // The order goes with the value, not the thread packing it
+    let v = std::thread::spawn(|| pack_to_vec(&Le(7u32)).unwrap()).join().unwrap();
+    assert_eq!(v, [7, 0, 0, 0]);
+}
@@ -0,0 +1,88 @@
+#[macro_use] extern crate xdr_codec;
+
+use std::convert::TryFrom;
+use std::io::Cursor;
+
+use xdr_codec::{Error, ErrorKind, Skip, Unpack, XdrEnum, pack_to_vec};
+use xdr_codec::enums::Lenient;
+
+const BASE: i32 = 10;
+
+xdr_enum! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Colour {
+        Red = 1,
+        Green = 2,
+        /// Documented variant
+        Blue = 4,
+    }
+}
+
+// Not Copy, with values from constants and a negative value
+xdr_enum! {
+    #[derive(Debug, PartialEq)]
+    #[repr(i32)]
+    enum Status {
+        Ok = 0,
+        Moved = BASE + 1,
+        Failed = -1
+    }
+}
+
+#[test]
+fn enum_convert() {
+    assert_eq!(Colour::Blue.to_i32(), 4);
+    assert_eq!(i32::from(Colour::Green), 2);
+    assert_eq!(Colour::try_from(1).unwrap(), Colour::Red);
+    assert_eq!(Colour::from_i32(4).unwrap(), Colour::Blue);
+    match Colour::try_from(3) {
+        Err(Error(ErrorKind::InvalidEnum(3), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    assert_eq!(Status::Moved.to_i32(), 11);
+    assert_eq!(i32::from(Status::Failed), -1);
+    assert_eq!(Status::try_from(11).unwrap(), Status::Moved);
+}
+
+#[test]
+fn enum_codec() {
+    assert_eq!(pack_to_vec(&Colour::Blue).unwrap(), vec![0, 0, 0, 4]);
+    assert_eq!(pack_to_vec(&Status::Failed).unwrap(), vec![0xff, 0xff, 0xff, 0xff]);
+
+    let mut cur = Cursor::new(vec![0, 0, 0, 2,  0, 0, 0, 11,  0, 0, 0, 3]);
+    assert_eq!(Colour::unpack(&mut cur).unwrap(), (Colour::Green, 4));
+    assert_eq!(Status::unpack(&mut cur).unwrap(), (Status::Moved, 4));
+    match Colour::unpack(&mut cur) {
+        Err(Error(ErrorKind::InvalidEnum(3), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let mut cur = Cursor::new(vec![0, 0, 0, 1,  0, 0, 0, 9]);
+    assert_eq!(Colour::skip(&mut cur).unwrap(), 4);
+    assert!(Colour::skip(&mut cur).is_err());
+}
+
+#[test]
+fn lenient() {
+    let mut cur = Cursor::new(vec![0, 0, 0, 4,  0, 0, 0, 3,  0, 0, 0, 9]);
+    let (v, sz) = Lenient::<Colour>::unpack(&mut cur).unwrap();
+    assert_eq!((v, sz), (Lenient::Known(Colour::Blue), 4));
+    assert!(v.is_known());
+    assert_eq!(v.into_known().unwrap(), Colour::Blue);
+
+    let (v, _) = Lenient::<Colour>::unpack(&mut cur).unwrap();
+    assert_eq!(v, Lenient::Unknown(3));
+    assert_eq!(v.known(), None);
+    match v.into_known() {
+        Err(Error(ErrorKind::InvalidEnum(3), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(Lenient::<Colour>::skip(&mut cur).unwrap(), 4);
+
+    // Unknown values are packed unchanged
+    let v = vec![Lenient::from(Colour::Green), Lenient::Unknown(-5)];
+    assert_eq!(pack_to_vec(&v).unwrap(), vec![0, 0, 0, 2,  0, 0, 0, 2,  0xff, 0xff, 0xff, 0xfb]);
+    assert_eq!(v[1].to_i32(), -5);
+    assert_eq!(Lenient::<Status>::from_i32(11).unwrap(), Lenient::Known(Status::Moved));
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "ffi")]
+extern crate xdr_codec;
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
+
+use xdr_codec::ffi::*;
+
+unsafe fn data(enc: *const XdrEncoder) -> Vec<u8> {
+    let mut len = 0;
+    let p = xdr_encoder_data(enc, &mut len);
+    slice::from_raw_parts(p, len).to_vec()
+}
+
+#[test]
+fn ffi_roundtrip() {
+    unsafe {
+        let enc = xdr_encoder_new();
+        assert_eq!(xdr_pack_int(enc, -1), XDR_OK);
+        assert_eq!(xdr_pack_uhyper(enc, 2), XDR_OK);
+        assert_eq!(xdr_pack_bool(enc, true), XDR_OK);
+        assert_eq!(xdr_pack_double(enc, 1.0), XDR_OK);
+        assert_eq!(xdr_pack_opaque_fixed(enc, [1, 2, 3].as_ptr(), 3), XDR_OK);
+        assert_eq!(xdr_pack_opaque(enc, [4, 5].as_ptr(), 2, XDR_NOMAX), XDR_OK);
+        assert_eq!(xdr_pack_string(enc, b"hello\0".as_ptr() as *const c_char, 8), XDR_OK);
+
+        // Failures don't leave anything behind
+        assert_eq!(xdr_pack_string(enc, b"toolong\0".as_ptr() as *const c_char, 3), XDR_ERR_LEN);
+        assert_eq!(xdr_pack_opaque(enc, ptr::null(), 2, XDR_NOMAX), XDR_ERR_NULL);
+
+        let enc_data = data(enc);
+        assert_eq!(enc_data, vec![0xff, 0xff, 0xff, 0xff,  0, 0, 0, 0, 0, 0, 0, 2,  0, 0, 0, 1,
+                                  0x3f, 0xf0, 0, 0, 0, 0, 0, 0,  1, 2, 3, 0,  0, 0, 0, 2, 4, 5, 0, 0,
+                                  0, 0, 0, 5, b'h', b'e', b'l', b'l', b'o', 0, 0, 0]);
+
+        let dec = xdr_decoder_new(enc_data.as_ptr(), enc_data.len());
+        let (mut i, mut h, mut b, mut d) = (0, 0, false, 0.0);
+        assert_eq!(xdr_unpack_int(dec, &mut i), XDR_OK);
+        assert_eq!(xdr_unpack_uhyper(dec, &mut h), XDR_OK);
+        assert_eq!(xdr_unpack_bool(dec, &mut b), XDR_OK);
+        assert_eq!(xdr_unpack_double(dec, &mut d), XDR_OK);
+        assert_eq!((i, h, b, d), (-1, 2, true, 1.0));
+
+        let mut fixed = [0; 3];
+        assert_eq!(xdr_unpack_opaque_fixed(dec, fixed.as_mut_ptr(), 3), XDR_OK);
+        assert_eq!(fixed, [1, 2, 3]);
+
+        // Too big for the buffer, and the position isn't moved
+        let (mut buf, mut len) = ([0; 8], 0);
+        assert_eq!(xdr_unpack_opaque(dec, buf.as_mut_ptr(), 1, &mut len), XDR_ERR_LEN);
+        assert_eq!(xdr_unpack_opaque(dec, buf.as_mut_ptr(), 8, &mut len), XDR_OK);
+        assert_eq!(&buf[..len], &[4, 5]);
+
+        let mut s = [0 as c_char; 6];
+        assert_eq!(xdr_unpack_string(dec, s.as_mut_ptr(), 5, &mut len), XDR_ERR_LEN);
+        assert_eq!(xdr_unpack_string(dec, s.as_mut_ptr(), 6, &mut len), XDR_OK);
+        assert_eq!((CStr::from_ptr(s.as_ptr()).to_bytes(), len), (&b"hello"[..], 5));
+
+        assert_eq!(xdr_decoder_remaining(dec), 0);
+        assert_eq!(xdr_unpack_int(dec, &mut i), XDR_ERR_SHORT);
+        xdr_decoder_free(dec);
+
+        let bad = [0, 0, 0, 2];
+        let dec = xdr_decoder_new(bad.as_ptr(), bad.len());
+        assert_eq!(xdr_unpack_bool(dec, &mut b), XDR_ERR_INVALID);
+        assert_eq!(xdr_decoder_remaining(dec), 4);
+        xdr_decoder_free(dec);
+
+        xdr_encoder_free(enc);
+        assert_eq!(CStr::from_ptr(xdr_strerror(XDR_ERR_LEN)).to_str().unwrap(), "invalid length");
+    }
+}
+
+#[test]
+fn ffi_records() {
+    unsafe {
+        let enc = xdr_encoder_new();
+        assert_eq!(xdr_record_frame(enc, b"abcdef".as_ptr(), 6, 4), XDR_OK);
+        assert_eq!(xdr_record_frame(enc, ptr::null(), 0, 0), XDR_OK);
+        let framed = data(enc);
+        assert_eq!(framed, vec![0, 0, 0, 4, b'a', b'b', b'c', b'd',  0x80, 0, 0, 2, b'e', b'f',
+                                0x80, 0, 0, 0]);
+
+        let rd = xdr_record_decoder_new(XDR_NOMAX, XDR_NOMAX);
+        let (mut p, mut len) = (ptr::null(), 0);
+        assert_eq!(xdr_record_decoder_feed(rd, framed.as_ptr(), 9), XDR_OK);
+        assert_eq!(xdr_record_decoder_next(rd, &mut p, &mut len), XDR_EMPTY);
+        assert_eq!(xdr_record_decoder_feed(rd, framed[9..].as_ptr(), framed.len() - 9), XDR_OK);
+        assert_eq!(xdr_record_decoder_next(rd, &mut p, &mut len), XDR_OK);
+        assert_eq!(slice::from_raw_parts(p, len), b"abcdef");
+        assert_eq!(xdr_record_decoder_next(rd, &mut p, &mut len), XDR_OK);
+        assert_eq!(len, 0);
+        assert_eq!(xdr_record_decoder_next(rd, &mut p, &mut len), XDR_EMPTY);
+        xdr_record_decoder_free(rd);
+
+        let rd = xdr_record_decoder_new(2, XDR_NOMAX);
+        assert_eq!(xdr_record_decoder_feed(rd, framed.as_ptr(), framed.len()), XDR_ERR_FRAMING);
+        xdr_record_decoder_free(rd);
+
+        xdr_encoder_free(enc);
+
+        // A huge fragment size doesn't allocate more than the record needs
+        let enc = xdr_encoder_new();
+        assert_eq!(xdr_record_frame(enc, b"abc".as_ptr(), 3, i32::MAX as usize), XDR_OK);
+        assert_eq!(data(enc), vec![0x80, 0, 0, 3, b'a', b'b', b'c']);
+        xdr_encoder_free(enc);
+    }
+}
@@ -0,0 +1,64 @@
+extern crate xdr_codec;
+
+use std::convert::TryFrom;
+use std::io::Cursor;
+
+use xdr_codec::{Error, ErrorKind, PackedFixedSize, Skip, Unpack, pack_to_vec, unpack_fixed_array};
+use xdr_codec::fixed::FixedOpaque;
+
+#[test]
+fn fixed_construct() {
+    let fh = FixedOpaque::from([1, 2, 3]);
+    assert_eq!(fh.as_bytes(), &[1, 2, 3]);
+    assert_eq!(FixedOpaque::<3>::try_from(&[1u8, 2, 3][..]).unwrap(), fh);
+    match FixedOpaque::<3>::from_slice(&[1, 2]) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(FixedOpaque::<40>::default().as_bytes(), &[0; 40][..]);
+
+    let mut v = FixedOpaque::<2>::default();
+    v[1] = 9;
+    assert_eq!(<[u8; 2]>::from(v), [0, 9]);
+}
+
+#[test]
+fn fixed_codec() {
+    // No length, and padded
+    let fh = FixedOpaque::new([1, 2, 3, 4, 5]);
+    let enc = pack_to_vec(&fh).unwrap();
+    assert_eq!(enc, vec![1, 2, 3, 4,  5, 0, 0, 0]);
+    assert_eq!(FixedOpaque::<5>::unpack(&mut Cursor::new(&enc[..])).unwrap(), (fh, 8));
+    assert_eq!(FixedOpaque::<5>::SIZE, 8);
+
+    let verf = FixedOpaque::new([0xaa; 8]);
+    let enc = pack_to_vec(&verf).unwrap();
+    assert_eq!(enc.len(), 8);
+    let mut cur = Cursor::new(&enc[..]);
+    assert_eq!(FixedOpaque::<8>::unpack(&mut cur).unwrap(), (verf, 8));
+
+    let mut cur = Cursor::new(vec![1, 2, 3, 4, 5, 0, 0, 0,  6]);
+    assert_eq!(FixedOpaque::<5>::skip(&mut cur).unwrap(), 8);
+    assert_eq!(cur.position(), 8);
+
+    // Short input
+    match FixedOpaque::<5>::unpack(&mut Cursor::new(vec![1, 2, 3, 4, 5])) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn fixed_array() {
+    let enc = [0, 0, 0, 1,  0, 0, 0, 2,  0, 0, 0, 3];
+
+    let (v, sz) = unpack_fixed_array::<_, u32, 3>(&mut &enc[..]).unwrap();
+    assert_eq!((v, sz), ([1, 2, 3], 12));
+    let (v, sz) = unpack_fixed_array::<_, String, 0>(&mut &enc[..]).unwrap();
+    assert_eq!((v, sz), ([], 0));
+
+    match unpack_fixed_array::<_, u32, 4>(&mut &enc[..]) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
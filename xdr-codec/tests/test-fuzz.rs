@@ -0,0 +1,63 @@
+extern crate xdr_codec;
+
+use xdr_codec::Opaque;
+use xdr_codec::bounded::BoundedString;
+use xdr_codec::fixed::FixedOpaque;
+use xdr_codec::fuzz::{fuzz_unpack, fuzz_unpack_ref, fuzz_roundtrip, fuzz_skip, fuzz_records};
+use xdr_codec::string::XdrString;
+
+// Deterministic junk, with some small words mixed in so lengths and discriminants are plausible
+fn inputs() -> Vec<Vec<u8>> {
+    let mut state = 0x1234_5678u32;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state
+    };
+
+    (0..500).map(|_| {
+        let len = (next() % 64) as usize;
+        let mut v = Vec::with_capacity(len);
+        while v.len() < len {
+            let w = next();
+            let w = if w & 1 == 0 { w % 8 } else { w };
+            v.extend_from_slice(&w.to_be_bytes());
+        }
+        v.truncate(len);
+        v
+    }).collect()
+}
+
+#[test]
+fn fuzz_junk() {
+    for data in inputs() {
+        fuzz_unpack::<Vec<Option<String>>>(&data);
+        fuzz_unpack::<Vec<Vec<u64>>>(&data);
+        fuzz_unpack_ref::<Vec<&str>>(&data);
+        fuzz_unpack_ref::<Option<Opaque>>(&data);
+        fuzz_roundtrip::<Vec<Option<XdrString>>>(&data);
+        fuzz_roundtrip::<BoundedString<8>>(&data);
+        fuzz_skip::<Vec<Option<String>>>(&data);
+        fuzz_skip::<Vec<Vec<i64>>>(&data);
+        fuzz_records::<Vec<u32>>(&data);
+    }
+}
+
+#[test]
+fn fuzz_huge_lengths() {
+    // A maximal element count mustn't be trusted to size an allocation
+    let data = [0xff, 0xff, 0xff, 0xff, 0, 0, 0, 1];
+
+    fuzz_unpack::<Vec<FixedOpaque<512>>>(&data);
+    fuzz_unpack::<Opaque>(&data);
+    fuzz_roundtrip::<Vec<Vec<u32>>>(&data);
+    fuzz_records::<Vec<u32>>(&[0xff, 0xff, 0xff, 0xff, 0, 0, 0, 1]);
+}
+
+#[test]
+fn fuzz_nan() {
+    // NaN isn't equal to itself, but its encoding survives
+    fuzz_roundtrip::<Vec<f64>>(&[0, 0, 0, 1,  0x7f, 0xf8, 0, 0, 0, 0, 0, 1]);
+    fuzz_roundtrip::<f32>(&[0xff, 0xc0, 0, 0]);
+}
@@ -0,0 +1,63 @@
+extern crate xdr_codec;
+
+use xdr_codec::{Error, ErrorKind, Opaque, UnpackInto, pack_to_vec, unpack_flex_into, unpack_opaque_flex_into};
+
+#[test]
+fn inplace_reuse() {
+    let mut v: Vec<String> = vec![String::with_capacity(64), String::from("old"), String::from("gone")];
+    let caps: Vec<_> = v.iter().map(|s| s.capacity()).collect();
+
+    let enc = pack_to_vec(&vec!["abc", "de"]).unwrap();
+    let sz = v.unpack_into(&mut &enc[..]).unwrap();
+    assert_eq!(sz, enc.len());
+    assert_eq!(v, ["abc", "de"]);
+    assert_eq!(v[0].capacity(), caps[0]);
+
+    let enc = pack_to_vec(&vec!["x", "y", "z"]).unwrap();
+    v.unpack_into(&mut &enc[..]).unwrap();
+    assert_eq!(v, ["x", "y", "z"]);
+    assert_eq!(v[0].capacity(), caps[0]);
+}
+
+#[test]
+fn inplace_option_opaque() {
+    let mut o: Option<Box<u32>> = Some(Box::new(1));
+    o.unpack_into(&mut &[0, 0, 0, 1,  0, 0, 0, 9][..]).unwrap();
+    assert_eq!(o, Some(Box::new(9)));
+    o.unpack_into(&mut &[0, 0, 0, 0][..]).unwrap();
+    assert_eq!(o, None);
+
+    let mut op = Opaque::owned(Vec::with_capacity(32));
+    let sz = op.unpack_into(&mut &[0, 0, 0, 3,  1, 2, 3, 0][..]).unwrap();
+    assert_eq!((&op[..], sz), (&[1, 2, 3][..], 8));
+    assert_eq!(op.0.into_owned().capacity(), 32);
+
+    let mut s = String::new();
+    match s.unpack_into(&mut &[0, 0, 0, 2,  1][..]) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn flex_into_append() {
+    let mut v = vec![1u32];
+    let sz = unpack_flex_into(&mut &[0, 0, 0, 2,  0, 0, 0, 2,  0, 0, 0, 3][..], &mut v, Some(2)).unwrap();
+    assert_eq!((&v[..], sz), (&[1, 2, 3][..], 12));
+
+    match unpack_flex_into(&mut &[0, 0, 0, 2,  0, 0, 0, 2,  0, 0, 0, 3][..], &mut v, Some(1)) {
+        Err(Error(ErrorKind::InvalidLen(1), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let mut buf = Vec::with_capacity(16);
+    for enc in &[&[0, 0, 0, 3,  1, 2, 3, 0][..], &[0, 0, 0, 1,  9, 0, 0, 0][..]] {
+        buf.clear();
+        let sz = unpack_opaque_flex_into(&mut &enc[..], &mut buf, None).unwrap();
+        assert_eq!(sz, 8);
+    }
+    assert_eq!((&buf[..], buf.capacity()), (&[9][..], 16));
+
+    let sz = unpack_opaque_flex_into(&mut &[0, 0, 0, 2,  7, 8, 0, 0][..], &mut buf, Some(4)).unwrap();
+    assert_eq!((&buf[..], sz), (&[9, 7, 8][..], 8));
+}
@@ -0,0 +1,121 @@
+#![cfg(feature="json")]
+extern crate xdr_codec;
+#[macro_use]
+extern crate serde_json;
+
+use xdr_codec::{Error, ErrorKind, Value};
+use xdr_codec::value::{Schema, Type};
+
+fn s(v: &str) -> String {
+    v.to_string()
+}
+
+fn schema() -> Schema {
+    let mut schema = Schema::new();
+
+    schema.define("colour", Type::Enum(vec![(s("RED"), 1), (s("GREEN"), 2)]));
+    schema.define("thing", Type::Struct(vec![
+        (s("id"), Type::FixedOpaque(2)),
+        (s("name"), Type::String(Some(4))),
+        (s("size"), Type::UHyper),
+        (s("ratio"), Type::Double),
+        (s("tags"), Type::Array(Box::new(Type::Named(s("colour"))), None)),
+        (s("next"), Type::Optional(Box::new(Type::Int))),
+        (s("res"), Type::Union {
+            discriminant: Box::new(Type::Bool),
+            cases: vec![(1, Type::Int), (0, Type::Void)],
+            default: None,
+        }),
+    ]));
+
+    schema
+}
+
+fn thing() -> Value {
+    Value::Struct(vec![
+        (s("id"), Value::FixedOpaque(vec![0xab, 0x01])),
+        (s("name"), Value::String(s("abc"))),
+        (s("size"), Value::UHyper(u64::MAX)),
+        (s("ratio"), Value::Double(0.5)),
+        (s("tags"), Value::Array(vec![Value::Enum { name: s("GREEN"), value: 2 }])),
+        (s("next"), Value::Optional(None)),
+        (s("res"), Value::Union { discriminant: Box::new(Value::Bool(true)), arm: Box::new(Value::Int(-3)) }),
+    ])
+}
+
+#[test]
+fn to_json() {
+    assert_eq!(thing().to_json(), json!({
+        "id": "ab01",
+        "name": "abc",
+        "size": u64::MAX,
+        "ratio": 0.5,
+        "tags": ["GREEN"],
+        "next": null,
+        "res": { "discriminant": true, "arm": -3 },
+    }));
+
+    assert_eq!(Value::Double(f64::NAN).to_json(), json!(null));
+    assert_eq!(serde_json::Value::from(&Value::Optional(Some(Box::new(Value::Int(1))))), json!(1));
+}
+
+#[test]
+fn from_json() {
+    let schema = schema();
+    let ty = Type::Named(s("thing"));
+
+    assert_eq!(schema.value_from_json(&ty, &thing().to_json()).unwrap(), thing());
+
+    // Alternative forms
+    let v = schema.value_from_json(&ty, &json!({
+        "id": [171, 1],
+        "name": "abc",
+        "size": u64::MAX,
+        "ratio": 0.5,
+        "tags": [2],
+        "res": { "discriminant": true, "arm": -3 },
+    })).unwrap();
+    assert_eq!(v, thing());
+}
+
+#[test]
+fn from_json_errors() {
+    let schema = schema();
+
+    let check = |ty: Type, j: serde_json::Value| schema.value_from_json(&ty, &j);
+
+    match check(Type::Int, json!(1u64 << 40)) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match check(Type::UInt, json!(-1)) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match check(Type::String(Some(2)), json!("abc")) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match check(Type::FixedOpaque(2), json!("abc")) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match check(Type::Named(s("colour")), json!(3)) {
+        Err(Error(ErrorKind::InvalidEnum(3), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match check(Type::Named(s("colour")), json!("BLUE")) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    // Missing non-optional field
+    match check(Type::Named(s("thing")), json!({})) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    let int_union = Type::Union { discriminant: Box::new(Type::Int), cases: vec![], default: None };
+    match check(int_union, json!({ "discriminant": 4 })) {
+        Err(Error(ErrorKind::InvalidCase(4), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
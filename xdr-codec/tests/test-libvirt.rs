@@ -0,0 +1,104 @@
+#![cfg(feature = "libvirt")]
+extern crate xdr_codec;
+
+use std::io;
+
+use xdr_codec::{Unpack, Error, ErrorKind, pack_to_vec};
+use xdr_codec::libvirt::*;
+
+fn reply(serial: u32, status: virNetMessageStatus) -> virNetMessageHeader {
+    virNetMessageHeader {
+        prog: REMOTE_PROGRAM,
+        vers: REMOTE_PROTOCOL_VERSION,
+        proc_: 66,
+        type_: virNetMessageType::VIR_NET_REPLY,
+        serial,
+        status,
+    }
+}
+
+#[test]
+fn header() {
+    let hdr = reply(9, virNetMessageStatus::VIR_NET_ERROR);
+    let v = pack_to_vec(&hdr).unwrap();
+    assert_eq!(v, vec![0x20, 0x00, 0x80, 0x86,
+                       0, 0, 0, 1,
+                       0, 0, 0, 66,
+                       0, 0, 0, 1,
+                       0, 0, 0, 9,
+                       0, 0, 0, 1]);
+    assert_eq!(v.len(), VIR_NET_MESSAGE_HEADER_MAX);
+    assert_eq!(virNetMessageHeader::unpack(&mut &v[..]).unwrap(), (hdr, 24));
+
+    let mut v = v;
+    v[15] = 7;
+    match virNetMessageHeader::unpack(&mut &v[..]) {
+        Err(Error(ErrorKind::InvalidEnum(7), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn framing() {
+    let mut w = MessageWriter::new(Vec::new());
+    assert_eq!(w.call(REMOTE_PROGRAM, REMOTE_PROTOCOL_VERSION, 60, &5u32).unwrap(), 0);
+    assert_eq!(w.call(KEEPALIVE_PROGRAM, KEEPALIVE_PROTOCOL_VERSION, 1, &()).unwrap(), 1);
+    w.write_message(&reply(0, virNetMessageStatus::VIR_NET_OK), &-1i64).unwrap();
+    let buf = w.into_inner();
+
+    assert_eq!(&buf[..4], &[0, 0, 0, 32]);
+    assert_eq!(&buf[28..32], &[0, 0, 0, 5]);
+    assert_eq!(&buf[32..36], &[0, 0, 0, 28]);
+    assert_eq!(buf.len(), 32 + 28 + 36);
+
+    let msgs: Vec<_> = MessageReader::new(&buf[..]).map(|m| m.unwrap()).collect();
+    assert_eq!(msgs.len(), 3);
+    assert_eq!(msgs[0].header.proc_, 60);
+    assert_eq!(msgs[0].header.type_, virNetMessageType::VIR_NET_CALL);
+    assert_eq!(msgs[0].unpack_body::<u32>().unwrap(), 5);
+    assert_eq!(msgs[1].header.prog, KEEPALIVE_PROGRAM);
+    assert_eq!(msgs[1].header.serial, 1);
+    assert!(msgs[1].body.is_empty());
+    assert_eq!(msgs[2].header, reply(0, virNetMessageStatus::VIR_NET_OK));
+    assert_eq!(msgs[2].unpack_body::<i64>().unwrap(), -1);
+}
+
+#[test]
+fn limits() {
+    let mut w = MessageWriter::new(Vec::new());
+    w.set_max_message(32);
+    w.call(REMOTE_PROGRAM, REMOTE_PROTOCOL_VERSION, 1, &0u32).unwrap();
+    match w.call(REMOTE_PROGRAM, REMOTE_PROTOCOL_VERSION, 1, &0u64) {
+        Err(Error(ErrorKind::RecordTooLarge(36, 32), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    let buf = w.into_inner();
+    assert_eq!(buf.len(), 32);
+
+    let mut r = MessageReader::new(&buf[..]);
+    r.set_max_message(31);
+    match r.read_message() {
+        Err(Error(ErrorKind::RecordTooLarge(32, 31), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    match MessageReader::new(&[0, 0, 0, 27][..]).read_message() {
+        Err(Error(ErrorKind::InvalidLen(27), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn truncated() {
+    let mut w = MessageWriter::new(Vec::new());
+    w.call(REMOTE_PROGRAM, REMOTE_PROTOCOL_VERSION, 1, &0u32).unwrap();
+    let buf = w.into_inner();
+
+    for len in &[2, 10, 31] {
+        match MessageReader::new(&buf[..*len]).read_message() {
+            Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+            res => panic!("bad result {:?}", res),
+        }
+    }
+    assert!(MessageReader::new(&buf[..0]).read_message().unwrap().is_none());
+}
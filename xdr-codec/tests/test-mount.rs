@@ -0,0 +1,103 @@
+#![cfg(feature = "mount")]
+extern crate xdr_codec;
+
+use xdr_codec::{Unpack, Error, ErrorKind, pack_to_vec};
+use xdr_codec::mount::*;
+
+#[test]
+fn dirpath_limit() {
+    assert_eq!(pack_to_vec(&dirpath("/x".to_string())).unwrap(), vec![0, 0, 0, 2,  b'/', b'x', 0, 0]);
+
+    match pack_to_vec(&dirpath("x".repeat(MNTPATHLEN + 1))) {
+        Err(Error(ErrorKind::InvalidLen(MNTPATHLEN), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn mnt_v1() {
+    let res = fhstatus::Ok([7; FHSIZE]);
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(v.len(), 4 + FHSIZE);
+    assert_eq!(&v[..5], &[0, 0, 0, 0, 7]);
+    assert_eq!(fhstatus::unpack(&mut &v[..]).unwrap(), (res, 36));
+
+    let res = fhstatus::Fail(13);
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 13]);
+    assert_eq!(fhstatus::unpack(&mut &v[..]).unwrap(), (res, 4));
+
+    match pack_to_vec(&fhstatus::Fail(0)) {
+        Err(Error(ErrorKind::InvalidCase(0), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn mnt_v3() {
+    let res = mountres3::Ok(mountres3_ok { fhandle: fhandle3(vec![1, 2, 3]), auth_flavors: vec![1, 6] });
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 0,
+                       0, 0, 0, 3,  1, 2, 3, 0,
+                       0, 0, 0, 2,  0, 0, 0, 1,  0, 0, 0, 6]);
+    assert_eq!(mountres3::unpack(&mut &v[..]).unwrap(), (res, 24));
+
+    let res = mountres3::Fail(mountstat3::MNT3ERR_ACCES);
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 13]);
+    assert_eq!(mountres3::unpack(&mut &v[..]).unwrap(), (res, 4));
+
+    match mountres3::unpack(&mut &[0, 0, 0, 3][..]) {
+        Err(Error(ErrorKind::InvalidEnum(3), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn dump() {
+    let list = mountlist(vec![
+        mountbody { ml_hostname: name("a".to_string()), ml_directory: dirpath("/x".to_string()) },
+        mountbody { ml_hostname: name("b".to_string()), ml_directory: dirpath("/y".to_string()) },
+    ]);
+    let v = pack_to_vec(&list).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 1,
+                       0, 0, 0, 1,  b'a', 0, 0, 0,
+                       0, 0, 0, 2,  b'/', b'x', 0, 0,
+                       0, 0, 0, 1,
+                       0, 0, 0, 1,  b'b', 0, 0, 0,
+                       0, 0, 0, 2,  b'/', b'y', 0, 0,
+                       0, 0, 0, 0]);
+    assert_eq!(mountlist::unpack(&mut &v[..]).unwrap(), (list, v.len()));
+
+    assert_eq!(pack_to_vec(&mountlist::default()).unwrap(), vec![0, 0, 0, 0]);
+}
+
+#[test]
+fn export() {
+    let ex = exports(vec![
+        exportnode {
+            ex_dir: dirpath("/srv".to_string()),
+            ex_groups: groups(vec![name("host1".to_string()), name("@net".to_string())]),
+        },
+        exportnode { ex_dir: dirpath("/pub".to_string()), ex_groups: groups::default() },
+    ]);
+    let v = pack_to_vec(&ex).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 1,
+                       0, 0, 0, 4,  b'/', b's', b'r', b'v',
+                       0, 0, 0, 1,
+                       0, 0, 0, 5,  b'h', b'o', b's', b't',  b'1', 0, 0, 0,
+                       0, 0, 0, 1,
+                       0, 0, 0, 4,  b'@', b'n', b'e', b't',
+                       0, 0, 0, 0,
+                       0, 0, 0, 1,
+                       0, 0, 0, 4,  b'/', b'p', b'u', b'b',
+                       0, 0, 0, 0,
+                       0, 0, 0, 0]);
+    assert_eq!(exports::unpack(&mut &v[..]).unwrap(), (ex, v.len()));
+
+    // A list which doesn't end
+    match exports::unpack(&mut &v[..v.len() - 4]) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "ndmp")]
+extern crate xdr_codec;
+
+use xdr_codec::{Unpack, Error, ErrorKind, pack_to_vec};
+use xdr_codec::ndmp::*;
+
+#[test]
+fn header() {
+    let hdr = ndmp_header {
+        sequence: 3,
+        time_stamp: 0x5f000000,
+        message_type: ndmp_header_message_type::NDMP_MESSAGE_REPLY,
+        message: NDMP_CONFIG_GET_HOST_INFO,
+        reply_sequence: 2,
+        error_code: ndmp_error::NDMP_NOT_SUPPORTED_ERR,
+    };
+    let v = pack_to_vec(&hdr).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 3,
+                       0x5f, 0, 0, 0,
+                       0, 0, 0, 1,
+                       0, 0, 1, 0,
+                       0, 0, 0, 2,
+                       0, 0, 0, 1]);
+    assert_eq!(ndmp_header::unpack(&mut &v[..]).unwrap(), (hdr, 24));
+
+    let mut v = v;
+    v[11] = 2;
+    match ndmp_header::unpack(&mut &v[..]) {
+        Err(Error(ErrorKind::InvalidEnum(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn connect_open() {
+    let v = pack_to_vec(&ndmp_connect_open_request { protocol_version: NDMPVER }).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 4]);
+
+    let reply = ndmp_connect_open_reply { error: ndmp_error::NDMP_ILLEGAL_ARGS_ERR };
+    let v = pack_to_vec(&reply).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 9]);
+    assert_eq!(ndmp_connect_open_reply::unpack(&mut &v[..]).unwrap(), (reply, 4));
+}
+
+#[test]
+fn matching() {
+    let mut client = Session::new();
+    let mut server = Session::new();
+
+    let open = client.request(NDMP_CONNECT_OPEN);
+    let info = client.request(NDMP_CONFIG_GET_HOST_INFO);
+    assert_eq!((open.sequence, info.sequence), (1, 2));
+    assert_eq!(open.reply_sequence, 0);
+    assert_eq!(client.pending(), 2);
+
+    // Server's own notification doesn't disturb numbering of replies
+    let note = server.notification(NDMP_NOTIFY_CONNECTION_STATUS);
+    assert_eq!(note.sequence, 1);
+
+    // Replies out of order
+    let r2 = server.reply(&info, ndmp_error::NDMP_NO_ERR);
+    let r1 = server.reply(&open, ndmp_error::NDMP_NO_ERR);
+    assert_eq!((r2.sequence, r2.reply_sequence), (2, 2));
+    assert_eq!(r2.message_type, ndmp_header_message_type::NDMP_MESSAGE_REPLY);
+
+    assert_eq!(client.complete(&r2).unwrap(), NDMP_CONFIG_GET_HOST_INFO);
+    assert!(client.is_pending(1));
+    assert!(!client.is_pending(2));
+    assert_eq!(client.complete(&r1).unwrap(), NDMP_CONNECT_OPEN);
+    assert_eq!(client.pending(), 0);
+}
+
+#[test]
+fn bad_replies() {
+    let mut client = Session::new();
+    let mut server = Session::new();
+
+    let req = client.request(NDMP_CONNECT_CLOSE);
+    let reply = server.reply(&req, ndmp_error::NDMP_NO_ERR);
+
+    let mut bad = reply;
+    bad.message = NDMP_CONNECT_OPEN;
+    match client.complete(&bad) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    match client.complete(&req) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Still pending after the bad replies, but only completes once
+    client.complete(&reply).unwrap();
+    match client.complete(&reply) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
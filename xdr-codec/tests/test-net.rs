@@ -0,0 +1,69 @@
+extern crate xdr_codec;
+
+use std::io::Cursor;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use xdr_codec::{Pack, Unpack, Error, ErrorKind};
+use xdr_codec::net::{Transport, netid, parse_netid, to_uaddr, from_uaddr};
+
+#[test]
+fn uaddr_v4() {
+    let addr: SocketAddr = "10.0.0.1:2049".parse().unwrap();
+
+    assert_eq!(to_uaddr(&addr), "10.0.0.1.8.1");
+    assert_eq!(from_uaddr("10.0.0.1.8.1").unwrap(), addr);
+    assert_eq!(netid(Transport::Tcp, &addr), "tcp");
+    assert_eq!(netid(Transport::Udp, &addr), "udp");
+}
+
+#[test]
+fn uaddr_v6() {
+    let addr: SocketAddr = "[fe80::1]:111".parse().unwrap();
+
+    assert_eq!(to_uaddr(&addr), "fe80::1.0.111");
+    assert_eq!(from_uaddr("fe80::1.0.111").unwrap(), addr);
+    assert_eq!(netid(Transport::Tcp, &addr), "tcp6");
+    assert_eq!(parse_netid("udp6").unwrap(), (Transport::Udp, true));
+}
+
+#[test]
+fn uaddr_bad() {
+    for bad in &["", "10.0.0.1", "10.0.0.1.8", "10.0.0.1.256.1", "10.0.0.1.8.x", "host.8.1", "::1.1.1.1.1"] {
+        match from_uaddr(bad) {
+            Err(Error(ErrorKind::InvalidAddr(ref s), _)) if s == bad => (),
+            res => panic!("{:?}: bad result {:?}", bad, res),
+        }
+    }
+    assert!(parse_netid("sctp").is_err());
+}
+
+#[test]
+fn raw_addrs() {
+    let mut out = Cursor::new(Vec::new());
+
+    assert_eq!(Ipv4Addr::new(192, 168, 1, 2).pack(&mut out).unwrap(), 4);
+    assert_eq!(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1).pack(&mut out).unwrap(), 16);
+    assert_eq!(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)).pack(&mut out).unwrap(), 8);
+    assert_eq!(IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)).pack(&mut out).unwrap(), 20);
+
+    let v = out.into_inner();
+    assert_eq!(&v[..4], &[192, 168, 1, 2]);
+    assert_eq!(&v[4..20], &[0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(&v[20..28], &[0, 0, 0, 4, 127, 0, 0, 1]);
+    assert_eq!(&v[28..32], &[0, 0, 0, 16]);
+
+    let mut input = Cursor::new(v);
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (Ipv4Addr::new(192, 168, 1, 2), 4));
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1), 16));
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8));
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)), 20));
+}
+
+#[test]
+fn raw_addr_bad_len() {
+    let mut input = Cursor::new(vec![0, 0, 0, 5,  1, 2, 3, 4,  5, 0, 0, 0]);
+    match IpAddr::unpack(&mut input) {
+        Err(Error(ErrorKind::InvalidLen(5), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
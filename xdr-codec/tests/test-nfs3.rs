@@ -0,0 +1,200 @@
+#![cfg(feature = "nfs3")]
+extern crate xdr_codec;
+
+use xdr_codec::{Unpack, Error, ErrorKind, pack_to_vec};
+use xdr_codec::nfs3::*;
+
+fn attrs() -> fattr3 {
+    fattr3 {
+        type_: ftype3::NF3REG,
+        mode: 0o644,
+        nlink: 1,
+        uid: 1000,
+        gid: 100,
+        size: 5,
+        used: 4096,
+        rdev: specdata3::default(),
+        fsid: 0x12,
+        fileid: 0x3456,
+        atime: nfstime3 { seconds: 1, nseconds: 2 },
+        mtime: nfstime3 { seconds: 3, nseconds: 4 },
+        ctime: nfstime3 { seconds: 5, nseconds: 6 },
+    }
+}
+
+fn fh() -> nfs_fh3 {
+    nfs_fh3 { data: vec![0xfe, 0xed] }
+}
+
+#[test]
+fn fattr3_roundtrip() {
+    let v = pack_to_vec(&attrs()).unwrap();
+
+    assert_eq!(v.len(), 84);
+    assert_eq!(&v[..8], &[0, 0, 0, 1,  0, 0, 0x01, 0xa4]);
+    assert_eq!(fattr3::unpack(&mut &v[..]).unwrap(), (attrs(), 84));
+}
+
+#[test]
+fn fh_limit() {
+    assert_eq!(pack_to_vec(&fh()).unwrap(), vec![0, 0, 0, 2,  0xfe, 0xed, 0, 0]);
+
+    match pack_to_vec(&nfs_fh3 { data: vec![0; NFS3_FHSIZE + 1] }) {
+        Err(Error(ErrorKind::InvalidLen(NFS3_FHSIZE), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn bad_status() {
+    match nfsstat3::unpack(&mut &[0, 0, 0, 3][..]) {
+        Err(Error(ErrorKind::InvalidEnum(3), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn read() {
+    let args = READ3args { file: fh(), offset: 0x1000, count: 16 };
+    assert_eq!(pack_to_vec(&args).unwrap(),
+               vec![0, 0, 0, 2,  0xfe, 0xed, 0, 0,
+                    0, 0, 0, 0,  0, 0, 0x10, 0,
+                    0, 0, 0, 16]);
+
+    let res: READ3res = nfs3res::Ok(READ3resok {
+        file_attributes: None,
+        count: 3,
+        eof: true,
+        data: b"abc".to_vec(),
+    });
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 0,
+                       0, 0, 0, 0,
+                       0, 0, 0, 3,
+                       0, 0, 0, 1,
+                       0, 0, 0, 3,  b'a', b'b', b'c', 0]);
+    assert_eq!(READ3res::unpack(&mut &v[..]).unwrap(), (res, 24));
+
+    let res: READ3res = nfs3res::Fail(nfsstat3::NFS3ERR_STALE,
+                                      READ3resfail { file_attributes: Some(attrs()) });
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(&v[..8], &[0, 0, 0, 70,  0, 0, 0, 1]);
+    assert_eq!(v.len(), 8 + 84);
+
+    let (ures, sz) = READ3res::unpack(&mut &v[..]).unwrap();
+    assert_eq!(sz, 92);
+    assert_eq!(ures.status(), nfsstat3::NFS3ERR_STALE);
+    assert_eq!(ures.into_result().unwrap_err().1.file_attributes, Some(attrs()));
+}
+
+#[test]
+fn fail_with_ok_status() {
+    let res: GETATTR3res = nfs3res::Fail(nfsstat3::NFS3_OK, ());
+
+    match pack_to_vec(&res) {
+        Err(Error(ErrorKind::InvalidCase(0), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn write() {
+    let args = WRITE3args {
+        file: fh(),
+        offset: 0,
+        count: 2,
+        stable: stable_how::FILE_SYNC,
+        data: vec![1, 2],
+    };
+    let v = pack_to_vec(&args).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 2,  0xfe, 0xed, 0, 0,
+                       0, 0, 0, 0,  0, 0, 0, 0,
+                       0, 0, 0, 2,
+                       0, 0, 0, 2,
+                       0, 0, 0, 2,  1, 2, 0, 0]);
+    assert_eq!(WRITE3args::unpack(&mut &v[..]).unwrap(), (args, 32));
+
+    let res: WRITE3res = nfs3res::Ok(WRITE3resok {
+        file_wcc: wcc_data {
+            before: Some(wcc_attr { size: 0, mtime: nfstime3::default(), ctime: nfstime3::default() }),
+            after: Some(attrs()),
+        },
+        count: 2,
+        committed: stable_how::DATA_SYNC,
+        verf: *b"verifier",
+    });
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(v.len(), 4 + (4 + 24) + (4 + 84) + 4 + 4 + 8);
+    assert_eq!(&v[v.len() - 8..], b"verifier");
+    assert_eq!(WRITE3res::unpack(&mut &v[..]).unwrap(), (res, v.len()));
+}
+
+#[test]
+fn readdirplus() {
+    let args = READDIRPLUS3args {
+        dir: fh(),
+        cookie: 0,
+        cookieverf: [0; NFS3_COOKIEVERFSIZE],
+        dircount: 512,
+        maxcount: 4096,
+    };
+    let v = pack_to_vec(&args).unwrap();
+    assert_eq!(v.len(), 8 + 8 + 8 + 4 + 4);
+    assert_eq!(READDIRPLUS3args::unpack(&mut &v[..]).unwrap(), (args, 32));
+
+    let entry = |fileid, name: &[u8]| entryplus3 {
+        fileid,
+        name: name.to_vec(),
+        cookie: fileid,
+        name_attributes: None,
+        name_handle: Some(fh()),
+    };
+    let list = dirlistplus3 { entries: vec![entry(1, b"."), entry(2, b"..")], eof: true };
+
+    let v = pack_to_vec(&list).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 1,
+                       0, 0, 0, 0,  0, 0, 0, 1,
+                       0, 0, 0, 1,  b'.', 0, 0, 0,
+                       0, 0, 0, 0,  0, 0, 0, 1,
+                       0, 0, 0, 0,
+                       0, 0, 0, 1,  0, 0, 0, 2,  0xfe, 0xed, 0, 0,
+                       0, 0, 0, 1,
+                       0, 0, 0, 0,  0, 0, 0, 2,
+                       0, 0, 0, 2,  b'.', b'.', 0, 0,
+                       0, 0, 0, 0,  0, 0, 0, 2,
+                       0, 0, 0, 0,
+                       0, 0, 0, 1,  0, 0, 0, 2,  0xfe, 0xed, 0, 0,
+                       0, 0, 0, 0,
+                       0, 0, 0, 1]);
+    assert_eq!(dirlistplus3::unpack(&mut &v[..]).unwrap(), (list.clone(), v.len()));
+
+    let empty = dirlistplus3 { entries: Vec::new(), eof: false };
+    assert_eq!(pack_to_vec(&empty).unwrap(), vec![0, 0, 0, 0,  0, 0, 0, 0]);
+
+    let res: READDIRPLUS3res = nfs3res::Ok(READDIRPLUS3resok {
+        dir_attributes: Some(attrs()),
+        cookieverf: *b"cookieve",
+        reply: list,
+    });
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(READDIRPLUS3res::unpack(&mut &v[..]).unwrap(), (res, v.len()));
+}
+
+#[test]
+fn lookup() {
+    let args = LOOKUP3args { what: diropargs3 { dir: fh(), name: b"file".to_vec() } };
+    let v = pack_to_vec(&args).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 2,  0xfe, 0xed, 0, 0,
+                       0, 0, 0, 4,  b'f', b'i', b'l', b'e']);
+    assert_eq!(LOOKUP3args::unpack(&mut &v[..]).unwrap(), (args, 16));
+
+    // Names are bytes, not necessarily UTF-8
+    let args = LOOKUP3args { what: diropargs3 { dir: fh(), name: vec![b'f', 0xe9, b'e'] } };
+    let v = pack_to_vec(&args).unwrap();
+    assert_eq!(LOOKUP3args::unpack(&mut &v[..]).unwrap(), (args, 16));
+
+    let res: LOOKUP3res = nfs3res::Fail(nfsstat3::NFS3ERR_NOENT, LOOKUP3resfail { dir_attributes: None });
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 2,  0, 0, 0, 0]);
+    assert_eq!(LOOKUP3res::unpack(&mut &v[..]).unwrap(), (res, 8));
+}
@@ -0,0 +1,174 @@
+#![cfg(feature = "nfs4")]
+extern crate xdr_codec;
+
+use xdr_codec::{Unpack, Error, ErrorKind, Opaque, pack_to_vec};
+use xdr_codec::string::XdrString;
+use xdr_codec::nfs4::*;
+
+#[test]
+fn bitmap() {
+    let mut map = bitmap4::new();
+    assert!(map.is_empty());
+
+    map.set(FATTR4_TYPE).set(FATTR4_SIZE).set(FATTR4_MODE);
+    assert_eq!(map.0, vec![0x12, 0x2]);
+    assert!(map.is_set(FATTR4_SIZE));
+    assert!(!map.is_set(FATTR4_CHANGE));
+    assert!(!map.is_set(100));
+    assert_eq!(map.bits().collect::<Vec<_>>(), vec![FATTR4_TYPE, FATTR4_SIZE, FATTR4_MODE]);
+
+    let v = pack_to_vec(&map).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 2,  0, 0, 0, 0x12,  0, 0, 0, 2]);
+    assert_eq!(bitmap4::unpack(&mut &v[..]).unwrap(), (map.clone(), 12));
+
+    map.clear(FATTR4_MODE);
+    assert_eq!(map.0, vec![0x12]);
+    map.clear(FATTR4_TYPE).clear(FATTR4_SIZE).clear(200);
+    assert!(map.0.is_empty());
+
+    let map: bitmap4 = vec![FATTR4_MOUNTED_ON_FILEID, 0].into_iter().collect();
+    assert_eq!(map.0, vec![1, 1 << 23]);
+}
+
+#[test]
+fn attrs() {
+    let mut attrs = fattr4::new();
+    attrs.push(FATTR4_SIZE, &0x1234u64).unwrap()
+         .push(FATTR4_OWNER, "root").unwrap();
+
+    match attrs.push(FATTR4_TYPE, &1u32) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let v = pack_to_vec(&attrs).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 2,  0, 0, 0, 0x10,  0, 0, 0, 0x10,
+                       0, 0, 0, 16,
+                       0, 0, 0, 0,  0, 0, 0x12, 0x34,
+                       0, 0, 0, 4,  b'r', b'o', b'o', b't']);
+    let (uattrs, sz) = fattr4::unpack(&mut &v[..]).unwrap();
+    assert_eq!(sz, v.len());
+    assert_eq!(uattrs, attrs);
+
+    let mut r = uattrs.reader();
+    assert_eq!(r.next_attr(), Some(FATTR4_SIZE));
+    assert_eq!(r.value::<u64>().unwrap(), 0x1234);
+    assert_eq!(r.next_attr(), Some(FATTR4_OWNER));
+    assert_eq!(r.value::<XdrString>().unwrap().0, b"root");
+    assert_eq!(r.next_attr(), None);
+    r.finish().unwrap();
+}
+
+#[test]
+fn attrs_unread() {
+    let mut attrs = fattr4::new();
+    attrs.push(FATTR4_TYPE, &1u32).unwrap()
+         .push(FATTR4_SIZE, &0u64).unwrap();
+
+    let mut r = attrs.reader();
+    r.next_attr();
+    r.value::<u32>().unwrap();
+    match r.finish() {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Reading the size as a u32 leaves half of it over
+    let mut r = attrs.reader();
+    r.next_attr();
+    r.value::<u32>().unwrap();
+    r.next_attr();
+    r.value::<u32>().unwrap();
+    match r.finish() {
+        Err(Error(ErrorKind::InvalidLen(4), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn compound_args() {
+    let fh = Opaque::borrowed(&[1, 2, 3, 4]);
+    let mut call = CompoundBuilder::args("t", 0);
+    call.op(OP_PUTFH, &fh).unwrap()
+        .op(OP_GETATTR, &[FATTR4_SIZE].iter().cloned().collect::<bitmap4>()).unwrap();
+
+    assert_eq!(call.status(), None);
+    assert_eq!(call.ops(), &[(OP_PUTFH, 4 + 8), (OP_GETATTR, 4 + 8)]);
+
+    let v = pack_to_vec(&call).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 1,  b't', 0, 0, 0,
+                       0, 0, 0, 0,
+                       0, 0, 0, 2,
+                       0, 0, 0, 22,  0, 0, 0, 4,  1, 2, 3, 4,
+                       0, 0, 0, 9,  0, 0, 0, 1,  0, 0, 0, 0x10]);
+
+    let mut r = CompoundReader::args(&v).unwrap();
+    assert_eq!(r.tag(), "t");
+    assert_eq!(r.minorversion(), Some(0));
+    assert_eq!(r.remaining(), 2);
+    assert_eq!(r.peek().unwrap(), Some(OP_PUTFH));
+
+    // The wrong operation consumes nothing
+    match r.op::<bitmap4>(OP_GETATTR) {
+        Err(Error(ErrorKind::InvalidCase(22), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    assert_eq!(r.op::<Opaque>(OP_PUTFH).unwrap(), fh);
+    assert!(r.op::<bitmap4>(OP_GETATTR).unwrap().is_set(FATTR4_SIZE));
+    assert_eq!(r.peek().unwrap(), None);
+    assert_eq!(r.ops(), call.ops());
+
+    match r.clone().op::<bitmap4>(OP_GETATTR) {
+        Err(Error(ErrorKind::InvalidLen(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    r.finish().unwrap();
+}
+
+#[test]
+fn compound_res() {
+    let mut reply = CompoundBuilder::res("");
+    reply.op(OP_PUTROOTFH, &NFS4_OK).unwrap();
+    assert_eq!(reply.status(), Some(NFS4_OK));
+
+    // Results must start with a status
+    match reply.op(OP_GETFH, &()) {
+        Err(Error(ErrorKind::InvalidLen(0), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(reply.ops().len(), 1);
+
+    reply.op(OP_LOOKUP, &2u32).unwrap();
+    assert_eq!(reply.status(), Some(2));
+
+    let v = pack_to_vec(&reply).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 2,
+                       0, 0, 0, 0,
+                       0, 0, 0, 2,
+                       0, 0, 0, 24,  0, 0, 0, 0,
+                       0, 0, 0, 15,  0, 0, 0, 2]);
+
+    let mut r = CompoundReader::res(&v).unwrap();
+    assert_eq!(r.status(), Some(2));
+    assert_eq!(r.minorversion(), None);
+    assert_eq!(r.op::<nfsstat4>(OP_PUTROOTFH).unwrap(), NFS4_OK);
+    assert_eq!(r.op::<nfsstat4>(OP_LOOKUP).unwrap(), 2);
+    assert_eq!(r.ops(), &[(OP_PUTROOTFH, 8), (OP_LOOKUP, 8)]);
+    r.finish().unwrap();
+}
+
+#[test]
+fn compound_truncated() {
+    let mut call = CompoundBuilder::args("", 0);
+    call.op(OP_PUTROOTFH, &()).unwrap().op(OP_GETFH, &()).unwrap();
+    let v = pack_to_vec(&call).unwrap();
+
+    let mut r = CompoundReader::args(&v[..v.len() - 4]).unwrap();
+    r.op::<()>(OP_PUTROOTFH).unwrap();
+    assert!(r.peek().is_err());
+    match r.finish() {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
@@ -0,0 +1,134 @@
+#![cfg(feature = "nlm")]
+extern crate xdr_codec;
+
+use xdr_codec::{Unpack, Error, ErrorKind, pack_to_vec};
+use xdr_codec::nlm::*;
+
+fn lock() -> nlm4_lock {
+    nlm4_lock {
+        caller_name: "host".to_string(),
+        fh: netobj(vec![1, 2, 3, 4]),
+        oh: netobj(vec![5]),
+        svid: 42,
+        l_offset: 0,
+        l_len: 100,
+    }
+}
+
+#[test]
+fn lockargs() {
+    let args = nlm4_lockargs {
+        cookie: netobj(vec![9, 9]),
+        block: true,
+        exclusive: true,
+        alock: lock(),
+        reclaim: false,
+        state: 3,
+    };
+    let v = pack_to_vec(&args).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 2,  9, 9, 0, 0,
+                       0, 0, 0, 1,
+                       0, 0, 0, 1,
+                       0, 0, 0, 4,  b'h', b'o', b's', b't',
+                       0, 0, 0, 4,  1, 2, 3, 4,
+                       0, 0, 0, 1,  5, 0, 0, 0,
+                       0, 0, 0, 42,
+                       0, 0, 0, 0,  0, 0, 0, 0,
+                       0, 0, 0, 0,  0, 0, 0, 100,
+                       0, 0, 0, 0,
+                       0, 0, 0, 3]);
+    assert_eq!(nlm4_lockargs::unpack(&mut &v[..]).unwrap(), (args, v.len()));
+}
+
+#[test]
+fn caller_name_limit() {
+    let mut l = lock();
+    l.caller_name = "x".repeat(LM_MAXSTRLEN + 1);
+
+    match pack_to_vec(&l) {
+        Err(Error(ErrorKind::InvalidLen(LM_MAXSTRLEN), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn testres() {
+    let holder = nlm4_holder { exclusive: true, svid: 7, oh: netobj(vec![1]), l_offset: 10, l_len: 20 };
+    let res = nlm4_testres { cookie: netobj(vec![]), stat: nlm4_testrply::Denied(holder) };
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(&v[..8], &[0, 0, 0, 0,  0, 0, 0, 1]);
+    assert_eq!(v.len(), 8 + 4 + 4 + 8 + 8 + 8);
+    assert_eq!(nlm4_testres::unpack(&mut &v[..]).unwrap(), (res.clone(), v.len()));
+    assert_eq!(res.stat.stat(), nlm4_stats::NLM4_DENIED);
+
+    let res = nlm4_testres { cookie: netobj(vec![]), stat: nlm4_testrply::Stat(nlm4_stats::NLM4_GRANTED) };
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 0,  0, 0, 0, 0]);
+    assert_eq!(nlm4_testres::unpack(&mut &v[..]).unwrap(), (res, 8));
+
+    match pack_to_vec(&nlm4_testrply::Stat(nlm4_stats::NLM4_DENIED)) {
+        Err(Error(ErrorKind::InvalidCase(1), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn res() {
+    let res = nlm4_res { cookie: netobj(vec![1]), stat: nlm4_stats::NLM4_DENIED_GRACE_PERIOD };
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 1,  1, 0, 0, 0,  0, 0, 0, 4]);
+    assert_eq!(nlm4_res::unpack(&mut &v[..]).unwrap(), (res, 12));
+
+    match nlm4_res::unpack(&mut &[0, 0, 0, 0,  0, 0, 0, 10][..]) {
+        Err(Error(ErrorKind::InvalidEnum(10), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn share() {
+    let args = nlm4_shareargs {
+        cookie: netobj(vec![]),
+        share: nlm4_share {
+            caller_name: "h".to_string(),
+            fh: netobj(vec![1]),
+            oh: netobj(vec![2]),
+            mode: fsh4_mode::fsm_DW,
+            access: fsh4_access::fsa_RW,
+        },
+        reclaim: true,
+    };
+    let v = pack_to_vec(&args).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 0,
+                       0, 0, 0, 1,  b'h', 0, 0, 0,
+                       0, 0, 0, 1,  1, 0, 0, 0,
+                       0, 0, 0, 1,  2, 0, 0, 0,
+                       0, 0, 0, 2,
+                       0, 0, 0, 3,
+                       0, 0, 0, 1]);
+    assert_eq!(nlm4_shareargs::unpack(&mut &v[..]).unwrap(), (args, v.len()));
+
+    let res = nlm4_shareres { cookie: netobj(vec![]), stat: nlm4_stats::NLM4_GRANTED, sequence: -1 };
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(nlm4_shareres::unpack(&mut &v[..]).unwrap(), (res, 12));
+}
+
+#[test]
+fn other_args() {
+    let args = nlm4_testargs { cookie: netobj(vec![]), exclusive: false, alock: lock() };
+    let v = pack_to_vec(&args).unwrap();
+    assert_eq!(nlm4_testargs::unpack(&mut &v[..]).unwrap(), (args, v.len()));
+
+    let args = nlm4_cancargs { cookie: netobj(vec![]), block: true, exclusive: false, alock: lock() };
+    let v = pack_to_vec(&args).unwrap();
+    assert_eq!(nlm4_cancargs::unpack(&mut &v[..]).unwrap(), (args, v.len()));
+
+    let args = nlm4_unlockargs { cookie: netobj(vec![]), alock: lock() };
+    let v = pack_to_vec(&args).unwrap();
+    assert_eq!(nlm4_unlockargs::unpack(&mut &v[..]).unwrap(), (args, v.len()));
+
+    let notify = nlm4_notify { name: "client".to_string(), state: 5 };
+    let v = pack_to_vec(&notify).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 6,  b'c', b'l', b'i', b'e',  b'n', b't', 0, 0,  0, 0, 0, 5]);
+    assert_eq!(nlm4_notify::unpack(&mut &v[..]).unwrap(), (notify, 16));
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "nsm")]
+extern crate xdr_codec;
+
+use xdr_codec::{Unpack, Error, ErrorKind, pack_to_vec};
+use xdr_codec::nsm::*;
+
+fn id() -> my_id {
+    my_id { my_name: "lo".to_string(), my_prog: 100021, my_vers: 4, my_proc: 16 }
+}
+
+#[test]
+fn monitor() {
+    let args = mon {
+        mon_id: mon_id { mon_name: "client".to_string(), my_id: id() },
+        priv_: *b"0123456789abcdef",
+    };
+    let v = pack_to_vec(&args).unwrap();
+    assert_eq!(&v[..12], &[0, 0, 0, 6,  b'c', b'l', b'i', b'e',  b'n', b't', 0, 0]);
+    assert_eq!(&v[12..32], &[0, 0, 0, 2,  b'l', b'o', 0, 0,
+                             0, 1, 0x86, 0xb5,
+                             0, 0, 0, 4,
+                             0, 0, 0, 16]);
+    assert_eq!(&v[32..], b"0123456789abcdef");
+    assert_eq!(mon::unpack(&mut &v[..]).unwrap(), (args, 48));
+
+    let res = sm_stat_res { res_stat: res::stat_succ, state: 3 };
+    let v = pack_to_vec(&res).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 0,  0, 0, 0, 3]);
+    assert_eq!(sm_stat_res::unpack(&mut &v[..]).unwrap(), (res, 8));
+
+    match sm_stat_res::unpack(&mut &[0, 0, 0, 2,  0, 0, 0, 3][..]) {
+        Err(Error(ErrorKind::InvalidEnum(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn unmonitor() {
+    let args = mon_id { mon_name: "client".to_string(), my_id: id() };
+    let v = pack_to_vec(&args).unwrap();
+    assert_eq!(mon_id::unpack(&mut &v[..]).unwrap(), (args, 32));
+
+    let v = pack_to_vec(&id()).unwrap();
+    assert_eq!(my_id::unpack(&mut &v[..]).unwrap(), (id(), 20));
+
+    let v = pack_to_vec(&sm_stat { state: 5 }).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 5]);
+    assert_eq!(sm_stat::unpack(&mut &v[..]).unwrap(), (sm_stat { state: 5 }, 4));
+
+    let name = sm_name { mon_name: "x".to_string() };
+    let v = pack_to_vec(&name).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 1,  b'x', 0, 0, 0]);
+    assert_eq!(sm_name::unpack(&mut &v[..]).unwrap(), (name, 8));
+}
+
+#[test]
+fn notify() {
+    let args = stat_chge { mon_name: "server".to_string(), state: 7 };
+    let v = pack_to_vec(&args).unwrap();
+    assert_eq!(v, vec![0, 0, 0, 6,  b's', b'e', b'r', b'v',  b'e', b'r', 0, 0,  0, 0, 0, 7]);
+    assert_eq!(stat_chge::unpack(&mut &v[..]).unwrap(), (args, 16));
+
+    let cb = status { mon_name: "server".to_string(), state: 7, priv_: [1; SM_PRIV_SIZE] };
+    let v = pack_to_vec(&cb).unwrap();
+    assert_eq!(v.len(), 16 + SM_PRIV_SIZE);
+    assert_eq!(status::unpack(&mut &v[..]).unwrap(), (cb, 32));
+
+    match pack_to_vec(&stat_chge { mon_name: "x".repeat(SM_MAXSTRLEN + 1), state: 1 }) {
+        Err(Error(ErrorKind::InvalidLen(SM_MAXSTRLEN), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
@@ -0,0 +1,187 @@
+#![cfg(feature = "nvlist")]
+extern crate xdr_codec;
+
+use xdr_codec::{Unpack, Error, ErrorKind, pack_to_vec};
+use xdr_codec::nvlist::*;
+
+#[test]
+fn layout() {
+    let mut nvl = NvList::new();
+    nvl.add("a", NvValue::Uint64(1));
+    nvl.add("flag", NvValue::Boolean);
+
+    let v = pack_nvlist(&nvl).unwrap();
+    assert_eq!(v[0], NV_ENCODE_XDR);
+    assert_eq!(&v[4..], &[0, 0, 0, 0,  0, 0, 0, 1,
+                          // "a": encode_size, decode_size, name, type, nelem, value
+                          0, 0, 0, 32,  0, 0, 0, 32,
+                          0, 0, 0, 1,  b'a', 0, 0, 0,
+                          0, 0, 0, 8,  0, 0, 0, 1,
+                          0, 0, 0, 0,  0, 0, 0, 1,
+                          // "flag"
+                          0, 0, 0, 24,  0, 0, 0, 24,
+                          0, 0, 0, 4,  b'f', b'l', b'a', b'g',
+                          0, 0, 0, 1,  0, 0, 0, 0,
+                          // end
+                          0, 0, 0, 0,  0, 0, 0, 0][..]);
+    assert_eq!(unpack_nvlist(&mut &v[..]).unwrap(), (nvl, v.len()));
+}
+
+#[test]
+fn all_types() {
+    let mut nvl = NvList::with_flags(0);
+    let values = vec![
+        NvValue::Boolean,
+        NvValue::BooleanValue(true),
+        NvValue::Byte(0xff),
+        NvValue::Int8(-2),
+        NvValue::Uint8(200),
+        NvValue::Int16(-300),
+        NvValue::Uint16(60000),
+        NvValue::Int32(-1),
+        NvValue::Uint32(0xffffffff),
+        NvValue::Int64(-5),
+        NvValue::Uint64(1 << 40),
+        NvValue::Double(1.5),
+        NvValue::Hrtime(123456789),
+        NvValue::String("hello".to_string()),
+        NvValue::ByteArray(vec![1, 2, 3, 4, 5]),
+        NvValue::BooleanArray(vec![true, false]),
+        NvValue::Int8Array(vec![-1, 1]),
+        NvValue::Uint8Array(vec![0, 255]),
+        NvValue::Int16Array(vec![-1000]),
+        NvValue::Uint16Array(vec![1, 2, 3]),
+        NvValue::Int32Array(vec![]),
+        NvValue::Uint32Array(vec![7]),
+        NvValue::Int64Array(vec![-1, 0, 1]),
+        NvValue::Uint64Array(vec![u64::MAX]),
+        NvValue::StringArray(vec!["x".to_string(), "".to_string(), "four".to_string()]),
+    ];
+    for (i, val) in values.iter().enumerate() {
+        nvl.add(format!("v{}", i), val.clone());
+    }
+
+    let v = pack_to_vec(&nvl).unwrap();
+    let (got, sz) = NvList::unpack(&mut &v[..]).unwrap();
+    assert_eq!(sz, v.len());
+    assert_eq!(got, nvl);
+    assert_eq!(got.len(), values.len());
+    assert_eq!(got.get("v14").unwrap().data_type(), data_type_t::DATA_TYPE_BYTE_ARRAY);
+}
+
+#[test]
+fn nested() {
+    let mut child = NvList::new();
+    child.add("guid", NvValue::Uint64(99));
+    child.add("path", NvValue::String("/dev/sda".to_string()));
+
+    let mut tree = NvList::new();
+    tree.add("type", NvValue::String("mirror".to_string()));
+    tree.add("children", NvValue::NvListArray(vec![child.clone(), child.clone()]));
+
+    let mut config = NvList::new();
+    config.add("vdev_tree", NvValue::NvList(tree));
+    config.add("empty", NvValue::NvList(NvList::new()));
+
+    let v = pack_to_vec(&config).unwrap();
+    // The outer pair's encode_size covers the nested lists
+    let (outer, _) = i32::unpack(&mut &v[8..]).unwrap();
+    let (after, _) = i32::unpack(&mut &v[8 + outer as usize..]).unwrap();
+    assert_eq!(after, 8 + 12 + 4 + 4 + 16);
+
+    let (got, sz) = NvList::unpack(&mut &v[..]).unwrap();
+    assert_eq!((&got, sz), (&config, v.len()));
+    match got.get("vdev_tree") {
+        Some(NvValue::NvList(tree)) => {
+            match tree.get("children") {
+                Some(NvValue::NvListArray(c)) => assert_eq!(c, &vec![child.clone(), child]),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn unique_names() {
+    let mut nvl = NvList::new();
+    nvl.add("a", NvValue::Uint32(1));
+    nvl.add("b", NvValue::Uint32(2));
+    nvl.add("a", NvValue::String("x".to_string()));
+    assert_eq!(nvl.iter().map(|(n, _)| n).collect::<Vec<_>>(), vec!["b", "a"]);
+
+    let mut nvl = NvList::with_flags(NV_UNIQUE_NAME_TYPE);
+    nvl.add("a", NvValue::Uint32(1));
+    nvl.add("a", NvValue::String("x".to_string()));
+    nvl.add("a", NvValue::Uint32(2));
+    assert_eq!(nvl.len(), 2);
+    assert_eq!(nvl.get("a"), Some(&NvValue::String("x".to_string())));
+
+    assert_eq!(nvl.remove("a"), Some(NvValue::String("x".to_string())));
+    assert!(nvl.is_empty());
+    assert_eq!(nvl.remove("a"), None);
+}
+
+#[test]
+fn bad_input() {
+    let mut nvl = NvList::new();
+    nvl.add("a", NvValue::Uint32(1));
+    let v = pack_nvlist(&nvl).unwrap();
+
+    let mut bad = v.clone();
+    bad[0] = 0;
+    match unpack_nvlist(&mut &bad[..]) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Wrong encode_size
+    let mut bad = v.clone();
+    bad[15] += 4;
+    match unpack_nvlist(&mut &bad[..]) {
+        Err(Error(ErrorKind::InvalidLen(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Negative encode_size
+    let mut bad = v.clone();
+    bad[12] = 0x80;
+    match unpack_nvlist(&mut &bad[..]) {
+        Err(Error(ErrorKind::InvalidLen(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Scalar with nelem 2
+    let mut bad = v.clone();
+    bad[35] = 2;
+    match unpack_nvlist(&mut &bad[..]) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Unknown type
+    let mut bad = v.clone();
+    bad[31] = 0;
+    match unpack_nvlist(&mut &bad[..]) {
+        Err(Error(ErrorKind::InvalidEnum(0), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Missing terminator
+    assert!(unpack_nvlist(&mut &v[..v.len() - 8]).is_err());
+}
+
+#[test]
+fn huge_nelem() {
+    let mut nvl = NvList::new();
+    nvl.add("a", NvValue::ByteArray(vec![1, 2, 3]));
+    let v = pack_nvlist(&nvl).unwrap();
+
+    // A byte array claiming 2GB, with only a few bytes behind it
+    let mut bad = v.clone();
+    bad[32..36].copy_from_slice(&[0x7f, 0xff, 0xff, 0xff]);
+    match unpack_nvlist(&mut &bad[..]) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
@@ -0,0 +1,124 @@
+extern crate xdr_codec;
+
+use std::borrow::Cow;
+use std::io::{self, Cursor};
+use std::panic;
+
+use xdr_codec::{Error, ErrorKind, DecodeOptions, decode_options, with_decode_options, unpack,
+                unpack_string, unpack_from_bytes, with_input_limit};
+use xdr_codec::borrowed::{unpack_ref, unpack_str_borrowed};
+use xdr_codec::stream::XdrReader;
+
+const BAD: [u8; 8] = [0, 0, 0, 3, b'a', 0xff, b'b', 0];
+
+#[test]
+fn strict_default() {
+    assert!(!decode_options().is_lossy_strings());
+
+    match unpack::<_, String>(&mut &BAD[..]) {
+        Err(Error(ErrorKind::InvalidUtf8(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn lossy_strings() {
+    let lossy = DecodeOptions::new().lossy_strings(true);
+
+    with_decode_options(lossy, || {
+        assert!(decode_options().is_lossy_strings());
+
+        let s: String = unpack(&mut &BAD[..]).unwrap();
+        assert_eq!(s, "a\u{fffd}b");
+
+        let (s, sz) = unpack_string(&mut &BAD[..], Some(3)).unwrap();
+        assert_eq!((s.as_str(), sz), ("a\u{fffd}b", 8));
+
+        // Nested inside other types
+        let v: Vec<Option<String>> = unpack(&mut &[0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0, 3, b'a', 0xff, b'b', 0][..]).unwrap();
+        assert_eq!(v, vec![Some("a\u{fffd}b".to_string())]);
+
+        // Cow<str> is repaired into an owned copy; valid strings are still borrowed
+        match unpack_ref::<Cow<str>>(&mut &BAD[..]).unwrap() {
+            Cow::Owned(ref s) if s == "a\u{fffd}b" => (),
+            res => panic!("bad result {:?}", res),
+        }
+        match unpack_ref::<Cow<str>>(&mut &[0, 0, 0, 2, b'o', b'k', 0, 0][..]).unwrap() {
+            Cow::Borrowed("ok") => (),
+            res => panic!("bad result {:?}", res),
+        }
+
+        // &str can't be
+        match unpack_str_borrowed(&mut &BAD[..], None) {
+            Err(Error(ErrorKind::InvalidUtf8(_), _)) => (),
+            res => panic!("bad result {:?}", res),
+        }
+    });
+
+    assert!(!decode_options().is_lossy_strings());
+}
+
+#[test]
+fn options_restored() {
+    let lossy = DecodeOptions::new().lossy_strings(true);
+
+    let res = panic::catch_unwind(|| with_decode_options(lossy, || panic!("oops")));
+    assert!(res.is_err());
+    assert_eq!(decode_options(), DecodeOptions::default());
+
+    // Nesting restores the outer options
+    with_decode_options(lossy, || {
+        with_decode_options(DecodeOptions::new(), || assert!(!decode_options().is_lossy_strings()));
+        assert!(decode_options().is_lossy_strings());
+    });
+}
+
+#[test]
+fn reader_options() {
+    let mut enc = BAD.to_vec();
+    enc.extend_from_slice(&BAD);
+
+    let mut r = XdrReader::new(&enc[..]);
+    assert!(r.unpack::<String>().is_err());
+
+    let mut r = XdrReader::new(&enc[..]);
+    r.set_decode_options(DecodeOptions::new().lossy_strings(true));
+    assert_eq!(r.unpack::<String>().unwrap(), "a\u{fffd}b");
+    assert_eq!(r.unpack::<String>().unwrap(), "a\u{fffd}b");
+    assert_eq!(r.position(), 16);
+    assert!(!decode_options().is_lossy_strings());
+}
+
+fn short_input<T: ::std::fmt::Debug>(res: xdr_codec::Result<T>) {
+    match res {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn input_limit() {
+    // Lengths which can't fit in what's there
+    let enc = [0x10, 0, 0, 0,  0, 0, 0, 1];
+    short_input(unpack_from_bytes::<Vec<u32>>(&enc));
+    short_input(unpack_from_bytes::<String>(&enc));
+    short_input(unpack_from_bytes::<Cow<[u8]>>(&enc));
+    short_input(unpack_ref::<Vec<u32>>(&mut &enc[..]));
+
+    // Zero-sized elements take no space
+    let (v, sz) = unpack_from_bytes::<Vec<()>>(&[0, 0, 0, 3]).unwrap();
+    assert_eq!((v.len(), sz), (3, 4));
+
+    // Only set within the closure
+    let enc = [0, 0, 0, 2,  0, 0, 0, 1,  0, 0, 0, 2];
+    short_input(with_input_limit(7, || unpack::<_, Vec<u32>>(&mut Cursor::new(&enc[..]))));
+    let v: Vec<u32> = unpack(&mut Cursor::new(&enc[..])).unwrap();
+    assert_eq!(v, [1, 2]);
+
+    // Checked against what's left, not the whole input: the second inner array's element would
+    // start at byte 16
+    let enc = [0, 0, 0, 2,  0, 0, 0, 1,  0, 0, 0, 9,  0, 0, 0, 1,  0, 0, 0, 9];
+    short_input(with_input_limit(16, || unpack::<_, Vec<Vec<u32>>>(&mut Cursor::new(&enc[..]))));
+    let v: Vec<Vec<u32>> = with_input_limit(20, || unpack(&mut Cursor::new(&enc[..]))).unwrap();
+    assert_eq!(v, [[9], [9]]);
+}
@@ -0,0 +1,74 @@
+extern crate xdr_codec;
+
+use std::io::Cursor;
+use std::ffi::CString;
+
+use xdr_codec::{Pack, Unpack, Error, ErrorKind};
+use xdr_codec::os::unpack_cstring;
+
+#[cfg(unix)]
+#[test]
+fn os_string() {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt;
+    use std::path::PathBuf;
+    use xdr_codec::os::{pack_os_str, unpack_os_string};
+
+    // not valid UTF-8
+    let name = OsString::from_vec(vec![b'f', 0xff, b'o']);
+    let expected = vec![0, 0, 0, 3,  b'f', 0xff, b'o', 0];
+
+    let mut out = Cursor::new(Vec::new());
+    assert_eq!(name.pack(&mut out).unwrap(), 8);
+    let v = out.into_inner();
+    assert_eq!(v, expected);
+
+    let mut input = Cursor::new(v.clone());
+    assert!(String::unpack(&mut input).is_err());
+
+    let mut input = Cursor::new(v.clone());
+    assert_eq!(OsString::unpack(&mut input).unwrap(), (name.clone(), 8));
+
+    let path = PathBuf::from(name.clone());
+    let mut out = Cursor::new(Vec::new());
+    assert_eq!(path.pack(&mut out).unwrap(), 8);
+    assert_eq!(out.into_inner(), expected);
+
+    let mut input = Cursor::new(v.clone());
+    assert_eq!(PathBuf::unpack(&mut input).unwrap(), (path, 8));
+
+    let mut out = Cursor::new(Vec::new());
+    match pack_os_str(&name, Some(2), &mut out) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let mut input = Cursor::new(v);
+    match unpack_os_string(&mut input, Some(2)) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn cstring() {
+    let s = CString::new(vec![b'a', 0x80, b'c', b'd', b'e']).unwrap();
+
+    let mut out = Cursor::new(Vec::new());
+    assert_eq!(s.pack(&mut out).unwrap(), 12);
+    let v = out.into_inner();
+    assert_eq!(v, vec![0, 0, 0, 5,  b'a', 0x80, b'c', b'd',  b'e', 0, 0, 0]);
+
+    let mut input = Cursor::new(v);
+    assert_eq!(CString::unpack(&mut input).unwrap(), (s, 12));
+}
+
+#[test]
+fn cstring_nul() {
+    let mut input = Cursor::new(vec![0, 0, 0, 3,  b'a', 0, b'c', 0]);
+
+    match unpack_cstring(&mut input, None) {
+        Err(Error(ErrorKind::InvalidCString(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
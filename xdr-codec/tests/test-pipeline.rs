@@ -0,0 +1,215 @@
+#![cfg(feature = "pipeline")]
+extern crate xdr_codec;
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use xdr_codec::{Error, ErrorKind, Pack, Result, pack_to_vec, unpack_complete};
+use xdr_codec::buffer::split_records;
+use xdr_codec::pipeline::{EncodePipeline, PipelineOptions};
+
+// Packing takes longer for some values than others, so the workers finish out of order
+fn value(i: u32) -> Vec<u32> {
+    (0..(i * 37) % 200).map(|j| i ^ j).collect()
+}
+
+#[test]
+fn ordered() {
+    let mut expect = Vec::new();
+    let mut pipe = EncodePipeline::with_options(Vec::new(), PipelineOptions::new().workers(4).queue_len(3));
+
+    for i in 0..500 {
+        expect.extend(pack_to_vec(&value(i)).unwrap());
+        pipe.send(value(i)).unwrap();
+    }
+    assert_eq!(pipe.sent(), 500);
+    assert_eq!(pipe.finish().unwrap(), expect);
+}
+
+#[test]
+fn records() {
+    let mut pipe = EncodePipeline::with_options(Vec::new(), PipelineOptions::new().record_marking(true));
+    for i in 0..100 {
+        pipe.send(value(i)).unwrap();
+    }
+
+    let recs = split_records(&pipe.finish().unwrap()).unwrap();
+    assert_eq!(recs.len(), 100);
+    for (i, rec) in recs.iter().enumerate() {
+        assert_eq!(unpack_complete::<Vec<u32>>(rec).unwrap(), value(i as u32));
+    }
+}
+
+#[cfg(feature = "little_endian")]
+#[test]
+fn byte_order() {
+    use xdr_codec::endian::Le;
+
+    // The byte order goes with the values to the workers
+    let mut pipe = EncodePipeline::with_options(Vec::new(), PipelineOptions::new().workers(4));
+    let mut expect = Vec::new();
+    for i in 0..100 {
+        expect.extend(pack_to_vec(&Le(value(i))).unwrap());
+        pipe.send(Le(value(i))).unwrap();
+    }
+    let out = pipe.finish().unwrap();
+    assert_eq!(out, expect);
+    let big: Vec<u8> = (0..100).flat_map(|i| pack_to_vec(&value(i)).unwrap()).collect();
+    assert_ne!(out, big);
+}
+
+#[test]
+fn empty() {
+    let pipe: EncodePipeline<u32, _> = EncodePipeline::new(Vec::new());
+    assert!(pipe.finish().unwrap().is_empty());
+}
+
+// Fails to pack odd numbers
+struct Even(u32);
+
+impl<Out: Write> Pack<Out> for Even {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        if self.0 % 2 == 1 {
+            return Err(ErrorKind::InvalidValue(format!("{} is odd", self.0)).into());
+        }
+        self.0.pack(out)
+    }
+}
+
+#[test]
+fn pack_error() {
+    let mut pipe = EncodePipeline::with_options(Vec::new(), PipelineOptions::new().workers(2).queue_len(1));
+    pipe.send(Even(0)).unwrap();
+    pipe.send(Even(1)).unwrap();
+
+    // Eventually the pipeline notices it's stopped
+    let mut stopped = false;
+    for _ in 0..100 {
+        match pipe.send(Even(2)) {
+            Ok(()) => (),
+            Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::BrokenPipe => {
+                stopped = true;
+                break;
+            },
+            res => panic!("bad result {:?}", res),
+        }
+        ::std::thread::sleep(::std::time::Duration::from_millis(1));
+    }
+    assert!(stopped);
+
+    match pipe.finish() {
+        Err(Error(ErrorKind::InvalidValue(ref msg), _)) if msg == "1 is odd" => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+// Packs slowly, or fails straight away
+struct Slow(u32, bool);
+
+impl<Out: Write> Pack<Out> for Slow {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        if self.1 {
+            return Err(ErrorKind::InvalidValue(format!("{} failed", self.0)).into());
+        }
+        ::std::thread::sleep(::std::time::Duration::from_millis(50));
+        self.0.pack(out)
+    }
+}
+
+// Panics when packing odd numbers
+struct Panicky(u32);
+
+impl<Out: Write> Pack<Out> for Panicky {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        if self.0 % 2 == 1 {
+            panic!("{} is odd", self.0);
+        }
+        self.0.pack(out)
+    }
+}
+
+// A writer whose output can be checked after the pipeline has failed
+#[derive(Clone, Default)]
+struct Shared(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn pack_error_out_of_order() {
+    // The second value fails while the first is still being packed, but the first is written
+    let out = Shared::default();
+    let mut pipe = EncodePipeline::with_options(out.clone(), PipelineOptions::new().workers(2));
+    pipe.send(Slow(1, false)).unwrap();
+    pipe.send(Slow(2, true)).unwrap();
+    let _ = pipe.send(Slow(3, false));
+
+    match pipe.finish() {
+        Err(Error(ErrorKind::InvalidValue(ref msg), _)) if msg == "2 failed" => (),
+        res => panic!("bad result {:?}", res.map(|_| ())),
+    }
+    assert_eq!(*out.0.lock().unwrap(), [0, 0, 0, 1]);
+}
+
+#[test]
+fn pack_panic() {
+    // The panic is reported as an error rather than leaving the pipeline waiting for the value
+    let out = Shared::default();
+    let mut pipe = EncodePipeline::with_options(out.clone(), PipelineOptions::new().workers(2).queue_len(1));
+    for i in 0..10 {
+        let _ = pipe.send(Panicky(i * 2 + (i == 3) as u32));
+    }
+
+    match pipe.finish() {
+        Err(Error(ErrorKind::Msg(ref msg), _)) if msg == "panicked while packing: 7 is odd" => (),
+        res => panic!("bad result {:?}", res.map(|_| ())),
+    }
+    assert_eq!(*out.0.lock().unwrap(), [0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 4]);
+
+    // Nor does dropping it hang
+    let mut pipe = EncodePipeline::new(Vec::new());
+    let _ = pipe.send(Panicky(1));
+    drop(pipe);
+}
+
+// Accepts a limited number of bytes
+struct Short(usize);
+
+impl Write for Short {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.0 == 0 {
+            return Err(io::Error::other("full"));
+        }
+        let sz = buf.len().min(self.0);
+        self.0 -= sz;
+        Ok(sz)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn write_error() {
+    let mut pipe = EncodePipeline::new(Short(10));
+    for i in 0..5u32 {
+        let _ = pipe.send(i);
+    }
+    match pipe.finish() {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::Other => (),
+        res => panic!("bad result {:?}", res.map(|_| ())),
+    }
+
+    // Dropping without finishing waits for the threads
+    let mut pipe = EncodePipeline::new(Vec::new());
+    pipe.send(1u32).unwrap();
+    drop(pipe);
+}
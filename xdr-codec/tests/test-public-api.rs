@@ -0,0 +1,119 @@
+//! Check the stable public surface exported by `xdr_codec::prelude`.
+//!
+//! `public_api` compares the crate's whole public API (with the default features), as listed by
+//! cargo-public-api from the rustdoc JSON, against the snapshot in `public-api.txt`. Building the
+//! JSON needs a nightly toolchain. After a deliberate API change, regenerate the snapshot with
+//! `UPDATE_EXPECT=1 cargo test --test test-public-api` and review the diff.
+//!
+//! The rest pin the signatures of everything generated code depends on by coercing each function
+//! to an explicit function pointer type and implementing the traits in the way xdrgen does. Any
+//! incompatible change to the prelude fails to compile.
+extern crate expect_test;
+extern crate public_api;
+extern crate rustdoc_json;
+extern crate xdr_codec;
+
+use std::io::Cursor;
+use xdr_codec::prelude::*;
+
+type Out = Vec<u8>;
+type In = Cursor<Vec<u8>>;
+
+#[derive(Debug, PartialEq)]
+struct Generated {
+    a: u32,
+    b: Vec<u8>,
+}
+
+impl<Out: Write> Pack<Out> for Generated {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        Ok(self.a.pack(out)? + pack_opaque_flex(&self.b, Some(16), out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for Generated {
+    fn unpack(input: &mut In) -> Result<(Generated, usize)> {
+        let mut sz = 0;
+        Ok((Generated {
+            a: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            b: { let (v, fsz) = unpack_opaque_flex(input, Some(16))?; sz += fsz; v },
+        }, sz))
+    }
+}
+
+#[test]
+fn public_api() {
+    let json = rustdoc_json::Builder::default()
+        .toolchain("nightly")
+        .manifest_path(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"))
+        .build()
+        .expect("building rustdoc JSON (is a nightly toolchain installed?)");
+    let api = public_api::Builder::from_rustdoc_json(json).build().unwrap();
+
+    expect_test::expect_file!["public-api.txt"].assert_eq(&api.to_string());
+}
+
+// The signatures are spelled out in full on purpose
+#[test]
+#[allow(clippy::type_complexity)]
+fn signatures() {
+    let _: fn(&u32, &mut Out) -> Result<()> = pack::<Out, u32>;
+    let _: fn(&mut In) -> Result<u32> = unpack::<In, u32>;
+    let _: fn(usize) -> &'static [u8] = padding;
+
+    let _: fn(&[u32], usize, &mut Out, Option<&u32>) -> Result<usize> = pack_array::<Out, u32>;
+    let _: fn(&[u8], usize, &mut Out) -> Result<usize> = pack_opaque_array::<Out>;
+    let _: fn(&[u32], Option<usize>, &mut Out) -> Result<usize> = pack_flex::<Out, u32>;
+    let _: fn(&[u8], Option<usize>, &mut Out) -> Result<usize> = pack_opaque_flex::<Out>;
+    let _: fn(&str, Option<usize>, &mut Out) -> Result<usize> = pack_string::<Out>;
+
+    let _: fn(&mut In, &mut [u32], usize, Option<&u32>) -> Result<usize> =
+        unpack_array::<In, u32>;
+    let _: fn(&mut In, &mut [u8], usize) -> Result<usize> = unpack_opaque_array::<In>;
+    let _: fn(&mut In, Option<usize>) -> Result<(Vec<u32>, usize)> = unpack_flex::<In, u32>;
+    let _: fn(&mut In, Option<usize>) -> Result<(Vec<u8>, usize)> = unpack_opaque_flex::<In>;
+    let _: fn(&mut In, Option<usize>) -> Result<(String, usize)> = unpack_string::<In>;
+
+    let _: fn(i32) -> Error = Error::invalidcase;
+    let _: fn(i32) -> Error = Error::invalidenum;
+    let _: fn(usize) -> Error = Error::invalidlen;
+    let _: fn(Vec<u8>) -> Opaque<'static> = Opaque::owned;
+}
+
+#[test]
+fn error_kinds() {
+    fn kind(e: &ErrorKind) -> &'static str {
+        match *e {
+            ErrorKind::InvalidCase(_) => "case",
+            ErrorKind::InvalidEnum(_) => "enum",
+            ErrorKind::InvalidLen(_) => "len",
+            _ => "other",
+        }
+    }
+
+    assert_eq!(kind(Error::invalidcase(1).kind()), "case");
+    assert_eq!(kind(Error::invalidenum(1).kind()), "enum");
+    assert_eq!(kind(Error::invalidlen(1).kind()), "len");
+}
+
+#[test]
+fn primitive_sizes() {
+    fn size<T: XdrPrimitive + Pack<Out>>(v: T) -> (usize, usize) {
+        let mut out = Vec::new();
+        (T::XDR_SIZE, v.pack(&mut out).unwrap())
+    }
+
+    for &(want, got) in &[size(1u32), size(1i32), size(1u64), size(1i64),
+                          size(1f32), size(1f64), size(true)] {
+        assert_eq!(want, got);
+    }
+}
+
+#[test]
+fn generated_roundtrip() {
+    let v = Generated { a: 7, b: vec![1, 2, 3] };
+    let mut out = Vec::new();
+
+    assert_eq!(v.pack(&mut out).unwrap(), 12);
+    assert_eq!(unpack::<_, Generated>(&mut Cursor::new(&out[..])).unwrap(), v);
+}
@@ -0,0 +1,83 @@
+extern crate xdr_codec;
+
+use std::io::Cursor;
+use std::f64;
+
+use xdr_codec::{Pack, Unpack};
+use xdr_codec::quadruple::XdrQuadruple;
+
+fn quad(hi: u64, lo: u64) -> XdrQuadruple {
+    XdrQuadruple::from_bits((hi as u128) << 64 | lo as u128)
+}
+
+#[test]
+fn from_f64() {
+    assert_eq!(XdrQuadruple::from(0.0), quad(0, 0));
+    assert_eq!(XdrQuadruple::from(-0.0), quad(0x8000_0000_0000_0000, 0));
+    assert_eq!(XdrQuadruple::from(1.0), quad(0x3fff_0000_0000_0000, 0));
+    assert_eq!(XdrQuadruple::from(-2.0), quad(0xc000_0000_0000_0000, 0));
+    assert_eq!(XdrQuadruple::from(1.0 / 3.0), quad(0x3ffd_5555_5555_5555, 0x5000_0000_0000_0000));
+    assert_eq!(XdrQuadruple::from(f64::INFINITY), quad(0x7fff_0000_0000_0000, 0));
+    assert_eq!(XdrQuadruple::from(f64::NEG_INFINITY), quad(0xffff_0000_0000_0000, 0));
+    // smallest f64 subnormal is 2^-1074
+    assert_eq!(XdrQuadruple::from(f64::from_bits(1)), quad(((16383 - 1074) as u64) << 48, 0));
+}
+
+#[test]
+fn to_f64() {
+    assert_eq!(quad(0x3fff_0000_0000_0000, 0).to_f64(), 1.0);
+    assert_eq!(quad(0x8000_0000_0000_0000, 0).to_f64().to_bits(), (-0.0f64).to_bits());
+    assert!(quad(0x7fff_8000_0000_0000, 0).to_f64().is_nan());
+    assert!(quad(0x7fff_0000_0000_0000, 1).to_f64().is_nan());
+    assert_eq!(quad(0x7fff_0000_0000_0000, 0).to_f64(), f64::INFINITY);
+
+    // out of range
+    assert_eq!(quad(0x7ffe_0000_0000_0000, 0).to_f64(), f64::INFINITY);
+    assert_eq!(quad(0xc3ff_0000_0000_0000, 0).to_f64(), f64::NEG_INFINITY);
+    assert_eq!(quad(0x0001_0000_0000_0000, 0).to_f64(), 0.0);
+    assert_eq!(quad(0x0000_0000_0000_0001, 0).to_f64(), 0.0);
+
+    // 1 + 2^-53 is a tie which rounds to even (1.0); 1 + 2^-53 + 2^-112 rounds up
+    assert_eq!(quad(0x3fff_0000_0000_0000, 0x0800_0000_0000_0000).to_f64(), 1.0);
+    assert_eq!(quad(0x3fff_0000_0000_0000, 0x0800_0000_0000_0001).to_f64(), 1.0 + f64::EPSILON);
+    // 1 + 3 * 2^-53 is a tie which rounds to even (1 + 2^-51)
+    assert_eq!(quad(0x3fff_0000_0000_0000, 0x1800_0000_0000_0000).to_f64(), 1.0 + 2.0 * f64::EPSILON);
+
+    // rounding up out of range
+    assert_eq!(quad(0x43fe_ffff_ffff_ffff, 0xffff_ffff_ffff_ffff).to_f64(), f64::INFINITY);
+
+    // subnormal results
+    assert_eq!(quad(((16383 - 1074) as u64) << 48, 0).to_f64(), f64::from_bits(1));
+    assert_eq!(quad(((16383 - 1075) as u64) << 48, 0).to_f64(), 0.0);
+    assert_eq!(quad(((16383 - 1075) as u64) << 48, 1).to_f64(), f64::from_bits(1));
+    assert_eq!(quad(((16383 - 1023) as u64) << 48, 0).to_f64(), f64::MIN_POSITIVE / 2.0);
+}
+
+#[test]
+fn f64_roundtrip() {
+    let vals = [0.0, 1.0, -1.5, f64::consts::PI, 1e300, -1e-300, f64::MAX, f64::MIN,
+                f64::MIN_POSITIVE, f64::MIN_POSITIVE / 3.0, f64::from_bits(1), f64::EPSILON,
+                f64::INFINITY, f64::NEG_INFINITY];
+
+    for v in &vals {
+        let q = XdrQuadruple::from(*v);
+        assert_eq!(f64::from(q).to_bits(), v.to_bits(), "{}", v);
+    }
+    assert!(XdrQuadruple::from(f64::NAN).to_f64().is_nan());
+}
+
+#[test]
+fn codec() {
+    let q = XdrQuadruple::from(-2.5);
+    let mut out = Cursor::new(Vec::new());
+
+    assert_eq!(q.pack(&mut out).unwrap(), 16);
+    let v = out.into_inner();
+    assert_eq!(v, vec![0xc0, 0x00, 0x40, 0, 0, 0, 0, 0,  0, 0, 0, 0, 0, 0, 0, 0]);
+
+    let mut input = Cursor::new(v);
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (q, 16));
+
+    let mut input = Cursor::new(vec![0; 15]);
+    assert!(XdrQuadruple::unpack(&mut input).is_err());
+}
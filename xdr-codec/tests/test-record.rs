@@ -1,7 +1,9 @@
 extern crate xdr_codec;
 
-use std::io::{Read, Write, Cursor};
-use xdr_codec::record::{XdrRecordReader, XdrRecordWriter};
+use std::io::{self, Read, BufRead, Write, Cursor, BufReader};
+use xdr_codec::{Error, ErrorKind};
+use xdr_codec::record::{XdrRecordReader, XdrRecordWriter, XdrRecordSeekWriter, RecordStats,
+                        RecordFragment};
 
 #[test]
 fn recread_full() {
@@ -65,6 +67,385 @@ fn recread_iter() {
     assert_eq!(expected, got);
 }
 
+#[test]
+fn recread_records() {
+    let inbuf = vec![ 0, 0, 0, 2,  1, 2,  0x80, 0, 0, 1,  3,
+                      0x80, 0, 0, 0,
+                      0x80, 0, 0, 3,  4, 5, 6,
+                      0, 0, 0, 3,  7, 8 ];
+    let mut recread = XdrRecordReader::new(Cursor::new(inbuf));
+
+    {
+        let mut records = recread.records();
+        assert_eq!(records.next().unwrap().unwrap(), vec![1, 2, 3]);
+        assert_eq!(records.next().unwrap().unwrap(), Vec::<u8>::new());
+    }
+
+    // Reader can still be used afterwards
+    let mut buf = [0; 3];
+    recread.read_exact(&mut buf).unwrap();
+    assert_eq!(buf, [4, 5, 6]);
+
+    // Truncated final record isn't returned
+    let rest: Vec<_> = recread.records().collect();
+    assert!(rest.is_empty());
+    assert_eq!(recread.stats().records, 3);
+}
+
+#[test]
+fn recread_fragments() {
+    let inbuf = vec![ 0, 0, 0, 2,  1, 2,  0, 0, 0, 0,  0x80, 0, 0, 1,  3,
+                      0x80, 0, 0, 0,
+                      0, 0, 0, 3,  4, 5 ];
+    let mut recread = XdrRecordReader::new(Cursor::new(inbuf));
+
+    let mut b = [0];
+    recread.read_exact(&mut b).unwrap();
+    assert_eq!((recread.fragment_size(), recread.fragment_remaining()), (2, 1));
+
+    let frags: Vec<_> = recread.fragments().collect();
+    assert_eq!(frags.len(), 4);
+    let frags: Vec<_> = frags.into_iter().map(|f| f.unwrap()).collect();
+    assert_eq!(frags, vec![ RecordFragment { data: vec![2], eor: false },
+                            RecordFragment { data: vec![], eor: false },
+                            RecordFragment { data: vec![3], eor: true },
+                            RecordFragment { data: vec![], eor: true } ]);
+
+    // Truncated fragment isn't returned, but the header was read
+    assert_eq!(recread.fragment_size(), 3);
+    assert_eq!(recread.stats().fragments, 5);
+}
+
+// Reader which returns each chunk in turn, with `WouldBlock` for empty chunks
+struct NonBlocking(Vec<Vec<u8>>);
+
+impl Read for NonBlocking {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() {
+            return Ok(0);
+        }
+        let chunk = self.0.remove(0);
+        if chunk.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::WouldBlock, "would block"));
+        }
+        assert!(chunk.len() <= buf.len());
+        buf[..chunk.len()].copy_from_slice(&chunk);
+        Ok(chunk.len())
+    }
+}
+
+fn would_block<T: ::std::fmt::Debug>(res: Option<io::Result<T>>) {
+    match res {
+        Some(Err(ref e)) if e.kind() == io::ErrorKind::WouldBlock => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn recread_nonblocking() {
+    let chunks = vec![ vec![0, 0], vec![], vec![0, 2, 1], vec![], vec![2, 0x80], vec![],
+                       vec![0, 0, 1, 3], vec![0x80, 0, 0, 2, 4], vec![], vec![5] ];
+    let mut recread = XdrRecordReader::new(BufReader::new(NonBlocking(chunks)));
+
+    {
+        let mut records = recread.records();
+        would_block(records.next());    // in first header
+        would_block(records.next());    // in first fragment body
+        would_block(records.next());    // in second header
+        assert_eq!(records.next().unwrap().unwrap(), vec![1, 2, 3]);
+        would_block(records.next());    // in second record body
+        assert_eq!(records.next().unwrap().unwrap(), vec![4, 5]);
+        assert!(records.next().is_none());
+    }
+    assert_eq!(recread.stats(), RecordStats { records: 2, fragments: 3, bytes: 5, errors: 0 });
+
+    let chunks = vec![ vec![0x80, 0], vec![], vec![0, 3, 1], vec![], vec![2, 3] ];
+    let mut recread = XdrRecordReader::new(BufReader::new(NonBlocking(chunks)));
+    {
+        let mut frags = recread.fragments();
+        would_block(frags.next());
+        would_block(frags.next());
+        assert_eq!(frags.next().unwrap().unwrap(), RecordFragment { data: vec![1, 2, 3], eor: true });
+    }
+
+    // Plain reads can be retried too
+    let chunks = vec![ vec![0x80], vec![], vec![0, 0, 2, 7], vec![], vec![8] ];
+    let mut recread = XdrRecordReader::new(BufReader::new(NonBlocking(chunks)));
+    let mut buf = [0; 2];
+    assert_eq!(recread.read(&mut buf).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+    assert_eq!(recread.read(&mut buf).unwrap(), 1);
+    assert_eq!(recread.read(&mut buf[1..]).unwrap_err().kind(), io::ErrorKind::WouldBlock);
+    assert_eq!(recread.read(&mut buf[1..]).unwrap(), 1);
+    assert_eq!(buf, [7, 8]);
+}
+
+#[test]
+fn recread_into_inner() {
+    // A record followed by data for some other protocol
+    let inbuf = vec![0x80, 0, 0, 2,  1, 2,  b'h', b'i', b'!'];
+    let mut recread = XdrRecordReader::new(BufReader::with_capacity(4, Cursor::new(inbuf)));
+
+    assert_eq!(recread.records().next().unwrap().unwrap(), vec![1, 2]);
+    let (mut rd, leftover) = recread.into_inner();
+    assert!(leftover.is_empty());
+
+    let mut rest = Vec::new();
+    rd.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b"hi!");
+
+    // Partially read header
+    let chunks = vec![ vec![0x80, 0, 0, 2,  1, 2,  0x80], vec![], vec![0, 0, 1,  3] ];
+    let mut recread = XdrRecordReader::new(BufReader::new(NonBlocking(chunks)));
+    {
+        let mut records = recread.records();
+        assert_eq!(records.next().unwrap().unwrap(), vec![1, 2]);
+        would_block(records.next());
+    }
+    let (mut rd, leftover) = recread.into_inner();
+    assert_eq!(leftover, vec![0x80]);
+    let mut rest = Vec::new();
+    rd.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, vec![0, 0, 1,  3]);
+
+    // Interrupted record, then part of the next fragment header
+    let chunks = vec![ vec![0, 0, 0, 2,  1, 2,  0x80, 0], vec![], vec![0, 1,  3] ];
+    let mut recread = XdrRecordReader::new(BufReader::new(NonBlocking(chunks)));
+    would_block(recread.records().next());
+    let (_, leftover) = recread.into_inner();
+    assert_eq!(leftover, vec![1, 2,  0x80, 0]);
+}
+
+#[test]
+fn recread_bufread() {
+    let inbuf = vec![0, 0, 0, 3,  b'a', b'b', b'\n',  0, 0, 0, 2,  b'c', b'd',  0x80, 0, 0, 2,  b'\n', b'e'];
+    let mut recread = XdrRecordReader::new(Cursor::new(inbuf));
+
+    // fill_buf stops at the end of the fragment
+    assert_eq!(recread.fill_buf().unwrap(), b"ab\n");
+    recread.consume(1);
+    assert_eq!(recread.fill_buf().unwrap(), b"b\n");
+    recread.consume(2);
+    assert_eq!(recread.fill_buf().unwrap(), b"cd");
+    assert!(!recread.eor());
+
+    // but line reading carries on through fragments
+    let mut line = String::new();
+    recread.read_line(&mut line).unwrap();
+    assert_eq!(line, "cd\n");
+    assert!(recread.eor());
+    line.clear();
+    recread.read_line(&mut line).unwrap();
+    assert_eq!(line, "e");
+    assert_eq!(recread.fill_buf().unwrap(), b"");
+}
+
+// Reader which counts the reads made of it
+struct Reads<R>(R, usize);
+
+impl<R: Read> Read for Reads<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.1 += 1;
+        self.0.read(buf)
+    }
+}
+
+#[test]
+fn recread_unpack_buffered() {
+    let mut enc = Vec::new();
+    {
+        let mut xw = XdrRecordWriter::with_fragment_size(&mut enc, 400);
+        for i in 0..1000u32 {
+            xdr_codec::pack(&i, &mut xw).unwrap();
+        }
+    }
+
+    let mut recread = XdrRecordReader::new(BufReader::new(Reads(Cursor::new(enc), 0)));
+    for i in 0..1000u32 {
+        assert_eq!(xdr_codec::unpack::<_, u32>(&mut recread).unwrap(), i);
+    }
+    assert!(recread.eor());
+
+    // 4040 bytes fit in the default buffer: one read for the data, and one for EOF
+    let (rd, _) = recread.into_inner();
+    assert!(rd.get_ref().1 <= 2, "{} reads", rd.get_ref().1);
+}
+
+fn limit_kind(err: &io::Error) -> &ErrorKind {
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    err.get_ref().and_then(|e| e.downcast_ref::<Error>()).expect("not an xdr error").kind()
+}
+
+fn checksummed(recs: &[&[u8]], fragsz: usize) -> Vec<u8> {
+    let mut xw = XdrRecordWriter::with_fragment_size(Vec::new(), fragsz);
+    xw.set_checksum(true);
+    for rec in recs {
+        xw.write_all(rec).unwrap();
+        xw.end_record().unwrap();
+    }
+    xw.into_inner()
+}
+
+#[test]
+fn checksum_write() {
+    let out = checksummed(&[&[1, 2, 3, 4, 5, 6], &[]], 4);
+
+    // "123456789" has a well-known CRC-32
+    let mut xw = XdrRecordWriter::new(Vec::new());
+    xw.set_checksum(true);
+    xw.write_all(b"123456789").unwrap();
+    let enc = xw.finish().unwrap();
+    assert_eq!(&enc[..4], &[0x80, 0, 0, 13]);
+    assert_eq!(&enc[13..], &[0xcb, 0xf4, 0x39, 0x26]);
+
+    // The checksum doesn't fit in the second fragment, so gets its own; the empty record is
+    // just a checksum (of nothing, which is 0)
+    assert_eq!(&out[..14], &[0, 0, 0, 4,  1, 2, 3, 4,  0, 0, 0, 2,  5, 6]);
+    assert_eq!(&out[14..18], &[0x80, 0, 0, 4]);
+    assert_eq!(&out[22..], &[0x80, 0, 0, 4,  0, 0, 0, 0]);
+    assert_eq!(out.len(), 30);
+}
+
+#[test]
+#[should_panic]
+fn checksum_small_fragment() {
+    let mut xw = XdrRecordWriter::with_fragment_size(Vec::new(), 3);
+    xw.set_checksum(true);
+}
+
+#[test]
+fn checksum_read() {
+    let recs: Vec<&[u8]> = vec![&[1, 2, 3, 4, 5, 6], &[], &[7, 8, 9]];
+    let enc = checksummed(&recs, 4);
+
+    let mut recread = XdrRecordReader::new(Cursor::new(&enc[..]));
+    recread.set_checksum(true);
+    let got: Vec<_> = recread.records().map(|r| r.unwrap()).collect();
+    assert_eq!(got, recs);
+    assert_eq!(recread.stats(), RecordStats { records: 3, fragments: 6, bytes: 9, errors: 0 });
+
+    let mut recread = XdrRecordReader::new(Cursor::new(&enc[..]));
+    recread.set_checksum(true);
+    let got: Vec<_> = recread.fragments().map(|r| r.unwrap().data).collect();
+    assert_eq!(got, vec![vec![1, 2, 3, 4], vec![5, 6], vec![], vec![], vec![7, 8, 9], vec![]]);
+
+    // Plain reads see just the payload
+    let mut recread = XdrRecordReader::new(Cursor::new(&enc[..]));
+    recread.set_checksum(true);
+    let mut data = Vec::new();
+    recread.read_to_end(&mut data).unwrap();
+    assert_eq!(data, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+    // Without checksums enabled, they're part of the record
+    let mut recread = XdrRecordReader::new(Cursor::new(&enc[..]));
+    assert_eq!(recread.records().nth(1).unwrap().unwrap(), vec![0, 0, 0, 0]);
+}
+
+#[test]
+fn checksum_corrupt() {
+    let mut enc = checksummed(&[&[1, 2, 3], &[4, 5, 6]], 64);
+    enc[5] ^= 0x10;
+
+    let mut recread = XdrRecordReader::new(Cursor::new(&enc[..]));
+    recread.set_checksum(true);
+    {
+        let mut records = recread.records();
+        let err = records.next().unwrap().unwrap_err();
+        match *limit_kind(&err) {
+            ErrorKind::ChecksumMismatch(..) => (),
+            ref kind => panic!("bad kind {:?}", kind),
+        }
+        assert!(records.next().is_none());
+    }
+    assert_eq!(recread.stats().errors, 1);
+
+    // Reads detect it at the end of the record
+    let mut recread = XdrRecordReader::new(Cursor::new(&enc[..]));
+    recread.set_checksum(true);
+    let mut buf = [0; 3];
+    recread.read_exact(&mut buf).unwrap();
+    let err = recread.read(&mut buf).unwrap_err();
+    match *limit_kind(&err) {
+        ErrorKind::ChecksumMismatch(..) => (),
+        ref kind => panic!("bad kind {:?}", kind),
+    }
+
+    // Final fragment too short to have a checksum
+    let mut recread = XdrRecordReader::new(Cursor::new(vec![0x80, 0, 0, 2,  1, 2]));
+    recread.set_checksum(true);
+    let err = recread.records().next().unwrap().unwrap_err();
+    match *limit_kind(&err) {
+        ErrorKind::MissingChecksum(2) => (),
+        ref kind => panic!("bad kind {:?}", kind),
+    }
+}
+
+#[test]
+fn checksum_nonblocking() {
+    let enc = checksummed(&[&[1, 2, 3], &[4]], 64);
+    assert_eq!(enc.len(), 11 + 9);
+
+    // Interrupted in each record's checksum
+    let chunks = vec![ enc[..9].to_vec(), vec![], enc[9..17].to_vec(), vec![], enc[17..].to_vec() ];
+    let mut recread = XdrRecordReader::new(BufReader::new(NonBlocking(chunks)));
+    recread.set_checksum(true);
+    {
+        let mut records = recread.records();
+        would_block(records.next());
+        assert_eq!(records.next().unwrap().unwrap(), vec![1, 2, 3]);
+        would_block(records.next());
+        assert_eq!(records.next().unwrap().unwrap(), vec![4]);
+        assert!(records.next().is_none());
+    }
+
+    let chunks = vec![ enc[..9].to_vec(), vec![], enc[9..].to_vec() ];
+    let mut recread = XdrRecordReader::new(BufReader::new(NonBlocking(chunks)));
+    recread.set_checksum(true);
+    {
+        let mut frags = recread.fragments();
+        would_block(frags.next());
+        assert_eq!(frags.next().unwrap().unwrap(), RecordFragment { data: vec![1, 2, 3], eor: true });
+    }
+}
+
+#[test]
+fn recread_limits() {
+    let inbuf = vec![ 0, 0, 0, 2,  1, 2,  0x80, 0, 0, 1,  3,
+                      0, 0, 0, 2,  4, 5,  0, 0, 0, 3,  6, 7, 8,  0x80, 0, 0, 0 ];
+
+    let mut recread = XdrRecordReader::new(Cursor::new(inbuf.clone()));
+    recread.set_max_fragment(Some(2));
+    recread.set_max_record(Some(4));
+    let mut records = recread.records();
+    assert_eq!(records.next().unwrap().unwrap(), vec![1, 2, 3]);
+    match records.next() {
+        Some(Err(ref e)) => match *limit_kind(e) {
+            ErrorKind::FragmentTooLarge(3, 2) => (),
+            ref k => panic!("bad kind {:?}", k),
+        },
+        res => panic!("bad result {:?}", res),
+    }
+    assert!(records.next().is_none());
+    assert_eq!(recread.stats().errors, 1);
+
+    let mut recread = XdrRecordReader::new(Cursor::new(inbuf.clone()));
+    recread.set_max_record(Some(4));
+    let mut buf = Vec::new();
+    match recread.read_to_end(&mut buf) {
+        Err(ref e) => match *limit_kind(e) {
+            ErrorKind::RecordTooLarge(5, 4) => (),
+            ref k => panic!("bad kind {:?}", k),
+        },
+        res => panic!("bad result {:?}", res),
+    }
+    // Only complete fragments within the limit were returned
+    assert_eq!(buf, vec![1, 2, 3, 4, 5]);
+
+    let mut recread = XdrRecordReader::new(Cursor::new(inbuf));
+    recread.set_max_record(Some(5));
+    let records: Vec<_> = recread.records().map(|r| r.unwrap()).collect();
+    assert_eq!(records, vec![vec![1, 2, 3], vec![4, 5, 6, 7, 8]]);
+}
+
 #[test]
 fn read_zerorec() {
     let inbuf = vec![0, 0, 0, 0,
@@ -130,3 +511,294 @@ fn largerec_flush() {
                            0, 0, 0, 2,  108, 111,
                            128, 0, 0, 0])
 }
+
+#[test]
+fn read_stats() {
+    let inbuf = vec![  0, 0, 0, 5,  0, 1, 2, 3, 4,
+                       128, 0, 0, 5,  5, 6, 7, 8, 9,
+                       128, 0, 0, 0,
+                       128, 0, 0, 1,  99];
+    let cur = Cursor::new(inbuf);
+    let mut recread = XdrRecordReader::new(cur);
+
+    assert_eq!(recread.stats(), RecordStats::default());
+
+    let mut buf = Vec::new();
+    recread.read_to_end(&mut buf).unwrap();
+
+    assert_eq!(buf, vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 99]);
+    assert_eq!(recread.stats(), RecordStats { records: 3, fragments: 4, bytes: 11, errors: 0 });
+}
+
+#[test]
+fn unpack_record() {
+    let inbuf = vec![  0, 0, 0, 4,  0, 0, 0, 1,
+                       128, 0, 0, 4,  0, 0, 0, 2,
+                       128, 0, 0, 6,  0, 0, 0, 3, 0xff, 0xff,
+                       128, 0, 0, 4,  0, 0, 0, 4];
+    let mut recread = XdrRecordReader::new(Cursor::new(inbuf));
+
+    assert_eq!(recread.unpack_record::<u64>().unwrap(), Some(0x1_0000_0002));
+    match recread.unpack_record::<u32>() {
+        Err(Error(ErrorKind::TrailingData(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    // The bad record was skipped
+    assert_eq!(recread.unpack_record::<u32>().unwrap(), Some(4));
+    assert_eq!(recread.unpack_record::<u32>().unwrap(), None);
+}
+
+#[test]
+fn stats_hook() {
+    use std::sync::{Arc, Mutex};
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let mut xw = XdrRecordWriter::with_fragment_size(Vec::new(), 4);
+    xw.set_checksum(true);
+    {
+        let seen = seen.clone();
+        xw.set_stats_hook(move |s| seen.lock().unwrap().push(*s));
+    }
+    xw.write_all(&[1, 2, 3, 4, 5]).unwrap();
+    xw.end_record().unwrap();
+    xw.end_record().unwrap();
+    let buf = xw.finish().unwrap();
+
+    // Checksums aren't counted as payload, even in the snapshot passed to the hook
+    assert_eq!(*seen.lock().unwrap(),
+               vec![RecordStats { records: 1, fragments: 3, bytes: 5, errors: 0 },
+                    RecordStats { records: 2, fragments: 4, bytes: 5, errors: 0 }]);
+
+    seen.lock().unwrap().clear();
+    let mut recread = XdrRecordReader::new(Cursor::new(buf));
+    recread.set_checksum(true);
+    recread.set_max_record(Some(16));
+    {
+        let seen = seen.clone();
+        recread.set_stats_hook(move |s| seen.lock().unwrap().push(*s));
+    }
+    assert_eq!(recread.records().count(), 2);
+    assert_eq!(*seen.lock().unwrap(),
+               vec![RecordStats { records: 1, fragments: 3, bytes: 5, errors: 0 },
+                    RecordStats { records: 2, fragments: 4, bytes: 5, errors: 0 }]);
+
+    seen.lock().unwrap().clear();
+    let mut recread = XdrRecordReader::new(Cursor::new(vec![0, 0, 0, 9]));
+    recread.set_max_fragment(Some(8));
+    {
+        let seen = seen.clone();
+        recread.set_stats_hook(move |s| seen.lock().unwrap().push(*s));
+    }
+    assert!(recread.records().next().unwrap().is_err());
+    assert_eq!(*seen.lock().unwrap(), vec![RecordStats { records: 0, fragments: 0, bytes: 0, errors: 1 }]);
+}
+
+// Reader which fails after returning its data
+struct Failing(Cursor<Vec<u8>>);
+
+impl Read for Failing {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf)? {
+            0 => Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset")),
+            n => Ok(n),
+        }
+    }
+}
+
+#[test]
+fn read_stats_error() {
+    let inbuf = vec![  0, 0, 0, 5,  0, 1, 2, 3, 4 ];
+    let rd = BufReader::new(Failing(Cursor::new(inbuf)));
+    let mut recread = XdrRecordReader::new(rd);
+
+    let mut buf = Vec::new();
+    assert!(recread.read_to_end(&mut buf).is_err());
+    assert_eq!(recread.stats(), RecordStats { records: 0, fragments: 1, bytes: 5, errors: 1 });
+}
+
+#[test]
+fn write_stats() {
+    let mut buf = Vec::new();
+
+    {
+        let mut xw = XdrRecordWriter::with_buffer(&mut buf, 3);
+
+        assert_eq!(write!(xw, "hello").unwrap(), ());
+        xw.flush_eor(true).unwrap();
+        assert_eq!(write!(xw, "hi").unwrap(), ());
+        xw.flush_eor(true).unwrap();
+
+        assert_eq!(xw.stats(), RecordStats { records: 2, fragments: 3, bytes: 7, errors: 0 });
+    }
+}
+
+// Writer which records the size of each write
+struct Writes(Vec<usize>);
+
+impl Write for Writes {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.push(buf.len());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+#[test]
+fn write_batched() {
+    let mut w = Writes(Vec::new());
+
+    {
+        let mut xw = XdrRecordWriter::with_buffer(&mut w, 64);
+
+        // Small packs are coalesced into one write per fragment, header included
+        for i in 0..30u32 {
+            xdr_codec::pack(&i, &mut xw).unwrap();
+        }
+        xw.flush_eor(true).unwrap();
+    }
+
+    assert_eq!(w.0, vec![68, 4 + 56]);
+}
+
+#[test]
+fn write_end_record() {
+    let mut buf = Vec::new();
+
+    {
+        let mut xw = XdrRecordWriter::with_fragment_size(&mut buf, 4);
+        assert_eq!(xw.fragment_size(), 4);
+
+        xw.write_all(&[1, 2, 3, 4, 5, 6]).unwrap();
+        xw.end_record().unwrap();
+        xw.end_record().unwrap();
+        xw.write_all(&[7]).unwrap();
+        xw.end_record().unwrap();
+    }
+
+    assert_eq!(buf, vec![0, 0, 0, 4,  1, 2, 3, 4,
+                         0x80, 0, 0, 2,  5, 6,
+                         0x80, 0, 0, 0,
+                         0x80, 0, 0, 1,  7]);
+}
+
+#[test]
+fn write_finish() {
+    let mut xw = XdrRecordWriter::with_fragment_size(Vec::new(), 4);
+    xw.write_all(&[1, 2, 3, 4, 5]).unwrap();
+    assert_eq!(xw.finish().unwrap(), vec![0, 0, 0, 4,  1, 2, 3, 4,  0x80, 0, 0, 1,  5]);
+
+    // Record already ended, so there's nothing more to write
+    let mut xw = XdrRecordWriter::new(Vec::new());
+    xw.write_all(&[1]).unwrap();
+    xw.end_record().unwrap();
+    assert_eq!(xw.finish().unwrap(), vec![0x80, 0, 0, 1,  1]);
+
+    // Nothing written at all gives an empty record, as on drop
+    let xw = XdrRecordWriter::new(Vec::new());
+    assert_eq!(xw.finish().unwrap(), vec![0x80, 0, 0, 0]);
+}
+
+#[test]
+fn write_into_inner() {
+    let mut xw = XdrRecordWriter::with_fragment_size(Vec::new(), 4);
+    xw.write_all(&[1, 2, 3, 4, 5]).unwrap();
+    assert_eq!(xw.into_inner(), vec![0, 0, 0, 4,  1, 2, 3, 4]);
+}
+
+// Writer which fails every write, counting the attempts
+struct Broken<'a>(&'a mut usize);
+
+impl<'a> Write for Broken<'a> {
+    fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+        *self.0 += 1;
+        Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+#[test]
+fn write_finish_error() {
+    let mut attempts = 0;
+
+    {
+        let mut xw = XdrRecordWriter::new(Broken(&mut attempts));
+        xw.write_all(&[1, 2, 3]).unwrap();
+        match xw.finish() {
+            Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => (),
+            res => panic!("bad result {:?}", res.map(|_| ())),
+        }
+    }
+
+    // Not retried on drop
+    assert_eq!(attempts, 1);
+}
+
+#[test]
+#[should_panic]
+fn write_bad_fragment_size() {
+    let _ = XdrRecordWriter::with_fragment_size(Vec::new(), 1 << 31);
+}
+
+#[test]
+fn seekwriter() {
+    let mut cur = Cursor::new(Vec::new());
+
+    {
+        let mut xw = XdrRecordSeekWriter::new(&mut cur);
+
+        assert_eq!(write!(xw, "hel").unwrap(), ());
+        xw.flush().unwrap();
+        assert_eq!(write!(xw, "lo").unwrap(), ());
+        xw.end_record().unwrap();
+        xw.end_record().unwrap();
+        assert_eq!(write!(xw, "hi").unwrap(), ());
+
+        assert_eq!(xw.stats(), RecordStats { records: 2, fragments: 2, bytes: 7, errors: 0 });
+    }
+
+    let buf = cur.into_inner();
+    assert_eq!(buf, vec![  128, 0, 0, 5,  104, 101, 108, 108, 111,
+                           128, 0, 0, 0,
+                           128, 0, 0, 2,  104, 105 ]);
+
+    let recread = XdrRecordReader::new(Cursor::new(buf));
+    let got: Vec<_> = recread.into_iter().map(|r| r.expect("IO error")).collect();
+    assert_eq!(got, vec![ b"hello".to_vec(), vec![], b"hi".to_vec() ]);
+}
+
+#[test]
+fn seekwriter_offset() {
+    // Records are patched relative to where the writer started
+    let mut cur = Cursor::new(vec![1, 2, 3]);
+    cur.set_position(3);
+
+    {
+        let mut xw = XdrRecordSeekWriter::new(&mut cur);
+        assert_eq!(write!(xw, "abc").unwrap(), ());
+    }
+
+    assert_eq!(cur.into_inner(), vec![1, 2, 3,  128, 0, 0, 3,  97, 98, 99]);
+}
+
+#[test]
+fn pack_record() {
+    let mut xw = XdrRecordWriter::with_fragment_size(Vec::new(), 8);
+    assert_eq!(xw.pack_record(&vec![1u32]).unwrap(), 8);
+    // Too big for a fragment, so split as usual
+    assert_eq!(xw.pack_record("hello").unwrap(), 12);
+    let out = xw.into_inner();
+    assert_eq!(out, vec![128, 0, 0, 8,  0, 0, 0, 1,  0, 0, 0, 1,
+                         0, 0, 0, 4,  0, 0, 0, 5,
+                         128, 0, 0, 8,  b'h', b'e', b'l', b'l', b'o', 0, 0, 0]);
+
+    let mut xw = XdrRecordWriter::new(Vec::new());
+    xw.set_checksum(true);
+    xw.pack_record(&vec![7u64, 8]).unwrap();
+    let out = xw.into_inner();
+    let mut recread = XdrRecordReader::new(Cursor::new(out));
+    recread.set_checksum(true);
+    assert_eq!(recread.unpack_record::<Vec<u64>>().unwrap(), Some(vec![7, 8]));
+}
@@ -0,0 +1,85 @@
+extern crate xdr_codec;
+
+use xdr_codec::{Error, ErrorKind};
+use xdr_codec::fixed::FixedOpaque;
+use xdr_codec::reflect::{XdrReflect, spec, spec_for};
+use xdr_codec::value::{Schema, Type};
+
+fn s(v: &str) -> String {
+    v.to_string()
+}
+
+#[test]
+fn simple() {
+    let mut schema = Schema::new();
+    assert_eq!(<Option<[u64; 2]>>::xdr_type(&mut schema),
+               Type::Optional(Box::new(Type::FixedArray(Box::new(Type::UHyper), 2))));
+    assert_eq!(<Vec<String>>::xdr_type(&mut schema).with_max(3),
+               Type::Array(Box::new(Type::String(None)), Some(3)));
+    assert_eq!(FixedOpaque::<5>::xdr_type(&mut schema), Type::FixedOpaque(5));
+    assert_eq!(spec(&schema).unwrap(), "");
+    assert_eq!(spec_for::<u32>().unwrap(), "");
+}
+
+#[test]
+fn definitions() {
+    let mut schema = Schema::new();
+    schema.define("stat", Type::Enum(vec![(s("OK"), 0), (s("NOENT"), 2)]));
+    schema.define("name", Type::String(Some(255)));
+    schema.define("names", Type::Array(Box::new(Type::Named(s("name"))), None));
+    schema.define("result", Type::Union {
+        discriminant: Box::new(Type::Named(s("stat"))),
+        cases: vec![(0, Type::Named(s("names")))],
+        default: Some(Box::new(Type::Void)),
+    });
+    schema.define("flag", Type::Union {
+        discriminant: Box::new(Type::Bool),
+        cases: vec![(1, Type::Struct(vec![(s("a"), Type::Int), (s("b"), Type::Opaque(Some(4)))])), (0, Type::Void)],
+        default: None,
+    });
+
+    assert_eq!(spec(&schema).unwrap(), "\
+union flag switch (bool discriminant) {
+case TRUE:
+    struct {
+        int a;
+        opaque b<4>;
+    } arm;
+case FALSE:
+    void;
+};
+
+typedef string name<255>;
+
+typedef name names<>;
+
+enum stat {
+    OK = 0,
+    NOENT = 2
+};
+
+union result switch (stat discriminant) {
+case OK:
+    names arm;
+default:
+    void;
+};
+");
+}
+
+#[test]
+fn inexpressible() {
+    let mut schema = Schema::new();
+    schema.define("nested", Type::Array(Box::new(Type::Array(Box::new(Type::Int), None)), None));
+    match spec(&schema) {
+        Err(Error(ErrorKind::InvalidSchema(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let mut schema = Schema::new();
+    schema.define("dangling", Type::Optional(Box::new(Type::Named(s("missing")))));
+    match spec(&schema) {
+        Err(Error(ErrorKind::UnknownType(ref name), _)) if name == "missing" => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
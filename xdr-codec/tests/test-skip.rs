@@ -0,0 +1,92 @@
+extern crate xdr_codec;
+
+use std::io::{self, Cursor, Read};
+
+use xdr_codec::{Pack, Unpack, Skip, Opaque, Error, ErrorKind, Result, skip};
+use xdr_codec::skip::{skip_opaque_flex, skip_string, skip_flex, skip_opaque_array};
+use xdr_codec::stream::XdrReader;
+
+#[derive(Debug, PartialEq)]
+struct Thing {
+    a: u32,
+    b: String,
+}
+
+impl<In: Read> Unpack<In> for Thing {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let mut sz = 0;
+        Ok((Thing {
+            a: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+            b: { let (v, fsz) = Unpack::unpack(input)?; sz += fsz; v },
+        }, sz))
+    }
+}
+
+impl<In: Read> Skip<In> for Thing {}
+
+fn encode() -> Vec<u8> {
+    let mut out = Vec::new();
+    1u32.pack(&mut out).unwrap();
+    0xffff_ffff_ffff_ffffu64.pack(&mut out).unwrap();
+    String::from("hello").pack(&mut out).unwrap();
+    Opaque::borrowed(&[1, 2, 3]).pack(&mut out).unwrap();
+    vec![1u32, 2, 3].pack(&mut out).unwrap();
+    Some(3.0f64).pack(&mut out).unwrap();
+    None::<u32>.pack(&mut out).unwrap();
+    2u32.pack(&mut out).unwrap();
+    String::from("x").pack(&mut out).unwrap();
+    7i32.pack(&mut out).unwrap();
+    out
+}
+
+#[test]
+fn skip_basic() {
+    let enc = encode();
+    let mut input = Cursor::new(&enc[..]);
+
+    assert_eq!(skip::<_, u32>(&mut input).unwrap(), 4);
+    assert_eq!(skip::<_, u64>(&mut input).unwrap(), 8);
+    assert_eq!(skip::<_, String>(&mut input).unwrap(), 12);
+    assert_eq!(skip::<_, Opaque>(&mut input).unwrap(), 8);
+    assert_eq!(skip::<_, Vec<u32>>(&mut input).unwrap(), 16);
+    assert_eq!(skip::<_, Option<f64>>(&mut input).unwrap(), 12);
+    assert_eq!(skip::<_, Option<u32>>(&mut input).unwrap(), 4);
+    assert_eq!(skip::<_, Thing>(&mut input).unwrap(), 12);
+    assert_eq!(i32::unpack(&mut input).unwrap(), (7, 4));
+    assert_eq!(input.position() as usize, enc.len());
+}
+
+#[test]
+fn skip_limits() {
+    let enc = encode();
+
+    assert_eq!(skip_string(&mut &enc[12..], Some(5)).unwrap(), 12);
+    assert_eq!(skip_opaque_array(&mut &enc[28..], 3).unwrap(), 4);
+    assert_eq!(skip_flex::<_, u32>(&mut &enc[32..], Some(3)).unwrap(), 16);
+
+    match skip_opaque_flex(&mut &enc[12..], Some(4)) {
+        Err(Error(ErrorKind::InvalidLen(4), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match skip_flex::<_, u32>(&mut &enc[32..], Some(2)) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match skip_string(&mut &enc[12..20], None) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn skip_reader() {
+    let enc = encode();
+    let mut r = XdrReader::new(&enc[..]);
+
+    assert_eq!(r.skip::<u32>().unwrap(), 4);
+    assert_eq!(r.skip::<u64>().unwrap(), 8);
+    assert_eq!(r.peek_u32().unwrap(), 5);
+    assert_eq!(r.skip::<String>().unwrap(), 12);
+    assert_eq!(r.position(), 24);
+    assert_eq!(r.unpack::<Opaque>().unwrap(), Opaque::borrowed(&[1, 2, 3]));
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "smallvec")]
+extern crate xdr_codec;
+
+use xdr_codec::{Error, ErrorKind, pack_opaque_flex, pack_to_vec, Opaque};
+use xdr_codec::small::{SmallVec, unpack_opaque_flex_small};
+
+#[test]
+fn small_opaque() {
+    let enc = pack_to_vec(&Opaque::borrowed(&[1, 2, 3, 4, 5])).unwrap();
+
+    let (v, sz): (SmallVec<[u8; 8]>, _) = unpack_opaque_flex_small(&mut &enc[..], None).unwrap();
+    assert_eq!((&v[..], sz), (&[1, 2, 3, 4, 5][..], 12));
+    assert!(!v.spilled());
+
+    let (v, sz): (SmallVec<[u8; 2]>, _) = unpack_opaque_flex_small(&mut &enc[..], None).unwrap();
+    assert_eq!((&v[..], sz), (&[1, 2, 3, 4, 5][..], 12));
+    assert!(v.spilled());
+
+    let mut out = Vec::new();
+    pack_opaque_flex(&v, None, &mut out).unwrap();
+    assert_eq!(out, enc);
+
+    match unpack_opaque_flex_small::<_, 8>(&mut &enc[..], Some(4)) {
+        Err(Error(ErrorKind::InvalidLen(4), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match unpack_opaque_flex_small::<_, 8>(&mut &enc[..7], None) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
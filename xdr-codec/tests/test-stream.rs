@@ -0,0 +1,260 @@
+extern crate xdr_codec;
+
+use std::io::{self, Cursor, Read, Write};
+
+use xdr_codec::{Error, ErrorKind, Opaque, pack_string, unpack_string, pack_flex, packed_len,
+                pack_to_vec};
+use xdr_codec::stream::{XdrWriter, XdrReader, XdrIterator, CountingWriter, StreamStats};
+
+#[test]
+fn writer_position() {
+    let mut w = XdrWriter::new(Vec::new());
+
+    assert_eq!(w.position(), 0);
+    assert!(w.is_aligned());
+
+    assert_eq!(w.pack(&1u32).unwrap(), 4);
+    assert_eq!(w.pack(&Opaque::borrowed(b"abc")).unwrap(), 8);
+    assert_eq!(pack_string("hello", None, &mut w).unwrap(), 12);
+    assert_eq!(w.pack(&vec![1u64, 2][..]).unwrap(), 20);
+    assert_eq!(w.position(), 44);
+    assert!(w.is_aligned());
+    assert_eq!(w.pad_to_alignment().unwrap(), 0);
+
+    w.write_all(b"xyzzy").unwrap();
+    assert_eq!(w.position(), 49);
+    assert_eq!(w.misalignment(), 1);
+    assert_eq!(w.pad_to_alignment().unwrap(), 3);
+    assert_eq!(w.position(), 52);
+    assert!(w.is_aligned());
+
+    let v = w.into_inner();
+    assert_eq!(v.len(), 52);
+    assert_eq!(&v[44..], b"xyzzy\0\0\0");
+}
+
+#[test]
+fn writer_inner() {
+    let mut cur = Cursor::new(Vec::new());
+    cur.write_all(b"xx").unwrap();
+
+    // Position is relative to when the wrapper was created
+    let mut w = XdrWriter::new(cur);
+    assert_eq!(w.pack(&0xffu32).unwrap(), 4);
+    w.get_mut().write_all(b"uncounted").unwrap();
+    assert_eq!(w.position(), 4);
+    assert_eq!(w.get_ref().position(), 15);
+}
+
+#[test]
+fn reader_peek() {
+    let mut w = XdrWriter::new(Vec::new());
+    w.pack(&2i32).unwrap();
+    w.pack(&0xdead_beefu32).unwrap();
+    pack_string("hello", None, &mut w).unwrap();
+    w.pack(&7u64).unwrap();
+    let enc = w.into_inner();
+
+    let mut r = XdrReader::new(Cursor::new(enc));
+
+    assert_eq!(r.peek_u32().unwrap(), 2);
+    assert_eq!(r.peek_u32().unwrap(), 2);
+    assert_eq!(r.position(), 0);
+    assert_eq!(r.unpack::<i32>().unwrap(), 2);
+    assert_eq!(r.position(), 4);
+
+    assert_eq!(r.peek_u32().unwrap(), 0xdead_beef);
+    r.skip_bytes(4).unwrap();
+    assert_eq!(r.position(), 8);
+
+    assert_eq!(r.peek_u32().unwrap(), 5);
+    assert_eq!(unpack_string(&mut r, None).unwrap(), (String::from("hello"), 12));
+    assert_eq!(r.position(), 20);
+
+    assert_eq!(r.unpack::<u64>().unwrap(), 7);
+    assert_eq!(r.position(), 28);
+
+    match r.peek_u32() {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+/// Reader which returns at most one byte per read
+struct Trickle<'a>(&'a [u8]);
+
+impl<'a> Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&mut self.0).take(1).read(buf)
+    }
+}
+
+#[test]
+fn reader_short() {
+    let mut r = XdrReader::new(Trickle(&[0, 0, 0, 9, 1, 2]));
+
+    assert_eq!(r.peek_u32().unwrap(), 9);
+    assert_eq!(r.unpack::<u32>().unwrap(), 9);
+
+    // A failed peek doesn't lose data
+    match r.peek_u32() {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+    let mut rest = Vec::new();
+    r.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, vec![1, 2]);
+    assert_eq!(r.position(), 6);
+
+    match XdrReader::new(&[0u8; 3][..]).skip_bytes(4) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn stream_stats() {
+    use std::sync::{Arc, Mutex};
+
+    let mut w = XdrWriter::new(Vec::new());
+    w.pack(&vec![1u32, 2]).unwrap();
+    w.pack("abc").unwrap();
+    assert!(w.pack(&Opaque::borrowed(&[0; 8])).is_ok());
+    w.write_all(&[9; 4]).unwrap();
+    assert_eq!(w.stats(), StreamStats { values: 3, bytes: 36, errors: 0 });
+
+    let enc = w.into_inner();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let mut r = XdrReader::new(&enc[..]);
+    {
+        let seen = seen.clone();
+        r.set_stats_hook(move |s| seen.lock().unwrap().push(*s));
+    }
+    assert_eq!(r.unpack::<Vec<u32>>().unwrap(), vec![1, 2]);
+    assert_eq!(r.skip::<String>().unwrap(), 8);
+    assert!(r.unpack::<bool>().is_err());
+    assert_eq!(*seen.lock().unwrap(),
+               vec![StreamStats { values: 1, bytes: 12, errors: 0 },
+                    StreamStats { values: 2, bytes: 20, errors: 0 },
+                    StreamStats { values: 2, bytes: 24, errors: 1 }]);
+    assert_eq!(r.stats().bytes, r.position());
+}
+
+#[test]
+fn counting() {
+    let mut w = CountingWriter::new();
+
+    assert_eq!(pack_string("hello", None, &mut w).unwrap(), 12);
+    w.write_all(&[0; 100]).unwrap();
+    assert_eq!(w.count(), 112);
+
+    let v = vec![Some(1u32), None, Some(3)];
+    assert_eq!(packed_len(&v).unwrap(), pack_to_vec(&v).unwrap().len());
+    assert_eq!(packed_len(&v).unwrap(), 24);
+    assert_eq!(packed_len("abcde").unwrap(), 12);
+
+    match pack_flex(&[1u32, 2, 3], Some(2), &mut CountingWriter::new()) {
+        Err(Error(ErrorKind::InvalidLen(2), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn iterator() {
+    let enc = pack_to_vec(&vec![1u32, 2, 3]).unwrap();
+
+    // The length, then the items, then a clean end
+    let mut it = XdrIterator::<_, u32>::new(Trickle(&enc));
+    assert_eq!(it.by_ref().map(|r| r.unwrap()).collect::<Vec<_>>(), vec![3, 1, 2, 3]);
+    assert!(it.next().is_none());
+    assert_eq!(it.get_ref().position(), 16);
+
+    let mut it = XdrIterator::<_, u64>::new(&enc[..12]);
+    assert_eq!(it.next().unwrap().unwrap(), 0x3_0000_0001);
+    match it.next() {
+        Some(Ok(v)) => panic!("unexpected {}", v),
+        Some(Err(Error(ErrorKind::IOError(ref e), _))) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert!(it.next().is_none());
+
+    let mut r = XdrReader::new(&enc[..4]);
+    assert!(!r.is_eof().unwrap());
+    assert_eq!(r.position(), 0);
+    assert_eq!(r.unpack::<u32>().unwrap(), 3);
+    assert!(r.is_eof().unwrap());
+}
+
+#[test]
+fn reader_eof() {
+    let enc = [0, 0, 0, 1,  0, 0];
+    let mut r = XdrReader::new(Trickle(&enc));
+
+    assert_eq!(r.unpack::<u32>().unwrap(), 1);
+    match r.unpack::<u32>() {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Only once nothing is left is it a clean end, which isn't counted as an error
+    match r.unpack::<u32>() {
+        Err(ref e) if e.is_eof() => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match r.skip::<u64>() {
+        Err(Error(ErrorKind::Eof, _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(r.stats(), StreamStats { values: 1, bytes: 6, errors: 1 });
+}
+
+// Seekable reader which counts the bytes actually read
+struct Counted(Cursor<Vec<u8>>, usize);
+
+impl Read for Counted {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let sz = self.0.read(buf)?;
+        self.1 += sz;
+        Ok(sz)
+    }
+}
+
+impl io::Seek for Counted {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+
+#[test]
+fn seek_skip() {
+    let body = vec![7u8; 1 << 20];
+    let mut w = XdrWriter::new(Vec::new());
+    w.pack(&Opaque::borrowed(&body[..body.len() - 1])).unwrap();
+    w.pack(&1u32).unwrap();
+    let enc = w.into_inner();
+
+    let mut r = XdrReader::seekable(Counted(Cursor::new(enc.clone()), 0));
+    assert_eq!(r.peek_u32().unwrap(), (1 << 20) - 1);
+    assert_eq!(r.skip_opaque(None).unwrap(), 4 + (1 << 20));
+    assert_eq!(r.unpack::<u32>().unwrap(), 1);
+    assert_eq!(r.position(), enc.len() as u64);
+    assert_eq!(r.get_ref().1, 8);
+    match r.skip_opaque(None) {
+        Err(Error(ErrorKind::Eof, _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Without seeking, the body is read
+    let mut r = XdrReader::new(Counted(Cursor::new(enc.clone()), 0));
+    assert_eq!(r.skip_opaque(None).unwrap(), 4 + (1 << 20));
+    assert_eq!(r.get_ref().1, 4 + (1 << 20));
+
+    match XdrReader::seekable(Cursor::new(&enc[..1000])).skip_opaque(None) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match XdrReader::seekable(Cursor::new(&enc[..])).skip_opaque(Some(1000)) {
+        Err(Error(ErrorKind::InvalidLen(1000), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
@@ -0,0 +1,46 @@
+extern crate xdr_codec;
+
+use std::convert::TryFrom;
+use std::io::Cursor;
+
+use xdr_codec::{Error, ErrorKind, Skip, Unpack, pack_to_vec};
+use xdr_codec::string::XdrString;
+
+#[test]
+fn xdrstring_convert() {
+    let s = XdrString::from("héllo");
+    assert_eq!(s.to_str().unwrap(), "héllo");
+    assert_eq!(String::try_from(s.clone()).unwrap(), "héllo");
+    assert_eq!(XdrString::from(String::from("héllo")), s);
+
+    let raw = XdrString::new(vec![b'a', 0xff, b'b']);
+    assert!(raw.to_str().is_err());
+    assert!(raw.clone().into_string().is_err());
+    assert_eq!(raw.to_string_lossy(), "a\u{fffd}b");
+    assert_eq!(raw.to_string(), "a\u{fffd}b");
+    assert_eq!(format!("{:?}", raw), "XdrString(b\"a\\xffb\")");
+    assert_eq!(raw.as_bytes(), &[b'a', 0xff, b'b']);
+    assert_eq!(Vec::from(raw), vec![b'a', 0xff, b'b']);
+}
+
+#[test]
+fn xdrstring_codec() {
+    // Same encoding as a String
+    let s = XdrString::from("hello");
+    let enc = pack_to_vec(&s).unwrap();
+    assert_eq!(enc, pack_to_vec("hello").unwrap());
+    assert_eq!(XdrString::unpack(&mut Cursor::new(&enc[..])).unwrap(), (s, 12));
+
+    // Invalid UTF-8 fails as a String but round-trips as an XdrString
+    let enc = vec![0, 0, 0, 3,  b'a', 0xff, b'b', 0];
+    match String::unpack(&mut Cursor::new(&enc[..])) {
+        Err(Error(ErrorKind::InvalidUtf8(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    let (s, sz) = XdrString::unpack(&mut Cursor::new(&enc[..])).unwrap();
+    assert_eq!(sz, 8);
+    assert_eq!(s.as_bytes(), &[b'a', 0xff, b'b']);
+    assert_eq!(pack_to_vec(&s).unwrap(), enc);
+
+    assert_eq!(XdrString::skip(&mut Cursor::new(&enc[..])).unwrap(), 8);
+}
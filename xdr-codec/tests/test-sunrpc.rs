@@ -0,0 +1,82 @@
+#![cfg(feature = "sunrpc_types")]
+extern crate xdr_codec;
+
+use std::io::Cursor;
+
+use xdr_codec::{Pack, Unpack, Error, ErrorKind};
+use xdr_codec::sunrpc_types::*;
+
+#[test]
+fn netobj_limit() {
+    let mut out = Cursor::new(Vec::new());
+
+    assert_eq!(netobj(vec![1, 2, 3]).pack(&mut out).unwrap(), 8);
+    assert_eq!(out.into_inner(), vec![0, 0, 0, 3,  1, 2, 3, 0]);
+
+    let mut out = Cursor::new(Vec::new());
+    match netobj(vec![0; MAXNETOBJ_SZ + 1]).pack(&mut out) {
+        Err(Error(ErrorKind::InvalidLen(MAXNETOBJ_SZ), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn authsys() {
+    let cred = authsys_parms {
+        stamp: 0x1234,
+        machinename: String::from("host"),
+        uid: 1000,
+        gid: 100,
+        gids: vec![4, 24],
+    };
+    let auth = opaque_auth {
+        flavor: AUTH_SYS,
+        body: {
+            let mut body = Vec::new();
+            cred.pack(&mut body).unwrap();
+            body
+        },
+    };
+
+    let mut out = Cursor::new(Vec::new());
+    assert_eq!(auth.pack(&mut out).unwrap(), 8 + 32);
+    let v = out.into_inner();
+    assert_eq!(v, vec![0, 0, 0, 1,  0, 0, 0, 32,
+                       0, 0, 0x12, 0x34,
+                       0, 0, 0, 4,  b'h', b'o', b's', b't',
+                       0, 0, 0x03, 0xe8,
+                       0, 0, 0, 100,
+                       0, 0, 0, 2,  0, 0, 0, 4,  0, 0, 0, 24]);
+
+    let mut input = Cursor::new(v);
+    let (uauth, sz) = opaque_auth::unpack(&mut input).unwrap();
+    assert_eq!(sz, 40);
+    assert_eq!(uauth, auth);
+
+    let mut input = Cursor::new(uauth.body);
+    assert_eq!(authsys_parms::unpack(&mut input).unwrap(), (cred, 32));
+}
+
+#[test]
+fn unknown_flavor() {
+    let mut input = Cursor::new(vec![0, 0, 0x01, 0x90,  0, 0, 0, 0]);
+    assert_eq!(opaque_auth::unpack(&mut input).unwrap(),
+               (opaque_auth { flavor: 400, body: vec![] }, 8));
+}
+
+#[test]
+fn holder() {
+    let h = nlm4_holder {
+        exclusive: true,
+        svid: -2,
+        oh: netobj(b"owner".to_vec()),
+        l_offset: 0x1_0000_0000,
+        l_len: 0,
+    };
+
+    let mut out = Cursor::new(Vec::new());
+    assert_eq!(h.pack(&mut out).unwrap(), 36);
+
+    let mut input = Cursor::new(out.into_inner());
+    assert_eq!(nlm4_holder::unpack(&mut input).unwrap(), (h, 36));
+}
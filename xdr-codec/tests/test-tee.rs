@@ -0,0 +1,61 @@
+extern crate xdr_codec;
+
+use std::io::Read;
+
+use xdr_codec::{Error, ErrorKind, Unpack};
+use xdr_codec::string::XdrString;
+use xdr_codec::tee::{TeeReader, unpack_captured};
+
+#[test]
+fn tee_reader() {
+    let mut tee = TeeReader::new(&[1, 2, 3, 4, 5][..]);
+    let mut buf = [0; 3];
+
+    tee.read_exact(&mut buf).unwrap();
+    assert_eq!(tee.captured(), [1, 2, 3]);
+    assert_eq!(tee.take_captured(), [1, 2, 3]);
+    assert!(tee.captured().is_empty());
+
+    let (rest, captured) = tee.into_inner();
+    assert_eq!(rest, [4, 5]);
+    assert!(captured.is_empty());
+}
+
+#[test]
+fn captured_ok() {
+    let mut input = &[0, 0, 0, 2, b'h', b'i', 0, 0, 0, 0, 0, 7][..];
+
+    let (s, sz, raw) = unpack_captured::<_, XdrString>(&mut input).unwrap();
+    assert_eq!(s.as_bytes(), b"hi");
+    assert_eq!(sz, 8);
+    assert_eq!(raw, [0, 0, 0, 2, b'h', b'i', 0, 0]);
+
+    let (v, sz, raw) = unpack_captured::<_, u32>(&mut input).unwrap();
+    assert_eq!((v, sz, raw), (7, 4, vec![0, 0, 0, 7]));
+    assert!(input.is_empty());
+}
+
+#[test]
+fn captured_err() {
+    // Bad bool after a good int
+    let input = [0, 0, 0, 1, 0, 0, 0, 2];
+    let mut tee = TeeReader::new(&input[..]);
+    let _: (u32, usize) = Unpack::unpack(&mut tee).unwrap();
+    match unpack_captured::<_, bool>(&mut tee) {
+        Err(ref e @ Error(ErrorKind::InvalidEnum(2), _)) => assert_eq!(e.captured(), Some(&[0, 0, 0, 2][..])),
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(tee.captured(), input);
+
+    // Short input
+    match unpack_captured::<_, Vec<u32>>(&mut &[0, 0, 0, 2, 0, 0, 0, 1, 0, 0][..]) {
+        Err(ref e) => assert_eq!(e.captured(), Some(&[0, 0, 0, 2, 0, 0, 0, 1, 0, 0][..])),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn uncaptured() {
+    let err = xdr_codec::unpack_complete::<u32>(&[0, 0]).unwrap_err();
+    assert_eq!(err.captured(), None);
+}
@@ -0,0 +1,103 @@
+#[macro_use]
+extern crate xdr_codec;
+
+use std::io::{Read, Write};
+
+use xdr_codec::{Pack, Unpack, Result};
+use xdr_codec::testing::{parse_hex, hexdump, hexdump_diff, roundtrip_mismatch, bytes_mismatch};
+
+#[test]
+fn hex_parse() {
+    assert_eq!(parse_hex(""), Vec::<u8>::new());
+    assert_eq!(parse_hex("0000002A\n 61 62"), vec![0, 0, 0, 0x2a, 0x61, 0x62]);
+}
+
+#[test]
+#[should_panic(expected = "invalid hex digit")]
+fn hex_parse_bad() {
+    parse_hex("0x12");
+}
+
+#[test]
+fn dump() {
+    assert_eq!(hexdump(&[0, 0, 0, 42, 1, 2]), "00000000  0000002a\n00000004  0102----\n");
+
+    assert_eq!(hexdump_diff(&[0, 0, 0, 42, 0, 0, 0, 3, 0x61, 0x62, 0x63],
+                            &[0, 0, 0, 42, 0, 0, 0, 4, 0x61, 0x62, 0x63, 0x64]),
+               "offset    expected  actual\n\
+                00000000  0000002a  0000002a\n\
+                00000004  00000003  00000004  <\n\
+                00000008  616263--  61626364  <\n");
+}
+
+#[test]
+fn dump_elided() {
+    let expected = vec![0u8; 400];
+    let mut actual = expected.clone();
+    actual[203] = 1;
+
+    let diff = hexdump_diff(&expected, &actual);
+    assert_eq!(diff,
+               "offset    expected  actual\n\
+                ...\n\
+                000000c0  00000000  00000000\n\
+                000000c4  00000000  00000000\n\
+                000000c8  00000000  00000001  <\n\
+                000000cc  00000000  00000000\n\
+                000000d0  00000000  00000000\n\
+                ...\n");
+}
+
+// Unpacks to a different value than it packs
+#[derive(Debug, PartialEq)]
+struct Lossy(u32);
+
+impl<Out: Write> Pack<Out> for Lossy {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        self.0.pack(out)
+    }
+}
+
+impl<In: Read> Unpack<In> for Lossy {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        u32::unpack(input).map(|(v, sz)| (Lossy(v & 0xff), sz))
+    }
+}
+
+#[test]
+fn roundtrip_ok() {
+    assert_xdr_roundtrip!(vec![1u32, 2, 3]);
+    assert_xdr_roundtrip!(Some(Lossy(12)));
+    assert_eq!(roundtrip_mismatch(&Lossy(7)), None);
+}
+
+#[test]
+fn roundtrip_bad() {
+    let msg = roundtrip_mismatch(&Lossy(0x1234)).unwrap();
+    assert_eq!(msg,
+               "  packed: Lossy(4660)\n\
+                unpacked: Lossy(52)\n\
+                offset    expected  actual\n\
+                00000000  00001234  00000034  <\n");
+}
+
+#[test]
+#[should_panic(expected = "`Lossy(0x100)` doesn't round trip")]
+fn roundtrip_panic() {
+    assert_xdr_roundtrip!(Lossy(0x100));
+}
+
+#[test]
+fn bytes() {
+    assert_xdr_bytes!(42u32, "0000002a");
+    assert_xdr_bytes!(*"abc", "00000003 61626300");
+    assert_eq!(bytes_mismatch(&1u32, "00000001"), None);
+    assert_eq!(bytes_mismatch(&1u32, "00000002").unwrap(),
+               "offset    expected  actual\n00000000  00000002  00000001  <\n");
+}
+
+#[test]
+#[should_panic(expected = "doesn't encode as expected")]
+fn bytes_panic() {
+    assert_xdr_bytes!(vec![1u32], "00000001");
+}
@@ -0,0 +1,139 @@
+#![cfg(feature = "testvectors")]
+#[macro_use]
+extern crate xdr_codec;
+
+use xdr_codec::{Error, Opaque, Pack, Unpack, Read, Result, Write, pack_array, pack_string, pack_opaque_flex,
+                unpack_fixed_array, unpack_opaque_flex, unpack_string};
+use xdr_codec::fixed::FixedOpaque;
+use xdr_codec::string::XdrString;
+use xdr_codec::testvectors::{self, Source, RFC_FILE_DECL, UNION_DECL, VECTORS, check, declarations};
+use xdr_codec::union::XdrUnion;
+
+#[derive(Debug)]
+struct Three([i32; 3]);
+
+impl<Out: Write> Pack<Out> for Three {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        pack_array(&self.0, 3, out, None)
+    }
+}
+
+impl<In: Read> Unpack<In> for Three {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        unpack_fixed_array(input).map(|(v, sz)| (Three(v), sz))
+    }
+}
+
+#[derive(Debug)]
+enum U {
+    Zero,
+    One(i32),
+    Two(String),
+}
+
+impl XdrUnion for U {
+    fn discriminant(&self) -> i32 {
+        match *self {
+            U::Zero => 0,
+            U::One(_) => 1,
+            U::Two(_) => 2,
+        }
+    }
+
+    fn pack_body<Out: Write>(&self, out: &mut Out) -> Result<usize> {
+        match *self {
+            U::Zero => Ok(0),
+            U::One(ref v) => v.pack(out),
+            U::Two(ref v) => v.as_str().pack(out),
+        }
+    }
+
+    fn unpack_body<In: Read>(disc: i32, input: &mut In) -> Result<(Self, usize)> {
+        match disc {
+            0 => Ok((U::Zero, 0)),
+            1 => i32::unpack(input).map(|(v, sz)| (U::One(v), sz)),
+            2 => String::unpack(input).map(|(v, sz)| (U::Two(v), sz)),
+            d => Err(Error::invalidcase(d)),
+        }
+    }
+}
+
+xdr_union_codec!(U);
+
+// The RFC example, with the `type` union flattened to (kind, name)
+#[derive(Debug)]
+struct File {
+    filename: String,
+    kind: i32,
+    name: Option<String>,
+    owner: String,
+    data: Vec<u8>,
+}
+
+impl<Out: Write> Pack<Out> for File {
+    fn pack(&self, out: &mut Out) -> Result<usize> {
+        let mut sz = pack_string(&self.filename, Some(255), out)? + self.kind.pack(out)?;
+        if let Some(ref name) = self.name {
+            sz += pack_string(name, Some(255), out)?;
+        }
+        Ok(sz + pack_string(&self.owner, Some(32), out)? + pack_opaque_flex(&self.data, Some(65535), out)?)
+    }
+}
+
+impl<In: Read> Unpack<In> for File {
+    fn unpack(input: &mut In) -> Result<(Self, usize)> {
+        let (filename, mut sz) = unpack_string(input, Some(255))?;
+        let (kind, ksz) = i32::unpack(input)?;
+        sz += ksz;
+        let name = match kind {
+            0 => None,
+            1 | 2 => {
+                let (name, nsz) = unpack_string(input, Some(255))?;
+                sz += nsz;
+                Some(name)
+            },
+            d => return Err(Error::invalidcase(d)),
+        };
+        let (owner, osz) = unpack_string(input, Some(32))?;
+        let (data, dsz) = unpack_opaque_flex(input, Some(65535))?;
+        Ok((File { filename, kind, name, owner, data }, sz + osz + dsz))
+    }
+}
+
+#[test]
+fn vectors_conform() {
+    assert_eq!(check::<i32>("int"), Ok(5));
+    assert_eq!(check::<u32>("unsigned int"), Ok(3));
+    assert_eq!(check::<i64>("hyper"), Ok(4));
+    assert_eq!(check::<u64>("unsigned hyper"), Ok(2));
+    assert_eq!(check::<f32>("float"), Ok(3));
+    assert_eq!(check::<f64>("double"), Ok(3));
+    assert_eq!(check::<bool>("bool"), Ok(2));
+    assert_eq!(check::<XdrString>("string<>"), Ok(4));
+    assert_eq!(check::<Opaque<'static>>("opaque<>"), Ok(2));
+    assert_eq!(check::<FixedOpaque<5>>("opaque[5]"), Ok(1));
+    assert_eq!(check::<Vec<i32>>("int<>"), Ok(2));
+    assert_eq!(check::<Three>("int[3]"), Ok(1));
+    assert_eq!(check::<Option<i32>>("int *"), Ok(2));
+    assert_eq!(check::<U>(UNION_DECL), Ok(3));
+    assert_eq!(check::<File>(RFC_FILE_DECL), Ok(1));
+
+    // Everything is covered above
+    assert_eq!(declarations().len(), 15);
+    assert_eq!(VECTORS.iter().filter(|v| v.source == Source::Rfc4506).count(), 1);
+}
+
+#[test]
+fn vectors_mismatch() {
+    match check::<u32>("string<>") {
+        Err(msg) => assert!(msg.contains("\"a\": unpack used 4 of 8 bytes"), "{}", msg),
+        res => panic!("bad result {:?}", res),
+    }
+    match check::<i32>("hyper") {
+        Err(msg) => assert!(msg.contains("0: unpack used 4 of 8 bytes"), "{}", msg),
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(check::<i32>("long"), Err("no test vectors for `long`".to_string()));
+    assert_eq!(testvectors::matching("bool").map(|v| v.bytes()).collect::<Vec<_>>(),
+               vec![vec![0, 0, 0, 0], vec![0, 0, 0, 1]]);
+}
@@ -0,0 +1,75 @@
+extern crate xdr_codec;
+
+use std::io::Cursor;
+use std::time::{Duration, UNIX_EPOCH};
+
+use xdr_codec::{Pack, Unpack, Error, ErrorKind};
+use xdr_codec::time::{pack_time, unpack_time};
+
+#[test]
+fn duration() {
+    let mut out = Cursor::new(Vec::new());
+    let d = Duration::new(0x1_0000_0002, 999_999_999);
+
+    assert_eq!(d.pack(&mut out).unwrap(), 12);
+    let v = out.into_inner();
+    assert_eq!(v, vec![0x00, 0x00, 0x00, 0x01,  0x00, 0x00, 0x00, 0x02,
+                       0x3b, 0x9a, 0xc9, 0xff]);
+
+    let mut input = Cursor::new(v);
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (d, 12));
+}
+
+#[test]
+fn duration_bad_nanos() {
+    let mut input = Cursor::new(vec![0x00, 0x00, 0x00, 0x00,  0x00, 0x00, 0x00, 0x01,
+                                     0x3b, 0x9a, 0xca, 0x00]);
+    match Duration::unpack(&mut input) {
+        Err(Error(ErrorKind::InvalidTime(1, 1_000_000_000), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn systemtime() {
+    let t = UNIX_EPOCH + Duration::new(1_500_000_000, 5);
+    let mut out = Cursor::new(Vec::new());
+
+    assert_eq!(t.pack(&mut out).unwrap(), 12);
+    let v = out.into_inner();
+    assert_eq!(v, vec![0x00, 0x00, 0x00, 0x00,  0x59, 0x68, 0x2f, 0x00,
+                       0x00, 0x00, 0x00, 0x05]);
+
+    let mut input = Cursor::new(v);
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (t, 12));
+}
+
+#[test]
+fn systemtime_before_epoch() {
+    // 1.25 seconds before the epoch is -2s + 0.75s
+    let t = UNIX_EPOCH - Duration::new(1, 250_000_000);
+    let mut out = Cursor::new(Vec::new());
+
+    assert_eq!(t.pack(&mut out).unwrap(), 12);
+    let v = out.into_inner();
+    assert_eq!(v, vec![0xff, 0xff, 0xff, 0xff,  0xff, 0xff, 0xff, 0xfe,
+                       0x2c, 0xb4, 0x17, 0x80]);
+
+    let mut input = Cursor::new(v);
+    assert_eq!(Unpack::unpack(&mut input).unwrap(), (t, 12));
+}
+
+#[test]
+fn custom_epoch() {
+    let epoch = UNIX_EPOCH + Duration::new(1000, 0);
+    let t = UNIX_EPOCH + Duration::new(1010, 20);
+    let mut out = Cursor::new(Vec::new());
+
+    assert_eq!(pack_time(t, epoch, &mut out).unwrap(), 12);
+    let v = out.into_inner();
+    assert_eq!(v, vec![0x00, 0x00, 0x00, 0x00,  0x00, 0x00, 0x00, 0x0a,
+                       0x00, 0x00, 0x00, 0x14]);
+
+    let mut input = Cursor::new(v);
+    assert_eq!(unpack_time(&mut input, epoch).unwrap(), (t, 12));
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "tracing")]
+extern crate xdr_codec;
+extern crate tracing;
+
+use std::fmt;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+use tracing::{Event, Metadata, Subscriber};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+
+use xdr_codec::{Pack, unpack_from_bytes};
+use xdr_codec::record::{XdrRecordReader, XdrRecordWriter};
+use xdr_codec::stream::XdrReader;
+
+// Subscriber which records each event as "field=value ..." strings
+#[derive(Clone, Default)]
+struct Collect(Arc<Mutex<Vec<String>>>);
+
+struct Fields(String);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.push_str(&format!("{}={:?} ", field.name(), value));
+    }
+}
+
+impl Subscriber for Collect {
+    fn enabled(&self, _: &Metadata<'_>) -> bool { true }
+    fn new_span(&self, _: &Attributes<'_>) -> Id { Id::from_u64(1) }
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+    fn event(&self, event: &Event<'_>) {
+        let mut f = Fields(String::new());
+        event.record(&mut f);
+        self.0.lock().unwrap().push(f.0.trim_end().to_string());
+    }
+    fn enter(&self, _: &Id) {}
+    fn exit(&self, _: &Id) {}
+}
+
+fn collect<F: FnOnce()>(f: F) -> Vec<String> {
+    let c = Collect::default();
+    tracing::subscriber::with_default(c.clone(), f);
+    let events = c.0.lock().unwrap().clone();
+    events
+}
+
+#[test]
+fn framing() {
+    let events = collect(|| {
+        let mut w = XdrRecordWriter::with_fragment_size(Vec::new(), 8);
+        1u64.pack(&mut w).unwrap();
+        2u32.pack(&mut w).unwrap();
+        w.end_record().unwrap();
+        let buf = w.finish().unwrap();
+
+        let mut rd = XdrRecordReader::new(Cursor::new(buf));
+        assert_eq!(rd.records().count(), 1);
+    });
+
+    assert_eq!(events, vec![
+        "message=wrote fragment size=8 eor=false",
+        "message=wrote fragment size=4 eor=true",
+        "message=read fragment size=8 eor=false",
+        "message=read fragment size=4 eor=true",
+        "message=end of record size=12",
+    ]);
+}
+
+#[test]
+fn bad_framing() {
+    let events = collect(|| {
+        let mut rd = XdrRecordReader::new(Cursor::new(vec![0x80, 0, 1, 0]));
+        rd.set_max_fragment(Some(16));
+        assert!(rd.records().next().unwrap().is_err());
+    });
+
+    assert_eq!(events.len(), 1);
+    assert!(events[0].starts_with("message=bad record framing error="), "{}", events[0]);
+}
+
+#[test]
+fn decode_failure() {
+    let enc = [0, 0, 0, 1,  0, 0, 0, 2];
+
+    let events = collect(|| {
+        let mut rd = XdrReader::new(&enc[..]);
+        assert_eq!(rd.unpack::<u32>().unwrap(), 1);
+        assert!(rd.unpack::<bool>().is_err());
+        assert!(unpack_from_bytes::<Vec<u64>>(&enc[..]).is_err());
+    });
+
+    assert_eq!(events.len(), 2);
+    assert!(events[0].starts_with("message=decode failed ty=\"bool\" start=4 offset=8 error="), "{}", events[0]);
+    assert!(events[1].starts_with("message=decode failed ty=\"alloc::vec::Vec<u64>\" offset=8 error="), "{}", events[1]);
+}
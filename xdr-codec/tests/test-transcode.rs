@@ -0,0 +1,154 @@
+extern crate xdr_codec;
+
+use std::io;
+
+use xdr_codec::{Error, ErrorKind, Value};
+use xdr_codec::transcode::Transcoder;
+use xdr_codec::value::{Schema, Type};
+
+fn s(v: &str) -> String {
+    v.to_string()
+}
+
+// A cut-down AUTH_UNIX credential and a directory listing
+fn schema() -> Schema {
+    let mut schema = Schema::new();
+    schema.define("cred", Type::Struct(vec![
+        (s("stamp"), Type::UInt),
+        (s("machine"), Type::String(Some(16))),
+        (s("uid"), Type::UInt),
+        (s("gids"), Type::Array(Box::new(Type::UInt), Some(4))),
+    ]));
+    schema.define("status", Type::Enum(vec![(s("OK"), 0), (s("ERR"), 1)]));
+    schema.define("entry", Type::Struct(vec![
+        (s("name"), Type::String(None)),
+        (s("next"), Type::Optional(Box::new(Type::Named(s("entry"))))),
+    ]));
+    schema.define("reply", Type::Union {
+        discriminant: Box::new(Type::Named(s("status"))),
+        cases: vec![(0, Type::Optional(Box::new(Type::Named(s("entry")))))],
+        default: Some(Box::new(Type::Void)),
+    });
+    schema
+}
+
+const CRED: [u8; 32] = [
+    0, 0, 0, 9,                             // stamp
+    0, 0, 0, 4, b'h', b'o', b's', b't',     // machine
+    0, 0, 3, 0xe8,                          // uid 1000
+    0, 0, 0, 2, 0, 0, 0, 100, 0, 0, 3, 0xe8, // gids
+    0, 0, 0, 0,                             // (next message)
+];
+
+#[test]
+fn unchanged() {
+    let schema = schema();
+    let mut paths = Vec::new();
+    let out = Transcoder::named(&schema, "cred", |p: &str, _: &mut Value| paths.push(p.to_string()))
+        .transcode_bytes(&CRED[..28])
+        .unwrap();
+
+    assert_eq!(out, &CRED[..28]);
+    assert_eq!(paths, ["", "stamp", "machine", "uid", "gids", "gids[0]", "gids[1]"]);
+}
+
+#[test]
+fn remap_ids() {
+    let schema = schema();
+    let mut tc = Transcoder::named(&schema, "cred", |p: &str, v: &mut Value| {
+        if p == "uid" || p.starts_with("gids[") {
+            if *v == Value::UInt(1000) {
+                *v = Value::UInt(2000);
+            }
+        } else if p == "machine" {
+            *v = Value::String(s("anonymous"));
+        }
+    });
+
+    let mut out = Vec::new();
+    assert_eq!(tc.transcode(&mut &CRED[..], &mut out).unwrap(), (28, 36));
+    assert_eq!(out, [
+        0, 0, 0, 9,
+        0, 0, 0, 9, b'a', b'n', b'o', b'n', b'y', b'm', b'o', b'u', b's', 0, 0, 0,
+        0, 0, 7, 0xd0,
+        0, 0, 0, 2, 0, 0, 0, 100, 0, 0, 7, 0xd0,
+    ]);
+}
+
+#[test]
+fn union_paths() {
+    let schema = schema();
+    let reply = [
+        0, 0, 0, 0,  0, 0, 0, 1,
+        0, 0, 0, 1, b'a', 0, 0, 0,  0, 0, 0, 1,
+        0, 0, 0, 1, b'b', 0, 0, 0,  0, 0, 0, 0,
+    ];
+    let mut names = Vec::new();
+    let out = Transcoder::named(&schema, "reply", |p: &str, v: &mut Value| {
+        if let Value::String(ref mut name) = *v {
+            names.push(p.to_string());
+            *name = name.to_uppercase();
+        }
+    }).transcode_bytes(&reply).unwrap();
+
+    assert_eq!(names, ["arm.name", "arm.next.name"]);
+    assert_eq!(out[12], b'A');
+    assert_eq!(out[24], b'B');
+
+    // Replacing the whole arm
+    let out = Transcoder::named(&schema, "reply", |p: &str, v: &mut Value| if p == "arm" {
+        *v = Value::Optional(None);
+    }).transcode_bytes(&reply).unwrap();
+    assert_eq!(out, [0, 0, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn invalid_rewrite() {
+    let schema = schema();
+    let mut out = Vec::new();
+
+    // String longer than its limit
+    let mut tc = Transcoder::named(&schema, "cred", |p: &str, v: &mut Value| if p == "machine" {
+        *v = Value::String(s("a-very-long-hostname"));
+    });
+    match tc.transcode(&mut &CRED[..], &mut out) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert!(out.is_empty());
+
+    // Enum value with no variant
+    let mut tc = Transcoder::named(&schema, "status", |_: &str, v: &mut Value| {
+        *v = Value::Enum { name: s("BAD"), value: 7 };
+    });
+    match tc.transcode_bytes(&[0, 0, 0, 1]) {
+        Err(Error(ErrorKind::InvalidValue(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+#[test]
+fn stream() {
+    let schema = schema();
+    let mut tc = Transcoder::named(&schema, "status", |_: &str, v: &mut Value| {
+        *v = Value::Enum { name: s("OK"), value: 0 };
+    });
+
+    let mut out = Vec::new();
+    assert_eq!(tc.transcode_stream(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1][..], &mut out).unwrap(), 3);
+    assert_eq!(out, [0; 12]);
+
+    let mut out = Vec::new();
+    assert_eq!(tc.transcode_stream(&[][..], &mut out).unwrap(), 0);
+
+    match tc.transcode_stream(&[0, 0, 0, 1, 0, 0][..], &mut out) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(out, [0; 4]);
+
+    match tc.transcode_bytes(&[0, 0, 0, 1, 0, 0, 0, 0]) {
+        Err(Error(ErrorKind::TrailingData(4), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
@@ -0,0 +1,119 @@
+#[macro_use] extern crate xdr_codec;
+
+use std::io::Cursor;
+
+use xdr_codec::{Error, ErrorKind, Pack, Unpack, Skip, Read, Write, Result, pack_to_vec};
+use xdr_codec::union::{XdrUnion, pack_union, pack_xdr_union, unpack_xdr_union};
+
+// union switch (int which) { case 1: unsigned int a; case 2: string b<>; case 3: void;
+// default: hyper other; }
+#[derive(Debug, PartialEq)]
+enum Thing {
+    A(u32),
+    B(String),
+    C,
+    Other(i32, i64),
+}
+
+impl XdrUnion for Thing {
+    fn discriminant(&self) -> i32 {
+        match *self {
+            Thing::A(_) => 1,
+            Thing::B(_) => 2,
+            Thing::C => 3,
+            Thing::Other(d, _) => d,
+        }
+    }
+
+    fn pack_body<Out: Write>(&self, out: &mut Out) -> Result<usize> {
+        match *self {
+            Thing::A(ref v) => v.pack(out),
+            Thing::B(ref v) => v.as_str().pack(out),
+            Thing::C => Ok(0),
+            Thing::Other(_, ref v) => v.pack(out),
+        }
+    }
+
+    fn unpack_body<In: Read>(disc: i32, input: &mut In) -> Result<(Self, usize)> {
+        Ok(match disc {
+            1 => { let (v, sz) = Unpack::unpack(input)?; (Thing::A(v), sz) },
+            2 => { let (v, sz) = Unpack::unpack(input)?; (Thing::B(v), sz) },
+            3 => (Thing::C, 0),
+            d => { let (v, sz) = Unpack::unpack(input)?; (Thing::Other(d, v), sz) },
+        })
+    }
+}
+
+xdr_union_codec!(Thing);
+
+// union switch (bool ok) { case TRUE: int v; }
+#[derive(Debug, PartialEq)]
+struct Maybe(i32);
+
+impl XdrUnion for Maybe {
+    fn discriminant(&self) -> i32 { 1 }
+
+    fn pack_body<Out: Write>(&self, out: &mut Out) -> Result<usize> {
+        self.0.pack(out)
+    }
+
+    fn unpack_body<In: Read>(disc: i32, input: &mut In) -> Result<(Self, usize)> {
+        match disc {
+            1 => i32::unpack(input).map(|(v, sz)| (Maybe(v), sz)),
+            d => Err(Error::invalidcase(d)),
+        }
+    }
+}
+
+#[test]
+fn union_pack() {
+    assert_eq!(pack_to_vec(&Thing::A(5)).unwrap(), vec![0, 0, 0, 1,  0, 0, 0, 5]);
+    assert_eq!(pack_to_vec(&Thing::B("hi".into())).unwrap(),
+               vec![0, 0, 0, 2,  0, 0, 0, 2,  b'h', b'i', 0, 0]);
+    assert_eq!(pack_to_vec(&Thing::C).unwrap(), vec![0, 0, 0, 3]);
+    assert_eq!(pack_to_vec(&Thing::Other(-7, 1)).unwrap(),
+               vec![0xff, 0xff, 0xff, 0xf9,  0, 0, 0, 0, 0, 0, 0, 1]);
+
+    // The helpers produce the same thing
+    let mut out = Vec::new();
+    assert_eq!(pack_union(1, &5u32, &mut out).unwrap(), 8);
+    assert_eq!(pack_union(3, &(), &mut out).unwrap(), 4);
+    assert_eq!(pack_union(2, "hi", &mut out).unwrap(), 12);
+    assert_eq!(out, [pack_to_vec(&Thing::A(5)).unwrap(), pack_to_vec(&Thing::C).unwrap(),
+                     pack_to_vec(&Thing::B("hi".into())).unwrap()].concat());
+
+    let mut out = Vec::new();
+    assert_eq!(pack_xdr_union(&Maybe(2), &mut out).unwrap(), 8);
+    assert_eq!(out, vec![0, 0, 0, 1,  0, 0, 0, 2]);
+}
+
+#[test]
+fn union_unpack() {
+    let vals = vec![Thing::A(5), Thing::C, Thing::B("hello".into()), Thing::Other(100, -1)];
+    let mut enc = Vec::new();
+    for v in &vals {
+        v.pack(&mut enc).unwrap();
+    }
+
+    let mut cur = Cursor::new(&enc[..]);
+    let mut got = Vec::new();
+    let mut total = 0;
+    while (cur.position() as usize) < enc.len() {
+        let (v, sz) = Thing::unpack(&mut cur).unwrap();
+        got.push(v);
+        total += sz;
+    }
+    assert_eq!(got, vals);
+    assert_eq!(total, enc.len());
+
+    let mut cur = Cursor::new(&enc[..]);
+    assert_eq!(Thing::skip(&mut cur).unwrap(), 8);
+    assert_eq!(cur.position(), 8);
+
+    let mut cur = Cursor::new(vec![0, 0, 0, 1,  0, 0, 0, 9,  0, 0, 0, 0]);
+    assert_eq!(unpack_xdr_union::<_, Maybe>(&mut cur).unwrap(), (Maybe(9), 8));
+    match unpack_xdr_union::<_, Maybe>(&mut cur) {
+        Err(Error(ErrorKind::InvalidCase(0), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
@@ -0,0 +1,201 @@
+extern crate xdr_codec;
+
+use std::io;
+
+use xdr_codec::{DecodeOptions, Error, ErrorKind, Value, pack_to_vec, with_decode_options, with_input_limit};
+use xdr_codec::value::{Schema, Type};
+
+fn s(v: &str) -> String {
+    v.to_string()
+}
+
+fn schema() -> Schema {
+    let mut schema = Schema::new();
+
+    schema.define("colour", Type::Enum(vec![(s("RED"), 1), (s("GREEN"), 2)]));
+    // Linked list, as in `struct entry { int val; entry *next; }`
+    schema.define("entry", Type::Struct(vec![
+        (s("val"), Type::Int),
+        (s("next"), Type::Optional(Box::new(Type::Named(s("entry"))))),
+    ]));
+    schema.define("result", Type::Union {
+        discriminant: Box::new(Type::Named(s("colour"))),
+        cases: vec![(1, Type::String(Some(8))), (2, Type::Void)],
+        default: None,
+    });
+    schema.define("blob", Type::Struct(vec![
+        (s("id"), Type::FixedOpaque(3)),
+        (s("data"), Type::Opaque(None)),
+        (s("big"), Type::UHyper),
+        (s("ok"), Type::Bool),
+        (s("pair"), Type::FixedArray(Box::new(Type::Double), 2)),
+    ]));
+
+    schema
+}
+
+#[test]
+fn value_list() {
+    let schema = schema();
+    let enc = [0, 0, 0, 5, 0, 0, 0, 1, 0, 0, 0, 6, 0, 0, 0, 0];
+
+    let (v, sz) = schema.unpack_named("entry", &mut &enc[..]).unwrap();
+    assert_eq!(sz, 16);
+    assert_eq!(v.field("val"), Some(&Value::Int(5)));
+    match v.field("next") {
+        Some(&Value::Optional(Some(ref next))) => {
+            assert_eq!(next.field("val").and_then(Value::as_i64), Some(6));
+            assert_eq!(next.field("next"), Some(&Value::Optional(None)));
+        }
+        res => panic!("bad result {:?}", res),
+    }
+    assert_eq!(pack_to_vec(&v).unwrap(), enc);
+}
+
+#[test]
+fn value_union() {
+    let schema = schema();
+
+    let enc = [0, 0, 0, 1, 0, 0, 0, 2, b'h', b'i', 0, 0];
+    let (v, _) = schema.unpack_named("result", &mut &enc[..]).unwrap();
+    assert_eq!(v, Value::Union {
+        discriminant: Box::new(Value::Enum { name: s("RED"), value: 1 }),
+        arm: Box::new(Value::String(s("hi"))),
+    });
+    assert_eq!(pack_to_vec(&v).unwrap(), enc);
+
+    let (v, sz) = schema.unpack_named("result", &mut &[0, 0, 0, 2][..]).unwrap();
+    assert_eq!(sz, 4);
+    assert_eq!(pack_to_vec(&v).unwrap(), [0, 0, 0, 2]);
+
+    match schema.unpack_named("result", &mut &[0, 0, 0, 3][..]) {
+        Err(Error(ErrorKind::InvalidEnum(3), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    match schema.unpack_named("result", &mut &[0, 0, 0, 1, 0, 0, 0, 9][..]) {
+        Err(Error(ErrorKind::InvalidLen(8), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let int_union = Type::Union { discriminant: Box::new(Type::Int), cases: vec![], default: None };
+    match schema.unpack(&int_union, &mut &[0, 0, 0, 7][..]) {
+        Err(Error(ErrorKind::InvalidCase(7), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    let default = Type::Union {
+        discriminant: Box::new(Type::UInt),
+        cases: vec![],
+        default: Some(Box::new(Type::Hyper)),
+    };
+    let (v, sz) = schema.unpack(&default, &mut &[0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0, 1][..]).unwrap();
+    assert_eq!(sz, 12);
+    assert_eq!(v, Value::Union { discriminant: Box::new(Value::UInt(7)), arm: Box::new(Value::Hyper(1)) });
+}
+
+#[test]
+fn value_blob() {
+    let schema = schema();
+    let mut enc = vec![1, 2, 3, 0, 0, 0, 0, 1, 9, 0, 0, 0];
+    enc.extend(pack_to_vec(&u64::MAX).unwrap());
+    enc.extend(pack_to_vec(&true).unwrap());
+    enc.extend(pack_to_vec(&1.5f64).unwrap());
+    enc.extend(pack_to_vec(&-2.0f64).unwrap());
+
+    let (v, sz) = schema.unpack_named("blob", &mut &enc[..]).unwrap();
+    assert_eq!(sz, enc.len());
+    assert_eq!(v, Value::Struct(vec![
+        (s("id"), Value::FixedOpaque(vec![1, 2, 3])),
+        (s("data"), Value::Opaque(vec![9])),
+        (s("big"), Value::UHyper(u64::MAX)),
+        (s("ok"), Value::Bool(true)),
+        (s("pair"), Value::FixedArray(vec![Value::Double(1.5), Value::Double(-2.0)])),
+    ]));
+    assert_eq!(v.field("big").unwrap().as_i64(), None);
+    assert_eq!(pack_to_vec(&v).unwrap(), enc);
+}
+
+#[test]
+fn value_schema_errors() {
+    let mut schema = schema();
+
+    match schema.unpack_named("missing", &mut &[][..]) {
+        Err(Error(ErrorKind::UnknownType(ref n), _)) if n == "missing" => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    schema.define("a", Type::Named(s("b")));
+    schema.define("b", Type::Named(s("a")));
+    match schema.unpack_named("a", &mut &[][..]) {
+        Err(Error(ErrorKind::InvalidSchema(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    let bad = Type::Union { discriminant: Box::new(Type::String(None)), cases: vec![], default: None };
+    match schema.unpack(&bad, &mut &[0, 0, 0, 0][..]) {
+        Err(Error(ErrorKind::InvalidSchema(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Huge counts don't preallocate
+    let arr = Type::Array(Box::new(Type::Int), None);
+    match schema.unpack(&arr, &mut &[0xff, 0xff, 0xff, 0xff][..]) {
+        Err(Error(ErrorKind::IOError(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Counts longer than the input fail before decoding any elements (here an invalid bool)
+    let arr = Type::Array(Box::new(Type::Named(s("entry"))), None);
+    match with_input_limit(12, || schema.unpack(&arr, &mut &[0, 0, 0, 3, 0, 0, 0, 1, 0, 0, 0, 2][..])) {
+        Err(Error(ErrorKind::IOError(ref e), _)) if e.kind() == io::ErrorKind::UnexpectedEof => (),
+        res => panic!("bad result {:?}", res),
+    }
+
+    // Arrays of elements which take no space would decode any count without reading input
+    let arr = Type::Array(Box::new(Type::Void), None);
+    match with_input_limit(4, || schema.unpack(&arr, &mut &[0xff, 0xff, 0xff, 0xff][..])) {
+        Err(Error(ErrorKind::InvalidSchema(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    schema.define("empty", Type::Struct(vec![(s("a"), Type::FixedArray(Box::new(Type::Int), 0))]));
+    let arr = Type::Array(Box::new(Type::Named(s("empty"))), None);
+    match schema.unpack(&arr, &mut &[0, 0, 0, 0][..]) {
+        Err(Error(ErrorKind::InvalidSchema(_), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
+
+// Encode a linked list of `n` entries
+fn list(n: usize) -> Vec<u8> {
+    let mut enc = Vec::new();
+    for i in 0..n {
+        enc.extend_from_slice(&[0, 0, 0, 1]);
+        enc.extend_from_slice(&[0, 0, 0, (i + 1 < n) as u8]);
+    }
+    enc
+}
+
+#[test]
+fn value_depth() {
+    let schema = schema();
+
+    // Each entry takes two levels, one for the struct and one for the optional
+    let enc = list(100);
+    let (_, sz) = schema.unpack_named("entry", &mut &enc[..]).unwrap();
+    assert_eq!(sz, enc.len());
+
+    match with_decode_options(DecodeOptions::new().max_depth(199),
+                              || schema.unpack_named("entry", &mut &enc[..])) {
+        Err(Error(ErrorKind::DepthExceeded(199), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+    let (_, sz) = with_decode_options(DecodeOptions::new().max_depth(200),
+                                      || schema.unpack_named("entry", &mut &enc[..])).unwrap();
+    assert_eq!(sz, enc.len());
+
+    // Deep input fails rather than overflowing the stack
+    let enc = list(1_000_000);
+    match schema.unpack_named("entry", &mut &enc[..]) {
+        Err(Error(ErrorKind::DepthExceeded(1000), _)) => (),
+        res => panic!("bad result {:?}", res),
+    }
+}
@@ -5,6 +5,9 @@
 //!
 //! It is intended to be used with the "xdr-codec" crate, which provides the runtime library for
 //! encoding/decoding primitive types, strings, opaque data and arrays.
+//!
+//! With the `python` feature, the `python` module is a Python extension for decoding and
+//! encoding with a specification at run time.
 
 extern crate xdr_codec as xdr;
 
@@ -23,6 +26,14 @@ extern crate nom;
 #[macro_use]
 extern crate bitflags;
 
+// pyo3's macros refer to `::core`, which needs declaring in this edition
+#[cfg(feature = "python")]
+extern crate core;
+#[cfg(feature = "python")]
+extern crate pyo3;
+#[cfg(feature = "python")]
+extern crate serde_json;
+
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use std::io::{Read, Write};
@@ -35,6 +46,9 @@ use xdr::Result;
 mod spec;
 use spec::{Symtab, Emit, Emitpack};
 
+#[cfg(feature = "python")]
+pub mod python;
+
 fn result_option<T, E>(resopt: result::Result<Option<T>, E>) -> Option<result::Result<T, E>> {
     match resopt {
         Ok(None) => None,
@@ -114,6 +128,22 @@ pub fn generate<In, Out>(infile: &str, mut input: In, mut output: Out) -> Result
     Ok(())
 }
 
+/// Build a runtime schema from an RFC4506 XDR specification
+///
+/// Rather than generating code, this parses the specification into a `xdr_codec::value::Schema`
+/// with all its named types, so values can be decoded as `xdr_codec::Value` by tools which only
+/// know the specification at run time.
+pub fn schema<In: Read>(mut input: In) -> Result<xdr::value::Schema> {
+    let mut source = String::new();
+
+    input.read_to_string(&mut source)?;
+
+    match spec::specification(&source) {
+        Ok(defns) => Symtab::new(&defns).schema(),
+        Err(e) => Err(xdr::Error::from(format!("parse error: {}", e))),
+    }
+}
+
 /// Simplest possible way to generate Rust code from an XDR specification.
 ///
 /// It is intended for use in a build.rs script:
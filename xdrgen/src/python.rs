@@ -0,0 +1,228 @@
+//! Python bindings
+//!
+//! This is a Python extension module, `xdrgen`, for decoding and encoding XDR with a schema
+//! parsed from a specification at run time, and for record framing. It lets test harnesses and
+//! scripts handle the same traffic as Rust code using this crate and `xdr-codec`:
+//!
+//! ```text
+//! import xdrgen
+//!
+//! schema = xdrgen.Schema(open("proto.x").read())
+//! msg, size = schema.unpack("reply", data)
+//! msg = schema.decode("reply", data)     # must use all of data
+//! data = schema.pack("call", {"xid": 1, "proc": "NULL", "args": b"\x01\x02"})
+//!
+//! stream = xdrgen.frame_record(data)
+//! records = xdrgen.RecordDecoder(max_record=1 << 20).feed(stream)
+//! ```
+//!
+//! Decoded values are converted to Python in the same way as `Value::to_json`, except that
+//! opaque data is `bytes` and floats keep their value: structs are `dict`s keyed by field name,
+//! arrays are `list`s, enums are the name of their variant, optional values are `None` or the
+//! value, and unions are `{"discriminant": ..., "arm": ...}`. Values to pack are accepted in
+//! any form `Schema::value_from_json` accepts, with `bytes` for opaque data and `tuple`s as
+//! arrays. Input which ends in the middle of a value raises `EOFError`, and any other failure
+//! raises `ValueError`.
+//!
+//! To build the extension module, build this crate as a `cdylib` and install it as
+//! `xdrgen.so` (or use `maturin`):
+//!
+//! ```text
+//! cargo rustc --release --features python,pyo3/extension-module --crate-type cdylib
+//! ```
+//!
+//! This module is only available with the `python` feature.
+
+// Triggered by the wrappers pyo3 generates around methods returning `PyResult`
+#![allow(clippy::useless_conversion)]
+
+use std::io;
+
+use pyo3::exceptions::{PyEOFError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyList, PyString, PyTuple};
+use serde_json::{self, Map, Number};
+
+use xdr::{self, Error, ErrorKind, Value, pack_to_vec};
+use xdr::decoder::RecordDecoder;
+use xdr::record::XdrRecordWriter;
+use xdr::value::{Schema, Type};
+
+type Json = serde_json::Value;
+
+fn py_err(err: Error) -> PyErr {
+    match err.0 {
+        ErrorKind::IOError(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => PyEOFError::new_err(err.to_string()),
+        _ => PyValueError::new_err(err.to_string()),
+    }
+}
+
+fn to_py(py: Python<'_>, val: &Value) -> PyObject {
+    match *val {
+        Value::Void | Value::Optional(None) => py.None(),
+        Value::Int(v) => v.into_py(py),
+        Value::UInt(v) => v.into_py(py),
+        Value::Hyper(v) => v.into_py(py),
+        Value::UHyper(v) => v.into_py(py),
+        Value::Float(v) => (v as f64).into_py(py),
+        Value::Double(v) => v.into_py(py),
+        Value::Quadruple(v) => f64::from(v).into_py(py),
+        Value::Bool(v) => v.into_py(py),
+        Value::Enum { ref name, .. } => name.into_py(py),
+        Value::FixedOpaque(ref v) | Value::Opaque(ref v) => PyBytes::new_bound(py, v).into_py(py),
+        Value::String(ref v) => v.into_py(py),
+        Value::FixedArray(ref v) | Value::Array(ref v) =>
+            PyList::new_bound(py, v.iter().map(|e| to_py(py, e))).into_py(py),
+        Value::Optional(Some(ref v)) => to_py(py, v),
+        Value::Struct(ref fields) => {
+            let dict = PyDict::new_bound(py);
+            for (name, v) in fields {
+                // Can't fail with a string key
+                let _ = dict.set_item(name, to_py(py, v));
+            }
+            dict.into_py(py)
+        },
+        Value::Union { ref discriminant, ref arm } => {
+            let dict = PyDict::new_bound(py);
+            let _ = dict.set_item("discriminant", to_py(py, discriminant));
+            let _ = dict.set_item("arm", to_py(py, arm));
+            dict.into_py(py)
+        },
+    }
+}
+
+// Convert a Python value to JSON for `Schema::value_from_json`
+fn to_json(obj: &Bound<'_, PyAny>) -> PyResult<Json> {
+    if obj.is_none() {
+        Ok(Json::Null)
+    } else if obj.is_instance_of::<PyBool>() {
+        Ok(Json::Bool(obj.extract()?))
+    } else if let Ok(v) = obj.extract::<i64>() {
+        Ok(Json::from(v))
+    } else if let Ok(v) = obj.extract::<u64>() {
+        Ok(Json::from(v))
+    } else if obj.is_instance_of::<PyFloat>() {
+        // NaN and infinities become null, which is unpacked as NaN
+        Ok(Number::from_f64(obj.extract()?).map_or(Json::Null, Json::Number))
+    } else if obj.is_instance_of::<PyString>() {
+        Ok(Json::String(obj.extract()?))
+    } else if obj.is_instance_of::<PyBytes>() || obj.is_instance_of::<PyByteArray>() {
+        let v: Vec<u8> = obj.extract()?;
+        Ok(Json::Array(v.into_iter().map(Json::from).collect()))
+    } else if obj.is_instance_of::<PyList>() || obj.is_instance_of::<PyTuple>() {
+        obj.iter()?.map(|e| to_json(&e?)).collect::<PyResult<_>>().map(Json::Array)
+    } else if let Ok(dict) = obj.downcast::<PyDict>() {
+        let mut map = Map::new();
+        for (k, v) in dict.iter() {
+            map.insert(k.extract()?, to_json(&v)?);
+        }
+        Ok(Json::Object(map))
+    } else {
+        Err(PyValueError::new_err(format!("can't convert {} to an XDR value", obj.get_type().name()?)))
+    }
+}
+
+/// A set of XDR types, parsed from a specification.
+#[pyclass(name = "Schema")]
+pub struct PySchema {
+    schema: Schema,
+}
+
+#[pymethods]
+impl PySchema {
+    #[new]
+    fn new(source: &str) -> PyResult<Self> {
+        ::schema(source.as_bytes()).map(|schema| PySchema { schema }).map_err(py_err)
+    }
+
+    /// The names of the types defined, sorted.
+    fn types(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.schema.types().map(|(n, _)| n.to_string()).collect();
+        names.sort();
+        names
+    }
+
+    /// Unpack a value of the named type from the start of `data`, returning it and the number
+    /// of bytes used.
+    fn unpack(&self, py: Python<'_>, name: &str, data: &[u8]) -> PyResult<(PyObject, usize)> {
+        let mut input = data;
+        let (val, sz) = xdr::with_input_limit(data.len(), || self.schema.unpack_named(name, &mut input))
+            .map_err(py_err)?;
+        Ok((to_py(py, &val), sz))
+    }
+
+    /// Unpack a value of the named type, which must use all of `data`.
+    fn decode(&self, py: Python<'_>, name: &str, data: &[u8]) -> PyResult<PyObject> {
+        let (val, sz) = self.unpack(py, name, data)?;
+        if sz != data.len() {
+            return Err(py_err(ErrorKind::TrailingData(data.len() - sz).into()));
+        }
+        Ok(val)
+    }
+
+    /// Pack a value of the named type.
+    fn pack<'py>(&self, py: Python<'py>, name: &str, value: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyBytes>> {
+        let json = to_json(value)?;
+        let val = self.schema.value_from_json(&Type::Named(name.to_string()), &json).map_err(py_err)?;
+        let enc = pack_to_vec(&val).map_err(py_err)?;
+        Ok(PyBytes::new_bound(py, &enc))
+    }
+}
+
+/// Incremental decoder for record-marked streams (see `xdr_codec::decoder::RecordDecoder`).
+#[pyclass(name = "RecordDecoder")]
+pub struct PyRecordDecoder {
+    decoder: RecordDecoder,
+}
+
+#[pymethods]
+impl PyRecordDecoder {
+    #[new]
+    #[pyo3(signature = (max_fragment=None, max_record=None))]
+    fn new(max_fragment: Option<usize>, max_record: Option<usize>) -> Self {
+        PyRecordDecoder { decoder: RecordDecoder::with_limits(max_fragment, max_record) }
+    }
+
+    /// Feed bytes into the decoder, returning a list of the records they complete.
+    fn feed<'py>(&mut self, py: Python<'py>, data: &[u8]) -> PyResult<Bound<'py, PyList>> {
+        let recs = self.decoder.feed(data).map_err(py_err)?;
+        Ok(PyList::new_bound(py, recs.iter().map(|r| PyBytes::new_bound(py, r))))
+    }
+
+    /// Whether the decoder is at a record boundary, with no partial record buffered.
+    fn is_idle(&self) -> bool {
+        self.decoder.is_idle()
+    }
+}
+
+/// Frame `data` as one record, in fragments of at most `fragsz` bytes.
+#[pyfunction]
+#[pyo3(signature = (data, fragsz=None))]
+fn frame_record<'py>(py: Python<'py>, data: &[u8], fragsz: Option<usize>) -> PyResult<Bound<'py, PyBytes>> {
+    match fragsz {
+        Some(0) => return Err(PyValueError::new_err("fragsz must be non-zero")),
+        Some(sz) if sz > i32::MAX as usize => return Err(PyValueError::new_err("fragsz must be less than 2^31")),
+        _ => (),
+    }
+
+    let mut out = Vec::new();
+    {
+        let mut w = match fragsz {
+            Some(sz) => XdrRecordWriter::with_fragment_size(&mut out, sz),
+            None => XdrRecordWriter::new(&mut out),
+        };
+        io::Write::write_all(&mut w, data).and_then(|_| w.finish().map(|_| ()))
+            .map_err(|e| py_err(e.into()))?;
+    }
+    Ok(PyBytes::new_bound(py, &out))
+}
+
+/// The `xdrgen` Python module.
+#[pymodule]
+#[pyo3(name = "xdrgen")]
+pub fn init(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySchema>()?;
+    m.add_class::<PyRecordDecoder>()?;
+    m.add_function(wrap_pyfunction!(self::frame_record, m)?)?;
+    Ok(())
+}
@@ -7,6 +7,7 @@ use std::result;
 use quote::{self, Tokens, ToTokens};
 
 mod xdr_nom;
+mod schema;
 
 use xdr::Error;
 
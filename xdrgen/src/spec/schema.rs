@@ -0,0 +1,118 @@
+// Conversion of a parsed specification into a runtime `Schema`
+
+use xdr::value::{self, Schema};
+
+use super::{Symtab, Type, Decl, EnumDefn, Value, Result};
+
+impl Symtab {
+    /// Build a runtime schema from all the named types and typedefs.
+    pub fn schema(&self) -> Result<Schema> {
+        let mut schema = Schema::new();
+
+        for (name, ty) in self.typespecs().chain(self.typesyns()) {
+            schema.define(name.as_str(), self.value_type(ty)?);
+        }
+
+        Ok(schema)
+    }
+
+    fn value_type(&self, ty: &Type) -> Result<value::Type> {
+        use self::Type::*;
+
+        let ret = match *ty {
+            UInt => value::Type::UInt,
+            Int => value::Type::Int,
+            UHyper => value::Type::UHyper,
+            Hyper => value::Type::Hyper,
+            Float => value::Type::Float,
+            Double => value::Type::Double,
+            Quadruple => value::Type::Quadruple,
+            Bool => value::Type::Bool,
+
+            Enum(ref defns) => {
+                let mut prev = -1;
+                let mut variants = Vec::new();
+
+                for EnumDefn(name, val) in defns {
+                    let v = match *val {
+                        None => prev + 1,
+                        Some(ref val) => self.const_value(val)?,
+                    };
+                    prev = v;
+                    variants.push((name.clone(), v as i32));
+                }
+                value::Type::Enum(variants)
+            }
+
+            Struct(ref decls) => {
+                let mut fields = Vec::new();
+
+                for decl in decls {
+                    if let Decl::Named(ref name, ref ty) = *decl {
+                        fields.push((name.clone(), self.value_type(ty)?));
+                    }
+                }
+                value::Type::Struct(fields)
+            }
+
+            Union(ref disc, ref cases, ref defl) => {
+                let discriminant = match **disc {
+                    Decl::Named(_, ref ty) => self.value_type(ty)?,
+                    Decl::Void => return Err("void union discriminant".into()),
+                };
+                let cases = cases.iter()
+                    .map(|case| Ok((self.const_value(&case.0)? as i32, self.decl_type(&case.1)?)))
+                    .collect::<Result<Vec<_>>>()?;
+                let default = match *defl {
+                    None => None,
+                    Some(ref decl) => Some(Box::new(self.decl_type(decl)?)),
+                };
+
+                value::Type::Union { discriminant: Box::new(discriminant), cases, default }
+            }
+
+            Option(ref ty) => value::Type::Optional(Box::new(self.value_type(ty)?)),
+
+            Array(ref ty, ref len) => {
+                let len = self.const_value(len)? as usize;
+                match **ty {
+                    Opaque => value::Type::FixedOpaque(len),
+                    String => return Err("fixed-size string".into()),
+                    ref ty => value::Type::FixedArray(Box::new(self.value_type(ty)?), len),
+                }
+            }
+
+            Flex(ref ty, ref max) => {
+                let max = match *max {
+                    None => None,
+                    Some(ref max) => Some(self.const_value(max)? as usize),
+                };
+                match **ty {
+                    Opaque => value::Type::Opaque(max),
+                    String => value::Type::String(max),
+                    ref ty => value::Type::Array(Box::new(self.value_type(ty)?), max),
+                }
+            }
+
+            Ident(ref name) => value::Type::Named(name.clone()),
+
+            Opaque | String => return Err(format!("bare {:?} outside array", ty).into()),
+        };
+
+        Ok(ret)
+    }
+
+    fn decl_type(&self, decl: &Decl) -> Result<value::Type> {
+        match *decl {
+            Decl::Void => Ok(value::Type::Void),
+            Decl::Named(_, ref ty) => self.value_type(ty),
+        }
+    }
+
+    fn const_value(&self, val: &Value) -> Result<i64> {
+        match self.value(val) {
+            Some(v) => Ok(v),
+            None => Err(format!("unknown constant {:?}", val).into()),
+        }
+    }
+}
@@ -190,3 +190,98 @@ union foo switch (int x) {
     println!("spec {:?}", s);
     assert!(s.is_ok())
 }
+
+#[test]
+fn schema() {
+    use super::super::schema;
+    use xdr::Value;
+    use xdr::value::Type;
+
+    let spec = r#"
+const MAXNAME = 8;
+enum kind { FILE = 1, DIR, LINK = 5 };
+typedef string name<MAXNAME>;
+struct entry {
+    name n;
+    kind k;
+    opaque cookie[3];
+    entry *next;
+};
+union res switch (kind k) {
+    case FILE: unsigned hyper size;
+    case DIR: entry *list;
+    default: void;
+};
+"#;
+    let schema = schema(Cursor::new(spec)).expect("schema failed");
+
+    assert_eq!(schema.get("name"), Some(&Type::String(Some(8))));
+    assert_eq!(schema.get("kind"),
+               Some(&Type::Enum(vec![("FILE".to_string(), 1), ("DIR".to_string(), 2), ("LINK".to_string(), 5)])));
+
+    let enc = [0, 0, 0, 2,                  // DIR
+               0, 0, 0, 1,                  // list present
+               0, 0, 0, 1, b'a', 0, 0, 0,   // n
+               0, 0, 0, 5,                  // k
+               1, 2, 3, 0,                  // cookie
+               0, 0, 0, 0];                 // no next
+    let (val, sz) = schema.unpack_named("res", &mut &enc[..]).expect("unpack failed");
+    assert_eq!(sz, enc.len());
+
+    match val {
+        Value::Union { ref discriminant, ref arm } => {
+            assert_eq!(**discriminant, Value::Enum { name: "DIR".to_string(), value: 2 });
+            match **arm {
+                Value::Optional(Some(ref e)) => {
+                    assert_eq!(e.field("n"), Some(&Value::String("a".to_string())));
+                    assert_eq!(e.field("k"), Some(&Value::Enum { name: "LINK".to_string(), value: 5 }));
+                    assert_eq!(e.field("cookie"), Some(&Value::FixedOpaque(vec![1, 2, 3])));
+                }
+                ref v => panic!("bad arm {:?}", v),
+            }
+        }
+        ref v => panic!("bad value {:?}", v),
+    }
+
+    let (val, _) = schema.unpack_named("res", &mut &[0, 0, 0, 5][..]).expect("unpack failed");
+    assert_eq!(val, Value::Union {
+        discriminant: Box::new(Value::Enum { name: "LINK".to_string(), value: 5 }),
+        arm: Box::new(Value::Void),
+    });
+
+    assert!(super::super::schema(Cursor::new("struct x { int a[NOPE]; };")).is_err());
+    assert!(super::super::schema(Cursor::new("struct x {")).is_err());
+}
+
+#[test]
+fn schema_export() {
+    use super::super::schema;
+    use xdr::reflect;
+
+    let spec = r#"
+enum kind { FILE = 1, DIR, LINK = 5 };
+typedef string name<8>;
+struct entry {
+    name n;
+    kind k;
+    opaque cookie[3];
+    entry *next;
+    struct { int a; float b<>; } inner;
+};
+union res switch (kind k) {
+    case FILE: unsigned hyper size;
+    case DIR: entry *list;
+    default: void;
+};
+"#;
+    let schema = schema(Cursor::new(spec)).expect("schema failed");
+    let exported = reflect::spec(&schema).expect("export failed");
+    let again = super::super::schema(Cursor::new(&exported)).expect("reparse failed");
+
+    let mut names: Vec<_> = schema.types().map(|(n, _)| n).collect();
+    names.sort();
+    for name in names {
+        assert_eq!(schema.get(name), again.get(name), "{} differs in\n{}", name, exported);
+    }
+    assert_eq!(again.types().count(), schema.types().count());
+}
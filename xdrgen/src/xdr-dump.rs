@@ -0,0 +1,183 @@
+#![crate_type = "bin"]
+
+extern crate xdrgen;
+extern crate xdr_codec;
+extern crate env_logger;
+extern crate clap;
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::io::{stdin, stderr};
+use std::process::exit;
+
+use clap::App;
+
+use xdr_codec::record::XdrRecordReader;
+use xdr_codec::testing::hexdump;
+use xdr_codec::Value;
+use xdr_codec::value::{Schema, Type};
+
+use xdrgen::schema;
+
+enum Format {
+    Hex,
+    Typed(Schema, Type),
+    Json(Schema, Type),
+}
+
+fn fail<T, E: std::fmt::Display>(msg: &str, e: E) -> T {
+    let _ = writeln!(&mut stderr(), "{}: {}", msg, e);
+    exit(1)
+}
+
+// Compact rendering of a value, with nested fields on indented lines
+fn show(v: &Value, indent: usize, out: &mut String) {
+    let nl = |out: &mut String| {
+        out.push('\n');
+        out.push_str(&"  ".repeat(indent));
+    };
+
+    match *v {
+        Value::Void => out.push_str("void"),
+        Value::Int(v) => out.push_str(&v.to_string()),
+        Value::UInt(v) => out.push_str(&v.to_string()),
+        Value::Hyper(v) => out.push_str(&v.to_string()),
+        Value::UHyper(v) => out.push_str(&v.to_string()),
+        Value::Float(v) => out.push_str(&v.to_string()),
+        Value::Double(v) => out.push_str(&v.to_string()),
+        Value::Quadruple(v) => out.push_str(&f64::from(v).to_string()),
+        Value::Bool(v) => out.push_str(&v.to_string()),
+        Value::Enum { ref name, value } => out.push_str(&format!("{} ({})", name, value)),
+        Value::FixedOpaque(ref v) | Value::Opaque(ref v) => {
+            out.push_str(&format!("[{} bytes] ", v.len()));
+            out.extend(v.iter().map(|b| format!("{:02x}", b)));
+        }
+        Value::String(ref v) => out.push_str(&format!("{:?}", v)),
+        Value::FixedArray(ref v) | Value::Array(ref v) => {
+            out.push_str(&format!("[{} elements]", v.len()));
+            for (i, e) in v.iter().enumerate() {
+                nl(out);
+                out.push_str(&format!("[{}]: ", i));
+                show(e, indent + 1, out);
+            }
+        }
+        Value::Optional(None) => out.push_str("NULL"),
+        Value::Optional(Some(ref v)) => show(v, indent, out),
+        Value::Struct(ref fields) => {
+            out.push('{');
+            for (n, f) in fields {
+                nl(out);
+                out.push_str(&format!("{}: ", n));
+                show(f, indent + 1, out);
+            }
+            out.push_str(" }");
+        }
+        Value::Union { ref discriminant, ref arm } => {
+            show(discriminant, indent, out);
+            out.push_str(" => ");
+            show(arm, indent, out);
+        }
+    }
+}
+
+// Dump each value in `msg`, returning false if it couldn't all be decoded
+fn dump(fmt: &Format, msg: &[u8]) -> bool {
+    let (schema, ty, json) = match *fmt {
+        Format::Hex => {
+            print!("{}", hexdump(msg));
+            return true;
+        }
+        Format::Typed(ref schema, ref ty) => (schema, ty, false),
+        Format::Json(ref schema, ref ty) => (schema, ty, true),
+    };
+
+    let mut input = msg;
+    while !input.is_empty() {
+        let off = msg.len() - input.len();
+
+        match schema.unpack(ty, &mut input) {
+            // A type with an empty encoding would never finish
+            Ok((_, 0)) => {
+                println!("type has an empty encoding; {} bytes left over", input.len());
+                return false;
+            }
+            Ok((v, _)) if json => println!("{}", v.to_json()),
+            Ok((v, _)) => {
+                let mut out = String::new();
+                show(&v, 1, &mut out);
+                println!("{}", out);
+            }
+            Err(e) => {
+                let rest = &msg[off..];
+                println!("decode failed at offset {}: {}; remaining data:", off, e);
+                print!("{}", hexdump(rest));
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+fn main() {
+    let _ = env_logger::init();
+
+    let matches = App::new("XDR dump")
+        .about("Dump XDR encoded data as hex, or decoded with a .x specification")
+        .args_from_usage("-r, --records 'Input is record-marked'
+                          -s, --spec [SPEC] 'Set .x file describing the data'
+                          -t, --type [TYPE] 'Set type of each value (needs --spec)'
+                          -j, --json 'Print decoded values as JSON'
+                          [FILE] 'Set input file (default stdin)'")
+        .get_matches();
+
+    let fmt = match (matches.value_of("spec"), matches.value_of("type")) {
+        (Some(spec), Some(ty)) => {
+            let f = File::open(spec).unwrap_or_else(|e| fail(&format!("Failed to open {}", spec), e));
+            let schema = schema(BufReader::new(f)).unwrap_or_else(|e| fail(&format!("Failed to load {}", spec), e));
+            if schema.get(ty).is_none() {
+                fail::<(), _>("Unknown type", ty);
+            }
+
+            let ty = Type::Named(ty.to_string());
+            if matches.is_present("json") {
+                Format::Json(schema, ty)
+            } else {
+                Format::Typed(schema, ty)
+            }
+        }
+        (None, None) if !matches.is_present("json") => Format::Hex,
+        _ => fail("Bad arguments", "--spec and --type are needed to decode values"),
+    };
+
+    let mut data = Vec::new();
+    let res = match matches.value_of("FILE") {
+        Some(fname) => File::open(fname).and_then(|mut f| f.read_to_end(&mut data)),
+        None => stdin().read_to_end(&mut data),
+    };
+    if let Err(e) = res {
+        fail::<(), _>("Failed to read input", e);
+    }
+
+    let ok = if matches.is_present("records") {
+        let mut ok = true;
+
+        for (i, rec) in XdrRecordReader::new(io::Cursor::new(&data)).into_iter().enumerate() {
+            match rec {
+                Ok(rec) => {
+                    println!("record {} ({} bytes)", i, rec.len());
+                    ok &= dump(&fmt, &rec);
+                }
+                Err(e) => fail("Bad record", e),
+            }
+        }
+
+        ok
+    } else {
+        dump(&fmt, &data)
+    };
+
+    if !ok {
+        exit(1);
+    }
+}
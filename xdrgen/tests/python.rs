@@ -0,0 +1,69 @@
+#![cfg(feature = "python")]
+extern crate pyo3;
+extern crate xdrgen;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+const SPEC: &str = r#"
+enum kind { A = 1, B = 2 };
+struct pt { int x; opaque tag<4>; string name<>; kind k; int *opt; };
+"#;
+
+fn run(code: &str) {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let module = PyModule::new_bound(py, "xdrgen").unwrap();
+        xdrgen::python::init(&module).unwrap();
+        let globals = PyDict::new_bound(py);
+        globals.set_item("xdrgen", module).unwrap();
+        globals.set_item("SPEC", SPEC).unwrap();
+        if let Err(e) = py.run_bound(code, Some(&globals), None) {
+            panic!("python failed: {}", e);
+        }
+    })
+}
+
+#[test]
+fn python_schema() {
+    run(r#"
+s = xdrgen.Schema(SPEC)
+assert s.types() == ["kind", "pt"]
+
+v = {"x": -1, "tag": b"\x01\x02", "name": "hi", "k": "B", "opt": 7}
+data = s.pack("pt", v)
+assert data == bytes.fromhex("ffffffff 00000002 01020000 00000002 68690000 00000002 00000001 00000007")
+assert s.decode("pt", data) == v
+assert s.unpack("pt", data + b"more") == (v, len(data))
+
+try:
+    s.decode("pt", data[:6])
+    assert False
+except EOFError:
+    pass
+try:
+    s.pack("pt", dict(v, tag=b"toolong"))
+    assert False
+except ValueError:
+    pass
+"#);
+}
+
+#[test]
+fn python_records() {
+    run(r#"
+f = xdrgen.frame_record(b"abcdef", 4)
+assert f == bytes.fromhex("00000004 61626364 80000002 6566")
+
+rd = xdrgen.RecordDecoder()
+assert rd.feed(f[:5]) == [] and not rd.is_idle()
+assert rd.feed(f[5:] + xdrgen.frame_record(b"")) == [b"abcdef", b""]
+assert rd.is_idle()
+
+try:
+    xdrgen.RecordDecoder(max_fragment=2).feed(f)
+    assert False
+except ValueError:
+    pass
+"#);
+}